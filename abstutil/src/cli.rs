@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use std::io::Write;
 
 pub struct CmdArgs {
     kv: HashMap<String, String>,
@@ -35,6 +36,49 @@ impl CmdArgs {
         args
     }
 
+    // Like new(), but fill in anything not specified on the command line from a TOML config file.
+    // Keys are flag names without the leading "--", like `rng_seed = 42` or `dev = true`. `load`
+    // is the free argument. A missing file is fine. Turn off a boolean from the file with
+    // `--dev=false`.
+    pub fn with_config_file(path: &str) -> CmdArgs {
+        let mut args = CmdArgs::new();
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                return args;
+            }
+        };
+        let table: toml::value::Table = match toml::from_str(&contents) {
+            Ok(table) => table,
+            Err(err) => panic!("Can't parse {}: {}", path, err),
+        };
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Boolean(true) => {
+                    let flag = format!("--{}", key);
+                    if !args.kv.contains_key(&flag) {
+                        args.bits.insert(flag);
+                    }
+                    continue;
+                }
+                toml::Value::Boolean(false) => {
+                    continue;
+                }
+                toml::Value::Integer(_) | toml::Value::Float(_) => value.to_string(),
+                _ => panic!("{} in {} must be a string, number, or bool", key, path),
+            };
+            if key == "load" {
+                if args.free.is_empty() {
+                    args.free.push(value);
+                }
+            } else {
+                args.kv.entry(format!("--{}", key)).or_insert(value);
+            }
+        }
+        args
+    }
+
     pub fn required(&mut self, key: &str) -> String {
         if let Some(value) = self.kv.remove(key) {
             value
@@ -74,8 +118,16 @@ impl CmdArgs {
         }
     }
 
+    // True for "--key" or "--key=true", false for "--key=false" or if it's missing.
     pub fn enabled(&mut self, key: &str) -> bool {
-        if self.bits.remove(key) {
+        if let Some(value) = self.kv.remove(key) {
+            self.used.insert(key.to_string());
+            match value.as_ref() {
+                "true" => true,
+                "false" => false,
+                x => panic!("{}={} is invalid; must be true or false", key, x),
+            }
+        } else if self.bits.remove(key) {
             self.used.insert(key.to_string());
             true
         } else if self.used.contains(key) {
@@ -113,3 +165,30 @@ impl CmdArgs {
         }
     }
 }
+
+pub enum ConfigValue {
+    Flag(bool),
+    Value(String),
+}
+
+// Overwrites some keys in a config file read by CmdArgs::with_config_file, keeping everything else.
+pub fn update_config_file(path: &str, updates: Vec<(&str, ConfigValue)>) {
+    let mut table: toml::value::Table = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default();
+    for (key, value) in updates {
+        table.insert(
+            key.to_string(),
+            match value {
+                ConfigValue::Flag(x) => toml::Value::Boolean(x),
+                ConfigValue::Value(x) => toml::Value::String(x),
+            },
+        );
+    }
+    let contents = toml::to_string(&table).unwrap();
+    match std::fs::File::create(path).and_then(|mut f| f.write_all(contents.as_bytes())) {
+        Ok(()) => println!("Wrote {}", path),
+        Err(err) => println!("Couldn't write {}: {}", path, err),
+    }
+}
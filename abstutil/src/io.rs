@@ -19,13 +19,32 @@ pub fn to_json<T: Serialize>(obj: &T) -> String {
     serde_json::to_string_pretty(obj).unwrap()
 }
 
+pub fn from_json<T: DeserializeOwned>(raw: &[u8]) -> Result<T, Error> {
+    serde_json::from_slice(raw).map_err(|err| Error::new(ErrorKind::Other, err))
+}
+
+pub fn from_binary<T: DeserializeOwned>(raw: &[u8]) -> Result<T, Error> {
+    bincode::deserialize(raw).map_err(|err| Error::new(ErrorKind::Other, err))
+}
+
+pub fn to_binary<T: Serialize>(obj: &T) -> Vec<u8> {
+    bincode::serialize(obj).unwrap()
+}
+
+// The same bytes write_versioned_binary would produce, to be written somewhere later
+pub fn to_versioned_binary<T: Serialize>(version: u32, obj: &T) -> Vec<u8> {
+    let mut bytes = VERSIONED_MAGIC.to_vec();
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend(to_binary(obj));
+    bytes
+}
+
 // TODO Idea: Have a wrapper type DotJSON(...) and DotBin(...) to distinguish raw path strings
-fn maybe_write_json<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
+pub fn maybe_write_json<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
     if !path.ends_with(".json") {
         panic!("write_json needs {} to end with .json", path);
     }
-    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
-        .expect("Creating parent dir failed");
+    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())?;
 
     let mut file = File::create(path)?;
     file.write_all(to_json(obj).as_bytes())?;
@@ -72,9 +91,7 @@ pub fn maybe_read_json<T: DeserializeOwned>(path: String, timer: &mut Timer) ->
 
     timer.start(format!("parse {}", path));
     // TODO timer.read_file isn't working here. And we need to call stop() if there's no file.
-    let result: Result<T, Error> = slurp_file(&path).and_then(|raw| {
-        serde_json::from_slice(&raw).map_err(|err| Error::new(ErrorKind::Other, err))
-    });
+    let result: Result<T, Error> = slurp_file(&path).and_then(|raw| from_json(&raw));
     timer.stop(format!("parse {}", path));
     result
 }
@@ -86,18 +103,107 @@ pub fn read_json<T: DeserializeOwned>(path: String, timer: &mut Timer) -> T {
     }
 }
 
-fn maybe_write_binary<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
+pub fn maybe_write_binary<T: Serialize>(path: &str, obj: &T) -> Result<(), Error> {
     if !path.ends_with(".bin") {
         panic!("write_binary needs {} to end with .bin", path);
     }
 
-    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
-        .expect("Creating parent dir failed");
+    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())?;
 
     let file = BufWriter::new(File::create(path)?);
     bincode::serialize_into(file, obj).map_err(|err| Error::new(ErrorKind::Other, err))
 }
 
+// Versioned binary files start with this, then the version as a little-endian u32, then the normal
+// bincode payload. Files from before versioning don't have the header and count as version 0.
+const VERSIONED_MAGIC: &[u8; 4] = b"ABST";
+
+fn maybe_write_versioned_binary<T: Serialize>(
+    path: &str,
+    version: u32,
+    obj: &T,
+) -> Result<(), Error> {
+    if !path.ends_with(".bin") {
+        panic!("write_versioned_binary needs {} to end with .bin", path);
+    }
+
+    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())?;
+
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(VERSIONED_MAGIC)?;
+    file.write_all(&version.to_le_bytes())?;
+    bincode::serialize_into(file, obj).map_err(|err| Error::new(ErrorKind::Other, err))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_versioned_binary<T: Serialize>(path: String, version: u32, obj: &T) {
+    if let Err(err) = maybe_write_versioned_binary(&path, version, obj) {
+        panic!("Can't write_versioned_binary({}): {}", path, err);
+    }
+    println!("Wrote {}", path);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_versioned_binary<T: Serialize>(path: String, version: u32, obj: &T) {
+    // TODO
+}
+
+// Returns the version a file was written with and its payload, without deserializing anything.
+// Reading everything into memory up-front and deserializing from the slice is much faster than
+// letting bincode pull small reads through the file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_versioned_bytes(path: &str, timer: &mut Timer) -> Result<(u32, Vec<u8>), Error> {
+    let total_bytes = std::fs::metadata(path)?.len() as usize;
+    let mut bytes = vec![0; total_bytes];
+    if total_bytes > 0 {
+        timer.read_file(path)?;
+        timer.read_exact(&mut bytes)?;
+    }
+    Ok(split_version(bytes))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_versioned_bytes(path: &str, _timer: &mut Timer) -> Result<(u32, Vec<u8>), Error> {
+    Ok(split_version(slurp_file(path)?))
+}
+
+fn split_version(mut bytes: Vec<u8>) -> (u32, Vec<u8>) {
+    if bytes.len() < 8 || &bytes[0..4] != VERSIONED_MAGIC {
+        return (0, bytes);
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&bytes[4..8]);
+    bytes.drain(0..8);
+    (u32::from_le_bytes(version), bytes)
+}
+
+// Older versions are only read if they happen to still deserialize.
+pub fn maybe_read_versioned_binary<T: DeserializeOwned>(
+    path: String,
+    current_version: u32,
+    timer: &mut Timer,
+) -> Result<T, Error> {
+    if !path.ends_with(".bin") {
+        panic!("read_versioned_binary needs {} to end with .bin", path);
+    }
+    let (version, bytes) = read_versioned_bytes(&path, timer)?;
+    if version > current_version {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "{} is version {}, but this build only understands up to {}",
+                path, version, current_version
+            ),
+        ));
+    }
+    from_binary(&bytes).map_err(|err| {
+        Error::new(
+            ErrorKind::Other,
+            format!("{} (version {} of {})", err, version, current_version),
+        )
+    })
+}
+
 pub fn serialized_size_bytes<T: Serialize>(obj: &T) -> usize {
     bincode::serialized_size(obj).unwrap() as usize
 }
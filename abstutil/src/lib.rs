@@ -3,11 +3,12 @@ mod clone;
 mod collections;
 mod error;
 mod io;
+mod logger;
 mod logs;
 mod random;
 mod time;
 
-pub use crate::cli::CmdArgs;
+pub use crate::cli::{update_config_file, CmdArgs, ConfigValue};
 pub use crate::clone::Cloneable;
 pub use crate::collections::{
     contains_duplicates, retain_btreemap, retain_btreeset, wraparound_get, Counter, MultiMap,
@@ -16,15 +17,20 @@ pub use crate::collections::{
 pub use crate::error::Error;
 pub use crate::io::{
     basename, deserialize_btreemap, deserialize_multimap, file_exists, find_next_file,
-    find_prev_file, list_all_objects, list_dir, load_all_objects, maybe_read_binary,
-    maybe_read_json, read_binary, read_json, serialize_btreemap, serialize_multimap,
-    serialized_size_bytes, slurp_file, to_json, write_binary, write_json, FileWithProgress,
+    find_prev_file, from_binary, from_json, list_all_objects, list_dir, load_all_objects,
+    maybe_read_binary, maybe_read_json, maybe_read_versioned_binary, maybe_write_binary,
+    maybe_write_json, read_binary, read_json, read_versioned_bytes, serialize_btreemap,
+    serialize_multimap, serialized_size_bytes, slurp_file, to_binary, to_json, to_versioned_binary,
+    write_binary, write_json, write_versioned_binary, FileWithProgress,
+};
+pub use crate::logger::{
+    get_log_filters, recent_logs, set_log_filters, set_log_sim_time, setup_logger, LogLine,
 };
 pub use crate::logs::Warn;
 pub use crate::random::{fork_rng, WeightedUsizeChoice};
 pub use crate::time::{
-    elapsed_seconds, prettyprint_usize, start_profiler, stop_profiler, MeasureMemory, Profiler,
-    Timer, TimerSink,
+    elapsed_seconds, prettyprint_usize, process_used_memory_bytes, start_profiler, stop_profiler,
+    MeasureMemory, Profiler, Timer, TimerSink,
 };
 use std::collections::BTreeSet;
 use std::fmt::Write;
@@ -61,6 +67,17 @@ pub fn plain_list_names(names: BTreeSet<String>) -> String {
     s
 }
 
+// Unlike std's DefaultHasher, this is the same across runs, platforms, and compiler versions, so
+// it's safe to save in files. 64-bit FNV-1a, in hex.
+pub fn stable_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        hash ^= u64::from(*b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
 // System data (Players can't edit, needed at runtime)
 
 pub fn path_map(map_name: &str) -> String {
@@ -94,6 +111,13 @@ pub fn path_all_synthetic_maps() -> String {
     format!("../data/system/synthetic_maps")
 }
 
+pub fn path_language_pack(language: &str) -> String {
+    format!("../data/system/translations/{}.json", language)
+}
+pub fn path_all_language_packs() -> String {
+    format!("../data/system/translations")
+}
+
 // Player data (Players edit this)
 
 pub fn path_camera_state(map_name: &str) -> String {
@@ -107,12 +131,60 @@ pub fn path_all_edits(map_name: &str) -> String {
     format!("../data/player/edits/{}", map_name)
 }
 
+pub fn path_config() -> String {
+    format!("../data/player/config.toml")
+}
+
+pub fn path_last_session() -> String {
+    format!("../data/player/last_session.json")
+}
+
 pub fn path_save(map_name: &str, edits_name: &str, run_name: &str, time: String) -> String {
     format!(
         "../data/player/saves/{}/{}_{}/{}.bin",
         map_name, edits_name, run_name, time
     )
 }
+pub fn path_recovery() -> String {
+    format!("../data/player/recovery/session.json")
+}
+pub fn path_recovery_savestate() -> String {
+    format!("../data/player/recovery/savestate.bin")
+}
+// A run that didn't exit cleanly is moved here, so the next run can autosave without clobbering it
+pub fn path_previous_recovery() -> String {
+    format!("../data/player/recovery/previous_session.json")
+}
+pub fn path_previous_recovery_savestate() -> String {
+    format!("../data/player/recovery/previous_savestate.bin")
+}
+
+pub fn path_crash_bundle(name: &str) -> String {
+    format!("../data/player/crashes/{}", name)
+}
+
+pub fn path_replay(map_name: &str, name: &str) -> String {
+    format!("../data/player/replays/{}/{}.bin", map_name, name)
+}
+pub fn path_all_replays(map_name: &str) -> String {
+    format!("../data/player/replays/{}", map_name)
+}
+
+pub fn path_experiment(map_name: &str, id: &str) -> String {
+    format!("../data/player/experiments/{}/{}.json", map_name, id)
+}
+pub fn path_all_experiments(map_name: &str) -> String {
+    format!("../data/player/experiments/{}", map_name)
+}
+
+pub fn path_geometry_cache(map_name: &str, key: &str) -> String {
+    format!("../data/player/cache/{}/{}.bin", map_name, key)
+}
+
+pub fn path_videos(map_name: &str) -> String {
+    format!("../data/player/videos/{}", map_name)
+}
+
 pub fn path_all_saves(map_name: &str, edits_name: &str, run_name: &str) -> String {
     format!(
         "../data/player/saves/{}/{}_{}",
@@ -0,0 +1,181 @@
+use crate::elapsed_seconds;
+use instant::Instant;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// A logger for the log crate. Every line is tagged with real time since startup, the current sim
+// time (if a sim is running), the level, and the module. Filters look like
+// "info,sim::mechanics=debug,map_model=warn": an optional default level, then overrides for any
+// module path prefix. The most specific match wins. Recent lines are kept in memory for the log
+// viewer in the game.
+
+const MAX_RECENT_LINES: usize = 1000;
+// f64 bits for "no sim time"
+const NO_SIM_TIME: u64 = std::u64::MAX;
+
+static LOGGER: Logger = Logger;
+static SIM_TIME: AtomicU64 = AtomicU64::new(NO_SIM_TIME);
+
+lazy_static::lazy_static! {
+    static ref STATE: Mutex<State> = Mutex::new(State {
+        started_at: Instant::now(),
+        filters: Filters::parse("info").unwrap(),
+        recent: VecDeque::new(),
+    });
+}
+
+#[derive(Clone)]
+pub struct LogLine {
+    // Seconds since the logger was set up
+    pub real_time: f64,
+    // Seconds since midnight
+    pub sim_time: Option<f64>,
+    pub level: Level,
+    pub module: String,
+    pub msg: String,
+}
+
+impl std::fmt::Display for LogLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{:.1}s] ", self.real_time)?;
+        if let Some(t) = self.sim_time {
+            let t = t as usize;
+            write!(
+                f,
+                "[sim {:02}:{:02}:{:02}] ",
+                t / 3600,
+                (t % 3600) / 60,
+                t % 60
+            )?;
+        }
+        write!(f, "{} {}: {}", self.level, self.module, self.msg)
+    }
+}
+
+// Call once from main. None means "info" for everything.
+pub fn setup_logger(filters: Option<String>) {
+    let spec = filters.unwrap_or_else(|| "info".to_string());
+    if let Err(err) = set_log_filters(&spec) {
+        panic!("Bad --log={}: {}", spec, err);
+    }
+    // If some other logger is already set up (like in tests), just leave it.
+    let _ = log::set_logger(&LOGGER);
+}
+
+// Can be changed anytime, like from the UI
+pub fn set_log_filters(spec: &str) -> Result<(), String> {
+    let filters = Filters::parse(spec)?;
+    log::set_max_level(filters.max_level());
+    STATE.lock().unwrap().filters = filters;
+    Ok(())
+}
+
+pub fn get_log_filters() -> String {
+    STATE.lock().unwrap().filters.spec.clone()
+}
+
+// The sim calls this as time advances. If several sims run in one process, the tag is only
+// meaningful for whichever stepped last.
+pub fn set_log_sim_time(seconds: f64) {
+    SIM_TIME.store(seconds.to_bits(), Ordering::Relaxed);
+}
+
+// Oldest first
+pub fn recent_logs() -> Vec<LogLine> {
+    STATE.lock().unwrap().recent.iter().cloned().collect()
+}
+
+struct State {
+    started_at: Instant,
+    filters: Filters,
+    recent: VecDeque<LogLine>,
+}
+
+struct Filters {
+    spec: String,
+    default: LevelFilter,
+    // Sorted so longer (more specific) prefixes come first
+    per_module: Vec<(String, LevelFilter)>,
+}
+
+impl Filters {
+    fn parse(spec: &str) -> Result<Filters, String> {
+        let mut default = LevelFilter::Info;
+        let mut per_module = Vec::new();
+        for part in spec.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let mut kv = part.splitn(2, '=');
+            let first = kv.next().unwrap();
+            match kv.next() {
+                Some(level) => {
+                    per_module.push((first.to_string(), parse_level(level)?));
+                }
+                None => {
+                    default = parse_level(first)?;
+                }
+            }
+        }
+        per_module.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Ok(Filters {
+            spec: spec.to_string(),
+            default,
+            per_module,
+        })
+    }
+
+    fn level_for(&self, module: &str) -> LevelFilter {
+        for (prefix, level) in &self.per_module {
+            if module == prefix || module.starts_with(&format!("{}::", prefix)) {
+                return *level;
+            }
+        }
+        self.default
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.per_module
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, |a, b| a.max(b))
+    }
+}
+
+fn parse_level(raw: &str) -> Result<LevelFilter, String> {
+    raw.parse::<LevelFilter>()
+        .map_err(|_| format!("{} isn't off, error, warn, info, debug, or trace", raw))
+}
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= STATE.lock().unwrap().filters.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        let mut state = STATE.lock().unwrap();
+        if record.level() > state.filters.level_for(record.target()) {
+            return;
+        }
+        let sim_time = SIM_TIME.load(Ordering::Relaxed);
+        let line = LogLine {
+            real_time: elapsed_seconds(state.started_at),
+            sim_time: if sim_time == NO_SIM_TIME {
+                None
+            } else {
+                Some(f64::from_bits(sim_time))
+            },
+            level: record.level(),
+            module: record.target().to_string(),
+            msg: record.args().to_string(),
+        };
+        println!("{}", line);
+        state.recent.push_back(line);
+        if state.recent.len() > MAX_RECENT_LINES {
+            state.recent.pop_front();
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -568,13 +568,18 @@ impl MeasureMemory {
     }
 }
 
-#[cfg(target_os = "linux")]
 fn process_used_memory_mb() -> usize {
-    (procfs::process::Process::myself().unwrap().stat.vsize / 1024 / 1024) as usize
+    process_used_memory_bytes() / 1024 / 1024
+}
+
+// Virtual memory size of this process. Always 0 outside of Linux.
+#[cfg(target_os = "linux")]
+pub fn process_used_memory_bytes() -> usize {
+    procfs::process::Process::myself().unwrap().stat.vsize as usize
 }
 
 #[cfg(not(target_os = "linux"))]
-fn process_used_memory_mb() -> usize {
+pub fn process_used_memory_bytes() -> usize {
     0
 }
 
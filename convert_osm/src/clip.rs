@@ -125,6 +125,9 @@ pub fn clip_map(map: &mut RawMap, timer: &mut Timer) {
     }
     map.areas = result_areas;
 
+    map.bike_racks
+        .retain(|rack| boundary_polygon.contains_pt(rack.pt));
+
     // TODO Don't touch parking lots. It'll be visually obvious if a clip intersects one of these.
     // The boundary should be manually adjusted.
 
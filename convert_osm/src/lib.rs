@@ -1,10 +1,11 @@
 mod clip;
 mod osm_reader;
+mod pbf;
 mod split_ways;
 mod srtm;
 
 use abstutil::Timer;
-use geom::{Distance, FindClosest, PolyLine, Pt2D};
+use geom::{Distance, FindClosest, PolyLine, Projection, Pt2D};
 use kml::ExtraShapes;
 use map_model::osm;
 use map_model::raw::{DrivingSide, OriginalBuilding, OriginalRoad, RawMap};
@@ -13,6 +14,7 @@ use map_model::raw::{DrivingSide, OriginalBuilding, OriginalRoad, RawMap};
 const DIRECTED_ROAD_THICKNESS: Distance = Distance::const_meters(2.5);
 
 pub struct Options {
+    // One .osm or .osm.pbf file, or several neighboring extracts separated by commas
     pub osm_input: String,
     pub city_name: String,
     pub name: String,
@@ -25,6 +27,10 @@ pub struct Options {
     pub elevation: Option<String>,
     pub clip: Option<String>,
     pub drive_on_right: bool,
+    // How to flatten lon/lat into map-space
+    pub projection: Projection,
+    // Points of a road's center line closer than this to the simplified line are dropped
+    pub simplify_epsilon: Distance,
 }
 
 // If a building doesn't have anything from public_offstreet_parking, how many private spots should
@@ -41,8 +47,10 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
             &opts.clip,
             &opts.city_name,
             &opts.name,
+            opts.projection,
             timer,
         ),
+        opts.simplify_epsilon,
         timer,
     );
     clip::clip_map(&mut map, timer);
@@ -1,7 +1,8 @@
 use abstutil::{FileWithProgress, Timer};
-use geom::{GPSBounds, HashablePt2D, LonLat, PolyLine, Polygon, Pt2D, Ring};
+use geom::{GPSBounds, HashablePt2D, LonLat, PolyLine, Polygon, Projection, Pt2D, Ring};
 use map_model::raw::{
-    OriginalBuilding, RawArea, RawBuilding, RawMap, RawParkingLot, RawRoad, RestrictionType,
+    OriginalBuilding, RawArea, RawBikeRack, RawBuilding, RawMap, RawParkingLot, RawRoad,
+    RestrictionType,
 };
 use map_model::{osm, AreaType};
 use osm_xml;
@@ -12,6 +13,7 @@ pub fn extract_osm(
     maybe_clip_path: &Option<String>,
     city_name: &str,
     map_name: &str,
+    projection: Projection,
     timer: &mut Timer,
 ) -> (
     RawMap,
@@ -19,6 +21,10 @@ pub fn extract_osm(
     Vec<(i64, RawRoad)>,
     // Traffic signals
     HashSet<HashablePt2D>,
+    // Crossings without any paint
+    HashSet<HashablePt2D>,
+    // Crossings with a raised kerb at one end, so no curb ramp
+    HashSet<HashablePt2D>,
     // OSM Node IDs
     HashMap<HashablePt2D, i64>,
     // Simple turn restrictions: (restriction type, from way ID, via node ID, to way ID)
@@ -28,19 +34,22 @@ pub fn extract_osm(
     // Amenities (location, name, amenity type)
     Vec<(Pt2D, String, String)>,
 ) {
-    let (reader, done) = FileWithProgress::new(osm_path).unwrap();
-    let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
+    // Several extracts can be stitched together, separated by commas
+    let mut docs = osm_path.split(',').map(|path| read_doc(path, timer));
+    let mut doc = docs.next().unwrap();
+    for other in docs {
+        stitch(&mut doc, other);
+    }
     println!(
         "OSM doc has {} nodes, {} ways, {} relations",
         doc.nodes.len(),
         doc.ways.len(),
         doc.relations.len()
     );
-    done(timer);
 
     let mut map = if let Some(path) = maybe_clip_path {
-        let pts = LonLat::read_osmosis_polygon(path.to_string()).unwrap();
-        let mut gps_bounds = GPSBounds::new();
+        let pts = LonLat::read_clipping_polygon(path.to_string()).unwrap();
+        let mut gps_bounds = GPSBounds::with_projection(projection);
         for pt in &pts {
             gps_bounds.update(*pt);
         }
@@ -51,6 +60,7 @@ pub fn extract_osm(
         map
     } else {
         let mut m = RawMap::blank(city_name, map_name);
+        m.gps_bounds = GPSBounds::with_projection(projection);
         for node in doc.nodes.values() {
             m.gps_bounds.update(LonLat::new(node.lon, node.lat));
         }
@@ -63,6 +73,9 @@ pub fn extract_osm(
     let mut traffic_signals: HashSet<HashablePt2D> = HashSet::new();
     let mut osm_node_ids = HashMap::new();
     let mut node_amenities = Vec::new();
+    let mut entrances: HashSet<HashablePt2D> = HashSet::new();
+    let mut unmarked_crossings: HashSet<HashablePt2D> = HashSet::new();
+    let mut missing_curb_ramps: HashSet<HashablePt2D> = HashSet::new();
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
     for node in doc.nodes.values() {
@@ -74,6 +87,22 @@ pub fn extract_osm(
         if tags.get(osm::HIGHWAY) == Some(&"traffic_signals".to_string()) {
             traffic_signals.insert(pt.to_hashable());
         }
+        if tags.contains_key("entrance") {
+            entrances.insert(pt.to_hashable());
+        }
+        if tags.get(osm::HIGHWAY) == Some(&"crossing".to_string()) && is_unmarked_crossing(&tags) {
+            unmarked_crossings.insert(pt.to_hashable());
+        }
+        if tags.get("kerb") == Some(&"raised".to_string()) {
+            missing_curb_ramps.insert(pt.to_hashable());
+        }
+        if tags.get("amenity") == Some(&"bicycle_parking".to_string()) {
+            map.bike_racks.push(RawBikeRack {
+                pt,
+                osm_node_id: node.id,
+                capacity: tags.get("capacity").and_then(|x| x.parse::<usize>().ok()),
+            });
+        }
         if let Some(amenity) = tags.get("amenity") {
             node_amenities.push((
                 pt,
@@ -119,7 +148,34 @@ pub fn extract_osm(
         let mut tags = tags_to_map(&way.tags);
         tags.insert(osm::OSM_WAY_ID.to_string(), way.id.to_string());
 
+        // Separately mapped crossings are represented by the crosswalk turns between sidewalks.
+        // Only remember whether they're marked, on the nodes shared with the road they cross.
+        if tags.get("footway") == Some(&"crossing".to_string()) {
+            if is_unmarked_crossing(&tags) {
+                for pt in &pts {
+                    unmarked_crossings.insert(pt.to_hashable());
+                }
+            }
+            // Kerbs are mapped at the ends of the crossing, but the road only shares a node in
+            // the middle.
+            if pts
+                .iter()
+                .any(|pt| missing_curb_ramps.contains(&pt.to_hashable()))
+            {
+                for pt in &pts {
+                    missing_curb_ramps.insert(pt.to_hashable());
+                }
+            }
+            continue;
+        }
+
         if is_road(&tags) {
+            // Sidewalks mapped as their own footways are folded into the road's sidewalk lanes.
+            // TODO Use the footway geometry instead of guessing from the road's width
+            if tags.get(osm::SIDEWALK) == Some(&"separate".to_string()) {
+                tags.insert(osm::SIDEWALK.to_string(), "both".to_string());
+            }
+
             // If there's no parking data in OSM already, then assume no parking and mark that it's
             // inferred.
             if !tags.contains_key(osm::PARKING_LEFT)
@@ -139,6 +195,8 @@ pub fn extract_osm(
                 tags.insert(osm::INFERRED_SIDEWALKS.to_string(), "true".to_string());
                 if tags.get(osm::HIGHWAY) == Some(&"motorway".to_string())
                     || tags.get(osm::HIGHWAY) == Some(&"motorway_link".to_string())
+                    || !tags.contains_key(osm::HIGHWAY)
+                    || tags.get(osm::HIGHWAY) == Some(&"service".to_string())
                     || tags.get("junction") == Some(&"roundabout".to_string())
                 {
                     tags.insert(osm::SIDEWALK.to_string(), "none".to_string());
@@ -192,14 +250,33 @@ pub fn extract_osm(
                     shop.clone(),
                 ));
             }
+            let polygon = Polygon::new(&deduped);
+            // Parking garages are open to the public
+            let (public_garage_name, num_parking_spots) =
+                if tags.get("amenity") == Some(&"parking".to_string()) {
+                    (
+                        Some(
+                            tags.get("name")
+                                .cloned()
+                                .unwrap_or_else(|| "unnamed garage".to_string()),
+                        ),
+                        parking_capacity(&tags, &polygon),
+                    )
+                } else {
+                    (None, 0)
+                };
             map.buildings.insert(
                 OriginalBuilding { osm_way_id: way.id },
                 RawBuilding {
-                    polygon: Polygon::new(&deduped),
+                    polygon,
                     osm_tags: tags,
-                    public_garage_name: None,
-                    num_parking_spots: 0,
+                    public_garage_name,
+                    num_parking_spots,
                     amenities,
+                    entrance: deduped
+                        .iter()
+                        .find(|pt| entrances.contains(&pt.to_hashable()))
+                        .cloned(),
                 },
             );
         } else if let Some(at) = get_area_type(&tags) {
@@ -215,12 +292,23 @@ pub fn extract_osm(
         } else if tags.get("natural") == Some(&"coastline".to_string()) {
             coastline_groups.push((way.id, pts));
         } else if tags.get("amenity") == Some(&"parking".to_string()) {
-            // TODO Verify parking = surface or handle other cases?
+            // Multi-storey and underground garages without a building get their capacity
+            // guessed, since there aren't aisles to infer spots from.
+            let polygon = Polygon::new(&pts);
+            let capacity = match tags.get("parking").map(|x| x.as_str()) {
+                Some("multi-storey") | Some("underground") => {
+                    Some(parking_capacity(&tags, &polygon))
+                }
+                _ => tags.get("capacity").and_then(|x| x.parse::<usize>().ok()),
+            };
             map.parking_lots.push(RawParkingLot {
-                polygon: Polygon::new(&pts),
+                polygon,
                 osm_id: way.id,
+                capacity,
+                park_and_ride: tags.get("park_ride").map(|x| x != "no").unwrap_or(false),
             });
         } else if tags.get("highway") == Some(&"service".to_string()) {
+            // Driveways and parking aisles. Alleys and other service roads are normal roads.
             map.parking_aisles.push(pts);
         } else {
             // The way might be part of a relation later.
@@ -362,6 +450,7 @@ pub fn extract_osm(
                         public_garage_name: None,
                         num_parking_spots: 0,
                         amenities,
+                        entrance: None,
                     },
                 );
             }
@@ -397,6 +486,8 @@ pub fn extract_osm(
         map,
         roads,
         traffic_signals,
+        unmarked_crossings,
+        missing_curb_ramps,
         osm_node_ids,
         simple_turn_restrictions,
         complicated_turn_restrictions,
@@ -418,9 +509,51 @@ fn tags_to_map(raw_tags: &[osm_xml::Tag]) -> BTreeMap<String, String> {
         .collect()
 }
 
+fn read_doc(path: &str, timer: &mut Timer) -> osm_xml::OSM {
+    if path.ends_with(".pbf") {
+        crate::pbf::read(path, timer).expect("OSM PBF parsing failed")
+    } else {
+        let (reader, done) = FileWithProgress::new(path).unwrap();
+        let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
+        done(timer);
+        doc
+    }
+}
+
+// Neighboring extracts overlap along their shared border. Nodes are identical in both, but a way
+// or relation crossing the border might only be partly included in one of them, so keep whichever
+// copy is more complete.
+fn stitch(doc: &mut osm_xml::OSM, other: osm_xml::OSM) {
+    doc.bounds = None;
+    for (id, node) in other.nodes {
+        doc.nodes.entry(id).or_insert(node);
+    }
+    for (id, way) in other.ways {
+        let replace = doc
+            .ways
+            .get(&id)
+            .map(|existing| existing.nodes.len() < way.nodes.len())
+            .unwrap_or(true);
+        if replace {
+            doc.ways.insert(id, way);
+        }
+    }
+    for (id, rel) in other.relations {
+        let replace = doc
+            .relations
+            .get(&id)
+            .map(|existing| existing.members.len() < rel.members.len())
+            .unwrap_or(true);
+        if replace {
+            doc.relations.insert(id, rel);
+        }
+    }
+}
+
 fn is_road(tags: &BTreeMap<String, String>) -> bool {
     if !tags.contains_key(osm::HIGHWAY) {
-        return false;
+        // Streetcar and light rail tracks become roads with just tracks
+        return osm::is_light_rail(tags);
     }
     // TODO Need to figure out how to ban cutting through in the contraction hierarchy.
     if tags.get("access") == Some(&"private".to_string()) {
@@ -437,21 +570,15 @@ fn is_road(tags: &BTreeMap<String, String>) -> bool {
     // potential reference
     for value in &[
         // List of non-car types from https://wiki.openstreetmap.org/wiki/Key:highway
-        // TODO Footways are very useful, but they need more work to associate with main roads
-        "footway",
         "living_street",
-        "pedestrian",
         "track",
         "bus_guideway",
         "escape",
         "raceway",
         "bridleway",
-        "steps",
         "path",
         "cycleway",
         "proposed",
-        // This one's debatable. Includes alleys.
-        "service",
         // more discovered manually
         "abandoned",
         "elevator",
@@ -464,10 +591,46 @@ fn is_road(tags: &BTreeMap<String, String>) -> bool {
             return false;
         }
     }
+    // Alleys and unclassified service roads can be driven through, but driveways, parking aisles,
+    // and drive-throughs just lead to one place.
+    if tags.get(osm::HIGHWAY) == Some(&"service".to_string())
+        && tags.get("service").map(|x| x != "alley").unwrap_or(false)
+    {
+        return false;
+    }
+
+    // Footways, stairs, and pedestrian streets become sidewalk-only roads. Sidewalks mapped
+    // separately alongside a road are already covered by that road's sidewalk lanes, and
+    // pedestrian plazas aren't linear.
+    if tags.get(osm::HIGHWAY) == Some(&"footway".to_string())
+        || tags.get(osm::HIGHWAY) == Some(&"steps".to_string())
+        || tags.get(osm::HIGHWAY) == Some(&"pedestrian".to_string())
+    {
+        return tags.get("footway") != Some(&"sidewalk".to_string())
+            && tags.get("area") != Some(&"yes".to_string());
+    }
 
     true
 }
 
+// Use the mapped capacity, or guess from the footprint and number of levels.
+fn parking_capacity(tags: &BTreeMap<String, String>, polygon: &Polygon) -> usize {
+    if let Some(n) = tags.get("capacity").and_then(|x| x.parse::<usize>().ok()) {
+        return n;
+    }
+    let levels = tags
+        .get("building:levels")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or(1);
+    // TODO Rough guess, including the space for aisles and ramps
+    ((polygon.area() / 30.0) as usize) * levels
+}
+
+fn is_unmarked_crossing(tags: &BTreeMap<String, String>) -> bool {
+    tags.get("crossing") == Some(&"unmarked".to_string())
+        || tags.get("crossing") == Some(&"no".to_string())
+}
+
 fn is_bldg(tags: &BTreeMap<String, String>) -> bool {
     tags.contains_key("building")
 }
@@ -0,0 +1,88 @@
+use abstutil::Timer;
+use osm_xml::{Member, Node, Relation, Tag, UnresolvedReference, Way, OSM};
+use osmpbfreader::{OsmId, OsmObj, OsmPbfReader, Tags};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+
+// Reads an .osm.pbf extract into the same structure the XML parser produces, so the rest of the
+// import doesn't care about the input format. PBF is much smaller and faster to parse than XML,
+// so huge extracts don't need to be converted first.
+pub fn read(path: &str, timer: &mut Timer) -> Result<OSM, Box<dyn Error>> {
+    timer.start(format!("read {}", path));
+    let mut reader = OsmPbfReader::new(File::open(path)?);
+
+    let mut doc = OSM {
+        bounds: None,
+        nodes: HashMap::new(),
+        ways: HashMap::new(),
+        relations: HashMap::new(),
+    };
+    for obj in reader.iter() {
+        match obj? {
+            OsmObj::Node(n) => {
+                doc.nodes.insert(
+                    n.id.0,
+                    Node {
+                        id: n.id.0,
+                        lat: n.lat(),
+                        lon: n.lon(),
+                        tags: convert_tags(&n.tags),
+                    },
+                );
+            }
+            OsmObj::Way(w) => {
+                doc.ways.insert(
+                    w.id.0,
+                    Way {
+                        id: w.id.0,
+                        tags: convert_tags(&w.tags),
+                        nodes: w
+                            .nodes
+                            .iter()
+                            .map(|id| UnresolvedReference::Node(id.0))
+                            .collect(),
+                    },
+                );
+            }
+            OsmObj::Relation(r) => {
+                doc.relations.insert(
+                    r.id.0,
+                    Relation {
+                        id: r.id.0,
+                        tags: convert_tags(&r.tags),
+                        members: r
+                            .refs
+                            .iter()
+                            .map(|m| {
+                                let role = m.role.to_string();
+                                match m.member {
+                                    OsmId::Node(id) => {
+                                        Member::Node(UnresolvedReference::Node(id.0), role)
+                                    }
+                                    OsmId::Way(id) => {
+                                        Member::Way(UnresolvedReference::Way(id.0), role)
+                                    }
+                                    OsmId::Relation(id) => {
+                                        Member::Relation(UnresolvedReference::Relation(id.0), role)
+                                    }
+                                }
+                            })
+                            .collect(),
+                    },
+                );
+            }
+        }
+    }
+    timer.stop(format!("read {}", path));
+    Ok(doc)
+}
+
+fn convert_tags(tags: &Tags) -> Vec<Tag> {
+    tags.iter()
+        .map(|(k, v)| Tag {
+            key: k.to_string(),
+            val: v.to_string(),
+        })
+        .collect()
+}
@@ -1,5 +1,5 @@
 use abstutil::{Counter, Timer};
-use geom::{Distance, HashablePt2D, Pt2D};
+use geom::{Distance, HashablePt2D, PolyLine, Pt2D};
 use map_model::raw::{
     OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad, RestrictionType,
 };
@@ -11,6 +11,8 @@ pub fn split_up_roads(
         mut map,
         roads,
         traffic_signals,
+        unmarked_crossings,
+        missing_curb_ramps,
         osm_node_ids,
         simple_turn_restrictions,
         complicated_turn_restrictions,
@@ -19,24 +21,40 @@ pub fn split_up_roads(
         RawMap,
         Vec<(i64, RawRoad)>,
         HashSet<HashablePt2D>,
+        HashSet<HashablePt2D>,
+        HashSet<HashablePt2D>,
         HashMap<HashablePt2D, i64>,
         Vec<(RestrictionType, i64, i64, i64)>,
         Vec<(i64, i64, i64)>,
         Vec<(Pt2D, String, String)>,
     ),
+    simplify_epsilon: Distance,
     timer: &mut Timer,
 ) -> (RawMap, Vec<(Pt2D, String, String)>) {
     timer.start("splitting up roads");
 
     let mut pt_to_intersection: HashMap<HashablePt2D, OriginalIntersection> = HashMap::new();
-    let mut counts_per_pt = Counter::new();
+    // Points in the middle of ways, per layer. A bridge passing over a road might share a point
+    // with it, but that's not an intersection.
+    let mut counts_per_pt: Counter<(HashablePt2D, isize)> = Counter::new();
+    // Where ways start or end, on any layer
+    let mut endpts: HashSet<HashablePt2D> = HashSet::new();
+    // Where ways cross in the middle, only on one layer
+    let mut crossings: HashSet<(HashablePt2D, isize)> = HashSet::new();
     for (_, r) in &roads {
+        let layer = osm::layer(&r.osm_tags);
         for (idx, raw_pt) in r.center_points.iter().enumerate() {
             let pt = raw_pt.to_hashable();
-            let count = counts_per_pt.inc(pt);
 
-            // All start and endpoints of ways are also intersections.
-            if count == 2 || idx == 0 || idx == r.center_points.len() - 1 {
+            // All start and endpoints of ways are also intersections. That's also how bridges and
+            // tunnels connect to the ground.
+            let is_endpt = idx == 0 || idx == r.center_points.len() - 1;
+            if is_endpt {
+                endpts.insert(pt);
+            } else if counts_per_pt.inc((pt, layer)) == 2 {
+                crossings.insert((pt, layer));
+            }
+            if is_endpt || crossings.contains(&(pt, layer)) {
                 if !pt_to_intersection.contains_key(&pt) {
                     let id = OriginalIntersection {
                         osm_node_id: osm_node_ids[&pt],
@@ -68,6 +86,7 @@ pub fn split_up_roads(
     for (osm_way_id, orig_road) in &roads {
         timer.next();
         let mut r = orig_road.clone();
+        let layer = osm::layer(&orig_road.osm_tags);
         let mut pts = Vec::new();
         let endpt1 = pt_to_intersection[&orig_road.center_points[0].to_hashable()];
         let endpt2 = pt_to_intersection[&orig_road.center_points.last().unwrap().to_hashable()];
@@ -78,7 +97,13 @@ pub fn split_up_roads(
             if pts.len() == 1 {
                 continue;
             }
-            if let Some(i2) = pt_to_intersection.get(&pt.to_hashable()) {
+            // Only split where this way actually meets another. Another way on a different layer
+            // might cross at this point, but a bridge doesn't connect to the road below it.
+            let hash_pt = pt.to_hashable();
+            if !endpts.contains(&hash_pt) && !crossings.contains(&(hash_pt, layer)) {
+                continue;
+            }
+            if let Some(i2) = pt_to_intersection.get(&hash_pt) {
                 if i1 == endpt1 {
                     r.osm_tags
                         .insert(osm::ENDPT_BACK.to_string(), "true".to_string());
@@ -87,7 +112,32 @@ pub fn split_up_roads(
                     r.osm_tags
                         .insert(osm::ENDPT_FWD.to_string(), "true".to_string());
                 }
-                r.center_points = dedupe_angles(std::mem::replace(&mut pts, Vec::new()));
+                // Crossings are usually mapped a few meters away from the intersection, so
+                // associate each one with the nearer end of the road.
+                for pt in &pts {
+                    if unmarked_crossings.contains(&pt.to_hashable()) {
+                        let key = if pt.dist_to(pts[0]) < pt.dist_to(*pts.last().unwrap()) {
+                            osm::UNMARKED_CROSSING_BACK
+                        } else {
+                            osm::UNMARKED_CROSSING_FWD
+                        };
+                        r.osm_tags.insert(key.to_string(), "true".to_string());
+                    }
+                    if missing_curb_ramps.contains(&pt.to_hashable()) {
+                        let key = if pt.dist_to(pts[0]) < pt.dist_to(*pts.last().unwrap()) {
+                            osm::NO_CURB_RAMP_BACK
+                        } else {
+                            osm::NO_CURB_RAMP_FWD
+                        };
+                        r.osm_tags.insert(key.to_string(), "true".to_string());
+                    }
+                }
+                let center_pts = dedupe_angles(std::mem::replace(&mut pts, Vec::new()));
+                // Mappers often trace curves with many wobbly points. Straighten those out.
+                r.center_points = match PolyLine::maybe_new(center_pts.clone()) {
+                    Some(pl) => pl.simplify(simplify_epsilon).into_points(),
+                    None => center_pts,
+                };
                 // Start a new road
                 map.roads.insert(
                     OriginalRoad {
@@ -99,6 +149,10 @@ pub fn split_up_roads(
                 );
                 r.osm_tags.remove(osm::ENDPT_FWD);
                 r.osm_tags.remove(osm::ENDPT_BACK);
+                r.osm_tags.remove(osm::UNMARKED_CROSSING_FWD);
+                r.osm_tags.remove(osm::UNMARKED_CROSSING_BACK);
+                r.osm_tags.remove(osm::NO_CURB_RAMP_FWD);
+                r.osm_tags.remove(osm::NO_CURB_RAMP_BACK);
                 i1 = *i2;
                 pts.push(*pt);
             }
@@ -208,3 +262,75 @@ fn dedupe_angles(pts: Vec<Pt2D>) -> Vec<Pt2D> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::split_up_roads;
+    use abstutil::Timer;
+    use geom::{Distance, Pt2D};
+    use map_model::osm;
+    use map_model::raw::{OriginalIntersection, OriginalRoad, RawMap, RawRoad};
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    fn road(pts: Vec<Pt2D>) -> RawRoad {
+        RawRoad {
+            center_points: pts,
+            osm_tags: BTreeMap::new(),
+            turn_restrictions: Vec::new(),
+            complicated_turn_restrictions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_curb_ramps_dont_carry_over() {
+        // One way split at two nodes by side streets, with a raised kerb only at the first split
+        let a = Pt2D::new(0.0, 0.0);
+        let b = Pt2D::new(50.0, 0.0);
+        let c = Pt2D::new(100.0, 0.0);
+        let d = Pt2D::new(150.0, 0.0);
+        let b_side = Pt2D::new(50.0, 50.0);
+        let c_side = Pt2D::new(100.0, 50.0);
+        let roads = vec![
+            (1, road(vec![a, b, c, d])),
+            (2, road(vec![b, b_side])),
+            (3, road(vec![c, c_side])),
+        ];
+        let mut osm_node_ids = HashMap::new();
+        for (id, pt) in vec![a, b, c, d, b_side, c_side].into_iter().enumerate() {
+            osm_node_ids.insert(pt.to_hashable(), id as i64);
+        }
+        let mut missing_curb_ramps = HashSet::new();
+        missing_curb_ramps.insert(b.to_hashable());
+
+        let (map, _) = split_up_roads(
+            (
+                RawMap::blank("test", "test"),
+                roads,
+                HashSet::new(),
+                HashSet::new(),
+                missing_curb_ramps,
+                osm_node_ids,
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+            ),
+            Distance::meters(0.5),
+            &mut Timer::throwaway(),
+        );
+
+        let tags = |i1: i64, i2: i64| {
+            let id = OriginalRoad {
+                osm_way_id: 1,
+                i1: OriginalIntersection { osm_node_id: i1 },
+                i2: OriginalIntersection { osm_node_id: i2 },
+            };
+            map.roads[&id].osm_tags.clone()
+        };
+        assert!(tags(0, 1).contains_key(osm::NO_CURB_RAMP_FWD));
+        assert!(!tags(0, 1).contains_key(osm::NO_CURB_RAMP_BACK));
+        assert!(tags(1, 2).contains_key(osm::NO_CURB_RAMP_BACK));
+        assert!(!tags(1, 2).contains_key(osm::NO_CURB_RAMP_FWD));
+        assert!(!tags(2, 3).contains_key(osm::NO_CURB_RAMP_BACK));
+        assert!(!tags(2, 3).contains_key(osm::NO_CURB_RAMP_FWD));
+    }
+}
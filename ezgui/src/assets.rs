@@ -16,6 +16,8 @@ pub struct Assets {
     // Keyed by filename, then scale factor mangled into a hashable form. Tuple doesn't work
     // because of borrowing.
     svg_cache: RefCell<HashMap<String, HashMap<usize, (GeomBatch, Bounds)>>>,
+    // From the English text in the code to the current language. Anything missing stays English.
+    translations: RefCell<HashMap<String, String>>,
     pub text_opts: Options,
 }
 
@@ -28,6 +30,7 @@ impl Assets {
             text_cache: RefCell::new(LruCache::new(500)),
             line_height_cache: RefCell::new(HashMap::new()),
             svg_cache: RefCell::new(HashMap::new()),
+            translations: RefCell::new(HashMap::new()),
             text_opts: Options::default(),
         };
         *a.default_line_height.borrow_mut() =
@@ -63,7 +66,8 @@ impl Assets {
         height
     }
 
-    // TODO No text in wasm yet
+    // TODO No text in wasm yet, because fonts can't be loaded from the filesystem. Until they're
+    // embedded, estimate the height from the size, so layout still matches native builds roughly.
     #[cfg(target_arch = "wasm32")]
     pub fn line_height(&self, font: Font, font_size: usize) -> f64 {
         let key = (font, font_size);
@@ -71,7 +75,13 @@ impl Assets {
             return *height;
         }
 
-        text::SCALE_LINE_HEIGHT * 30.0
+        // Ascent plus descent of Overpass and Bungee, relative to the em size
+        let height = text::SCALE_LINE_HEIGHT
+            * *self.scale_factor.borrow()
+            * WASM_FONT_HEIGHT_PER_EM
+            * (font_size as f64);
+        self.line_height_cache.borrow_mut().insert(key, height);
+        height
     }
 
     pub fn get_cached_text(&self, key: &String) -> Option<GeomBatch> {
@@ -95,6 +105,20 @@ impl Assets {
             .insert(key_scale_factor(scale_factor), (geom, bounds));
     }
 
+    pub fn translate(&self, text: &str) -> String {
+        self.translations
+            .borrow()
+            .get(text)
+            .cloned()
+            .unwrap_or_else(|| text.to_string())
+    }
+
+    pub fn set_translations(&self, translations: HashMap<String, String>) {
+        *self.translations.borrow_mut() = translations;
+        // Cached text is keyed by the English text
+        self.text_cache.borrow_mut().clear();
+    }
+
     pub fn set_scale_factor(&self, scale_factor: f64) {
         *self.scale_factor.borrow_mut() = scale_factor;
         self.text_cache.borrow_mut().clear();
@@ -104,6 +128,9 @@ impl Assets {
     }
 }
 
+#[cfg(target_arch = "wasm32")]
+const WASM_FONT_HEIGHT_PER_EM: f64 = 1.25;
+
 fn key_scale_factor(x: f64) -> usize {
     (x * 100.0) as usize
 }
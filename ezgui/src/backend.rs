@@ -0,0 +1,45 @@
+use crate::drawing::Uniforms;
+use crate::{Canvas, Color, FancyColor, ScreenRectangle};
+use geom::Polygon;
+
+// Exactly one backend is compiled in, chosen by feature. Each provides setup(window_title),
+// creating the window and returning PrerenderInnards and the event loop, and implements the traits
+// below. Only one backend exists in a build, so nothing is dispatched dynamically; the traits just
+// pin down what a new backend (like the web one) has to provide.
+//
+// Text and SVGs are turned into plain polygons before reaching the backend, so nothing else is
+// platform-specific, except for loading fonts (see Assets) and scrolling (see Event).
+#[cfg(feature = "glium-backend")]
+pub use crate::backend_glium::*;
+
+#[cfg(feature = "glow-backend")]
+pub use crate::backend_glow::*;
+
+#[cfg(feature = "wasm-backend")]
+pub use crate::backend_wasm::*;
+
+// Owns the window and GPU context. Uploads geometry and starts drawing each frame.
+pub trait PrerenderBackend {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable;
+    fn request_redraw(&self);
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon);
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_>;
+    fn window_resized(&self, width: f64, height: f64);
+    fn get_inner_size(&self) -> (f64, f64);
+    fn set_window_icon(&self, icon: winit::window::Icon);
+    fn monitor_scale_factor(&self) -> f64;
+}
+
+// Represents one frame that's being drawn
+pub trait GfxCtxBackend {
+    // However the backend remembers the current clipping rectangle
+    type Clip;
+
+    fn clear(&mut self, color: Color);
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards);
+    fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas);
+    fn disable_clipping(&mut self, canvas: &Canvas);
+    fn take_clip(&mut self) -> Option<Self::Clip>;
+    fn restore_clip(&mut self, clip: Option<Self::Clip>);
+    fn finish(self);
+}
@@ -1,3 +1,4 @@
+use crate::backend::{GfxCtxBackend, PrerenderBackend};
 use crate::drawing::Uniforms;
 use crate::{Canvas, Color, FancyColor, ScreenDims, ScreenRectangle};
 use geom::Polygon;
@@ -103,15 +104,17 @@ pub struct GfxCtxInnards<'a> {
     params: glium::DrawParameters<'a>,
 }
 
-impl<'a> GfxCtxInnards<'a> {
-    pub fn clear(&mut self, c: Color) {
+impl<'a> GfxCtxBackend for GfxCtxInnards<'a> {
+    type Clip = glium::Rect;
+
+    fn clear(&mut self, c: Color) {
         // Without this, SRGB gets enabled and post-processes the color from the fragment
         // shader.
         self.target
             .clear_color_srgb_and_depth((c.r, c.g, c.b, c.a), 1.0);
     }
 
-    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
         self.target
             .draw(
                 &obj.vertex_buffer,
@@ -123,7 +126,7 @@ impl<'a> GfxCtxInnards<'a> {
             .unwrap();
     }
 
-    pub fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
+    fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
         assert!(self.params.scissor.is_none());
         // The scissor rectangle has to be in device coordinates, so you would think some transform
         // by scale factor (previously called HiDPI factor) has to happen here. But actually,
@@ -138,19 +141,19 @@ impl<'a> GfxCtxInnards<'a> {
         });
     }
 
-    pub fn disable_clipping(&mut self, _: &Canvas) {
+    fn disable_clipping(&mut self, _: &Canvas) {
         assert!(self.params.scissor.is_some());
         self.params.scissor = None;
     }
 
-    pub fn take_clip(&mut self) -> Option<glium::Rect> {
+    fn take_clip(&mut self) -> Option<glium::Rect> {
         self.params.scissor.take()
     }
-    pub fn restore_clip(&mut self, clip: Option<glium::Rect>) {
+    fn restore_clip(&mut self, clip: Option<glium::Rect>) {
         self.params.scissor = clip;
     }
 
-    pub fn finish(self) {
+    fn finish(self) {
         self.target.finish().unwrap();
     }
 }
@@ -161,6 +164,13 @@ pub struct Drawable {
     index_buffer: glium::IndexBuffer<u32>,
 }
 
+impl Drawable {
+    // How much GPU memory this is holding onto
+    pub fn size_bytes(&self) -> usize {
+        self.vertex_buffer.get_size() + self.index_buffer.get_size()
+    }
+}
+
 #[derive(Copy, Clone)]
 struct Vertex {
     position: [f32; 2],
@@ -181,8 +191,8 @@ pub struct PrerenderInnards {
     pub total_bytes_uploaded: Cell<usize>,
 }
 
-impl PrerenderInnards {
-    pub fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
+impl PrerenderBackend for PrerenderInnards {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
         let mut vertices: Vec<Vertex> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
@@ -235,15 +245,15 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn request_redraw(&self) {
+    fn request_redraw(&self) {
         self.display.gl_window().window().request_redraw();
     }
 
-    pub fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
         self.display.gl_window().window().set_cursor_icon(icon);
     }
 
-    pub fn draw_new_frame<'a>(&self) -> GfxCtxInnards<'a> {
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_> {
         GfxCtxInnards {
             target: self.display.draw(),
             params: glium::DrawParameters {
@@ -258,21 +268,21 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn window_resized(&self, _: f64, _: f64) {}
+    fn window_resized(&self, _: f64, _: f64) {}
 
-    pub fn get_inner_size(&self) -> (f64, f64) {
+    fn get_inner_size(&self) -> (f64, f64) {
         let size = self.display.gl_window().window().inner_size();
         (size.width.into(), size.height.into())
     }
 
-    pub fn set_window_icon(&self, icon: winit::window::Icon) {
+    fn set_window_icon(&self, icon: winit::window::Icon) {
         self.display
             .gl_window()
             .window()
             .set_window_icon(Some(icon));
     }
 
-    pub fn monitor_scale_factor(&self) -> f64 {
+    fn monitor_scale_factor(&self) -> f64 {
         self.display.gl_window().window().scale_factor()
     }
 }
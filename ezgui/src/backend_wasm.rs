@@ -1,3 +1,4 @@
+use crate::backend::{GfxCtxBackend, PrerenderBackend};
 use crate::drawing::Uniforms;
 use crate::{Canvas, Color, FancyColor, ScreenDims, ScreenRectangle};
 use geom::Polygon;
@@ -114,8 +115,10 @@ pub struct GfxCtxInnards<'a> {
     current_clip: Option<[i32; 4]>,
 }
 
-impl<'a> GfxCtxInnards<'a> {
-    pub fn clear(&mut self, color: Color) {
+impl<'a> GfxCtxBackend for GfxCtxInnards<'a> {
+    type Clip = [i32; 4];
+
+    fn clear(&mut self, color: Color) {
         unsafe {
             self.gl.clear_color(color.r, color.g, color.b, color.a);
             self.gl.clear(glow::COLOR_BUFFER_BIT);
@@ -125,7 +128,7 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, _: &PrerenderInnards) {
+    fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, _: &PrerenderInnards) {
         unsafe {
             let transform_loc = self
                 .gl
@@ -147,7 +150,7 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
+    fn enable_clipping(&mut self, rect: ScreenRectangle, canvas: &Canvas) {
         assert!(self.current_clip.is_none());
         // The scissor rectangle has to be in device coordinates, so you would think some transform
         // by scale factor (previously called HiDPI factor) has to happen here. But actually,
@@ -164,7 +167,7 @@ impl<'a> GfxCtxInnards<'a> {
         self.current_clip = Some([left, bottom, width, height]);
     }
 
-    pub fn disable_clipping(&mut self, canvas: &Canvas) {
+    fn disable_clipping(&mut self, canvas: &Canvas) {
         assert!(self.current_clip.is_some());
         self.current_clip = None;
         unsafe {
@@ -177,10 +180,10 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn take_clip(&mut self) -> Option<[i32; 4]> {
+    fn take_clip(&mut self) -> Option<[i32; 4]> {
         self.current_clip.take()
     }
-    pub fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
+    fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
         self.current_clip = clip;
         if let Some(c) = clip {
             unsafe {
@@ -189,7 +192,7 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
-    pub fn finish(self) {}
+    fn finish(self) {}
 }
 
 // Something that's been sent to the GPU already.
@@ -199,6 +202,14 @@ pub struct Drawable {
     vert_array: glow::WebVertexArrayKey,
     _elem_buffer: glow::WebBufferKey,
     num_indices: i32,
+    num_bytes: usize,
+}
+
+impl Drawable {
+    // How much GPU memory this is holding onto
+    pub fn size_bytes(&self) -> usize {
+        self.num_bytes
+    }
 }
 
 pub struct PrerenderInnards {
@@ -211,8 +222,8 @@ pub struct PrerenderInnards {
     pub total_bytes_uploaded: Cell<usize>,
 }
 
-impl PrerenderInnards {
-    pub fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
+impl PrerenderBackend for PrerenderInnards {
+    fn actually_upload(&self, permanent: bool, list: Vec<(FancyColor, &Polygon)>) -> Drawable {
         let mut vertices: Vec<[f32; 6]> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
 
@@ -284,13 +295,12 @@ impl PrerenderInnards {
             (vert_buffer, vert_array, elem_buffer)
         };
         let num_indices = indices.len() as i32;
+        let num_bytes = vertices.len() * std::mem::size_of::<[f32; 6]>()
+            + indices.len() * std::mem::size_of::<u32>();
 
         if permanent {
-            /*self.total_bytes_uploaded.set(
-                self.total_bytes_uploaded.get()
-                    + vertex_buffer.get_size()
-                    + index_buffer.get_size(),
-            );*/
+            self.total_bytes_uploaded
+                .set(self.total_bytes_uploaded.get() + num_bytes);
         }
 
         Drawable {
@@ -298,18 +308,19 @@ impl PrerenderInnards {
             vert_array,
             _elem_buffer: elem_buffer,
             num_indices,
+            num_bytes,
         }
     }
 
-    pub fn request_redraw(&self) {
+    fn request_redraw(&self) {
         self.window.request_redraw();
     }
 
-    pub fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
+    fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
         self.window.set_cursor_icon(icon);
     }
 
-    pub fn draw_new_frame(&self) -> GfxCtxInnards {
+    fn draw_new_frame(&self) -> GfxCtxInnards<'_> {
         GfxCtxInnards {
             gl: &self.gl,
             program: &self.program,
@@ -317,7 +328,7 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn window_resized(&self, width: f64, height: f64) {
+    fn window_resized(&self, width: f64, height: f64) {
         unsafe {
             self.gl.viewport(0, 0, width as i32, height as i32);
             // I think it's safe to assume there's not a clip right now.
@@ -325,16 +336,16 @@ impl PrerenderInnards {
         }
     }
 
-    pub fn get_inner_size(&self) -> (f64, f64) {
+    fn get_inner_size(&self) -> (f64, f64) {
         let size = self.window.inner_size();
         (size.width.into(), size.height.into())
     }
 
-    pub fn set_window_icon(&self, icon: winit::window::Icon) {
+    fn set_window_icon(&self, icon: winit::window::Icon) {
         self.window.set_window_icon(Some(icon));
     }
 
-    pub fn monitor_scale_factor(&self) -> f64 {
+    fn monitor_scale_factor(&self) -> f64 {
         self.window.scale_factor()
     }
 }
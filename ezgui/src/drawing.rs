@@ -1,5 +1,5 @@
 use crate::assets::Assets;
-use crate::backend::{GfxCtxInnards, PrerenderInnards};
+use crate::backend::{GfxCtxBackend, GfxCtxInnards, PrerenderBackend, PrerenderInnards};
 use crate::{
     Canvas, Color, Drawable, FancyColor, GeomBatch, ScreenDims, ScreenPt, ScreenRectangle, Style,
     Text,
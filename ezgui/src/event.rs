@@ -376,6 +376,89 @@ impl Key {
     }
 }
 
+// Parses the variant name, like "A", "Num1", or "LeftArrow"
+impl std::str::FromStr for Key {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Key, String> {
+        Ok(match raw {
+            "A" => Key::A,
+            "B" => Key::B,
+            "C" => Key::C,
+            "D" => Key::D,
+            "E" => Key::E,
+            "F" => Key::F,
+            "G" => Key::G,
+            "H" => Key::H,
+            "I" => Key::I,
+            "J" => Key::J,
+            "K" => Key::K,
+            "L" => Key::L,
+            "M" => Key::M,
+            "N" => Key::N,
+            "O" => Key::O,
+            "P" => Key::P,
+            "Q" => Key::Q,
+            "R" => Key::R,
+            "S" => Key::S,
+            "T" => Key::T,
+            "U" => Key::U,
+            "V" => Key::V,
+            "W" => Key::W,
+            "X" => Key::X,
+            "Y" => Key::Y,
+            "Z" => Key::Z,
+            "Num1" => Key::Num1,
+            "Num2" => Key::Num2,
+            "Num3" => Key::Num3,
+            "Num4" => Key::Num4,
+            "Num5" => Key::Num5,
+            "Num6" => Key::Num6,
+            "Num7" => Key::Num7,
+            "Num8" => Key::Num8,
+            "Num9" => Key::Num9,
+            "Num0" => Key::Num0,
+            "LeftBracket" => Key::LeftBracket,
+            "RightBracket" => Key::RightBracket,
+            "Space" => Key::Space,
+            "Slash" => Key::Slash,
+            "Dot" => Key::Dot,
+            "Comma" => Key::Comma,
+            "Semicolon" => Key::Semicolon,
+            "Colon" => Key::Colon,
+            "Equals" => Key::Equals,
+            "SingleQuote" => Key::SingleQuote,
+            "Escape" => Key::Escape,
+            "Enter" => Key::Enter,
+            "Tab" => Key::Tab,
+            "Backspace" => Key::Backspace,
+            "LeftShift" => Key::LeftShift,
+            "LeftControl" => Key::LeftControl,
+            "LeftAlt" => Key::LeftAlt,
+            "RightAlt" => Key::RightAlt,
+            "LeftArrow" => Key::LeftArrow,
+            "RightArrow" => Key::RightArrow,
+            "UpArrow" => Key::UpArrow,
+            "DownArrow" => Key::DownArrow,
+            "F1" => Key::F1,
+            "F2" => Key::F2,
+            "F3" => Key::F3,
+            "F4" => Key::F4,
+            "F5" => Key::F5,
+            "F6" => Key::F6,
+            "F7" => Key::F7,
+            "F8" => Key::F8,
+            "F9" => Key::F9,
+            "F10" => Key::F10,
+            "F11" => Key::F11,
+            "F12" => Key::F12,
+            _ => {
+                return Err(format!("Unknown key {}", raw));
+            }
+        })
+    }
+}
+
 // TODO This is not an ideal representation at all.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum MultiKey {
@@ -1,3 +1,4 @@
+use crate::backend::PrerenderBackend;
 use crate::{
     svg, text, Canvas, Color, Drawable, Event, GeomBatch, GfxCtx, Line, Prerender, ScreenPt, Style,
     Text, UserInput,
@@ -5,7 +6,7 @@ use crate::{
 use abstutil::{elapsed_seconds, Timer, TimerSink};
 use geom::Polygon;
 use instant::Instant;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 pub struct EventCtx<'a> {
     pub(crate) fake_mouseover: bool,
@@ -91,6 +92,16 @@ impl<'a> EventCtx<'a> {
         self.prerender.upload(batch)
     }
 
+    // Spans marked translatable are looked up as they're drawn. Text built with format! won't
+    // match, so translate the template first.
+    pub fn set_translations(&self, translations: HashMap<String, String>) {
+        self.prerender.assets.set_translations(translations)
+    }
+
+    pub fn translate(&self, text: &str) -> String {
+        self.prerender.assets.translate(text)
+    }
+
     pub fn set_scale_factor(&self, scale: f64) {
         self.prerender.assets.set_scale_factor(scale)
     }
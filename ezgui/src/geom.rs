@@ -1,8 +1,9 @@
 use crate::{svg, Color, Drawable, EventCtx, FancyColor, GfxCtx, Prerender, ScreenDims};
 use geom::{Angle, Bounds, Polygon, Pt2D};
+use serde::{Deserialize, Serialize};
 
 /// A mutable builder for a group of colored polygons.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GeomBatch {
     pub(crate) list: Vec<(FancyColor, Polygon)>,
     pub autocrop_dims: bool,
@@ -170,6 +171,20 @@ impl GeomBatch {
         self
     }
 
+    /// Rotates the whole batch around the center of its bounding box, keeping the polygons in
+    /// place relative to each other.
+    pub fn rotate_around_batch_center(mut self, angle: Angle) -> GeomBatch {
+        let mut bounds = Bounds::new();
+        for (_, poly) in &self.list {
+            bounds.union(poly.get_bounds());
+        }
+        let center = bounds.center();
+        for (_, poly) in &mut self.list {
+            *poly = poly.rotate_around(angle, center);
+        }
+        self
+    }
+
     /// Scales the batch by some factor.
     pub fn scale(mut self, factor: f64) -> GeomBatch {
         for (_, poly) in &mut self.list {
@@ -22,6 +22,7 @@
 //! * [`TexBox`] - single line text entry
 
 mod assets;
+mod backend;
 #[cfg(feature = "glium-backend")]
 mod backend_glium;
 #[cfg(feature = "glow-backend")]
@@ -53,7 +54,7 @@ pub use crate::event_ctx::EventCtx;
 pub use crate::geom::{GeomBatch, RewriteColor};
 pub use crate::input::UserInput;
 pub use crate::managed::{Composite, Widget};
-pub use crate::runner::{run, EventLoopMode, Settings, GUI};
+pub use crate::runner::{run, CrashInfo, EventLoopMode, Settings, GUI};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::Style;
 pub use crate::text::{Line, Text, TextExt, TextSpan};
@@ -82,14 +83,3 @@ pub(crate) enum InputResult<T: Clone> {
     StillActive,
     Done(String, T),
 }
-
-mod backend {
-    #[cfg(feature = "glium-backend")]
-    pub use crate::backend_glium::*;
-
-    #[cfg(feature = "glow-backend")]
-    pub use crate::backend_glow::*;
-
-    #[cfg(feature = "wasm-backend")]
-    pub use crate::backend_wasm::*;
-}
@@ -1,20 +1,24 @@
 use crate::assets::Assets;
-use crate::tools::screenshot::screenshot_everything;
+use crate::backend::{GfxCtxBackend, PrerenderBackend};
+use crate::tools::screenshot::{screenshot_current, screenshot_everything};
 use crate::{text, Canvas, Event, EventCtx, GfxCtx, Key, Prerender, Style, UserInput};
 use geom::Duration;
 use image::{GenericImageView, Pixel};
 use instant::Instant;
 use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
 use std::panic;
 use winit::window::Icon;
 
 const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
+// How many input events to remember for crash reports
+const NUM_RECENT_EVENTS: usize = 100;
 
 pub trait GUI {
     fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode;
     fn draw(&self, g: &mut GfxCtx);
     // Will be called if event or draw panics.
-    fn dump_before_abort(&self, _canvas: &Canvas) {}
+    fn dump_before_abort(&self, _canvas: &Canvas, _crash: &CrashInfo) {}
     // Only before a normal exit, like window close
     fn before_quit(&self, _canvas: &Canvas) {}
 }
@@ -29,12 +33,43 @@ pub enum EventLoopMode {
         max_x: f64,
         max_y: f64,
     },
+    // Draw normally, then save exactly what's on screen to this file, which should be a .png or
+    // .jpg. Keeps animating afterwards, so the caller gets the next update event. If capturing
+    // fails, the file just won't exist, so callers should check for it.
+    ScreenCaptureCurrentShot {
+        filename: String,
+    },
+}
+
+// Passed to GUI::dump_before_abort
+pub struct CrashInfo {
+    pub msg: String,
+    // Oldest first, not including Update events
+    pub recent_events: Vec<String>,
+}
+
+impl CrashInfo {
+    fn new(err: &(dyn std::any::Any + Send), recent_events: &VecDeque<String>) -> CrashInfo {
+        let msg = if let Some(s) = err.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = err.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic".to_string()
+        };
+        CrashInfo {
+            msg,
+            recent_events: recent_events.iter().cloned().collect(),
+        }
+    }
 }
 
 pub(crate) struct State<G: GUI> {
     pub(crate) gui: G,
     pub(crate) canvas: Canvas,
     style: Style,
+    recent_events: VecDeque<String>,
+    key_remap: HashMap<Key, Key>,
 }
 
 impl<G: GUI> State<G> {
@@ -45,6 +80,19 @@ impl<G: GUI> State<G> {
                 ev = Event::MouseWheelScroll(-dx, -dy);
             }
         }
+        match ev {
+            Event::KeyPress(key) => {
+                if let Some(to) = self.key_remap.get(&key) {
+                    ev = Event::KeyPress(*to);
+                }
+            }
+            Event::KeyRelease(key) => {
+                if let Some(to) = self.key_remap.get(&key) {
+                    ev = Event::KeyRelease(*to);
+                }
+            }
+            _ => {}
+        }
 
         // Always reset the cursor, unless we're handling an update event. If we're hovering on a
         // button, we'll discover that by plumbing through the event.
@@ -65,6 +113,14 @@ impl<G: GUI> State<G> {
                 });
         }
 
+        if let Event::Update(_) = ev {
+        } else {
+            self.recent_events.push_back(format!("{:?}", ev));
+            if self.recent_events.len() > NUM_RECENT_EVENTS {
+                self.recent_events.pop_front();
+            }
+        }
+
         // It's impossible / very unlikey we'll grab the cursor in map space before the very first
         // start_drawing call.
         let input = UserInput::new(ev, &self.canvas);
@@ -135,7 +191,8 @@ impl<G: GUI> State<G> {
         })) {
             Ok(pair) => pair,
             Err(err) => {
-                self.gui.dump_before_abort(&self.canvas);
+                self.gui
+                    .dump_before_abort(&self.canvas, &CrashInfo::new(&*err, &self.recent_events));
                 panic::resume_unwind(err);
             }
         }
@@ -150,7 +207,8 @@ impl<G: GUI> State<G> {
         if let Err(err) = panic::catch_unwind(panic::AssertUnwindSafe(|| {
             self.gui.draw(&mut g);
         })) {
-            self.gui.dump_before_abort(&self.canvas);
+            self.gui
+                .dump_before_abort(&self.canvas, &CrashInfo::new(&*err, &self.recent_events));
             panic::resume_unwind(err);
         }
         let naming_hint = g.naming_hint.take();
@@ -177,6 +235,7 @@ pub struct Settings {
     dump_raw_events: bool,
     scale_factor: Option<f64>,
     window_icon: Option<String>,
+    key_remap: HashMap<Key, Key>,
 }
 
 impl Settings {
@@ -189,6 +248,7 @@ impl Settings {
             dump_raw_events: false,
             scale_factor: None,
             window_icon: None,
+            key_remap: HashMap::new(),
         }
     }
 
@@ -206,6 +266,11 @@ impl Settings {
         self.default_font_size = size;
     }
 
+    // Pressing the physical key `from` acts like pressing `to`
+    pub fn remap_key(&mut self, from: Key, to: Key) {
+        self.key_remap.insert(from, to);
+    }
+
     pub fn scale_factor(&mut self, scale_factor: f64) {
         self.scale_factor = Some(scale_factor);
     }
@@ -252,7 +317,13 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
         style: &mut style,
     });
 
-    let mut state = State { canvas, gui, style };
+    let mut state = State {
+        canvas,
+        gui,
+        style,
+        recent_events: VecDeque::new(),
+        key_remap: settings.key_remap,
+    };
 
     if settings.profiling_enabled {
         abstutil::start_profiler();
@@ -344,6 +415,16 @@ pub fn run<G: 'static + GUI, F: FnOnce(&mut EventCtx) -> G>(settings: Settings,
             } => {
                 screenshot_everything(&mut state, &dir, &prerender, zoom, max_x, max_y);
             }
+            EventLoopMode::ScreenCaptureCurrentShot { filename } => {
+                if !screenshot_current(&mut state, &prerender, &filename) {
+                    println!("Couldn't capture {}", filename);
+                }
+                // Don't count the time spent capturing
+                last_update = Instant::now();
+                *control_flow =
+                    winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
+                running = true;
+            }
         }
     });
 }
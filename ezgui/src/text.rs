@@ -35,6 +35,7 @@ pub struct TextSpan {
     fg_color: Color,
     size: usize,
     font: Font,
+    translate: bool,
 }
 
 impl TextSpan {
@@ -48,6 +49,13 @@ impl TextSpan {
         Text::from(self).draw(ctx)
     }
 
+    // Look this up in the language pack when it's drawn. Only use for UI text written in the code,
+    // never for names from the map or the player, which could happen to match something.
+    pub fn translatable(mut self) -> TextSpan {
+        self.translate = true;
+        self
+    }
+
     // Yuwen's new styles, defined in Figma. Should document them in Github better.
 
     pub fn display_title(mut self) -> TextSpan {
@@ -97,6 +105,7 @@ pub fn Line<S: Into<String>>(text: S) -> TextSpan {
         fg_color: DEFAULT_FG_COLOR,
         size: DEFAULT_FONT_SIZE,
         font: DEFAULT_FONT,
+        translate: false,
     }
 }
 
@@ -156,13 +165,25 @@ impl Text {
         if let Some(ref key) = hotkey {
             Text::from_all(vec![
                 Line(key.describe()).fg(ctx.style().hotkey_color).small(),
-                Line(format!(" - {}", action)).small(),
+                Line(" - ").small(),
+                // Separate, so it can be translated
+                Line(action).small(),
             ])
         } else {
             Text::from(Line(action).small())
         }
     }
 
+    // Marks every span as translatable
+    pub fn translatable(mut self) -> Text {
+        for (_, spans) in self.lines.iter_mut() {
+            for span in spans {
+                span.translate = true;
+            }
+        }
+        self
+    }
+
     pub fn change_fg(mut self, fg: Color) -> Text {
         for (_, spans) in self.lines.iter_mut() {
             for span in spans {
@@ -314,7 +335,12 @@ impl Text {
 
     pub(crate) fn inner_wrap_to_pct(mut self, limit: f64, assets: &Assets) -> Text {
         let mut lines = Vec::new();
-        for (bg, spans) in self.lines.drain(..) {
+        for (bg, mut spans) in self.lines.drain(..) {
+            // Wrapping splits up spans, so translate them while they're still whole
+            for span in &mut spans {
+                translate_span(span, assets);
+            }
+
             // First optimistically assume everything just fits.
             if render_line(spans.clone(), svg::LOW_QUALITY, assets)
                 .get_dims()
@@ -339,6 +365,7 @@ impl Text {
                             size: span.size,
                             font: span.font,
                             fg_color: span.fg_color,
+                            translate: false,
                         }],
                         svg::LOW_QUALITY,
                         assets,
@@ -371,7 +398,19 @@ impl Text {
     }
 }
 
-fn render_line(spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBatch {
+// Only happens once per span, however many times it's wrapped and rendered
+fn translate_span(span: &mut TextSpan, assets: &Assets) {
+    if span.translate {
+        span.text = assets.translate(&span.text);
+        span.translate = false;
+    }
+}
+
+fn render_line(mut spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBatch {
+    for span in &mut spans {
+        translate_span(span, assets);
+    }
+
     // TODO This assumes size and font don't change mid-line. We might be able to support that now,
     // actually.
     // https://www.oreilly.com/library/view/svg-text-layout/9781491933817/ch04.html
@@ -52,15 +52,23 @@ pub(crate) fn screenshot_everything<G: GUI>(
     finish(dir_path, filenames, num_tiles_x, num_tiles_y);
 }
 
-fn screencap(filename: &str) -> bool {
+// This runs once per frame when recording, so unlike screencap, it skips converting the image.
+// scrot picks the format from the extension, so use .png or .jpg. Returns false if capturing
+// failed; the file won't exist then.
+pub(crate) fn screenshot_current<G: GUI>(
+    state: &mut State<G>,
+    prerender: &Prerender,
+    filename: &str,
+) -> bool {
+    state.draw(prerender, false);
+    // Same problem as above
+    thread::sleep(time::Duration::from_millis(100));
+    scrot(filename)
+}
+
+fn scrot(filename: &str) -> bool {
     if !process::Command::new("scrot")
-        .args(&[
-            "--quality",
-            "100",
-            "--focused",
-            "--silent",
-            "screenshot.png",
-        ])
+        .args(&["--quality", "100", "--focused", "--silent", filename])
         .status()
         .map(|s| s.success())
         .unwrap_or(false)
@@ -68,6 +76,13 @@ fn screencap(filename: &str) -> bool {
         println!("Screencapping failed; you probably don't have scrot (https://en.wikipedia.org/wiki/Scrot) installed");
         return false;
     }
+    true
+}
+
+fn screencap(filename: &str) -> bool {
+    if !scrot("screenshot.png") {
+        return false;
+    }
     if !process::Command::new("convert")
         .arg("screenshot.png")
         .arg(filename)
@@ -228,6 +228,42 @@ pub enum BtnBuilder {
 }
 
 impl BtnBuilder {
+    // Translate the label when it's drawn. The action stays in English.
+    pub fn translatable(self) -> BtnBuilder {
+        match self {
+            BtnBuilder::TextFG(label, txt, maybe_tooltip) => {
+                BtnBuilder::TextFG(label, txt.translatable(), maybe_tooltip)
+            }
+            BtnBuilder::PlainText {
+                label,
+                txt,
+                maybe_tooltip,
+                pad,
+            } => BtnBuilder::PlainText {
+                label,
+                txt: txt.translatable(),
+                maybe_tooltip,
+                pad,
+            },
+            BtnBuilder::TextBG {
+                label,
+                maybe_tooltip,
+                text,
+                unselected_bg_color,
+                selected_bg_color,
+            } => BtnBuilder::TextBG {
+                label,
+                maybe_tooltip,
+                text: text.translatable(),
+                unselected_bg_color,
+                selected_bg_color,
+            },
+            BtnBuilder::SVG { .. } | BtnBuilder::Custom(_, _, _, _) => {
+                panic!("Only text buttons can be translated")
+            }
+        }
+    }
+
     pub fn tooltip(mut self, tooltip: Text) -> BtnBuilder {
         match self {
             BtnBuilder::TextFG(_, _, ref mut maybe_tooltip)
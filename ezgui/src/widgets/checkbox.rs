@@ -1,6 +1,7 @@
+use crate::widgets::button::BtnBuilder;
 use crate::{
-    Btn, Button, Color, EventCtx, GeomBatch, GfxCtx, MultiKey, ScreenDims, ScreenPt, Widget,
-    WidgetImpl, WidgetOutput,
+    Btn, Button, Color, EventCtx, GeomBatch, GfxCtx, Line, MultiKey, ScreenDims, ScreenPt, Text,
+    Widget, WidgetImpl, WidgetOutput,
 };
 use geom::{Polygon, Pt2D};
 
@@ -34,11 +35,41 @@ impl Checkbox {
         hotkey: Option<MultiKey>,
         enabled: bool,
     ) -> Widget {
-        let label = label.into();
+        Checkbox::inner_text(ctx, label.into(), hotkey, enabled, false)
+    }
+
+    // Like text, but the label is looked up in the language pack. The name stays in English.
+    pub fn translatable_text<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        hotkey: Option<MultiKey>,
+        enabled: bool,
+    ) -> Widget {
+        Checkbox::inner_text(ctx, label.into(), hotkey, enabled, true)
+    }
+
+    fn inner_text(
+        ctx: &EventCtx,
+        label: String,
+        hotkey: Option<MultiKey>,
+        enabled: bool,
+        translate: bool,
+    ) -> Widget {
+        let btn = |prefix: &str| {
+            let mut name = Line(label.clone());
+            if translate {
+                name = name.translatable();
+            }
+            BtnBuilder::TextFG(
+                label.clone(),
+                Text::from_all(vec![Line(prefix), name]),
+                None,
+            )
+        };
         Checkbox::new(
             enabled,
-            Btn::text_fg(format!("[ ] {}", label)).build(ctx, &label, hotkey.clone()),
-            Btn::text_fg(format!("[X] {}", label)).build(ctx, &label, hotkey),
+            btn("[ ] ").build(ctx, &label, hotkey.clone()),
+            btn("[X] ").build(ctx, &label, hotkey),
         )
         .outline(ctx.style().outline_thickness, ctx.style().outline_color)
         .named(label)
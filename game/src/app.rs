@@ -1,22 +1,27 @@
 use crate::challenges::HighScore;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
+use crate::language;
 use crate::layer::Layer;
 use crate::options::Options;
 use crate::render::{AgentCache, AgentColorScheme, DrawMap, DrawOptions, Renderable};
-use crate::sandbox::{GameplayMode, TutorialState};
+use crate::sandbox::{GameplayMode, LastSession, TutorialState};
 use abstutil::{MeasureMemory, Timer};
 use ezgui::{EventCtx, GfxCtx, Prerender};
 use geom::{Bounds, Circle, Distance, Duration, Pt2D, Time};
+use log::{info, warn};
 use map_model::{IntersectionID, Map, Traversable};
 use rand::seq::SliceRandom;
-use sim::{Analytics, GetDrawAgents, Sim, SimCallback, SimFlags};
+use sim::{Analytics, BusService, GetDrawAgents, Sim, SimCallback, SimFlags};
 use std::collections::BTreeMap;
 
 pub struct App {
     // Naming is from older days when there was an A/B test, "side-by-side" mode. Keeping this
     // naming, because that mode will return someday.
     pub primary: PerMap,
+    // Maps loaded earlier in this session, kept around so switching back is instant and keeps
+    // their edits and simulation.
+    pub other_maps: Vec<PerMap>,
     // Only exists in some gameplay modes. Must be carefully reset otherwise. Has the map and
     // scenario name too. TODO Embed that in Analytics directly instead.
     prebaked: Option<(String, String, Analytics)>,
@@ -40,6 +45,7 @@ impl App {
     pub fn new(flags: Flags, opts: Options, ctx: &mut EventCtx, splash: bool) -> App {
         let cs = ColorScheme::new(opts.color_scheme);
         ctx.set_style(cs.gui_style.clone());
+        language::set_language(ctx, &opts.language);
 
         let primary = ctx.loading_screen("load map", |ctx, mut timer| {
             PerMap::new(flags, &cs, ctx, &mut timer)
@@ -66,13 +72,14 @@ impl App {
             ctx.canvas.center_on_map_pt(rand_focus_pt);
         } else {
             if !ctx.canvas.load_camera_state(primary.map.get_name()) {
-                println!("Couldn't load camera state, just focusing on an arbitrary building");
+                warn!("Couldn't load camera state, just focusing on an arbitrary building");
                 ctx.canvas.center_on_map_pt(rand_focus_pt);
             }
         }
 
         App {
             primary,
+            other_maps: Vec::new(),
             prebaked: None,
             agent_cs: AgentColorScheme::new(&cs),
             cs,
@@ -96,43 +103,43 @@ impl App {
         if false {
             if let Some((_, _, ref a)) = self.prebaked {
                 use abstutil::{prettyprint_usize, serialized_size_bytes};
-                println!(
+                info!(
                     "- road_thruput: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.road_thruput))
                 );
-                println!(
+                info!(
                     "- intersection_thruput: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.intersection_thruput))
                 );
-                println!(
+                info!(
                     "- bus_arrivals : {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.bus_arrivals))
                 );
-                println!(
+                info!(
                     "- bus_passengers_waiting: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.bus_passengers_waiting))
                 );
-                println!(
+                info!(
                     "- started_trips: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.started_trips))
                 );
-                println!(
+                info!(
                     "- finished_trips: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.finished_trips))
                 );
-                println!(
+                info!(
                     "- trip_log: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.trip_log))
                 );
-                println!(
+                info!(
                     "- intersection_delays: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.intersection_delays))
                 );
-                println!(
+                info!(
                     "- parking_lane_changes: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.parking_lane_changes))
                 );
-                println!(
+                info!(
                     "- parking_lot_changes: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.parking_lot_changes))
                 );
@@ -140,13 +147,84 @@ impl App {
         }
     }
 
+    // The current map stays loaded in the background. "Switching" to the current map reloads it
+    // from scratch, which is needed after things like changing the color scheme.
     pub fn switch_map(&mut self, ctx: &mut EventCtx, load: String) {
+        ctx.canvas.save_camera_state(self.primary.map.get_name());
+        if load == self.primary.current_flags.sim_flags.load {
+            self.cs = ColorScheme::new(self.opts.color_scheme);
+            self.agent_cs = AgentColorScheme::new(&self.cs);
+            ctx.set_style(self.cs.gui_style.clone());
+
+            let mut flags = self.primary.current_flags.clone();
+            flags.sim_flags.load = load;
+            let cs = &self.cs;
+            let primary = ctx.loading_screen("load map", |ctx, mut timer| {
+                PerMap::new(flags, cs, ctx, &mut timer)
+            });
+            // The old copy of this map isn't worth keeping around
+            self.replace_primary(ctx, primary);
+            return;
+        }
+
+        let name = abstutil::basename(&load);
+        let primary = if let Some(idx) = self
+            .other_maps
+            .iter()
+            .position(|per_map| per_map.map.get_name() == &name)
+        {
+            self.other_maps.remove(idx)
+        } else {
+            let mut flags = self.primary.current_flags.clone();
+            flags.sim_flags.load = load;
+            let cs = &self.cs;
+            ctx.loading_screen("load map", |ctx, mut timer| {
+                PerMap::new(flags, cs, ctx, &mut timer)
+            })
+        };
+        let old = self.replace_primary(ctx, primary);
+        self.other_maps.push(old);
+    }
+
+    // Finishes switching to a map that was loaded somewhere else, like on a background thread.
+    // Drawing it still happens here.
+    pub fn switch_to_loaded_map(&mut self, ctx: &mut EventCtx, load: String, map: Map) {
         ctx.canvas.save_camera_state(self.primary.map.get_name());
         let mut flags = self.primary.current_flags.clone();
         flags.sim_flags.load = load;
-        let session = std::mem::replace(&mut self.session, SessionState::empty());
-        *self = App::new(flags, self.opts.clone(), ctx, false);
-        self.session = session;
+        let cs = &self.cs;
+        let primary = ctx.loading_screen("draw map", |ctx, mut timer| {
+            let sim = Sim::new(&map, flags.sim_flags.opts.clone(), &mut timer);
+            PerMap::from_loaded(flags, map, sim, cs, ctx, &mut timer)
+        });
+        let old = self.replace_primary(ctx, primary);
+        self.other_maps.push(old);
+    }
+
+    pub fn has_map_loaded(&self, name: &str) -> bool {
+        self.primary.map.get_name() == name
+            || self
+                .other_maps
+                .iter()
+                .any(|per_map| per_map.map.get_name() == name)
+    }
+
+    // Returns the previous primary map
+    fn replace_primary(&mut self, ctx: &mut EventCtx, primary: PerMap) -> PerMap {
+        crate::layer::set_layer(ctx, self, None);
+        let old = std::mem::replace(&mut self.primary, primary);
+
+        self.primary.current_selection = None;
+        self.prebaked = None;
+        self.suspended_sim = None;
+        self.per_obj = PerObjectActions::new();
+
+        let bounds = self.primary.map.get_bounds();
+        ctx.canvas.map_dims = (bounds.width(), bounds.height());
+        if !ctx.canvas.load_camera_state(self.primary.map.get_name()) {
+            ctx.canvas.center_on_map_pt(bounds.center());
+        }
+        old
     }
 
     pub fn draw(
@@ -181,6 +259,11 @@ impl App {
                 g.redraw(&self.primary.draw_map.draw_all_buildings);
                 // Not the building paths
             }
+            if layers.show_lanes && self.opts.label_roads {
+                self.primary
+                    .draw_map
+                    .draw_road_labels(g, &self.primary.map, &self.cs, true);
+            }
 
             // Still show some shape selection when zoomed out.
             // TODO Refactor! Ideally use get_obj
@@ -220,6 +303,7 @@ impl App {
                 } else {
                     None
                 },
+                Some(g.get_screen_bounds()),
             );
         } else {
             let mut cache = self.primary.draw_map.agents.borrow_mut();
@@ -265,6 +349,13 @@ impl App {
                     }
                 }
             }
+
+            // On top of everything else, so buildings and agents don't cover up names
+            if self.opts.label_roads && show_objs.layers().show_lanes {
+                self.primary
+                    .draw_map
+                    .draw_road_labels(g, &self.primary.map, &self.cs, false);
+            }
         }
 
         if let Some(i) = sample_intersection {
@@ -372,6 +463,7 @@ impl App {
         let mut lanes: Vec<&dyn Renderable> = Vec::new();
         let mut roads: Vec<&dyn Renderable> = Vec::new();
         let mut intersections: Vec<&dyn Renderable> = Vec::new();
+        let mut crosswalks: Vec<&dyn Renderable> = Vec::new();
         let mut buildings: Vec<&dyn Renderable> = Vec::new();
         let mut bus_stops: Vec<&dyn Renderable> = Vec::new();
         let mut agents_on: Vec<Traversable> = Vec::new();
@@ -394,6 +486,9 @@ impl App {
                 }
                 ID::Intersection(id) => {
                     intersections.push(draw_map.get_i(id));
+                    for c in &draw_map.get_i(id).crosswalks {
+                        crosswalks.push(c);
+                    }
                     for t in &map.get_i(id).turns {
                         agents_on.push(Traversable::Turn(*t));
                     }
@@ -405,7 +500,11 @@ impl App {
                     agents_on.push(Traversable::Lane(map.get_pl(id).driving_pos.lane()));
                 }
 
-                ID::BusStop(_) | ID::Car(_) | ID::Pedestrian(_) | ID::PedCrowd(_) => {
+                ID::BusStop(_)
+                | ID::Car(_)
+                | ID::Pedestrian(_)
+                | ID::PedCrowd(_)
+                | ID::Crosswalk(_) => {
                     panic!("{:?} shouldn't be in the quadtree", id)
                 }
             }
@@ -418,6 +517,7 @@ impl App {
         borrows.extend(lanes);
         borrows.extend(roads);
         borrows.extend(intersections);
+        borrows.extend(crosswalks);
         borrows.extend(buildings);
         borrows.extend(bus_stops);
 
@@ -497,6 +597,9 @@ pub struct Flags {
     // Number of agents to generate when requested. If unspecified, trips to/from borders will be
     // included.
     pub num_agents: Option<usize>,
+    // Keyed by route name. Only applies to the live run, so the prebaked baseline still shows the
+    // normal service.
+    pub bus_service: BTreeMap<String, BusService>,
 }
 
 // All of the state that's bound to a specific map+edit has to live here.
@@ -518,7 +621,18 @@ impl PerMap {
         let mut mem = MeasureMemory::new();
         let (map, sim, _) = flags.sim_flags.load(timer);
         mem.reset("Map and Sim", timer);
+        PerMap::from_loaded(flags, map, sim, cs, ctx, timer)
+    }
 
+    pub fn from_loaded(
+        flags: Flags,
+        map: Map,
+        sim: Sim,
+        cs: &ColorScheme,
+        ctx: &mut EventCtx,
+        timer: &mut Timer,
+    ) -> PerMap {
+        let mut mem = MeasureMemory::new();
         timer.start("draw_map");
         let draw_map = DrawMap::new(&map, &flags, cs, ctx, timer);
         timer.stop("draw_map");
@@ -554,6 +668,8 @@ impl PerMap {
 pub struct SessionState {
     pub tutorial: Option<TutorialState>,
     pub high_scores: BTreeMap<GameplayMode, Vec<HighScore>>,
+    // Autosaved work found at startup, if the previous run crashed
+    pub recovery: Option<LastSession>,
 }
 
 impl SessionState {
@@ -561,6 +677,7 @@ impl SessionState {
         SessionState {
             tutorial: None,
             high_scores: BTreeMap::new(),
+            recovery: None,
         }
     }
 }
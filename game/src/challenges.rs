@@ -1,6 +1,7 @@
 use crate::app::App;
 use crate::game::{State, Transition};
 use crate::managed::{Callback, ManagedGUIState, WrappedComposite};
+use crate::sandbox::gameplay::optimize_bus::BusObjective;
 use crate::sandbox::gameplay::Tutorial;
 use crate::sandbox::{GameplayMode, SandboxMode, TutorialState};
 use abstutil::Timer;
@@ -72,6 +73,25 @@ impl Challenge {
                 },
             ],
         );
+        tree.insert(
+            "Speed up a bus route".to_string(),
+            vec![Challenge {
+                title: "Route 43".to_string(),
+                description: vec![
+                    "Make the 43 faster with a handful of edits, without angering drivers"
+                        .to_string(),
+                ],
+                alias: "bus/pt1".to_string(),
+                gameplay: GameplayMode::OptimizeBus(BusObjective {
+                    route: "43".to_string(),
+                    faster_by_percent: 20,
+                    max_car_slowdown_percent: 10,
+                    edit_budget: 10,
+                    duration: Duration::hours(6),
+                }),
+                cutscene: Some(crate::sandbox::gameplay::optimize_bus::OptimizeBus::cutscene_pt1),
+            }],
+        );
         tree.insert(
             "Traffic signal survivor".to_string(),
             vec![Challenge {
@@ -387,10 +407,8 @@ fn prebake(map: &Map, scenario: Scenario, time_limit: Option<Duration>, timer: &
         );
     }
 
-    abstutil::write_binary(
-        abstutil::path_prebaked_results(&scenario.map_name, &scenario.scenario_name),
-        sim.get_analytics(),
-    );
+    sim.get_analytics()
+        .save_prebaked(&scenario.map_name, &scenario.scenario_name);
     timer.stop(format!(
         "prebake for {} / {}",
         scenario.map_name, scenario.scenario_name
@@ -16,6 +16,8 @@ use ezgui::{Choice, Color, Style};
 pub enum ColorSchemeChoice {
     Standard,
     NightMode,
+    HighContrast,
+    Deuteranopia,
     SAMGreenDay,
     SAMDesertDay,
     BAP,
@@ -28,6 +30,8 @@ impl ColorSchemeChoice {
         vec![
             Choice::new("default", ColorSchemeChoice::Standard),
             Choice::new("night mode", ColorSchemeChoice::NightMode),
+            Choice::new("high contrast", ColorSchemeChoice::HighContrast),
+            Choice::new("color-blind safe", ColorSchemeChoice::Deuteranopia),
             Choice::new("sam green day", ColorSchemeChoice::SAMGreenDay),
             Choice::new("sam desert day", ColorSchemeChoice::SAMDesertDay),
             Choice::new("bap", ColorSchemeChoice::BAP),
@@ -59,13 +63,16 @@ pub struct ColorScheme {
     pub parking_lane: Color,
     pub bike_lane: Color,
     pub under_construction: Color,
+    pub light_rail_track: Color,
     pub sidewalk: Color,
     pub sidewalk_lines: Color,
     pub general_road_marking: Color,
     pub road_center_line: Color,
+    pub modal_filter: Color,
     pub unzoomed_highway: Color,
     pub unzoomed_arterial: Color,
     pub unzoomed_residential: Color,
+    pub bridge_casing: Color,
 
     // Intersections
     pub normal_intersection: Color,
@@ -111,13 +118,20 @@ pub struct ColorScheme {
     // Layers
     pub good_to_bad_red: ColorScale,
     pub good_to_bad_green: ColorScale,
+    // When comparing before and after some changes
+    pub better: Color,
+    pub worse: Color,
+    pub traffic_jam: Color,
     pub bus_layer: Color,
     pub edits_layer: Color,
 
     // Misc
     pub parking_trip: Color,
+    pub delayed_start: Color,
+    pub remote_trip: Color,
     pub before_changes: Color,
     pub after_changes: Color,
+    plot_colors: Vec<Color>,
 }
 
 impl ColorScheme {
@@ -125,6 +139,8 @@ impl ColorScheme {
         match scheme {
             ColorSchemeChoice::Standard => ColorScheme::standard(),
             ColorSchemeChoice::NightMode => ColorScheme::night_mode(),
+            ColorSchemeChoice::HighContrast => ColorScheme::high_contrast(),
+            ColorSchemeChoice::Deuteranopia => ColorScheme::deuteranopia(),
             ColorSchemeChoice::SAMGreenDay => ColorScheme::sam_green_day(),
             ColorSchemeChoice::SAMDesertDay => ColorScheme::sam_desert_day(),
             ColorSchemeChoice::BAP => ColorScheme::bap(),
@@ -157,13 +173,16 @@ impl ColorScheme {
             parking_lane: Color::grey(0.2),
             bike_lane: Color::rgb(15, 125, 75),
             under_construction: Color::rgb(255, 109, 0),
+            light_rail_track: Color::rgb(110, 95, 80),
             sidewalk: Color::grey(0.8),
             sidewalk_lines: Color::grey(0.7),
             general_road_marking: Color::WHITE,
             road_center_line: Color::YELLOW,
+            modal_filter: Color::rgb(0, 160, 60),
             unzoomed_highway: Color::rgb(232, 146, 162),
             unzoomed_arterial: Color::rgb(255, 199, 62),
             unzoomed_residential: Color::WHITE,
+            bridge_casing: Color::grey(0.4),
 
             // Intersections
             normal_intersection: Color::grey(0.2),
@@ -215,27 +234,30 @@ impl ColorScheme {
             // Layers
             good_to_bad_red: ColorScale(vec![hex("#F19A93"), hex("#A32015")]),
             good_to_bad_green: ColorScale(vec![hex("#BEDB92"), hex("#397A4C")]),
+            better: hex("#5D9630"),
+            worse: hex("#A32015"),
+            traffic_jam: Color::RED,
             bus_layer: hex("#4CA7E9"),
             edits_layer: hex("#12409D"),
 
             // Misc
             parking_trip: hex("#4E30A6"),
+            delayed_start: Color::YELLOW,
+            remote_trip: Color::PINK,
             before_changes: Color::BLUE,
             after_changes: Color::RED,
-        }
-    }
-
-    pub fn rotating_color_plot(&self, idx: usize) -> Color {
-        modulo_color(
-            &vec![
+            plot_colors: vec![
                 Color::RED,
                 Color::BLUE,
                 Color::GREEN,
                 Color::PURPLE,
                 Color::BLACK,
             ],
-            idx,
-        )
+        }
+    }
+
+    pub fn rotating_color_plot(&self, idx: usize) -> Color {
+        modulo_color(&self.plot_colors, idx)
     }
 
     pub fn rotating_color_agents(&self, idx: usize) -> Color {
@@ -252,21 +274,161 @@ fn hex(x: &str) -> Color {
     Color::hex(x)
 }
 
-// Alternate, in-progress schemes
+// Complete alternate schemes. Anything not overridden here should still be readable against the
+// new backgrounds, so check every section of ColorScheme when adding one.
 impl ColorScheme {
     fn night_mode() -> ColorScheme {
         let mut cs = ColorScheme::standard();
-        cs.building = hex("#42208B");
+        cs.panel_bg = hex("#1E1B2E").alpha(0.9);
+        cs.gui_style.panel_bg = cs.panel_bg;
+        cs.section_bg = hex("#2E2A45");
+        cs.inner_panel = hex("#2E2A45");
+        cs.fade_map_dark = Color::BLACK.alpha(0.8);
+
+        cs.driving_lane = hex("#1A1A2E");
+        cs.parking_lane = hex("#24243D");
+        cs.bus_lane = hex("#7A2E3A");
+        cs.bike_lane = hex("#1F5E45");
         cs.sidewalk = hex("#7C55C8");
-        cs.grass = hex("#063D88");
-        cs.map_background = hex("#070747");
-        cs.unzoomed_arterial = hex("#54247A");
+        cs.sidewalk_lines = hex("#5F3FA3");
+        cs.general_road_marking = Color::grey(0.6);
+        cs.road_center_line = hex("#C9A227");
         cs.unzoomed_highway = hex("#DD1F7F");
+        cs.unzoomed_arterial = hex("#54247A");
         cs.unzoomed_residential = hex("#4D51AC");
+        cs.bridge_casing = hex("#070747");
+        cs.normal_intersection = hex("#24243D");
+
+        cs.map_background = hex("#070747");
+        cs.unzoomed_interesting_intersection = Color::WHITE;
+        cs.building = hex("#42208B");
+        cs.building_outline = hex("#2A1459");
+        cs.parking_lot = hex("#2E2A45");
+        cs.grass = hex("#063D88");
         cs.water = hex("#2A43AA");
-        // Horrible choice, but demonstrate it can be done.
-        cs.panel_bg = Color::PURPLE;
-        cs.gui_style.panel_bg = Color::PURPLE;
+
+        // Agents need to glow a bit against the dark map
+        cs.unzoomed_car = hex("#FF6B5E");
+        cs.unzoomed_bike = hex("#8EE05B");
+        cs.unzoomed_bus = hex("#5FB4FF");
+        cs.unzoomed_pedestrian = hex("#FFB067");
+        cs.agent_colors = vec![
+            hex("#9D86E9"),
+            hex("#6CC3F5"),
+            hex("#F5DC5A"),
+            hex("#E36C68"),
+            hex("#3BE0B4"),
+        ];
+        cs.route = Color::ORANGE.alpha(0.7);
+        cs.bus_layer = hex("#5FB4FF");
+        cs.edits_layer = hex("#5FB4FF");
+        cs.plot_colors = vec![
+            hex("#FF6B5E"),
+            hex("#5FB4FF"),
+            hex("#8EE05B"),
+            hex("#C792EA"),
+            Color::WHITE,
+        ];
+        cs
+    }
+
+    // For low vision or washed-out projectors: few colors, all saturated, separated by lightness.
+    fn high_contrast() -> ColorScheme {
+        let mut cs = ColorScheme::standard();
+        cs.gui_style.panel_bg = Color::BLACK;
+        cs.gui_style.outline_thickness = 3.0;
+        cs.gui_style.outline_color = Color::WHITE;
+        cs.gui_style.hotkey_color = Color::YELLOW;
+        cs.gui_style.hovering_color = Color::CYAN;
+        cs.panel_bg = Color::BLACK;
+        cs.hovering = Color::CYAN;
+        cs.section_bg = Color::grey(0.2);
+        cs.inner_panel = Color::grey(0.2);
+        cs.selected = Color::CYAN.alpha(0.8);
+        cs.fade_map_dark = Color::BLACK.alpha(0.8);
+
+        cs.driving_lane = Color::BLACK;
+        cs.parking_lane = Color::grey(0.3);
+        cs.bus_lane = Color::rgb(220, 0, 0);
+        cs.bike_lane = Color::rgb(0, 170, 0);
+        cs.sidewalk = Color::WHITE;
+        cs.sidewalk_lines = Color::grey(0.5);
+        cs.general_road_marking = Color::WHITE;
+        cs.road_center_line = Color::YELLOW;
+        cs.unzoomed_highway = Color::BLACK;
+        cs.unzoomed_arterial = Color::grey(0.25);
+        cs.unzoomed_residential = Color::grey(0.45);
+        cs.bridge_casing = Color::WHITE;
+        cs.normal_intersection = Color::BLACK;
+
+        cs.map_background = Color::WHITE;
+        cs.unzoomed_interesting_intersection = Color::RED;
+        cs.building = Color::grey(0.8);
+        cs.building_outline = Color::BLACK;
+        cs.parking_lot = Color::grey(0.6);
+        cs.grass = Color::rgb(170, 230, 120);
+        cs.water = Color::rgb(80, 160, 255);
+
+        cs.unzoomed_car = Color::rgb(230, 0, 0);
+        cs.unzoomed_bike = Color::rgb(0, 170, 0);
+        cs.unzoomed_bus = Color::rgb(0, 0, 230);
+        cs.unzoomed_pedestrian = Color::rgb(255, 130, 0);
+        cs.agent_colors = vec![
+            Color::rgb(0, 0, 230),
+            Color::rgb(230, 0, 0),
+            Color::rgb(255, 200, 0),
+            Color::rgb(150, 0, 200),
+            Color::rgb(0, 170, 0),
+        ];
+        cs.route = Color::rgb(255, 0, 255).alpha(0.7);
+        cs.good_to_bad_red = ColorScale(vec![Color::rgb(255, 200, 200), Color::rgb(200, 0, 0)]);
+        cs.good_to_bad_green = ColorScale(vec![Color::rgb(200, 255, 200), Color::rgb(0, 130, 0)]);
+        cs.better = Color::rgb(0, 130, 0);
+        cs.worse = Color::rgb(200, 0, 0);
+        cs
+    }
+
+    // Safe for deuteranopia (and mostly protanopia), the most common kinds of color blindness.
+    // Red/green pairs are swapped out for the Okabe-Ito palette.
+    fn deuteranopia() -> ColorScheme {
+        let orange = hex("#E69F00");
+        let sky_blue = hex("#56B4E9");
+        let bluish_green = hex("#009E73");
+        let yellow = hex("#F0E442");
+        let blue = hex("#0072B2");
+        let vermillion = hex("#D55E00");
+        let reddish_purple = hex("#CC79A7");
+
+        let mut cs = ColorScheme::standard();
+        cs.selected = sky_blue.alpha(0.7);
+        cs.bus_lane = vermillion;
+        cs.bike_lane = blue;
+        cs.signal_protected_turn = bluish_green;
+        cs.signal_permitted_turn = sky_blue;
+        cs.stop_sign = vermillion;
+
+        cs.unzoomed_car = vermillion;
+        cs.unzoomed_bike = blue;
+        cs.unzoomed_bus = yellow;
+        cs.unzoomed_pedestrian = reddish_purple;
+        cs.agent_colors = vec![blue, orange, sky_blue, reddish_purple, bluish_green];
+        cs.brake_light = vermillion;
+        cs.ped_preparing_bike_body = reddish_purple;
+
+        cs.good_to_bad_red = ColorScale(vec![hex("#FBD9B5"), vermillion]);
+        cs.good_to_bad_green = ColorScale(vec![hex("#C6E2F3"), blue]);
+        cs.better = blue;
+        cs.worse = vermillion;
+        cs.traffic_jam = vermillion;
+        cs.bus_layer = sky_blue;
+        cs.edits_layer = blue;
+
+        cs.parking_trip = reddish_purple;
+        cs.delayed_start = yellow;
+        cs.remote_trip = orange;
+        cs.before_changes = blue;
+        cs.after_changes = orange;
+        cs.plot_colors = vec![vermillion, blue, bluish_green, reddish_purple, Color::BLACK];
         cs
     }
 
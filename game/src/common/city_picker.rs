@@ -105,6 +105,12 @@ impl CityPicker {
             .build(ctx),
         })
     }
+
+    fn switch_map(&mut self, ctx: &mut EventCtx, app: &mut App, name: String) -> Transition {
+        // The map loader takes over this state's place, so it needs on_load
+        let on_load = std::mem::replace(&mut self.on_load, Box::new(|_, _| Transition::Keep));
+        crate::common::switch_map(ctx, app, name, on_load)
+    }
 }
 
 impl State for CityPicker {
@@ -115,10 +121,7 @@ impl State for CityPicker {
                     return Transition::Pop;
                 }
                 name => {
-                    return ctx.loading_screen("switch map", |ctx, _| {
-                        app.switch_map(ctx, abstutil::path_map(name));
-                        (self.on_load)(ctx, app)
-                    });
+                    return self.switch_map(ctx, app, name.to_string());
                 }
             },
             None => {}
@@ -152,10 +155,8 @@ impl State for CityPicker {
                 .per_obj
                 .left_click(ctx, format!("switch to {}", nice_map_name(name)))
             {
-                return ctx.loading_screen("switch map", |ctx, _| {
-                    app.switch_map(ctx, abstutil::path_map(name));
-                    (self.on_load)(ctx, app)
-                });
+                let name = name.clone();
+                return self.switch_map(ctx, app, name);
             }
         }
 
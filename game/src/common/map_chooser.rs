@@ -0,0 +1,226 @@
+use crate::app::App;
+use crate::game::{msg, DrawBaselayer, State, Transition};
+use crate::helpers::nice_map_name;
+use abstutil::Timer;
+use ezgui::{hotkey, Btn, Color, Composite, EventCtx, GfxCtx, Key, Line, Outcome, Text, Widget};
+use map_model::City;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+// Lists the maps installed locally, grouped by the city they belong to, and optionally maps that
+// can be downloaded from an index.
+pub struct MapChooser {
+    composite: Composite,
+    // Map name to URL, only for maps not installed yet
+    downloads: BTreeMap<String, String>,
+    on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+}
+
+// The index is a JSON list of these. The URL points directly at the map's .bin file.
+#[derive(Deserialize)]
+struct MapIndexEntry {
+    name: String,
+    city: String,
+    bytes: u64,
+    url: String,
+}
+
+impl MapChooser {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+    ) -> Box<dyn State> {
+        app.primary.current_selection = None;
+
+        // Which city does each map belong to?
+        let mut region_to_city: BTreeMap<String, String> = BTreeMap::new();
+        for city_name in abstutil::list_all_objects("../data/system/cities".to_string()) {
+            if let Ok(city) = abstutil::maybe_read_binary::<City>(
+                format!("../data/system/cities/{}.bin", city_name),
+                &mut Timer::throwaway(),
+            ) {
+                for (region, _) in city.regions {
+                    region_to_city.insert(region, city_name.clone());
+                }
+            }
+        }
+
+        let local = abstutil::list_all_objects(abstutil::path_all_maps());
+        let mut per_city: BTreeMap<String, Vec<Widget>> = BTreeMap::new();
+        for name in &local {
+            let city = region_to_city
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| "other".to_string());
+            let size = std::fs::metadata(abstutil::path_map(name))
+                .map(|m| describe_bytes(m.len()))
+                .unwrap_or_else(|_| "unknown size".to_string());
+            let label = if name == app.primary.map.get_name() {
+                format!("{} (current)", nice_map_name(name))
+            } else {
+                nice_map_name(name).to_string()
+            };
+            per_city.entry(city).or_insert_with(Vec::new).push(
+                Widget::row(vec![
+                    Btn::text_fg(label).build(ctx, name, None).margin_right(10),
+                    Line(size).secondary().draw(ctx).centered_vert(),
+                ])
+                .margin_below(5),
+            );
+        }
+
+        let mut col = vec![Widget::row(vec![
+            Line("Choose a map").small_heading().draw(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ])
+        .margin_below(10)];
+        for (city, rows) in per_city {
+            col.push(Line(city).draw(ctx).margin_below(5));
+            col.push(Widget::col(rows).margin_below(10));
+        }
+
+        let mut downloads = BTreeMap::new();
+        if let Some(ref url) = app.opts.map_index_url {
+            col.push(Line("Download more maps").small_heading().draw(ctx));
+            match ctx.loading_screen("fetch map index", |_, _| fetch_index(url)) {
+                Ok(index) => {
+                    let mut any = false;
+                    for entry in index {
+                        if local.contains(&entry.name) {
+                            continue;
+                        }
+                        any = true;
+                        col.push(
+                            Widget::row(vec![
+                                Btn::text_fg(format!("download {}", entry.name))
+                                    .build_def(ctx, None)
+                                    .margin_right(10),
+                                Line(format!("{}, {}", entry.city, describe_bytes(entry.bytes)))
+                                    .secondary()
+                                    .draw(ctx)
+                                    .centered_vert(),
+                            ])
+                            .margin_below(5),
+                        );
+                        downloads.insert(entry.name, entry.url);
+                    }
+                    if !any {
+                        col.push(Line("You already have every map").secondary().draw(ctx));
+                    }
+                }
+                Err(err) => {
+                    col.push(
+                        Text::from(Line(format!("Couldn't fetch {}: {}", url, err)).fg(Color::RED))
+                            .wrap_to_pct(ctx, 40)
+                            .draw(ctx),
+                    );
+                }
+            }
+        }
+
+        Box::new(MapChooser {
+            composite: Composite::new(
+                Widget::col(col)
+                    .bg(app.cs.panel_bg)
+                    .outline(2.0, Color::WHITE)
+                    .padding(10),
+            )
+            .max_size_percent(50, 80)
+            .build(ctx),
+            downloads,
+            on_load,
+        })
+    }
+
+    fn switch_map(&mut self, ctx: &mut EventCtx, app: &mut App, name: String) -> Transition {
+        // The map loader takes over this state's place, so it needs on_load
+        let on_load = std::mem::replace(&mut self.on_load, Box::new(|_, _| Transition::Keep));
+        crate::common::switch_map(ctx, app, name, on_load)
+    }
+}
+
+impl State for MapChooser {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                x if x.starts_with("download ") => {
+                    let name = x["download ".len()..].to_string();
+                    let url = self.downloads[&name].clone();
+                    if let Err(err) = ctx.loading_screen("download map", |_, _| {
+                        download(&url, abstutil::path_map(&name))
+                    }) {
+                        return Transition::Push(msg(
+                            "Download failed",
+                            vec![format!("Couldn't download {}: {}", url, err)],
+                        ));
+                    }
+                    return self.switch_map(ctx, app, name);
+                }
+                name => {
+                    if name == app.primary.map.get_name() {
+                        return (self.on_load)(ctx, app);
+                    }
+                    return self.switch_map(ctx, app, name.to_string());
+                }
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn describe_bytes(bytes: u64) -> String {
+    let mb = (bytes as f64) / 1024.0 / 1024.0;
+    if mb < 1.0 {
+        format!("{} KB", (bytes as f64 / 1024.0).ceil())
+    } else {
+        format!("{:.1} MB", mb)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fetch_index(_: &str) -> Result<Vec<MapIndexEntry>, Box<dyn Error>> {
+    Err("Downloading maps isn't supported on the web"
+        .to_string()
+        .into())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_index(url: &str) -> Result<Vec<MapIndexEntry>, Box<dyn Error>> {
+    let resp = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+    Ok(abstutil::from_json(&resp)?)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(_: &str, _: String) -> Result<(), Box<dyn Error>> {
+    Err("Downloading maps isn't supported on the web"
+        .to_string()
+        .into())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn download(url: &str, path: String) -> Result<(), Box<dyn Error>> {
+    let bytes = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+    // Write somewhere temporary first, so a failed download doesn't leave a corrupt map behind
+    let tmp = format!("{}.download", path);
+    std::fs::write(&tmp, &bytes)?;
+    std::fs::rename(tmp, &path)?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use crate::app::App;
+use crate::game::{msg, DrawBaselayer, State, Transition};
+use abstutil::{Timer, TimerSink};
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, EventLoopMode, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, VerticalAlignment, Widget,
+};
+use map_model::Map;
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+// How many of the most recent progress lines to show
+const NUM_LINES: usize = 10;
+
+// Switches to another map, then calls on_load. Maps that aren't in memory yet are read and
+// deserialized on a background thread, which takes tens of seconds for big maps, so the UI stays
+// responsive and loading can be cancelled. Drawing the map uploads to the GPU, so that still has
+// to happen on the main thread afterwards.
+//
+// Limitations:
+// - Cancelling only stops waiting. Map::new can't be interrupted, so the thread still finishes
+//   deserializing the whole map in the background before its result is thrown away.
+// - The very first map is loaded in App::new, before there's any state to show while waiting, so
+//   that load still blocks the UI.
+pub fn switch_map(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    name: String,
+    on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+) -> Transition {
+    // No threads on the web
+    if cfg!(target_arch = "wasm32") || app.has_map_loaded(&name) {
+        return ctx.loading_screen("switch map", |ctx, _| {
+            app.switch_map(ctx, abstutil::path_map(&name));
+            on_load(ctx, app)
+        });
+    }
+    Transition::Replace(MapLoader::new(ctx, app, name, on_load))
+}
+
+struct MapLoader {
+    composite: Composite,
+    name: String,
+    rx: Receiver<Map>,
+    progress: Arc<Mutex<VecDeque<String>>>,
+    on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+}
+
+impl MapLoader {
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        name: String,
+        on_load: Box<dyn Fn(&mut EventCtx, &mut App) -> Transition>,
+    ) -> Box<dyn State> {
+        let (tx, rx) = channel();
+        let progress = Arc::new(Mutex::new(VecDeque::new()));
+        let sink = ProgressSink {
+            lines: progress.clone(),
+        };
+        let path = abstutil::path_map(&name);
+        std::thread::spawn(move || {
+            let mut timer = Timer::new_with_sink("load map", Box::new(sink));
+            let map = Map::new(path, &mut timer);
+            // If loading was cancelled, nobody's listening anymore. That's fine.
+            let _ = tx.send(map);
+        });
+
+        Box::new(MapLoader {
+            composite: Composite::new(
+                Widget::col(vec![
+                    Line(format!("Loading {}", name))
+                        .small_heading()
+                        .draw(ctx)
+                        .margin_below(10),
+                    Text::new().draw(ctx).named("progress").margin_below(10),
+                    Btn::text_fg("cancel")
+                        .translatable()
+                        .build_def(ctx, hotkey(Key::Escape)),
+                ])
+                .padding(10)
+                .bg(app.cs.panel_bg),
+            )
+            .exact_size_percent(50, 50)
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+            .build(ctx),
+            name,
+            rx,
+            progress,
+            on_load,
+        })
+    }
+}
+
+impl State for MapLoader {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                // The thread keeps going, but its result gets thrown away
+                "cancel" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        match self.rx.try_recv() {
+            Ok(map) => {
+                app.switch_to_loaded_map(ctx, abstutil::path_map(&self.name), map);
+                return (self.on_load)(ctx, app);
+            }
+            Err(TryRecvError::Empty) => {}
+            // The thread panicked
+            Err(TryRecvError::Disconnected) => {
+                return Transition::Replace(msg(
+                    "Error",
+                    vec![format!("Couldn't load {}. Check the logs.", self.name)],
+                ));
+            }
+        }
+
+        let mut txt = Text::new();
+        for line in self.progress.lock().unwrap().iter() {
+            txt.add(Line(line));
+        }
+        self.composite
+            .replace(ctx, "progress", txt.draw(ctx).named("progress"));
+
+        // Keep polling the thread
+        Transition::KeepWithMode(EventLoopMode::Animation)
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+struct ProgressSink {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl TimerSink for ProgressSink {
+    fn println(&mut self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == NUM_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    fn reprintln(&mut self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+        lines.pop_back();
+        lines.push_back(line);
+    }
+}
@@ -279,6 +279,8 @@ impl Minimap {
             } else {
                 None
             },
+            // The minimap is clipped to its own panel, so don't cull anything
+            None,
         );
 
         // The cursor
@@ -1,6 +1,8 @@
 mod city_picker;
 mod colors;
 mod heatmap;
+mod map_chooser;
+mod map_loader;
 mod minimap;
 mod navigate;
 mod panels;
@@ -9,6 +11,8 @@ mod warp;
 pub use self::city_picker::CityPicker;
 pub use self::colors::{ColorDiscrete, ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 pub use self::heatmap::{make_heatmap, HeatmapOptions};
+pub use self::map_chooser::MapChooser;
+pub use self::map_loader::switch_map;
 pub use self::minimap::Minimap;
 pub use self::panels::tool_panel;
 pub use self::warp::Warping;
@@ -204,6 +208,19 @@ impl CommonState {
                 // Only selectable in dev mode anyway
                 osd.append(Line(a.to_string()).fg(id_color));
             }
+            ID::Crosswalk(c) => {
+                if app.opts.dev {
+                    osd.append(Line(c.to_string()).fg(id_color));
+                    osd.append(Line(" is "));
+                }
+                osd.append_all(vec![
+                    Line(format!(
+                        "{} crosswalk over ",
+                        map.get_r(c.r).crosswalk_type(c.i).describe()
+                    )),
+                    Line(map.get_r(c.r).get_name()).fg(name_color),
+                ]);
+            }
             ID::Road(r) => {
                 if app.opts.dev {
                     osd.append(Line(r.to_string()).fg(id_color));
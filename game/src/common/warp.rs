@@ -6,6 +6,7 @@ use crate::info::OpenTrip;
 use crate::sandbox::SandboxMode;
 use ezgui::{EventCtx, GfxCtx, Warper, Wizard};
 use geom::Pt2D;
+use log::info;
 use map_model::{AreaID, BuildingID, IntersectionID, LaneID, RoadID};
 use sim::{PedestrianID, PersonID, TripID};
 use std::collections::BTreeMap;
@@ -155,7 +156,7 @@ fn inner_warp(ctx: &mut EventCtx, app: &mut App, line: &str) -> Option<Transitio
         }
     };
     if let Some(pt) = id.canonical_point(&app.primary) {
-        println!("Warping to {:?}", id);
+        info!("Warping to {:?}", id);
         Some(Transition::Replace(Warping::new(
             ctx,
             pt,
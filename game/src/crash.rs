@@ -0,0 +1,67 @@
+use crate::app::App;
+use crate::sandbox::GameplayMode;
+use ezgui::CrashInfo;
+use geom::Time;
+use log::{info, warn};
+use map_model::PermanentMapEdits;
+use serde::Serialize;
+use std::panic;
+
+// Written to a fresh directory when the game panics, so bugs come with everything needed to
+// reproduce them. To replay one, start the same scenario on the same map with --rng_seed, apply
+// edits.json, and load savestate.bin (if it was written) with Sim::load_savestate.
+#[derive(Serialize)]
+struct CrashReport {
+    msg: String,
+    map_name: String,
+    edits_name: String,
+    // None for freeform mode, the tutorial, or outside of sandbox mode
+    scenario: Option<String>,
+    rng_seed: u8,
+    sim_time: Time,
+    // Oldest first
+    recent_events: Vec<String>,
+}
+
+pub fn write_bundle(app: &App, mode: Option<&GameplayMode>, crash: &CrashInfo) {
+    let dir =
+        abstutil::path_crash_bundle(&chrono::Local::now().format("%Y-%m-%d_%H.%M.%S").to_string());
+    let map = &app.primary.map;
+    // While editing, the real simulation is stashed away
+    let sim = app.suspended_sim.as_ref().unwrap_or(&app.primary.sim);
+
+    let scenario = match mode {
+        Some(GameplayMode::PlayScenario(_, ref scenario)) => Some(scenario.clone()),
+        Some(GameplayMode::Freeform(_)) | Some(GameplayMode::Tutorial(_)) | None => None,
+        // The challenges all use this
+        Some(_) => Some("weekday".to_string()),
+    };
+    abstutil::write_json(
+        format!("{}/report.json", dir),
+        &CrashReport {
+            msg: crash.msg.clone(),
+            map_name: map.get_name().to_string(),
+            edits_name: map.get_edits().edits_name.clone(),
+            scenario,
+            rng_seed: app.primary.current_flags.sim_flags.rng_seed,
+            sim_time: sim.time(),
+            recent_events: crash.recent_events.clone(),
+        },
+    );
+    abstutil::write_json(
+        format!("{}/edits.json", dir),
+        &PermanentMapEdits::to_permanent(map.get_edits(), map),
+    );
+
+    // If the sim itself broke, it might be in the middle of a step, so saving could fail too.
+    let path = format!("{}/savestate.bin", dir);
+    if panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        sim.clone().save_as(path.clone());
+    }))
+    .is_err()
+    {
+        warn!("Couldn't write {}", path);
+    }
+
+    info!("Wrote a crash report to {}", dir);
+}
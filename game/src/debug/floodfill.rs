@@ -5,6 +5,7 @@ use ezgui::{
     hotkey, Btn, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
     Outcome, TextExt, VerticalAlignment, Widget,
 };
+use log::warn;
 use map_model::{connectivity, LaneID, Map, PathConstraints};
 use std::collections::HashSet;
 
@@ -48,7 +49,7 @@ impl Floodfiller {
         let num_unreachable = unreachable_lanes.len();
         for l in unreachable_lanes {
             colorer.add_l(l, "unreachable");
-            println!("{} is unreachable", l);
+            warn!("{} is unreachable", l);
         }
 
         let (unzoomed, zoomed, legend) = colorer.build(ctx);
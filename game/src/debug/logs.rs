@@ -0,0 +1,99 @@
+use crate::app::App;
+use crate::game::{msg, DrawBaselayer, State, Transition};
+use ezgui::{
+    hotkey, Btn, Color, Composite, EventCtx, GfxCtx, Key, Line, Outcome, Text, TextExt, Widget,
+};
+use log::Level;
+
+// How many of the most recent lines to show
+const NUM_LINES: usize = 40;
+
+// Shows recent log lines and lets the filters be changed without restarting.
+pub struct LogViewer {
+    composite: Composite,
+}
+
+impl LogViewer {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        Box::new(LogViewer {
+            composite: make_panel(ctx, app),
+        })
+    }
+}
+
+impl State for LogViewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "refresh" => {
+                    self.composite = make_panel(ctx, app);
+                }
+                "apply filters" => {
+                    let spec = self.composite.text_box("filters");
+                    if let Err(err) = abstutil::set_log_filters(&spec) {
+                        return Transition::Push(msg("Bad filters", vec![err]));
+                    }
+                    self.composite = make_panel(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App) -> Composite {
+    let logs = abstutil::recent_logs();
+    let mut txt = Text::new();
+    for line in logs.iter().skip(logs.len().saturating_sub(NUM_LINES)) {
+        let color = match line.level {
+            Level::Error => Color::RED,
+            Level::Warn => Color::YELLOW,
+            Level::Info => Color::WHITE,
+            Level::Debug | Level::Trace => Color::grey(0.7),
+        };
+        txt.add(Line(line.to_string()).fg(color).small());
+    }
+    if logs.is_empty() {
+        txt.add(Line("Nothing logged yet").secondary());
+    }
+
+    Composite::new(
+        Widget::col(vec![
+            Widget::row(vec![
+                Line("Logs").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Widget::row(vec![
+                "Filters:".draw_text(ctx).margin_right(10),
+                Widget::text_entry(ctx, abstutil::get_log_filters(), true).named("filters"),
+                Btn::text_fg("apply filters")
+                    .build_def(ctx, hotkey(Key::Enter))
+                    .margin_right(10),
+                Btn::text_fg("refresh").build_def(ctx, hotkey(Key::R)),
+            ]),
+            "Like \"info,sim::mechanics=debug\"".draw_text(ctx),
+            txt.draw(ctx),
+        ])
+        .padding(10)
+        .bg(app.cs.panel_bg),
+    )
+    .max_size_percent(90, 80)
+    .build(ctx)
+}
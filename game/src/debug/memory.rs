@@ -0,0 +1,110 @@
+use crate::app::App;
+use crate::game::{DrawBaselayer, State, Transition};
+use ezgui::{hotkey, Btn, Composite, EventCtx, GfxCtx, Key, Line, Outcome, Text, Widget};
+
+// Breaks down memory use by subsystem, so optimizing a metro-scale map starts with the real hogs.
+// Measuring walks through everything, so it only happens when the panel is opened or refreshed.
+pub struct MemoryUsage {
+    composite: Composite,
+}
+
+impl MemoryUsage {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        Box::new(MemoryUsage {
+            composite: make_panel(ctx, app),
+        })
+    }
+}
+
+impl State for MemoryUsage {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "refresh" => {
+                    self.composite = make_panel(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App) -> Composite {
+    let sections = vec![
+        ("Map geometry", app.primary.map.memory_usage()),
+        ("Drawing (GPU)", app.primary.draw_map.gpu_memory_usage()),
+        ("Drawing (CPU)", app.primary.draw_map.cpu_memory_usage()),
+        ("Agents and paths", app.primary.sim.memory_usage()),
+    ];
+    let total: usize = sections
+        .iter()
+        .flat_map(|(_, list)| list.iter().map(|(_, bytes)| *bytes))
+        .sum();
+
+    let mut txt = Text::new();
+    for (name, mut list) in sections {
+        list.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+        let subtotal: usize = list.iter().map(|(_, bytes)| *bytes).sum();
+        txt.add(Line(format!("{}: {}", name, prettyprint_bytes(subtotal))).small_heading());
+        for (part, bytes) in list {
+            txt.add(Line(format!(
+                "- {}: {} ({:.1}%)",
+                part,
+                prettyprint_bytes(bytes),
+                100.0 * (bytes as f64) / (total.max(1) as f64)
+            )));
+        }
+        txt.add(Line(""));
+    }
+    txt.add(Line(format!(
+        "Whole process (virtual): {}",
+        prettyprint_bytes(abstutil::process_used_memory_bytes())
+    )));
+    txt.add(Line(format!(
+        "Ever uploaded to the GPU, including things freed since: {}",
+        prettyprint_bytes(ctx.prerender.get_total_bytes_uploaded())
+    )));
+    txt.add(Line("Subsystems are measured by serialized size, so they undercount").secondary());
+
+    Composite::new(
+        Widget::col(vec![
+            Widget::row(vec![
+                Line("Memory usage").small_heading().draw(ctx),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Btn::text_fg("refresh")
+                .build_def(ctx, hotkey(Key::R))
+                .margin_below(10),
+            txt.draw(ctx),
+        ])
+        .padding(10)
+        .bg(app.cs.panel_bg),
+    )
+    .max_size_percent(60, 80)
+    .build(ctx)
+}
+
+fn prettyprint_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", (bytes as f64) / 1024.0 / 1024.0)
+    } else {
+        format!("{:.1} KB", (bytes as f64) / 1024.0)
+    }
+}
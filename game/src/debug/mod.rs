@@ -1,6 +1,9 @@
 mod floodfill;
+mod logs;
+mod memory;
 mod objects;
 mod polygons;
+mod replay;
 
 use crate::app::{App, ShowLayers, ShowObject};
 use crate::common::{tool_panel, CommonState, ContextualActions};
@@ -14,8 +17,9 @@ use ezgui::{
     GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text, VerticalAlignment, Widget, Wizard,
 };
 use geom::Pt2D;
+use log::info;
 use map_model::{ControlTrafficSignal, NORMAL_LANE_THICKNESS};
-use sim::{AgentID, Sim, TripID};
+use sim::{AgentID, Replay, Sim, TripID};
 use std::collections::HashSet;
 
 pub struct DebugMode {
@@ -61,7 +65,11 @@ impl DebugMode {
                             (hotkey(Key::Y), "load previous sim state"),
                             (hotkey(Key::U), "load next sim state"),
                             (None, "pick a savestate to load"),
+                            (None, "save replay"),
+                            (None, "load replay"),
                             (None, "find bad traffic signals"),
+                            (None, "view logs"),
+                            (None, "view memory usage"),
                         ]
                         .into_iter()
                         .map(|(key, action)| {
@@ -181,6 +189,28 @@ impl State for DebugMode {
                 "pick a savestate to load" => {
                     return Transition::Push(WizardState::new(Box::new(load_savestate)));
                 }
+                "save replay" => match app.primary.sim.make_replay(&app.primary.map) {
+                    Some(replay) => {
+                        let path = app.primary.sim.replay_path();
+                        abstutil::write_binary(path.clone(), &replay);
+                        return Transition::Push(msg("Saved replay", vec![path]));
+                    }
+                    None => {
+                        return Transition::Push(msg(
+                            "Error",
+                            vec!["Run with --record_replay to save a replay"],
+                        ));
+                    }
+                },
+                "view logs" => {
+                    return Transition::Push(logs::LogViewer::new(ctx, app));
+                }
+                "view memory usage" => {
+                    return Transition::Push(memory::MemoryUsage::new(ctx, app));
+                }
+                "load replay" => {
+                    return Transition::Push(WizardState::new(Box::new(load_replay)));
+                }
                 "unhide everything" => {
                     self.hidden.clear();
                     app.primary.current_selection = app.calculate_current_selection(
@@ -398,6 +428,22 @@ fn load_savestate(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option
     Some(Transition::Pop)
 }
 
+fn load_replay(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+    let name = wiz.wrap(ctx).choose_string("Load which replay?", || {
+        abstutil::list_all_objects(abstutil::path_all_replays(app.primary.map.get_name()))
+    })?;
+    let path = abstutil::path_replay(app.primary.map.get_name(), &name);
+    let replay: Result<Replay, String> = ctx.loading_screen("load replay", |_, mut timer| {
+        abstutil::maybe_read_binary(path, &mut timer).map_err(|err| err.to_string())
+    });
+    match replay.and_then(|r| r.check_map(&app.primary.map).map(|_| r)) {
+        Ok(replay) => Some(Transition::Replace(replay::ReplayViewer::new(
+            ctx, app, replay,
+        ))),
+        Err(err) => Some(Transition::Replace(msg("Can't load replay", vec![err]))),
+    }
+}
+
 fn calc_all_routes(ctx: &EventCtx, app: &mut App) -> (usize, Drawable) {
     let trips: Vec<TripID> = app
         .primary
@@ -470,7 +516,7 @@ impl ContextualActions for Actions {
         match (id, action.as_ref()) {
             (id, "hide this") => Transition::KeepWithData(Box::new(|state, ctx, app| {
                 let mode = state.downcast_mut::<DebugMode>().unwrap();
-                println!("Hiding {:?}", id);
+                info!("Hiding {:?}", id);
                 app.primary.current_selection = None;
                 mode.hidden.insert(id);
                 mode.reset_info(ctx);
@@ -605,7 +651,7 @@ impl ContextualActions for Actions {
 }
 
 fn find_bad_signals(app: &App) {
-    println!("Bad traffic signals:");
+    info!("Bad traffic signals:");
     for i in app.primary.map.all_intersections() {
         if i.is_traffic_signal() {
             let first = &ControlTrafficSignal::get_possible_policies(
@@ -615,7 +661,7 @@ fn find_bad_signals(app: &App) {
             )[0]
             .0;
             if first == "phase per road" || first == "arbitrary assignment" {
-                println!("- {}", i.id);
+                info!("- {}", i.id);
                 ControlTrafficSignal::brute_force(&app.primary.map, i.id);
             }
         }
@@ -1,6 +1,7 @@
 use crate::app::App;
 use crate::helpers::ID;
 use ezgui::{EventCtx, GfxCtx, Key, Line, Text};
+use log::info;
 use map_model::{Map, PathConstraints};
 use sim::{AgentID, Sim};
 
@@ -44,21 +45,22 @@ impl ObjectDebugger {
         match id {
             ID::Lane(id) => {
                 let l = map.get_l(id);
-                println!("{}", abstutil::to_json(l));
+                info!("{}", abstutil::to_json(l));
 
                 sim.debug_lane(id);
 
                 let r = map.get_parent(id);
-                println!("Parent {} ({}) points to {}", r.id, r.orig_id, r.dst_i);
+                info!("Parent {} ({}) points to {}", r.id, r.orig_id, r.dst_i);
 
                 if l.lane_type.is_for_moving_vehicles() {
                     for constraint in vec![
                         PathConstraints::Car,
                         PathConstraints::Bike,
                         PathConstraints::Bus,
+                        PathConstraints::Train,
                     ] {
                         if constraint.can_use(l, map) {
-                            println!(
+                            info!(
                                 "Cost for {:?}: {}",
                                 constraint,
                                 l.get_max_cost(constraint, map)
@@ -69,51 +71,59 @@ impl ObjectDebugger {
             }
             ID::Intersection(id) => {
                 let i = map.get_i(id);
-                println!("{}", abstutil::to_json(i));
+                info!("{}", abstutil::to_json(i));
 
                 sim.debug_intersection(id, map);
 
-                println!("{} connecting:", i.orig_id);
+                info!("{} connecting:", i.orig_id);
                 for r in &i.roads {
                     let road = map.get_r(*r);
-                    println!("- {} = {}", road.id, road.orig_id);
+                    info!("- {} = {}", road.id, road.orig_id);
                 }
             }
             ID::Building(id) => {
-                println!("{}", abstutil::to_json(map.get_b(id)));
+                info!("{}", abstutil::to_json(map.get_b(id)));
             }
             ID::ParkingLot(id) => {
-                println!("{}", abstutil::to_json(map.get_pl(id)));
+                info!("{}", abstutil::to_json(map.get_pl(id)));
             }
             ID::Car(id) => {
                 sim.debug_car(id);
                 if let Some(t) = sim.agent_to_trip(AgentID::Car(id)) {
-                    println!("Trip log for {}", t);
+                    info!("Trip log for {}", t);
                     for p in sim.get_analytics().get_trip_phases(t, map) {
-                        println!("- {:?}", p);
+                        info!("- {:?}", p);
                     }
                 }
             }
             ID::Pedestrian(id) => {
                 sim.debug_ped(id);
                 if let Some(t) = sim.agent_to_trip(AgentID::Pedestrian(id)) {
-                    println!("Trip log for {}", t);
+                    info!("Trip log for {}", t);
                     for p in sim.get_analytics().get_trip_phases(t, map) {
-                        println!("- {:?}", p);
+                        info!("- {:?}", p);
                     }
                 }
             }
             ID::PedCrowd(members) => {
-                println!("Crowd with {} members", members.len());
+                info!("Crowd with {} members", members.len());
                 for p in members {
                     sim.debug_ped(p);
                 }
             }
             ID::BusStop(id) => {
-                println!("{}", abstutil::to_json(map.get_bs(id)));
+                info!("{}", abstutil::to_json(map.get_bs(id)));
             }
             ID::Area(id) => {
-                println!("{}", abstutil::to_json(map.get_a(id)));
+                info!("{}", abstutil::to_json(map.get_a(id)));
+            }
+            ID::Crosswalk(id) => {
+                if let Some(c) = map.maybe_get_crosswalk(id) {
+                    info!("{} is {:?}", id, c.crosswalk_type);
+                    for t in c.turns {
+                        info!("- {}", t);
+                    }
+                }
             }
             ID::Road(_) => unreachable!(),
         }
@@ -0,0 +1,129 @@
+use crate::app::{App, ShowEverything};
+use crate::game::{DrawBaselayer, State, Transition};
+use crate::render::DrawOptions;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Slider, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, Time};
+use sim::{AgentID, DontDrawAgents, Replay, VehicleType};
+
+// Watch a recorded run without simulating anything. Only works for replays recorded on the
+// currently loaded map with the same edits.
+pub struct ReplayViewer {
+    composite: Composite,
+    replay: Replay,
+    time: Time,
+    draw: Drawable,
+}
+
+impl ReplayViewer {
+    pub fn new(ctx: &mut EventCtx, app: &App, replay: Replay) -> Box<dyn State> {
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Line("Replay").small_heading().draw(ctx).margin(5),
+                    Btn::text_fg("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                format!(
+                    "{} / {} / {}, {} agents",
+                    replay.map_name,
+                    replay.edits_name,
+                    replay.run_name,
+                    abstutil::prettyprint_usize(replay.num_agents())
+                )
+                .draw_text(ctx),
+                "time".draw_text(ctx).named("time"),
+                Slider::horizontal(ctx, 300.0, 25.0, 0.0)
+                    .named("time slider")
+                    .centered_horiz(),
+            ])
+            .bg(app.cs.panel_bg)
+            .padding(5),
+        )
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+        let mut viewer = ReplayViewer {
+            composite,
+            replay,
+            time: Time::START_OF_DAY,
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        viewer.recalculate(ctx, app);
+        Box::new(viewer)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.composite.replace(
+            ctx,
+            "time",
+            Text::from(Line(format!("At {}", self.time)))
+                .draw(ctx)
+                .named("time"),
+        );
+
+        let mut batch = GeomBatch::new();
+        for (id, pt) in self.replay.agents_at(self.time, &app.primary.map) {
+            batch.push(
+                agent_color(app, id),
+                Circle::new(pt, Distance::meters(3.0)).to_polygon(),
+            );
+        }
+        self.draw = ctx.upload(batch);
+    }
+}
+
+impl State for ReplayViewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        let time = Time::START_OF_DAY
+            + self.composite.slider("time slider").get_percent()
+                * (self.replay.end_time - Time::START_OF_DAY);
+        if time != self.time {
+            self.time = time;
+            self.recalculate(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        // Only the replayed agents, not whatever the live sim is doing
+        app.draw(
+            g,
+            DrawOptions::new(),
+            &DontDrawAgents {},
+            &ShowEverything::new(),
+        );
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+    }
+}
+
+fn agent_color(app: &App, id: AgentID) -> Color {
+    match id {
+        AgentID::Car(c) => match c.1 {
+            VehicleType::Car => app.cs.unzoomed_car,
+            VehicleType::Bike => app.cs.unzoomed_bike,
+            VehicleType::Bus | VehicleType::Train => app.cs.unzoomed_bus,
+        },
+        AgentID::Pedestrian(_) | AgentID::BusPassenger(_, _) => app.cs.unzoomed_pedestrian,
+    }
+}
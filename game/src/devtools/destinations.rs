@@ -4,39 +4,76 @@ use crate::game::{State, Transition};
 use crate::helpers::{amenity_type, ID};
 use abstutil::Counter;
 use ezgui::{
-    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
-    HorizontalAlignment, Key, Line, Outcome, Text, VerticalAlignment, Widget,
+    hotkey, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use map_model::BuildingID;
 use sim::{DontDrawAgents, Scenario, TripEndpoint};
 
+// Shows where a scenario's trips start and end, before simulating it.
 pub struct PopularDestinations {
+    origins: Counter<BuildingID>,
+    destinations: Counter<BuildingID>,
+    endpoints: Endpoints,
+    // Derived from the above
     per_bldg: Counter<BuildingID>,
     composite: Composite,
     opts: Option<HeatmapOptions>,
     draw: Drawable,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum Endpoints {
+    Origins,
+    Destinations,
+    Both,
+}
+
 impl PopularDestinations {
     pub fn new(ctx: &mut EventCtx, app: &App, scenario: &Scenario) -> Box<dyn State> {
-        let mut per_bldg = Counter::new();
+        let mut origins = Counter::new();
+        let mut destinations = Counter::new();
         for p in &scenario.people {
             for trip in &p.trips {
+                if let TripEndpoint::Bldg(b) = trip.trip.start(&app.primary.map) {
+                    origins.inc(b);
+                }
                 if let TripEndpoint::Bldg(b) = trip.trip.end(&app.primary.map) {
-                    per_bldg.inc(b);
+                    destinations.inc(b);
                 }
             }
         }
-        PopularDestinations::make(ctx, app, per_bldg, None)
+        PopularDestinations::make(
+            ctx,
+            app,
+            origins,
+            destinations,
+            Endpoints::Destinations,
+            None,
+        )
     }
 
     fn make(
         ctx: &mut EventCtx,
         app: &App,
-        per_bldg: Counter<BuildingID>,
+        origins: Counter<BuildingID>,
+        destinations: Counter<BuildingID>,
+        endpoints: Endpoints,
         opts: Option<HeatmapOptions>,
     ) -> Box<dyn State> {
         let map = &app.primary.map;
+        let mut per_bldg = Counter::new();
+        if endpoints != Endpoints::Destinations {
+            for (b, cnt) in origins.borrow() {
+                per_bldg.add(*b, *cnt);
+            }
+        }
+        if endpoints != Endpoints::Origins {
+            for (b, cnt) in destinations.borrow() {
+                per_bldg.add(*b, *cnt);
+            }
+        }
+
         let mut batch = GeomBatch::new();
         let controls = if let Some(ref o) = opts {
             let mut pts = Vec::new();
@@ -89,12 +126,15 @@ impl PopularDestinations {
         }
 
         Box::new(PopularDestinations {
+            origins,
+            destinations,
+            endpoints,
             per_bldg,
             draw: ctx.upload(batch),
             composite: Composite::new(
                 Widget::col(vec![
                     Widget::row(vec![
-                        Line("Most popular destinations")
+                        Line("Trip activity")
                             .small_heading()
                             .draw(ctx)
                             .margin_right(10),
@@ -102,6 +142,19 @@ impl PopularDestinations {
                             .build(ctx, "close", hotkey(Key::Escape))
                             .align_right(),
                     ]),
+                    Widget::row(vec![
+                        "Count trip".draw_text(ctx).margin_right(10),
+                        Widget::dropdown(
+                            ctx,
+                            "endpoints",
+                            endpoints,
+                            vec![
+                                Choice::new("origins", Endpoints::Origins),
+                                Choice::new("destinations", Endpoints::Destinations),
+                                Choice::new("origins and destinations", Endpoints::Both),
+                            ],
+                        ),
+                    ]),
                     Checkbox::text(ctx, "Show heatmap", None, opts.is_some()),
                     controls,
                     breakdown.draw(ctx),
@@ -149,11 +202,14 @@ impl State for PopularDestinations {
         } else {
             None
         };
-        if self.opts != opts {
+        let endpoints = self.composite.dropdown_value("endpoints");
+        if self.opts != opts || self.endpoints != endpoints {
             return Transition::Replace(PopularDestinations::make(
                 ctx,
                 app,
-                self.per_bldg.clone(),
+                self.origins.clone(),
+                self.destinations.clone(),
+                endpoints,
                 opts,
             ));
         }
@@ -167,9 +223,13 @@ impl State for PopularDestinations {
 
         if let Some(ID::Building(b)) = app.primary.current_selection {
             let mut txt = Text::new();
+            txt.add(Line(format!(
+                "{} trips from here",
+                abstutil::prettyprint_usize(self.origins.get(b))
+            )));
             txt.add(Line(format!(
                 "{} trips to here",
-                abstutil::prettyprint_usize(self.per_bldg.get(b))
+                abstutil::prettyprint_usize(self.destinations.get(b))
             )));
             for (name, amenity) in &app.primary.map.get_b(b).amenities {
                 txt.add(Line(format!("- {} ({})", name, amenity)));
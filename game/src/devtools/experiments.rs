@@ -0,0 +1,121 @@
+use crate::app::App;
+use crate::game::{DrawBaselayer, State, Transition};
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use sim::RunManifest;
+use std::collections::BTreeSet;
+
+// Lists the runs recorded for this map by headless, newest first
+pub struct Experiments {
+    composite: Composite,
+    scenario: Option<String>,
+}
+
+impl Experiments {
+    pub fn new(ctx: &mut EventCtx, app: &App, scenario: Option<String>) -> Box<dyn State> {
+        let map_name = app.primary.map.get_name();
+        let all = RunManifest::load_all(map_name);
+
+        let mut choices = vec![Choice::new("all scenarios", None)];
+        for name in all
+            .iter()
+            .map(|m| m.scenario_name.clone())
+            .collect::<BTreeSet<_>>()
+        {
+            choices.push(Choice::new(name.clone(), Some(name)));
+        }
+
+        // Runs on an older import of the map can't be compared with the newest ones
+        let latest_hash = all.get(0).map(|m| m.map_hash.clone());
+        let mut txt = Text::new();
+        for run in &all {
+            if scenario.is_some() && scenario.as_ref() != Some(&run.scenario_name) {
+                continue;
+            }
+            txt.add(Line(&run.id).small_heading());
+            txt.add(Line(format!(
+                "Finished {}, simulated until {}",
+                chrono::NaiveDateTime::from_timestamp(run.finished_at as i64, 0)
+                    .format("%Y-%m-%d %H:%M"),
+                run.sim_time.ampm_tostring()
+            )));
+            txt.add(Line(format!(
+                "Scenario {}, edits \"{}\", seed {}, code {}",
+                run.scenario_name, run.edits_name, run.rng_seed, run.code_version
+            )));
+            if Some(&run.map_hash) != latest_hash.as_ref() {
+                txt.add(Line("Older version of the map").fg(Color::RED));
+            }
+            for (metric, value) in &run.metrics {
+                txt.add(Line(format!("{}: {:.1}", metric, value)).secondary());
+            }
+            txt.add(Line(""));
+        }
+        if all.is_empty() {
+            txt.add(Line(
+                "No runs recorded yet. headless records one after every run.",
+            ));
+        }
+
+        Box::new(Experiments {
+            composite: Composite::new(
+                Widget::col(vec![
+                    Widget::row(vec![
+                        Line(format!("Experiments on {}", map_name))
+                            .small_heading()
+                            .draw(ctx)
+                            .margin_right(10),
+                        Btn::text_fg("X")
+                            .build(ctx, "close", hotkey(Key::Escape))
+                            .align_right(),
+                    ])
+                    .margin_below(5),
+                    Widget::row(vec![
+                        "Scenario:".draw_text(ctx).margin_right(10),
+                        Widget::dropdown(ctx, "scenario", scenario.clone(), choices),
+                    ])
+                    .margin_below(10),
+                    txt.draw(ctx),
+                ])
+                .padding(10)
+                .bg(app.cs.panel_bg),
+            )
+            .max_size_percent(60, 80)
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+            .build(ctx),
+            scenario,
+        })
+    }
+}
+
+impl State for Experiments {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        let scenario: Option<String> = self.composite.dropdown_value("scenario");
+        if scenario != self.scenario {
+            return Transition::Replace(Experiments::new(ctx, app, scenario));
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
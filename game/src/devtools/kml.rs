@@ -1,12 +1,11 @@
 use crate::app::App;
 use crate::game::{State, Transition};
-use aabb_quadtree::QuadTree;
 use abstutil::prettyprint_usize;
 use ezgui::{
     hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{Circle, Distance, PolyLine, Polygon, Pt2D, Ring};
+use geom::{Circle, Distance, PolyLine, Polygon, Pt2D, QuadTree, Ring};
 use kml::ExtraShapes;
 use map_model::BuildingID;
 use std::collections::{BTreeMap, HashSet};
@@ -46,7 +45,7 @@ impl ViewKML {
 
             let mut batch = GeomBatch::new();
             let mut objects = Vec::new();
-            let mut quadtree = QuadTree::default(app.primary.map.get_bounds().as_bbox());
+            let mut quadtree = QuadTree::new(app.primary.map.get_bounds());
             timer.start_iter("convert shapes", raw_shapes.shapes.len());
             for shape in raw_shapes.shapes {
                 timer.next();
@@ -60,7 +59,7 @@ impl ViewKML {
                     .collect();
                 let obj = make_object(app, shape.attributes, pts, &dataset_name);
 
-                quadtree.insert_with_box(objects.len(), obj.polygon.get_bounds().as_bbox());
+                quadtree.insert_with_bounds(objects.len(), obj.polygon.get_bounds());
                 batch.push(Color::RED.alpha(0.8), obj.polygon.clone());
                 objects.push(obj);
             }
@@ -122,11 +121,10 @@ impl State for ViewKML {
         if ctx.redo_mouseover() {
             self.selected = None;
             if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
-                for &(idx, _, _) in &self.quadtree.query(
-                    Circle::new(pt, Distance::meters(3.0))
-                        .get_bounds()
-                        .as_bbox(),
-                ) {
+                for idx in self
+                    .quadtree
+                    .query(&Circle::new(pt, Distance::meters(3.0)).get_bounds())
+                {
                     if self.objects[*idx].polygon.contains_pt(pt) {
                         self.selected = Some(*idx);
                         break;
@@ -0,0 +1,175 @@
+use crate::app::App;
+use crate::game::{DrawBaselayer, State, Transition};
+use abstutil::{prettyprint_usize, Counter};
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Text,
+    VerticalAlignment, Widget,
+};
+use geom::{LonLat, Polygon, Pt2D};
+use log::{info, warn};
+use map_model::MapStats;
+use sim::{Scenario, TripEndpoint};
+use std::fs::File;
+use std::io::Write;
+
+pub struct MapStatsReport {
+    composite: Composite,
+    stats: MapStats,
+}
+
+impl MapStatsReport {
+    pub fn new(ctx: &mut EventCtx, app: &App, scenario: Option<&Scenario>) -> Box<dyn State> {
+        let map = &app.primary.map;
+        let mut stats = MapStats::new(map);
+        if let Some(s) = scenario {
+            stats.population_per_zone = population_per_zone(app, s).consume();
+        }
+
+        let mut txt = Text::new();
+        txt.add(Line(format!(
+            "{} roads, {} buildings",
+            prettyprint_usize(stats.num_roads),
+            prettyprint_usize(stats.num_buildings)
+        )));
+        txt.add(Line(""));
+        txt.add(Line("Lane length by type").small_heading());
+        for (lt, dist) in &stats.lane_length_per_type {
+            txt.add(Line(format!(
+                "{}: {}",
+                lt,
+                dist.describe_rounded(app.opts.units)
+            )));
+        }
+        txt.add(Line(""));
+        txt.add(Line("Intersections").small_heading());
+        for (control, cnt) in &stats.intersections_per_type {
+            txt.add(Line(format!("{}: {}", control, prettyprint_usize(*cnt))));
+        }
+        txt.add(Line(""));
+        txt.add(Line("Parking").small_heading());
+        txt.add(Line(format!(
+            "{} on-street, {} off-street, {} in lots",
+            prettyprint_usize(stats.onstreet_parking_spots),
+            prettyprint_usize(stats.offstreet_parking_spots),
+            prettyprint_usize(stats.parking_lot_spots)
+        )));
+        txt.add(Line(""));
+        txt.add(Line("Sidewalks").small_heading());
+        txt.add(Line(format!(
+            "{}% of road length has a sidewalk on at least one side",
+            (stats.sidewalk_coverage() * 100.0).round()
+        )));
+        if !stats.population_per_zone.is_empty() {
+            txt.add(Line(""));
+            txt.add(Line("Population per zone").small_heading());
+            for (zone, cnt) in &stats.population_per_zone {
+                txt.add(Line(format!("{}: {}", zone, prettyprint_usize(*cnt))));
+            }
+        }
+
+        Box::new(MapStatsReport {
+            composite: Composite::new(
+                Widget::col(vec![
+                    Widget::row(vec![
+                        Line(format!("Statistics for {}", map.get_name()))
+                            .small_heading()
+                            .draw(ctx)
+                            .margin_right(10),
+                        Btn::text_fg("X")
+                            .build(ctx, "close", hotkey(Key::Escape))
+                            .align_right(),
+                    ])
+                    .margin_below(10),
+                    txt.draw(ctx).margin_below(10),
+                    Widget::row(vec![
+                        Btn::text_fg("export JSON").build_def(ctx, hotkey(Key::J)),
+                        Btn::text_fg("export Markdown").build_def(ctx, hotkey(Key::M)),
+                    ])
+                    .evenly_spaced(),
+                ])
+                .padding(10)
+                .bg(app.cs.panel_bg),
+            )
+            .max_size_percent(50, 80)
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+            .build(ctx),
+            stats,
+        })
+    }
+}
+
+impl State for MapStatsReport {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "export JSON" => {
+                    abstutil::write_json(
+                        format!("../map_stats_{}.json", self.stats.map_name),
+                        &self.stats,
+                    );
+                }
+                "export Markdown" => {
+                    let path = format!("../map_stats_{}.md", self.stats.map_name);
+                    match File::create(&path).and_then(|mut f| {
+                        f.write_all(self.stats.to_markdown(app.opts.units).as_bytes())
+                    }) {
+                        Ok(()) => info!("Wrote {}", path),
+                        Err(err) => warn!("Couldn't write {}: {}", path, err),
+                    }
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        State::grey_out_map(g, app);
+        self.composite.draw(g);
+    }
+}
+
+// Zones are the clipping polygons used to import maps for this city, so a huge map gets broken
+// down by the smaller maps it contains. People are counted by the building their first trip starts
+// from.
+fn population_per_zone(app: &App, scenario: &Scenario) -> Counter<String> {
+    let map = &app.primary.map;
+    let dir = format!("../data/input/{}/polygons", map.get_city_name());
+    let mut zones: Vec<(String, Polygon)> = Vec::new();
+    for name in abstutil::list_all_objects(dir.clone()) {
+        if let Ok(pts) = LonLat::read_osmosis_polygon(format!("{}/{}.poly", dir, name)) {
+            let pts: Vec<Pt2D> = map.get_gps_bounds().forcibly_convert(&pts);
+            if pts.len() >= 3 {
+                zones.push((name, Polygon::new(&pts)));
+            }
+        }
+    }
+
+    let mut counter = Counter::new();
+    for person in &scenario.people {
+        let home = match person.trips.get(0).map(|t| t.trip.start(map)) {
+            Some(TripEndpoint::Bldg(b)) => map.get_b(b).label_center,
+            _ => {
+                counter.inc("off-map".to_string());
+                continue;
+            }
+        };
+        // Zones can overlap; just take the first match
+        let zone = zones
+            .iter()
+            .find(|(_, poly)| poly.contains_pt(home))
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| "outside any zone".to_string());
+        counter.inc(zone);
+    }
+    counter
+}
@@ -1,6 +1,8 @@
 mod blocks;
 mod destinations;
+mod experiments;
 mod kml;
+mod map_stats;
 pub mod mapping;
 mod polygon;
 mod scenario;
@@ -16,6 +18,7 @@ use ezgui::{
     VerticalAlignment, Widget, Wizard,
 };
 use geom::LonLat;
+use log::warn;
 
 pub struct DevToolsMode {
     composite: Composite,
@@ -48,6 +51,8 @@ impl DevToolsMode {
                         Btn::text_fg("load scenario").build_def(ctx, hotkey(Key::W)),
                         Btn::text_fg("view KML").build_def(ctx, hotkey(Key::K)),
                         Btn::text_fg("story maps").build_def(ctx, hotkey(Key::S)),
+                        Btn::text_fg("map statistics").build_def(ctx, hotkey(Key::M)),
+                        Btn::text_fg("experiments").build_def(ctx, hotkey(Key::X)),
                     ])
                     .flex_wrap(ctx, 60),
                 ])
@@ -87,6 +92,12 @@ impl State for DevToolsMode {
                 "story maps" => {
                     return Transition::Push(story::StoryMapEditor::new(ctx, app));
                 }
+                "map statistics" => {
+                    return Transition::Push(map_stats::MapStatsReport::new(ctx, app, None));
+                }
+                "experiments" => {
+                    return Transition::Push(experiments::Experiments::new(ctx, app, None));
+                }
                 "change map" => {
                     return Transition::Push(CityPicker::new(
                         ctx,
@@ -138,7 +149,7 @@ fn choose_polygon(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option
             ctx, app, name, pts,
         ))),
         Err(err) => {
-            println!("Bad polygon {}: {}", name, err);
+            warn!("Bad polygon {}: {}", name, err);
             Some(Transition::Pop)
         }
     }
@@ -6,6 +6,7 @@ use ezgui::{
     VerticalAlignment, Widget,
 };
 use geom::{Circle, Distance, LonLat, Polygon, Pt2D};
+use log::info;
 use std::fs::File;
 use std::io::{Error, Write};
 
@@ -179,6 +180,6 @@ fn save_as_osmosis(name: &str, pts: &Vec<LonLat>) -> Result<(), Error> {
     writeln!(f, "END")?;
     writeln!(f, "END")?;
 
-    println!("Exported {}", path);
+    info!("Exported {}", path);
     Ok(())
 }
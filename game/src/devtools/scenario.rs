@@ -2,6 +2,7 @@ use crate::app::App;
 use crate::common::{ColorDiscrete, CommonState};
 use crate::devtools::blocks::BlockMap;
 use crate::devtools::destinations::PopularDestinations;
+use crate::devtools::map_stats::MapStatsReport;
 use crate::game::{State, Transition};
 use abstutil::prettyprint_usize;
 use ezgui::{
@@ -58,7 +59,8 @@ impl ScenarioManager {
                             .align_right(),
                     ]),
                     Btn::text_fg("block map").build_def(ctx, hotkey(Key::B)),
-                    Btn::text_fg("popular destinations").build_def(ctx, hotkey(Key::D)),
+                    Btn::text_fg("trip activity").build_def(ctx, hotkey(Key::D)),
+                    Btn::text_fg("map statistics").build_def(ctx, hotkey(Key::M)),
                     Text::from_multiline(vec![
                         Line(format!(
                             "{} people",
@@ -100,9 +102,12 @@ impl State for ScenarioManager {
                 "block map" => {
                     return Transition::Push(BlockMap::new(ctx, app, self.scenario.clone()));
                 }
-                "popular destinations" => {
+                "trip activity" => {
                     return Transition::Push(PopularDestinations::new(ctx, app, &self.scenario));
                 }
+                "map statistics" => {
+                    return Transition::Push(MapStatsReport::new(ctx, app, Some(&self.scenario)));
+                }
                 _ => unreachable!(),
             },
             None => {}
@@ -1,6 +1,5 @@
 use crate::app::{App, ShowEverything};
 use crate::common::CommonState;
-use crate::edit::lanes::try_change_lane_type;
 use crate::edit::{apply_map_edits, change_speed_limit};
 use crate::game::{msg, State, Transition};
 use crate::helpers::ID;
@@ -9,7 +8,7 @@ use ezgui::{
     HorizontalAlignment, Key, Line, Outcome, RewriteColor, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Speed};
-use map_model::{EditCmd, IntersectionID, LaneType, Map, RoadID};
+use map_model::{EditCmd, IntersectionID, LaneType, Map, PathConstraints, RoadID};
 use petgraph::graphmap::UnGraphMap;
 use sim::DontDrawAgents;
 use std::collections::BTreeSet;
@@ -186,7 +185,7 @@ impl BulkEdit {
                         .small_heading()
                         .draw(ctx),
                     Widget::row(vec![
-                        change_speed_limit(ctx, Speed::miles_per_hour(25.0)),
+                        change_speed_limit(ctx, Speed::miles_per_hour(25.0), app.opts.units),
                         Btn::text_fg("Confirm")
                             .build(ctx, "confirm speed limit", None)
                             .align_right(),
@@ -225,6 +224,66 @@ impl BulkEdit {
                             .align_right(),
                     ])
                     .margin_below(5),
+                    Widget::row(vec![
+                        "Through traffic:"
+                            .draw_text(ctx)
+                            .centered_vert()
+                            .margin_right(5),
+                        Widget::dropdown(
+                            ctx,
+                            "no through traffic",
+                            vec![PathConstraints::Car].into_iter().collect(),
+                            vec![
+                                Choice::new("allowed", BTreeSet::new()),
+                                Choice::new(
+                                    "no cars",
+                                    vec![PathConstraints::Car].into_iter().collect(),
+                                ),
+                                Choice::new(
+                                    "no cars or bikes",
+                                    vec![PathConstraints::Car, PathConstraints::Bike]
+                                        .into_iter()
+                                        .collect(),
+                                ),
+                            ],
+                        ),
+                        Btn::text_fg("Confirm")
+                            .build(ctx, "confirm through traffic", None)
+                            .align_right(),
+                    ])
+                    .margin_below(5),
+                    Widget::row(vec![
+                        "Banned vehicles:"
+                            .draw_text(ctx)
+                            .centered_vert()
+                            .margin_right(5),
+                        Widget::dropdown(
+                            ctx,
+                            "banned vehicles",
+                            vec![PathConstraints::Car].into_iter().collect(),
+                            vec![
+                                Choice::new("none", BTreeSet::new()),
+                                Choice::new(
+                                    "no cars",
+                                    vec![PathConstraints::Car].into_iter().collect(),
+                                ),
+                                Choice::new(
+                                    "no bikes",
+                                    vec![PathConstraints::Bike].into_iter().collect(),
+                                ),
+                                Choice::new(
+                                    "no cars or bikes",
+                                    vec![PathConstraints::Car, PathConstraints::Bike]
+                                        .into_iter()
+                                        .collect(),
+                                ),
+                            ],
+                        ),
+                        Btn::text_fg("Confirm")
+                            .build(ctx, "confirm banned vehicles", None)
+                            .align_right(),
+                    ])
+                    .margin_below(5),
                     Btn::text_fg("Quit").build_def(ctx, hotkey(Key::Escape)),
                 ])
                 .bg(app.cs.panel_bg)
@@ -260,6 +319,34 @@ impl State for BulkEdit {
                     apply_map_edits(ctx, app, edits);
                     return Transition::Keep;
                 }
+                "confirm through traffic" => {
+                    let vehicles: BTreeSet<PathConstraints> =
+                        self.composite.dropdown_value("no through traffic");
+                    let mut edits = app.primary.map.get_edits().clone();
+                    for r in &self.roads {
+                        if let Err(err) =
+                            edits.change_no_through_traffic(&app.primary.map, *r, vehicles.clone())
+                        {
+                            return Transition::Push(msg("Error", vec![err]));
+                        }
+                    }
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Keep;
+                }
+                "confirm banned vehicles" => {
+                    let vehicles: BTreeSet<PathConstraints> =
+                        self.composite.dropdown_value("banned vehicles");
+                    let mut edits = app.primary.map.get_edits().clone();
+                    for r in &self.roads {
+                        if let Err(err) =
+                            edits.change_banned_vehicles(&app.primary.map, *r, vehicles.clone())
+                        {
+                            return Transition::Push(msg("Error", vec![err]));
+                        }
+                    }
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Keep;
+                }
                 "confirm lanes" => {
                     return Transition::Push(msg(
                         "Edited lane types",
@@ -463,27 +550,24 @@ fn change_lane_types(
 ) -> Vec<String> {
     let mut changes = 0;
     let mut errors = Vec::new();
-    ctx.loading_screen("change lane types", |ctx, _| {
-        for r in roads {
-            for l in app.primary.map.get_r(*r).all_lanes() {
-                if app.primary.map.get_l(l).lane_type == from {
-                    match try_change_lane_type(l, to, &app.primary.map) {
-                        Ok(cmd) => {
-                            let mut edits = app.primary.map.get_edits().clone();
-                            edits.commands.push(cmd);
-                            // Do this immediately, so the next lane we consider sees the true state
-                            // of the world.
-                            apply_map_edits(ctx, app, edits);
-                            changes += 1;
-                        }
-                        Err(err) => {
-                            errors.push(err);
-                        }
+    let map = &app.primary.map;
+    // Each change is checked against the ones before it, so everything can be applied at once.
+    let mut edits = map.get_edits().clone();
+    for r in roads {
+        for l in map.get_r(*r).all_lanes() {
+            if edits.lane_type(map, l) == from {
+                match edits.change_lane_type(map, l, to) {
+                    Ok(()) => {
+                        changes += 1;
+                    }
+                    Err(err) => {
+                        errors.push(err);
                     }
                 }
             }
         }
-    });
+    }
+    apply_map_edits(ctx, app, edits);
 
     errors.insert(
         0,
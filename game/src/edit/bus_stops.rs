@@ -0,0 +1,98 @@
+use crate::app::App;
+use crate::edit::apply_map_edits;
+use crate::game::{msg, State, Transition, WizardState};
+use ezgui::Choice;
+use geom::{Distance, Pt2D};
+use map_model::{BusStopID, LaneID, Position};
+
+// How far one change slides a stop along its sidewalk
+const MOVE_STEP: Distance = Distance::const_meters(20.0);
+
+// Slide a stop along the block, or get rid of it. The routes serving it just skip it.
+pub fn edit_bus_stop(id: BusStopID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let choice = wiz
+            .wrap(ctx)
+            .choose_string("Change this bus stop how?", || {
+                vec![
+                    "move it forwards along the sidewalk",
+                    "move it backwards along the sidewalk",
+                    "remove it",
+                ]
+            })?;
+        let map = &app.primary.map;
+        let mut edits = map.get_edits().clone();
+        let dist = map.get_bs(id).sidewalk_pos.dist_along();
+        let result = match choice.as_ref() {
+            "move it forwards along the sidewalk" => edits.move_bus_stop(map, id, dist + MOVE_STEP),
+            "move it backwards along the sidewalk" => {
+                edits.move_bus_stop(map, id, dist - MOVE_STEP)
+            }
+            _ => edits.remove_bus_stop(map, id),
+        };
+        if let Err(err) = result {
+            return Some(Transition::Replace(msg("Error", vec![err])));
+        }
+        apply_map_edits(ctx, app, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+// Adds a stop to one route, at the spot on the sidewalk closest to the cursor
+pub fn add_bus_stop(app: &App, sidewalk: LaneID, cursor: Pt2D) -> Box<dyn State> {
+    let sidewalk_pos = Position::new(sidewalk, closest_dist_along(app, sidewalk, cursor));
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let map = &app.primary.map;
+        if map.get_all_bus_routes().is_empty() {
+            return Some(Transition::Replace(msg(
+                "Error",
+                vec!["This map doesn't have any bus routes"],
+            )));
+        }
+        let mut wizard = wiz.wrap(ctx);
+        let (_, route) = wizard.choose("Which route should stop here?", || {
+            map.get_all_bus_routes()
+                .iter()
+                .map(|r| Choice::new(r.name.clone(), r.id))
+                .collect()
+        })?;
+        let (_, idx) = wizard.choose("Stop here after which stop?", || {
+            map.get_br(route)
+                .stops
+                .iter()
+                .enumerate()
+                .map(|(idx, bs)| {
+                    Choice::new(
+                        format!(
+                            "after stop {} on {}",
+                            idx + 1,
+                            map.get_parent(bs.sidewalk).get_name()
+                        ),
+                        idx + 1,
+                    )
+                })
+                .collect()
+        })?;
+
+        let mut edits = map.get_edits().clone();
+        if let Err(err) = edits.add_bus_stop(map, sidewalk_pos, vec![(route, idx)]) {
+            return Some(Transition::Replace(msg("Error", vec![err])));
+        }
+        apply_map_edits(ctx, app, edits);
+        Some(Transition::Pop)
+    }))
+}
+
+fn closest_dist_along(app: &App, sidewalk: LaneID, pt: Pt2D) -> Distance {
+    let mut best = (Distance::ZERO, None);
+    let mut dist_so_far = Distance::ZERO;
+    for line in app.primary.map.get_l(sidewalk).lane_center_pts.lines() {
+        let hit = line.project_pt(pt);
+        let dist_away = hit.dist_to(pt);
+        if best.1.map(|d| dist_away < d).unwrap_or(true) {
+            best = (dist_so_far + line.pt1().dist_to(hit), Some(dist_away));
+        }
+        dist_so_far += line.length();
+    }
+    best.0
+}
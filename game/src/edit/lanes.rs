@@ -1,15 +1,18 @@
 use crate::app::App;
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, can_edit_lane, change_speed_limit};
+use crate::edit::{apply_map_edits, can_edit_lane, change_speed_limit, check_lane_connectivity};
 use crate::game::{msg, State, Transition};
 use crate::helpers::ID;
 use crate::render::Renderable;
 use crate::sandbox::GameplayMode;
 use ezgui::{
-    hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
+    hotkey, Btn, Choice, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
     RewriteColor, TextExt, VerticalAlignment, Widget,
 };
-use map_model::{EditCmd, LaneID, LaneType, Map};
+use geom::{Distance, Duration, Speed, Time, Units};
+use map_model::{
+    EditCmd, IntersectionID, Lane, LaneID, LaneType, Map, PathConstraints, Road, TimedSpeedLimit,
+};
 use std::collections::BTreeSet;
 
 pub struct LaneEditor {
@@ -79,7 +82,39 @@ impl LaneEditor {
                 .draw_text(ctx)
                 .centered_horiz(),
             Widget::row(row).centered().margin_below(5),
-            change_speed_limit(ctx, parent.speed_limit).margin_below(5),
+            change_lane_width(ctx, app.primary.map.get_l(l), app.opts.units).margin_below(5),
+            change_speed_limit(ctx, parent.speed_limit, app.opts.units).margin_below(5),
+            timed_speed_limits(ctx, parent, app.opts.units).margin_below(5),
+            Widget::row(vec![
+                Btn::text_fg("make one-way in this direction")
+                    .build_def(ctx, None)
+                    .margin_right(10),
+                Btn::text_fg("make one-way the other way")
+                    .build_def(ctx, None)
+                    .margin_right(10),
+                Btn::text_fg("make two-way").build_def(ctx, None),
+            ])
+            .margin_below(5),
+            Widget::row(vec![
+                if parent.no_through_traffic.is_empty() {
+                    Btn::text_fg("ban through traffic").build_def(ctx, None)
+                } else {
+                    Btn::text_fg("allow through traffic").build_def(ctx, None)
+                }
+                .margin_right(10),
+                if parent.banned_vehicles.is_empty() {
+                    Btn::text_fg("ban cars").build_def(ctx, None)
+                } else {
+                    Btn::text_fg("lift vehicle bans").build_def(ctx, None)
+                }
+                .margin_right(10),
+                if parent.modal_filter.is_none() {
+                    Btn::text_fg("add a modal filter where this lane ends").build_def(ctx, None)
+                } else {
+                    Btn::text_fg("remove the modal filter").build_def(ctx, None)
+                },
+            ])
+            .margin_below(5),
             Widget::row(vec![
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
                 // TODO Handle reverting speed limit too...
@@ -100,6 +135,89 @@ impl LaneEditor {
 
         LaneEditor { l, mode, composite }
     }
+
+    // Just for cars; many roads can be restricted at once, for other vehicles too, from the bulk
+    // editor.
+    fn change_no_through_traffic(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        vehicles: BTreeSet<PathConstraints>,
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        let r = app.primary.map.get_l(self.l).parent;
+        if let Err(err) = edits.change_no_through_traffic(&app.primary.map, r, vehicles) {
+            return Transition::Push(msg("Error", vec![err]));
+        }
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(Box::new(LaneEditor::new(
+            ctx,
+            app,
+            self.l,
+            self.mode.clone(),
+        )))
+    }
+
+    // Also just for cars, like change_no_through_traffic
+    fn change_banned_vehicles(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        vehicles: BTreeSet<PathConstraints>,
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        let r = app.primary.map.get_l(self.l).parent;
+        if let Err(err) = edits.change_banned_vehicles(&app.primary.map, r, vehicles) {
+            return Transition::Push(msg("Error", vec![err]));
+        }
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(Box::new(LaneEditor::new(
+            ctx,
+            app,
+            self.l,
+            self.mode.clone(),
+        )))
+    }
+
+    fn change_modal_filter(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        filter: Option<IntersectionID>,
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        let r = app.primary.map.get_l(self.l).parent;
+        if let Err(err) = edits.change_modal_filter(&app.primary.map, r, filter) {
+            return Transition::Push(msg("Error", vec![err]));
+        }
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(Box::new(LaneEditor::new(
+            ctx,
+            app,
+            self.l,
+            self.mode.clone(),
+        )))
+    }
+
+    fn change_speed_limit_schedule(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        schedule: Vec<TimedSpeedLimit>,
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        let r = app.primary.map.get_l(self.l).parent;
+        if let Err(err) = edits.change_speed_limit_schedule(&app.primary.map, r, schedule) {
+            return Transition::Push(msg("Error", vec![err]));
+        }
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(Box::new(LaneEditor::new(
+            ctx,
+            app,
+            self.l,
+            self.mode.clone(),
+        )))
+    }
 }
 
 impl State for LaneEditor {
@@ -147,6 +265,61 @@ impl State for LaneEditor {
                         try_change_lane_type(self.l, LaneType::Construction, map)
                     }
                     "reverse lane direction" => try_reverse(self.l, map),
+                    "make a school zone" => {
+                        return self.change_speed_limit_schedule(ctx, app, school_zone());
+                    }
+                    "remove timed speed limits" => {
+                        return self.change_speed_limit_schedule(ctx, app, Vec::new());
+                    }
+                    "ban through traffic" => {
+                        let mut vehicles = BTreeSet::new();
+                        vehicles.insert(PathConstraints::Car);
+                        return self.change_no_through_traffic(ctx, app, vehicles);
+                    }
+                    "allow through traffic" => {
+                        return self.change_no_through_traffic(ctx, app, BTreeSet::new());
+                    }
+                    "ban cars" => {
+                        let mut vehicles = BTreeSet::new();
+                        vehicles.insert(PathConstraints::Car);
+                        return self.change_banned_vehicles(ctx, app, vehicles);
+                    }
+                    "lift vehicle bans" => {
+                        return self.change_banned_vehicles(ctx, app, BTreeSet::new());
+                    }
+                    "make one-way in this direction"
+                    | "make one-way the other way"
+                    | "make two-way" => {
+                        let r = map.get_l(self.l).parent;
+                        let fwd = map.get_r(r).is_forwards(self.l);
+                        let mut edits = map.get_edits().clone();
+                        let result = match x.as_ref() {
+                            "make one-way in this direction" => {
+                                edits.convert_to_one_way(map, r, fwd)
+                            }
+                            "make one-way the other way" => edits.convert_to_one_way(map, r, !fwd),
+                            _ => edits.convert_to_two_way(map, r),
+                        };
+                        if let Err(err) = result {
+                            return Transition::Push(msg("Error", vec![err]));
+                        }
+                        if let Some(err_state) = check_lane_connectivity(ctx, app, edits) {
+                            return Transition::Push(err_state);
+                        }
+                        return Transition::Replace(Box::new(LaneEditor::new(
+                            ctx,
+                            app,
+                            self.l,
+                            self.mode.clone(),
+                        )));
+                    }
+                    "add a modal filter where this lane ends" => {
+                        let i = map.get_l(self.l).dst_i;
+                        return self.change_modal_filter(ctx, app, Some(i));
+                    }
+                    "remove the modal filter" => {
+                        return self.change_modal_filter(ctx, app, None);
+                    }
                     "Finish" => {
                         return Transition::Pop;
                     }
@@ -178,6 +351,21 @@ impl State for LaneEditor {
                 }
             }
             None => {
+                let width = self.composite.dropdown_value("lane width");
+                if width != app.primary.map.get_l(self.l).width {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    if let Err(err) = edits.change_lane_width(&app.primary.map, self.l, width) {
+                        return Transition::Push(msg("Error", vec![err]));
+                    }
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(LaneEditor::new(
+                        ctx,
+                        app,
+                        self.l,
+                        self.mode.clone(),
+                    )));
+                }
+
                 let parent = app.primary.map.get_parent(self.l);
                 let new = self.composite.dropdown_value("speed limit");
                 let old = parent.speed_limit;
@@ -215,61 +403,8 @@ impl State for LaneEditor {
     }
 }
 
-fn can_change_lane_type(l: LaneID, new_lt: LaneType, map: &Map) -> Option<String> {
-    let r = map.get_parent(l);
-    let (fwds, idx) = r.dir_and_offset(l);
-    let (mut proposed_lts, other_side) = if fwds {
-        (r.get_lane_types().0, r.get_lane_types().1)
-    } else {
-        (r.get_lane_types().1, r.get_lane_types().0)
-    };
-    proposed_lts[idx] = new_lt;
-
-    // No-op change
-    if map.get_l(l).lane_type == new_lt {
-        return None;
-    }
-
-    // Only one parking lane per side.
-    if proposed_lts
-        .iter()
-        .filter(|lt| **lt == LaneType::Parking)
-        .count()
-        > 1
-    {
-        // TODO Actually, we just don't want two adjacent parking lanes
-        // (What about dppd though?)
-        return Some(format!(
-            "You can only have one parking lane on the same side of the road"
-        ));
-    }
-
-    // Don't let players orphan a bus stop.
-    if !r.all_bus_stops(map).is_empty()
-        && !proposed_lts
-            .iter()
-            .any(|lt| *lt == LaneType::Driving || *lt == LaneType::Bus)
-    {
-        return Some(format!("You need a driving or bus lane for the bus stop!"));
-    }
-
-    let all_types: BTreeSet<LaneType> = other_side
-        .into_iter()
-        .chain(proposed_lts.iter().cloned())
-        .collect();
-
-    // A parking lane must have a driving lane somewhere on the road.
-    if all_types.contains(&LaneType::Parking) && !all_types.contains(&LaneType::Driving) {
-        return Some(format!(
-            "A parking lane needs a driving lane somewhere on the same road"
-        ));
-    }
-
-    None
-}
-
 pub fn try_change_lane_type(l: LaneID, new_lt: LaneType, map: &Map) -> Result<EditCmd, String> {
-    if let Some(err) = can_change_lane_type(l, new_lt, map) {
+    if let Some(err) = map.get_edits().can_change_lane_type(map, l, new_lt) {
         return Err(err);
     }
     Ok(EditCmd::ChangeLaneType {
@@ -294,3 +429,61 @@ fn try_reverse(l: LaneID, map: &Map) -> Result<EditCmd, String> {
         })
     }
 }
+
+// Narrowing lanes is one way to calm traffic, and frees up room to widen the others.
+fn change_lane_width(ctx: &mut EventCtx, lane: &Lane, units: Units) -> Widget {
+    let widths: Vec<Distance> = match units {
+        Units::Metric => (4..=16)
+            .map(|quarters| Distance::meters((quarters as f64) * 0.25))
+            .collect(),
+        Units::Imperial => (3..=13)
+            .map(|feet| Distance::meters((feet as f64) / 3.28084))
+            .collect(),
+    };
+    let mut choices: Vec<Choice<Distance>> = widths
+        .into_iter()
+        .map(|width| Choice::new(width.describe_rounded(units), width))
+        .collect();
+    if !choices.iter().any(|c| c.data == lane.width) {
+        choices.push(Choice::new(lane.width.describe_rounded(units), lane.width));
+    }
+    Widget::row(vec![
+        "Lane width:"
+            .draw_text(ctx)
+            .centered_vert()
+            .margin_right(15),
+        Widget::dropdown(ctx, "lane width", lane.width, choices),
+    ])
+}
+
+fn timed_speed_limits(ctx: &mut EventCtx, road: &Road, units: Units) -> Widget {
+    if road.speed_limit_schedule.is_empty() {
+        Widget::row(vec![
+            "No timed speed limits"
+                .draw_text(ctx)
+                .centered_vert()
+                .margin_right(10),
+            Btn::text_fg("make a school zone").build_def(ctx, None),
+        ])
+    } else {
+        let mut col: Vec<Widget> = road
+            .speed_limit_schedule
+            .iter()
+            .map(|timed| timed.describe(units).draw_text(ctx))
+            .collect();
+        col.push(Btn::text_fg("remove timed speed limits").build_def(ctx, None));
+        Widget::col(col)
+    }
+}
+
+// A lower limit while students arrive and leave
+fn school_zone() -> Vec<TimedSpeedLimit> {
+    vec![(7, 9), (14, 16)]
+        .into_iter()
+        .map(|(start, end)| TimedSpeedLimit {
+            limit: Speed::miles_per_hour(20.0),
+            start: Time::START_OF_DAY + Duration::hours(start),
+            end: Time::START_OF_DAY + Duration::hours(end),
+        })
+        .collect()
+}
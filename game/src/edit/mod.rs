@@ -1,11 +1,14 @@
 mod bulk;
+mod bus_stops;
 mod cluster_traffic_signals;
 mod lanes;
+mod signal_corridors;
 mod stop_signs;
 mod traffic_signals;
 
 pub use self::cluster_traffic_signals::ClusterTrafficSignalEditor;
 pub use self::lanes::LaneEditor;
+pub use self::signal_corridors::SignalCorridorEditor;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
 use crate::app::{App, ShowEverything};
@@ -14,7 +17,7 @@ use crate::debug::DebugMode;
 use crate::game::{msg, State, Transition, WizardState};
 use crate::helpers::ID;
 use crate::managed::{WrappedComposite, WrappedOutcome};
-use crate::render::{DrawIntersection, DrawLane, DrawRoad};
+use crate::render::{DrawBusStop, DrawIntersection, DrawLane, DrawRoad};
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 use abstutil::Timer;
 use ezgui::{
@@ -22,10 +25,11 @@ use ezgui::{
     Key, Line, Outcome, PersistentSplit, RewriteColor, ScreenRectangle, Text, TextExt,
     VerticalAlignment, Widget, WrappedWizard,
 };
-use geom::Speed;
+use geom::{Speed, Units};
+use map_model::connectivity::Unreachable;
 use map_model::{
-    connectivity, EditCmd, EditIntersection, IntersectionID, LaneID, LaneType, MapEdits,
-    PathConstraints, PermanentMapEdits,
+    connectivity, CrosswalkID, CrosswalkType, EditCmd, EditIntersection, IntersectionID, LaneID,
+    LaneType, MapEdits, PathConstraints, PermanentMapEdits,
 };
 use sim::DontDrawAgents;
 use std::collections::BTreeSet;
@@ -42,6 +46,11 @@ pub struct EditMode {
 
     // edits name, number of commands
     changelist_key: (String, usize),
+    // What was already cut off before this round of editing
+    orig_unreachable: BTreeSet<Unreachable>,
+    // What these edits cut off, and a panel to go look at each problem
+    problems: Vec<Unreachable>,
+    problems_panel: Option<Composite>,
 
     unzoomed: Drawable,
     zoomed: Drawable,
@@ -62,6 +71,9 @@ impl EditMode {
             orig_dirty,
             mode,
             changelist_key: (edits.edits_name.clone(), edits.commands.len()),
+            orig_unreachable: connectivity::find_unreachable(&app.primary.map),
+            problems: Vec::new(),
+            problems_panel: None,
             unzoomed: layer.unzoomed,
             zoomed: layer.zoomed,
         }
@@ -122,6 +134,12 @@ impl State for EditMode {
                 let layer = crate::layer::map::Static::edits(ctx, app);
                 self.unzoomed = layer.unzoomed;
                 self.zoomed = layer.zoomed;
+
+                self.problems = connectivity::find_unreachable(&app.primary.map)
+                    .difference(&self.orig_unreachable)
+                    .cloned()
+                    .collect();
+                self.problems_panel = make_problems(ctx, app, &self.problems);
             }
         }
 
@@ -137,7 +155,14 @@ impl State for EditMode {
                 false,
             );
             if let Some(ID::Lane(l)) = app.primary.current_selection {
-                if !can_edit_lane(&self.mode, l, app) {
+                // Sidewalks can't be edited, but bus stops can be added to them
+                if !can_edit_lane(&self.mode, l, app)
+                    && !(self.mode.can_edit_lanes() && app.primary.map.get_l(l).is_sidewalk())
+                {
+                    app.primary.current_selection = None;
+                }
+            } else if let Some(ID::BusStop(_)) = app.primary.current_selection {
+                if !self.mode.can_edit_lanes() {
                     app.primary.current_selection = None;
                 }
             } else if let Some(ID::Intersection(i)) = app.primary.current_selection {
@@ -147,6 +172,10 @@ impl State for EditMode {
                     app.primary.current_selection = None;
                 }
             } else if let Some(ID::Road(_)) = app.primary.current_selection {
+            } else if let Some(ID::Crosswalk(_)) = app.primary.current_selection {
+                if !self.mode.can_edit_lanes() {
+                    app.primary.current_selection = None;
+                }
             } else {
                 app.primary.current_selection = None;
             }
@@ -162,6 +191,16 @@ impl State for EditMode {
                     return Transition::Push(bulk::PaintSelect::new(ctx, app));
                 }
                 "finish editing" => {
+                    // Challenges with an edit budget
+                    if !self.mode.allows(app.primary.map.get_edits()) {
+                        return Transition::Push(msg(
+                            "Too many edits",
+                            vec![
+                                "This challenge doesn't allow these edits.",
+                                "Undo some changes before continuing.",
+                            ],
+                        ));
+                    }
                     return self.quit(ctx, app);
                 }
                 _ => unreachable!(),
@@ -216,6 +255,26 @@ impl State for EditMode {
             },
             None => {}
         }
+        if let Some(ref mut panel) = self.problems_panel {
+            match panel.event(ctx) {
+                Some(Outcome::Clicked(x)) => {
+                    let idx = x["problem #".len()..].parse::<usize>().unwrap();
+                    let id = match self.problems[idx] {
+                        Unreachable::Lane(l, _) => ID::Lane(l),
+                        Unreachable::Building(b, _) => ID::Building(b),
+                        Unreachable::BusStop(bs) => ID::BusStop(bs),
+                    };
+                    return Transition::Push(Warping::new(
+                        ctx,
+                        id.canonical_point(&app.primary).unwrap(),
+                        Some(10.0),
+                        Some(id),
+                        &mut app.primary,
+                    ));
+                }
+                None => {}
+            }
+        }
         // Just kind of constantly scrape this
         app.opts.resume_after_edit = self.top_center.persistent_split_value("finish editing");
 
@@ -268,8 +327,23 @@ impl State for EditMode {
                     apply_map_edits(ctx, app, edits);
                 }
             }
+            if let Some(ID::Crosswalk(id)) = app.primary.current_selection {
+                if app.per_obj.left_click(ctx, "change crosswalk") {
+                    return Transition::Push(change_crosswalk(id));
+                }
+            }
+            if let Some(ID::BusStop(bs)) = app.primary.current_selection {
+                if app.per_obj.left_click(ctx, "edit bus stop") {
+                    return Transition::Push(bus_stops::edit_bus_stop(bs));
+                }
+            }
             if let Some(ID::Lane(l)) = app.primary.current_selection {
-                if app.per_obj.left_click(ctx, "edit lane") {
+                if app.primary.map.get_l(l).is_sidewalk() {
+                    if app.per_obj.left_click(ctx, "add a bus stop here") {
+                        let cursor = ctx.canvas.get_cursor_in_map_space().unwrap();
+                        return Transition::Push(bus_stops::add_bus_stop(app, l, cursor));
+                    }
+                } else if app.per_obj.left_click(ctx, "edit lane") {
                     return Transition::Push(Box::new(LaneEditor::new(
                         ctx,
                         app,
@@ -294,6 +368,9 @@ impl State for EditMode {
         self.tool_panel.draw(g);
         self.top_center.draw(g);
         self.changelist.draw(g);
+        if let Some(ref panel) = self.problems_panel {
+            panel.draw(g);
+        }
         if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
             g.redraw(&self.unzoomed);
         } else {
@@ -451,6 +528,23 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
     let (roads_changed, turns_deleted, turns_added, mut modified_intersections) =
         app.primary.map.apply_edits(edits, &mut timer);
 
+    // Bus stops might've been added, removed, or moved along any changed road
+    let map = &app.primary.map;
+    app.primary
+        .draw_map
+        .bus_stops
+        .retain(|id, _| map.all_bus_stops().contains_key(id));
+    for r in &roads_changed {
+        for l in map.get_r(*r).all_lanes() {
+            for bs in &map.get_l(l).bus_stops {
+                app.primary.draw_map.bus_stops.insert(
+                    *bs,
+                    DrawBusStop::new(map.get_bs(*bs), map, &app.cs, ctx.prerender),
+                );
+            }
+        }
+    }
+
     for r in roads_changed {
         let road = app.primary.map.get_r(r);
         app.primary.draw_map.roads[r.0] =
@@ -492,10 +586,31 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
     }
 
     if app.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
-        app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+        let layer = crate::layer::map::Static::edits(ctx, app);
+        crate::layer::set_layer(ctx, app, Some(Box::new(layer)));
     }
 }
 
+fn change_crosswalk(id: CrosswalkID) -> Box<dyn State> {
+    WizardState::new(Box::new(move |wiz, ctx, app| {
+        let current = app.primary.map.get_r(id.r).crosswalk_type(id.i);
+        let (_, new) = wiz.wrap(ctx).choose("What kind of crosswalk?", || {
+            CrosswalkType::all()
+                .into_iter()
+                .map(|ct| Choice::new(ct.describe(), ct).active(ct != current))
+                .collect()
+        })?;
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeCrosswalk {
+            id,
+            new,
+            old: current,
+        });
+        apply_map_edits(ctx, app, edits);
+        Some(Transition::Pop)
+    }))
+}
+
 pub fn can_edit_lane(mode: &GameplayMode, l: LaneID, app: &App) -> bool {
     mode.can_edit_lanes()
         && !app.primary.map.get_l(l).is_sidewalk()
@@ -592,33 +707,68 @@ pub fn check_parking_blackholes(
     }
 }
 
-pub fn change_speed_limit(ctx: &mut EventCtx, default: Speed) -> Widget {
+// Applies the edits, unless they disconnect any lanes that cars or bikes could reach before. Then
+// the edits are reverted, and the error shows the lanes.
+pub fn check_lane_connectivity(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    edits: MapEdits,
+) -> Option<Box<dyn State>> {
+    let orig_edits = app.primary.map.get_edits().clone();
+    let disconnected_originally = connectivity::disconnected_vehicle_lanes(&app.primary.map);
+
+    apply_map_edits(ctx, app, edits);
+    let newly_disconnected: BTreeSet<LaneID> =
+        connectivity::disconnected_vehicle_lanes(&app.primary.map)
+            .difference(&disconnected_originally)
+            .cloned()
+            .collect();
+    if newly_disconnected.is_empty() {
+        return None;
+    }
+
+    let mut err_state = msg(
+        "Error",
+        vec![format!(
+            "{} lanes would be disconnected from the rest of the map",
+            newly_disconnected.len()
+        )],
+    );
+    let mut c = ColorDiscrete::new(app, vec![("disconnected", Color::RED)]);
+    for l in newly_disconnected {
+        c.add_l(l, "disconnected");
+    }
+    let (unzoomed, zoomed, _) = c.build(ctx);
+    err_state.downcast_mut::<WizardState>().unwrap().also_draw = Some((unzoomed, zoomed));
+    apply_map_edits(ctx, app, orig_edits);
+    Some(err_state)
+}
+
+pub fn change_speed_limit(ctx: &mut EventCtx, default: Speed, units: Units) -> Widget {
+    let mut choices: Vec<Choice<Speed>> = match units {
+        // Don't need anything higher. Though now I kind of miss 3am drives on TX-71...
+        Units::Imperial => (2..=14)
+            .map(|x| Speed::miles_per_hour((x as f64) * 5.0))
+            .collect(),
+        Units::Metric => (1..=11)
+            .map(|x| Speed::km_per_hour((x as f64) * 10.0))
+            .collect(),
+    }
+    .into_iter()
+    .map(|speed| Choice::new(speed.describe_rounded(units), speed))
+    .collect();
+    // The current limit might've come from the other system
+    if !choices.iter().any(|c| c.data == default) {
+        choices.push(Choice::new(default.describe_rounded(units), default));
+        choices.sort_by(|a, b| a.data.partial_cmp(&b.data).unwrap());
+    }
+
     Widget::row(vec![
         "Change speed limit:"
             .draw_text(ctx)
             .centered_vert()
             .margin_right(15),
-        Widget::dropdown(
-            ctx,
-            "speed limit",
-            default,
-            vec![
-                Choice::new("10 mph", Speed::miles_per_hour(10.0)),
-                Choice::new("15 mph", Speed::miles_per_hour(15.0)),
-                Choice::new("20 mph", Speed::miles_per_hour(20.0)),
-                Choice::new("25 mph", Speed::miles_per_hour(25.0)),
-                Choice::new("30 mph", Speed::miles_per_hour(30.0)),
-                Choice::new("35 mph", Speed::miles_per_hour(35.0)),
-                Choice::new("40 mph", Speed::miles_per_hour(40.0)),
-                Choice::new("45 mph", Speed::miles_per_hour(45.0)),
-                Choice::new("50 mph", Speed::miles_per_hour(50.0)),
-                Choice::new("55 mph", Speed::miles_per_hour(55.0)),
-                Choice::new("60 mph", Speed::miles_per_hour(60.0)),
-                Choice::new("65 mph", Speed::miles_per_hour(65.0)),
-                Choice::new("70 mph", Speed::miles_per_hour(70.0)),
-                // Don't need anything higher. Though now I kind of miss 3am drives on TX-71...
-            ],
-        ),
+        Widget::dropdown(ctx, "speed limit", default, choices),
     ])
 }
 
@@ -660,14 +810,47 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         Text::from_multiline(vec![
             Line(format!("{} lane types changed", edits.original_lts.len())),
             Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
+            Line(format!(
+                "{} lane widths changed",
+                edits.changed_lane_widths.len()
+            )),
             Line(format!(
                 "{} speed limits changed",
                 edits.changed_speed_limits.len()
             )),
+            Line(format!(
+                "{} roads with timed speed limits changed",
+                edits.changed_speed_limit_schedules.len()
+            )),
+            Line(format!(
+                "{} roads with through traffic changed",
+                edits.changed_no_through_traffic.len()
+            )),
+            Line(format!(
+                "{} roads with vehicle bans changed",
+                edits.changed_banned_vehicles.len()
+            )),
+            Line(format!("{} modal filters", edits.modal_filters.len())),
             Line(format!(
                 "{} intersections changed",
                 edits.original_intersections.len()
             )),
+            Line(format!(
+                "{} crosswalks changed",
+                edits.changed_crosswalks.len()
+            )),
+            Line(format!(
+                "{} signals ban right turns on red",
+                edits.banned_right_turn_on_red.len()
+            )),
+            Line(format!(
+                "{} intersections ban U-turns",
+                edits.banned_u_turns.len()
+            )),
+            Line(format!(
+                "{} bus stops changed",
+                edits.changed_bus_stops.len()
+            )),
         ])
         .draw(ctx)
         .margin_below(10),
@@ -691,11 +874,49 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         .build(ctx)
 }
 
+// Only shows up when the edits cut something off
+fn make_problems(ctx: &mut EventCtx, app: &App, problems: &Vec<Unreachable>) -> Option<Composite> {
+    if problems.is_empty() {
+        return None;
+    }
+    let mut col = vec![Line(format!(
+        "These edits made {} things unreachable",
+        problems.len()
+    ))
+    .small_heading()
+    .fg(Color::RED)
+    .draw(ctx)
+    .margin_below(10)];
+    for (idx, problem) in problems.iter().enumerate().take(10) {
+        col.push(Btn::plaintext(problem.describe()).build(ctx, format!("problem #{}", idx), None));
+    }
+    if problems.len() > 10 {
+        col.push(format!("{} more...", problems.len() - 10).draw_text(ctx));
+    }
+    Some(
+        Composite::new(Widget::col(col).padding(16).bg(app.cs.panel_bg))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Center)
+            .build(ctx),
+    )
+}
+
 fn cmd_to_id(cmd: &EditCmd) -> ID {
     match cmd {
         EditCmd::ChangeLaneType { id, .. } => ID::Lane(*id),
         EditCmd::ReverseLane { l, .. } => ID::Lane(*l),
+        EditCmd::ChangeLaneWidth { id, .. } => ID::Lane(*id),
         EditCmd::ChangeSpeedLimit { id, .. } => ID::Road(*id),
+        EditCmd::ChangeSpeedLimitSchedule { id, .. } => ID::Road(*id),
+        EditCmd::ChangeNoThroughTraffic { id, .. } => ID::Road(*id),
+        EditCmd::ChangeBannedVehicles { id, .. } => ID::Road(*id),
+        EditCmd::ChangeModalFilter { r, .. } => ID::Road(*r),
         EditCmd::ChangeIntersection { i, .. } => ID::Intersection(*i),
+        EditCmd::ChangeCrosswalk { id, .. } => ID::Crosswalk(*id),
+        EditCmd::ChangeRightTurnOnRed { i, .. } => ID::Intersection(*i),
+        EditCmd::ChangeUTurns { i, .. } => ID::Intersection(*i),
+        // The stop itself might not exist anymore
+        EditCmd::AddBusStop { id, .. }
+        | EditCmd::RemoveBusStop { id, .. }
+        | EditCmd::MoveBusStop { id, .. } => ID::Lane(id.sidewalk),
     }
 }
@@ -0,0 +1,305 @@
+use crate::app::{App, ShowEverything};
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::game::{msg, State, Transition};
+use crate::helpers::ID;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Spinner, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Duration;
+use map_model::{ControlTrafficSignal, IntersectionID, RoadID, SignalCorridor};
+use sim::{DontDrawAgents, TripMode};
+
+// Cars delayed longer than this at a signal probably had to stop
+const STOP_THRESHOLD: Duration = Duration::const_seconds(1.0);
+
+// Groups signals along a route, so they can share a cycle length and be offset to form a green
+// wave.
+pub struct SignalCorridorEditor {
+    composite: Composite,
+    // Into the map's signal_corridors
+    idx: usize,
+    draw: Drawable,
+}
+
+impl SignalCorridorEditor {
+    // Opens the corridor containing this signal, or starts a new one with just this signal.
+    pub fn new(ctx: &mut EventCtx, app: &mut App, i: IntersectionID) -> Box<dyn State> {
+        let mut corridors = app.primary.map.get_edits().signal_corridors.clone();
+        let idx = if let Some(idx) = corridors.iter().position(|c| c.members.contains(&i)) {
+            idx
+        } else {
+            corridors.push(SignalCorridor {
+                name: format!("corridor {}", corridors.len() + 1),
+                members: vec![i],
+            });
+            app.primary.map.set_signal_corridors(corridors);
+            app.primary.map.get_edits().signal_corridors.len() - 1
+        };
+        Box::new(SignalCorridorEditor::make(ctx, app, idx))
+    }
+
+    fn make(ctx: &mut EventCtx, app: &mut App, idx: usize) -> SignalCorridorEditor {
+        app.primary.current_selection = None;
+        let map = &app.primary.map;
+        let corridor = &map.get_edits().signal_corridors[idx];
+        let roads = corridor.roads(map);
+
+        let mut batch = GeomBatch::new();
+        for i in &corridor.members {
+            batch.push(Color::CYAN.alpha(0.5), map.get_i(*i).polygon.clone());
+        }
+        if let Ok(ref roads) = roads {
+            for r in roads {
+                batch.push(
+                    Color::CYAN.alpha(0.3),
+                    map.get_r(*r).get_thick_polygon(map).unwrap(),
+                );
+            }
+        }
+
+        let mut col = vec![
+            Line(format!("Signal corridor: {}", corridor.name))
+                .small_heading()
+                .draw(ctx),
+            "Click a traffic signal to add or remove it"
+                .draw_text(ctx)
+                .margin_below(10),
+        ];
+
+        let mut txt = Text::from(Line(format!("{} signals", corridor.members.len())));
+        let cycle_length = corridor.cycle_length(map);
+        match cycle_length {
+            Some(dt) => txt.add(Line(format!("Cycle length: {}", dt))),
+            None => txt.add(Line("The signals have different cycle lengths").fg(Color::RED)),
+        }
+        if let Err(ref err) = roads {
+            txt.add(Line(err).fg(Color::RED));
+        }
+        col.push(txt.draw(ctx).margin_below(10));
+
+        let current = cycle_length
+            .unwrap_or_else(|| map.get_traffic_signal(corridor.members[0]).cycle_length());
+        col.push(
+            Widget::row(vec![
+                "Cycle length (seconds):"
+                    .draw_text(ctx)
+                    .centered_vert()
+                    .margin_right(10),
+                Spinner::new(ctx, (10, 300), current.inner_seconds() as usize)
+                    .named("cycle length")
+                    .margin_right(10),
+                Btn::text_fg("apply").build(ctx, "apply cycle length", None),
+            ])
+            .margin_below(5),
+        );
+        col.push(
+            if cycle_length.is_some() && corridor.members.len() > 1 && roads.is_ok() {
+                Btn::text_fg("set offsets for a green wave").build_def(ctx, hotkey(Key::G))
+            } else {
+                Btn::text_fg("set offsets for a green wave").inactive(ctx)
+            }
+            .margin_below(10),
+        );
+
+        for i in &corridor.members {
+            col.push(format!("{}: offset {}", i, map.get_traffic_signal(*i).offset).draw_text(ctx));
+        }
+
+        if let Ok(ref roads) = roads {
+            if !roads.is_empty() {
+                col.push(metrics(ctx, app, corridor, roads).margin_above(10));
+            }
+        }
+
+        col.push(
+            Widget::row(vec![
+                Btn::text_fg("delete corridor")
+                    .build_def(ctx, None)
+                    .margin_right(10),
+                Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
+            ])
+            .margin_above(10),
+        );
+
+        SignalCorridorEditor {
+            composite: Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(10))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+            idx,
+            draw: ctx.upload(batch),
+        }
+    }
+
+    fn corridor<'a>(&self, app: &'a App) -> &'a SignalCorridor {
+        &app.primary.map.get_edits().signal_corridors[self.idx]
+    }
+
+    fn change_signals(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        signals: Vec<ControlTrafficSignal>,
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        for signal in signals {
+            if let Err(err) = edits.change_traffic_signal(&app.primary.map, signal) {
+                return Transition::Push(msg("Error", vec![err]));
+            }
+        }
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(Box::new(SignalCorridorEditor::make(ctx, app, self.idx)))
+    }
+}
+
+impl State for SignalCorridorEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            app.primary.current_selection = app.calculate_current_selection(
+                ctx,
+                &DontDrawAgents {},
+                &ShowEverything::new(),
+                false,
+                true,
+                false,
+            );
+            if let Some(ID::Intersection(i)) = app.primary.current_selection {
+                if app.primary.map.maybe_get_traffic_signal(i).is_none() {
+                    app.primary.current_selection = None;
+                }
+            } else {
+                app.primary.current_selection = None;
+            }
+        }
+
+        if let Some(ID::Intersection(i)) = app.primary.current_selection {
+            let mut corridors = app.primary.map.get_edits().signal_corridors.clone();
+            let corridor = &mut corridors[self.idx];
+            let label = if corridor.members.contains(&i) {
+                "remove from corridor"
+            } else {
+                "add to corridor"
+            };
+            if app.per_obj.left_click(ctx, label) {
+                if corridor.members.contains(&i) {
+                    corridor.members.retain(|x| *x != i);
+                } else {
+                    // Signals are usually added in order, but let the corridor grow at either end
+                    let mut prepend = corridor.clone();
+                    prepend.members.insert(0, i);
+                    let mut append = corridor.clone();
+                    append.members.push(i);
+                    if prepend.roads(&app.primary.map).is_ok()
+                        && append.roads(&app.primary.map).is_err()
+                    {
+                        *corridor = prepend;
+                    } else {
+                        *corridor = append;
+                    }
+                }
+
+                if corridor.members.is_empty() {
+                    corridors.remove(self.idx);
+                    app.primary.map.set_signal_corridors(corridors);
+                    return Transition::Pop;
+                }
+                app.primary.map.set_signal_corridors(corridors);
+                return Transition::Replace(Box::new(SignalCorridorEditor::make(
+                    ctx, app, self.idx,
+                )));
+            }
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "Finish" => {
+                    return Transition::Pop;
+                }
+                "delete corridor" => {
+                    let mut corridors = app.primary.map.get_edits().signal_corridors.clone();
+                    corridors.remove(self.idx);
+                    app.primary.map.set_signal_corridors(corridors);
+                    return Transition::Pop;
+                }
+                "apply cycle length" => {
+                    let cycle_length =
+                        Duration::seconds(self.composite.spinner("cycle length") as f64);
+                    let signals = self
+                        .corridor(app)
+                        .with_cycle_length(&app.primary.map, cycle_length);
+                    return self.change_signals(ctx, app, signals);
+                }
+                "set offsets for a green wave" => {
+                    match self.corridor(app).green_wave(&app.primary.map) {
+                        Ok(signals) => {
+                            return self.change_signals(ctx, app, signals);
+                        }
+                        Err(err) => {
+                            return Transition::Push(msg("Error", vec![err]));
+                        }
+                    }
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+// Estimates what happens to a car driving the whole corridor: the time to drive each road at the
+// speed limit, plus the average delay at each later signal for cars arriving along the corridor.
+// Delays only come from the current simulation.
+fn metrics(ctx: &EventCtx, app: &App, corridor: &SignalCorridor, roads: &Vec<RoadID>) -> Widget {
+    let map = &app.primary.map;
+    let delays = &app.primary.sim.get_analytics().intersection_delays;
+
+    let mut travel_time = Duration::ZERO;
+    let mut stops = 0.0;
+    let mut samples = 0;
+    for (idx, r) in roads.iter().enumerate() {
+        let road = map.get_r(*r);
+        travel_time += road.center_pts.length() / road.speed_limit;
+
+        let mut total = Duration::ZERO;
+        let mut num = 0;
+        let mut num_stopped = 0;
+        if let Some(list) = delays.get(&corridor.members[idx + 1]) {
+            for (_, dt, mode, from) in list {
+                if *mode == TripMode::Drive && from == r {
+                    total += *dt;
+                    num += 1;
+                    if *dt > STOP_THRESHOLD {
+                        num_stopped += 1;
+                    }
+                }
+            }
+        }
+        if num > 0 {
+            travel_time += total / (num as f64);
+            stops += (num_stopped as f64) / (num as f64);
+            samples += num;
+        }
+    }
+
+    Text::from_multiline(vec![
+        Line("Driving the whole corridor"),
+        Line(format!("Estimated travel time: {}", travel_time)),
+        Line(format!("Estimated stops: {:.1}", stops)),
+        Line(format!(
+            "Based on {} cars measured so far",
+            abstutil::prettyprint_usize(samples)
+        ))
+        .secondary(),
+    ])
+    .draw(ctx)
+}
@@ -11,7 +11,8 @@ use ezgui::{
 };
 use geom::Polygon;
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, RoadID,
+    ApproachControl, ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection,
+    IntersectionID, RoadID,
 };
 use std::collections::HashMap;
 
@@ -21,7 +22,7 @@ pub struct StopSignEditor {
     composite: Composite,
     id: IntersectionID,
     mode: GameplayMode,
-    // (octagon, pole)
+    // (sign, pole)
     geom: HashMap<RoadID, (Polygon, Polygon)>,
     selected_sign: Option<RoadID>,
 }
@@ -41,9 +42,8 @@ impl StopSignEditor {
             .roads
             .iter()
             .map(|(r, ss)| {
-                let (octagon, pole) =
-                    DrawIntersection::stop_sign_geom(ss, &app.primary.map).unwrap();
-                (*r, (octagon, pole))
+                let (sign, pole) = DrawIntersection::stop_sign_geom(ss, &app.primary.map).unwrap();
+                (*r, (sign, pole))
             })
             .collect();
 
@@ -57,6 +57,28 @@ impl StopSignEditor {
                 } else {
                     Btn::text_fg("reset to default").inactive(ctx)
                 },
+                if app
+                    .primary
+                    .map
+                    .get_stop_sign(id)
+                    .roads
+                    .values()
+                    .any(|ss| ss.control == ApproachControl::Stop)
+                {
+                    Btn::text_fg("convert to yield signs").build_def(ctx, hotkey(Key::Y))
+                } else {
+                    Btn::text_fg("convert to yield signs").inactive(ctx)
+                },
+                if mode.can_edit_lanes() {
+                    Btn::text_fg(if app.primary.map.get_i(id).u_turns {
+                        "ban U-turns"
+                    } else {
+                        "allow U-turns"
+                    })
+                    .build(ctx, "toggle U-turns", None)
+                } else {
+                    Widget::nothing()
+                },
                 Btn::text_fg("close intersection for construction").build_def(ctx, None),
                 Btn::text_fg("convert to traffic signal").build_def(ctx, None),
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
@@ -84,8 +106,8 @@ impl State for StopSignEditor {
         if ctx.redo_mouseover() {
             self.selected_sign = None;
             if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
-                for (r, (octagon, _)) in &self.geom {
-                    if octagon.contains_pt(pt) {
+                for (r, (sign, _)) in &self.geom {
+                    if sign.contains_pt(pt) {
                         self.selected_sign = Some(*r);
                         break;
                     }
@@ -95,10 +117,10 @@ impl State for StopSignEditor {
 
         if let Some(r) = self.selected_sign {
             let mut sign = app.primary.map.get_stop_sign(self.id).clone();
-            let label = if sign.roads[&r].must_stop {
-                "remove stop sign"
-            } else {
-                "add stop sign"
+            let label = match sign.roads[&r].control {
+                ApproachControl::Priority => "add yield sign",
+                ApproachControl::Yield => "change to stop sign",
+                ApproachControl::Stop => "remove stop sign",
             };
             if app.per_obj.left_click(ctx, label) {
                 sign.flip_sign(r);
@@ -142,6 +164,37 @@ impl State for StopSignEditor {
                         self.mode.clone(),
                     )));
                 }
+                "convert to yield signs" => {
+                    let mut sign = app.primary.map.get_stop_sign(self.id).clone();
+                    sign.convert_to_yields();
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeIntersection {
+                        i: self.id,
+                        old: app.primary.map.get_i_edit(self.id),
+                        new: EditIntersection::StopSign(sign),
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(StopSignEditor::new(
+                        ctx,
+                        app,
+                        self.id,
+                        self.mode.clone(),
+                    )));
+                }
+                "toggle U-turns" => {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeUTurns {
+                        i: self.id,
+                        allowed: !app.primary.map.get_i(self.id).u_turns,
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(StopSignEditor::new(
+                        ctx,
+                        app,
+                        self.id,
+                        self.mode.clone(),
+                    )));
+                }
                 "close intersection for construction" => {
                     return close_intersection(ctx, app, self.id, true);
                 }
@@ -177,15 +230,16 @@ impl State for StopSignEditor {
 
         let mut batch = GeomBatch::new();
 
-        for (r, (octagon, pole)) in &self.geom {
-            // The intersection will already draw enabled stop signs
+        for (r, (shape, pole)) in &self.geom {
+            // The intersection will already draw enabled stop and yield signs
+            let enabled = sign.roads[r].control != ApproachControl::Priority;
             if Some(*r) == self.selected_sign {
-                batch.push(app.cs.perma_selected_object, octagon.clone());
-                if !sign.roads[r].must_stop {
+                batch.push(app.cs.perma_selected_object, shape.clone());
+                if !enabled {
                     batch.push(app.cs.stop_sign_pole.alpha(0.6), pole.clone());
                 }
-            } else if !sign.roads[r].must_stop {
-                batch.push(app.cs.stop_sign.alpha(0.6), octagon.clone());
+            } else if !enabled {
+                batch.push(app.cs.stop_sign.alpha(0.6), shape.clone());
                 batch.push(app.cs.stop_sign_pole.alpha(0.6), pole.clone());
             }
         }
@@ -196,7 +250,7 @@ impl State for StopSignEditor {
         if let Some(r) = self.selected_sign {
             let mut osd = Text::new();
             osd.add_appended(vec![
-                Line("Stop sign for "),
+                Line(format!("{} for ", sign.roads[&r].control.describe())),
                 Line(app.primary.map.get_r(r).get_name()).fg(app.cs.bottom_bar_name),
             ]);
             CommonState::draw_custom_osd(g, app, osd);
@@ -1,6 +1,6 @@
 use crate::app::{App, ShowEverything};
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, close_intersection, StopSignEditor};
+use crate::edit::{apply_map_edits, close_intersection, SignalCorridorEditor, StopSignEditor};
 use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
 use crate::render::{
     draw_signal_phase, make_signal_diagram, DrawOptions, DrawTurnGroup, BIG_ARROW_THICKNESS,
@@ -244,6 +244,27 @@ impl State for TrafficSignalEditor {
 
                     return Transition::Push(make_previewer(self.i, self.current_phase));
                 }
+                "Animate" => {
+                    if app
+                        .primary
+                        .map
+                        .get_traffic_signal(self.i)
+                        .phases
+                        .iter()
+                        .all(|p| p.duration == Duration::ZERO)
+                    {
+                        return Transition::Push(msg(
+                            "Error",
+                            vec!["Every phase lasts 0s, so there's nothing to animate"],
+                        ));
+                    }
+                    return Transition::Push(Box::new(AnimateSignal::new(
+                        ctx,
+                        app,
+                        self.i,
+                        self.current_phase,
+                    )));
+                }
                 "undo" => {
                     self.redo_stack.push(orig_signal.clone());
                     change_traffic_signal(self.command_stack.pop().unwrap(), ctx, app);
@@ -416,7 +437,10 @@ pub fn make_top_panel(ctx: &mut EventCtx, app: &App, can_undo: bool, can_redo: b
         Btn::text_fg("Finish")
             .build_def(ctx, hotkey(Key::Escape))
             .margin_right(5),
-        Btn::text_fg("Preview").build_def(ctx, lctrl(Key::P)),
+        Btn::text_fg("Preview")
+            .build_def(ctx, lctrl(Key::P))
+            .margin_right(5),
+        Btn::text_fg("Animate").build_def(ctx, lctrl(Key::A)),
         (if can_undo {
             Btn::svg_def("../data/system/assets/tools/undo.svg").build(ctx, "undo", lctrl(Key::Z))
         } else {
@@ -504,18 +528,33 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
         .iter()
         .any(|t| t.between_sidewalks());
     let current_offset = app.primary.map.get_traffic_signal(i).offset;
+    let right_turn_on_red = app.primary.map.get_i(i).right_turn_on_red;
+    let u_turns = app.primary.map.get_i(i).u_turns;
 
     WizardState::new(Box::new(move |wiz, ctx, app| {
         let use_template = "use template";
         let all_walk = "add an all-walk phase at the end";
+        let lpi = "give pedestrians a head start";
         let stop_sign = "convert to stop signs";
         let close = "close intersection for construction";
         let offset = "edit signal offset";
+        let corridor = "coordinate with other signals in a corridor";
+        let rtor = if right_turn_on_red {
+            "ban right turns on red"
+        } else {
+            "allow right turns on red"
+        };
+        let u_turn = if u_turns {
+            "ban U-turns"
+        } else {
+            "allow U-turns"
+        };
         let reset = "reset to default";
 
         let mut choices = vec![use_template];
         if has_sidewalks {
             choices.push(all_walk);
+            choices.push(lpi);
         }
         // TODO Conflating stop signs and construction here
         if mode.can_edit_stop_signs() {
@@ -523,6 +562,11 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
             choices.push(close);
         }
         choices.push(offset);
+        choices.push(rtor);
+        if mode.can_edit_lanes() {
+            choices.push(u_turn);
+        }
+        choices.push(corridor);
         choices.push(reset);
 
         let mut wizard = wiz.wrap(ctx);
@@ -561,6 +605,27 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
                     }
                 })))
             }
+            x if x == lpi => {
+                let head_start = wizard.input_something(
+                    "How many seconds should pedestrians get before vehicles?",
+                    Some("5".to_string()),
+                    Box::new(|line| line.parse::<usize>().ok().filter(|x| *x > 0)),
+                )?;
+                Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
+                    let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                    let orig_signal = app.primary.map.get_traffic_signal(editor.i);
+                    let mut new_signal = orig_signal.clone();
+                    if new_signal
+                        .add_leading_pedestrian_intervals(Duration::seconds(head_start as f64))
+                    {
+                        editor.command_stack.push(orig_signal.clone());
+                        editor.redo_stack.clear();
+                        editor.top_panel = make_top_panel(ctx, app, true, false);
+                        change_traffic_signal(new_signal, ctx, app);
+                        editor.change_phase(0, ctx, app);
+                    }
+                })))
+            }
             x if x == stop_sign => {
                 let mut edits = app.primary.map.get_edits().clone();
                 edits.commands.push(EditCmd::ChangeIntersection {
@@ -593,6 +658,30 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
                     editor.change_phase(editor.current_phase, ctx, app);
                 })))
             }
+            x if x == rtor => {
+                let mut edits = app.primary.map.get_edits().clone();
+                edits.commands.push(EditCmd::ChangeRightTurnOnRed {
+                    i,
+                    allowed: !right_turn_on_red,
+                });
+                apply_map_edits(ctx, app, edits);
+                Some(Transition::Pop)
+            }
+            // The turn groups change, so the signal gets reset
+            x if x == u_turn => {
+                let mut edits = app.primary.map.get_edits().clone();
+                edits.commands.push(EditCmd::ChangeUTurns {
+                    i,
+                    allowed: !u_turns,
+                });
+                apply_map_edits(ctx, app, edits);
+                Some(Transition::PopThenReplace(Box::new(
+                    TrafficSignalEditor::new(ctx, app, i, mode.clone()),
+                )))
+            }
+            x if x == corridor => Some(Transition::PopThenReplace(SignalCorridorEditor::new(
+                ctx, app, i,
+            ))),
             x if x == reset => {
                 Some(Transition::PopWithData(Box::new(move |state, ctx, app| {
                     let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
@@ -798,3 +887,171 @@ impl State for PreviewTrafficSignal {
         self.time_panel.draw(g);
     }
 }
+
+// Cycles through the phases on a separate clock from the sim, so timing can be checked without
+// spawning any traffic.
+struct AnimateSignal {
+    i: IntersectionID,
+    composite: Composite,
+    current_phase: usize,
+    time_left: Duration,
+    multiplier: f64,
+    paused: bool,
+}
+
+impl AnimateSignal {
+    fn new(ctx: &mut EventCtx, app: &App, i: IntersectionID, phase: usize) -> AnimateSignal {
+        let time_left = app.primary.map.get_traffic_signal(i).phases[phase].duration;
+        let multiplier = 10.0;
+        AnimateSignal {
+            i,
+            composite: AnimateSignal::make_panel(ctx, app, i, phase, time_left, multiplier, false),
+            current_phase: phase,
+            time_left,
+            multiplier,
+            paused: false,
+        }
+    }
+
+    fn make_panel(
+        ctx: &mut EventCtx,
+        app: &App,
+        i: IntersectionID,
+        current_phase: usize,
+        time_left: Duration,
+        multiplier: f64,
+        paused: bool,
+    ) -> Composite {
+        let signal = app.primary.map.get_traffic_signal(i);
+        let cycle_length = signal
+            .phases
+            .iter()
+            .fold(Duration::ZERO, |sum, p| sum + p.duration);
+        Composite::new(
+            Widget::col(vec![
+                Line("Animating traffic signal")
+                    .small_heading()
+                    .draw(ctx)
+                    .margin_below(10),
+                Text::from_multiline(vec![
+                    Line(format!(
+                        "Phase {} of {}",
+                        current_phase + 1,
+                        signal.phases.len()
+                    )),
+                    Line(format!(
+                        "{} left of {}",
+                        time_left, signal.phases[current_phase].duration
+                    )),
+                    Line(format!("Whole cycle lasts {}", cycle_length)),
+                    Line(format!("Playing at {}x", multiplier)),
+                ])
+                .draw(ctx)
+                .margin_below(10),
+                Widget::row(vec![
+                    Btn::text_fg("slower")
+                        .build_def(ctx, hotkey(Key::LeftArrow))
+                        .margin_right(5),
+                    (if paused {
+                        Btn::text_fg("play").build_def(ctx, hotkey(Key::Space))
+                    } else {
+                        Btn::text_fg("pause").build_def(ctx, hotkey(Key::Space))
+                    })
+                    .margin_right(5),
+                    Btn::text_fg("faster").build_def(ctx, hotkey(Key::RightArrow)),
+                ])
+                .margin_below(10),
+                Btn::text_fg("back to editing").build_def(ctx, hotkey(Key::Escape)),
+            ])
+            .bg(app.cs.panel_bg)
+            .padding(16),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx)
+    }
+
+    fn rebuild_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.composite = AnimateSignal::make_panel(
+            ctx,
+            app,
+            self.i,
+            self.current_phase,
+            self.time_left,
+            self.multiplier,
+            self.paused,
+        );
+    }
+}
+
+impl State for AnimateSignal {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "back to editing" => {
+                    return Transition::Pop;
+                }
+                "slower" => {
+                    self.multiplier = (self.multiplier / 2.0).max(1.0);
+                    self.rebuild_panel(ctx, app);
+                }
+                "faster" => {
+                    self.multiplier = (self.multiplier * 2.0).min(160.0);
+                    self.rebuild_panel(ctx, app);
+                }
+                "play" | "pause" => {
+                    self.paused = !self.paused;
+                    self.rebuild_panel(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        if self.paused {
+            return Transition::Keep;
+        }
+
+        if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+            ctx.input.use_update_event();
+            let signal = app.primary.map.get_traffic_signal(self.i);
+            let mut dt = self.multiplier * real_dt;
+            // At high speeds, a single update might skip over several short phases. Phases lasting
+            // 0s are skipped entirely. This ends, because at least one phase lasts longer; see
+            // "Animate".
+            while dt >= self.time_left {
+                dt -= self.time_left;
+                self.current_phase = (self.current_phase + 1) % signal.phases.len();
+                self.time_left = signal.phases[self.current_phase].duration;
+            }
+            self.time_left -= dt;
+            self.rebuild_panel(ctx, app);
+        }
+        Transition::KeepWithMode(EventLoopMode::Animation)
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        let mut opts = DrawOptions::new();
+        opts.suppress_traffic_signal_details.push(self.i);
+        app.draw(g, opts, &app.primary.sim, &ShowEverything::new());
+
+        let mut batch = GeomBatch::new();
+        draw_signal_phase(
+            g.prerender,
+            &app.primary.map.get_traffic_signal(self.i).phases[self.current_phase],
+            self.i,
+            Some(self.time_left),
+            &mut batch,
+            app,
+            app.opts.traffic_signal_style.clone(),
+        );
+        batch.draw(g);
+
+        self.composite.draw(g);
+    }
+}
@@ -1,10 +1,17 @@
 use crate::app::{App, Flags, ShowEverything};
+use crate::crash;
 use crate::options::Options;
 use crate::pregame::TitleScreen;
 use crate::render::DrawOptions;
-use crate::sandbox::{GameplayMode, SandboxMode};
-use ezgui::{Canvas, Drawable, EventCtx, EventLoopMode, GfxCtx, Wizard, GUI};
+use crate::sandbox::{GameplayMode, LastSession, SandboxMode};
+use ezgui::{Canvas, CrashInfo, Drawable, EventCtx, EventLoopMode, GfxCtx, Wizard, GUI};
 use geom::Polygon;
+use instant::Instant;
+use log::{error, info, warn};
+use std::panic;
+
+// How often to write the recovery slot, in seconds of real time
+const AUTOSAVE_EVERY: f64 = 120.0;
 
 // This is the top-level of the GUI logic. This module should just manage interactions between the
 // top-level game states.
@@ -12,6 +19,7 @@ pub struct Game {
     // A stack of states
     states: Vec<Box<dyn State>>,
     app: App,
+    last_autosave: Instant,
 }
 
 impl Game {
@@ -27,6 +35,10 @@ impl Game {
             && !flags.sim_flags.load.contains("data/system/scenarios")
             && maybe_mode.is_none();
         let mut app = App::new(flags, opts, ctx, title);
+        app.session.recovery = LastSession::load_recovery();
+        if app.session.recovery.is_some() {
+            info!("The last run didn't exit cleanly; its work can be recovered from the main menu");
+        }
 
         // Handle savestates
         let savestate = if app
@@ -70,13 +82,20 @@ impl Game {
             // PlayScenario without clobbering.
             app.primary.sim = ss;
         }
-        Game { states, app }
+        Game {
+            states,
+            app,
+            last_autosave: Instant::now(),
+        }
     }
 }
 
 impl GUI for Game {
     fn event(&mut self, ctx: &mut EventCtx) -> EventLoopMode {
         self.app.per_obj.reset();
+        if abstutil::elapsed_seconds(self.last_autosave) >= AUTOSAVE_EVERY {
+            self.autosave();
+        }
 
         let transition = self.states.last_mut().unwrap().event(ctx, &mut self.app);
         // If we fall through, there's a new state that we need to wakeup.
@@ -188,17 +207,54 @@ impl GUI for Game {
         state.draw(g, &self.app);
     }
 
-    fn dump_before_abort(&self, canvas: &Canvas) {
-        println!(
-            "********************************************************************************"
-        );
-        println!("UI broke! Primary sim:");
+    fn dump_before_abort(&self, canvas: &Canvas, crash: &CrashInfo) {
+        error!("UI broke! Primary sim:");
         self.app.primary.sim.dump_before_abort();
         canvas.save_camera_state(self.app.primary.map.get_name());
+
+        let mode = self
+            .states
+            .iter()
+            .rev()
+            .find_map(|s| s.downcast_ref::<SandboxMode>())
+            .map(|sandbox| &sandbox.gameplay_mode);
+        // Don't let a second panic hide the first
+        if panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            crash::write_bundle(&self.app, mode, crash);
+        }))
+        .is_err()
+        {
+            warn!("Couldn't write a crash report");
+        }
     }
 
     fn before_quit(&self, canvas: &Canvas) {
         canvas.save_camera_state(self.app.primary.map.get_name());
+        if let Some(sandbox) = self
+            .states
+            .iter()
+            .rev()
+            .find_map(|s| s.downcast_ref::<SandboxMode>())
+        {
+            LastSession::record(&self.app, &sandbox.gameplay_mode);
+        }
+        // A clean exit; nothing to recover next time
+        LastSession::clear_recovery();
+    }
+}
+
+impl Game {
+    fn autosave(&mut self) {
+        self.last_autosave = Instant::now();
+        // Edit mode sits on top of the sandbox, so this covers edits in progress too
+        if let Some(sandbox) = self
+            .states
+            .iter()
+            .rev()
+            .find_map(|s| s.downcast_ref::<SandboxMode>())
+        {
+            LastSession::autosave(&mut self.app, &sandbox.gameplay_mode);
+        }
     }
 }
 
@@ -1,7 +1,9 @@
 use crate::app::{App, PerMap};
 use ezgui::{hotkey, Btn, Color, EventCtx, Key, Line, Text, TextSpan, Widget};
 use geom::{Duration, Pt2D};
-use map_model::{AreaID, BuildingID, BusStopID, IntersectionID, LaneID, ParkingLotID, RoadID};
+use map_model::{
+    AreaID, BuildingID, BusStopID, CrosswalkID, IntersectionID, LaneID, ParkingLotID, RoadID,
+};
 use sim::{AgentID, CarID, PedestrianID, TripMode, TripPhaseType};
 use std::collections::BTreeSet;
 
@@ -18,6 +20,7 @@ pub enum ID {
     PedCrowd(Vec<PedestrianID>),
     BusStop(BusStopID),
     Area(AreaID),
+    Crosswalk(CrosswalkID),
 }
 
 impl abstutil::Cloneable for ID {}
@@ -61,6 +64,13 @@ impl ID {
                 .maybe_get_bs(id)
                 .map(|bs| bs.sidewalk_pos.pt(&primary.map)),
             ID::Area(id) => primary.map.maybe_get_a(id).map(|a| a.polygon.center()),
+            // The drawn crosswalks are already cached
+            ID::Crosswalk(id) => primary
+                .draw_map
+                .intersections
+                .get(id.i.0)
+                .and_then(|i| i.crosswalks.iter().find(|c| c.id == id))
+                .map(|c| c.center()),
         }
     }
 }
@@ -138,8 +148,8 @@ pub fn color_for_trip_phase(app: &App, tpt: TripPhaseType) -> Color {
         TripPhaseType::WaitingForBus(_, _) => app.cs.bus_layer,
         TripPhaseType::RidingBus(_, _, _) => app.cs.bus_lane,
         TripPhaseType::Aborted | TripPhaseType::Finished => unreachable!(),
-        TripPhaseType::DelayedStart => Color::YELLOW,
-        TripPhaseType::Remote => Color::PINK,
+        TripPhaseType::DelayedStart => app.cs.delayed_start,
+        TripPhaseType::Remote => app.cs.remote_trip,
     }
 }
 
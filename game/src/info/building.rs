@@ -1,9 +1,10 @@
 use crate::app::App;
 use crate::info::{header_btns, make_table, make_tabs, Details, Tab};
 use crate::render::DrawPedestrian;
+use abstutil::prettyprint_usize;
 use ezgui::{Btn, Color, EventCtx, Line, Text, TextExt, Widget};
-use geom::{Angle, Circle, Distance, Speed, Time};
-use map_model::{BuildingID, LaneID, Traversable, SIDEWALK_THICKNESS};
+use geom::{Angle, Circle, Distance, Duration, Speed, Time};
+use map_model::{BuildingID, LaneID, Traversable, WalkingProfile, SIDEWALK_THICKNESS};
 use sim::{DrawPedestrianInput, PedestrianID, PersonID, TripMode, TripResult};
 use std::collections::BTreeMap;
 
@@ -17,6 +18,7 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID
     if let Some(ref name) = b.name {
         kv.push(("Name", name.clone()));
     }
+    kv.push(("Land use", b.land_use.describe().to_string()));
     if app.opts.dev {
         kv.push(("OSM ID", format!("{}", b.osm_way_id)));
     }
@@ -60,7 +62,7 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID
         .primary
         .sim
         .walking_path_to_nearest_parking_spot(&app.primary.map, id)
-        .and_then(|(path, start_dist)| path.trace(&app.primary.map, start_dist, None))
+        .and_then(|(path, start_dist)| path.trace(&app.primary.map, start_dist, None).ok())
     {
         let color = app.cs.parking_trip;
         // TODO But this color doesn't show up well against the info panel...
@@ -170,7 +172,11 @@ fn header(
         ctx,
         &mut details.hyperlinks,
         tab,
-        vec![("Info", Tab::BldgInfo(id)), ("People", Tab::BldgPeople(id))],
+        vec![
+            ("Info", Tab::BldgInfo(id)),
+            ("People", Tab::BldgPeople(id)),
+            ("Walkshed", Tab::BldgWalkshed(id, WalkingProfile::Default)),
+        ],
     ));
 
     draw_occupants(details, app, id, None);
@@ -179,6 +185,71 @@ fn header(
     rows
 }
 
+// Everywhere reachable on foot (or by wheelchair) from here within 15 minutes, to audit how
+// accessible a proposal is.
+pub fn walkshed(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    id: BuildingID,
+    profile: WalkingProfile,
+) -> Vec<Widget> {
+    let mut rows = header(ctx, app, details, id, Tab::BldgWalkshed(id, profile));
+    let map = &app.primary.map;
+    let limit = Duration::minutes(15);
+    let start = map.get_b(id).front_path.sidewalk;
+
+    let times = map.walkshed(start, profile, limit);
+    for (b, dt) in &times {
+        let color = app.cs.good_to_bad_red.eval(*dt / limit);
+        details.unzoomed.push(color, map.get_b(*b).polygon.clone());
+        details
+            .zoomed
+            .push(color.alpha(0.5), map.get_b(*b).polygon.clone());
+    }
+
+    let (verb, other, other_verb) = match profile {
+        WalkingProfile::Default => ("walking", WalkingProfile::Wheelchair, "by wheelchair"),
+        WalkingProfile::Wheelchair => ("by wheelchair", WalkingProfile::Default, "walking"),
+    };
+    let other_count = map.walkshed(start, other, limit).len();
+    rows.push(
+        Text::from_multiline(vec![
+            Line(format!(
+                "{} buildings within {} {}",
+                prettyprint_usize(times.len()),
+                limit,
+                verb
+            )),
+            Line(format!(
+                "{} buildings {}",
+                prettyprint_usize(other_count),
+                other_verb
+            ))
+            .secondary(),
+        ])
+        .draw(ctx),
+    );
+    if profile == WalkingProfile::Wheelchair {
+        rows.push(
+            Text::from_multiline(vec![
+                Line("Avoids stairs, steep sidewalks, and crossings without curb ramps"),
+                Line("Simulated trips don't account for this yet").secondary(),
+            ])
+            .draw(ctx)
+            .margin_below(5),
+        );
+    }
+
+    let label = format!("show {}", other_verb);
+    details
+        .hyperlinks
+        .insert(label.clone(), Tab::BldgWalkshed(id, other));
+    rows.push(Btn::text_bg1(label).build_def(ctx, None));
+
+    rows
+}
+
 pub fn draw_occupants(details: &mut Details, app: &App, id: BuildingID, focus: Option<PersonID>) {
     // TODO Lots of fun ideas here. Have a deterministic simulation based on building ID and time
     // to have people "realistically" move around. Draw little floor plans.
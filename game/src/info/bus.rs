@@ -1,6 +1,7 @@
 use crate::app::App;
 use crate::helpers::ID;
 use crate::info::{header_btns, make_table, make_tabs, Details, Tab};
+use abstutil::prettyprint_usize;
 use ezgui::{
     Btn, Color, EventCtx, GeomBatch, Line, LinePlot, PlotOptions, RewriteColor, Series, Text,
     TextExt, Widget,
@@ -52,6 +53,21 @@ pub fn stop(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusStopID)
         {
             txt.add(Line(format!("  Waiting: {}", hgram.describe())).secondary());
         }
+        if let Some((_, left_behind)) = sim
+            .get_analytics()
+            .bus_crowding(sim.time(), r.id)
+            .remove(&id)
+        {
+            if left_behind > 0 {
+                txt.add(
+                    Line(format!(
+                        "  {} riders left behind by full buses",
+                        prettyprint_usize(left_behind)
+                    ))
+                    .secondary(),
+                );
+            }
+        }
         rows.push(txt.draw(ctx));
     }
 
@@ -68,6 +84,7 @@ pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarI
 
     let route = app.primary.sim.bus_route_id(id).unwrap();
     rows.push(passenger_delay(ctx, app, details, route));
+    rows.push(crowding(ctx, app, route));
 
     rows
 }
@@ -149,6 +166,39 @@ fn delays_over_time(ctx: &mut EventCtx, app: &App, id: BusRouteID) -> Widget {
     ])
 }
 
+fn crowding(ctx: &mut EventCtx, app: &App, id: BusRouteID) -> Widget {
+    let route = app.primary.map.get_br(id);
+    let mut crowding_per_stop = app
+        .primary
+        .sim
+        .get_analytics()
+        .bus_crowding(app.primary.sim.time(), id);
+
+    let mut txt = Text::from(Line("Crowding").small_heading());
+    for idx in 0..route.stops.len() {
+        if let Some((hgram, left_behind)) = crowding_per_stop.remove(&route.stops[idx]) {
+            txt.add(Line(format!(
+                "Stop {}: leaving {}% full on average, {}% at worst",
+                idx + 1,
+                hgram.select(Statistic::Mean),
+                hgram.select(Statistic::Max)
+            )));
+            if left_behind > 0 {
+                txt.add(
+                    Line(format!(
+                        "  {} riders left behind",
+                        prettyprint_usize(left_behind)
+                    ))
+                    .fg(Color::RED),
+                );
+            }
+        } else {
+            txt.add(Line(format!("Stop {}: no departures yet", idx + 1)).secondary());
+        }
+    }
+    txt.draw(ctx)
+}
+
 fn passenger_delay(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteID) -> Widget {
     let route = app.primary.map.get_br(id);
     let mut master_col = vec![Line("Passengers waiting").small_heading().draw(ctx)];
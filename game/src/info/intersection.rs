@@ -4,7 +4,7 @@ use crate::info::{header_btns, make_tabs, throughput, DataOptions, Details, Tab}
 use abstutil::prettyprint_usize;
 use ezgui::{Color, EventCtx, GeomBatch, Line, PlotOptions, ScatterPlot, Series, Text, Widget};
 use geom::{ArrowCap, Distance, Duration, PolyLine, Time};
-use map_model::{IntersectionID, IntersectionType};
+use map_model::{CrosswalkID, CrosswalkType, IntersectionID, IntersectionType};
 use sim::TripMode;
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -21,6 +21,9 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID
         // TODO The spacing is ignored, so use -
         txt.add(Line(format!("- {}", r)));
     }
+    if i.is_traffic_signal() && !i.right_turn_on_red {
+        txt.add(Line("No right turn on red"));
+    }
     if app.opts.dev {
         txt.add(Line(format!("OSM node ID: {}", i.orig_id.osm_node_id)).secondary());
     }
@@ -29,6 +32,34 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID
     rows
 }
 
+pub fn crosswalk(ctx: &EventCtx, app: &App, _: &mut Details, id: CrosswalkID) -> Vec<Widget> {
+    let mut rows = vec![];
+    let map = &app.primary.map;
+    let ct = map.get_r(id.r).crosswalk_type(id.i);
+
+    rows.push(Widget::row(vec![
+        Line(format!("Crosswalk at {}", id.i))
+            .small_heading()
+            .draw(ctx),
+        header_btns(ctx),
+    ]));
+
+    let mut txt = Text::from(Line(format!("Crossing {}", map.get_r(id.r).get_name())));
+    txt.add(Line(format!("Type: {}", ct.describe())));
+    txt.add(
+        Line(match ct {
+            CrosswalkType::Marked => "Zebra stripes give pedestrians priority here",
+            CrosswalkType::Unmarked => "Pedestrians wait for a gap in traffic to cross",
+            // TODO Vehicles don't slow down for these yet
+            CrosswalkType::Raised => "Level with the sidewalk, so no curb ramps are needed",
+        })
+        .secondary(),
+    );
+    rows.push(txt.draw(ctx));
+
+    rows
+}
+
 pub fn traffic(
     ctx: &mut EventCtx,
     app: &App,
@@ -197,7 +228,7 @@ fn delay_plot(ctx: &EventCtx, app: &App, i: IntersectionID, opts: &DataOptions)
         app.primary.sim.time()
     };
     if let Some(list) = data.intersection_delays.get(&i) {
-        for (t, dt, mode) in list {
+        for (t, dt, mode, _) in list {
             if *t > limit {
                 break;
             }
@@ -27,10 +27,42 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
             ),
         ));
     } else {
-        kv.push(("Speed limit", r.speed_limit.to_string()));
+        kv.push((
+            "Speed limit",
+            r.speed_limit.describe_rounded(app.opts.units),
+        ));
+        for timed in &r.speed_limit_schedule {
+            kv.push(("Timed speed limit", timed.describe(app.opts.units)));
+        }
+        if let Some(i) = r.modal_filter {
+            kv.push(("Modal filter", format!("cars can't pass {}", i)));
+        }
+        if !r.no_through_traffic.is_empty() {
+            kv.push((
+                "No through traffic",
+                r.no_through_traffic
+                    .iter()
+                    .map(|c| format!("{:?}", c))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+        }
+        if !r.banned_vehicles.is_empty() {
+            kv.push((
+                "Banned",
+                r.banned_vehicles
+                    .iter()
+                    .map(|c| format!("{:?}", c))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+        }
     }
 
-    kv.push(("Length", l.length().describe_rounded()));
+    kv.push(("Length", l.length().describe_rounded(app.opts.units)));
+    if l.width != l.default_width() {
+        kv.push(("Width", l.width.describe_rounded(app.opts.units)));
+    }
 
     rows.extend(make_table(ctx, kv));
 
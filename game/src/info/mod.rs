@@ -18,7 +18,10 @@ use ezgui::{
     VerticalAlignment, Widget,
 };
 use geom::{Circle, Distance, Time};
-use map_model::{AreaID, BuildingID, BusStopID, IntersectionID, LaneID, ParkingLotID};
+use map_model::{
+    AreaID, BuildingID, BusStopID, CrosswalkID, IntersectionID, LaneID, ParkingLotID,
+    WalkingProfile,
+};
 use sim::{
     AgentID, Analytics, CarID, ParkingSpot, PedestrianID, PersonID, PersonState, TripID, TripMode,
     VehicleType,
@@ -61,6 +64,7 @@ pub enum Tab {
 
     BldgInfo(BuildingID),
     BldgPeople(BuildingID),
+    BldgWalkshed(BuildingID, WalkingProfile),
 
     ParkingLot(ParkingLotID),
 
@@ -73,6 +77,8 @@ pub enum Tab {
     IntersectionDelay(IntersectionID, DataOptions),
     IntersectionDemand(IntersectionID),
 
+    Crosswalk(CrosswalkID),
+
     LaneInfo(LaneID),
     LaneDebug(LaneID),
     LaneTraffic(LaneID, DataOptions),
@@ -92,7 +98,7 @@ impl Tab {
                         p,
                         OpenTrip::single(app.primary.sim.agent_to_trip(AgentID::Car(c)).unwrap()),
                     )
-                } else if c.1 == VehicleType::Bus {
+                } else if c.1.is_transit() {
                     Tab::BusStatus(c)
                 } else {
                     Tab::ParkedCar(c)
@@ -113,6 +119,7 @@ impl Tab {
             ID::PedCrowd(members) => Tab::Crowd(members),
             ID::BusStop(bs) => Tab::BusStop(bs),
             ID::Area(a) => Tab::Area(a),
+            ID::Crosswalk(c) => Tab::Crosswalk(c),
         }
     }
 
@@ -140,7 +147,9 @@ impl Tab {
                 ParkingSpot::Offstreet(b, _) => Some(ID::Building(b)),
                 ParkingSpot::Lot(_, _) => Some(ID::Car(*c)),
             },
-            Tab::BldgInfo(b) | Tab::BldgPeople(b) => Some(ID::Building(*b)),
+            Tab::BldgInfo(b) | Tab::BldgPeople(b) | Tab::BldgWalkshed(b, _) => {
+                Some(ID::Building(*b))
+            }
             Tab::ParkingLot(pl) => Some(ID::ParkingLot(*pl)),
             Tab::Crowd(members) => Some(ID::PedCrowd(members.clone())),
             Tab::Area(a) => Some(ID::Area(*a)),
@@ -148,6 +157,7 @@ impl Tab {
             | Tab::IntersectionTraffic(i, _)
             | Tab::IntersectionDelay(i, _)
             | Tab::IntersectionDemand(i) => Some(ID::Intersection(*i)),
+            Tab::Crosswalk(c) => Some(ID::Crosswalk(*c)),
             Tab::LaneInfo(l) | Tab::LaneDebug(l) | Tab::LaneTraffic(l, _) => Some(ID::Lane(*l)),
         }
     }
@@ -226,6 +236,10 @@ impl InfoPanel {
             ),
             Tab::BldgInfo(b) => (building::info(ctx, app, &mut details, b), true),
             Tab::BldgPeople(b) => (building::people(ctx, app, &mut details, b), false),
+            Tab::BldgWalkshed(b, profile) => (
+                building::walkshed(ctx, app, &mut details, b, profile),
+                false,
+            ),
             Tab::ParkingLot(pl) => (parking_lot::info(ctx, app, &mut details, pl), true),
             Tab::Crowd(ref members) => (person::crowd(ctx, app, &mut details, members), true),
             Tab::Area(a) => (debug::area(ctx, app, &mut details, a), true),
@@ -241,6 +255,7 @@ impl InfoPanel {
                 intersection::current_demand(ctx, app, &mut details, i),
                 false,
             ),
+            Tab::Crosswalk(c) => (intersection::crosswalk(ctx, app, &mut details, c), true),
             Tab::LaneInfo(l) => (lane::info(ctx, app, &mut details, l), true),
             Tab::LaneDebug(l) => (lane::debug(ctx, app, &mut details, l), false),
             Tab::LaneTraffic(l, ref opts) => {
@@ -61,7 +61,7 @@ pub fn ongoing(
         AgentID::Car(c) => match c.1 {
             VehicleType::Car => "driving",
             VehicleType::Bike => "biking",
-            VehicleType::Bus => unreachable!(),
+            VehicleType::Bus | VehicleType::Train => unreachable!(),
         },
         AgentID::BusPassenger(_, _) => "riding the bus",
     };
@@ -86,8 +86,12 @@ pub fn ongoing(
                 .force_width_pct(ctx, col_width),
             Widget::col(vec![
                 Text::from_all(vec![
-                    Line(props.dist_crossed.describe_rounded()),
-                    Line(format!("/{}", props.total_dist.describe_rounded())).secondary(),
+                    Line(props.dist_crossed.describe_rounded(app.opts.units)),
+                    Line(format!(
+                        "/{}",
+                        props.total_dist.describe_rounded(app.opts.units)
+                    ))
+                    .secondary(),
                 ])
                 .draw(ctx),
                 Text::from_all(vec![
@@ -543,7 +547,7 @@ fn make_timeline(
 
             // This is expensive, so cache please
             if idx == open_trip.cached_routes.len() {
-                if let Some(trace) = path.trace(map, dist, None) {
+                if let Ok(trace) = path.trace(map, dist, None) {
                     open_trip.cached_routes.push(Some((
                         trace.make_polygons(Distance::meters(10.0)),
                         trace.dashed_lines(
@@ -0,0 +1,53 @@
+use abstutil::Timer;
+use ezgui::EventCtx;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The code writes every user-facing string in English. A language pack maps those strings to
+// another language, so translating the UI only takes a new JSON file in data/system/translations.
+// Only text the code marks as translatable is looked up, so names from the map or the player are
+// never replaced. Anything the pack doesn't cover stays in English.
+#[derive(Serialize, Deserialize)]
+pub struct LanguagePack {
+    // What the language calls itself, like "Español"
+    pub name: String,
+    pub strings: BTreeMap<String, String>,
+}
+
+pub const ENGLISH: &str = "en";
+
+// (file name, name of the language), starting with English, which is built in
+pub fn all_languages() -> Vec<(String, String)> {
+    let mut results = vec![(ENGLISH.to_string(), "English".to_string())];
+    for path in abstutil::list_all_objects(abstutil::path_all_language_packs()) {
+        let language = abstutil::basename(&path);
+        if let Ok(pack) = load(&language) {
+            results.push((language, pack.name));
+        }
+    }
+    results
+}
+
+pub fn set_language(ctx: &EventCtx, language: &str) {
+    if language == ENGLISH {
+        ctx.set_translations(Default::default());
+        return;
+    }
+    match load(language) {
+        Ok(pack) => {
+            ctx.set_translations(pack.strings.into_iter().collect());
+        }
+        Err(err) => {
+            warn!("Couldn't load language pack {}: {}", language, err);
+            ctx.set_translations(Default::default());
+        }
+    }
+}
+
+fn load(language: &str) -> Result<LanguagePack, abstutil::Error> {
+    abstutil::maybe_read_json(
+        abstutil::path_language_pack(language),
+        &mut Timer::throwaway(),
+    )
+}
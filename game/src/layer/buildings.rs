@@ -0,0 +1,127 @@
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Polygon};
+use map_model::Building;
+
+// Extrudes buildings based on their number of levels, with shadows. A cheap way to get a feel for
+// a neighborhood without real 3D rendering.
+pub struct BuildingHeights {
+    composite: Composite,
+    zoomed: Drawable,
+}
+
+// Meters per level
+const LEVEL_HEIGHT: f64 = 3.0;
+// We're looking down at the map from a steep angle, facing north. This is how much of a
+// building's height shows up on screen.
+const TILT: f64 = 0.5;
+// Shadows fall to the southeast
+const SHADOW_LENGTH: f64 = 0.3;
+
+impl Layer for BuildingHeights {
+    fn name(&self) -> Option<&'static str> {
+        Some("3D buildings")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        _: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        // Only worth it when the buildings are big enough to see
+        if g.canvas.cam_zoom >= app.opts.min_zoom_for_detail {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, _: &mut GfxCtx) {}
+}
+
+impl BuildingHeights {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> BuildingHeights {
+        let mut bldgs: Vec<&Building> = app.primary.map.all_buildings().iter().collect();
+        // Draw from north to south, so closer buildings cover up the ones behind them
+        bldgs.sort_by(|a, b| a.label_center.y().partial_cmp(&b.label_center.y()).unwrap());
+
+        let wall_color = app.cs.building.lerp(Color::BLACK, 0.3);
+        let mut shadows = GeomBatch::new();
+        let mut batch = GeomBatch::new();
+        let mut num_tall = 0;
+        let mut max_levels = 0.0_f64;
+        for b in bldgs {
+            if b.levels > 1.0 {
+                num_tall += 1;
+            }
+            max_levels = max_levels.max(b.levels);
+
+            let height = b.levels * LEVEL_HEIGHT;
+            let dy = -height * TILT;
+            shadows.push(
+                Color::BLACK.alpha(0.3),
+                b.polygon
+                    .translate(height * SHADOW_LENGTH, height * SHADOW_LENGTH),
+            );
+            // Walls facing away from the camera get covered up by the roof
+            for pair in b.polygon.points().windows(2) {
+                if pair[0] == pair[1] {
+                    continue;
+                }
+                batch.push(
+                    wall_color,
+                    Polygon::new(&vec![
+                        pair[0],
+                        pair[1],
+                        pair[1].offset(0.0, dy),
+                        pair[0].offset(0.0, dy),
+                    ]),
+                );
+            }
+            let roof = b.polygon.translate(0.0, dy);
+            let outline = roof.maybe_to_outline(Distance::meters(0.1));
+            batch.push(app.cs.building, roof);
+            if let Some(outline) = outline {
+                batch.push(app.cs.building_outline, outline);
+            }
+        }
+        shadows.append(batch);
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "3D buildings".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from_multiline(vec![
+                    Line(format!(
+                        "{} buildings taller than one story",
+                        abstutil::prettyprint_usize(num_tall)
+                    )),
+                    Line(format!("Tallest has {} levels", max_levels)),
+                    Line("Heights come from OpenStreetMap").secondary(),
+                    Line("Zoom in to see them").secondary(),
+                ])
+                .draw(ctx),
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        BuildingHeights {
+            composite,
+            zoomed: shadows.upload(ctx),
+        }
+    }
+}
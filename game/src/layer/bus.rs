@@ -6,7 +6,7 @@ use ezgui::{
     Line, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Circle, Distance, Pt2D, Time};
-use map_model::{BusRouteID, PathConstraints, PathRequest, PathStep};
+use map_model::{BusRouteID, PathRequest, PathStep};
 
 // TODO This maybe shouldn't be a layer
 pub struct ShowBusRoute {
@@ -98,7 +98,7 @@ impl ShowBusRoute {
                 .pathfind(PathRequest {
                     start: bs1.driving_pos,
                     end: bs2.driving_pos,
-                    constraints: PathConstraints::Bus,
+                    constraints: route.route_type,
                 })
                 .unwrap()
                 .get_steps()
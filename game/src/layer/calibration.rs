@@ -0,0 +1,164 @@
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Slider, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration, Speed, Units};
+use log::info;
+use sim::ModelConstants;
+
+// Sliders for the simulation's behavioral constants. Changes apply to the running sim right away,
+// so calibrating against observed counts doesn't need a recompile for every guess. This isn't a
+// map layer, but like one, it stays open while time passes.
+pub struct Calibration {
+    composite: Composite,
+    // The constants when this layer was opened
+    before: Option<ModelConstants>,
+}
+
+// (name, min, max) for each slider. Distances are in meters, durations in seconds, and speeds in
+// meters per second; they're only displayed in the player's units.
+const RANGES: [(&str, f64, f64); 5] = [
+    ("following distance", 0.5, 5.0),
+    ("start-up lost time", 0.0, 4.0),
+    ("yield critical gap", 1.0, 10.0),
+    ("min walking speed", 0.4, 2.3),
+    ("max walking speed", 0.4, 2.3),
+];
+
+impl Layer for Calibration {
+    fn name(&self) -> Option<&'static str> {
+        Some("model constants")
+    }
+    fn on_activate(&mut self, _: &mut EventCtx, app: &mut App) {
+        self.before = Some(app.primary.sim.get_model_constants().clone());
+    }
+    // Calibrating happens interactively, so leave a record of what changed, to be copied somewhere
+    // more permanent.
+    fn on_deactivate(&mut self, _: &mut EventCtx, app: &mut App) {
+        let after = app.primary.sim.get_model_constants();
+        if let Some(before) = self.before.take() {
+            if &before != after {
+                info!("Model constants changed from {:?} to {:?}", before, after);
+            }
+        }
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        let outcome = Layer::simple_event(ctx, minimap, &mut self.composite);
+
+        let values: Vec<f64> = RANGES
+            .iter()
+            .map(|(name, min, max)| {
+                let pct = self.composite.slider(name).get_percent();
+                // Round off, so tiny drags don't thrash the sim
+                ((min + pct * (max - min)) * 10.0).round() / 10.0
+            })
+            .collect();
+        let constants = ModelConstants {
+            following_distance: Distance::meters(values[0]),
+            start_up_lost_time: Duration::seconds(values[1]),
+            yield_critical_gap: Duration::seconds(values[2]),
+            min_walking_speed: Speed::meters_per_second(values[3]),
+            // The range can't be backwards
+            max_walking_speed: Speed::meters_per_second(values[3].max(values[4])),
+        };
+        if &constants != app.primary.sim.get_model_constants() {
+            app.primary.sim.set_model_constants(constants);
+            self.composite
+                .replace(ctx, "values", describe(ctx, app).named("values"));
+        }
+
+        outcome
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+    }
+    fn draw_minimap(&self, _: &mut GfxCtx) {}
+}
+
+impl Calibration {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Calibration {
+        let current = current_values(app.primary.sim.get_model_constants());
+        let mut col = vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg").margin_right(10),
+                "Model constants".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            describe(ctx, app).named("values").margin_below(5),
+        ];
+        for ((name, min, max), value) in RANGES.iter().zip(current) {
+            col.push(name.draw_text(ctx));
+            col.push(
+                Slider::horizontal(
+                    ctx,
+                    200.0,
+                    25.0,
+                    abstutil::clamp((value - min) / (max - min), 0.0, 1.0),
+                )
+                .named(*name)
+                .margin_below(5),
+            );
+        }
+
+        Calibration {
+            composite: Composite::new(Widget::col(col).padding(5).bg(app.cs.panel_bg))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .build(ctx),
+            before: None,
+        }
+    }
+}
+
+// In the same order and units as RANGES
+fn current_values(c: &ModelConstants) -> Vec<f64> {
+    vec![
+        c.following_distance.inner_meters(),
+        c.start_up_lost_time.inner_seconds(),
+        c.yield_critical_gap.inner_seconds(),
+        c.min_walking_speed.inner_meters_per_second(),
+        c.max_walking_speed.inner_meters_per_second(),
+    ]
+}
+
+// The usual describe_rounded drops the decimal places that matter for these small values
+fn fmt_dist(meters: f64, units: Units) -> String {
+    match units {
+        Units::Metric => format!("{:.1} m", meters),
+        Units::Imperial => format!("{:.1} ft", meters * 3.28084),
+    }
+}
+
+fn fmt_speed(mps: f64, units: Units) -> String {
+    match units {
+        Units::Metric => format!("{:.1} km/h", mps * 3.6),
+        Units::Imperial => format!("{:.1} mph", mps * 2.23694),
+    }
+}
+
+fn describe(ctx: &mut EventCtx, app: &App) -> Widget {
+    let values = current_values(app.primary.sim.get_model_constants());
+    let mut txt = Text::new();
+    let units = app.opts.units;
+    txt.add(Line(format!(
+        "Following distance: {}",
+        fmt_dist(values[0], units)
+    )));
+    txt.add(Line(format!("Start-up lost time: {:.1}s", values[1])));
+    txt.add(Line(format!("Yield critical gap: {:.1}s", values[2])));
+    txt.add(Line(format!(
+        "Walking speeds: {} to {}",
+        fmt_speed(values[3], units),
+        fmt_speed(values[4], units)
+    )));
+    txt.add(Line("A new following distance applies to each lane once it's empty").secondary());
+    txt.draw(ctx)
+}
@@ -0,0 +1,240 @@
+use crate::app::App;
+use crate::helpers::color_for_mode;
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::{prettyprint_usize, Counter};
+use ezgui::{
+    hotkey, Btn, Checkbox, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment,
+    Key, Line, Outcome, Spinner, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Distance, Duration, HashablePt2D, Pt2D, Time};
+use map_model::Map;
+use sim::{TripEndpoint, TripMode};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Straight lines between where trips start and end, ignoring the actual route taken. Thicker lines
+// mean more trips. Trips in both directions between the same two places share one line.
+pub struct DesireLines {
+    opts: Options,
+    draw: Drawable,
+    composite: Composite,
+}
+
+// When bundling, endpoints are grouped into square cells this big.
+const BUNDLE_SIZE: f64 = 500.0;
+const MIN_WIDTH: Distance = Distance::const_meters(2.0);
+const MAX_WIDTH: Distance = Distance::const_meters(50.0);
+
+impl Layer for DesireLines {
+    fn name(&self) -> Option<&'static str> {
+        Some("desire lines")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let new_opts = self.options();
+                if self.opts != new_opts {
+                    *self = DesireLines::new(ctx, app, new_opts);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl DesireLines {
+    pub fn new(ctx: &mut EventCtx, app: &App, opts: Options) -> DesireLines {
+        let map = &app.primary.map;
+        let start_time = Time::START_OF_DAY + Duration::hours(opts.start_hour);
+        let end_time = Time::START_OF_DAY + Duration::hours(opts.end_hour);
+
+        let mut num_trips = 0;
+        let mut pairs: BTreeMap<(HashablePt2D, HashablePt2D), Counter<TripMode>> = BTreeMap::new();
+        for (_, departure, from, to, mode) in app.primary.sim.all_trip_info() {
+            if departure < start_time || departure >= end_time || !opts.modes.contains(&mode) {
+                continue;
+            }
+            let pt1 = endpoint_pt(map, &from, opts.bundle).to_hashable();
+            let pt2 = endpoint_pt(map, &to, opts.bundle).to_hashable();
+            // Trips within one building or cell don't have a line to draw
+            if pt1 == pt2 {
+                continue;
+            }
+            num_trips += 1;
+            let key = if pt1 < pt2 { (pt1, pt2) } else { (pt2, pt1) };
+            pairs.entry(key).or_insert_with(Counter::new).inc(mode);
+        }
+
+        let mut lines: Vec<(usize, TripMode, HashablePt2D, HashablePt2D)> = pairs
+            .into_iter()
+            .map(|((pt1, pt2), cnt)| {
+                // Color each line by whatever mode most of its trips use
+                let mode = *cnt.borrow().iter().max_by_key(|(_, n)| **n).unwrap().0;
+                (cnt.sum(), mode, pt1, pt2)
+            })
+            .collect();
+        // Draw the thick lines first, so the thin ones stay visible on top
+        lines.sort_by_key(|(cnt, _, _, _)| std::cmp::Reverse(*cnt));
+        let max_cnt = lines.get(0).map(|(cnt, _, _, _)| *cnt).unwrap_or(1);
+
+        let mut batch = GeomBatch::new();
+        for (cnt, mode, pt1, pt2) in &lines {
+            let width = MIN_WIDTH + (MAX_WIDTH - MIN_WIDTH) * ((*cnt as f64) / (max_cnt as f64));
+            if let Some(l) = geom::Line::maybe_new(pt1.to_pt2d(), pt2.to_pt2d()) {
+                batch.push(
+                    color_for_mode(app, *mode).alpha(0.7),
+                    l.make_polygons(width),
+                );
+            }
+        }
+
+        let composite = make_controls(ctx, app, &opts, num_trips, lines.len(), max_cnt);
+        DesireLines {
+            opts,
+            draw: ctx.upload(batch),
+            composite,
+        }
+    }
+
+    fn options(&self) -> Options {
+        let mut modes = BTreeSet::new();
+        for m in TripMode::all() {
+            if self.composite.is_checked(m.ongoing_verb()) {
+                modes.insert(m);
+            }
+        }
+        let start_hour = self.composite.spinner("start hour");
+        // Keep the window at least an hour long
+        let end_hour = self.composite.spinner("end hour").max(start_hour + 1);
+        Options {
+            modes,
+            start_hour,
+            end_hour,
+            bundle: self.composite.is_checked("bundle nearby endpoints"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Options {
+    modes: BTreeSet<TripMode>,
+    // Only trips departing in [start_hour, end_hour)
+    start_hour: usize,
+    end_hour: usize,
+    // Group endpoints into a coarse grid, to see the overall pattern instead of individual
+    // buildings
+    bundle: bool,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            modes: TripMode::all().into_iter().collect(),
+            start_hour: 0,
+            end_hour: 24,
+            bundle: true,
+        }
+    }
+}
+
+fn endpoint_pt(map: &Map, endpt: &TripEndpoint, bundle: bool) -> Pt2D {
+    let pt = match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(*b).label_center,
+        TripEndpoint::Border(i, _) => map.get_i(*i).polygon.center(),
+    };
+    if bundle {
+        Pt2D::new(
+            ((pt.x() / BUNDLE_SIZE).floor() + 0.5) * BUNDLE_SIZE,
+            ((pt.y() / BUNDLE_SIZE).floor() + 0.5) * BUNDLE_SIZE,
+        )
+    } else {
+        pt
+    }
+}
+
+fn make_controls(
+    ctx: &mut EventCtx,
+    app: &App,
+    opts: &Options,
+    num_trips: usize,
+    num_lines: usize,
+    max_cnt: usize,
+) -> Composite {
+    let mut col = vec![
+        Widget::row(vec![
+            Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg").margin_right(10),
+            "Desire lines".draw_text(ctx),
+            Btn::plaintext("X")
+                .build(ctx, "close", hotkey(Key::Escape))
+                .align_right(),
+        ]),
+        Text::from_multiline(vec![
+            Line(format!("{} trips", prettyprint_usize(num_trips))),
+            Line(format!(
+                "{} origin-destination pairs",
+                prettyprint_usize(num_lines)
+            )),
+            Line(format!(
+                "Thickest line has {} trips",
+                prettyprint_usize(max_cnt)
+            ))
+            .secondary(),
+        ])
+        .draw(ctx)
+        .margin_below(5),
+    ];
+    for m in TripMode::all() {
+        col.push(
+            Checkbox::colored(
+                ctx,
+                m.ongoing_verb(),
+                color_for_mode(app, m),
+                opts.modes.contains(&m),
+            )
+            .margin_below(5),
+        );
+    }
+    col.push(
+        Widget::row(vec![
+            "Departing between hours"
+                .draw_text(ctx)
+                .centered_vert()
+                .margin_right(10),
+            Spinner::new(ctx, (0, 23), opts.start_hour)
+                .named("start hour")
+                .margin_right(5),
+            "and".draw_text(ctx).centered_vert().margin_right(5),
+            Spinner::new(ctx, (1, 24), opts.end_hour).named("end hour"),
+        ])
+        .margin_below(5),
+    );
+    col.push(Checkbox::text(
+        ctx,
+        "bundle nearby endpoints",
+        None,
+        opts.bundle,
+    ));
+
+    Composite::new(Widget::col(col).padding(5).bg(app.cs.panel_bg))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx)
+}
@@ -2,12 +2,19 @@ use crate::app::App;
 use crate::common::{ColorLegend, ColorNetwork};
 use crate::layer::{Layer, LayerOutcome};
 use ezgui::{
-    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
-    Line, Text, TextExt, VerticalAlignment, Widget,
+    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{ArrowCap, Distance, PolyLine};
+use geom::{ArrowCap, Distance, PolyLine, Polygon, Pt2D};
+use map_model::Map;
+
+// In meters
+const HILLSHADE_RESOLUTION: f64 = 20.0;
+// How many cells away to look for intersections when estimating the elevation of a cell
+const HILLSHADE_RADIUS: isize = 5;
 
 pub struct Elevation {
+    hillshade: bool,
     unzoomed: Drawable,
     zoomed: Drawable,
     composite: Composite,
@@ -20,10 +27,26 @@ impl Layer for Elevation {
     fn event(
         &mut self,
         ctx: &mut EventCtx,
-        _: &mut App,
+        app: &mut App,
         minimap: &Composite,
     ) -> Option<LayerOutcome> {
-        Layer::simple_event(ctx, minimap, &mut self.composite)
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let hillshade = self.composite.is_checked("Hillshading");
+                if hillshade != self.hillshade {
+                    *self = Elevation::new(ctx, app, hillshade);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
     }
     fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.composite.draw(g);
@@ -39,7 +62,7 @@ impl Layer for Elevation {
 }
 
 impl Elevation {
-    pub fn new(ctx: &mut EventCtx, app: &App) -> Elevation {
+    pub fn new(ctx: &mut EventCtx, app: &App, hillshade: bool) -> Elevation {
         let mut colorer = ColorNetwork::new(app);
 
         let mut max = 0.0_f64;
@@ -92,6 +115,18 @@ impl Elevation {
         }
         colorer.unzoomed.append(batch);
 
+        let (unzoomed, zoomed) = if hillshade {
+            // Shade underneath the roads
+            let shading = make_hillshade(&app.primary.map);
+            let mut unzoomed = shading.clone();
+            unzoomed.append(colorer.unzoomed);
+            let mut zoomed = shading;
+            zoomed.append(colorer.zoomed);
+            (unzoomed, zoomed)
+        } else {
+            (colorer.unzoomed, colorer.zoomed)
+        };
+
         let composite = Composite::new(
             Widget::col(vec![
                 Widget::row(vec![
@@ -108,6 +143,7 @@ impl Elevation {
                 ])
                 .draw(ctx),
                 ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["flat", "steep"]),
+                Checkbox::text(ctx, "Hillshading", None, hillshade).margin_above(5),
             ])
             .padding(5)
             .bg(app.cs.panel_bg),
@@ -116,9 +152,102 @@ impl Elevation {
         .build(ctx);
 
         Elevation {
-            unzoomed: ctx.upload(colorer.unzoomed),
-            zoomed: ctx.upload(colorer.zoomed),
+            hillshade,
+            unzoomed: ctx.upload(unzoomed),
+            zoomed: ctx.upload(zoomed),
             composite,
         }
     }
 }
+
+// Estimates the elevation everywhere from the nearby intersections, then darkens slopes facing
+// away from the sun, which sits to the northwest, 45 degrees above the horizon.
+fn make_hillshade(map: &Map) -> GeomBatch {
+    let bounds = map.get_bounds();
+    let width = (bounds.width() / HILLSHADE_RESOLUTION).ceil() as usize;
+    let height = (bounds.height() / HILLSHADE_RESOLUTION).ceil() as usize;
+
+    let mut buckets: Vec<Vec<(Pt2D, f64)>> =
+        std::iter::repeat(Vec::new()).take(width * height).collect();
+    for i in map.all_intersections() {
+        let pt = i.polygon.center();
+        let x = (((pt.x() - bounds.min_x) / HILLSHADE_RESOLUTION) as usize).min(width - 1);
+        let y = (((pt.y() - bounds.min_y) / HILLSHADE_RESOLUTION) as usize).min(height - 1);
+        buckets[y * width + x].push((pt, i.elevation.inner_meters()));
+    }
+
+    // Inverse distance weighting. None if there are no intersections nearby.
+    let mut elevation: Vec<Option<f64>> = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let center = Pt2D::new(
+                bounds.min_x + (x as f64 + 0.5) * HILLSHADE_RESOLUTION,
+                bounds.min_y + (y as f64 + 0.5) * HILLSHADE_RESOLUTION,
+            );
+            let mut total = 0.0;
+            let mut total_weight = 0.0;
+            for dy in -HILLSHADE_RADIUS..=HILLSHADE_RADIUS {
+                for dx in -HILLSHADE_RADIUS..=HILLSHADE_RADIUS {
+                    let next_x = x as isize + dx;
+                    let next_y = y as isize + dy;
+                    if next_x < 0
+                        || next_y < 0
+                        || next_x >= width as isize
+                        || next_y >= height as isize
+                    {
+                        continue;
+                    }
+                    for (pt, e) in &buckets[(next_y as usize) * width + (next_x as usize)] {
+                        let weight = 1.0 / center.dist_to(*pt).inner_meters().max(1.0).powi(2);
+                        total += weight * e;
+                        total_weight += weight;
+                    }
+                }
+            }
+            elevation.push(if total_weight > 0.0 {
+                Some(total / total_weight)
+            } else {
+                None
+            });
+        }
+    }
+
+    // The direction pointing at the sun, as (east, north, up)
+    let azimuth = 315.0_f64.to_radians();
+    let altitude = 45.0_f64.to_radians();
+    let sun = (
+        altitude.cos() * azimuth.sin(),
+        altitude.cos() * azimuth.cos(),
+        altitude.sin(),
+    );
+
+    let mut batch = GeomBatch::new();
+    let square = Polygon::rectangle(HILLSHADE_RESOLUTION, HILLSHADE_RESOLUTION);
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            let get = |x: usize, y: usize| elevation[y * width + x];
+            // Map y coordinates point south
+            if let (Some(west), Some(east), Some(north), Some(south)) =
+                (get(x - 1, y), get(x + 1, y), get(x, y - 1), get(x, y + 1))
+            {
+                let dz_east = (east - west) / (2.0 * HILLSHADE_RESOLUTION);
+                let dz_north = (north - south) / (2.0 * HILLSHADE_RESOLUTION);
+                // Compare the surface normal with the sun direction
+                let len = (dz_east.powi(2) + dz_north.powi(2) + 1.0).sqrt();
+                let light = ((-dz_east * sun.0 - dz_north * sun.1 + sun.2) / len).max(0.0);
+                // Flat ground gets sun.2 worth of light; only darken things in the shade
+                let darkness = (sun.2 - light) / sun.2;
+                if darkness > 0.02 {
+                    batch.push(
+                        Color::BLACK.alpha((0.6 * darkness).min(0.6) as f32),
+                        square.translate(
+                            bounds.min_x + (x as f64) * HILLSHADE_RESOLUTION,
+                            bounds.min_y + (y as f64) * HILLSHADE_RESOLUTION,
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    batch
+}
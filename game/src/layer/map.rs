@@ -8,8 +8,9 @@ use ezgui::{
     Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Time};
-use map_model::LaneType;
+use map_model::{LaneType, RoadID};
 use sim::TripMode;
+use std::collections::BTreeSet;
 
 pub struct BikeNetwork {
     composite: Composite,
@@ -110,7 +111,7 @@ impl BikeNetwork {
                     Line(format!("{} lanes", num_lanes)),
                     Line(format!(
                         "total distance of {}",
-                        total_dist.describe_rounded()
+                        total_dist.describe_rounded(app.opts.units)
                     )),
                 ])
                 .draw(ctx)
@@ -254,15 +255,40 @@ impl Static {
         );
 
         let edits = app.primary.map.get_edits();
-        for l in edits.original_lts.keys().chain(&edits.reversed_lanes) {
+        for l in edits
+            .original_lts
+            .keys()
+            .chain(&edits.reversed_lanes)
+            .chain(&edits.changed_lane_widths)
+        {
             colorer.add_l(*l, "modified lane/intersection");
         }
         for i in edits.original_intersections.keys() {
             colorer.add_i(*i, "modified lane/intersection");
         }
-        for r in &edits.changed_speed_limits {
+        for r in edits
+            .changed_speed_limits
+            .iter()
+            .chain(&edits.changed_speed_limit_schedules)
+            .chain(&edits.changed_no_through_traffic)
+            .chain(&edits.changed_banned_vehicles)
+            .chain(&edits.modal_filters)
+        {
             colorer.add_r(*r, "modified lane/intersection");
         }
+        for id in &edits.changed_crosswalks {
+            colorer.add_i(id.i, "modified lane/intersection");
+        }
+        for i in edits
+            .banned_right_turn_on_red
+            .iter()
+            .chain(&edits.banned_u_turns)
+        {
+            colorer.add_i(*i, "modified lane/intersection");
+        }
+        for bs in &edits.changed_bus_stops {
+            colorer.add_l(bs.sidewalk, "modified lane/intersection");
+        }
 
         Static::new(
             ctx,
@@ -273,19 +299,83 @@ impl Static {
             Text::from_multiline(vec![
                 Line(format!("{} lane types changed", edits.original_lts.len())),
                 Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
+                Line(format!(
+                    "{} lane widths changed",
+                    edits.changed_lane_widths.len()
+                )),
                 Line(format!(
                     "{} speed limits changed",
                     edits.changed_speed_limits.len()
                 )),
+                Line(format!(
+                    "{} roads with timed speed limits changed",
+                    edits.changed_speed_limit_schedules.len()
+                )),
+                Line(format!(
+                    "{} roads with through traffic changed",
+                    edits.changed_no_through_traffic.len()
+                )),
+                Line(format!(
+                    "{} roads with vehicle bans changed",
+                    edits.changed_banned_vehicles.len()
+                )),
+                Line(format!("{} modal filters", edits.modal_filters.len())),
                 Line(format!(
                     "{} intersections changed",
                     edits.original_intersections.len()
                 )),
+                Line(format!(
+                    "{} crosswalks changed",
+                    edits.changed_crosswalks.len()
+                )),
+                Line(format!(
+                    "{} signals ban right turns on red",
+                    edits.banned_right_turn_on_red.len()
+                )),
+                Line(format!(
+                    "{} intersections ban U-turns",
+                    edits.banned_u_turns.len()
+                )),
+                Line(format!(
+                    "{} bus stops changed",
+                    edits.changed_bus_stops.len()
+                )),
             ])
             .draw(ctx),
         )
     }
 
+    fn timed_speed_limits(ctx: &mut EventCtx, app: &App, active: &BTreeSet<RoadID>) -> Static {
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("in effect now", Color::RED),
+                ("not in effect", Color::YELLOW),
+            ],
+        );
+        let mut total = 0;
+        for r in app.primary.map.all_roads() {
+            if r.speed_limit_schedule.is_empty() {
+                continue;
+            }
+            total += 1;
+            if active.contains(&r.id) {
+                colorer.add_r(r.id, "in effect now");
+            } else {
+                colorer.add_r(r.id, "not in effect");
+            }
+        }
+
+        Static::new(
+            ctx,
+            app,
+            colorer,
+            "timed speed limits",
+            "School zones and other timed speed limits".to_string(),
+            format!("{} of {} roads have a lower limit now", active.len(), total).draw_text(ctx),
+        )
+    }
+
     pub fn amenities(ctx: &mut EventCtx, app: &App) -> Static {
         let mut colorer = ColorDiscrete::new(
             app,
@@ -328,3 +418,53 @@ impl Static {
         )
     }
 }
+
+// Updates as the time-of-day limits come and go
+pub struct TimedSpeedLimits {
+    layer: Static,
+    active: BTreeSet<RoadID>,
+}
+
+impl Layer for TimedSpeedLimits {
+    fn name(&self) -> Option<&'static str> {
+        self.layer.name()
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if active_timed_limits(app) != self.active {
+            *self = TimedSpeedLimits::new(ctx, app);
+        }
+        self.layer.event(ctx, app, minimap)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.layer.draw(g, app);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        self.layer.draw_minimap(g);
+    }
+}
+
+impl TimedSpeedLimits {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> TimedSpeedLimits {
+        let active = active_timed_limits(app);
+        TimedSpeedLimits {
+            layer: Static::timed_speed_limits(ctx, app, &active),
+            active,
+        }
+    }
+}
+
+fn active_timed_limits(app: &App) -> BTreeSet<RoadID> {
+    let now = app.primary.sim.time();
+    app.primary
+        .map
+        .all_roads()
+        .iter()
+        .filter(|r| r.speed_limit_at(now) < r.speed_limit)
+        .map(|r| r.id)
+        .collect()
+}
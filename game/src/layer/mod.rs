@@ -1,6 +1,11 @@
+mod buildings;
 pub mod bus;
+mod calibration;
+mod desire_lines;
 mod elevation;
 pub mod map;
+mod noise;
+mod overlay;
 mod pandemic;
 mod parking;
 mod population;
@@ -15,8 +20,16 @@ use ezgui::{hotkey, Btn, Composite, EventCtx, GfxCtx, Key, Line, Outcome, TextEx
 // TODO Good ideas in
 // https://towardsdatascience.com/top-10-map-types-in-data-visualization-b3a80898ea70
 
+// Layers are ambient: at most one is active at a time, it's drawn on top of whatever State is
+// running, and it only sees input that the State's own panels didn't consume. Anything modal
+// should be a State pushed onto the stack instead. Always change the active layer through
+// set_layer, so the lifecycle hooks run.
 pub trait Layer {
     fn name(&self) -> Option<&'static str>;
+    // Called right after becoming the active layer
+    fn on_activate(&mut self, _: &mut EventCtx, _: &mut App) {}
+    // Called right before another layer replaces this one, or it's closed
+    fn on_deactivate(&mut self, _: &mut EventCtx, _: &mut App) {}
     fn event(
         &mut self,
         ctx: &mut EventCtx,
@@ -46,6 +59,16 @@ impl dyn Layer {
     }
 }
 
+pub fn set_layer(ctx: &mut EventCtx, app: &mut App, layer: Option<Box<dyn Layer>>) {
+    if let Some(mut old) = app.layer.take() {
+        old.on_deactivate(ctx, app);
+    }
+    if let Some(mut new) = layer {
+        new.on_activate(ctx, app);
+        app.layer = Some(new);
+    }
+}
+
 // TODO Just return a bool for closed? Less readable...
 pub enum LayerOutcome {
     Close,
@@ -66,7 +89,7 @@ impl PickLayer {
         let mut layer = app.layer.take().unwrap();
         match layer.event(ctx, app, minimap) {
             Some(LayerOutcome::Close) => {
-                app.layer = None;
+                layer.on_deactivate(ctx, app);
                 return None;
             }
             None => {}
@@ -106,6 +129,9 @@ impl PickLayer {
                     btn("delay", Key::D),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
+                    btn("queues", Key::Q),
+                    btn("conflicts", Key::C),
+                    btn("noise", Key::O),
                 ]),
                 Widget::col(vec![
                     "Map".draw_text(ctx).margin_below(10),
@@ -114,6 +140,9 @@ impl PickLayer {
                     btn("bike network", Key::B),
                     btn("bus network", Key::U),
                     btn("population map", Key::X),
+                    btn("3D buildings", Key::H),
+                    btn("desire lines", Key::L),
+                    btn("timed speed limits", Key::V),
                 ]),
             ])
             .evenly_spaced(),
@@ -124,6 +153,8 @@ impl PickLayer {
             btn("amenities", Key::A),
             btn("backpressure", Key::Z),
             btn("elevation", Key::S),
+            btn("external data", Key::G),
+            btn("model constants", Key::K),
         ]);
         if app.primary.sim.get_pandemic_model().is_some() {
             col.push(btn("pandemic model", Key::Y));
@@ -140,64 +171,57 @@ impl PickLayer {
 impl State for PickLayer {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         match self.composite.event(ctx) {
-            Some(Outcome::Clicked(x)) => match x.as_ref() {
-                "close" => {}
-                "None" => {
-                    app.layer = None;
-                }
-                "parking occupancy" => {
-                    app.layer = Some(Box::new(parking::Occupancy::new(
+            Some(Outcome::Clicked(x)) => {
+                let layer: Option<Box<dyn Layer>> = match x.as_ref() {
+                    "close" => {
+                        return Transition::Pop;
+                    }
+                    "None" => None,
+                    "external data" => {
+                        return Transition::Replace(overlay::Overlay::pick_file());
+                    }
+                    "parking occupancy" => Some(Box::new(parking::Occupancy::new(
                         ctx, app, true, true, true, false,
-                    )));
-                }
-                "delay" => {
-                    app.layer = Some(Box::new(traffic::Delay::new(ctx, app, false)));
-                }
-                "traffic jams" => {
-                    app.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
-                }
-                "throughput" => {
-                    app.layer = Some(Box::new(traffic::Throughput::new(ctx, app, false)));
-                }
-                "backpressure" => {
-                    app.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
-                }
-                "bike network" => {
-                    app.layer = Some(Box::new(map::BikeNetwork::new(ctx, app)));
-                }
-                "bus network" => {
-                    app.layer = Some(Box::new(map::Static::bus_network(ctx, app)));
-                }
-                "elevation" => {
-                    app.layer = Some(Box::new(elevation::Elevation::new(ctx, app)));
-                }
-                "map edits" => {
-                    app.layer = Some(Box::new(map::Static::edits(ctx, app)));
-                }
-                "amenities" => {
-                    app.layer = Some(Box::new(map::Static::amenities(ctx, app)));
-                }
-                "population map" => {
-                    app.layer = Some(Box::new(population::PopulationMap::new(
+                    ))),
+                    "delay" => Some(Box::new(traffic::Delay::new(ctx, app, false))),
+                    "traffic jams" => Some(Box::new(traffic::TrafficJams::new(ctx, app))),
+                    "queues" => Some(Box::new(traffic::QueueLengths::new(ctx, app))),
+                    "conflicts" => Some(Box::new(traffic::Conflicts::new(ctx, app, None))),
+                    "noise" => Some(Box::new(noise::Noise::new(ctx, app))),
+                    "model constants" => Some(Box::new(calibration::Calibration::new(ctx, app))),
+                    "throughput" => Some(Box::new(traffic::Throughput::new(ctx, app, false))),
+                    "backpressure" => Some(Box::new(traffic::Backpressure::new(ctx, app))),
+                    "bike network" => Some(Box::new(map::BikeNetwork::new(ctx, app))),
+                    "bus network" => Some(Box::new(map::Static::bus_network(ctx, app))),
+                    "3D buildings" => Some(Box::new(buildings::BuildingHeights::new(ctx, app))),
+                    "desire lines" => Some(Box::new(desire_lines::DesireLines::new(
+                        ctx,
+                        app,
+                        desire_lines::Options::new(),
+                    ))),
+                    "elevation" => Some(Box::new(elevation::Elevation::new(ctx, app, false))),
+                    "map edits" => Some(Box::new(map::Static::edits(ctx, app))),
+                    "timed speed limits" => Some(Box::new(map::TimedSpeedLimits::new(ctx, app))),
+                    "amenities" => Some(Box::new(map::Static::amenities(ctx, app))),
+                    "population map" => Some(Box::new(population::PopulationMap::new(
                         ctx,
                         app,
                         population::Options {
                             heatmap: Some(HeatmapOptions::new()),
                         },
-                    )));
-                }
-                "pandemic model" => {
-                    app.layer = Some(Box::new(pandemic::Pandemic::new(
+                    ))),
+                    "pandemic model" => Some(Box::new(pandemic::Pandemic::new(
                         ctx,
                         app,
                         pandemic::Options {
                             heatmap: Some(HeatmapOptions::new()),
                             state: pandemic::SEIR::Infected,
                         },
-                    )));
-                }
-                _ => unreachable!(),
-            },
+                    ))),
+                    _ => unreachable!(),
+                };
+                set_layer(ctx, app, layer);
+            }
             None => {
                 if self.composite.clicked_outside(ctx) {
                     return Transition::Pop;
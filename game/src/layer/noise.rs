@@ -0,0 +1,133 @@
+use crate::app::App;
+use crate::common::ColorDiscrete;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+use instant::Instant;
+use sim::NoiseEstimate;
+
+// Colors buildings by the estimated road noise reaching them, in 5dB bands like a noise map.
+pub struct Noise {
+    // Estimating is slow, so only redo it once per simulated hour, and not more often than
+    // REFRESH_EVERY when the sim runs fast
+    hour: usize,
+    last_refresh: Instant,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+// In real seconds
+const REFRESH_EVERY: f64 = 5.0;
+
+const BANDS: [(f64, &str); 5] = [
+    (70.0, "70+ dB"),
+    (65.0, "65-70 dB"),
+    (60.0, "60-65 dB"),
+    (55.0, "55-60 dB"),
+    (0.0, "under 55 dB"),
+];
+
+impl Layer for Noise {
+    fn name(&self) -> Option<&'static str> {
+        Some("noise")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time().get_hours() != self.hour
+            && abstutil::elapsed_seconds(self.last_refresh) >= REFRESH_EVERY
+        {
+            *self = Noise::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Noise {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Noise {
+        let sim = &app.primary.sim;
+        let noise = NoiseEstimate::new(sim, &app.primary.map);
+
+        let scale = &app.cs.good_to_bad_red;
+        let mut colorer = ColorDiscrete::new(
+            app,
+            BANDS
+                .iter()
+                .enumerate()
+                .map(|(idx, (_, label))| {
+                    let pct = 1.0 - (idx as f64) / ((BANDS.len() - 1) as f64);
+                    (*label, scale.eval(pct))
+                })
+                .collect(),
+        );
+        for (b, db) in &noise.per_building {
+            // The quietest band catches anything below 0dB too
+            let (_, label) = BANDS
+                .iter()
+                .find(|(min, _)| db >= min)
+                .unwrap_or(&BANDS[BANDS.len() - 1]);
+            colorer.add_b(*b, *label);
+        }
+        let (unzoomed, zoomed, legend) = colorer.build(ctx);
+
+        let mut txt = Text::new();
+        for (threshold, people) in noise.exposure(sim) {
+            txt.add(Line(format!(
+                "{} residents at {}+ dB",
+                abstutil::prettyprint_usize(people),
+                threshold
+            )));
+        }
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "Road noise".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from(
+                    Line("Estimated from traffic so far, ignoring anything blocking the sound")
+                        .secondary(),
+                )
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+                txt.draw(ctx).margin_below(5),
+                legend,
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Noise {
+            hour: sim.time().get_hours(),
+            last_refresh: Instant::now(),
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::app::App;
+use crate::game::{msg, State, Transition, WizardState};
+use crate::layer::{set_layer, Layer, LayerOutcome};
+use abstutil::prettyprint_usize;
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Circle, Distance, PolyLine, Polygon, Pt2D, QuadTree, Ring};
+use std::collections::BTreeMap;
+
+// Drop GeoJSON files here to view them on any map.
+const OVERLAY_DIR: &str = "../data/player/overlays/";
+
+const RADIUS: Distance = Distance::const_meters(5.0);
+const THICKNESS: Distance = Distance::const_meters(2.0);
+
+// Shows points, lines, and polygons from some external dataset, like collision reports or bike
+// counts. Hovering on something shows its properties. Features can be styled with the
+// "marker-color", "stroke", and "fill" properties from
+// https://github.com/mapbox/simplestyle-spec.
+pub struct Overlay {
+    composite: Composite,
+    objects: Vec<Object>,
+    quadtree: QuadTree<usize>,
+    draw: Drawable,
+    hovering: Option<usize>,
+}
+
+struct Object {
+    polygon: Polygon,
+    attribs: BTreeMap<String, String>,
+}
+
+impl Layer for Overlay {
+    fn name(&self) -> Option<&'static str> {
+        Some("external data")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        _: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                for idx in self
+                    .quadtree
+                    .query(&Circle::new(pt, Distance::meters(3.0)).get_bounds())
+                {
+                    if self.objects[*idx].polygon.contains_pt(pt) {
+                        self.hovering = Some(*idx);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        g.redraw(&self.draw);
+
+        if let Some(idx) = self.hovering {
+            let obj = &self.objects[idx];
+            g.draw_polygon(app.cs.hovering, &obj.polygon);
+            let mut txt = Text::new();
+            for (k, v) in &obj.attribs {
+                txt.add(Line(format!("{} = {}", k, v)));
+            }
+            if !txt.is_empty() {
+                g.draw_mouse_tooltip(txt);
+            }
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl Overlay {
+    pub fn pick_file() -> Box<dyn State> {
+        WizardState::new(Box::new(|wiz, ctx, app| {
+            let path = wiz
+                .wrap(ctx)
+                .choose_string("Show which GeoJSON file?", || {
+                    abstutil::list_dir(std::path::Path::new(OVERLAY_DIR))
+                        .into_iter()
+                        .filter(|x| x.ends_with(".geojson") || x.ends_with(".json"))
+                        .collect()
+                })?;
+            match Overlay::new(ctx, app, &path) {
+                Ok(layer) => {
+                    set_layer(ctx, app, Some(Box::new(layer)));
+                    Some(Transition::Pop)
+                }
+                Err(err) => Some(Transition::Replace(msg(
+                    "Error",
+                    vec![format!("Couldn't load {}: {}", path, err)],
+                ))),
+            }
+        }))
+    }
+
+    fn new(ctx: &mut EventCtx, app: &App, path: &str) -> Result<Overlay, std::io::Error> {
+        let raw_shapes = ctx.loading_screen("load GeoJSON", |_, mut timer| {
+            kml::load_geojson(path, app.primary.map.get_gps_bounds(), &mut timer)
+        })?;
+        let bounds = app.primary.map.get_gps_bounds();
+
+        let mut batch = GeomBatch::new();
+        let mut objects = Vec::new();
+        let mut quadtree = QuadTree::new(app.primary.map.get_bounds());
+        for shape in raw_shapes.shapes {
+            let pts: Vec<Pt2D> = shape
+                .points
+                .into_iter()
+                .map(|gps| Pt2D::forcibly_from_gps(gps, bounds))
+                .collect();
+            let pts = Pt2D::approx_dedupe(pts, Distance::meters(0.1));
+            let attribs = shape.attributes;
+
+            let polygon = if pts.len() == 1 {
+                let color = style(&attribs, "marker-color").unwrap_or(Color::RED);
+                let circle = Circle::new(pts[0], RADIUS).to_polygon();
+                batch.push(color.alpha(0.8), circle.clone());
+                circle
+            } else if pts.len() >= 4 && pts[0] == *pts.last().unwrap() {
+                let fill = style(&attribs, "fill").unwrap_or(Color::RED);
+                let stroke = style(&attribs, "stroke").unwrap_or(Color::RED);
+                let polygon = Polygon::new(&pts);
+                batch.push(fill.alpha(0.4), polygon.clone());
+                // Self-intersecting rings still get filled, just not outlined
+                if let Some(ring) = Ring::maybe_new(pts) {
+                    batch.push(stroke.alpha(0.8), ring.make_polygons(THICKNESS));
+                }
+                polygon
+            } else if let Some(pl) = PolyLine::maybe_new(pts) {
+                let color = style(&attribs, "stroke").unwrap_or(Color::RED);
+                let polygon = pl.make_polygons(THICKNESS);
+                batch.push(color.alpha(0.8), polygon.clone());
+                polygon
+            } else {
+                continue;
+            };
+
+            quadtree.insert_with_bounds(objects.len(), polygon.get_bounds());
+            objects.push(Object { polygon, attribs });
+        }
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Line(abstutil::basename(path)).small_heading().draw(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                format!("{} objects", prettyprint_usize(objects.len())).draw_text(ctx),
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Ok(Overlay {
+            composite,
+            objects,
+            quadtree,
+            draw: ctx.upload(batch),
+            hovering: None,
+        })
+    }
+}
+
+// Accepts "#rrggbb" and "#rgb"
+fn style(attribs: &BTreeMap<String, String>, key: &str) -> Option<Color> {
+    let raw = attribs.get(key)?;
+    if !raw.starts_with('#') {
+        return None;
+    }
+    let hex = &raw[1..];
+    let hex = match hex.len() {
+        6 => hex.to_string(),
+        3 => hex.chars().flat_map(|c| vec![c, c]).collect(),
+        _ => {
+            return None;
+        }
+    };
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(Color::hex(&format!("#{}", hex)))
+}
@@ -3,12 +3,13 @@ use crate::common::{ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 use crate::layer::{Layer, LayerOutcome};
 use abstutil::Counter;
 use ezgui::{
-    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    hotkey, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Duration, Polygon, Time};
-use map_model::{IntersectionID, Map, Traversable};
+use map_model::{IntersectionID, LaneID, Map, Traversable};
 use maplit::btreeset;
+use sim::ConflictType;
 use std::collections::BTreeSet;
 
 pub struct Backpressure {
@@ -251,7 +252,7 @@ impl Throughput {
 
         let mut colorer = ColorNetwork::new(app);
 
-        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+        let scale = DivergingScale::new(app.cs.better, Color::WHITE, app.cs.worse)
             .range(0.0, 2.0)
             .ignore(0.7, 1.3);
 
@@ -416,8 +417,8 @@ impl Delay {
     // TODO Needs work.
     fn compare_delay(ctx: &mut EventCtx, app: &App) -> Delay {
         let mut colorer = ColorNetwork::new(app);
-        let red = Color::hex("#A32015");
-        let green = Color::hex("#5D9630");
+        let red = app.cs.worse;
+        let green = app.cs.better;
 
         let results = app
             .primary
@@ -523,15 +524,18 @@ impl TrafficJams {
             app.primary.sim.delayed_intersections(Duration::minutes(5)),
         ) {
             cnt += 1;
-            unzoomed.push(Color::RED, boundary.to_outline(Distance::meters(5.0)));
-            unzoomed.push(Color::RED.alpha(0.5), boundary.clone());
+            unzoomed.push(
+                app.cs.traffic_jam,
+                boundary.to_outline(Distance::meters(5.0)),
+            );
+            unzoomed.push(app.cs.traffic_jam.alpha(0.5), boundary.clone());
             unzoomed.push(Color::WHITE, epicenter.clone());
 
             zoomed.push(
-                Color::RED.alpha(0.4),
+                app.cs.traffic_jam.alpha(0.4),
                 boundary.to_outline(Distance::meters(5.0)),
             );
-            zoomed.push(Color::RED.alpha(0.3), boundary);
+            zoomed.push(app.cs.traffic_jam.alpha(0.3), boundary);
             zoomed.push(Color::WHITE.alpha(0.4), epicenter);
         }
 
@@ -567,6 +571,231 @@ impl TrafficJams {
     }
 }
 
+// Surrogate safety measures counted at each intersection so far
+pub struct Conflicts {
+    time: Time,
+    filter: Option<ConflictType>,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Conflicts {
+    fn name(&self) -> Option<&'static str> {
+        Some("conflicts")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Conflicts::new(ctx, app, self.filter);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let new_filter = self.composite.dropdown_value("filter");
+                if new_filter != self.filter {
+                    *self = Conflicts::new(ctx, app, new_filter);
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Conflicts {
+    pub fn new(ctx: &mut EventCtx, app: &App, filter: Option<ConflictType>) -> Conflicts {
+        let counts = app
+            .primary
+            .sim
+            .get_analytics()
+            .conflicts_per_intersection(app.primary.sim.time(), filter);
+        let total = counts.sum();
+
+        let mut choices = vec![Choice::new("all conflicts", None)];
+        for c in ConflictType::all() {
+            choices.push(Choice::new(c.describe(), Some(c)));
+        }
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "Conflicts".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from(
+                    Line("Near misses that often come before crashes, counted since midnight")
+                        .secondary(),
+                )
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+                Widget::dropdown(ctx, "filter", filter, choices).margin_below(5),
+                format!("{} total", abstutil::prettyprint_usize(total)).draw_text(ctx),
+                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["fewest", "most"]),
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        let mut colorer = ColorNetwork::new(app);
+        colorer.ranked_intersections(counts, &app.cs.good_to_bad_red);
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        Conflicts {
+            time: app.primary.sim.time(),
+            filter,
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+}
+
+// Colors the end of every lane where vehicles are stopped, as far back as the queue reaches.
+pub struct QueueLengths {
+    time: Time,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+// Queues this long or longer get the worst color
+const MAX_QUEUE: usize = 10;
+
+impl Layer for QueueLengths {
+    fn name(&self) -> Option<&'static str> {
+        Some("queues")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = QueueLengths::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.composite)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl QueueLengths {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> QueueLengths {
+        let map = &app.primary.map;
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        let mut total = 0;
+        let mut worst: Option<(LaneID, usize)> = None;
+        for (l, (count, length)) in app.primary.sim.get_queue_lengths() {
+            total += count;
+            if worst.map(|(_, n)| count > n).unwrap_or(true) {
+                worst = Some((l, count));
+            }
+
+            let lane = map.get_l(l);
+            let pl = &lane.lane_center_pts;
+            let length = length.min(pl.length());
+            if length <= Distance::ZERO {
+                continue;
+            }
+            let slice = pl.exact_slice(pl.length() - length, pl.length());
+            let color = app
+                .cs
+                .good_to_bad_red
+                .eval((count.min(MAX_QUEUE) as f64) / (MAX_QUEUE as f64));
+            zoomed.push(color.alpha(0.7), slice.make_polygons(lane.width));
+            // Exaggerate the width, so short queues still show up zoomed out
+            unzoomed.push(color, slice.make_polygons(3.0 * lane.width));
+        }
+
+        let mut txt = Text::from(Line(format!(
+            "{} vehicles queued",
+            abstutil::prettyprint_usize(total)
+        )));
+        if let Some((l, count)) = worst {
+            txt.add(Line(format!(
+                "Longest: {} vehicles on {}",
+                count,
+                map.get_parent(l).get_name()
+            )));
+        }
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "Queues".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from(
+                    Line("Vehicles stopped at the end of each lane, waiting to go").secondary(),
+                )
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+                txt.draw(ctx),
+                ColorLegend::gradient(
+                    ctx,
+                    &app.cs.good_to_bad_red,
+                    vec!["1".to_string(), format!("{}+ vehicles", MAX_QUEUE)],
+                ),
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        QueueLengths {
+            time: app.primary.sim.time(),
+            unzoomed: ctx.upload(unzoomed),
+            zoomed: ctx.upload(zoomed),
+            composite,
+        }
+    }
+}
+
 struct Jam {
     epicenter: IntersectionID,
     members: BTreeSet<IntersectionID>,
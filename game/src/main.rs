@@ -2,6 +2,7 @@ mod app;
 mod challenges;
 mod colors;
 mod common;
+mod crash;
 mod cutscene;
 mod debug;
 mod devtools;
@@ -9,6 +10,7 @@ mod edit;
 mod game;
 mod helpers;
 mod info;
+mod language;
 mod layer;
 mod managed;
 mod options;
@@ -18,10 +20,15 @@ mod sandbox;
 
 use crate::app::Flags;
 use abstutil::CmdArgs;
-use sim::SimFlags;
+use sim::{BusService, SimFlags};
+use std::collections::BTreeMap;
 
 fn main() {
-    let mut args = CmdArgs::new();
+    // Anything not specified on the command line comes from the config file. The settings panel
+    // writes to the same file.
+    let mut args = CmdArgs::with_config_file(&abstutil::path_config());
+    // Like "info,sim::mechanics=debug". Can also be changed from debug mode.
+    abstutil::setup_logger(args.optional("--log"));
 
     if args.enabled("--prebake") {
         challenges::prebake_all();
@@ -32,32 +39,12 @@ fn main() {
         sim_flags: SimFlags::from_args(&mut args),
         draw_lane_markings: !args.enabled("--dont_draw_lane_markings"),
         num_agents: args.optional_parse("--num_agents", |s| s.parse()),
+        // Like "44=10m/6" to run 6 buses on route 44, 10 minutes apart
+        bus_service: args
+            .optional_parse("--bus_service", BusService::parse_list)
+            .unwrap_or_else(BTreeMap::new),
     };
-    let mut opts = options::Options::default();
-    opts.dev = args.enabled("--dev");
-    if args.enabled("--lowzoom") {
-        opts.min_zoom_for_detail = 1.0;
-    }
-
-    if let Some(x) = args.optional("--color_scheme") {
-        let mut ok = false;
-        let mut options = Vec::new();
-        for c in colors::ColorSchemeChoice::choices() {
-            options.push(c.label.clone());
-            if c.label == x {
-                opts.color_scheme = c.data;
-                ok = true;
-                break;
-            }
-        }
-        if !ok {
-            panic!(
-                "Invalid --color_scheme={}. Choices: {}",
-                x,
-                options.join(", ")
-            );
-        }
-    }
+    let opts = options::Options::from_args(&mut args);
     let mut settings = ezgui::Settings::new("A/B Street", "../data/system/fonts");
     settings.window_icon("../data/system/assets/pregame/icon.png");
     if args.enabled("--enable_profiler") {
@@ -72,6 +59,21 @@ fn main() {
     if let Some(s) = args.optional_parse("--scale_factor", |s| s.parse::<f64>()) {
         settings.scale_factor(s);
     }
+    // Like "Q:A,W:Z" to make the physical Q key act like A. Names are from ezgui::Key.
+    if let Some(x) = args.optional("--remap_keys") {
+        for pair in x.split(',') {
+            let keys: Vec<&str> = pair.split(':').collect();
+            match (
+                keys.get(0).map(|k| k.parse()),
+                keys.get(1).map(|k| k.parse()),
+            ) {
+                (Some(Ok(from)), Some(Ok(to))) if keys.len() == 2 => {
+                    settings.remap_key(from, to);
+                }
+                _ => panic!("Bad --remap_keys entry {}; should be like Q:A", pair),
+            }
+        }
+    }
 
     let mut mode = None;
     if let Some(x) = args.optional("--challenge") {
@@ -1,10 +1,13 @@
 use crate::app::App;
 use crate::colors::ColorSchemeChoice;
 use crate::game::{State, Transition};
+use crate::language;
+use abstutil::{CmdArgs, ConfigValue};
 use ezgui::{
     hotkey, Btn, Checkbox, Choice, Composite, EventCtx, GfxCtx, Key, Line, Outcome, TextExt, Widget,
 };
-use geom::Duration;
+use geom::{Duration, Units};
+use log::info;
 
 // TODO SimOptions stuff too
 #[derive(Clone)]
@@ -16,12 +19,63 @@ pub struct Options {
     pub color_scheme: ColorSchemeChoice,
     pub min_zoom_for_detail: f64,
     pub large_unzoomed_agents: bool,
+    pub units: Units,
+    // The file name of a language pack, or "en"
+    pub language: String,
 
     pub time_increment: Duration,
     pub resume_after_edit: bool,
+
+    // A JSON list of maps that can be downloaded
+    pub map_index_url: Option<String>,
 }
 
 impl Options {
+    pub fn from_args(args: &mut CmdArgs) -> Options {
+        let mut opts = Options::default();
+        opts.dev = args.enabled("--dev");
+        opts.map_index_url = args.optional("--map_index");
+        if args.enabled("--lowzoom") {
+            opts.min_zoom_for_detail = 1.0;
+        }
+        if let Some(z) = args.optional_parse("--min_zoom", |s| s.parse::<f64>()) {
+            opts.min_zoom_for_detail = z;
+        }
+
+        if let Some(x) = args.optional("--units") {
+            opts.units = match x.as_ref() {
+                "metric" => Units::Metric,
+                "imperial" => Units::Imperial,
+                _ => panic!("Invalid --units={}. Choices: metric, imperial", x),
+            };
+        }
+
+        if let Some(x) = args.optional("--language") {
+            opts.language = x;
+        }
+
+        if let Some(x) = args.optional("--color_scheme") {
+            let mut ok = false;
+            let mut options = Vec::new();
+            for c in ColorSchemeChoice::choices() {
+                options.push(c.label.clone());
+                if c.label == x {
+                    opts.color_scheme = c.data;
+                    ok = true;
+                    break;
+                }
+            }
+            if !ok {
+                panic!(
+                    "Invalid --color_scheme={}. Choices: {}",
+                    x,
+                    options.join(", ")
+                );
+            }
+        }
+        opts
+    }
+
     pub fn default() -> Options {
         Options {
             dev: false,
@@ -31,9 +85,13 @@ impl Options {
             color_scheme: ColorSchemeChoice::Standard,
             min_zoom_for_detail: 4.0,
             large_unzoomed_agents: false,
+            units: Units::Imperial,
+            language: language::ENGLISH.to_string(),
 
             time_increment: Duration::minutes(10),
             resume_after_edit: true,
+
+            map_index_url: None,
         }
     }
 }
@@ -45,6 +103,8 @@ pub enum TrafficSignalStyle {
     Sidewalks,
     Icons,
     IndividualTurnArrows,
+    // Red/yellow/green lights per approach and walk signals
+    Realistic,
 }
 
 pub struct OptionsPanel {
@@ -53,28 +113,39 @@ pub struct OptionsPanel {
 
 impl OptionsPanel {
     pub fn new(ctx: &mut EventCtx, app: &App) -> OptionsPanel {
+        let languages = language::all_languages();
+        // The configured language pack might've been removed since
+        let current_language = if languages.iter().any(|(file, _)| *file == app.opts.language) {
+            app.opts.language.clone()
+        } else {
+            language::ENGLISH.to_string()
+        };
+
         OptionsPanel {
             composite: Composite::new(
                 Widget::col(vec![
                     Widget::row(vec![
-                        Line("Settings").small_heading().draw(ctx),
+                        Line("Settings").small_heading().translatable().draw(ctx),
                         Btn::plaintext("X")
                             .build(ctx, "close", hotkey(Key::Escape))
                             .align_right(),
                     ])
                     .margin_below(10),
-                    Checkbox::text(ctx, "Enable developer mode", None, app.opts.dev)
+                    Checkbox::translatable_text(ctx, "Enable developer mode", None, app.opts.dev)
+                        .margin_below(10),
+                    Line("Camera controls")
+                        .translatable()
+                        .draw(ctx)
                         .margin_below(10),
-                    "Camera controls".draw_text(ctx).margin_below(10),
                     Widget::col(vec![
-                        Checkbox::text(
+                        Checkbox::translatable_text(
                             ctx,
                             "Invert direction of vertical scrolling",
                             None,
                             ctx.canvas.invert_scroll,
                         )
                         .margin_below(10),
-                        Checkbox::text(
+                        Checkbox::translatable_text(
                             ctx,
                             "Pan map when cursor is at edge of screen",
                             None,
@@ -82,14 +153,14 @@ impl OptionsPanel {
                         )
                         .named("autopan")
                         .margin_below(10),
-                        Checkbox::text(
+                        Checkbox::translatable_text(
                             ctx,
                             "Use touchpad to pan and hold Control to zoom",
                             None,
                             ctx.canvas.touchpad_to_move,
                         )
                         .margin_below(10),
-                        Checkbox::text(
+                        Checkbox::translatable_text(
                             ctx,
                             "Use arrow keys to pan and Q/W to zoom",
                             None,
@@ -99,12 +170,20 @@ impl OptionsPanel {
                     .bg(app.cs.section_bg)
                     .padding(8)
                     .margin_below(10),
-                    "Appearance".draw_text(ctx).margin_below(10),
+                    Line("Appearance").translatable().draw(ctx).margin_below(10),
                     Widget::col(vec![
-                        Checkbox::text(ctx, "Draw road names", None, app.opts.label_roads)
-                            .margin_below(10),
+                        Checkbox::translatable_text(
+                            ctx,
+                            "Draw road names",
+                            None,
+                            app.opts.label_roads,
+                        )
+                        .margin_below(10),
                         Widget::row(vec![
-                            "Traffic signal rendering:".draw_text(ctx).margin_right(15),
+                            Line("Traffic signal rendering:")
+                                .translatable()
+                                .draw(ctx)
+                                .margin_right(15),
                             Widget::dropdown(
                                 ctx,
                                 "Traffic signal rendering",
@@ -128,6 +207,10 @@ impl OptionsPanel {
                                         "icons for movements (like the editor UI)",
                                         TrafficSignalStyle::Icons,
                                     ),
+                                    Choice::new(
+                                        "signal lights for each approach, like drivers see",
+                                        TrafficSignalStyle::Realistic,
+                                    ),
                                     Choice::new(
                                         "arrows showing individual turns (to debug)",
                                         TrafficSignalStyle::IndividualTurnArrows,
@@ -137,7 +220,10 @@ impl OptionsPanel {
                         ])
                         .margin_below(10),
                         Widget::row(vec![
-                            "Color scheme:".draw_text(ctx).margin_right(5),
+                            Line("Color scheme:")
+                                .translatable()
+                                .draw(ctx)
+                                .margin_right(5),
                             Widget::dropdown(
                                 ctx,
                                 "Color scheme",
@@ -147,10 +233,10 @@ impl OptionsPanel {
                         ])
                         .margin_below(10),
                         Widget::row(vec![
-                            format!(
+                            ctx.translate(
                                 "Scale factor for text / UI elements (your monitor is {}):",
-                                ctx.monitor_scale_factor()
                             )
+                            .replace("{}", &ctx.monitor_scale_factor().to_string())
                             .draw_text(ctx)
                             .margin_right(15),
                             Widget::dropdown(
@@ -167,8 +253,9 @@ impl OptionsPanel {
                         ])
                         .margin_below(10),
                         Widget::row(vec![
-                            "Camera zoom to switch to unzoomed view"
-                                .draw_text(ctx)
+                            Line("Camera zoom to switch to unzoomed view")
+                                .translatable()
+                                .draw(ctx)
                                 .margin_right(15),
                             Widget::dropdown(
                                 ctx,
@@ -185,17 +272,44 @@ impl OptionsPanel {
                             ),
                         ])
                         .margin_below(10),
-                        Checkbox::text(
+                        Checkbox::translatable_text(
                             ctx,
                             "Draw enlarged unzoomed agents",
                             None,
                             app.opts.large_unzoomed_agents,
-                        ),
+                        )
+                        .margin_below(10),
+                        Widget::row(vec![
+                            Line("Units:").translatable().draw(ctx).margin_right(15),
+                            Widget::dropdown(
+                                ctx,
+                                "Units",
+                                app.opts.units,
+                                vec![
+                                    Choice::new("imperial (miles, feet, mph)", Units::Imperial),
+                                    Choice::new("metric (km, meters, km/h)", Units::Metric),
+                                ],
+                            ),
+                        ])
+                        .margin_below(10),
+                        Widget::row(vec![
+                            Line("Language:").translatable().draw(ctx).margin_right(15),
+                            Widget::dropdown(
+                                ctx,
+                                "Language",
+                                current_language,
+                                languages
+                                    .into_iter()
+                                    .map(|(file, name)| Choice::new(name, file))
+                                    .collect(),
+                            ),
+                        ]),
                     ])
                     .bg(app.cs.section_bg)
                     .padding(8)
                     .margin_below(10),
                     Btn::text_bg2("Apply")
+                        .translatable()
                         .build_def(ctx, hotkey(Key::Enter))
                         .centered_horiz(),
                 ])
@@ -232,7 +346,7 @@ impl State for OptionsPanel {
                     let style = self.composite.dropdown_value("Traffic signal rendering");
                     if app.opts.traffic_signal_style != style {
                         app.opts.traffic_signal_style = style;
-                        println!("Rerendering traffic signals...");
+                        info!("Rerendering traffic signals...");
                         for i in app.primary.draw_map.intersections.iter_mut() {
                             *i.draw_traffic_signal.borrow_mut() = None;
                         }
@@ -241,6 +355,8 @@ impl State for OptionsPanel {
                     let scheme = self.composite.dropdown_value("Color scheme");
                     if app.opts.color_scheme != scheme {
                         app.opts.color_scheme = scheme;
+                        // The other loaded maps were drawn with the old colors
+                        app.other_maps.clear();
                         app.switch_map(ctx, app.primary.current_flags.sim_flags.load.clone());
                     }
 
@@ -252,6 +368,42 @@ impl State for OptionsPanel {
                     app.opts.min_zoom_for_detail = self.composite.dropdown_value("min zoom");
                     app.opts.large_unzoomed_agents =
                         self.composite.is_checked("Draw enlarged unzoomed agents");
+                    app.opts.units = self.composite.dropdown_value("Units");
+                    let lang: String = self.composite.dropdown_value("Language");
+                    if app.opts.language != lang {
+                        language::set_language(ctx, &lang);
+                        app.opts.language = lang;
+                    }
+
+                    // Remember the settings that have command-line flags for next time
+                    let scheme_name = ColorSchemeChoice::choices()
+                        .into_iter()
+                        .find(|c| c.data == app.opts.color_scheme)
+                        .unwrap()
+                        .label;
+                    abstutil::update_config_file(
+                        &abstutil::path_config(),
+                        vec![
+                            ("dev", ConfigValue::Flag(app.opts.dev)),
+                            ("color_scheme", ConfigValue::Value(scheme_name)),
+                            ("scale_factor", ConfigValue::Value(factor.to_string())),
+                            (
+                                "min_zoom",
+                                ConfigValue::Value(app.opts.min_zoom_for_detail.to_string()),
+                            ),
+                            ("language", ConfigValue::Value(app.opts.language.clone())),
+                            (
+                                "units",
+                                ConfigValue::Value(
+                                    match app.opts.units {
+                                        Units::Metric => "metric",
+                                        Units::Imperial => "imperial",
+                                    }
+                                    .to_string(),
+                                ),
+                            ),
+                        ],
+                    );
 
                     return Transition::Pop;
                 }
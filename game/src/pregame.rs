@@ -1,10 +1,11 @@
 use crate::app::App;
 use crate::challenges::challenges_picker;
+use crate::common::MapChooser;
 use crate::devtools::DevToolsMode;
 use crate::edit::apply_map_edits;
 use crate::game::{msg, DrawBaselayer, State, Transition};
 use crate::sandbox::gameplay::Tutorial;
-use crate::sandbox::{GameplayMode, SandboxMode};
+use crate::sandbox::{GameplayMode, LastSession, SandboxMode};
 use ezgui::{
     hotkey, hotkeys, Btn, Color, Composite, EventCtx, EventLoopMode, GfxCtx, Key, Line, Outcome,
     RewriteColor, Text, Widget,
@@ -32,6 +33,7 @@ impl TitleScreen {
                     // TODO that nicer font
                     // TODO Any key
                     Btn::text_bg2("PLAY")
+                        .translatable()
                         .build(ctx, "start game", hotkeys(vec![Key::Space, Key::Enter]))
                         .margin(5),
                 ])
@@ -89,8 +91,8 @@ impl MainMenu {
                     RewriteColor::Change(Color::WHITE, app.cs.hovering),
                 )
                 .tooltip({
-                    let mut txt = Text::tooltip(ctx, hotkey(Key::T), "Tutorial");
-                    txt.add(Line("Learn how to play the game").small());
+                    let mut txt = Text::tooltip(ctx, hotkey(Key::T), "Tutorial").translatable();
+                    txt.add(Line("Learn how to play the game").small().translatable());
                     txt
                 })
                 .build(ctx, "Tutorial", hotkey(Key::T)),
@@ -99,8 +101,12 @@ impl MainMenu {
                     RewriteColor::Change(Color::WHITE, app.cs.hovering),
                 )
                 .tooltip({
-                    let mut txt = Text::tooltip(ctx, hotkey(Key::S), "Sandbox");
-                    txt.add(Line("No goals, try out any idea here").small());
+                    let mut txt = Text::tooltip(ctx, hotkey(Key::S), "Sandbox").translatable();
+                    txt.add(
+                        Line("No goals, try out any idea here")
+                            .small()
+                            .translatable(),
+                    );
                     txt
                 })
                 .build(ctx, "Sandbox mode", hotkey(Key::S)),
@@ -109,17 +115,38 @@ impl MainMenu {
                     RewriteColor::Change(Color::WHITE, app.cs.hovering),
                 )
                 .tooltip({
-                    let mut txt = Text::tooltip(ctx, hotkey(Key::C), "Challenges");
-                    txt.add(Line("Fix specific problems").small());
+                    let mut txt = Text::tooltip(ctx, hotkey(Key::C), "Challenges").translatable();
+                    txt.add(Line("Fix specific problems").small().translatable());
                     txt
                 })
                 .build(ctx, "Challenges", hotkey(Key::C)),
             ])
             .centered(),
+            if app.session.recovery.is_some() {
+                Btn::text_bg2("Recover unsaved work")
+                    .translatable()
+                    .tooltip({
+                        let mut txt =
+                            Text::tooltip(ctx, None, "Recover unsaved work").translatable();
+                        txt.add(Line("The game didn't exit cleanly last time").small());
+                        txt
+                    })
+                    .build_def(ctx, None)
+                    .centered_horiz()
+            } else if abstutil::file_exists(abstutil::path_last_session()) {
+                Btn::text_bg2("Resume last session")
+                    .translatable()
+                    .build_def(ctx, hotkey(Key::R))
+                    .centered_horiz()
+            } else {
+                Widget::nothing()
+            },
             Widget::row(vec![
                 Btn::text_bg2("Community Proposals")
+                    .translatable()
                     .tooltip({
-                        let mut txt = Text::tooltip(ctx, hotkey(Key::P), "Community Proposals");
+                        let mut txt = Text::tooltip(ctx, hotkey(Key::P), "Community Proposals")
+                            .translatable();
                         txt.add(Line("See existing ideas for improving traffic").small());
                         txt
                     })
@@ -140,8 +167,13 @@ impl MainMenu {
             .centered(),
             Widget::col(vec![
                 Widget::row(vec![
-                    Btn::text_bg2("About").build_def(ctx, None).margin_right(20),
-                    Btn::text_bg2("Feedback").build_def(ctx, None),
+                    Btn::text_bg2("About")
+                        .translatable()
+                        .build_def(ctx, None)
+                        .margin_right(20),
+                    Btn::text_bg2("Feedback")
+                        .translatable()
+                        .build_def(ctx, None),
                 ]),
                 built_info::time().draw(ctx),
             ])
@@ -168,31 +200,45 @@ impl State for MainMenu {
                     return Tutorial::start(ctx, app);
                 }
                 "Sandbox mode" => {
-                    // We might've left with a synthetic map loaded.
-                    let map_path = if abstutil::list_all_objects(abstutil::path_all_maps())
-                        .contains(app.primary.map.get_name())
-                    {
-                        abstutil::path_map(app.primary.map.get_name())
-                    } else {
-                        abstutil::path_map("montlake")
-                    };
-                    let scenario = if abstutil::file_exists(abstutil::path_scenario(
-                        app.primary.map.get_name(),
-                        "weekday",
-                    )) {
-                        "weekday"
-                    } else {
-                        "random"
-                    };
-                    return Transition::Push(Box::new(SandboxMode::new(
+                    return Transition::Push(MapChooser::new(
                         ctx,
                         app,
-                        GameplayMode::PlayScenario(map_path, scenario.to_string()),
-                    )));
+                        Box::new(|ctx, app| {
+                            let name = app.primary.map.get_name().to_string();
+                            let scenario =
+                                if abstutil::file_exists(abstutil::path_scenario(&name, "weekday"))
+                                {
+                                    "weekday"
+                                } else {
+                                    "synthetic"
+                                };
+                            Transition::Replace(Box::new(SandboxMode::new(
+                                ctx,
+                                app,
+                                GameplayMode::PlayScenario(
+                                    abstutil::path_map(&name),
+                                    scenario.to_string(),
+                                ),
+                            )))
+                        }),
+                    ));
                 }
                 "Challenges" => {
                     return Transition::Push(challenges_picker(ctx, app));
                 }
+                "Resume last session" => {
+                    if let Some(session) = LastSession::load() {
+                        return session.resume(ctx, app);
+                    }
+                    return Transition::Push(msg(
+                        "Can't resume last session",
+                        vec!["The saved session is missing or corrupt."],
+                    ));
+                }
+                "Recover unsaved work" => {
+                    let session = app.session.recovery.take().unwrap();
+                    return session.resume(ctx, app);
+                }
                 "About" => {
                     return Transition::Push(About::new(ctx, app));
                 }
@@ -422,7 +468,8 @@ impl State for Proposals {
                     if let Some(err) = maybe_err {
                         return Transition::Push(msg("Can't load proposal", vec![err]));
                     } else {
-                        app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+                        let layer = crate::layer::map::Static::edits(ctx, app);
+                        crate::layer::set_layer(ctx, app, Some(Box::new(layer)));
                         return Transition::Push(Box::new(SandboxMode::new(
                             ctx,
                             app,
@@ -2,10 +2,10 @@ use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::render::{DrawOptions, Renderable};
-use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender};
+use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Prerender};
 use geom::{ArrowCap, Circle, Distance, Line, PolyLine, Polygon};
 use map_model::{Map, SIDEWALK_THICKNESS};
-use sim::{CarID, DrawCarInput};
+use sim::{CarID, CarStatus, DrawCarInput};
 
 pub struct DrawBike {
     pub id: CarID,
@@ -19,6 +19,7 @@ pub struct DrawBike {
 impl DrawBike {
     pub fn new(
         input: DrawCarInput,
+        step_count: usize,
         map: &Map,
         prerender: &Prerender,
         cs: &ColorScheme,
@@ -28,13 +29,51 @@ impl DrawBike {
         // TODO Share constants with DrawPedestrian
         let body_radius = SIDEWALK_THICKNESS / 4.0;
         let body_color = cs.rotating_color_agents(input.id.0);
+        let len = input.body.length();
+
+        // From above, the tires look like short dark bars at either end of a skinny frame. That
+        // silhouette is what tells bikes apart from pedestrians at a glance.
         draw_default.push(
             cs.bike_frame,
-            input.body.make_polygons(Distance::meters(0.4)),
+            input
+                .body
+                .exact_slice(0.1 * len, 0.9 * len)
+                .make_polygons(Distance::meters(0.15)),
         );
+        for (start, end) in vec![(0.0, 0.3), (0.7, 1.0)] {
+            draw_default.push(
+                Color::BLACK,
+                input
+                    .body
+                    .exact_slice(start * len, end * len)
+                    .make_polygons(Distance::meters(0.12)),
+            );
+        }
 
-        let (body_pos, facing) = input.body.dist_along(0.4 * input.body.length());
+        let (body_pos, facing) = input.body.dist_along(0.4 * len);
         let body_circle = Circle::new(body_pos, body_radius);
+
+        // Feet on the pedals, alternating as the rider pedals. Same jitter trick as pedestrians.
+        {
+            let (crank_pos, _) = input.body.dist_along(0.5 * len);
+            let pedaling = input.waiting_for_turn.is_none() && input.status == CarStatus::Moving;
+            let phase = if pedaling && (input.id.0 % 2 == 0) == (step_count % 6 < 3) {
+                0.15
+            } else if pedaling {
+                -0.15
+            } else {
+                0.0
+            };
+            for (side, offset) in vec![(90.0, phase), (-90.0, -phase)] {
+                let foot = crank_pos
+                    .project_away(len * offset, facing)
+                    .project_away(0.7 * body_radius, facing.rotate_degs(side));
+                draw_default.push(
+                    cs.ped_foot,
+                    Circle::new(foot, 0.2 * body_radius).to_polygon(),
+                );
+            }
+        }
         draw_default.push(body_color, body_circle.to_polygon());
         draw_default.push(
             cs.ped_head,
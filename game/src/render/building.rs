@@ -61,6 +61,15 @@ impl DrawBuilding {
             label: RefCell::new(None),
         }
     }
+
+    // Just the label, once it's been drawn. The building itself is part of one big Drawable.
+    pub fn gpu_bytes(&self) -> usize {
+        self.label
+            .borrow()
+            .as_ref()
+            .map(|draw| draw.size_bytes())
+            .unwrap_or(0)
+    }
 }
 
 impl Renderable for DrawBuilding {
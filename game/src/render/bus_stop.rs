@@ -52,6 +52,10 @@ impl DrawBusStop {
             draw_default: prerender.upload(batch),
         }
     }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.draw_default.size_bytes()
+    }
 }
 
 impl Renderable for DrawBusStop {
@@ -0,0 +1,119 @@
+use crate::colors::ColorScheme;
+use crate::render::lane::AlmostDrawLane;
+use abstutil::Timer;
+use ezgui::GeomBatch;
+use log::warn;
+use map_model::Map;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// Bump this whenever lane or intersection drawing changes, so stale caches are ignored
+const CACHE_VERSION: u32 = 1;
+// Each set of edits gets its own file, so only keep the most recently written ones per map
+const MAX_CACHED_PER_MAP: usize = 5;
+
+// Tessellating every lane and intersection is a big part of launching a large map, and the result
+// only depends on the map, its edits, and a few colors. So it's saved to disk, and the next launch
+// of the same map reads it back instead.
+#[derive(Serialize, Deserialize)]
+pub struct GeometryCache {
+    pub lanes: Vec<AlmostDrawLane>,
+    pub intersections: Vec<GeomBatch>,
+}
+
+impl GeometryCache {
+    pub fn load(
+        map: &Map,
+        draw_lane_markings: bool,
+        cs: &ColorScheme,
+        timer: &mut Timer,
+    ) -> Option<GeometryCache> {
+        let path = abstutil::path_geometry_cache(map.get_name(), &key(map, draw_lane_markings, cs));
+        if !abstutil::file_exists(path.clone()) {
+            return None;
+        }
+        match abstutil::maybe_read_binary::<GeometryCache>(path, timer) {
+            Ok(cache)
+                if cache.lanes.len() == map.all_lanes().len()
+                    && cache.intersections.len() == map.all_intersections().len() =>
+            {
+                Some(cache)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn save(&self, map: &Map, draw_lane_markings: bool, cs: &ColorScheme) {
+        // No filesystem on the web
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        // The cache is optional, so a read-only or full disk shouldn't stop anything
+        let path = abstutil::path_geometry_cache(map.get_name(), &key(map, draw_lane_markings, cs));
+        if let Err(err) = abstutil::maybe_write_binary(&path, self) {
+            warn!("Couldn't save the geometry cache to {}: {}", path, err);
+            return;
+        }
+        evict_old(&path);
+    }
+}
+
+// Deletes everything but the newest few files in the same directory as the one just written
+fn evict_old(just_wrote: &str) {
+    let dir = std::path::Path::new(just_wrote).parent().unwrap();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            return;
+        }
+    };
+    let mut files: Vec<(std::time::SystemTime, std::path::PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    // Newest first
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    for (_, path) in files.into_iter().skip(MAX_CACHED_PER_MAP) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn key(map: &Map, draw_lane_markings: bool, cs: &ColorScheme) -> String {
+    let mut hasher = DefaultHasher::new();
+    CACHE_VERSION.hash(&mut hasher);
+    // Re-importing the map changes its size or modification time
+    if let Ok(meta) = std::fs::metadata(abstutil::path_map(map.get_name())) {
+        meta.len().hash(&mut hasher);
+        if let Ok(modified) = meta.modified() {
+            modified.hash(&mut hasher);
+        }
+    }
+    format!("{:?}", map.get_edits().commands).hash(&mut hasher);
+    draw_lane_markings.hash(&mut hasher);
+    // Only the colors baked into lanes and intersections
+    format!(
+        "{:?}",
+        vec![
+            cs.driving_lane,
+            cs.bus_lane,
+            cs.parking_lane,
+            cs.sidewalk,
+            cs.sidewalk_lines,
+            cs.bike_lane,
+            cs.under_construction,
+            cs.light_rail_track,
+            cs.general_road_marking,
+            cs.road_center_line,
+            cs.bridge_casing,
+            cs.normal_intersection,
+            cs.stop_sign,
+            cs.stop_sign_pole,
+        ]
+    )
+    .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
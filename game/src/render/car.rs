@@ -191,7 +191,7 @@ fn thick_line_from_angle(
 }
 
 fn zoomed_color_car(input: &DrawCarInput, cs: &ColorScheme) -> Color {
-    if input.id.1 == VehicleType::Bus {
+    if input.id.1.is_transit() {
         cs.bus_body
     } else {
         match input.status {
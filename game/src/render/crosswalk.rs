@@ -0,0 +1,52 @@
+use crate::app::App;
+use crate::helpers::ID;
+use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
+use ezgui::GfxCtx;
+use geom::{Polygon, Pt2D};
+use map_model::{Crosswalk, CrosswalkID, Map};
+
+// The stripes are baked into the parent DrawIntersection. This just exists so crosswalks can be
+// selected separately.
+pub struct DrawCrosswalk {
+    pub id: CrosswalkID,
+    polygon: Polygon,
+    zorder: isize,
+}
+
+impl DrawCrosswalk {
+    pub fn new(crosswalk: &Crosswalk, zorder: isize) -> DrawCrosswalk {
+        DrawCrosswalk {
+            id: crosswalk.id,
+            polygon: crosswalk.polygon.clone(),
+            zorder,
+        }
+    }
+
+    pub fn center(&self) -> Pt2D {
+        self.polygon.center()
+    }
+}
+
+impl Renderable for DrawCrosswalk {
+    fn get_id(&self) -> ID {
+        ID::Crosswalk(self.id)
+    }
+
+    fn draw(&self, _: &mut GfxCtx, _: &App, _: &DrawOptions) {}
+
+    fn get_outline(&self, _: &Map) -> Polygon {
+        if let Some(p) = self.polygon.maybe_to_outline(OUTLINE_THICKNESS) {
+            p
+        } else {
+            self.polygon.clone()
+        }
+    }
+
+    fn contains_pt(&self, pt: Pt2D, _: &Map) -> bool {
+        self.polygon.contains_pt(pt)
+    }
+
+    fn get_zorder(&self) -> isize {
+        self.zorder
+    }
+}
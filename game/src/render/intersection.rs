@@ -3,14 +3,16 @@ use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::options::TrafficSignalStyle;
 use crate::render::{
-    draw_signal_phase, DrawOptions, Renderable, CROSSWALK_LINE_THICKNESS, OUTLINE_THICKNESS,
+    draw_signal_phase, DrawCrosswalk, DrawOptions, Renderable, CROSSWALK_LINE_THICKNESS,
+    OUTLINE_THICKNESS,
 };
 use abstutil::Timer;
 use ezgui::{Color, Drawable, FancyColor, GeomBatch, GfxCtx, Line, Prerender, RewriteColor, Text};
 use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D, Time, EPSILON_DIST};
 use map_model::raw::DrivingSide;
 use map_model::{
-    Intersection, IntersectionID, IntersectionType, Map, Road, RoadWithStopSign, Turn, TurnType,
+    ApproachControl, CrosswalkType, Intersection, IntersectionID, IntersectionType, Map, Road,
+    RoadWithStopSign, Turn, TurnType,
 };
 use std::cell::RefCell;
 
@@ -21,6 +23,7 @@ pub struct DrawIntersection {
 
     draw_default: Drawable,
     pub draw_traffic_signal: RefCell<Option<(Time, Drawable)>>,
+    pub crosswalks: Vec<DrawCrosswalk>,
 }
 
 impl DrawIntersection {
@@ -31,6 +34,21 @@ impl DrawIntersection {
         prerender: &Prerender,
         timer: &mut Timer,
     ) -> DrawIntersection {
+        DrawIntersection::from_geom(
+            i,
+            map,
+            prerender,
+            DrawIntersection::default_geom(i, map, cs, timer),
+        )
+    }
+
+    // The slow part of new(), separated so GeometryCache can skip it
+    pub fn default_geom(
+        i: &Intersection,
+        map: &Map,
+        cs: &ColorScheme,
+        timer: &mut Timer,
+    ) -> GeomBatch {
         // Order matters... main polygon first, then sidewalk corners.
         let mut default_geom = GeomBatch::new();
         default_geom.push(
@@ -62,9 +80,9 @@ impl DrawIntersection {
             }
             IntersectionType::StopSign => {
                 for ss in map.get_stop_sign(i.id).roads.values() {
-                    if ss.must_stop {
-                        if let Some((octagon, pole)) = DrawIntersection::stop_sign_geom(ss, map) {
-                            default_geom.push(cs.stop_sign, octagon);
+                    if ss.control != ApproachControl::Priority {
+                        if let Some((sign, pole)) = DrawIntersection::stop_sign_geom(ss, map) {
+                            default_geom.push(cs.stop_sign, sign);
                             default_geom.push(cs.stop_sign_pole, pole);
                         }
                     }
@@ -76,21 +94,35 @@ impl DrawIntersection {
             IntersectionType::TrafficSignal => {}
         }
 
-        let zorder = i.get_zorder(map);
-        if zorder < 0 {
+        if i.get_zorder(map) < 0 {
             default_geom = default_geom.color(RewriteColor::ChangeAlpha(0.5));
         }
+        default_geom
+    }
 
+    pub fn from_geom(
+        i: &Intersection,
+        map: &Map,
+        prerender: &Prerender,
+        default_geom: GeomBatch,
+    ) -> DrawIntersection {
+        let zorder = i.get_zorder(map);
         DrawIntersection {
             id: i.id,
             intersection_type: i.intersection_type,
             zorder,
             draw_default: prerender.upload(default_geom),
             draw_traffic_signal: RefCell::new(None),
+            crosswalks: map
+                .get_crosswalks(i.id)
+                .iter()
+                .map(|c| DrawCrosswalk::new(c, zorder))
+                .collect(),
         }
     }
 
-    // Returns the (octagon, pole) if there's room to draw it.
+    // Returns the (sign, pole) if there's room to draw it. Yield signs are triangles; everything
+    // else gets an octagon.
     pub fn stop_sign_geom(ss: &RoadWithStopSign, map: &Map) -> Option<(Polygon, Polygon)> {
         let trim_back = Distance::meters(0.1);
         let rightmost = map.get_l(ss.rightmost_lane);
@@ -107,7 +139,11 @@ impl DrawIntersection {
             rightmost.width,
         );
 
-        let octagon = make_octagon(last_line.pt2(), Distance::meters(1.0), last_line.angle());
+        let sign = if ss.control == ApproachControl::Yield {
+            make_triangle(last_line.pt2(), Distance::meters(1.0), last_line.angle())
+        } else {
+            make_octagon(last_line.pt2(), Distance::meters(1.0), last_line.angle())
+        };
         let pole = Line::new(
             last_line
                 .pt2()
@@ -118,7 +154,18 @@ impl DrawIntersection {
                 .project_away(Distance::meters(0.9), last_line.angle().opposite()),
         )
         .make_polygons(Distance::meters(0.3));
-        Some((octagon, pole))
+        Some((sign, pole))
+    }
+
+    // Including the traffic signal, if it's been drawn
+    pub fn gpu_bytes(&self) -> usize {
+        self.draw_default.size_bytes()
+            + self
+                .draw_traffic_signal
+                .borrow()
+                .as_ref()
+                .map(|(_, draw)| draw.size_bytes())
+                .unwrap_or(0)
     }
 }
 
@@ -152,7 +199,9 @@ impl Renderable for DrawIntersection {
                     app,
                     app.opts.traffic_signal_style.clone(),
                 );
-                if app.opts.traffic_signal_style != TrafficSignalStyle::BAP {
+                if app.opts.traffic_signal_style != TrafficSignalStyle::BAP
+                    && app.opts.traffic_signal_style != TrafficSignalStyle::Realistic
+                {
                     batch.append(
                         Text::from(Line(format!("{}", idx + 1)))
                             .render_to_batch(g.prerender)
@@ -317,21 +366,41 @@ fn make_octagon(center: Pt2D, radius: Distance, facing: Angle) -> Polygon {
     )
 }
 
+// Points back at the drivers who have to yield
+fn make_triangle(center: Pt2D, radius: Distance, facing: Angle) -> Polygon {
+    Polygon::new(
+        &(0..3)
+            .map(|i| center.project_away(radius, facing.rotate_degs(180.0 + f64::from(i * 120))))
+            .collect(),
+    )
+}
+
 pub fn make_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
     if make_rainbow_crosswalk(batch, turn, map) {
         return;
     }
-
     let width = map.get_l(turn.id.src).width;
-    // Start at least width out to not hit sidewalk corners. Also account for the thickness of the
-    // crosswalk line itself. Center the lines inside these two boundaries.
-    let boundary = width;
-    let tile_every = width * 0.6;
     let line = {
         // The middle line in the crosswalk geometry is the main crossing line.
         let pts = turn.geom.points();
         Line::new(pts[1], pts[2])
     };
+    match map.get_parent(turn.id.src).crosswalk_type(turn.id.parent) {
+        // People still cross here, there's just nothing painted.
+        CrosswalkType::Unmarked => {
+            return;
+        }
+        CrosswalkType::Marked => {}
+        // The whole crossing is at sidewalk height
+        CrosswalkType::Raised => {
+            batch.push(cs.sidewalk, line.make_polygons(width * 1.5));
+        }
+    }
+
+    // Start at least width out to not hit sidewalk corners. Also account for the thickness of the
+    // crosswalk line itself. Center the lines inside these two boundaries.
+    let boundary = width;
+    let tile_every = width * 0.6;
 
     let available_length = line.length() - (boundary * 2.0);
     if available_length > Distance::ZERO {
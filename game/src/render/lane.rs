@@ -1,14 +1,16 @@
 use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
-use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
+use crate::render::{DrawOptions, Renderable, BRIDGE_CASING_THICKNESS, OUTLINE_THICKNESS};
 use abstutil::Timer;
 use ezgui::{Drawable, FancyColor, GeomBatch, GfxCtx, Prerender, RewriteColor};
 use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D};
 use map_model::{Lane, LaneID, LaneType, Map, Road, TurnType, PARKING_SPOT_LENGTH};
+use serde::{Deserialize, Serialize};
 
 // Split into two phases like this, because AlmostDrawLane can be created in parallel, but GPU
-// upload has to be serial.
+// upload has to be serial. It's also what GeometryCache saves.
+#[derive(Serialize, Deserialize)]
 pub struct AlmostDrawLane {
     pub id: LaneID,
     polygon: Polygon,
@@ -95,6 +97,7 @@ impl DrawLane {
                 LaneType::Biking => cs.bike_lane,
                 LaneType::SharedLeftTurn => cs.driving_lane,
                 LaneType::Construction => cs.under_construction,
+                LaneType::LightRail => cs.light_rail_track,
             },
             polygon.clone(),
         );
@@ -109,7 +112,7 @@ impl DrawLane {
                 LaneType::Driving | LaneType::Bus => {
                     draw.extend(
                         cs.general_road_marking,
-                        calculate_driving_lines(map, lane, road, timer),
+                        calculate_lane_dividers(map, lane, road, timer),
                     );
                     draw.extend(
                         cs.general_road_marking,
@@ -120,7 +123,13 @@ impl DrawLane {
                         calculate_one_way_markings(lane, road),
                     );
                 }
-                LaneType::Biking => {}
+                LaneType::Biking => {
+                    draw.extend(
+                        cs.general_road_marking,
+                        calculate_lane_dividers(map, lane, road, timer),
+                    );
+                    draw.extend(cs.general_road_marking, calculate_bike_arrows(lane));
+                }
                 LaneType::SharedLeftTurn => {
                     draw.push(
                         cs.road_center_line,
@@ -141,9 +150,26 @@ impl DrawLane {
                     // TODO Can't put this in ColorScheme without switching to FancyColor
                     draw.fancy_push(FancyColor::Hatching, polygon.clone());
                 }
+                LaneType::LightRail => {
+                    draw.extend(cs.general_road_marking, calculate_rails(lane, timer));
+                }
             };
         }
 
+        // The outermost lanes of a bridge get an edge, so it's clear it passes over the roads
+        // beneath
+        if road.zorder > 0
+            && (road.children_forwards.last().map(|(l, _)| *l) == Some(lane.id)
+                || road.children_backwards.last().map(|(l, _)| *l) == Some(lane.id))
+        {
+            draw.push(
+                cs.bridge_casing,
+                map.right_shift(lane.lane_center_pts.clone(), lane.width / 2.0)
+                    .get(timer)
+                    .make_polygons(BRIDGE_CASING_THICKNESS),
+            );
+        }
+
         AlmostDrawLane {
             id: lane.id,
             polygon,
@@ -151,6 +177,11 @@ impl DrawLane {
             draw_default: draw,
         }
     }
+
+    // Only what's uploaded to the GPU
+    pub fn gpu_bytes(&self) -> usize {
+        self.draw_default.size_bytes()
+    }
 }
 
 impl Renderable for DrawLane {
@@ -205,6 +236,21 @@ fn calculate_sidewalk_lines(lane: &Lane) -> Vec<Polygon> {
     result
 }
 
+fn calculate_rails(lane: &Lane, timer: &mut Timer) -> Vec<Polygon> {
+    // Standard gauge is about 1.4m
+    let gauge = Distance::meters(1.435);
+    vec![
+        lane.lane_center_pts
+            .shift_left(gauge / 2.0)
+            .get(timer)
+            .make_polygons(Distance::meters(0.15)),
+        lane.lane_center_pts
+            .shift_right(gauge / 2.0)
+            .get(timer)
+            .make_polygons(Distance::meters(0.15)),
+    ]
+}
+
 fn calculate_parking_lines(map: &Map, lane: &Lane) -> Vec<Polygon> {
     // meters, but the dims get annoying below to remove
     let leg_length = Distance::meters(1.0);
@@ -234,25 +280,68 @@ fn calculate_parking_lines(map: &Map, lane: &Lane) -> Vec<Polygon> {
     result
 }
 
-fn calculate_driving_lines(
+// Each lane draws the line along its left edge. Lanes of the same type are split by dashed lines,
+// since vehicles can change between them. Buses, bikes, and general traffic are kept apart by
+// solid lines.
+fn calculate_lane_dividers(
     map: &Map,
     lane: &Lane,
     parent: &Road,
     timer: &mut Timer,
 ) -> Vec<Polygon> {
-    // The leftmost lanes don't have dashed lines.
+    // The leftmost lanes get the center line instead.
     let (dir, idx) = parent.dir_and_offset(lane.id);
-    if idx == 0 || (dir && parent.children_forwards[idx - 1].1 == LaneType::SharedLeftTurn) {
+    if idx == 0 {
         return Vec::new();
     }
+    let neighbor = if dir {
+        parent.children_forwards[idx - 1].1
+    } else {
+        parent.children_backwards[idx - 1].1
+    };
+    match neighbor {
+        LaneType::Driving | LaneType::Bus | LaneType::Biking => {}
+        // Parking and sidewalks have their own markings, and shared left turn lanes draw both
+        // edges.
+        _ => {
+            return Vec::new();
+        }
+    }
+
     let lane_edge_pts = map
         .left_shift(lane.lane_center_pts.clone(), lane.width / 2.0)
         .get(timer);
-    lane_edge_pts.dashed_lines(
-        Distance::meters(0.25),
-        Distance::meters(1.0),
-        Distance::meters(1.5),
-    )
+    let thickness = Distance::meters(0.25);
+    if neighbor == lane.lane_type {
+        lane_edge_pts.dashed_lines(thickness, Distance::meters(1.0), Distance::meters(1.5))
+    } else {
+        vec![lane_edge_pts.make_polygons(thickness)]
+    }
+}
+
+// Arrows just past each bike symbol (placed in AlmostDrawLane::finish), so the direction of
+// contraflow bike lanes is obvious.
+fn calculate_bike_arrows(lane: &Lane) -> Vec<Polygon> {
+    let mut results = Vec::new();
+    let arrow_len = Distance::meters(2.0);
+    let btwn = Distance::meters(30.0);
+    let thickness = Distance::meters(0.2);
+    let len = lane.length();
+
+    let mut dist = Distance::meters(5.0);
+    while dist + arrow_len <= len {
+        let (pt, angle) = lane.lane_center_pts.dist_along(dist);
+        results.push(
+            PolyLine::new(vec![
+                pt.project_away(arrow_len / 2.0, angle.opposite()),
+                pt.project_away(arrow_len / 2.0, angle),
+            ])
+            .make_arrow(thickness, ArrowCap::Triangle)
+            .unwrap(),
+        );
+        dist += btwn;
+    }
+    results
 }
 
 fn calculate_turn_markings(map: &Map, lane: &Lane, timer: &mut Timer) -> Vec<Polygon> {
@@ -3,15 +3,19 @@ use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::render::building::DrawBuilding;
 use crate::render::bus_stop::DrawBusStop;
+use crate::render::cache::GeometryCache;
 use crate::render::intersection::DrawIntersection;
 use crate::render::lane::DrawLane;
 use crate::render::parking_lot::DrawParkingLot;
 use crate::render::road::DrawRoad;
-use crate::render::{draw_vehicle, DrawArea, DrawPedCrowd, DrawPedestrian, Renderable};
-use aabb_quadtree::QuadTree;
+use crate::render::road_labels::RoadLabels;
+use crate::render::{
+    draw_vehicle, DrawArea, DrawCrosswalk, DrawPedCrowd, DrawPedestrian, Renderable,
+    BRIDGE_CASING_THICKNESS,
+};
 use abstutil::Timer;
 use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Prerender};
-use geom::{Bounds, Circle, Distance, Pt2D, Time};
+use geom::{Bounds, Circle, Distance, Polygon, Pt2D, QuadTree, Time};
 use map_model::{
     AreaID, BuildingID, BusStopID, Intersection, IntersectionID, LaneID, Map, ParkingLotID, Road,
     RoadID, Traversable, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
@@ -32,6 +36,8 @@ pub struct DrawMap {
 
     // TODO Move?
     pub agents: RefCell<AgentCache>,
+    // Lazily placed the first time they're drawn
+    road_labels: RefCell<Option<RoadLabels>>,
 
     pub boundary_polygon: Drawable,
     pub draw_all_thick_roads: Drawable,
@@ -65,10 +71,15 @@ impl DrawMap {
         road_refs.sort_by_key(|r| r.zorder);
         let mut all_roads = GeomBatch::new();
         for r in road_refs {
-            all_roads.push(
-                osm_rank_to_color(cs, r.get_rank()),
-                r.get_thick_polygon(map).get(timer),
-            );
+            let (pl, width) = r.get_thick_polyline(map).get(timer);
+            // Outline anything above ground, so it's clear it passes over the roads beneath
+            if r.zorder > 0 {
+                all_roads.push(
+                    cs.bridge_casing,
+                    pl.make_polygons(width + 2.0 * BRIDGE_CASING_THICKNESS),
+                );
+            }
+            all_roads.push(osm_rank_to_color(cs, r.get_rank()), pl.make_polygons(width));
             /*if false {
                 all_roads.push(
                     color,
@@ -79,31 +90,50 @@ impl DrawMap {
         let draw_all_thick_roads = all_roads.upload(ctx);
         timer.stop("generate thick roads");
 
-        let almost_lanes =
-            timer.parallelize("prepare DrawLanes", map.all_lanes().iter().collect(), |l| {
-                DrawLane::new(
-                    l,
-                    map,
-                    flags.draw_lane_markings,
-                    cs,
-                    // TODO Really parallelize should give us something thread-safe that can at
-                    // least take notes.
-                    &mut Timer::throwaway(),
-                )
-            });
-        timer.start_iter("finalize DrawLanes", almost_lanes.len());
+        let cache =
+            if let Some(cache) = GeometryCache::load(map, flags.draw_lane_markings, cs, timer) {
+                timer.note("Using cached lane and intersection geometry".to_string());
+                cache
+            } else {
+                let lanes =
+                    timer.parallelize("prepare DrawLanes", map.all_lanes().iter().collect(), |l| {
+                        DrawLane::new(
+                            l,
+                            map,
+                            flags.draw_lane_markings,
+                            cs,
+                            // TODO Really parallelize should give us something thread-safe that can
+                            // at least take notes.
+                            &mut Timer::throwaway(),
+                        )
+                    });
+                let mut intersections = Vec::new();
+                timer.start_iter("prepare DrawIntersections", map.all_intersections().len());
+                for i in map.all_intersections() {
+                    timer.next();
+                    intersections.push(DrawIntersection::default_geom(i, map, cs, timer));
+                }
+                let cache = GeometryCache {
+                    lanes,
+                    intersections,
+                };
+                cache.save(map, flags.draw_lane_markings, cs);
+                cache
+            };
+
+        timer.start_iter("finalize DrawLanes", cache.lanes.len());
         let mut lanes: Vec<DrawLane> = Vec::new();
-        for almost in almost_lanes {
+        for almost in cache.lanes {
             timer.next();
             let lane = map.get_l(almost.id);
             lanes.push(almost.finish(ctx.prerender, cs, lane));
         }
 
         let mut intersections: Vec<DrawIntersection> = Vec::new();
-        timer.start_iter("make DrawIntersections", map.all_intersections().len());
-        for i in map.all_intersections() {
+        timer.start_iter("finalize DrawIntersections", cache.intersections.len());
+        for (i, geom) in map.all_intersections().iter().zip(cache.intersections) {
             timer.next();
-            intersections.push(DrawIntersection::new(i, map, cs, ctx.prerender, timer));
+            intersections.push(DrawIntersection::from_geom(i, map, ctx.prerender, geom));
         }
 
         timer.start("generate unzoomed intersections");
@@ -188,35 +218,30 @@ impl DrawMap {
         )]));
 
         timer.start("create quadtree");
-        let mut quadtree = QuadTree::default(map.get_bounds().as_bbox());
+        let mut quadtree = QuadTree::new(map.get_bounds());
         // TODO use iter chain if everything was boxed as a renderable...
         for obj in &roads {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert_with_bounds(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &lanes {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert_with_bounds(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &intersections {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert_with_bounds(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &buildings {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert_with_bounds(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         for obj in &parking_lots {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert_with_bounds(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         // Don't put BusStops in the quadtree
         for obj in &areas {
-            quadtree.insert_with_box(obj.get_id(), obj.get_outline(map).get_bounds().as_bbox());
+            quadtree.insert_with_bounds(obj.get_id(), obj.get_outline(map).get_bounds());
         }
         timer.stop("create quadtree");
 
-        timer.note(format!(
-            "static DrawMap consumes {} MB on the GPU",
-            abstutil::prettyprint_usize(ctx.prerender.get_total_bytes_uploaded() / 1024 / 1024)
-        ));
-
-        DrawMap {
+        let draw_map = DrawMap {
             roads,
             lanes,
             intersections,
@@ -236,11 +261,134 @@ impl DrawMap {
             agents: RefCell::new(AgentCache {
                 time: None,
                 agents_per_on: HashMap::new(),
+                unzoomed_agents: None,
                 unzoomed: None,
             }),
+            road_labels: RefCell::new(None),
 
             quadtree,
+        };
+        timer.note(format!(
+            "static DrawMap consumes {} MB on the GPU",
+            abstutil::prettyprint_usize(
+                draw_map
+                    .gpu_memory_usage()
+                    .into_iter()
+                    .map(|(_, bytes)| bytes)
+                    .sum::<usize>()
+                    / 1024
+                    / 1024
+            )
+        ));
+        draw_map
+    }
+
+    // Everything currently uploaded, not counting agents drawn while zoomed in
+    pub fn gpu_memory_usage(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            (
+                "whole-map layers",
+                vec![
+                    &self.boundary_polygon,
+                    &self.draw_all_thick_roads,
+                    &self.draw_all_unzoomed_intersections,
+                    &self.draw_all_buildings,
+                    &self.draw_all_building_paths,
+                    &self.draw_all_building_outlines,
+                    &self.draw_all_unzoomed_parking_lots,
+                    &self.draw_all_areas,
+                ]
+                .into_iter()
+                .map(|draw| draw.size_bytes())
+                .sum(),
+            ),
+            (
+                "lanes and roads",
+                self.lanes.iter().map(|l| l.gpu_bytes()).sum::<usize>()
+                    + self.roads.iter().map(|r| r.gpu_bytes()).sum::<usize>(),
+            ),
+            (
+                "intersections",
+                self.intersections.iter().map(|i| i.gpu_bytes()).sum(),
+            ),
+            (
+                "building labels",
+                self.buildings.iter().map(|b| b.gpu_bytes()).sum(),
+            ),
+            (
+                "parking lots and bus stops",
+                self.parking_lots
+                    .iter()
+                    .map(|pl| pl.gpu_bytes())
+                    .sum::<usize>()
+                    + self
+                        .bus_stops
+                        .values()
+                        .map(|bs| bs.gpu_bytes())
+                        .sum::<usize>(),
+            ),
+            (
+                "road labels",
+                self.road_labels
+                    .borrow()
+                    .as_ref()
+                    .map(|labels| labels.gpu_bytes())
+                    .unwrap_or(0),
+            ),
+            ("unzoomed agents", self.agents.borrow().gpu_bytes()),
+        ]
+    }
+
+    // The objects themselves, plus lane polygons measured by serialized size, like the map
+    pub fn cpu_memory_usage(&self) -> Vec<(&'static str, usize)> {
+        use std::mem::size_of;
+        vec![
+            (
+                "lanes",
+                self.lanes.len() * size_of::<DrawLane>()
+                    + self
+                        .lanes
+                        .iter()
+                        .map(|l| abstutil::serialized_size_bytes(&l.polygon))
+                        .sum::<usize>(),
+            ),
+            (
+                "intersections and crosswalks",
+                self.intersections.len() * size_of::<DrawIntersection>()
+                    + self
+                        .intersections
+                        .iter()
+                        .map(|i| i.crosswalks.len() * size_of::<DrawCrosswalk>())
+                        .sum::<usize>(),
+            ),
+            (
+                "other objects",
+                self.roads.len() * size_of::<DrawRoad>()
+                    + self.buildings.len() * size_of::<DrawBuilding>()
+                    + self.parking_lots.len() * size_of::<DrawParkingLot>()
+                    + self.bus_stops.len() * size_of::<DrawBusStop>()
+                    + self.areas.len() * size_of::<DrawArea>(),
+            ),
+            (
+                "quadtree",
+                // Everything but bus stops is in there
+                (self.roads.len()
+                    + self.lanes.len()
+                    + self.intersections.len()
+                    + self.buildings.len()
+                    + self.parking_lots.len()
+                    + self.areas.len())
+                    * size_of::<(ID, Bounds)>(),
+            ),
+        ]
+    }
+
+    pub fn draw_road_labels(&self, g: &mut GfxCtx, map: &Map, cs: &ColorScheme, unzoomed: bool) {
+        let mut labels = self.road_labels.borrow_mut();
+        if labels.is_none() {
+            *labels = Some(RoadLabels::new(map, cs, g.prerender));
         }
+        g.redraw(labels.as_ref().unwrap().get(unzoomed));
     }
 
     // The alt to these is implementing std::ops::Index, but that's way more verbose!
@@ -319,6 +467,14 @@ impl DrawMap {
             ID::Area(id) => {
                 return Some(self.get_a(id));
             }
+            ID::Crosswalk(id) => {
+                return self
+                    .get_i(id.i)
+                    .crosswalks
+                    .iter()
+                    .find(|c| c.id == id)
+                    .map(|c| c as &dyn Renderable);
+            }
         };
 
         agents.populate_if_needed(on, &app.primary.map, &app.primary.sim, &app.cs, prerender);
@@ -331,7 +487,7 @@ impl DrawMap {
     // Unsorted, unexpanded, raw result.
     pub fn get_matching_objects(&self, bounds: Bounds) -> Vec<ID> {
         let mut results: Vec<ID> = Vec::new();
-        for &(id, _, _) in &self.quadtree.query(bounds.as_bbox()) {
+        for id in self.quadtree.query(&bounds) {
             results.push(id.clone());
         }
         results
@@ -342,8 +498,16 @@ pub struct AgentCache {
     // This time applies to agents_per_on. unzoomed has its own possibly separate Time!
     time: Option<Time>,
     agents_per_on: HashMap<Traversable, Vec<Box<dyn Renderable>>>,
-    // agent radius also matters
-    unzoomed: Option<(Time, Option<Distance>, AgentColorScheme, Drawable)>,
+    // Every unzoomed agent at some time, indexed by position
+    unzoomed_agents: Option<(Time, Vec<UnzoomedAgent>, QuadTree<usize>)>,
+    // agent radius and the area drawn also matter
+    unzoomed: Option<(
+        Time,
+        Option<Distance>,
+        AgentColorScheme,
+        Option<Bounds>,
+        Drawable,
+    )>,
 }
 
 impl AgentCache {
@@ -370,7 +534,7 @@ impl AgentCache {
 
         let mut list: Vec<Box<dyn Renderable>> = Vec::new();
         for c in source.get_draw_cars(on, map).into_iter() {
-            list.push(draw_vehicle(c, map, prerender, cs));
+            list.push(draw_vehicle(c, step_count, map, prerender, cs));
         }
         let (loners, crowds) = source.get_draw_peds(on, map);
         for p in loners {
@@ -390,8 +554,16 @@ impl AgentCache {
         self.agents_per_on.insert(on, list);
     }
 
+    fn gpu_bytes(&self) -> usize {
+        self.unzoomed
+            .as_ref()
+            .map(|(_, _, _, _, draw)| draw.size_bytes())
+            .unwrap_or(0)
+    }
+
     // TODO GetDrawAgents indirection added for time traveling, but that's been removed. Maybe
     // simplify this.
+    // Only agents inside the area are drawn; None means everywhere.
     pub fn draw_unzoomed_agents(
         &mut self,
         source: &dyn GetDrawAgents,
@@ -399,45 +571,105 @@ impl AgentCache {
         acs: &AgentColorScheme,
         g: &mut GfxCtx,
         maybe_radius: Option<Distance>,
+        area: Option<Bounds>,
     ) {
         let now = source.time();
-        if let Some((time, r, ref orig_acs, ref draw)) = self.unzoomed {
-            if now == time && maybe_radius == r && acs == orig_acs {
+        if let Some((time, r, ref orig_acs, ref orig_area, ref draw)) = self.unzoomed {
+            if now == time && maybe_radius == r && acs == orig_acs && &area == orig_area {
                 g.redraw(draw);
                 return;
             }
         }
 
-        let mut batch = GeomBatch::new();
-        // It's quite silly to produce triangles for the same circle over and over again. ;)
-        if let Some(r) = maybe_radius {
-            let circle = Circle::new(Pt2D::new(0.0, 0.0), r).to_polygon();
-            for agent in source.get_unzoomed_agents(map) {
-                if let Some(color) = acs.color(&agent) {
-                    batch.push(color, circle.translate(agent.pos.x(), agent.pos.y()));
-                }
+        // Panning around doesn't change the agents, so only rebuild the index when time passes
+        if self
+            .unzoomed_agents
+            .as_ref()
+            .map(|(time, _, _)| *time != now)
+            .unwrap_or(true)
+        {
+            let agents = source.get_unzoomed_agents(map);
+            let mut quadtree = QuadTree::new(map.get_bounds());
+            for (idx, agent) in agents.iter().enumerate() {
+                quadtree.insert_with_bounds(idx, Bounds::from(&vec![agent.pos]));
             }
+            self.unzoomed_agents = Some((now, agents, quadtree));
+        }
+        let (_, ref agents, ref quadtree) = self.unzoomed_agents.as_ref().unwrap();
+
+        let mut batch = GeomBatch::new();
+        // It's quite silly to produce triangles for the same shape over and over again. ;) Each
+        // mode gets a different silhouette, so mode share is visible even without color.
+        let shapes = if let Some(r) = maybe_radius {
+            UnzoomedShapes::new(r, r, 0.7 * r)
         } else {
-            // Lane thickness is a little hard to see, so double it. Most of the time, the circles
+            // Lane thickness is a little hard to see, so double it. Most of the time, the shapes
             // don't leak out of the road too much.
-            let car_circle =
-                Circle::new(Pt2D::new(0.0, 0.0), 4.0 * NORMAL_LANE_THICKNESS).to_polygon();
-            let ped_circle =
-                Circle::new(Pt2D::new(0.0, 0.0), 4.0 * SIDEWALK_THICKNESS).to_polygon();
-            for agent in source.get_unzoomed_agents(map) {
-                if let Some(color) = acs.color(&agent) {
-                    if agent.vehicle_type.is_some() {
-                        batch.push(color, car_circle.translate(agent.pos.x(), agent.pos.y()));
-                    } else {
-                        batch.push(color, ped_circle.translate(agent.pos.x(), agent.pos.y()));
-                    }
-                }
+            UnzoomedShapes::new(
+                4.0 * NORMAL_LANE_THICKNESS,
+                3.0 * NORMAL_LANE_THICKNESS,
+                4.0 * SIDEWALK_THICKNESS,
+            )
+        };
+        let visible: Vec<&UnzoomedAgent> = if let Some(ref b) = area {
+            // Agents centered just off-screen still partly show up
+            let pad = shapes.bus.get_bounds().max_x;
+            let padded = Bounds {
+                min_x: b.min_x - pad,
+                min_y: b.min_y - pad,
+                max_x: b.max_x + pad,
+                max_y: b.max_y + pad,
+            };
+            quadtree
+                .query(&padded)
+                .into_iter()
+                .map(|idx| &agents[*idx])
+                .collect()
+        } else {
+            agents.iter().collect()
+        };
+        for agent in visible {
+            if let Some(color) = acs.color(agent) {
+                let shape = match agent.vehicle_type {
+                    Some(VehicleType::Car) => &shapes.car,
+                    Some(VehicleType::Bike) => &shapes.bike,
+                    Some(VehicleType::Bus) | Some(VehicleType::Train) => &shapes.bus,
+                    None => &shapes.ped,
+                };
+                batch.push(color, shape.translate(agent.pos.x(), agent.pos.y()));
             }
         }
 
         let draw = g.upload(batch);
         g.redraw(&draw);
-        self.unzoomed = Some((now, maybe_radius, acs.clone(), draw));
+        self.unzoomed = Some((now, maybe_radius, acs.clone(), area, draw));
+    }
+}
+
+// Centered at the origin
+struct UnzoomedShapes {
+    car: Polygon,
+    bike: Polygon,
+    bus: Polygon,
+    ped: Polygon,
+}
+
+impl UnzoomedShapes {
+    fn new(car: Distance, bike: Distance, ped: Distance) -> UnzoomedShapes {
+        let origin = Pt2D::new(0.0, 0.0);
+        let b = bike.inner_meters();
+        UnzoomedShapes {
+            car: Circle::new(origin, car).to_polygon(),
+            // A diamond
+            bike: Polygon::new(&vec![
+                Pt2D::new(0.0, -b),
+                Pt2D::new(b, 0.0),
+                Pt2D::new(0.0, b),
+                Pt2D::new(-b, 0.0),
+            ]),
+            bus: Polygon::rectangle_centered(origin, 1.6 * car, 1.6 * car),
+            ped: Circle::new(origin, ped).to_polygon(),
+        }
     }
 }
 
@@ -477,7 +709,8 @@ impl AgentColorScheme {
         let category = match agent.vehicle_type {
             Some(VehicleType::Car) => "Car".to_string(),
             Some(VehicleType::Bike) => "Bike".to_string(),
-            Some(VehicleType::Bus) => "Bus".to_string(),
+            // Trains are toggled along with buses
+            Some(VehicleType::Bus) | Some(VehicleType::Train) => "Bus".to_string(),
             None => "Pedestrian".to_string(),
         };
         for (name, color, enabled) in &self.rows {
@@ -492,7 +725,7 @@ impl AgentColorScheme {
     }
 }
 
-fn osm_rank_to_color(cs: &ColorScheme, rank: usize) -> Color {
+pub fn osm_rank_to_color(cs: &ColorScheme, rank: usize) -> Color {
     if rank >= 16 {
         cs.unzoomed_highway
     } else if rank >= 6 {
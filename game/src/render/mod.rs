@@ -2,13 +2,16 @@ mod area;
 mod bike;
 mod building;
 mod bus_stop;
+mod cache;
 mod car;
+mod crosswalk;
 mod intersection;
 mod lane;
 mod map;
 mod parking_lot;
 mod pedestrian;
 mod road;
+mod road_labels;
 mod traffic_signal;
 mod turn;
 
@@ -17,7 +20,9 @@ use crate::colors::ColorScheme;
 use crate::helpers::ID;
 pub use crate::render::area::DrawArea;
 use crate::render::bike::DrawBike;
+pub use crate::render::bus_stop::DrawBusStop;
 use crate::render::car::DrawCar;
+pub use crate::render::crosswalk::DrawCrosswalk;
 pub use crate::render::intersection::{calculate_corners, DrawIntersection};
 pub use crate::render::lane::DrawLane;
 pub use crate::render::map::{AgentCache, AgentColorScheme, DrawMap};
@@ -32,6 +37,8 @@ use sim::{DrawCarInput, VehicleType};
 
 pub const BIG_ARROW_THICKNESS: Distance = Distance::const_meters(0.5);
 
+pub const BRIDGE_CASING_THICKNESS: Distance = Distance::const_meters(0.5);
+
 pub const CROSSWALK_LINE_THICKNESS: Distance = Distance::const_meters(0.15);
 
 pub const OUTLINE_THICKNESS: Distance = Distance::const_meters(0.5);
@@ -58,12 +65,13 @@ pub trait Renderable {
 
 fn draw_vehicle(
     input: DrawCarInput,
+    step_count: usize,
     map: &Map,
     prerender: &Prerender,
     cs: &ColorScheme,
 ) -> Box<dyn Renderable> {
     if input.id.1 == VehicleType::Bike {
-        Box::new(DrawBike::new(input, map, prerender, cs))
+        Box::new(DrawBike::new(input, step_count, map, prerender, cs))
     } else {
         Box::new(DrawCar::new(input, map, prerender, cs))
     }
@@ -85,6 +85,10 @@ impl DrawParkingLot {
             draw: prerender.upload(batch),
         }
     }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.draw.size_bytes()
+    }
 }
 
 impl Renderable for DrawParkingLot {
@@ -100,7 +100,14 @@ impl DrawPedestrian {
         // Jitter based on ID so we don't all walk synchronized.
         let jitter = input.id.0 % 2 == 0;
         let remainder = step_count % 6;
-        if input.waiting_for_turn.is_some() || input.waiting_for_bus {
+        let standing = input.waiting_for_turn.is_some() || input.waiting_for_bus;
+        // The head bobs forward a bit as each foot lands
+        let head_pos = if standing || remainder % 3 != 0 {
+            input.pos
+        } else {
+            input.pos.project_away(0.15 * radius, input.facing)
+        };
+        if standing {
             batch.push(foot_color, left_foot.to_polygon());
             batch.push(foot_color, right_foot.to_polygon());
             batch.push(hand_color, left_hand.to_polygon());
@@ -155,7 +162,7 @@ impl DrawPedestrian {
             );
         };
 
-        let head_circle = Circle::new(input.pos, 0.5 * radius);
+        let head_circle = Circle::new(head_pos, 0.5 * radius);
         batch.push(
             if input.preparing_bike {
                 cs.ped_preparing_bike_body
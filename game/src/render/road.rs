@@ -2,17 +2,15 @@ use crate::app::App;
 use crate::colors::ColorScheme;
 use crate::helpers::ID;
 use crate::render::{DrawOptions, Renderable};
-use ezgui::{Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
-use geom::{Distance, Polygon, Pt2D};
-use map_model::{LaneType, Map, Road, RoadID};
-use std::cell::RefCell;
+use ezgui::{Drawable, GeomBatch, GfxCtx, Prerender};
+use geom::{Circle, Distance, Line, Polygon, Pt2D};
+use map_model::{IntersectionID, LaneType, Map, Road, RoadID};
 
 pub struct DrawRoad {
     pub id: RoadID,
     zorder: isize,
 
     draw_center_line: Drawable,
-    label: RefCell<Option<Drawable>>,
 }
 
 impl DrawRoad {
@@ -36,13 +34,20 @@ impl DrawRoad {
             );
         }
 
+        if let Some(i) = r.modal_filter {
+            draw.extend(cs.modal_filter, draw_modal_filter(r, i, map));
+        }
+
         DrawRoad {
             id: r.id,
             zorder: r.zorder,
             draw_center_line: prerender.upload(draw),
-            label: RefCell::new(None),
         }
     }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.draw_center_line.size_bytes()
+    }
 }
 
 impl Renderable for DrawRoad {
@@ -50,54 +55,8 @@ impl Renderable for DrawRoad {
         ID::Road(self.id)
     }
 
-    fn draw(&self, g: &mut GfxCtx, app: &App, _: &DrawOptions) {
+    fn draw(&self, g: &mut GfxCtx, _: &App, _: &DrawOptions) {
         g.redraw(&self.draw_center_line);
-
-        if app.opts.label_roads {
-            // Lazily calculate
-            let mut label = self.label.borrow_mut();
-            if label.is_none() {
-                let mut batch = GeomBatch::new();
-                let r = app.primary.map.get_r(self.id);
-
-                if false {
-                    // Style 1: banner
-                    let mut txt = Text::new().with_bg();
-                    txt.add(Line(r.get_name()));
-                    batch.append(
-                        txt.render_to_batch(g.prerender)
-                            .scale(0.1)
-                            .centered_on(r.center_pts.middle()),
-                    );
-                } else {
-                    // Style 2: Yellow center-line
-                    let name = r.get_name();
-                    if r.center_pts.length() >= Distance::meters(30.0) && name != "???" {
-                        // TODO If it's definitely straddling bus/bike lanes, change the color? Or
-                        // even easier, just skip the center lines?
-                        let txt = Text::from(Line(name).fg(app.cs.road_center_line))
-                            .bg(app.cs.driving_lane);
-                        let (pt, mut angle) = r.center_pts.dist_along(r.center_pts.length() / 2.0);
-                        let theta = angle.normalized_degrees().rem_euclid(360.0);
-                        if theta > 90.0 {
-                            angle = angle.opposite();
-                        }
-                        if theta > 270.0 {
-                            angle = angle.opposite();
-                        }
-                        batch.append(
-                            txt.render_to_batch(g.prerender)
-                                .scale(0.1)
-                                .centered_on(pt)
-                                .rotate(angle),
-                        );
-                    }
-                }
-                *label = Some(g.prerender.upload(batch));
-            }
-            // TODO Covered up sometimes. We could fork and force a different z value...
-            g.redraw(label.as_ref().unwrap());
-        }
     }
 
     fn get_outline(&self, map: &Map) -> Polygon {
@@ -116,3 +75,28 @@ impl Renderable for DrawRoad {
         self.zorder
     }
 }
+
+// A row of bollards across the road, just before the intersection
+fn draw_modal_filter(r: &Road, i: IntersectionID, map: &Map) -> Vec<Polygon> {
+    let (pl, width) = r.get_thick_polyline(map).unwrap();
+    let buffer = Distance::meters(2.0).min(pl.length() / 2.0);
+    let (pt, angle) = if i == r.dst_i {
+        pl.dist_along(pl.length() - buffer)
+    } else {
+        pl.dist_along(buffer)
+    };
+    let across = Line::new(
+        pt.project_away(width / 2.0, angle.rotate_degs(90.0)),
+        pt.project_away(width / 2.0, angle.rotate_degs(-90.0)),
+    );
+    let num_bollards = (width / Distance::meters(1.5)).floor().max(2.0) as usize;
+    (0..=num_bollards)
+        .map(|idx| {
+            Circle::new(
+                across.percent_along((idx as f64) / (num_bollards as f64)),
+                Distance::meters(0.4),
+            )
+            .to_polygon()
+        })
+        .collect()
+}
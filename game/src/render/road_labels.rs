@@ -0,0 +1,145 @@
+use crate::colors::ColorScheme;
+use crate::render::map::osm_rank_to_color;
+use ezgui::{Color, Drawable, GeomBatch, Line, Prerender, Text};
+use geom::{Distance, Polygon, Pt2D, QuadTree};
+use map_model::{Map, Road};
+use std::collections::HashMap;
+
+// Street names drawn along road center-lines. Labels for the whole map are placed at once, most
+// important roads first, skipping any spot that would overlap a label already placed.
+pub struct RoadLabels {
+    zoomed: Drawable,
+    unzoomed: Drawable,
+}
+
+// Each level of detail places labels independently, since the text is a different size.
+struct Tier {
+    // Only label roads at least this important, according to Road::get_rank
+    min_rank: usize,
+    // From text pixels to meters
+    scale: f64,
+    // Long roads (or many roads with the same name) repeat their label this often
+    spacing: Distance,
+}
+
+const ZOOMED: Tier = Tier {
+    min_rank: 0,
+    scale: 0.1,
+    spacing: Distance::const_meters(150.0),
+};
+const UNZOOMED: Tier = Tier {
+    min_rank: 6,
+    scale: 0.6,
+    spacing: Distance::const_meters(1000.0),
+};
+
+// Labels that need to curve more than this to follow the road aren't drawn.
+const MAX_BEND_DEGREES: f64 = 30.0;
+
+impl RoadLabels {
+    pub fn new(map: &Map, cs: &ColorScheme, prerender: &Prerender) -> RoadLabels {
+        RoadLabels {
+            zoomed: prerender.upload(place_labels(map, cs, prerender, &ZOOMED)),
+            unzoomed: prerender.upload(place_labels(map, cs, prerender, &UNZOOMED)),
+        }
+    }
+
+    pub fn get(&self, unzoomed: bool) -> &Drawable {
+        if unzoomed {
+            &self.unzoomed
+        } else {
+            &self.zoomed
+        }
+    }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.zoomed.size_bytes() + self.unzoomed.size_bytes()
+    }
+}
+
+fn place_labels(map: &Map, cs: &ColorScheme, prerender: &Prerender, tier: &Tier) -> GeomBatch {
+    let mut roads: Vec<&Road> = map
+        .all_roads()
+        .iter()
+        .filter(|r| r.get_rank() >= tier.min_rank)
+        .collect();
+    roads.sort_by(|a, b| {
+        b.get_rank().cmp(&a.get_rank()).then(
+            b.center_pts
+                .length()
+                .partial_cmp(&a.center_pts.length())
+                .unwrap(),
+        )
+    });
+
+    let mut batch = GeomBatch::new();
+    let mut placed: QuadTree<Polygon> = QuadTree::new(&map.get_bounds());
+    let mut placed_per_name: HashMap<String, Vec<Pt2D>> = HashMap::new();
+    for r in roads {
+        let name = r.get_name();
+        if name == "???" {
+            continue;
+        }
+        let txt = if tier.min_rank == 0 {
+            // TODO If it's definitely straddling bus/bike lanes, change the color? Or even easier,
+            // just skip the center lines?
+            Text::from(Line(&name).fg(cs.road_center_line)).bg(cs.driving_lane)
+        } else {
+            Text::from(Line(&name).fg(Color::BLACK)).bg(osm_rank_to_color(cs, r.get_rank()))
+        };
+        let label = txt.render_to_batch(prerender).scale(tier.scale);
+        let dims = label.get_dims();
+        let (width, height) = (Distance::meters(dims.width), Distance::meters(dims.height));
+
+        let pl = &r.center_pts;
+        if pl.length() < width {
+            continue;
+        }
+        let num_labels = ((pl.length() / tier.spacing).floor() as usize).max(1);
+        for i in 0..num_labels {
+            let dist = pl.length() * ((i as f64 + 0.5) / (num_labels as f64));
+            if dist < width / 2.0 || dist + width / 2.0 > pl.length() {
+                continue;
+            }
+            let (pt, mut angle) = pl.dist_along(dist);
+            let (_, angle1) = pl.dist_along(dist - width / 2.0);
+            let (_, angle2) = pl.dist_along(dist + width / 2.0);
+            if !angle1.approx_eq(angle2, MAX_BEND_DEGREES) {
+                continue;
+            }
+            if placed_per_name
+                .get(&name)
+                .map(|pts| pts.iter().any(|other| other.dist_to(pt) < tier.spacing))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            // Don't draw text upside-down
+            let theta = angle.normalized_degrees().rem_euclid(360.0);
+            if theta > 90.0 && theta <= 270.0 {
+                angle = angle.opposite();
+            }
+            let outline = Polygon::rectangle_centered(pt, width, height).rotate(angle);
+            if placed
+                .query(&outline.get_bounds())
+                .into_iter()
+                .any(|other| outline.overlaps(other))
+            {
+                continue;
+            }
+
+            batch.append(
+                label
+                    .clone()
+                    .centered_on(pt)
+                    .rotate_around_batch_center(angle),
+            );
+            placed.insert_with_bounds(outline.clone(), outline.get_bounds());
+            placed_per_name
+                .entry(name.clone())
+                .or_insert_with(Vec::new)
+                .push(pt);
+        }
+    }
+    batch
+}
@@ -7,8 +7,11 @@ use ezgui::{
     RewriteColor, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Angle, ArrowCap, Circle, Distance, Duration, Line, PolyLine, Polygon, Pt2D};
-use map_model::{IntersectionID, Phase, TurnPriority, SIDEWALK_THICKNESS};
-use std::collections::BTreeSet;
+use map_model::{
+    DirectedRoadID, IntersectionID, Lane, Phase, TurnGroup, TurnGroupID, TurnPriority, TurnType,
+    SIDEWALK_THICKNESS,
+};
+use std::collections::{BTreeMap, BTreeSet};
 
 // Only draws a box when time_left is present
 pub fn draw_signal_phase(
@@ -122,6 +125,11 @@ pub fn draw_signal_phase(
             // No time_left box
             return;
         }
+        TrafficSignalStyle::Realistic => {
+            draw_signal_heads(prerender, phase, i, time_left, batch, app);
+            // The indications already show when the phase is ending
+            return;
+        }
         TrafficSignalStyle::GroupArrows => {
             for g in &phase.yield_groups {
                 assert!(!g.crosswalk);
@@ -276,6 +284,151 @@ pub fn draw_signal_phase(
     );
 }
 
+// Signal heads with red/yellow/green lights for every approach, like drivers see them. Protected
+// movements get green arrows, permitted ones a plain green ball. Crosswalks get walk signals with
+// a countdown.
+fn draw_signal_heads(
+    prerender: &Prerender,
+    phase: &Phase,
+    i: IntersectionID,
+    time_left: Option<Duration>,
+    batch: &mut GeomBatch,
+    app: &App,
+) {
+    let map = &app.primary.map;
+    let signal = map.get_traffic_signal(i);
+    // If we're drawing a phase that's being edited, there's no next phase to look at.
+    let next_phase = signal
+        .phases
+        .iter()
+        .position(|p| p == phase)
+        .map(|idx| &signal.phases[(idx + 1) % signal.phases.len()]);
+    let ending_soon = time_left
+        .map(|t| t <= Duration::seconds(5.0))
+        .unwrap_or(false);
+    // Is the group about to lose its right-of-way?
+    let changing = |g: TurnGroupID| {
+        ending_soon
+            && next_phase
+                .map(|next| next.get_priority_of_group(g) < phase.get_priority_of_group(g))
+                .unwrap_or(true)
+    };
+
+    let mut approaches: BTreeMap<DirectedRoadID, Vec<&TurnGroup>> = BTreeMap::new();
+    for group in signal.turn_groups.values() {
+        if group.turn_type == TurnType::Crosswalk {
+            let (center, angle) = crosswalk_icon(&group.geom);
+            let walk = phase.get_priority_of_group(group.id) == TurnPriority::Protected;
+            let icon = if walk && !changing(group.id) {
+                "../data/system/assets/map/walk.svg"
+            } else {
+                "../data/system/assets/map/dont_walk.svg"
+            };
+            batch.append(
+                GeomBatch::mapspace_svg(prerender, icon)
+                    .scale(0.07)
+                    .centered_on(center)
+                    .rotate(angle),
+            );
+            if walk {
+                if let Some(t) = time_left {
+                    batch.append(
+                        Text::from(Line(format!("{}", t.inner_seconds().ceil())))
+                            .render_to_batch(prerender)
+                            .scale(0.05)
+                            .centered_on(center.project_away(Distance::meters(1.2), angle)),
+                    );
+                }
+            }
+        } else if group.turn_type != TurnType::SharedSidewalkCorner {
+            approaches
+                .entry(group.id.from)
+                .or_insert_with(Vec::new)
+                .push(group);
+        }
+    }
+
+    let radius = Distance::meters(0.35);
+    let spacing = 0.9;
+    for (dr, mut groups) in approaches {
+        // Left-to-right, from the driver's point of view
+        groups.sort_by_key(|g| match g.turn_type {
+            TurnType::Left | TurnType::LaneChangeLeft => 0,
+            TurnType::Straight => 1,
+            _ => 2,
+        });
+
+        let lanes: Vec<&Lane> = map
+            .get_r(dr.id)
+            .incoming_lanes(i)
+            .iter()
+            .filter(|(_, lt)| lt.is_for_moving_vehicles())
+            .map(|(l, _)| map.get_l(*l))
+            .collect();
+        if lanes.is_empty() {
+            continue;
+        }
+        let angle = lanes[0].lane_center_pts.last_line().angle();
+        let stop_line = Pt2D::center(&lanes.iter().map(|l| l.lane_center_pts.last_pt()).collect());
+        let center = stop_line.project_away(Distance::meters(2.5), angle.opposite());
+
+        // Build the head in local coordinates, with the intersection up and the driver's right to
+        // the right, then rotate into place.
+        let mut head = GeomBatch::new();
+        let width = (groups.len() as f64) * spacing;
+        head.push(
+            Color::BLACK,
+            Polygon::rectangle_centered(
+                center,
+                Distance::meters(width + 0.2),
+                Distance::meters(3.0 * spacing + 0.2),
+            ),
+        );
+        for (col, g) in groups.iter().enumerate() {
+            let x = center.x() - width / 2.0 + (col as f64 + 0.5) * spacing;
+            let priority = phase.get_priority_of_group(g.id);
+            let lit = if priority == TurnPriority::Banned {
+                0
+            } else if changing(g.id) {
+                1
+            } else {
+                2
+            };
+            // Red on top, closest to the intersection
+            for (row, color) in vec![Color::RED, Color::YELLOW, Color::GREEN]
+                .into_iter()
+                .enumerate()
+            {
+                let pt = Pt2D::new(x, center.y() + ((row as f64) - 1.0) * spacing);
+                head.push(
+                    if row == lit { color } else { Color::grey(0.2) },
+                    Circle::new(pt, radius).to_polygon(),
+                );
+                if row == lit && lit == 2 && priority == TurnPriority::Protected {
+                    // Local angles: the intersection is at -90 degrees
+                    let arrow_angle = Angle::new_degs(match g.turn_type {
+                        TurnType::Left | TurnType::LaneChangeLeft => 180.0,
+                        TurnType::Straight => -90.0,
+                        _ => 0.0,
+                    });
+                    head.push(
+                        Color::BLACK,
+                        PolyLine::new(vec![
+                            pt.project_away(radius * 0.6, arrow_angle.opposite()),
+                            pt.project_away(radius * 0.6, arrow_angle),
+                        ])
+                        .make_arrow(Distance::meters(0.1), ArrowCap::Triangle)
+                        .unwrap(),
+                    );
+                }
+            }
+        }
+        for (color, poly) in head.consume() {
+            batch.fancy_push(color, poly.rotate_around(angle.rotate_degs(90.0), center));
+        }
+    }
+}
+
 // TODO Kind of a hack to know that the second point is a better center.
 // Returns (center, angle)
 fn crosswalk_icon(geom: &PolyLine) -> (Pt2D, Angle) {
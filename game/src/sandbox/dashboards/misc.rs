@@ -1,11 +1,15 @@
 use crate::app::App;
 use crate::common::Tab;
 use crate::game::{msg, DrawBaselayer, State, Transition};
+use crate::helpers::color_for_mode;
 use crate::sandbox::dashboards::DashTab;
 use crate::sandbox::SandboxMode;
 use ezgui::{
-    Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series, Widget,
+    Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series, Text, TextExt,
+    Widget,
 };
+use geom::{Duration, Time};
+use sim::{PeakHour, PeakHourReport, TripMode};
 
 pub struct ActiveTraffic {
     composite: Composite,
@@ -138,3 +142,159 @@ impl State for BusRoutes {
         self.composite.draw(g);
     }
 }
+
+// How many of the most congested roads and intersections to list for each peak hour
+const TOP_N: usize = 10;
+
+pub struct PeakHours {
+    composite: Composite,
+}
+
+impl PeakHours {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let map = &app.primary.map;
+        let report =
+            PeakHourReport::new(app.primary.sim.get_analytics(), map, app.primary.sim.time());
+
+        let pts = bin_pts(&report.system_volume);
+        let delay_pts = bin_pts(
+            &report
+                .system_delay
+                .iter()
+                .map(|dt| dt.unwrap_or(Duration::ZERO))
+                .collect(),
+        );
+        let mut col = vec![
+            DashTab::PeakHours.picker(ctx, app),
+            Line("Vehicles entering roads, per 15 minutes")
+                .small_heading()
+                .draw(ctx),
+            LinePlot::new(
+                ctx,
+                vec![Series {
+                    label: "Vehicles".to_string(),
+                    color: color_for_mode(app, TripMode::Drive),
+                    pts,
+                }],
+                PlotOptions::fixed(),
+            ),
+            Line("Average delay at traffic signals, per 15 minutes")
+                .small_heading()
+                .draw(ctx)
+                .margin_above(20),
+            LinePlot::new(
+                ctx,
+                vec![Series {
+                    label: "Delay".to_string(),
+                    color: color_for_mode(app, TripMode::Drive),
+                    pts: delay_pts,
+                }],
+                PlotOptions::fixed(),
+            ),
+            Line("Conflicts and finished trips, per 15 minutes")
+                .small_heading()
+                .draw(ctx)
+                .margin_above(20),
+            LinePlot::new(
+                ctx,
+                vec![
+                    Series {
+                        label: "Conflicts".to_string(),
+                        color: app.cs.before_changes,
+                        pts: bin_pts(&report.system_conflicts),
+                    },
+                    Series {
+                        label: "Finished trips".to_string(),
+                        color: app.cs.after_changes,
+                        pts: bin_pts(&report.finished_trips),
+                    },
+                ],
+                PlotOptions::fixed(),
+            ),
+        ];
+        for (name, peak) in vec![("AM", &report.am), ("PM", &report.pm)] {
+            col.push(match peak {
+                Some(peak) => describe_peak(app, name, peak).draw(ctx).margin_above(20),
+                None => format!("No {} peak yet", name)
+                    .draw_text(ctx)
+                    .margin_above(20),
+            });
+        }
+
+        Box::new(PeakHours {
+            composite: Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(10))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+// Each value is for one TIME_BIN, starting at midnight
+fn bin_pts<T: Copy>(values: &Vec<T>) -> Vec<(Time, T)> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(bin, x)| (Time::START_OF_DAY + sim::TIME_BIN * (bin as f64), *x))
+        .collect()
+}
+
+fn describe_peak(app: &App, name: &str, peak: &PeakHour) -> Text {
+    let map = &app.primary.map;
+    let mut txt = Text::from(
+        Line(format!(
+            "{} peak hour: {} to {}",
+            name,
+            peak.start.ampm_tostring(),
+            (peak.start + Duration::hours(1)).ampm_tostring()
+        ))
+        .small_heading(),
+    );
+    txt.add(Line(format!(
+        "{} vehicles, peak hour factor {:.2}",
+        abstutil::prettyprint_usize(peak.volume),
+        peak.peak_hour_factor
+    )));
+    txt.add(Line("Most congested roads").secondary());
+    for r in peak.roads.iter().take(TOP_N) {
+        txt.add(Line(format!(
+            "{}: {} vehicles, V/C {:.2}{}",
+            map.get_r(r.id).get_name(),
+            abstutil::prettyprint_usize(r.volume),
+            r.volume_to_capacity(),
+            r.avg_delay
+                .map(|dt| format!(", {} average signal delay", dt))
+                .unwrap_or_else(String::new)
+        )));
+    }
+    txt.add(Line("Most congested intersections").secondary());
+    for i in peak.intersections.iter().take(TOP_N) {
+        txt.add(Line(format!(
+            "{}: {} vehicles, V/C {:.2}{}",
+            map.get_i(i.id).name(map),
+            abstutil::prettyprint_usize(i.volume),
+            i.volume_to_capacity(),
+            i.avg_delay
+                .map(|dt| format!(", {} average signal delay", dt))
+                .unwrap_or_else(String::new)
+        )));
+    }
+    txt
+}
+
+impl State for PeakHours {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => DashTab::PeakHours.transition(ctx, app, &x),
+            None => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.grass);
+        self.composite.draw(g);
+    }
+}
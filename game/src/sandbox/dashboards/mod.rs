@@ -16,6 +16,7 @@ pub enum DashTab {
     ParkingOverhead,
     ActiveTraffic,
     BusRoutes,
+    PeakHours,
 }
 
 impl DashTab {
@@ -27,6 +28,7 @@ impl DashTab {
             ("parking overhead", DashTab::ParkingOverhead),
             ("active traffic", DashTab::ActiveTraffic),
             ("bus routes", DashTab::BusRoutes),
+            ("peak hours", DashTab::PeakHours),
         ] {
             if tab == DashTab::TripSummaries && app.has_prebaked().is_none() {
                 continue;
@@ -61,6 +63,7 @@ impl DashTab {
             }
             "active traffic" => Transition::Replace(misc::ActiveTraffic::new(ctx, app)),
             "bus routes" => Transition::Replace(misc::BusRoutes::new(ctx, app)),
+            "peak hours" => Transition::Replace(misc::PeakHours::new(ctx, app)),
             _ => unreachable!(),
         }
     }
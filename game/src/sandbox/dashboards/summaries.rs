@@ -1,15 +1,17 @@
 use crate::app::App;
-use crate::game::{DrawBaselayer, State, Transition};
+use crate::game::{msg, DrawBaselayer, State, Transition};
 use crate::helpers::color_for_mode;
 use crate::sandbox::dashboards::DashTab;
 use abstutil::prettyprint_usize;
 use ezgui::{
-    Checkbox, Choice, Color, CompareTimes, Composite, DrawWithTooltips, EventCtx, GeomBatch,
+    Btn, Checkbox, Choice, Color, CompareTimes, Composite, DrawWithTooltips, EventCtx, GeomBatch,
     GfxCtx, Line, Outcome, Text, TextExt, Widget,
 };
 use geom::{Distance, Duration, Polygon, Pt2D};
 use sim::TripMode;
 use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
 
 pub struct TripSummaries {
     composite: Composite,
@@ -42,6 +44,9 @@ impl TripSummaries {
             );
             filters.push(m.ongoing_verb().draw_text(ctx).margin_right(10));
         }
+        if app.has_prebaked().is_some() {
+            filters.push(Btn::text_fg("export report").build_def(ctx, None));
+        }
 
         Box::new(TripSummaries {
             composite: Composite::new(
@@ -70,7 +75,12 @@ impl TripSummaries {
 impl State for TripSummaries {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         match self.composite.event(ctx) {
-            Some(Outcome::Clicked(x)) => DashTab::TripSummaries.transition(ctx, app, &x),
+            Some(Outcome::Clicked(x)) => {
+                if x == "export report" {
+                    return export_report(app);
+                }
+                DashTab::TripSummaries.transition(ctx, app, &x)
+            }
             None => {
                 let mut filter = Filter {
                     changes_pct: self.composite.dropdown_value("filter"),
@@ -100,6 +110,29 @@ impl State for TripSummaries {
     }
 }
 
+// Compares against the prebaked run without edits, for attaching to a proposal
+fn export_report(app: &App) -> Transition {
+    let map = &app.primary.map;
+    let path = format!(
+        "../report_{}_{}.html",
+        map.get_name(),
+        map.get_edits().edits_name
+    );
+    let html = sim::ab_report_html(
+        map,
+        app.prebaked(),
+        app.primary.sim.get_analytics(),
+        app.primary.sim.time(),
+    );
+    match File::create(&path).and_then(|mut f| f.write_all(html.as_bytes())) {
+        Ok(()) => Transition::Push(msg("Exported report", vec![path])),
+        Err(err) => Transition::Push(msg(
+            "Couldn't export report",
+            vec![format!("{}: {}", path, err)],
+        )),
+    }
+}
+
 fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     if app.has_prebaked().is_none() {
         return Widget::nothing();
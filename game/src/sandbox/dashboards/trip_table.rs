@@ -10,14 +10,19 @@ use ezgui::{
     RewriteColor, ScreenDims, ScreenPt, Text, TextExt, Widget,
 };
 use geom::{Distance, Duration, Polygon, Pt2D, Time};
-use sim::{TripEndpoint, TripID, TripMode};
+use log::{info, warn};
+use sim::{TraceFormat, TripEndpoint, TripID, TripMode};
 use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
 
 const ROWS: usize = 10;
 
 pub struct TripTable {
     composite: Composite,
     opts: Options,
+    // Every trip matching the current filters, not just the visible page
+    trips: Vec<TripID>,
 }
 
 struct Options {
@@ -62,16 +67,40 @@ impl TripTable {
             off_map_ends: true,
             skip: 0,
         };
+        let (composite, trips) = make(ctx, app, &opts);
         Box::new(TripTable {
-            composite: make(ctx, app, &opts),
+            composite,
             opts,
+            trips,
         })
     }
 
     fn recalc(&mut self, ctx: &mut EventCtx, app: &App) {
-        let mut new = make(ctx, app, &self.opts);
+        let (mut new, trips) = make(ctx, app, &self.opts);
         new.restore(ctx, &self.composite);
         self.composite = new;
+        self.trips = trips;
+    }
+
+    fn export(&self, ctx: &mut EventCtx, app: &App, format: TraceFormat) {
+        let path = format!(
+            "../trips_{}.{}",
+            app.primary.map.get_name(),
+            format.extension()
+        );
+        let contents = ctx.loading_screen("export trip traces", |_, mut timer| {
+            sim::export_trip_traces(
+                app.primary.sim.get_analytics(),
+                &self.trips,
+                &app.primary.map,
+                format,
+                &mut timer,
+            )
+        });
+        match File::create(&path).and_then(|mut f| f.write_all(contents.as_bytes())) {
+            Ok(()) => info!("Wrote {}", path),
+            Err(err) => warn!("Couldn't write {}: {}", path, err),
+        }
     }
 }
 
@@ -111,6 +140,12 @@ impl State for TripTable {
                     self.opts.skip += ROWS;
                     self.recalc(ctx, app);
                 }
+                "export GeoJSON" => {
+                    self.export(ctx, app, TraceFormat::GeoJSON);
+                }
+                "export GPX" => {
+                    self.export(ctx, app, TraceFormat::GPX);
+                }
                 x => {
                     if let Ok(idx) = x.parse::<usize>() {
                         let trip = TripID(idx);
@@ -178,7 +213,7 @@ struct Entry {
     percent_waiting: usize,
 }
 
-fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
+fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> (Composite, Vec<TripID>) {
     // Gather raw data
     let mut data = Vec::new();
     let sim = &app.primary.sim;
@@ -245,6 +280,7 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
         data.reverse();
     }
     let total_rows = data.len();
+    let trips: Vec<TripID> = data.iter().map(|x| x.trip).collect();
 
     // Render data
     let mut rows = Vec::new();
@@ -329,7 +365,11 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
     col.push(
         Widget::row(vec![
             Checkbox::text(ctx, "starting off-map", None, opts.off_map_starts).margin_right(10),
-            Checkbox::text(ctx, "ending off-map", None, opts.off_map_ends),
+            Checkbox::text(ctx, "ending off-map", None, opts.off_map_ends).margin_right(10),
+            Btn::text_fg("export GeoJSON")
+                .build_def(ctx, None)
+                .margin_right(10),
+            Btn::text_fg("export GPX").build_def(ctx, None),
         ])
         .margin_below(5),
     );
@@ -387,9 +427,12 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
         .margin_above(10),
     );
 
-    Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(10))
-        .exact_size_percent(90, 90)
-        .build(ctx)
+    (
+        Composite::new(Widget::col(col).bg(app.cs.panel_bg).padding(10))
+            .exact_size_percent(90, 90)
+            .build(ctx),
+        trips,
+    )
 }
 
 // TODO Figure out a nicer API to construct generic sortable tables.
@@ -492,7 +535,7 @@ fn preview_route(g: &mut GfxCtx, app: &App, trip: TripID) -> GeomBatch {
         .get_trip_phases(trip, &app.primary.map)
     {
         if let Some((dist, ref path)) = p.path {
-            if let Some(trace) = path.trace(&app.primary.map, dist, None) {
+            if let Ok(trace) = path.trace(&app.primary.map, dist, None) {
                 batch.push(
                     color_for_trip_phase(app, p.phase_type),
                     trace.make_polygons(Distance::meters(20.0)),
@@ -6,7 +6,9 @@ use crate::edit::EditMode;
 use crate::game::{State, Transition};
 use crate::helpers::cmp_duration_shorter;
 use crate::helpers::ID;
-use crate::sandbox::gameplay::{challenge_header, FinalScore, GameplayMode, GameplayState};
+use crate::sandbox::gameplay::{
+    challenge_header, FinalScore, GameplayMode, GameplayState, METER_HACK,
+};
 use crate::sandbox::SandboxControls;
 use ezgui::{
     Btn, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
@@ -16,9 +18,6 @@ use geom::{Duration, Polygon, Time};
 use sim::{OrigPersonID, PersonID, TripID};
 use std::collections::BTreeMap;
 
-// TODO Avoid hack entirely, or tune appearance
-const METER_HACK: f64 = -15.0;
-
 // TODO A nice level to unlock: specifying your own commute, getting to work on it
 
 pub struct OptimizeCommute {
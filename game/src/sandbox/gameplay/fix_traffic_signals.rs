@@ -5,7 +5,9 @@ use crate::cutscene::{CutsceneBuilder, FYI};
 use crate::edit::EditMode;
 use crate::game::{State, Transition};
 use crate::helpers::ID;
-use crate::sandbox::gameplay::{challenge_header, FinalScore, GameplayMode, GameplayState};
+use crate::sandbox::gameplay::{
+    challenge_header, FinalScore, GameplayMode, GameplayState, METER_HACK,
+};
 use crate::sandbox::{SandboxControls, SandboxMode};
 use ezgui::{
     Btn, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
@@ -15,8 +17,6 @@ use geom::{Duration, Polygon, Time};
 use map_model::IntersectionID;
 
 const THRESHOLD: Duration = Duration::const_seconds(20.0 * 60.0);
-// TODO Avoid hack entirely, or tune appearance
-const METER_HACK: f64 = -15.0;
 
 pub struct FixTrafficSignals {
     top_center: Composite,
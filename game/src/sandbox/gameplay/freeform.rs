@@ -12,6 +12,7 @@ use ezgui::{
     Key, Line, Outcome, ScreenRectangle, Spinner, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Polygon};
+use log::warn;
 use map_model::{
     BuildingID, IntersectionID, Map, PathConstraints, PathRequest, Position, NORMAL_LANE_THICKNESS,
 };
@@ -155,10 +156,16 @@ pub fn make_change_traffic(btn: ScreenRectangle, current: String) -> Box<dyn Sta
                                      same trips every day.",
                                 ),
                         );
-                    } else {
+                    } else if name != "synthetic" {
                         list.push(Choice::new(name.clone(), name));
                     }
                 }
+                list.push(
+                    Choice::new("synthetic commutes", "synthetic".to_string()).tooltip(
+                        "People commute between homes and workplaces, based on census data if \
+                         it was imported for this map, or otherwise guessed from the buildings.",
+                    ),
+                );
                 list.push(
                     Choice::new("random unrealistic trips", "random".to_string()).tooltip(
                         "Lots of trips will start at midnight, but not constantly appear through \
@@ -313,6 +320,7 @@ impl State for AgentSpawner {
                 self.goal = Some((
                     to,
                     path.trace(&app.primary.map, Distance::ZERO, None)
+                        .ok()
                         .map(|pl| pl.make_polygons(NORMAL_LANE_THICKNESS)),
                 ));
             } else {
@@ -385,6 +393,7 @@ impl State for AgentSpawner {
                         self.goal = Some((
                             hovering,
                             path.trace(&app.primary.map, Distance::ZERO, None)
+                                .ok()
                                 .map(|pl| pl.make_polygons(NORMAL_LANE_THICKNESS)),
                         ));
                     } else {
@@ -504,7 +513,7 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
     let mut spawner = sim.make_spawner();
 
     if map.all_buildings().is_empty() {
-        println!("No buildings, can't pick destinations");
+        warn!("No buildings, can't pick destinations");
         return;
     }
 
@@ -2,6 +2,7 @@
 pub mod commute;
 pub mod fix_traffic_signals;
 mod freeform;
+pub mod optimize_bus;
 mod play_scenario;
 mod tutorial;
 
@@ -21,9 +22,14 @@ use ezgui::{
     Widget, Wizard,
 };
 use geom::{Duration, Polygon};
+use log::{error, warn};
 use map_model::{EditCmd, EditIntersection, Map, MapEdits};
 use rand_xorshift::XorShiftRng;
-use sim::{Analytics, OrigPersonID, Scenario, ScenarioGenerator};
+use sim::{Analytics, OrigPersonID, Population, Scenario, ScenarioGenerator};
+
+// Where challenges put their own meter, below the agent meter.
+// TODO Avoid hack entirely, or tune appearance
+const METER_HACK: f64 = -15.0;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum GameplayMode {
@@ -34,6 +40,7 @@ pub enum GameplayMode {
     PlayScenario(String, String),
     FixTrafficSignals,
     OptimizeCommute(OrigPersonID, Duration),
+    OptimizeBus(optimize_bus::BusObjective),
 
     // current
     Tutorial(TutorialPointer),
@@ -83,6 +90,7 @@ impl GameplayMode {
             GameplayMode::PlayScenario(ref path, _) => path.to_string(),
             GameplayMode::FixTrafficSignals => abstutil::path_map("downtown"),
             GameplayMode::OptimizeCommute(_, _) => abstutil::path_map("montlake"),
+            GameplayMode::OptimizeBus(_) => abstutil::path_map("montlake"),
             GameplayMode::Tutorial(_) => abstutil::path_map("montlake"),
         }
     }
@@ -114,6 +122,11 @@ impl GameplayMode {
                 ScenarioGenerator::small_run(map)
             })
             .generate(map, &mut rng, &mut Timer::new("generate scenario"))
+        } else if name == "synthetic"
+            && !abstutil::file_exists(abstutil::path_scenario(map.get_name(), &name))
+        {
+            // The importer didn't make one from census data, so guess from the buildings
+            Population::guess(map).make_scenario(&name, map, &mut rng, timer)
         } else if name == "5 weekdays repeated" {
             let s: Scenario =
                 abstutil::read_binary(abstutil::path_scenario(map.get_name(), "weekday"), timer);
@@ -123,8 +136,8 @@ impl GameplayMode {
             match abstutil::maybe_read_binary(path.clone(), timer) {
                 Ok(s) => s,
                 Err(err) => {
-                    println!("\n\n{} is missing or corrupt. Check https://github.com/dabreegster/abstreet/blob/master/docs/dev.md and file an issue if you have trouble.", path);
-                    println!("\n{}", err);
+                    error!("{} is missing or corrupt. Check https://github.com/dabreegster/abstreet/blob/master/docs/dev.md and file an issue if you have trouble.", path);
+                    error!("{}", err);
                     std::process::exit(1);
                 }
             }
@@ -153,11 +166,26 @@ impl GameplayMode {
     }
 
     pub fn allows(&self, edits: &MapEdits) -> bool {
+        if let GameplayMode::OptimizeBus(ref objective) = self {
+            if edits.commands.len() > objective.edit_budget {
+                return false;
+            }
+        }
         for cmd in &edits.commands {
             match cmd {
                 EditCmd::ChangeLaneType { .. }
                 | EditCmd::ReverseLane { .. }
-                | EditCmd::ChangeSpeedLimit { .. } => {
+                | EditCmd::ChangeLaneWidth { .. }
+                | EditCmd::ChangeSpeedLimit { .. }
+                | EditCmd::ChangeSpeedLimitSchedule { .. }
+                | EditCmd::ChangeNoThroughTraffic { .. }
+                | EditCmd::ChangeBannedVehicles { .. }
+                | EditCmd::ChangeModalFilter { .. }
+                | EditCmd::ChangeCrosswalk { .. }
+                | EditCmd::ChangeUTurns { .. }
+                | EditCmd::AddBusStop { .. }
+                | EditCmd::RemoveBusStop { .. }
+                | EditCmd::MoveBusStop { .. } => {
                     if !self.can_edit_lanes() {
                         return false;
                     }
@@ -171,6 +199,8 @@ impl GameplayMode {
                     }
                     _ => {}
                 },
+                // Same as editing the signal
+                EditCmd::ChangeRightTurnOnRed { .. } => {}
             }
         }
         true
@@ -180,14 +210,19 @@ impl GameplayMode {
         ctx.loading_screen("setup challenge", |ctx, timer| {
             if &abstutil::basename(&self.map_path()) != app.primary.map.get_name() {
                 app.switch_map(ctx, self.map_path());
+                // If the map was already loaded, it might have a sim running
+                app.primary.clear_sim();
             }
 
-            if let Some(scenario) = self.scenario(
+            if let Some(mut scenario) = self.scenario(
                 &app.primary.map,
                 app.primary.current_flags.num_agents,
                 app.primary.current_flags.sim_flags.make_rng(),
                 timer,
             ) {
+                scenario
+                    .bus_service
+                    .extend(app.primary.current_flags.bus_service.clone());
                 scenario.instantiate(
                     &mut app.primary.sim,
                     &app.primary.map,
@@ -205,25 +240,26 @@ impl GameplayMode {
                     .unwrap_or(false)
                 {
                     // If there's no prebaked data, so be it; some functionality disappears
-                    if let Ok(prebaked) = abstutil::maybe_read_binary::<Analytics>(
-                        abstutil::path_prebaked_results(
-                            &scenario.map_name,
-                            &scenario.scenario_name,
-                        ),
+                    match Analytics::load_prebaked(
+                        &scenario.map_name,
+                        &scenario.scenario_name,
                         timer,
                     ) {
-                        app.set_prebaked(Some((
-                            scenario.map_name.clone(),
-                            scenario.scenario_name.clone(),
-                            prebaked,
-                        )));
-                    } else {
-                        println!(
-                            "WARNING: Missing or corrupt prebaked results for {} on {}, some \
-                             stuff might break",
-                            scenario.scenario_name, scenario.map_name
-                        );
-                        app.set_prebaked(None);
+                        Ok(prebaked) => {
+                            app.set_prebaked(Some((
+                                scenario.map_name.clone(),
+                                scenario.scenario_name.clone(),
+                                prebaked,
+                            )));
+                        }
+                        Err(err) => {
+                            warn!(
+                                "Missing or outdated prebaked results for {} on {}, \
+                                 some stuff might break: {}",
+                                scenario.scenario_name, scenario.map_name, err
+                            );
+                            app.set_prebaked(None);
+                        }
                     }
                 }
             }
@@ -239,6 +275,16 @@ impl GameplayMode {
             GameplayMode::OptimizeCommute(p, goal) => {
                 commute::OptimizeCommute::new(ctx, app, *p, *goal)
             }
+            GameplayMode::OptimizeBus(ref objective) => {
+                optimize_bus::OptimizeBus::new(ctx, app, objective.clone()).unwrap_or_else(|| {
+                    warn!(
+                        "{} has no bus route {}, playing freeform instead",
+                        app.primary.map.get_name(),
+                        objective.route
+                    );
+                    freeform::Freeform::new(ctx, app, GameplayMode::Freeform(self.map_path()))
+                })
+            }
             GameplayMode::Tutorial(current) => Tutorial::new(ctx, app, *current),
         }
     }
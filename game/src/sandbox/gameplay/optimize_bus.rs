@@ -0,0 +1,427 @@
+use crate::app::App;
+use crate::challenges::{Challenge, HighScore};
+use crate::cutscene::{CutsceneBuilder, FYI};
+use crate::edit::EditMode;
+use crate::game::{State, Transition};
+use crate::helpers::cmp_duration_shorter;
+use crate::sandbox::gameplay::{
+    challenge_header, FinalScore, GameplayMode, GameplayState, METER_HACK,
+};
+use crate::sandbox::SandboxControls;
+use ezgui::{
+    Btn, Color, Composite, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome,
+    RewriteColor, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Duration, Polygon, Time};
+use map_model::BusRouteID;
+use sim::{Analytics, TripMode};
+
+// Everything that defines one of these challenges. Percentages are whole numbers so this can live
+// in GameplayMode.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct BusObjective {
+    pub route: String,
+    // Reduce the mean time buses on the route take between stops by at least this much
+    pub faster_by_percent: usize,
+    // ... while the total time spent in finished driving trips grows by at most this much
+    pub max_car_slowdown_percent: usize,
+    // How many edit commands are allowed
+    pub edit_budget: usize,
+    // The score is calculated this long after the start of the day
+    pub duration: Duration,
+}
+
+pub struct OptimizeBus {
+    top_center: Composite,
+    meter: Composite,
+    route: BusRouteID,
+    objective: BusObjective,
+    mode: GameplayMode,
+    time: Time,
+    done: bool,
+}
+
+impl OptimizeBus {
+    // None if the map doesn't have the objective's route
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        objective: BusObjective,
+    ) -> Option<Box<dyn GameplayState>> {
+        let route = app.primary.map.get_bus_route(&objective.route)?.id;
+        Some(Box::new(OptimizeBus {
+            top_center: Composite::new(
+                Widget::col(vec![
+                    challenge_header(ctx, &format!("Speed up route {}", objective.route)),
+                    Widget::row(vec![
+                        format!(
+                            "Make buses {}% faster, slow cars down by at most {}%, with {} edits",
+                            objective.faster_by_percent,
+                            objective.max_car_slowdown_percent,
+                            objective.edit_budget
+                        )
+                        .draw_text(ctx)
+                        .centered_vert()
+                        .margin_right(16),
+                        Btn::svg(
+                            "../data/system/assets/tools/hint.svg",
+                            RewriteColor::Change(Color::WHITE, app.cs.hovering),
+                        )
+                        .build(ctx, "hint", None)
+                        .align_right(),
+                    ]),
+                ])
+                .bg(app.cs.panel_bg)
+                .padding(16),
+            )
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            meter: make_meter(ctx, app, &objective, &Score::empty()),
+            route,
+            mode: GameplayMode::OptimizeBus(objective.clone()),
+            objective,
+            time: Time::START_OF_DAY,
+            done: false,
+        }))
+    }
+
+    pub fn cutscene_pt1(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Box<dyn State> {
+        CutsceneBuilder::new("Speed up one bus route")
+            .boss("The transit agency is on the phone again. Their riders are furious.")
+            .player("Let me guess, the bus is stuck in the same traffic as everybody else?")
+            .boss("Bingo. Make that route faster, and do it before the council meeting.")
+            .player("Bus lanes, coming right up!")
+            .boss(
+                "Not so fast. If drivers get much slower, I'm the one getting yelled at. And we \
+                 only have the budget for a handful of changes.",
+            )
+            .player("(A few well-placed edits, then. How hard can it be?)")
+            .build(ctx, app, cutscene_task(mode))
+    }
+}
+
+impl GameplayState for OptimizeBus {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        controls: &mut SandboxControls,
+    ) -> Option<Transition> {
+        if let Some(ref agent_meter) = controls.agent_meter {
+            self.meter
+                .align_below(ctx, &agent_meter.composite, METER_HACK);
+        }
+
+        if self.time != app.primary.sim.time() && !self.done {
+            self.time = app.primary.sim.time();
+
+            let score = Score::new(app, self.route);
+            self.meter = make_meter(ctx, app, &self.objective, &score);
+            if let Some(ref agent_meter) = controls.agent_meter {
+                self.meter
+                    .align_below(ctx, &agent_meter.composite, METER_HACK);
+            }
+
+            if self.time >= Time::START_OF_DAY + self.objective.duration {
+                self.done = true;
+                return Some(Transition::Push(final_score(
+                    ctx,
+                    app,
+                    self.mode.clone(),
+                    &self.objective,
+                    score,
+                )));
+            }
+        }
+
+        match self.top_center.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "edit map" => {
+                    return Some(Transition::Push(Box::new(EditMode::new(
+                        ctx,
+                        app,
+                        self.mode.clone(),
+                    ))));
+                }
+                "instructions" => {
+                    let contents = (cutscene_task(&self.mode))(ctx);
+                    return Some(Transition::Push(FYI::new(ctx, contents, Color::WHITE)));
+                }
+                "hint" => {
+                    let mut txt = Text::from(Line("Hints"));
+                    txt.add(Line(""));
+                    txt.add(Line("Click a bus stop or a bus to see the route's delays."));
+                    txt.add(Line("Bus lanes help the most where buses wait in queues."));
+                    txt.add(Line("Taking away the only driving lane can backfire."));
+                    let contents = txt.draw(ctx);
+                    return Some(Transition::Push(FYI::new(ctx, contents, app.cs.panel_bg)));
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        None
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.top_center.draw(g);
+        self.meter.draw(g);
+    }
+}
+
+struct Score {
+    // Mean time between consecutive stops, before and after the edits
+    bus_before: Option<Duration>,
+    bus_after: Option<Duration>,
+    // Total time of driving trips that've finished in both worlds
+    cars_before: Duration,
+    cars_after: Duration,
+}
+
+impl Score {
+    fn empty() -> Score {
+        Score {
+            bus_before: None,
+            bus_after: None,
+            cars_before: Duration::ZERO,
+            cars_after: Duration::ZERO,
+        }
+    }
+
+    fn new(app: &App, route: BusRouteID) -> Score {
+        let now = app.primary.sim.time();
+        let analytics = app.primary.sim.get_analytics();
+        let mut cars_before = Duration::ZERO;
+        let mut cars_after = Duration::ZERO;
+        for (before, after, mode) in analytics.both_finished_trips(now, app.prebaked()) {
+            if mode == TripMode::Drive {
+                cars_before += before;
+                cars_after += after;
+            }
+        }
+        Score {
+            bus_before: mean_bus_delay(app.prebaked(), now, route),
+            bus_after: mean_bus_delay(analytics, now, route),
+            cars_before,
+            cars_after,
+        }
+    }
+
+    // How much time buses save between stops, and that as a percent. Positive means buses got
+    // faster.
+    fn bus_improvement(&self) -> Option<(Duration, f64)> {
+        let before = self.bus_before?;
+        let after = self.bus_after?;
+        if before == Duration::ZERO {
+            return None;
+        }
+        Some((before - after, 100.0 * (1.0 - after / before)))
+    }
+
+    // Positive means cars got slower
+    fn car_slowdown_percent(&self) -> f64 {
+        if self.cars_before == Duration::ZERO {
+            return 0.0;
+        }
+        100.0 * (self.cars_after / self.cars_before - 1.0)
+    }
+}
+
+fn mean_bus_delay(analytics: &Analytics, now: Time, route: BusRouteID) -> Option<Duration> {
+    let mut sum = Duration::ZERO;
+    let mut cnt = 0;
+    for delays in analytics.bus_arrivals_over_time(now, route).values() {
+        for (_, dt) in delays {
+            sum += *dt;
+            cnt += 1;
+        }
+    }
+    if cnt == 0 {
+        None
+    } else {
+        Some(sum / (cnt as f64))
+    }
+}
+
+fn make_meter(ctx: &mut EventCtx, app: &App, objective: &BusObjective, score: &Score) -> Composite {
+    let mut bus_txt = Text::from(Line("Time between stops: "));
+    if let (Some(before), Some(after)) = (score.bus_before, score.bus_after) {
+        bus_txt.append(Line(format!("{} (", after)));
+        bus_txt.append_all(cmp_duration_shorter(after, before));
+        bus_txt.append(Line(")"));
+    } else {
+        bus_txt.append(Line("no data yet").secondary());
+    }
+
+    let slowdown = score.car_slowdown_percent();
+    let car_txt = Text::from_all(vec![
+        Line("Driving trips: "),
+        Line(format!("{:+.1}%", slowdown)).fg(
+            if slowdown > objective.max_car_slowdown_percent as f64 {
+                Color::RED
+            } else {
+                Color::GREEN
+            },
+        ),
+    ]);
+
+    let edits = app.primary.map.get_edits().commands.len();
+
+    Composite::new(
+        Widget::col(vec![
+            // Separator
+            Widget::draw_batch(
+                ctx,
+                GeomBatch::from(vec![(
+                    Color::WHITE,
+                    Polygon::rectangle(0.2 * ctx.canvas.window_width / ctx.get_scale_factor(), 2.0),
+                )]),
+            )
+            .margin(15)
+            .centered_horiz(),
+            bus_txt.draw(ctx),
+            car_txt.draw(ctx),
+            format!("{}/{} edits used", edits, objective.edit_budget).draw_text(ctx),
+        ])
+        .bg(app.cs.panel_bg)
+        .padding(20),
+    )
+    .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+    .build(ctx)
+}
+
+fn final_score(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    mode: GameplayMode,
+    objective: &BusObjective,
+    score: Score,
+) -> Box<dyn State> {
+    let mut next_mode: Option<GameplayMode> = None;
+    let slowdown = score.car_slowdown_percent();
+
+    let msg = match score.bus_improvement() {
+        None => format!(
+            "Not a single bus on route {} made it between two stops. What happened out there?!",
+            objective.route
+        ),
+        Some((_, pct)) if pct <= 0.0 => format!(
+            "Route {} didn't get any faster. The transit agency is going to have my head!",
+            objective.route
+        ),
+        Some((_, pct)) if pct < objective.faster_by_percent as f64 => format!(
+            "Buses on route {} are {:.1}% faster. That's a start, but I asked for {}%.",
+            objective.route, pct, objective.faster_by_percent
+        ),
+        Some(_) if slowdown > objective.max_car_slowdown_percent as f64 => format!(
+            "Sure, the buses are faster, but driving trips are {:.1}% slower. The phones are \
+             ringing off the hook! I said no more than {}%.",
+            slowdown, objective.max_car_slowdown_percent
+        ),
+        Some((saved, pct)) => {
+            HighScore {
+                goal: format!(
+                    "make route {} {}% faster, slowing cars by at most {}%",
+                    objective.route,
+                    objective.faster_by_percent,
+                    objective.max_car_slowdown_percent
+                ),
+                score: saved,
+                edits_name: app.primary.map.get_edits().edits_name.clone(),
+            }
+            .record(app, mode.clone());
+
+            next_mode = Challenge::find(&mode).1.map(|c| c.gameplay);
+
+            format!(
+                "Route {} is {:.1}% faster, and drivers barely noticed ({:+.1}%). Nice work!",
+                objective.route, pct, slowdown
+            )
+        }
+    };
+
+    FinalScore::new(ctx, app, msg, mode, next_mode)
+}
+
+fn cutscene_task(mode: &GameplayMode) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
+    let objective = match mode {
+        GameplayMode::OptimizeBus(ref o) => o.clone(),
+        _ => unreachable!(),
+    };
+
+    Box::new(move |ctx| {
+        Widget::col(vec![
+            Text::from_multiline(vec![
+                Line(format!(
+                    "Make buses on route {} at least {}% faster",
+                    objective.route, objective.faster_by_percent
+                ))
+                .fg(Color::BLACK),
+                Line(format!(
+                    "Don't slow down driving trips by more than {}%",
+                    objective.max_car_slowdown_percent
+                ))
+                .fg(Color::BLACK),
+                Line(format!(
+                    "You can make at most {} edits",
+                    objective.edit_budget
+                ))
+                .fg(Color::BLACK),
+            ])
+            .draw(ctx)
+            .margin_below(30),
+            Widget::row(vec![
+                Widget::col(vec![
+                    Line("Time").fg(Color::BLACK).draw(ctx),
+                    Widget::draw_svg_transform(
+                        ctx,
+                        "../data/system/assets/tools/time.svg",
+                        RewriteColor::ChangeAll(Color::BLACK),
+                    )
+                    .margin_below(5)
+                    .margin_above(5),
+                    Line(objective.duration.to_string())
+                        .fg(Color::BLACK)
+                        .draw(ctx),
+                ]),
+                Widget::col(vec![
+                    Line("Goal").fg(Color::BLACK).draw(ctx),
+                    Widget::draw_svg_transform(
+                        ctx,
+                        "../data/system/assets/tools/location.svg",
+                        RewriteColor::ChangeAll(Color::BLACK),
+                    )
+                    .margin_below(5)
+                    .margin_above(5),
+                    Text::from_multiline(vec![
+                        Line(format!("Buses {}% faster", objective.faster_by_percent))
+                            .fg(Color::BLACK),
+                        Line(format!(
+                            "Cars at most {}% slower",
+                            objective.max_car_slowdown_percent
+                        ))
+                        .fg(Color::BLACK),
+                    ])
+                    .draw(ctx),
+                ]),
+                Widget::col(vec![
+                    Line("Score").fg(Color::BLACK).draw(ctx),
+                    Widget::draw_svg_transform(
+                        ctx,
+                        "../data/system/assets/tools/star.svg",
+                        RewriteColor::ChangeAll(Color::BLACK),
+                    )
+                    .margin_below(5)
+                    .margin_above(5),
+                    Text::from_multiline(vec![
+                        Line("How much time buses").fg(Color::BLACK),
+                        Line("save between stops").fg(Color::BLACK),
+                    ])
+                    .draw(ctx),
+                ]),
+            ])
+            .evenly_spaced(),
+        ])
+    })
+}
@@ -17,6 +17,7 @@ use ezgui::{
     VerticalAlignment, Widget,
 };
 use geom::{ArrowCap, Distance, Duration, PolyLine, Polygon, Pt2D, Time};
+use log::warn;
 use map_model::raw::{OriginalIntersection, OriginalRoad};
 use map_model::{BuildingID, Map, OriginalLane, Position};
 use sim::{
@@ -30,7 +31,8 @@ const CAR_BIKE_CONTENTION_GOAL: Duration = Duration::const_seconds(60.0);
 
 pub struct Tutorial {
     top_center: Composite,
-    last_finished_task: Task,
+    // Everything unlocked by this stage and the previous ones
+    unlocked: Vec<Unlock>,
 
     msg_panel: Option<Composite>,
     warped: bool,
@@ -121,11 +123,8 @@ impl Tutorial {
                     return Some(transition(ctx, app, tut));
                 }
                 "edit map" => {
-                    // TODO Ideally this would be an inactive button in message states
-                    if self.msg_panel.is_none() {
-                        let mode = GameplayMode::Tutorial(tut.current);
-                        return Some(Transition::Push(Box::new(EditMode::new(ctx, app, mode))));
-                    }
+                    let mode = GameplayMode::Tutorial(tut.current);
+                    return Some(Transition::Push(Box::new(EditMode::new(ctx, app, mode))));
                 }
                 _ => unreachable!(),
             },
@@ -152,173 +151,21 @@ impl Tutorial {
             }
         }
 
-        // Interaction things
-        if tut.interaction() == Task::Camera {
-            if app.primary.current_selection == Some(ID::Building(tut.fire_station))
-                && app.per_obj.left_click(ctx, "put out the... fire?")
-            {
-                tut.next();
-                return Some(transition(ctx, app, tut));
-            }
-        } else if tut.interaction() == Task::InspectObjects {
-            // TODO Have to wiggle the mouse or something after opening the panel, because of the
-            // order in SandboxMode.
-            match controls.common.as_ref().unwrap().info_panel_open(app) {
-                Some(ID::Lane(l)) => {
-                    if app.primary.map.get_l(l).is_biking() && !tut.inspected_bike_lane {
-                        tut.inspected_bike_lane = true;
-                        self.top_center = tut.make_top_center(ctx, &app.cs, false);
-                    }
-                }
-                Some(ID::Building(_)) => {
-                    if !tut.inspected_building {
-                        tut.inspected_building = true;
-                        self.top_center = tut.make_top_center(ctx, &app.cs, false);
-                    }
-                }
-                Some(ID::Intersection(i)) => {
-                    let i = app.primary.map.get_i(i);
-                    if i.is_stop_sign() && !tut.inspected_stop_sign {
-                        tut.inspected_stop_sign = true;
-                        self.top_center = tut.make_top_center(ctx, &app.cs, false);
-                    }
-                    if i.is_border() && !tut.inspected_border {
-                        tut.inspected_border = true;
-                        self.top_center = tut.make_top_center(ctx, &app.cs, false);
-                    }
-                }
-                _ => {}
-            }
-            if tut.inspected_bike_lane
-                && tut.inspected_building
-                && tut.inspected_stop_sign
-                && tut.inspected_border
-            {
-                tut.next();
-                return Some(transition(ctx, app, tut));
-            }
-        } else if tut.interaction() == Task::TimeControls {
-            if app.primary.sim.time() >= Time::START_OF_DAY + Duration::hours(17) {
-                tut.next();
-                return Some(transition(ctx, app, tut));
-            }
-        } else if tut.interaction() == Task::PauseResume {
-            let is_paused = controls.speed.as_ref().unwrap().is_paused();
-            if tut.was_paused && !is_paused {
-                tut.was_paused = false;
-            }
-            if !tut.was_paused && is_paused {
-                tut.num_pauses += 1;
-                tut.was_paused = true;
-                self.top_center = tut.make_top_center(ctx, &app.cs, false);
-            }
-            if tut.num_pauses == 3 {
-                tut.next();
-                return Some(transition(ctx, app, tut));
-            }
-        } else if tut.interaction() == Task::Escort {
-            let following_car =
-                controls.common.as_ref().unwrap().info_panel_open(app) == Some(ID::Car(ESCORT));
-            let is_parked = app
-                .primary
-                .sim
-                .agent_to_trip(AgentID::Car(ESCORT))
-                .is_none();
-            if !tut.car_parked && is_parked && tut.following_car {
-                tut.car_parked = true;
-                self.top_center = tut.make_top_center(ctx, &app.cs, false);
-            }
-
-            if following_car && !tut.following_car {
-                // TODO There's a delay of one event before the checklist updates, because the
-                // info panel opening happens at the end of the event. Not a big deal.
-                tut.following_car = true;
-                self.top_center = tut.make_top_center(ctx, &app.cs, false);
+        // Has the player finished the task yet?
+        let check = tut.stage().check;
+        match check(ctx, app, controls, tut) {
+            Progress::Waiting => {}
+            Progress::Partial => {
+                self.top_center =
+                    tut.make_top_center(ctx, &app.cs, self.unlocked.contains(&Unlock::EditMap));
             }
-
-            if tut.prank_done {
+            Progress::Done => {
                 tut.next();
                 return Some(transition(ctx, app, tut));
             }
-        } else if tut.interaction() == Task::LowParking {
-            if tut.parking_found {
-                tut.next();
-                return Some(transition(ctx, app, tut));
-            }
-        } else if tut.interaction() == Task::WatchBikes {
-            if app.primary.sim.time() >= Time::START_OF_DAY + Duration::minutes(3) {
-                tut.next();
-                return Some(transition(ctx, app, tut));
-            }
-        } else if tut.interaction() == Task::FixBikes {
-            if app.primary.sim.is_done() {
-                let mut before = Duration::ZERO;
-                let mut after = Duration::ZERO;
-                for (b, a, _) in app
-                    .primary
-                    .sim
-                    .get_analytics()
-                    .both_finished_trips(app.primary.sim.get_end_of_day(), app.prebaked())
-                {
-                    before = before.max(b);
-                    after = after.max(a);
-                }
-                if !tut.score_delivered {
-                    tut.score_delivered = true;
-                    if before == after {
-                        return Some(Transition::Push(msg(
-                            "All trips completed",
-                            vec![
-                                "Your changes didn't affect anything!",
-                                "Try editing the map to create some bike lanes.",
-                            ],
-                        )));
-                    }
-                    if after > before {
-                        return Some(Transition::Push(msg(
-                            "All trips completed",
-                            vec![
-                                "Your changes made things worse!".to_string(),
-                                format!(
-                                    "All trips originally finished in {}, but now they took {}",
-                                    before, after
-                                ),
-                                "".to_string(),
-                                "Try again!".to_string(),
-                            ],
-                        )));
-                    }
-                    if before - after < CAR_BIKE_CONTENTION_GOAL {
-                        return Some(Transition::Push(msg(
-                            "All trips completed",
-                            vec![
-                                "Nice, you helped things a bit!".to_string(),
-                                format!(
-                                    "All trips originally took {}, but now they took {}",
-                                    before, after
-                                ),
-                                "".to_string(),
-                                "See if you can do a little better though.".to_string(),
-                            ],
-                        )));
-                    }
-                    return Some(Transition::Push(msg(
-                        "All trips completed",
-                        vec![format!(
-                            "Awesome! All trips originally took {}, but now they only took {}",
-                            before, after
-                        )],
-                    )));
-                }
-                if before - after >= CAR_BIKE_CONTENTION_GOAL {
-                    tut.next();
-                }
-                return Some(transition(ctx, app, tut));
+            Progress::Transition(t) => {
+                return Some(t);
             }
-        } else if tut.interaction() == Task::Done {
-            // If the player chooses to stay here, at least go back to the message panel.
-            tut.prev();
-            return Some(maybe_exit_sandbox());
         }
 
         None
@@ -393,25 +240,25 @@ impl GameplayState for Tutorial {
         self.msg_panel.is_none()
     }
     fn can_examine_objects(&self) -> bool {
-        self.last_finished_task >= Task::WatchBikes
+        self.unlocked.contains(&Unlock::ExamineObjects)
     }
     fn has_common(&self) -> bool {
-        self.last_finished_task >= Task::Camera
+        self.unlocked.contains(&Unlock::Common)
     }
     fn has_tool_panel(&self) -> bool {
         true
     }
     fn has_time_panel(&self) -> bool {
-        self.last_finished_task >= Task::InspectObjects
+        self.unlocked.contains(&Unlock::TimeControls)
     }
     fn has_speed(&self) -> bool {
-        self.last_finished_task >= Task::InspectObjects
+        self.unlocked.contains(&Unlock::TimeControls)
     }
     fn has_agent_meter(&self) -> bool {
-        self.last_finished_task >= Task::PauseResume
+        self.unlocked.contains(&Unlock::AgentMeter)
     }
     fn has_minimap(&self) -> bool {
-        self.last_finished_task >= Task::Escort
+        self.unlocked.contains(&Unlock::Minimap)
     }
 }
 
@@ -517,6 +364,32 @@ impl Task {
     }
 }
 
+// Parts of the UI that're hidden until some stage introduces them. Once a stage unlocks something,
+// it stays available in all later stages.
+#[derive(Clone, Copy, PartialEq)]
+enum Unlock {
+    Common,
+    TimeControls,
+    AgentMeter,
+    Minimap,
+    ExamineObjects,
+    EditMap,
+}
+
+// The result of checking the player's progress on a stage's task
+enum Progress {
+    Waiting,
+    // Part of the task is done, so the checklist needs to be updated
+    Partial,
+    Done,
+    // Something else has to happen first, like telling the player how they did
+    Transition(Transition),
+}
+
+// Called on every event while the player is working on a stage's task, but not while they're
+// reading the instructions. Records any progress in TutorialState.
+type Check = fn(&mut EventCtx, &mut App, &SandboxControls, &mut TutorialState) -> Progress;
+
 struct Stage {
     messages: Vec<(
         Vec<String>,
@@ -526,6 +399,8 @@ struct Stage {
     task: Task,
     warp_to: Option<(ID, f64)>,
     spawn: Option<Box<dyn Fn(&mut App)>>,
+    unlocks: Vec<Unlock>,
+    check: Check,
 }
 
 fn arrow(pt: ScreenPt) -> Option<Box<dyn Fn(&GfxCtx, &App) -> Pt2D>> {
@@ -539,6 +414,8 @@ impl Stage {
             task,
             warp_to: None,
             spawn: None,
+            unlocks: Vec::new(),
+            check: |_, _, _, _| Progress::Waiting,
         }
     }
 
@@ -573,6 +450,16 @@ impl Stage {
         self
     }
 
+    fn unlock(mut self, unlocks: Vec<Unlock>) -> Stage {
+        self.unlocks.extend(unlocks);
+        self
+    }
+
+    fn check(mut self, check: Check) -> Stage {
+        self.check = check;
+        self
+    }
+
     fn spawn(mut self, cb: Box<dyn Fn(&mut App)>) -> Stage {
         assert!(self.spawn.is_none());
         self.spawn = Some(cb);
@@ -594,15 +481,20 @@ impl Stage {
                 &mut timer,
             );
 
-            let prebaked: Analytics = abstutil::read_binary(
-                abstutil::path_prebaked_results(&scenario.map_name, &scenario.scenario_name),
-                &mut timer,
-            );
-            app.set_prebaked(Some((
-                scenario.map_name.clone(),
-                scenario.scenario_name.clone(),
-                prebaked,
-            )));
+            match Analytics::load_prebaked(&scenario.map_name, &scenario.scenario_name, &mut timer)
+            {
+                Ok(prebaked) => {
+                    app.set_prebaked(Some((
+                        scenario.map_name.clone(),
+                        scenario.scenario_name.clone(),
+                        prebaked,
+                    )));
+                }
+                Err(err) => {
+                    warn!("the tutorial's comparisons won't work: {}", err);
+                    app.set_prebaked(None);
+                }
+            }
         }))
     }
 }
@@ -657,6 +549,232 @@ fn transition(ctx: &mut EventCtx, app: &mut App, tut: &mut TutorialState) -> Tra
     Transition::Replace(Box::new(SandboxMode::new(ctx, app, mode)))
 }
 
+fn check_camera(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    _: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    if app.primary.current_selection == Some(ID::Building(tut.fire_station))
+        && app.per_obj.left_click(ctx, "put out the... fire?")
+    {
+        return Progress::Done;
+    }
+    Progress::Waiting
+}
+
+fn check_inspect_objects(
+    _: &mut EventCtx,
+    app: &mut App,
+    controls: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    let mut progress = Progress::Waiting;
+    // TODO Have to wiggle the mouse or something after opening the panel, because of the order
+    // in SandboxMode.
+    match controls.common.as_ref().unwrap().info_panel_open(app) {
+        Some(ID::Lane(l)) => {
+            if app.primary.map.get_l(l).is_biking() && !tut.inspected_bike_lane {
+                tut.inspected_bike_lane = true;
+                progress = Progress::Partial;
+            }
+        }
+        Some(ID::Building(_)) => {
+            if !tut.inspected_building {
+                tut.inspected_building = true;
+                progress = Progress::Partial;
+            }
+        }
+        Some(ID::Intersection(i)) => {
+            let i = app.primary.map.get_i(i);
+            if i.is_stop_sign() && !tut.inspected_stop_sign {
+                tut.inspected_stop_sign = true;
+                progress = Progress::Partial;
+            }
+            if i.is_border() && !tut.inspected_border {
+                tut.inspected_border = true;
+                progress = Progress::Partial;
+            }
+        }
+        _ => {}
+    }
+    if tut.inspected_bike_lane
+        && tut.inspected_building
+        && tut.inspected_stop_sign
+        && tut.inspected_border
+    {
+        return Progress::Done;
+    }
+    progress
+}
+
+fn check_time_controls(
+    _: &mut EventCtx,
+    app: &mut App,
+    _: &SandboxControls,
+    _: &mut TutorialState,
+) -> Progress {
+    if app.primary.sim.time() >= Time::START_OF_DAY + Duration::hours(17) {
+        return Progress::Done;
+    }
+    Progress::Waiting
+}
+
+fn check_pause_resume(
+    _: &mut EventCtx,
+    _: &mut App,
+    controls: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    let mut progress = Progress::Waiting;
+    let is_paused = controls.speed.as_ref().unwrap().is_paused();
+    if tut.was_paused && !is_paused {
+        tut.was_paused = false;
+    }
+    if !tut.was_paused && is_paused {
+        tut.num_pauses += 1;
+        tut.was_paused = true;
+        progress = Progress::Partial;
+    }
+    if tut.num_pauses == 3 {
+        return Progress::Done;
+    }
+    progress
+}
+
+fn check_escort(
+    _: &mut EventCtx,
+    app: &mut App,
+    controls: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    let mut progress = Progress::Waiting;
+    let following_car =
+        controls.common.as_ref().unwrap().info_panel_open(app) == Some(ID::Car(ESCORT));
+    let is_parked = app
+        .primary
+        .sim
+        .agent_to_trip(AgentID::Car(ESCORT))
+        .is_none();
+    if !tut.car_parked && is_parked && tut.following_car {
+        tut.car_parked = true;
+        progress = Progress::Partial;
+    }
+
+    if following_car && !tut.following_car {
+        // TODO There's a delay of one event before the checklist updates, because the info panel
+        // opening happens at the end of the event. Not a big deal.
+        tut.following_car = true;
+        progress = Progress::Partial;
+    }
+
+    // The last step happens through actions()/execute()
+    if tut.prank_done {
+        return Progress::Done;
+    }
+    progress
+}
+
+fn check_low_parking(
+    _: &mut EventCtx,
+    _: &mut App,
+    _: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    if tut.parking_found {
+        return Progress::Done;
+    }
+    Progress::Waiting
+}
+
+fn check_watch_bikes(
+    _: &mut EventCtx,
+    app: &mut App,
+    _: &SandboxControls,
+    _: &mut TutorialState,
+) -> Progress {
+    if app.primary.sim.time() >= Time::START_OF_DAY + Duration::minutes(3) {
+        return Progress::Done;
+    }
+    Progress::Waiting
+}
+
+fn check_fix_bikes(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    _: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    if !app.primary.sim.is_done() {
+        return Progress::Waiting;
+    }
+
+    let mut before = Duration::ZERO;
+    let mut after = Duration::ZERO;
+    for (b, a, _) in app
+        .primary
+        .sim
+        .get_analytics()
+        .both_finished_trips(app.primary.sim.get_end_of_day(), app.prebaked())
+    {
+        before = before.max(b);
+        after = after.max(a);
+    }
+
+    // First tell the player how they did. The next event after that, move on or retry.
+    if !tut.score_delivered {
+        tut.score_delivered = true;
+        let lines = if before == after {
+            vec![
+                "Your changes didn't affect anything!".to_string(),
+                "Try editing the map to create some bike lanes.".to_string(),
+            ]
+        } else if after > before {
+            vec![
+                "Your changes made things worse!".to_string(),
+                format!(
+                    "All trips originally finished in {}, but now they took {}",
+                    before, after
+                ),
+                "".to_string(),
+                "Try again!".to_string(),
+            ]
+        } else if before - after < CAR_BIKE_CONTENTION_GOAL {
+            vec![
+                "Nice, you helped things a bit!".to_string(),
+                format!(
+                    "All trips originally took {}, but now they took {}",
+                    before, after
+                ),
+                "".to_string(),
+                "See if you can do a little better though.".to_string(),
+            ]
+        } else {
+            vec![format!(
+                "Awesome! All trips originally took {}, but now they only took {}",
+                before, after
+            )]
+        };
+        return Progress::Transition(Transition::Push(msg("All trips completed", lines)));
+    }
+    if before - after >= CAR_BIKE_CONTENTION_GOAL {
+        return Progress::Done;
+    }
+    // Restart the stage
+    Progress::Transition(transition(ctx, app, tut))
+}
+
+fn check_done(
+    _: &mut EventCtx,
+    _: &mut App,
+    _: &SandboxControls,
+    tut: &mut TutorialState,
+) -> Progress {
+    // If the player chooses to stay here, at least go back to the message panel.
+    tut.prev();
+    Progress::Transition(maybe_exit_sandbox())
+}
+
 impl TutorialState {
     // These're mutex to each state, but still important to reset. Otherwise if you go back to a
     // previous interaction stage, it'll just be automatically marked done.
@@ -772,10 +890,23 @@ impl TutorialState {
             }
         }
         if edit_map {
+            // Only allow editing once the player is actually working on the task, not while
+            // they're reading instructions
             col.push(
-                Btn::svg_def("../data/system/assets/tools/edit_map.svg")
-                    .build(ctx, "edit map", lctrl(Key::E))
-                    .margin(5),
+                if self.interaction() == Task::Nil {
+                    Widget::draw_svg_transform(
+                        ctx,
+                        "../data/system/assets/tools/edit_map.svg",
+                        RewriteColor::ChangeAll(Color::WHITE.alpha(0.5)),
+                    )
+                } else {
+                    Btn::svg_def("../data/system/assets/tools/edit_map.svg").build(
+                        ctx,
+                        "edit map",
+                        lctrl(Key::E),
+                    )
+                }
+                .margin(5),
             );
         }
 
@@ -796,15 +927,14 @@ impl TutorialState {
                 .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
         }
 
-        let last_finished_task = if self.current.stage == 0 {
-            Task::Nil
-        } else {
-            self.stages[self.current.stage - 1].task
-        };
+        let unlocked: Vec<Unlock> = self.stages[..=self.current.stage]
+            .iter()
+            .flat_map(|stage| stage.unlocks.clone())
+            .collect();
 
         Box::new(Tutorial {
-            top_center: self.make_top_center(ctx, &app.cs, last_finished_task >= Task::WatchBikes),
-            last_finished_task,
+            top_center: self.make_top_center(ctx, &app.cs, unlocked.contains(&Unlock::EditMap)),
+            unlocked,
 
             msg_panel: if let Some((ref lines, horiz_align, _)) = self.lines() {
                 let mut col = vec![{
@@ -921,6 +1051,7 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::Camera)
+                .check(check_camera)
                 .warp_to(
                     ID::Intersection(map.find_i_by_osm_id(53096945).unwrap()),
                     None,
@@ -959,6 +1090,8 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::InspectObjects)
+                .unlock(vec![Unlock::Common])
+                .check(check_inspect_objects)
                 .msg(
                     vec![
                         "What, no fire? Er, sorry about that. Just a little joke we like to play \
@@ -979,6 +1112,8 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::TimeControls)
+                .unlock(vec![Unlock::TimeControls])
+                .check(check_time_controls)
                 .warp_to(
                     ID::Intersection(map.find_i_by_osm_id(53096945).unwrap()),
                     Some(6.5),
@@ -1019,6 +1154,7 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::PauseResume)
+                .check(check_pause_resume)
                 .msg(
                     vec!["Whew, that took a while! (Hopefully not though...)"],
                     None,
@@ -1038,6 +1174,8 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::Escort)
+                .unlock(vec![Unlock::AgentMeter])
+                .check(check_escort)
                 // Don't center on where the agents are, be a little offset
                 .warp_to(
                     ID::Building(map.find_b_by_osm_id(217699780).unwrap()),
@@ -1172,6 +1310,8 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::LowParking)
+                .unlock(vec![Unlock::Minimap])
+                .check(check_low_parking)
                 // TODO Actually, we ideally just want a bunch of parked cars, not all these trips
                 .spawn(Box::new(|app| {
                     ScenarioGenerator {
@@ -1235,6 +1375,7 @@ impl TutorialState {
 
         state.stages.push(
             Stage::new(Task::WatchBikes)
+                .check(check_watch_bikes)
                 .warp_to(ID::Building(bike_lane_focus_pt), None)
                 .spawn_scenario(bike_lane_scenario.clone())
                 .msg(
@@ -1251,6 +1392,8 @@ impl TutorialState {
         let top_center = state.make_top_center(ctx, &app.cs, true);
         state.stages.push(
             Stage::new(Task::FixBikes)
+                .unlock(vec![Unlock::ExamineObjects, Unlock::EditMap])
+                .check(check_fix_bikes)
                 .spawn_scenario(bike_lane_scenario)
                 .warp_to(ID::Building(bike_lane_focus_pt), None)
                 .msg(
@@ -1304,8 +1447,10 @@ impl TutorialState {
                 ),
         );
 
-        state.stages.push(Stage::new(Task::Done).msg(
-            vec![
+        state
+            .stages
+            .push(Stage::new(Task::Done).check(check_done).msg(
+                vec![
                 "You're ready for the hard stuff now.",
                 "",
                 "- Try out some challenges",
@@ -1315,8 +1460,8 @@ impl TutorialState {
                 "",
                 "Go have the appropriate amount of fun!",
             ],
-            None,
-        ));
+                None,
+            ));
 
         state
 
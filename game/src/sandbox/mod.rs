@@ -1,6 +1,8 @@
 mod dashboards;
 pub mod gameplay;
 mod misc_tools;
+mod record;
+mod session;
 mod speed;
 mod uber_turns;
 
@@ -14,7 +16,7 @@ use crate::edit::{
 };
 use crate::game::{State, Transition, WizardState};
 use crate::helpers::ID;
-use crate::layer::PickLayer;
+use crate::layer::{set_layer, PickLayer};
 use crate::managed::{WrappedComposite, WrappedOutcome};
 use crate::pregame::MainMenu;
 use crate::render::AgentColorScheme;
@@ -25,6 +27,7 @@ use ezgui::{
 pub use gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
 use geom::{Polygon, Time};
 use map_model::MapEdits;
+pub use session::LastSession;
 use sim::{TripMode, VehicleType};
 pub use speed::TimeWarpScreen;
 pub use speed::{SpeedControls, TimePanel};
@@ -133,9 +136,6 @@ impl State for SandboxMode {
             if let Some(t) = m.event(ctx, app) {
                 return t;
             }
-            if let Some(t) = PickLayer::update(ctx, app, &m.composite) {
-                return t;
-            }
         }
 
         if let Some(ref mut s) = self.controls.speed {
@@ -159,6 +159,14 @@ impl State for SandboxMode {
             }
         }
 
+        // The active layer is ambient, so it only gets input after everything else in the
+        // foreground. Key::Escape closes the info panel first, then the layer, then asks to quit.
+        if let Some(ref m) = self.controls.minimap {
+            if let Some(t) = PickLayer::update(ctx, app, &m.composite) {
+                return t;
+            }
+        }
+
         if let Some(ref mut tp) = self.controls.time_panel {
             tp.event(ctx, app);
         }
@@ -170,6 +178,7 @@ impl State for SandboxMode {
                 }
                 Some(WrappedOutcome::Clicked(x)) => match x.as_ref() {
                     "back" => {
+                        LastSession::record(app, &self.gameplay_mode);
                         return maybe_exit_sandbox();
                     }
                     _ => unreachable!(),
@@ -228,8 +237,8 @@ impl State for SandboxMode {
         self.gameplay.draw(g, app);
     }
 
-    fn on_destroy(&mut self, _: &mut EventCtx, app: &mut App) {
-        app.layer = None;
+    fn on_destroy(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        set_layer(ctx, app, None);
         app.agent_cs = AgentColorScheme::new(&app.cs);
         self.gameplay.on_destroy(app);
     }
@@ -402,7 +411,7 @@ impl ContextualActions for Actions {
                     }
                 }
                 ID::Car(c) => {
-                    if c.1 == VehicleType::Bus {
+                    if c.1.is_transit() {
                         // TODO Hide the button if the layer is open
                         actions.push((Key::R, "show route".to_string()));
                     }
@@ -445,11 +454,9 @@ impl ContextualActions for Actions {
             ),
             (ID::Car(c), "show route") => {
                 *close_panel = false;
-                app.layer = Some(Box::new(crate::layer::bus::ShowBusRoute::new(
-                    ctx,
-                    app,
-                    app.primary.sim.bus_route_id(c).unwrap(),
-                )));
+                let route = app.primary.sim.bus_route_id(c).unwrap();
+                let layer = crate::layer::bus::ShowBusRoute::new(ctx, app, route);
+                set_layer(ctx, app, Some(Box::new(layer)));
                 Transition::Keep
             }
             (_, "follow (run the simulation)") => {
@@ -0,0 +1,262 @@
+use crate::app::App;
+use crate::game::{msg, DrawBaselayer, State, Transition};
+use abstutil::Timer;
+use ezgui::{
+    hotkey, Btn, Choice, Composite, EventCtx, EventLoopMode, GfxCtx, Key, Line, Outcome, Spinner,
+    Text, TextExt, Widget,
+};
+use geom::{Duration, Time};
+use log::warn;
+use std::process::Command;
+
+// Steps the sim forwards a little at a time, capturing the screen after each step, then stitches
+// the frames together into a video. Whatever's on screen gets recorded, so the camera, layers, and
+// panels should be set up first. Needs scrot and ImageMagick, plus ffmpeg for MP4.
+pub struct RecordVideo {
+    composite: Composite,
+    target: Time,
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    dir: String,
+    frames: usize,
+    sim_step: Duration,
+    fps: usize,
+    format: VideoFormat,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VideoFormat {
+    GIF,
+    MP4,
+}
+
+impl RecordVideo {
+    pub fn new(ctx: &mut EventCtx, app: &App, target: Time) -> Box<dyn State> {
+        Box::new(RecordVideo {
+            composite: Composite::new(
+                Widget::col(vec![
+                    Widget::row(vec![
+                        Line("Record a video").small_heading().draw(ctx),
+                        Btn::plaintext("X")
+                            .build(ctx, "close", hotkey(Key::Escape))
+                            .align_right(),
+                    ])
+                    .margin_below(10),
+                    Text::from_multiline(vec![
+                        Line(format!(
+                            "From {} until {}",
+                            app.primary.sim.time().ampm_tostring(),
+                            target.ampm_tostring()
+                        )),
+                        Line("Everything currently on screen will be recorded.").secondary(),
+                        Line("Press escape to stop early.").secondary(),
+                    ])
+                    .draw(ctx)
+                    .margin_below(10),
+                    Widget::row(vec![
+                        "Simulated seconds per frame:"
+                            .draw_text(ctx)
+                            .centered_vert()
+                            .margin_right(10),
+                        Spinner::new(ctx, (1, 600), 10)
+                            .named("sim seconds")
+                            .align_right(),
+                    ])
+                    .margin_below(5),
+                    Widget::row(vec![
+                        "Frames per second of video:"
+                            .draw_text(ctx)
+                            .centered_vert()
+                            .margin_right(10),
+                        Spinner::new(ctx, (1, 60), 10).named("fps").align_right(),
+                    ])
+                    .margin_below(5),
+                    Widget::row(vec![
+                        "Format:".draw_text(ctx).centered_vert().margin_right(10),
+                        Widget::dropdown(
+                            ctx,
+                            "format",
+                            VideoFormat::GIF,
+                            vec![
+                                Choice::new("GIF", VideoFormat::GIF),
+                                Choice::new("MP4", VideoFormat::MP4),
+                            ],
+                        ),
+                    ])
+                    .margin_below(10),
+                    Btn::text_bg2("Start recording")
+                        .build_def(ctx, hotkey(Key::Enter))
+                        .centered_horiz(),
+                ])
+                .bg(app.cs.panel_bg)
+                .padding(16),
+            )
+            .build(ctx),
+            target,
+            recording: None,
+        })
+    }
+}
+
+impl State for RecordVideo {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut rec) = self.recording {
+            if ctx.input.new_was_pressed(&hotkey(Key::Escape).unwrap()) {
+                return finish(rec);
+            }
+            if ctx.input.nonblocking_is_update_event().is_some() {
+                ctx.input.use_update_event();
+                // The first frame shows the starting time
+                if rec.frames > 0 {
+                    // Capturing happens outside of the game, so the only way to find out if the
+                    // last frame worked is to look for it.
+                    let last = frame_path(rec, rec.frames - 1);
+                    if !std::path::Path::new(&last).exists() {
+                        let err = format!(
+                            "Couldn't capture {}; you probably don't have scrot \
+                             (https://en.wikipedia.org/wiki/Scrot) installed",
+                            last
+                        );
+                        // Keep any frames that did work
+                        rec.frames -= 1;
+                        if rec.frames == 0 {
+                            return Transition::Replace(msg("Recording failed", vec![err]));
+                        }
+                        return finish(rec);
+                    }
+                    if app.primary.sim.time() >= self.target {
+                        return finish(rec);
+                    }
+                    let dt = rec.sim_step.min(self.target - app.primary.sim.time());
+                    app.primary.sim.timed_step(
+                        &app.primary.map,
+                        dt,
+                        &mut app.primary.sim_cb,
+                        &mut Timer::throwaway(),
+                    );
+                }
+                let filename = frame_path(rec, rec.frames);
+                rec.frames += 1;
+                return Transition::KeepWithMode(EventLoopMode::ScreenCaptureCurrentShot {
+                    filename,
+                });
+            }
+            return Transition::KeepWithMode(EventLoopMode::Animation);
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Start recording" => {
+                    let dir = format!(
+                        "{}/{}_to_{}",
+                        abstutil::path_videos(app.primary.map.get_name()),
+                        app.primary.sim.time().as_filename(),
+                        self.target.as_filename()
+                    );
+                    if let Err(err) = std::fs::create_dir_all(&dir) {
+                        return Transition::Replace(msg(
+                            "Error",
+                            vec![format!("Couldn't create {}: {}", dir, err)],
+                        ));
+                    }
+                    self.recording = Some(Recording {
+                        dir,
+                        frames: 0,
+                        sim_step: Duration::seconds(self.composite.spinner("sim seconds") as f64),
+                        fps: self.composite.spinner("fps"),
+                        format: self.composite.dropdown_value("format"),
+                    });
+                    return Transition::KeepWithMode(EventLoopMode::Animation);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        // Don't show up in the video
+        if self.recording.is_none() {
+            State::grey_out_map(g, app);
+            self.composite.draw(g);
+        }
+    }
+}
+
+fn frame_path(rec: &Recording, frame: usize) -> String {
+    format!("{}/frame_{:05}.png", rec.dir, frame)
+}
+
+fn finish(rec: &Recording) -> Transition {
+    if rec.frames == 0 {
+        return Transition::Pop;
+    }
+    match encode(rec) {
+        Ok(path) => Transition::Replace(msg(
+            "Recording finished",
+            vec![format!("{} frames saved to {}", rec.frames, path)],
+        )),
+        Err(err) => Transition::Replace(msg(
+            "Recording failed",
+            vec![
+                err,
+                format!("The individual frames are still in {}", rec.dir),
+            ],
+        )),
+    }
+}
+
+// Returns the path to the video. The individual frames are cleaned up if this succeeds.
+fn encode(rec: &Recording) -> Result<String, String> {
+    let (path, mut cmd, tool) = match rec.format {
+        VideoFormat::GIF => {
+            let path = format!("{}.gif", rec.dir);
+            let mut cmd = Command::new("convert");
+            // The delay is in hundredths of a second
+            cmd.arg("-delay")
+                .arg((100 / rec.fps).to_string())
+                .arg("-loop")
+                .arg("0")
+                .arg(format!("{}/frame_*.png", rec.dir))
+                .arg(&path);
+            (path, cmd, "convert (https://imagemagick.org)")
+        }
+        VideoFormat::MP4 => {
+            let path = format!("{}.mp4", rec.dir);
+            let mut cmd = Command::new("ffmpeg");
+            cmd.arg("-y")
+                .arg("-framerate")
+                .arg(rec.fps.to_string())
+                .arg("-i")
+                .arg(format!("{}/frame_%05d.png", rec.dir))
+                // Most players can't handle odd dimensions
+                .arg("-vf")
+                .arg("pad=ceil(iw/2)*2:ceil(ih/2)*2")
+                .arg("-pix_fmt")
+                .arg("yuv420p")
+                .arg(&path);
+            (path, cmd, "ffmpeg (https://ffmpeg.org)")
+        }
+    };
+    if !cmd.status().map(|s| s.success()).unwrap_or(false) {
+        return Err(format!(
+            "Encoding the video failed; you probably don't have {} installed",
+            tool
+        ));
+    }
+    if let Err(err) = std::fs::remove_dir_all(&rec.dir) {
+        warn!("Couldn't clean up {}: {}", rec.dir, err);
+    }
+    Ok(path)
+}
@@ -0,0 +1,184 @@
+use crate::app::App;
+use crate::edit::apply_map_edits;
+use crate::game::{msg, Transition};
+use crate::sandbox::{GameplayMode, SandboxMode};
+use abstutil::Timer;
+use ezgui::EventCtx;
+use geom::Time;
+use log::warn;
+use map_model::PermanentMapEdits;
+use serde::{Deserialize, Serialize};
+use sim::Sim;
+
+// Enough to pick up where the player left off in sandbox mode. Written when leaving the sandbox or
+// closing the window, and offered on the main menu. The camera is already remembered per map.
+// TODO Remember the open info panel and layer too
+#[derive(Serialize, Deserialize)]
+pub struct LastSession {
+    pub map_name: String,
+    // None means freeform mode
+    pub scenario: Option<String>,
+    // Stored directly, so untitled edits survive too
+    pub edits: PermanentMapEdits,
+    // None if the simulation never started
+    pub savestate: Option<String>,
+}
+
+impl LastSession {
+    // Challenges and the tutorial have their own progression, so those aren't remembered.
+    pub fn record(app: &App, mode: &GameplayMode) {
+        let scenario = match mode {
+            GameplayMode::Freeform(_) => None,
+            GameplayMode::PlayScenario(_, ref scenario) => Some(scenario.clone()),
+            _ => {
+                return;
+            }
+        };
+        let map = &app.primary.map;
+        // While editing, the real simulation is stashed away
+        let sim = app.suspended_sim.as_ref().unwrap_or(&app.primary.sim);
+        let savestate = if sim.time() == Time::START_OF_DAY {
+            None
+        } else {
+            // Saving temporarily mutates the scheduler
+            Some(sim.clone().save())
+        };
+        abstutil::write_json(
+            abstutil::path_last_session(),
+            &LastSession {
+                map_name: map.get_name().to_string(),
+                scenario,
+                edits: PermanentMapEdits::to_permanent(map.get_edits(), map),
+                savestate,
+            },
+        );
+    }
+
+    pub fn load() -> Option<LastSession> {
+        abstutil::maybe_read_json(abstutil::path_last_session(), &mut Timer::throwaway()).ok()
+    }
+
+    // Called periodically while the game runs, so a crash doesn't lose much work. Unlike
+    // record, edits made in any gameplay mode are kept; recovering just drops into freeform mode.
+    // The sim is serialized here, but the files are written in the background.
+    pub fn autosave(app: &mut App, mode: &GameplayMode) {
+        let (scenario, keep_sim) = match mode {
+            GameplayMode::Freeform(_) => (None, true),
+            GameplayMode::PlayScenario(_, ref scenario) => (Some(scenario.clone()), true),
+            _ => (None, false),
+        };
+        // While editing, the real simulation is stashed away, but it goes with the edits from
+        // before edit mode started. Keep the edits in progress and start the day over instead.
+        let savestate_bytes = if keep_sim
+            && app.suspended_sim.is_none()
+            && app.primary.sim.time() != Time::START_OF_DAY
+        {
+            Some(app.primary.sim.savestate_bytes())
+        } else {
+            None
+        };
+        let map = &app.primary.map;
+        let session = LastSession {
+            map_name: map.get_name().to_string(),
+            scenario,
+            edits: PermanentMapEdits::to_permanent(map.get_edits(), map),
+            savestate: savestate_bytes
+                .as_ref()
+                .map(|_| abstutil::path_recovery_savestate()),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::thread::spawn(move || {
+                if let Err(err) = write_recovery(session, savestate_bytes) {
+                    warn!("Couldn't autosave: {}", err);
+                }
+            });
+        }
+    }
+
+    // Only present if some earlier run didn't exit cleanly. The files are moved aside first, so
+    // this run's autosaves don't overwrite them before the player decides to recover.
+    pub fn load_recovery() -> Option<LastSession> {
+        let mut timer = Timer::throwaway();
+        if let Ok(mut session) =
+            abstutil::maybe_read_json::<LastSession>(abstutil::path_recovery(), &mut timer)
+        {
+            if session.savestate.is_some() {
+                let path = abstutil::path_previous_recovery_savestate();
+                session.savestate =
+                    if std::fs::rename(abstutil::path_recovery_savestate(), &path).is_ok() {
+                        Some(path)
+                    } else {
+                        None
+                    };
+            }
+            if abstutil::maybe_write_json(&abstutil::path_previous_recovery(), &session).is_ok() {
+                let _ = std::fs::remove_file(abstutil::path_recovery());
+            }
+        }
+        abstutil::maybe_read_json(abstutil::path_previous_recovery(), &mut timer).ok()
+    }
+
+    pub fn clear_recovery() {
+        let _ = std::fs::remove_file(abstutil::path_recovery());
+        let _ = std::fs::remove_file(abstutil::path_recovery_savestate());
+        let _ = std::fs::remove_file(abstutil::path_previous_recovery());
+        let _ = std::fs::remove_file(abstutil::path_previous_recovery_savestate());
+    }
+
+    pub fn resume(self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let LastSession {
+            map_name,
+            scenario,
+            edits,
+            savestate,
+        } = self;
+        let maybe_err = ctx.loading_screen("resume last session", |ctx, mut timer| {
+            if &map_name != app.primary.map.get_name() {
+                app.switch_map(ctx, abstutil::path_map(&map_name));
+            }
+            match PermanentMapEdits::from_permanent(edits, &app.primary.map) {
+                Ok(edits) => {
+                    apply_map_edits(ctx, app, edits);
+                    app.primary
+                        .map
+                        .recalculate_pathfinding_after_edits(&mut timer);
+                    None
+                }
+                Err(err) => Some(err),
+            }
+        });
+        if let Some(err) = maybe_err {
+            return Transition::Push(msg("Can't resume last session", vec![err]));
+        }
+
+        let path = abstutil::path_map(&map_name);
+        let mode = match scenario {
+            Some(scenario) => GameplayMode::PlayScenario(path, scenario),
+            None => GameplayMode::Freeform(path),
+        };
+        let sandbox = SandboxMode::new(ctx, app, mode);
+        if let Some(ss) = savestate {
+            // If the savestate is gone, just start the day over
+            if let Ok(sim) = ctx.loading_screen("load savestate", |_, mut timer| {
+                Sim::load_savestate(ss, &app.primary.map, &mut timer)
+            }) {
+                app.primary.sim = sim;
+            }
+        }
+        ctx.canvas.load_camera_state(&map_name);
+        Transition::Push(Box::new(sandbox))
+    }
+}
+
+// The savestate goes first, so the session never points at a missing one
+#[cfg(not(target_arch = "wasm32"))]
+fn write_recovery(session: LastSession, savestate: Option<Vec<u8>>) -> std::io::Result<()> {
+    if let Some(bytes) = savestate {
+        let path = abstutil::path_recovery_savestate();
+        std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
+        std::fs::write(path, bytes)?;
+    }
+    abstutil::maybe_write_json(&abstutil::path_recovery(), &session)
+}
@@ -2,6 +2,7 @@ use crate::app::{App, FindDelayedIntersections};
 use crate::common::Warping;
 use crate::game::{msg, State, Transition};
 use crate::helpers::ID;
+use crate::sandbox::record::RecordVideo;
 use crate::sandbox::{GameplayMode, SandboxMode};
 use ezgui::{
     hotkey, AreaSlider, Btn, Choice, Color, Composite, EventCtx, EventLoopMode, GeomBatch, GfxCtx,
@@ -395,6 +396,10 @@ impl JumpToTime {
                     .margin_above(20)
                     .margin_below(20),
                     Btn::text_bg2("Jump to the next delay over 5 minutes")
+                        .build_def(ctx, None)
+                        .centered_horiz()
+                        .margin_below(10),
+                    Btn::text_bg2("Record a video until then")
                         .build_def(ctx, None)
                         .centered_horiz(),
                 ])
@@ -429,6 +434,15 @@ impl State for JumpToTime {
                     }
                     return Transition::Replace(TimeWarpScreen::new(ctx, app, self.target, false));
                 }
+                "Record a video until then" => {
+                    if self.target <= app.primary.sim.time() {
+                        return Transition::Replace(msg(
+                            "Error",
+                            vec!["Pick a time in the future to record until."],
+                        ));
+                    }
+                    return Transition::Replace(RecordVideo::new(ctx, app, self.target));
+                }
                 "Jump to the next delay over 5 minutes" => {
                     return Transition::Replace(TimeWarpScreen::new(
                         ctx,
@@ -540,8 +554,8 @@ impl State for TimeWarpScreen {
                 if let Some((i, t)) = di.currently_delayed.get(0) {
                     if app.primary.sim.time() - *t > di.halt_limit {
                         let id = ID::Intersection(*i);
-                        app.layer =
-                            Some(Box::new(crate::layer::traffic::TrafficJams::new(ctx, app)));
+                        let layer = crate::layer::traffic::TrafficJams::new(ctx, app);
+                        crate::layer::set_layer(ctx, app, Some(Box::new(layer)));
                         return Transition::Replace(Warping::new(
                             ctx,
                             id.canonical_point(&app.primary).unwrap(),
@@ -0,0 +1,137 @@
+use crate::{trim_f64, Distance, Duration, Speed};
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops};
+
+// In meters per second squared. Negative means slowing down.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Acceleration(f64);
+
+impl Acceleration {
+    pub const ZERO: Acceleration = Acceleration::const_meters_per_second_squared(0.0);
+
+    pub fn meters_per_second_squared(value: f64) -> Acceleration {
+        if !value.is_finite() {
+            panic!("Bad Acceleration {}", value);
+        }
+
+        Acceleration(trim_f64(value))
+    }
+
+    pub const fn const_meters_per_second_squared(value: f64) -> Acceleration {
+        Acceleration(value)
+    }
+
+    // TODO Remove if possible.
+    pub fn inner_meters_per_second_squared(self) -> f64 {
+        self.0
+    }
+
+    // How far something starting at initial_speed travels after accelerating for some time.
+    // Doesn't stop at zero; if decelerating long enough, this'll start going backwards.
+    pub fn dist_covered(self, initial_speed: Speed, time: Duration) -> Distance {
+        initial_speed * time + Distance::meters(0.5 * self.0 * time.inner_seconds().powi(2))
+    }
+
+    // How far something going at some speed needs to come to a stop, decelerating at this rate.
+    // The sign of the acceleration doesn't matter.
+    pub fn stopping_distance(self, speed: Speed) -> Distance {
+        if self.0 == 0.0 {
+            panic!("Can't stop from {} with zero acceleration", speed);
+        }
+        Distance::meters(speed.inner_meters_per_second().powi(2) / (2.0 * self.0.abs()))
+    }
+
+    pub fn max(self, other: Acceleration) -> Acceleration {
+        if self >= other {
+            self
+        } else {
+            other
+        }
+    }
+
+    pub fn min(self, other: Acceleration) -> Acceleration {
+        if self <= other {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+impl ops::Add for Acceleration {
+    type Output = Acceleration;
+
+    fn add(self, other: Acceleration) -> Acceleration {
+        Acceleration::meters_per_second_squared(self.0 + other.0)
+    }
+}
+
+impl ops::Sub for Acceleration {
+    type Output = Acceleration;
+
+    fn sub(self, other: Acceleration) -> Acceleration {
+        Acceleration::meters_per_second_squared(self.0 - other.0)
+    }
+}
+
+impl ops::Neg for Acceleration {
+    type Output = Acceleration;
+
+    fn neg(self) -> Acceleration {
+        Acceleration::meters_per_second_squared(-self.0)
+    }
+}
+
+impl ops::Mul<f64> for Acceleration {
+    type Output = Acceleration;
+
+    fn mul(self, scalar: f64) -> Acceleration {
+        Acceleration::meters_per_second_squared(self.0 * scalar)
+    }
+}
+
+impl ops::Mul<Duration> for Acceleration {
+    type Output = Speed;
+
+    fn mul(self, other: Duration) -> Speed {
+        Speed::meters_per_second(self.0 * other.inner_seconds())
+    }
+}
+
+impl ops::Mul<Acceleration> for Duration {
+    type Output = Speed;
+
+    fn mul(self, other: Acceleration) -> Speed {
+        other * self
+    }
+}
+
+impl ops::Div<Duration> for Speed {
+    type Output = Acceleration;
+
+    fn div(self, other: Duration) -> Acceleration {
+        if other == Duration::ZERO {
+            panic!("Can't divide {} / {}", self, other);
+        }
+        Acceleration::meters_per_second_squared(
+            self.inner_meters_per_second() / other.inner_seconds(),
+        )
+    }
+}
+
+impl ops::Div<Acceleration> for Speed {
+    type Output = Duration;
+
+    fn div(self, other: Acceleration) -> Duration {
+        if other == Acceleration::ZERO {
+            panic!("Can't divide {} / {}", self, other);
+        }
+        Duration::seconds(self.inner_meters_per_second() / other.0)
+    }
+}
+
+impl fmt::Display for Acceleration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} m/s^2", self.0)
+    }
+}
@@ -1,17 +1,25 @@
 use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
 use std::fmt;
 
-// Stores in radians
+const TAU: f64 = 2.0 * PI;
+
+// Stores in radians, always normalized to [0, 2pi)
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct Angle(f64);
 
 impl Angle {
     pub const ZERO: Angle = Angle(0.0);
 
-    // TODO Normalize here, and be careful about % vs euclid_rem
     pub(crate) fn new_rads(rads: f64) -> Angle {
         // Retain more precision for angles...
-        Angle((rads * 10_000_000.0).round() / 10_000_000.0)
+        let rads = (rads.rem_euclid(TAU) * 10_000_000.0).round() / 10_000_000.0;
+        // Rounding might bump something just under 2pi up to it
+        if rads >= TAU {
+            Angle(0.0)
+        } else {
+            Angle(rads)
+        }
     }
 
     pub fn new_degs(degs: f64) -> Angle {
@@ -19,11 +27,11 @@ impl Angle {
     }
 
     pub fn opposite(self) -> Angle {
-        Angle::new_rads(self.0 + std::f64::consts::PI)
+        Angle::new_rads(self.0 + PI)
     }
 
     pub(crate) fn invert_y(self) -> Angle {
-        Angle::new_rads(2.0 * std::f64::consts::PI - self.0)
+        Angle::new_rads(TAU - self.0)
     }
 
     pub fn rotate_degs(self, degrees: f64) -> Angle {
@@ -31,32 +39,28 @@ impl Angle {
     }
 
     pub fn normalized_radians(self) -> f64 {
-        if self.0 < 0.0 {
-            self.0 + (2.0 * std::f64::consts::PI)
-        } else {
-            self.0
-        }
+        self.0
     }
 
     pub fn normalized_degrees(self) -> f64 {
         self.normalized_radians().to_degrees()
     }
 
+    // How many degrees to rotate self by to reach other, taking the shorter way around. Positive
+    // is counter-clockwise. Always in [-180, 180).
+    pub fn signed_degrees_towards(self, other: Angle) -> f64 {
+        // https://math.stackexchange.com/questions/110080/shortest-way-to-achieve-target-angle
+        (other.normalized_degrees() - self.normalized_degrees() + 180.0).rem_euclid(360.0) - 180.0
+    }
+
     // Logically this returns [-180, 180], but keep in mind when we print this angle, it'll
     // normalize to be [0, 360].
     pub fn shortest_rotation_towards(self, other: Angle) -> Angle {
-        // https://math.stackexchange.com/questions/110080/shortest-way-to-achieve-target-angle
-        Angle::new_degs(
-            ((self.normalized_degrees() - other.normalized_degrees() + 540.0) % 360.0) - 180.0,
-        )
+        Angle::new_degs(-self.signed_degrees_towards(other))
     }
 
     pub fn approx_eq(self, other: Angle, within_degrees: f64) -> bool {
-        // https://math.stackexchange.com/questions/110080/shortest-way-to-achieve-target-angle
-        // This yields [-180, 180]
-        let rotation =
-            ((self.normalized_degrees() - other.normalized_degrees() + 540.0) % 360.0) - 180.0;
-        rotation.abs() < within_degrees
+        self.signed_degrees_towards(other).abs() < within_degrees
     }
 }
 
@@ -92,3 +96,22 @@ impl std::ops::Div<f64> for Angle {
         Angle::new_rads(self.0 / scalar)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Angle;
+
+    #[test]
+    fn test_wraparound() {
+        assert_eq!(Angle::new_degs(-90.0), Angle::new_degs(270.0));
+        assert_eq!(Angle::new_degs(720.0 + 10.0), Angle::new_degs(10.0));
+
+        // Just on either side of the +/- 180 degree seam
+        let a = Angle::new_degs(179.0);
+        let b = Angle::new_degs(-179.0);
+        assert!((a.signed_degrees_towards(b) - 2.0).abs() < 1e-6);
+        assert!((b.signed_degrees_towards(a) + 2.0).abs() < 1e-6);
+        assert!(a.approx_eq(b, 5.0));
+        assert!(a.opposite().approx_eq(Angle::new_degs(-1.0), 0.01));
+    }
+}
@@ -1,8 +1,8 @@
-use crate::{LonLat, Polygon, Pt2D};
+use crate::{LonLat, Polygon, Projection, Pt2D};
 use aabb_quadtree::geom::{Point, Rect};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Bounds {
     pub min_x: f64,
     pub min_y: f64,
@@ -88,15 +88,22 @@ pub struct GPSBounds {
     pub(crate) min_lat: f64,
     pub(crate) max_lon: f64,
     pub(crate) max_lat: f64,
+    #[serde(default)]
+    pub projection: Projection,
 }
 
 impl GPSBounds {
     pub fn new() -> GPSBounds {
+        GPSBounds::with_projection(Projection::default())
+    }
+
+    pub fn with_projection(projection: Projection) -> GPSBounds {
         GPSBounds {
             min_lon: f64::MAX,
             min_lat: f64::MAX,
             max_lon: f64::MIN,
             max_lat: f64::MIN,
+            projection,
         }
     }
 
@@ -116,11 +123,64 @@ impl GPSBounds {
 
     // TODO cache this
     pub fn get_max_world_pt(&self) -> Pt2D {
-        let width = LonLat::new(self.min_lon, self.min_lat)
-            .gps_dist_meters(LonLat::new(self.max_lon, self.min_lat));
-        let height = LonLat::new(self.min_lon, self.min_lat)
-            .gps_dist_meters(LonLat::new(self.min_lon, self.max_lat));
-        Pt2D::new(width.inner_meters(), height.inner_meters())
+        match self.projection {
+            Projection::Linear => {
+                let width = LonLat::new(self.min_lon, self.min_lat)
+                    .gps_dist_meters(LonLat::new(self.max_lon, self.min_lat));
+                let height = LonLat::new(self.min_lon, self.min_lat)
+                    .gps_dist_meters(LonLat::new(self.min_lon, self.max_lat));
+                Pt2D::new(width.inner_meters(), height.inner_meters())
+            }
+            Projection::Mercator => self.project(LonLat::new(self.max_lon, self.min_lat)),
+        }
+    }
+
+    // Map-space has the northwest corner at (0, 0), with y increasing southwards. Points outside
+    // the bounds project outside of that too.
+    pub fn project(&self, gps: LonLat) -> Pt2D {
+        match self.projection {
+            Projection::Linear => {
+                let max = self.get_max_world_pt();
+                let x = (gps.x() - self.min_lon) / (self.max_lon - self.min_lon) * max.x();
+                // Invert y, so that the northernmost latitude is 0. Screen drawing order, not
+                // Cartesian grid.
+                let y =
+                    max.y() - ((gps.y() - self.min_lat) / (self.max_lat - self.min_lat) * max.y());
+                Pt2D::new(x, y)
+            }
+            Projection::Mercator => {
+                let scale = self.mercator_scale();
+                let x = scale * (gps.x() - self.min_lon).to_radians();
+                let y = scale * (mercator_y(self.max_lat) - mercator_y(gps.y()));
+                Pt2D::new(x, y)
+            }
+        }
+    }
+
+    // The inverse of project
+    pub fn unproject(&self, pt: Pt2D) -> LonLat {
+        match self.projection {
+            Projection::Linear => {
+                let max = self.get_max_world_pt();
+                let lon = (pt.x() / max.x() * (self.max_lon - self.min_lon)) + self.min_lon;
+                let lat =
+                    self.min_lat + ((self.max_lat - self.min_lat) * (max.y() - pt.y()) / max.y());
+                LonLat::new(lon, lat)
+            }
+            Projection::Mercator => {
+                let scale = self.mercator_scale();
+                let lon = self.min_lon + (pt.x() / scale).to_degrees();
+                let merc = mercator_y(self.max_lat) - pt.y() / scale;
+                let lat = (2.0 * merc.exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees();
+                LonLat::new(lon, lat)
+            }
+        }
+    }
+
+    // Mercator stretches everything away from the equator. Scale so that distances are true in
+    // the middle of the map, which is plenty accurate for something city-sized.
+    fn mercator_scale(&self) -> f64 {
+        EARTH_RADIUS_METERS * ((self.min_lat + self.max_lat) / 2.0).to_radians().cos()
     }
 
     pub fn to_bounds(&self) -> Bounds {
@@ -165,5 +225,14 @@ impl GPSBounds {
         LonLat::new(self.min_lon, self.min_lat).approx_eq(LonLat::new(other.min_lon, other.min_lat))
             && LonLat::new(self.max_lon, self.max_lat)
                 .approx_eq(LonLat::new(other.max_lon, other.max_lat))
+            && self.projection == other.projection
     }
 }
+
+const EARTH_RADIUS_METERS: f64 = 6_378_137.0;
+
+fn mercator_y(lat: f64) -> f64 {
+    (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0)
+        .tan()
+        .ln()
+}
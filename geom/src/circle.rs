@@ -1,4 +1,4 @@
-use crate::{Angle, Bounds, Distance, Polygon, Pt2D, Ring};
+use crate::{Angle, Bounds, Distance, PolyLine, Polygon, Pt2D, Ring};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -73,6 +73,68 @@ impl Circle {
     }
 }
 
+// Part of a circle's circumference, starting at some angle and sweeping counter-clockwise (for
+// positive degrees) or clockwise (negative).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Arc {
+    pub circle: Circle,
+    pub start: Angle,
+    pub sweep_degrees: f64,
+}
+
+impl Arc {
+    pub fn new(circle: Circle, start: Angle, sweep_degrees: f64) -> Arc {
+        assert!(sweep_degrees.abs() < 360.0);
+        Arc {
+            circle,
+            start,
+            sweep_degrees,
+        }
+    }
+
+    // A half circle from pt1 to pt2, bulging out in the given direction. Useful for U-turns.
+    pub fn half_circle(pt1: Pt2D, pt2: Pt2D, bulge: Angle) -> Arc {
+        let center = Pt2D::center(&vec![pt1, pt2]);
+        let start = center.angle_to(pt1);
+        // Sweep whichever way passes through the bulge halfway around
+        let sweep_degrees = if start.rotate_degs(90.0).approx_eq(bulge, 90.0) {
+            180.0
+        } else {
+            -180.0
+        };
+        Arc::new(
+            Circle::new(center, pt1.dist_to(pt2) / 2.0),
+            start,
+            sweep_degrees,
+        )
+    }
+
+    pub fn length(&self) -> Distance {
+        self.circle.radius * self.sweep_degrees.abs().to_radians()
+    }
+
+    pub fn pt_at(&self, percent: f64) -> Pt2D {
+        self.circle.center.project_away(
+            self.circle.radius,
+            self.start.rotate_degs(percent * self.sweep_degrees),
+        )
+    }
+
+    // No piece of the result is longer than max_segment. None if the arc is too small to have any
+    // length, like when the radius is 0.
+    pub fn to_polyline(&self, max_segment: Distance) -> Option<PolyLine> {
+        if self.length() <= Distance::ZERO || max_segment <= Distance::ZERO {
+            return None;
+        }
+        let pieces = ((self.length() / max_segment).ceil() as usize).max(2);
+        let mut pts: Vec<Pt2D> = (0..=pieces)
+            .map(|i| self.pt_at((i as f64) / (pieces as f64)))
+            .collect();
+        pts.dedup();
+        PolyLine::maybe_new(pts)
+    }
+}
+
 impl fmt::Display for Circle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Circle({}, {})", self.center, self.radius)
@@ -1,4 +1,4 @@
-use crate::{trim_f64, Duration, Speed};
+use crate::{split_number_unit, trim_f64, Duration, Speed, Units};
 use serde::{Deserialize, Serialize};
 use std::{cmp, f64, fmt, ops};
 
@@ -59,14 +59,47 @@ impl Distance {
         self.0
     }
 
-    // TODO Store a bit in Maps to mark if they're in the US or not, plumb here to use meters
-    pub fn describe_rounded(self) -> String {
-        let feet = self.0 * 3.28084;
-        let miles = feet / 5280.0;
-        if miles >= 0.1 {
-            format!("{} miles", (miles * 10.0).round() / 10.0)
-        } else {
-            format!("{} ft", feet.round())
+    pub fn describe_rounded(self, units: Units) -> String {
+        match units {
+            Units::Imperial => {
+                let feet = self.0 * 3.28084;
+                let miles = feet / 5280.0;
+                if miles >= 0.1 {
+                    format!("{} miles", (miles * 10.0).round() / 10.0)
+                } else {
+                    format!("{} ft", feet.round())
+                }
+            }
+            Units::Metric => {
+                if self.0 >= 1000.0 {
+                    format!("{} km", (self.0 / 100.0).round() / 10.0)
+                } else if self.0 >= 10.0 {
+                    format!("{} m", self.0.round())
+                } else {
+                    // Lane widths and such need a bit more precision
+                    format!("{} m", (self.0 * 100.0).round() / 100.0)
+                }
+            }
+        }
+    }
+}
+
+// Accepts "120m", "1.5km", "20ft", "0.3mi", and so on. A bare number is meters.
+impl std::str::FromStr for Distance {
+    type Err = abstutil::Error;
+
+    fn from_str(string: &str) -> Result<Distance, abstutil::Error> {
+        let (value, unit) = split_number_unit(string)?;
+        match unit {
+            "" | "m" => Ok(Distance::meters(value)),
+            "km" => Ok(Distance::meters(1000.0 * value)),
+            "cm" => Ok(Distance::meters(value / 100.0)),
+            "ft" => Ok(Distance::meters(value / 3.28084)),
+            "mi" | "miles" => Ok(Distance::miles(value)),
+            _ => Err(abstutil::Error::new(format!(
+                "Distance {}: unknown unit {}",
+                string, unit
+            ))),
         }
     }
 }
@@ -79,7 +79,7 @@ impl Duration {
         )
     }
 
-    // TODO This is NOT the inverse of Display!
+    // Parses the "hh:mm:ss.s" format. FromStr handles what Display produces.
     pub fn parse(string: &str) -> Result<Duration, abstutil::Error> {
         let parts: Vec<&str> = string.split(':').collect();
         if parts.is_empty() {
@@ -189,6 +189,48 @@ impl Duration {
     }
 }
 
+// The inverse of Display: "1h5m30.5s", "90s", "5m", and so on. The old "hh:mm:ss" format and a
+// bare number of seconds work too.
+impl std::str::FromStr for Duration {
+    type Err = abstutil::Error;
+
+    fn from_str(string: &str) -> Result<Duration, abstutil::Error> {
+        let string = string.trim();
+        if string.contains(':') {
+            return Duration::parse(string);
+        }
+        if let Ok(seconds) = string.parse::<f64>() {
+            return Ok(Duration::seconds(seconds));
+        }
+        let (negative, mut rest) = if string.starts_with('-') {
+            (true, &string[1..])
+        } else {
+            (false, string)
+        };
+        let mut seconds = 0.0;
+        while !rest.is_empty() {
+            let (value, remainder) = crate::split_number_unit(rest)?;
+            let unit_len = remainder
+                .find(|c: char| c.is_ascii_digit())
+                .unwrap_or_else(|| remainder.len());
+            seconds += value
+                * match &remainder[..unit_len] {
+                    "h" => 3600.0,
+                    "m" => 60.0,
+                    "s" => 1.0,
+                    unit => {
+                        return Err(abstutil::Error::new(format!(
+                            "Duration {}: unknown unit {}",
+                            string, unit
+                        )));
+                    }
+                };
+            rest = &remainder[unit_len..];
+        }
+        Ok(Duration::seconds(if negative { -seconds } else { seconds }))
+    }
+}
+
 impl std::fmt::Display for Duration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         if *self == Duration::ZERO {
@@ -310,3 +352,37 @@ impl std::iter::Sum for Duration {
         sum
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Distance, Duration, Speed};
+
+    #[test]
+    fn test_parse() {
+        for d in vec![
+            Duration::seconds(0.5),
+            Duration::seconds(90.0),
+            Duration::hours(2),
+            Duration::seconds(3930.5),
+            Duration::seconds(-45.0),
+        ] {
+            assert_eq!(d.to_string().parse::<Duration>().unwrap(), d);
+        }
+        assert_eq!(
+            "00:01:30".parse::<Duration>().unwrap(),
+            Duration::seconds(90.0)
+        );
+
+        assert_eq!("120m".parse::<Distance>().unwrap(), Distance::meters(120.0));
+        assert_eq!(
+            "1.5 km".parse::<Distance>().unwrap(),
+            Distance::meters(1500.0)
+        );
+        assert_eq!(
+            "25mph".parse::<Speed>().unwrap(),
+            Speed::miles_per_hour(25.0)
+        );
+        assert!("25".parse::<Speed>().is_err());
+        assert!("3 parsecs".parse::<Distance>().is_err());
+    }
+}
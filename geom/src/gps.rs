@@ -1,10 +1,30 @@
 use crate::Distance;
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Error, ErrorKind};
 
+// How a map flattens lon/lat into map-space. This is stored as part of the GPSBounds, so every
+// conversion for one map agrees.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    // Stretch the bounding box into a rectangle. Distances along the edges are right, but
+    // everything else is squished a bit, more so for maps covering a bigger area.
+    Linear,
+    // Conformal, so angles and shapes are preserved. Scaled to be accurate in the middle of the
+    // map.
+    Mercator,
+}
+
+// Maps imported before the projection was configurable all used Linear.
+impl Default for Projection {
+    fn default() -> Projection {
+        Projection::Linear
+    }
+}
+
 // longitude is x, latitude is y
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub struct LonLat {
@@ -78,6 +98,146 @@ impl LonLat {
         }
         Ok(pts)
     }
+
+    // Reads the outer ring of the first polygon in a GeoJSON file, as drawn by tools like
+    // geojson.io. The file can be a FeatureCollection, a single Feature, or a bare geometry. Other
+    // geometry types and malformed features are skipped.
+    pub fn read_geojson_polygon(path: String) -> Result<Vec<LonLat>, Error> {
+        let value: serde_json::Value =
+            abstutil::maybe_read_json(path.clone(), &mut abstutil::Timer::throwaway())?;
+        geojson_features(&value)
+            .into_iter()
+            .find_map(|(geometry, _)| {
+                let coords = &geometry["coordinates"];
+                match geometry["type"].as_str() {
+                    Some("Polygon") => outer_ring(coords),
+                    Some("MultiPolygon") => coords.as_array()?.iter().find_map(outer_ring),
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| Error::new(ErrorKind::Other, format!("{} has no polygon", path)))
+    }
+
+    // Reads every point, line, and polygon in a GeoJSON file, along with the properties of the
+    // feature it came from. Each part of a Multi* geometry or GeometryCollection is returned
+    // separately, and polygons only keep their outer ring. Non-string properties are stored as
+    // JSON. Malformed parts are skipped.
+    pub fn read_geojson(
+        path: String,
+        timer: &mut abstutil::Timer,
+    ) -> Result<Vec<(Vec<LonLat>, BTreeMap<String, String>)>, Error> {
+        let value: serde_json::Value = abstutil::maybe_read_json(path.clone(), timer)?;
+        if value["type"].as_str().is_none() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("{} isn't GeoJSON", path),
+            ));
+        }
+
+        let mut results = Vec::new();
+        for (geometry, properties) in geojson_features(&value) {
+            let mut attributes = BTreeMap::new();
+            for (k, v) in properties.into_iter().flatten() {
+                let v = match v {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Null => continue,
+                    _ => v.to_string(),
+                };
+                attributes.insert(k.clone(), v);
+            }
+            for pts in geometry_parts(geometry) {
+                results.push((pts, attributes.clone()));
+            }
+        }
+        Ok(results)
+    }
+
+    // Dispatches on the file extension: .poly for Osmosis, anything else for GeoJSON
+    pub fn read_clipping_polygon(path: String) -> Result<Vec<LonLat>, Error> {
+        if path.ends_with(".poly") {
+            LonLat::read_osmosis_polygon(path)
+        } else {
+            LonLat::read_geojson_polygon(path)
+        }
+    }
+}
+
+// The geometry and properties of every feature in a FeatureCollection or a single Feature. A bare
+// geometry has no properties. Features without a geometry are skipped.
+fn geojson_features(
+    value: &serde_json::Value,
+) -> Vec<(
+    &serde_json::Value,
+    Option<&serde_json::Map<String, serde_json::Value>>,
+)> {
+    match value["type"].as_str() {
+        Some("FeatureCollection") => value["features"]
+            .as_array()
+            .map(|features| {
+                features
+                    .iter()
+                    .map(|f| (&f["geometry"], f["properties"].as_object()))
+                    .filter(|(g, _)| g.is_object())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Some("Feature") => vec![(&value["geometry"], value["properties"].as_object())],
+        Some(_) => vec![(value, None)],
+        None => Vec::new(),
+    }
+}
+
+fn geometry_parts(geometry: &serde_json::Value) -> Vec<Vec<LonLat>> {
+    let coords = &geometry["coordinates"];
+    match geometry["type"].as_str() {
+        Some("Point") => parse_pt(coords).map(|pt| vec![pt]).into_iter().collect(),
+        Some("MultiPoint") => list(coords)
+            .iter()
+            .filter_map(|x| parse_pt(x).map(|pt| vec![pt]))
+            .collect(),
+        Some("LineString") => parse_pts(coords).into_iter().collect(),
+        Some("MultiLineString") => list(coords).iter().filter_map(parse_pts).collect(),
+        Some("Polygon") => outer_ring(coords).into_iter().collect(),
+        Some("MultiPolygon") => list(coords).iter().filter_map(outer_ring).collect(),
+        Some("GeometryCollection") => list(&geometry["geometries"])
+            .iter()
+            .flat_map(geometry_parts)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn list(value: &serde_json::Value) -> &[serde_json::Value] {
+    value.as_array().map(|x| x.as_slice()).unwrap_or(&[])
+}
+
+fn parse_pt(coords: &serde_json::Value) -> Option<LonLat> {
+    Some(LonLat::new(
+        coords.get(0)?.as_f64()?,
+        coords.get(1)?.as_f64()?,
+    ))
+}
+
+// All of the points have to be well-formed
+fn parse_pts(coords: &serde_json::Value) -> Option<Vec<LonLat>> {
+    let pts = coords
+        .as_array()?
+        .iter()
+        .map(parse_pt)
+        .collect::<Option<Vec<_>>>()?;
+    if pts.is_empty() {
+        return None;
+    }
+    Some(pts)
+}
+
+// The first ring of a polygon's coordinates, if it's well-formed
+fn outer_ring(coords: &serde_json::Value) -> Option<Vec<LonLat>> {
+    let ring = parse_pts(coords.as_array()?.get(0)?)?;
+    if ring.len() < 3 {
+        return None;
+    }
+    Some(ring)
 }
 
 impl fmt::Display for LonLat {
@@ -1,3 +1,4 @@
+mod acceleration;
 mod angle;
 mod bounds;
 mod circle;
@@ -9,30 +10,50 @@ mod line;
 mod polygon;
 mod polyline;
 mod pt;
+mod quadtree;
 mod ring;
 mod speed;
 mod stats;
 mod time;
+mod units;
 
+pub use crate::acceleration::Acceleration;
 pub use crate::angle::Angle;
 pub use crate::bounds::{Bounds, GPSBounds};
-pub use crate::circle::Circle;
+pub use crate::circle::{Arc, Circle};
 pub use crate::distance::Distance;
 pub use crate::duration::Duration;
 pub use crate::find_closest::FindClosest;
-pub use crate::gps::LonLat;
+pub use crate::gps::{LonLat, Projection};
 pub use crate::line::{InfiniteLine, Line};
 pub use crate::polygon::{Polygon, Triangle};
-pub use crate::polyline::{ArrowCap, PolyLine};
+pub use crate::polyline::{ArrowCap, PolyLine, SliceError};
 pub use crate::pt::{HashablePt2D, Pt2D};
+pub use crate::quadtree::{HasBounds, QuadTree, QuadTreeID};
 pub use crate::ring::Ring;
 pub use crate::speed::Speed;
 pub use crate::stats::{HgramValue, Histogram, Statistic};
 pub use crate::time::Time;
+pub use crate::units::Units;
 
 // About 0.4 inches... which is quite tiny on the scale of things. :)
 pub const EPSILON_DIST: Distance = Distance::const_meters(0.01);
 
+// Splits something like "12.5mph" into (12.5, "mph"). Whitespace between is fine.
+pub(crate) fn split_number_unit(string: &str) -> Result<(f64, &str), abstutil::Error> {
+    let string = string.trim();
+    let idx = string
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or_else(|| string.len());
+    if idx == 0 {
+        return Err(abstutil::Error::new(format!(
+            "{} doesn't start with a number",
+            string
+        )));
+    }
+    Ok((string[..idx].parse::<f64>()?, string[idx..].trim()))
+}
+
 pub fn trim_f64(x: f64) -> f64 {
     (x * 10_000.0).round() / 10_000.0
 }
@@ -63,16 +63,21 @@ impl Line {
             return None;
         }
 
-        let hit = self.infinite().intersection(&other.infinite())?;
-        if self.contains_pt(hit) {
-            // TODO and other contains pt, then we dont need ccw check thing
-            Some(hit)
-        } else {
-            panic!(
-                "{} and {} intersect, but first line doesn't contain_pt({})",
-                self, other, hit
-            );
+        // The orientation checks already say the segments cross, so any distance outside the
+        // segment is just floating point error.
+        let (t, _) = line_params(self.pt1(), self.pt2(), other.pt1(), other.pt2())?;
+        Some(self.percent_along(t.max(0.0).min(1.0)))
+    }
+
+    // Where does a ray starting at origin and pointing at the angle first hit this segment?
+    pub fn ray_intersection(&self, origin: Pt2D, angle: Angle) -> Option<Pt2D> {
+        let ray_end = origin.project_away(Distance::meters(1.0), angle);
+        let (t, u) = line_params(self.pt1(), self.pt2(), origin, ray_end)?;
+        let slop = EPSILON_DIST / self.length();
+        if u < 0.0 || t < -slop || t > 1.0 + slop {
+            return None;
         }
+        Some(self.percent_along(t.max(0.0).min(1.0)))
     }
 
     // An intersection that isn't just two endpoints touching
@@ -216,35 +221,47 @@ fn is_counter_clockwise(pt1: Pt2D, pt2: Pt2D, pt3: Pt2D) -> bool {
     (pt3.y() - pt1.y()) * (pt2.x() - pt1.x()) > (pt2.y() - pt1.y()) * (pt3.x() - pt1.x())
 }
 
+// Where do the infinite lines through (p1, p2) and (q1, q2) cross? Returns how far along each
+// line, as a multiple of its length. Fails for (nearly) parallel lines.
+// https://stackoverflow.com/a/565282 by way of
+// https://github.com/ucarion/line_intersection/blob/master/src/lib.rs
+fn line_params(p1: Pt2D, p2: Pt2D, q1: Pt2D, q2: Pt2D) -> Option<(f64, f64)> {
+    fn cross(a: (f64, f64), b: (f64, f64)) -> f64 {
+        a.0 * b.1 - a.1 * b.0
+    }
+
+    let r = (p2.x() - p1.x(), p2.y() - p1.y());
+    let s = (q2.x() - q1.x(), q2.y() - q1.y());
+    let r_cross_s = cross(r, s);
+    // Scale by the lengths, so this is really checking the sine of the angle between them
+    if r_cross_s.abs() <= PARALLEL_EPSILON * r.0.hypot(r.1) * s.0.hypot(s.1) {
+        return None;
+    }
+    let q_minus_p = (q1.x() - p1.x(), q1.y() - p1.y());
+    Some((
+        cross(q_minus_p, s) / r_cross_s,
+        cross(q_minus_p, r) / r_cross_s,
+    ))
+}
+
+const PARALLEL_EPSILON: f64 = 1e-9;
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct InfiniteLine(Pt2D, Pt2D);
 
 impl InfiniteLine {
+    // The line through a point, pointing in some direction
+    pub fn from_pt_angle(pt: Pt2D, angle: Angle) -> InfiniteLine {
+        InfiniteLine(pt, pt.project_away(Distance::meters(1.0), angle))
+    }
+
     // Fails for parallel lines.
-    // https://stackoverflow.com/a/565282 by way of
-    // https://github.com/ucarion/line_intersection/blob/master/src/lib.rs
     pub fn intersection(&self, other: &InfiniteLine) -> Option<Pt2D> {
-        fn cross(a: (f64, f64), b: (f64, f64)) -> f64 {
-            a.0 * b.1 - a.1 * b.0
-        }
-
-        let p = self.0;
-        let q = other.0;
-        let r = (self.1.x() - self.0.x(), self.1.y() - self.0.y());
-        let s = (other.1.x() - other.0.x(), other.1.y() - other.0.y());
-
-        let r_cross_s = cross(r, s);
-        let q_minus_p = (q.x() - p.x(), q.y() - p.y());
-        //let q_minus_p_cross_r = cross(q_minus_p, r);
-
-        if r_cross_s == 0.0 {
-            // Parallel
-            None
-        } else {
-            let t = cross(q_minus_p, (s.0 / r_cross_s, s.1 / r_cross_s));
-            //let u = cross(q_minus_p, Pt2D::new(r.x() / r_cross_s, r.y() / r_cross_s));
-            Some(Pt2D::new(p.x() + t * r.0, p.y() + t * r.1))
-        }
+        let (t, _) = line_params(self.0, self.1, other.0, other.1)?;
+        Some(Pt2D::new(
+            self.0.x() + t * (self.1.x() - self.0.x()),
+            self.0.y() + t * (self.1.y() - self.0.y()),
+        ))
     }
 }
 
@@ -2,7 +2,7 @@ use crate::{Angle, Bounds, Distance, HashablePt2D, PolyLine, Pt2D, Ring};
 use geo::algorithm::area::Area;
 use geo::algorithm::convexhull::ConvexHull;
 use geo_booleanop::boolean::BooleanOp;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -10,16 +10,32 @@ pub struct Polygon {
     points: Vec<Pt2D>,
     // Groups of three indices make up the triangles
     // TODO u32 better for later, but then we can't index stuff!
+    #[serde(
+        serialize_with = "serialize_indices",
+        deserialize_with = "deserialize_indices"
+    )]
     indices: Vec<usize>,
 }
 
+// Stored as u32 to save space, since no polygon has billions of points
+fn serialize_indices<S: Serializer>(indices: &Vec<usize>, s: S) -> Result<S::Ok, S::Error> {
+    indices
+        .iter()
+        .map(|idx| *idx as u32)
+        .collect::<Vec<_>>()
+        .serialize(s)
+}
+
+fn deserialize_indices<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<usize>, D::Error> {
+    let indices = <Vec<u32>>::deserialize(d)?;
+    Ok(indices.into_iter().map(|idx| idx as usize).collect())
+}
+
 // TODO The triangulation is a bit of a mess. Everything except for Polygon::new comes from
 // https://github.com/lionfish0/earclip/blob/master/earclip/__init__.py.
 
 impl Polygon {
-    // TODO Should the first and last points match or not?
-    // Adapted from https://crates.io/crates/polygon2; couldn't use the crate directly because it
-    // depends on nightly.
+    // TODO Should the first and last points match or not? Both work.
     pub fn new(orig_pts: &Vec<Pt2D>) -> Polygon {
         assert!(orig_pts.len() >= 3);
 
@@ -31,6 +47,50 @@ impl Polygon {
             orig_pts.clone()
         };
 
+        // Don't feed the repeated endpoint to the triangulation
+        let num_pts = if pts.len() > 3 && pts[0] == *pts.last().unwrap() {
+            pts.len() - 1
+        } else {
+            pts.len()
+        };
+        let indices = earcut(&pts[0..num_pts], &Vec::new());
+        if indices.is_empty() {
+            // earcut gives up on some degenerate input. The old approach at least produces
+            // something.
+            return Polygon::ear_clip(pts);
+        }
+        Polygon {
+            points: pts,
+            indices,
+        }
+    }
+
+    // Triangulates a polygon with holes cut out of it, like a building with a courtyard. The
+    // boolean operations below don't understand the holes yet.
+    pub fn with_holes(outer: Ring, holes: Vec<Ring>) -> Polygon {
+        let mut points = outer.into_points();
+        points.pop();
+        let num_outer = points.len();
+        let mut hole_starts = Vec::new();
+        for hole in holes {
+            hole_starts.push(points.len());
+            let mut pts = hole.into_points();
+            pts.pop();
+            points.extend(pts);
+        }
+        let indices = earcut(&points, &hole_starts);
+        if indices.is_empty() {
+            // earcut gives up on some degenerate input. Lose the holes, but at least draw the
+            // outline.
+            points.truncate(num_outer);
+            return Polygon::new(&points);
+        }
+        Polygon { points, indices }
+    }
+
+    // Adapted from https://crates.io/crates/polygon2; couldn't use the crate directly because it
+    // depends on nightly. Expects counter-clockwise points.
+    fn ear_clip(pts: Vec<Pt2D>) -> Polygon {
         let mut indices: Vec<usize> = Vec::new();
         let mut avl = Vec::with_capacity(pts.len());
         for i in 0..pts.len() {
@@ -136,7 +196,11 @@ impl Polygon {
     }
 
     pub fn rotate(&self, angle: Angle) -> Polygon {
-        let center = self.center();
+        self.rotate_around(angle, self.center())
+    }
+
+    pub fn rotate_around(&self, angle: Angle, pivot: Pt2D) -> Polygon {
+        let center = pivot;
 
         Polygon {
             points: self
@@ -246,6 +310,8 @@ impl Polygon {
         Polygon::new(&pts)
     }
 
+    // Just combines the triangles, without changing any geometry. Cheap and good enough for
+    // drawing. Use boolean_union to actually merge the outlines.
     pub fn union(self, other: Polygon) -> Polygon {
         let mut points = self.points;
         let mut indices = self.indices;
@@ -265,12 +331,31 @@ impl Polygon {
         result
     }
 
+    // These boolean operations only work for polygons that're formed from rings, like
+    // to_outline. Pieces that degenerate to less than a triangle are dropped.
+    // TODO Holes in the result are dropped too.
     pub fn intersection(&self, other: &Polygon) -> Vec<Polygon> {
         from_multi(to_geo(self.points()).intersection(&to_geo(other.points())))
     }
     pub fn difference(&self, other: &Polygon) -> Vec<Polygon> {
         from_multi(to_geo(self.points()).difference(&to_geo(other.points())))
     }
+    pub fn boolean_union(&self, other: &Polygon) -> Vec<Polygon> {
+        from_multi(to_geo(self.points()).union(&to_geo(other.points())))
+    }
+    pub fn overlaps(&self, other: &Polygon) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    // Merge the outlines of many polygons, like the area reachable from somewhere. Overlapping
+    // pieces become one polygon.
+    pub fn dissolve(list: Vec<Polygon>) -> Vec<Polygon> {
+        let mut result: geo::MultiPolygon<f64> = geo::MultiPolygon(Vec::new());
+        for p in list {
+            result = result.union(&to_geo(p.points()));
+        }
+        from_multi(result)
+    }
 
     pub fn convex_hull(list: Vec<Polygon>) -> Polygon {
         let mp: geo::MultiPolygon<f64> = list.into_iter().map(|p| to_geo(p.points())).collect();
@@ -410,6 +495,17 @@ impl Triangle {
     }
 }
 
+// Returns groups of three indices into pts, one per triangle. hole_starts are the indices where
+// each hole's points begin.
+fn earcut(pts: &[Pt2D], hole_starts: &Vec<usize>) -> Vec<usize> {
+    let mut vertices = Vec::with_capacity(2 * pts.len());
+    for pt in pts {
+        vertices.push(pt.x());
+        vertices.push(pt.y());
+    }
+    earcutr::earcut(&vertices, hole_starts, 2)
+}
+
 fn is_clockwise_polygon(pts: &Vec<Pt2D>) -> bool {
     // Initialize with the last element
     let mut sum = (pts[0].x() - pts.last().unwrap().x()) * (pts[0].y() + pts.last().unwrap().y());
@@ -442,5 +538,14 @@ fn from_geo(p: geo::Polygon<f64>) -> Polygon {
 }
 
 fn from_multi(multi: geo::MultiPolygon<f64>) -> Vec<Polygon> {
-    multi.into_iter().map(from_geo).collect()
+    multi
+        .into_iter()
+        .filter(|p| {
+            // The exterior ring repeats the first point at the end
+            let mut pts = p.exterior().0.clone();
+            pts.dedup();
+            pts.len() >= 4
+        })
+        .map(from_geo)
+        .collect()
 }
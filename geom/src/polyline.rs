@@ -8,6 +8,9 @@ use std::fmt;
 
 // TODO How to tune this?
 const MITER_THRESHOLD: f64 = 500.0;
+// Sharp corners make the miter point shoot far away from the original corner. Clamp it to this
+// many times the shift width, like SVG's stroke-miterlimit.
+const MITER_LIMIT: f64 = 4.0;
 
 pub enum ArrowCap {
     Triangle,
@@ -192,22 +195,23 @@ impl PolyLine {
         self.length
     }
 
-    // Returns the excess distance left over from the end. None if the result would be too squished
-    // together.
-    pub fn slice(&self, start: Distance, end: Distance) -> Option<(PolyLine, Distance)> {
+    // Returns the excess distance left over from the end.
+    pub fn slice(
+        &self,
+        start: Distance,
+        end: Distance,
+    ) -> Result<(PolyLine, Distance), SliceError> {
         if start > end || start < Distance::ZERO || end < Distance::ZERO {
-            panic!("Can't get a polyline slice [{}, {}]", start, end);
+            return Err(SliceError::BadRange { start, end });
         }
         if start > self.length() {
-            panic!(
-                "Can't get a polyline slice [{}, {}] on something of length {}",
+            return Err(SliceError::StartTooFar {
                 start,
-                end,
-                self.length()
-            );
+                length: self.length(),
+            });
         }
         if end - start < EPSILON_DIST {
-            return None;
+            return Err(SliceError::TooShort { start, end });
         }
 
         let mut result: Vec<Pt2D> = Vec::new();
@@ -230,9 +234,9 @@ impl PolyLine {
                 result.push(last_pt);
                 if result.len() == 1 {
                     // TODO Understand what happened here.
-                    return None;
+                    return Err(SliceError::TooShort { start, end });
                 }
-                return Some((PolyLine::new(result), Distance::ZERO));
+                return Ok((PolyLine::new(result), Distance::ZERO));
             }
 
             // If we're in the middle, just collect the endpoint. But not if it's too close to the
@@ -245,35 +249,39 @@ impl PolyLine {
         }
 
         if result.is_empty() {
-            panic!(
-                "Slice [{}, {}] has a start too big for polyline of length {}",
+            // The start is within EPSILON_DIST of the end
+            return Err(SliceError::StartTooFar {
                 start,
-                end,
-                self.length()
-            );
+                length: self.length(),
+            });
         }
         if result.len() == 1 {
-            return None;
+            return Err(SliceError::TooShort { start, end });
         }
 
-        Some((PolyLine::new(result), end - dist_so_far))
+        Ok((PolyLine::new(result), end - dist_so_far))
     }
 
     // No excess leftover distance allowed.
-    pub fn exact_slice(&self, start: Distance, end: Distance) -> PolyLine {
-        let (pl, leftover) = self
-            .slice(start, end)
-            .unwrap_or_else(|| panic!("exact_slice({}, {}) yielded empty slice", start, end));
+    pub fn maybe_exact_slice(
+        &self,
+        start: Distance,
+        end: Distance,
+    ) -> Result<PolyLine, SliceError> {
+        let (pl, leftover) = self.slice(start, end)?;
         if leftover > EPSILON_DIST {
-            panic!(
-                "exact_slice({}, {}) on a PL of length {} yielded leftover distance of {}",
-                start,
+            return Err(SliceError::Leftover {
                 end,
-                self.length(),
-                leftover
-            );
+                length: self.length(),
+            });
         }
-        pl
+        Ok(pl)
+    }
+
+    // Panics if the slice can't be made; use maybe_exact_slice when the input is suspicious.
+    pub fn exact_slice(&self, start: Distance, end: Distance) -> PolyLine {
+        self.maybe_exact_slice(start, end)
+            .unwrap_or_else(|err| panic!("exact_slice({}, {}) failed: {}", start, end, err))
     }
 
     pub fn first_half(&self) -> PolyLine {
@@ -352,17 +360,48 @@ impl PolyLine {
 
     // Things to remember about shifting polylines:
     // - the length before and after probably don't match up
-    // - the number of points will match
+    // - the number of points might not match. On the inside of tight curves, the shifted segments
+    //   flip around and cross each other; those get collapsed and the loops cut out.
     fn shift_with_corrections(&self, width: Distance) -> Warn<PolyLine> {
-        let mut raw = self.shift_with_sharp_angles(width, MITER_THRESHOLD);
-        raw.dedup();
-        let result = PolyLine::new(raw);
-        let fixed = if result.pts.len() == self.pts.len() {
-            fix_angles(self, result)
+        let raw = self.shift_with_sharp_angles(width, MITER_THRESHOLD);
+        let (collapsed, any_flipped) = collapse_flipped_segments(&self.pts, raw);
+        let pts = if any_flipped {
+            remove_loops(collapsed)
         } else {
-            result
+            collapsed
         };
-        check_angles(self, fixed)
+        let pts = Pt2D::approx_dedupe(pts, Distance::meters(0.001));
+        if pts.len() < 2 {
+            // Everything collapsed. Just connect the shifted endpoints.
+            let l1 = self.first_line().shift_either_direction(width);
+            let l2 = self.last_line().shift_either_direction(width);
+            return match PolyLine::maybe_new(vec![l1.pt1(), l2.pt2()]) {
+                Some(result) => Warn::warn(
+                    result,
+                    format!(
+                        "Shifting {} by {} collapsed everything, just connecting the endpoints",
+                        self, width
+                    ),
+                ),
+                None => Warn::warn(
+                    self.clone(),
+                    format!("Couldn't shift {} by {}, leaving it alone", self, width),
+                ),
+            };
+        }
+        match PolyLine::maybe_new(pts) {
+            Some(result) => {
+                if result.pts.len() == self.pts.len() {
+                    check_angles(self, result)
+                } else {
+                    Warn::ok(result)
+                }
+            }
+            None => Warn::warn(
+                self.clone(),
+                format!("Couldn't shift {} by {}, leaving it alone", self, width),
+            ),
+        }
     }
 
     fn shift_with_sharp_angles(&self, width: Distance, miter_threshold: f64) -> Vec<Pt2D> {
@@ -391,7 +430,12 @@ impl PolyLine {
                 // Miter caps sometimes explode out to infinity. Hackily work around this.
                 let dist_away = l1.pt1().raw_dist_to(pt2_shift);
                 if dist_away < miter_threshold {
-                    result.push(pt2_shift);
+                    let limit = width.abs() * MITER_LIMIT;
+                    if pt2_raw.dist_to(pt2_shift) > limit {
+                        result.push(pt2_raw.project_away(limit, pt2_raw.angle_to(pt2_shift)));
+                    } else {
+                        result.push(pt2_shift);
+                    }
                 } else {
                     result.push(l1.pt2());
                 }
@@ -611,6 +655,14 @@ impl PolyLine {
     }
 
     // TODO Also distance along
+    // The closest place a ray from origin pointing at the angle hits this polyline
+    pub fn ray_intersection(&self, origin: Pt2D, angle: Angle) -> Option<Pt2D> {
+        self.lines()
+            .into_iter()
+            .filter_map(|l| l.ray_intersection(origin, angle))
+            .min_by_key(|hit| hit.dist_to(origin))
+    }
+
     pub fn intersection_infinite(&self, other: &InfiniteLine) -> Option<Pt2D> {
         for l in self.lines() {
             if let Some(hit) = l.intersection_infinite(other) {
@@ -690,8 +742,109 @@ impl PolyLine {
     pub fn get_bounds(&self) -> Bounds {
         Bounds::from(&self.pts)
     }
+
+    // Douglas-Peucker: drop points that're within epsilon of the line through their neighbors.
+    // The endpoints always stay.
+    pub fn simplify(&self, epsilon: Distance) -> PolyLine {
+        let mut keep = vec![false; self.pts.len()];
+        keep[0] = true;
+        keep[self.pts.len() - 1] = true;
+        let mut stack = vec![(0, self.pts.len() - 1)];
+        while let Some((start, end)) = stack.pop() {
+            let mut furthest = None;
+            let mut max_dist = epsilon;
+            for idx in (start + 1)..end {
+                let dist = dist_to_segment(self.pts[idx], self.pts[start], self.pts[end]);
+                if dist > max_dist {
+                    max_dist = dist;
+                    furthest = Some(idx);
+                }
+            }
+            if let Some(idx) = furthest {
+                keep[idx] = true;
+                stack.push((start, idx));
+                stack.push((idx, end));
+            }
+        }
+        let pts: Vec<Pt2D> = self
+            .pts
+            .iter()
+            .zip(keep)
+            .filter_map(|(pt, keep)| if keep { Some(*pt) } else { None })
+            .collect();
+        // Dropping points from a loop might make it touch itself
+        PolyLine::maybe_new(pts).unwrap_or_else(|| self.clone())
+    }
+
+    // Chaikin's corner cutting. Each pass replaces every interior corner with two points a quarter
+    // of the way along the adjacent segments. The endpoints stay put, but the result no longer
+    // passes through the interior points.
+    pub fn smooth(&self, iterations: usize) -> PolyLine {
+        let mut pts = self.pts.clone();
+        for _ in 0..iterations {
+            if pts.len() < 3 {
+                break;
+            }
+            let mut smoothed = vec![pts[0]];
+            for (idx, pair) in pts.windows(2).enumerate() {
+                let (p1, p2) = (pair[0], pair[1]);
+                if idx != 0 {
+                    smoothed.push(Pt2D::new(
+                        0.75 * p1.x() + 0.25 * p2.x(),
+                        0.75 * p1.y() + 0.25 * p2.y(),
+                    ));
+                }
+                if idx != pts.len() - 2 {
+                    smoothed.push(Pt2D::new(
+                        0.25 * p1.x() + 0.75 * p2.x(),
+                        0.25 * p1.y() + 0.75 * p2.y(),
+                    ));
+                }
+            }
+            smoothed.push(*pts.last().unwrap());
+            pts = Pt2D::approx_dedupe(smoothed, Distance::meters(0.01));
+        }
+        PolyLine::maybe_new(pts).unwrap_or_else(|| self.clone())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SliceError {
+    // The start is after the end, or something's negative
+    BadRange { start: Distance, end: Distance },
+    StartTooFar { start: Distance, length: Distance },
+    // The result would be too squished together to be a PolyLine
+    TooShort { start: Distance, end: Distance },
+    // Only for exact slices
+    Leftover { end: Distance, length: Distance },
+    // Pieces sliced from consecutive steps of a path couldn't be glued together
+    DuplicatePoints,
 }
 
+impl fmt::Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SliceError::BadRange { start, end } => write!(f, "bad slice [{}, {}]", start, end),
+            SliceError::StartTooFar { start, length } => write!(
+                f,
+                "slice starts at {}, past the end of something {} long",
+                start, length
+            ),
+            SliceError::TooShort { start, end } => {
+                write!(f, "slice [{}, {}] is too short", start, end)
+            }
+            SliceError::Leftover { end, length } => write!(
+                f,
+                "slice ends at {}, past the end of something {} long",
+                end, length
+            ),
+            SliceError::DuplicatePoints => write!(f, "pieces to glue together share points"),
+        }
+    }
+}
+
+impl std::error::Error for SliceError {}
+
 impl fmt::Display for PolyLine {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "PolyLine::new(vec![")?;
@@ -714,29 +867,86 @@ impl fmt::Display for PolyLine {
     }
 }
 
-fn fix_angles(orig: &PolyLine, result: PolyLine) -> PolyLine {
-    let mut pts = result.pts.clone();
+fn dist_to_segment(pt: Pt2D, a: Pt2D, b: Pt2D) -> Distance {
+    let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+    let len_squared = dx * dx + dy * dy;
+    if len_squared == 0.0 {
+        return pt.dist_to(a);
+    }
+    let t = (((pt.x() - a.x()) * dx + (pt.y() - a.y()) * dy) / len_squared)
+        .max(0.0)
+        .min(1.0);
+    pt.dist_to(Pt2D::new(a.x() + t * dx, a.y() + t * dy))
+}
 
-    // Check that the angles roughly match up between the original and shifted line
-    for (idx, (orig_l, shifted_l)) in orig.lines().iter().zip(result.lines().iter()).enumerate() {
-        let orig_angle = orig_l.angle();
-        let shifted_angle = shifted_l.angle();
+// A shifted segment pointing the opposite way as the original means the shift went past the center
+// of a curve. Merge each run of points joined by flipped segments into one point. The endpoints
+// stay put. Also returns true if anything was flipped.
+fn collapse_flipped_segments(orig: &Vec<Pt2D>, shifted: Vec<Pt2D>) -> (Vec<Pt2D>, bool) {
+    let flipped: Vec<bool> = orig
+        .windows(2)
+        .zip(shifted.windows(2))
+        .map(|(o, s)| {
+            let dot = (o[1].x() - o[0].x()) * (s[1].x() - s[0].x())
+                + (o[1].y() - o[0].y()) * (s[1].y() - s[0].y());
+            dot < 0.0
+        })
+        .collect();
+    if !flipped.contains(&true) {
+        return (shifted, false);
+    }
 
-        if !orig_angle.approx_eq(shifted_angle, 1.0) {
-            // When this happens, the rotation is usually right around 180 -- so try swapping
-            // the points!
-            /*println!(
-                "Points changed angles from {} to {} (rot {})",
-                orig_angle, shifted_angle, rot
-            );*/
-            pts.swap(idx, idx + 1);
-            // TODO Start the fixing over. but make sure we won't infinite loop...
-            //return fix_angles(orig, result);
+    let last_idx = shifted.len() - 1;
+    let mut result = Vec::new();
+    let mut run: Vec<usize> = vec![0];
+    for idx in 1..shifted.len() {
+        if flipped[idx - 1] {
+            run.push(idx);
+            continue;
         }
+        result.push(collapse_run(&shifted, &run, last_idx));
+        run = vec![idx];
+    }
+    result.push(collapse_run(&shifted, &run, last_idx));
+    if run.contains(&0) && run.contains(&last_idx) {
+        // The whole thing flipped
+        return (vec![shifted[0], shifted[last_idx]], true);
     }
+    (result, true)
+}
 
-    // When we swap points, length of the entire PolyLine may change! Recalculating is vital.
-    PolyLine::new(pts)
+fn collapse_run(pts: &Vec<Pt2D>, run: &Vec<usize>, last_idx: usize) -> Pt2D {
+    if run.contains(&0) {
+        pts[0]
+    } else if run.contains(&last_idx) {
+        pts[last_idx]
+    } else {
+        Pt2D::center(&run.iter().map(|idx| pts[*idx]).collect())
+    }
+}
+
+// If the line crosses itself, cut out the loop between the two crossing segments.
+fn remove_loops(mut pts: Vec<Pt2D>) -> Vec<Pt2D> {
+    'restart: loop {
+        for i in 0..pts.len().saturating_sub(1) {
+            let l1 = match Line::maybe_new(pts[i], pts[i + 1]) {
+                Some(l) => l,
+                None => continue,
+            };
+            for j in (i + 2)..pts.len() - 1 {
+                if let Some(l2) = Line::maybe_new(pts[j], pts[j + 1]) {
+                    if let Some(hit) = l1.intersection(&l2) {
+                        let mut fixed = pts[0..=i].to_vec();
+                        fixed.push(hit);
+                        fixed.extend(pts[j + 1..].iter().cloned());
+                        pts = fixed;
+                        continue 'restart;
+                    }
+                }
+            }
+        }
+        return pts;
+    }
 }
 
 fn check_angles(orig: &PolyLine, fixed: PolyLine) -> Warn<PolyLine> {
@@ -769,3 +979,94 @@ fn to_set(pts: &[Pt2D]) -> (HashSet<HashablePt2D>, HashSet<HashablePt2D>) {
     }
     (deduped, dupes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PolyLine;
+    use crate::{Distance, Line, Pt2D};
+
+    // Shifting these used to produce spikes and loops. Whatever comes out shouldn't cross itself.
+    #[test]
+    fn test_shift_pathological() {
+        let mut inputs = vec![
+            // Hairpin
+            vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(50.0, 0.0),
+                Pt2D::new(50.5, 1.0),
+                Pt2D::new(0.0, 2.0),
+            ],
+            // Tight zigzag
+            (0..20)
+                .map(|i| Pt2D::new(i as f64, if i % 2 == 0 { 0.0 } else { 1.5 }))
+                .collect(),
+            // Nearly doubles back
+            vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(10.0, 0.0),
+                Pt2D::new(0.0, 0.1),
+            ],
+        ];
+        // Small circles, smaller than the shift width
+        for num_pts in 5..12 {
+            inputs.push(
+                (0..num_pts)
+                    .map(|i| {
+                        let theta = (i as f64) / (num_pts as f64) * 1.5 * std::f64::consts::PI;
+                        Pt2D::new(100.0 + 2.0 * theta.cos(), 100.0 + 2.0 * theta.sin())
+                    })
+                    .collect(),
+            );
+        }
+
+        for pts in inputs {
+            let pl = PolyLine::new(pts);
+            for width in &[0.5, 2.0, 5.0] {
+                let width = Distance::meters(*width);
+                for shifted in vec![pl.shift_right(width), pl.shift_left(width)] {
+                    assert_no_self_intersections(shifted.unwrap().points());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_simplify_and_smooth() {
+        // A straight road with some jitter
+        let jittery = PolyLine::new(
+            (0..10)
+                .map(|i| Pt2D::new(10.0 * (i as f64), if i % 2 == 0 { 0.0 } else { 0.2 }))
+                .collect(),
+        );
+        let simple = jittery.simplify(Distance::meters(0.5));
+        assert_eq!(simple.points().len(), 2);
+        assert_eq!(simple.first_pt(), jittery.first_pt());
+        assert_eq!(simple.last_pt(), jittery.last_pt());
+        // A real corner survives
+        let corner = PolyLine::new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(10.0, 10.0),
+        ]);
+        assert_eq!(corner.simplify(Distance::meters(0.5)).points().len(), 3);
+
+        let smooth = corner.smooth(3);
+        assert!(smooth.points().len() > 3);
+        assert_eq!(smooth.first_pt(), corner.first_pt());
+        assert_eq!(smooth.last_pt(), corner.last_pt());
+        assert!(smooth.length() < corner.length());
+    }
+
+    fn assert_no_self_intersections(pts: &Vec<Pt2D>) {
+        let lines: Vec<Line> = pts.windows(2).map(|p| Line::new(p[0], p[1])).collect();
+        for i in 0..lines.len() {
+            for j in (i + 2)..lines.len() {
+                assert!(
+                    lines[i].intersection(&lines[j]).is_none(),
+                    "{:?} crosses itself",
+                    pts
+                );
+            }
+        }
+    }
+}
@@ -1,15 +1,64 @@
 use crate::{trim_f64, Angle, Distance, GPSBounds, LonLat, EPSILON_DIST};
 use ordered_float::NotNan;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 // This represents world-space in meters.
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug)]
 pub struct Pt2D {
     inner_x: f64,
     inner_y: f64,
 }
 
+// Coordinates are trimmed to 4 decimal places anyway, so binary formats store them as fixed-point
+// integers. That halves the size and round-trips exactly. This limits maps to about 200km across.
+// JSON stays readable and keeps the plain float form.
+const FIXED_POINT_SCALE: f64 = 10_000.0;
+
+#[derive(Serialize, Deserialize)]
+struct FloatPt2D {
+    inner_x: f64,
+    inner_y: f64,
+}
+
+impl Serialize for Pt2D {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return FloatPt2D {
+                inner_x: self.inner_x,
+                inner_y: self.inner_y,
+            }
+            .serialize(serializer);
+        }
+        let to_fixed = |value: f64| {
+            let fixed = (value * FIXED_POINT_SCALE).round();
+            if fixed < (std::i32::MIN as f64) || fixed > (std::i32::MAX as f64) {
+                Err(serde::ser::Error::custom(format!(
+                    "{} is too far away to store as a Pt2D",
+                    value
+                )))
+            } else {
+                Ok(fixed as i32)
+            }
+        };
+        (to_fixed(self.inner_x)?, to_fixed(self.inner_y)?).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pt2D {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pt2D, D::Error> {
+        if deserializer.is_human_readable() {
+            let pt = FloatPt2D::deserialize(deserializer)?;
+            return Ok(Pt2D::new(pt.inner_x, pt.inner_y));
+        }
+        let (x, y) = <(i32, i32)>::deserialize(deserializer)?;
+        Ok(Pt2D::new(
+            (x as f64) / FIXED_POINT_SCALE,
+            (y as f64) / FIXED_POINT_SCALE,
+        ))
+    }
+}
+
 impl std::cmp::PartialEq for Pt2D {
     fn eq(&self, other: &Pt2D) -> bool {
         self.approx_eq(*other, EPSILON_DIST)
@@ -45,27 +94,12 @@ impl Pt2D {
 
     // Can go out of bounds.
     pub fn forcibly_from_gps(gps: LonLat, b: &GPSBounds) -> Pt2D {
-        let (width, height) = {
-            let pt = b.get_max_world_pt();
-            (pt.x(), pt.y())
-        };
-
-        let x = (gps.x() - b.min_lon) / (b.max_lon - b.min_lon) * width;
-        // Invert y, so that the northernmost latitude is 0. Screen drawing order, not Cartesian
-        // grid.
-        let y = height - ((gps.y() - b.min_lat) / (b.max_lat - b.min_lat) * height);
-        Pt2D::new(x, y)
+        b.project(gps)
     }
 
     // Can go out of bounds.
     pub fn forcibly_to_gps(self, b: &GPSBounds) -> LonLat {
-        let (width, height) = {
-            let pt = b.get_max_world_pt();
-            (pt.x(), pt.y())
-        };
-        let lon = (self.x() / width * (b.max_lon - b.min_lon)) + b.min_lon;
-        let lat = b.min_lat + ((b.max_lat - b.min_lat) * (height - self.y()) / height);
-        LonLat::new(lon, lat)
+        b.unproject(self)
     }
 
     pub fn to_gps(self, b: &GPSBounds) -> Option<LonLat> {
@@ -76,11 +110,7 @@ impl Pt2D {
         if self.x() < 0.0 || self.y() < 0.0 || self.x() > width || self.y() > height {
             return None;
         }
-
-        let lon = (self.x() / width * (b.max_lon - b.min_lon)) + b.min_lon;
-        let lat = b.min_lat + ((b.max_lat - b.min_lat) * (height - self.y()) / height);
-
-        Some(LonLat::new(lon, lat))
+        Some(b.unproject(self))
     }
 
     pub fn x(self) -> f64 {
@@ -0,0 +1,109 @@
+use crate::{Bounds, Circle, Distance, PolyLine, Polygon, Pt2D};
+use aabb_quadtree::geom::{Point, Rect};
+use aabb_quadtree::ItemId;
+
+// Anything that takes up space and can be indexed
+pub trait HasBounds {
+    fn get_bounds(&self) -> Bounds;
+}
+
+impl HasBounds for Bounds {
+    fn get_bounds(&self) -> Bounds {
+        self.clone()
+    }
+}
+
+impl HasBounds for Pt2D {
+    fn get_bounds(&self) -> Bounds {
+        Bounds::from(&vec![*self])
+    }
+}
+
+impl HasBounds for Polygon {
+    fn get_bounds(&self) -> Bounds {
+        Polygon::get_bounds(self)
+    }
+}
+
+impl HasBounds for PolyLine {
+    fn get_bounds(&self) -> Bounds {
+        PolyLine::get_bounds(self)
+    }
+}
+
+impl HasBounds for Circle {
+    fn get_bounds(&self) -> Bounds {
+        Circle::get_bounds(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuadTreeID(ItemId);
+
+// Finds things overlapping some area, without checking everything. The values stored are usually
+// IDs; the caller keeps the real geometry elsewhere and does precise checks on the candidates.
+pub struct QuadTree<T> {
+    tree: aabb_quadtree::QuadTree<(T, Bounds)>,
+}
+
+impl<T> QuadTree<T> {
+    // Everything inserted has to fit inside these bounds. They also have to be non-negative.
+    pub fn new(bounds: &Bounds) -> QuadTree<T> {
+        QuadTree {
+            tree: aabb_quadtree::QuadTree::default(bounds.as_bbox()),
+        }
+    }
+
+    pub fn insert_with_bounds(&mut self, value: T, bounds: Bounds) -> QuadTreeID {
+        let bbox = bounds.as_bbox();
+        QuadTreeID(self.tree.insert_with_box((value, bounds), bbox))
+    }
+
+    pub fn remove(&mut self, id: QuadTreeID) -> Option<T> {
+        self.tree.remove(id.0).map(|((value, _), _)| value)
+    }
+
+    // Everything whose bounding box overlaps the query, in no particular order
+    pub fn query(&self, bounds: &Bounds) -> Vec<&T> {
+        self.tree
+            .query(bounds.as_bbox())
+            .into_iter()
+            .map(|((value, _), _, _)| value)
+            .collect()
+    }
+
+    // The thing whose bounding box is closest to the point, if anything's within max_dist
+    pub fn nearest(&self, pt: Pt2D, max_dist: Distance) -> Option<&T> {
+        let query = Rect {
+            top_left: Point {
+                x: (pt.x() - max_dist.inner_meters()) as f32,
+                y: (pt.y() - max_dist.inner_meters()) as f32,
+            },
+            bottom_right: Point {
+                x: (pt.x() + max_dist.inner_meters()) as f32,
+                y: (pt.y() + max_dist.inner_meters()) as f32,
+            },
+        };
+        self.tree
+            .query(query)
+            .into_iter()
+            .map(|((value, bounds), _, _)| (value, dist_to_bounds(pt, bounds)))
+            .filter(|(_, dist)| *dist <= max_dist)
+            .min_by_key(|(_, dist)| *dist)
+            .map(|(value, _)| value)
+    }
+}
+
+impl<T: HasBounds> QuadTree<T> {
+    pub fn insert(&mut self, value: T) -> QuadTreeID {
+        let bounds = value.get_bounds();
+        self.insert_with_bounds(value, bounds)
+    }
+}
+
+// 0 if the point is inside
+fn dist_to_bounds(pt: Pt2D, b: &Bounds) -> Distance {
+    let dx = (b.min_x - pt.x()).max(0.0).max(pt.x() - b.max_x);
+    let dy = (b.min_y - pt.y()).max(0.0).max(pt.y() - b.max_y);
+    Distance::meters((dx * dx + dy * dy).sqrt())
+}
@@ -71,6 +71,22 @@ impl Ring {
         self.pts
     }
 
+    // Even-odd rule: cast a ray to the right and count how many edges it crosses. Unlike
+    // Polygon::contains_pt, this doesn't need triangles.
+    pub fn contains_pt(&self, pt: Pt2D) -> bool {
+        let mut inside = false;
+        for pair in self.pts.windows(2) {
+            let (p1, p2) = (pair[0], pair[1]);
+            if (p1.y() > pt.y()) != (p2.y() > pt.y()) {
+                let x = p1.x() + (pt.y() - p1.y()) / (p2.y() - p1.y()) * (p2.x() - p1.x());
+                if pt.x() < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
     // The order of results isn't meaningful. Dedupes.
     pub fn all_intersections(&self, other: &PolyLine) -> Vec<Pt2D> {
         let mut hits = Vec::new();
@@ -1,4 +1,4 @@
-use crate::{trim_f64, Distance, Duration, EPSILON_DIST};
+use crate::{split_number_unit, trim_f64, Distance, Duration, Units, EPSILON_DIST};
 use serde::{Deserialize, Serialize};
 use std::{fmt, ops};
 
@@ -31,6 +31,10 @@ impl Speed {
         Speed::meters_per_second(0.44704 * value)
     }
 
+    pub fn km_per_hour(value: f64) -> Speed {
+        Speed::meters_per_second(value / 3.6)
+    }
+
     pub fn from_dist_time(d: Distance, t: Duration) -> Speed {
         Speed::meters_per_second(d.inner_meters() / t.inner_seconds())
     }
@@ -40,6 +44,13 @@ impl Speed {
         self.0
     }
 
+    pub fn describe_rounded(self, units: Units) -> String {
+        match units {
+            Units::Imperial => format!("{} mph", (self.0 * 2.23694).round()),
+            Units::Metric => format!("{} km/h", (self.0 * 3.6).round()),
+        }
+    }
+
     pub fn max(self, other: Speed) -> Speed {
         if self >= other {
             self
@@ -105,6 +116,24 @@ impl ops::Mul<Duration> for Speed {
     }
 }
 
+// Accepts "25mph", "40km/h", or "11m/s". The unit is required, since there's no obvious default.
+impl std::str::FromStr for Speed {
+    type Err = abstutil::Error;
+
+    fn from_str(string: &str) -> Result<Speed, abstutil::Error> {
+        let (value, unit) = split_number_unit(string)?;
+        match unit {
+            "mph" => Ok(Speed::miles_per_hour(value)),
+            "km/h" | "kmh" | "kph" => Ok(Speed::km_per_hour(value)),
+            "m/s" => Ok(Speed::meters_per_second(value)),
+            _ => Err(abstutil::Error::new(format!(
+                "Speed {}: unknown unit {}",
+                string, unit
+            ))),
+        }
+    }
+}
+
 impl fmt::Display for Speed {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} mph", (self.0 * 2.23694).round())
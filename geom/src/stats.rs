@@ -7,7 +7,9 @@ pub enum Statistic {
     Min,
     Mean,
     P50,
+    P85,
     P90,
+    P95,
     P99,
     Max,
 }
@@ -18,7 +20,9 @@ impl Statistic {
             Statistic::Min,
             Statistic::Mean,
             Statistic::P50,
+            Statistic::P85,
             Statistic::P90,
+            Statistic::P95,
             Statistic::P99,
             Statistic::Max,
         ]
@@ -31,7 +35,9 @@ impl std::fmt::Display for Statistic {
             Statistic::Min => write!(f, "minimum"),
             Statistic::Mean => write!(f, "mean"),
             Statistic::P50 => write!(f, "50%ile"),
+            Statistic::P85 => write!(f, "85%ile"),
             Statistic::P90 => write!(f, "90%ile"),
+            Statistic::P95 => write!(f, "95%ile"),
             Statistic::P99 => write!(f, "99%ile"),
             Statistic::Max => write!(f, "maximum"),
         }
@@ -145,7 +151,9 @@ impl<T: HgramValue<T>> Histogram<T> {
         assert_ne!(self.count, 0);
         let raw = match stat {
             Statistic::P50 => self.histogram.percentile(50.0).unwrap(),
+            Statistic::P85 => self.histogram.percentile(85.0).unwrap(),
             Statistic::P90 => self.histogram.percentile(90.0).unwrap(),
+            Statistic::P95 => self.histogram.percentile(95.0).unwrap(),
             Statistic::P99 => self.histogram.percentile(99.0).unwrap(),
             Statistic::Min => {
                 return self.min;
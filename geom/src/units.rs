@@ -0,0 +1,6 @@
+// Everything is stored in metric. This just picks how distances and speeds are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Units {
+    Metric,
+    Imperial,
+}
@@ -1,4 +1,4 @@
-use geom::LonLat;
+use geom::{Duration, LonLat, Time};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeSet, HashMap};
@@ -8,17 +8,25 @@ use transitfeed::GTFSIterator;
 pub struct Route {
     pub name: String,
     pub stops: Vec<LonLat>,
+    // When each trip departs from the first stop, sorted. Empty if the schedule is unknown.
+    pub spawn_times: Vec<Time>,
+    // Streetcars and light rail run on tracks instead of roads
+    pub is_rail: bool,
 }
 
 pub fn load(dir_path: &str) -> Vec<Route> {
     println!("Loading GTFS from {}", dir_path);
 
     let mut route_id_to_name: HashMap<String, String> = HashMap::new();
+    let mut rail_routes: BTreeSet<String> = BTreeSet::new();
     for rec in GTFSIterator::<_, transitfeed::Route>::from_path(&format!("{}/routes.txt", dir_path))
         .unwrap()
     {
         let rec = rec.unwrap();
         route_id_to_name.insert(rec.route_id.clone(), rec.route_short_name.clone());
+        if let transitfeed::RouteType::LightRail = rec.route_type {
+            rail_routes.insert(rec.route_id.clone());
+        }
     }
 
     let mut stop_id_to_pt: HashMap<String, LonLat> = HashMap::new();
@@ -29,11 +37,18 @@ pub fn load(dir_path: &str) -> Vec<Route> {
         stop_id_to_pt.insert(rec.stop_id.clone(), LonLat::new(rec.stop_lon, rec.stop_lat));
     }
 
+    let services = weekday_services(dir_path);
+
     let mut trip_id_to_route_id_and_direction: HashMap<String, (String, bool)> = HashMap::new();
     for rec in
         GTFSIterator::<_, transitfeed::Trip>::from_path(&format!("{}/trips.txt", dir_path)).unwrap()
     {
         let rec = rec.unwrap();
+        if let Some(ref services) = services {
+            if !services.contains(&rec.service_id) {
+                continue;
+            }
+        }
         trip_id_to_route_id_and_direction.insert(
             rec.trip_id.clone(),
             (
@@ -45,8 +60,9 @@ pub fn load(dir_path: &str) -> Vec<Route> {
 
     // Each (directed) route has many trips. Ignore all but the first and assume the list of stops
     // is the same. Also assume that records with the same trip are contiguous and that
-    // stop_sequence is monotonic.
+    // stop_sequence is monotonic. Every trip contributes its departure time, though.
     let mut directed_routes: HashMap<(String, bool), Vec<LonLat>> = HashMap::new();
+    let mut departures: HashMap<(String, bool), Vec<Time>> = HashMap::new();
     for (key, group) in
         GTFSIterator::<_, transitfeed::StopTime>::from_path(&format!("{}/stop_times.txt", dir_path))
             .unwrap()
@@ -55,13 +71,27 @@ pub fn load(dir_path: &str) -> Vec<Route> {
             .group_by(|rec| rec.trip_id.clone())
             .into_iter()
     {
-        let (route_id, forwards) = trip_id_to_route_id_and_direction[&key].clone();
+        // Trips that don't run on the day we're simulating
+        let (route_id, forwards) = match trip_id_to_route_id_and_direction.get(&key) {
+            Some(pair) => pair.clone(),
+            None => {
+                continue;
+            }
+        };
+        let stop_times: Vec<transitfeed::StopTime> = group.collect();
+        departures
+            .entry((route_id.clone(), forwards))
+            .or_insert_with(Vec::new)
+            .push(Time::START_OF_DAY + Duration::seconds(stop_times[0].departure_time as f64));
         if directed_routes.contains_key(&(route_id.clone(), forwards)) {
             continue;
         }
         directed_routes.insert(
             (route_id, forwards),
-            group.map(|rec| stop_id_to_pt[&rec.stop_id]).collect(),
+            stop_times
+                .into_iter()
+                .map(|rec| stop_id_to_pt[&rec.stop_id])
+                .collect(),
         );
     }
 
@@ -79,12 +109,48 @@ pub fn load(dir_path: &str) -> Vec<Route> {
             stops.extend(more_stops);
         }
         assert!(!stops.is_empty());
+        // Buses loop through both directions, so a trip in one direction is the start of a new
+        // bus. Prefer the forwards trips, since those begin at the first stop.
+        let mut spawn_times = departures
+            .remove(&(route_id.clone(), true))
+            .or_else(|| departures.remove(&(route_id.clone(), false)))
+            .unwrap_or_else(Vec::new);
+        spawn_times.sort();
+        // Feeds sometimes list the same departure twice; one bus is enough
+        spawn_times.dedup();
         results.push(Route {
             name: route_id_to_name[&route_id].to_string(),
             stops,
+            spawn_times,
+            is_rail: rail_routes.contains(&route_id),
         });
     }
     assert!(directed_routes.is_empty());
 
     results
 }
+
+// The sim models a single weekday. Feeds describe weekends, holidays, and different seasons as
+// separate services, so only keep the ones running on a Wednesday on one particular date: the
+// latest date any Wednesday service starts. None if there's no calendar.txt, in which case all
+// trips are used.
+fn weekday_services(dir_path: &str) -> Option<BTreeSet<String>> {
+    let path = format!("{}/calendar.txt", dir_path);
+    if !std::path::Path::new(&path).exists() {
+        return None;
+    }
+    let wednesdays: Vec<transitfeed::Calendar> =
+        GTFSIterator::<_, transitfeed::Calendar>::from_path(&path)
+            .unwrap()
+            .map(|rec| rec.unwrap())
+            .filter(|rec| rec.wednesday)
+            .collect();
+    let date = wednesdays.iter().map(|rec| rec.start_date).max()?;
+    Some(
+        wednesdays
+            .into_iter()
+            .filter(|rec| rec.start_date <= date && date <= rec.end_date)
+            .map(|rec| rec.service_id)
+            .collect(),
+    )
+}
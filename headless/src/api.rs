@@ -0,0 +1,294 @@
+// A tiny HTTP server to control the simulation from other processes, like Python notebooks or
+// integration tests. Every response is JSON. There's deliberately no real web framework here;
+// requests are handled one at a time, blocking the sim while they run.
+//
+// Endpoints:
+// - GET /map/load?name=montlake&scenario=weekday
+// - GET /map/get-edits
+// - POST /map/apply-edits with PermanentMapEdits as JSON. The sim restarts from midnight. Edits
+//   that disconnect lanes cars or bikes could reach before are rejected.
+// - POST /map/apply-edit-script with an EditScript as JSON, on top of the current edits. Returns
+//   a list of changes that were skipped. The sim restarts from midnight. Like apply-edits, the
+//   whole script is rejected if it disconnects part of the map.
+// - GET /sim/reset
+// - GET /sim/get-time
+// - GET /sim/goto-time?t=07:30:00
+// - GET /data/get-finished-trips
+// - GET /data/get-agent-positions
+//
+// TODO Screenshots need a graphics context, so for now, use the game's screen capture tool.
+
+use abstutil::Timer;
+use geom::{Duration, LonLat, Time};
+use map_model::{EditScript, LaneID, Map, PermanentMapEdits};
+use serde::Serialize;
+use sim::{GetDrawAgents, Scenario, Sim, SimFlags, TripMode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// Don't let a client make us allocate arbitrary amounts of memory. Edits for a big map are well
+// under this.
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+struct Instance {
+    map: Map,
+    sim: Sim,
+    scenario: Scenario,
+    flags: SimFlags,
+}
+
+impl Instance {
+    fn load(map_name: &str, scenario_name: &str) -> Result<Instance, String> {
+        let mut timer = Timer::new(format!("load {} / {}", map_name, scenario_name));
+        let map_path = abstutil::path_map(map_name);
+        if !abstutil::file_exists(map_path.clone()) {
+            return Err(format!("{} doesn't exist", map_path));
+        }
+        let map = Map::new(map_path, &mut timer);
+        let scenario: Scenario = abstutil::maybe_read_binary(
+            abstutil::path_scenario(map_name, scenario_name),
+            &mut timer,
+        )
+        .map_err(|err| format!("Couldn't load scenario {}: {}", scenario_name, err))?;
+        let flags = SimFlags::synthetic_test(map_name, "api");
+        let mut instance = Instance {
+            sim: Sim::new(&map, flags.opts.clone(), &mut timer),
+            map,
+            scenario,
+            flags,
+        };
+        instance.reset();
+        Ok(instance)
+    }
+
+    fn reset(&mut self) {
+        let mut timer = Timer::new("reset sim");
+        let mut rng = self.flags.make_rng();
+        self.sim = Sim::new(&self.map, self.flags.opts.clone(), &mut timer);
+        self.scenario
+            .instantiate(&mut self.sim, &self.map, &mut rng, &mut timer);
+    }
+}
+
+pub fn serve(port: u16, map_name: &str, scenario_name: &str) {
+    let mut instance = Instance::load(map_name, scenario_name).unwrap();
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    println!("Listening on http://localhost:{}", port);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(err) = handle_connection(stream, &mut instance) {
+                    println!("WARNING: Couldn't handle request: {}", err);
+                }
+            }
+            Err(err) => println!("WARNING: Bad connection: {}", err),
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, instance: &mut Instance) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        let lower = header.to_lowercase();
+        if lower.starts_with("content-length:") {
+            content_length = lower["content-length:".len()..].trim().parse().unwrap_or(0);
+        }
+    }
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    let (status, response) = if content_length > MAX_BODY_BYTES {
+        (
+            "413 Payload Too Large",
+            error_json(&format!(
+                "The body is {} bytes; the limit is {}",
+                content_length, MAX_BODY_BYTES
+            )),
+        )
+    } else if parts.len() < 2 {
+        ("400 Bad Request", error_json("Malformed request"))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let (path, params) = parse_url(parts[1]);
+        println!("{} {}", parts[0], parts[1]);
+        match handle_command(parts[0], &path, &params, body, instance) {
+            Ok(json) => ("200 OK", json),
+            Err(err) => ("400 Bad Request", error_json(&err)),
+        }
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    )?;
+    stream.flush()
+}
+
+fn handle_command(
+    method: &str,
+    path: &str,
+    params: &BTreeMap<String, String>,
+    body: Vec<u8>,
+    instance: &mut Instance,
+) -> Result<String, String> {
+    let get_param = |key: &str| -> Result<&String, String> {
+        params
+            .get(key)
+            .ok_or_else(|| format!("{} needs a {} parameter", path, key))
+    };
+
+    match (method, path) {
+        ("GET", "/map/load") => {
+            let scenario = params
+                .get("scenario")
+                .cloned()
+                .unwrap_or_else(|| "weekday".to_string());
+            *instance = Instance::load(get_param("name")?, &scenario)?;
+            Ok(ok_json())
+        }
+        ("GET", "/map/get-edits") => Ok(abstutil::to_json(&PermanentMapEdits::to_permanent(
+            instance.map.get_edits(),
+            &instance.map,
+        ))),
+        ("POST", "/map/apply-edits") => {
+            let perma: PermanentMapEdits =
+                abstutil::from_json(&body).map_err(|err| err.to_string())?;
+            let edits = PermanentMapEdits::from_permanent(perma, &instance.map)?;
+            let mut timer = Timer::new("apply edits");
+            instance
+                .map
+                .try_apply_edits(edits, &mut timer)
+                .map_err(disconnected_error)?;
+            instance.map.recalculate_pathfinding_after_edits(&mut timer);
+            instance.reset();
+            Ok(ok_json())
+        }
+        ("POST", "/map/apply-edit-script") => {
+            let script: EditScript = abstutil::from_json(&body).map_err(|err| err.to_string())?;
+            let mut timer = Timer::new("apply edit script");
+            let (edits, warnings) = script.make_edits(&instance.map, &mut timer);
+            instance
+                .map
+                .try_apply_edits(edits, &mut timer)
+                .map_err(disconnected_error)?;
+            instance.map.recalculate_pathfinding_after_edits(&mut timer);
+            instance.reset();
+            Ok(abstutil::to_json(&warnings))
+        }
+        ("GET", "/sim/reset") => {
+            instance.reset();
+            Ok(ok_json())
+        }
+        ("GET", "/sim/get-time") => Ok(abstutil::to_json(&instance.sim.time().to_string())),
+        ("GET", "/sim/goto-time") => {
+            let t = Time::parse(get_param("t")?).map_err(|err| err.to_string())?;
+            if t <= instance.sim.time() {
+                return Err(format!(
+                    "{} is in the past; the sim is at {}. Reset first.",
+                    t,
+                    instance.sim.time()
+                ));
+            }
+            let dt = t - instance.sim.time();
+            instance
+                .sim
+                .timed_step(&instance.map, dt, &mut None, &mut Timer::throwaway());
+            Ok(abstutil::to_json(&instance.sim.time().to_string()))
+        }
+        ("GET", "/data/get-finished-trips") => {
+            let trips: Vec<FinishedTrip> = instance
+                .sim
+                .get_analytics()
+                .finished_trips
+                .iter()
+                .map(|(t, id, mode, duration)| FinishedTrip {
+                    id: id.0,
+                    time: *t,
+                    mode: *mode,
+                    duration: *duration,
+                })
+                .collect();
+            Ok(abstutil::to_json(&trips))
+        }
+        ("GET", "/data/get-agent-positions") => {
+            let gps_bounds = instance.map.get_gps_bounds();
+            let agents: Vec<AgentPosition> = instance
+                .sim
+                .get_unzoomed_agents(&instance.map)
+                .into_iter()
+                .map(|a| AgentPosition {
+                    vehicle_type: a.vehicle_type.map(|vt| vt.to_string()),
+                    person: a.person.map(|p| p.0),
+                    pos: a.pos.forcibly_to_gps(gps_bounds),
+                })
+                .collect();
+            Ok(abstutil::to_json(&agents))
+        }
+        _ => Err(format!("Unknown command {} {}", method, path)),
+    }
+}
+
+#[derive(Serialize)]
+struct FinishedTrip {
+    id: usize,
+    time: Time,
+    // None means aborted
+    mode: Option<TripMode>,
+    duration: Duration,
+}
+
+#[derive(Serialize)]
+struct AgentPosition {
+    // None means a pedestrian
+    vehicle_type: Option<String>,
+    // None means a bus
+    person: Option<usize>,
+    pos: LonLat,
+}
+
+// Returns the path and query parameters. Doesn't handle percent-encoding.
+fn parse_url(url: &str) -> (String, BTreeMap<String, String>) {
+    let mut params = BTreeMap::new();
+    let mut parts = url.splitn(2, '?');
+    let path = parts.next().unwrap().to_string();
+    if let Some(query) = parts.next() {
+        for pair in query.split('&') {
+            let mut kv = pair.splitn(2, '=');
+            if let (Some(k), Some(v)) = (kv.next(), kv.next()) {
+                params.insert(k.to_string(), v.to_string());
+            }
+        }
+    }
+    (path, params)
+}
+
+fn ok_json() -> String {
+    r#"{"ok": true}"#.to_string()
+}
+
+fn error_json(msg: &str) -> String {
+    let mut map = BTreeMap::new();
+    map.insert("error", msg);
+    abstutil::to_json(&map)
+}
+
+fn disconnected_error(lanes: BTreeSet<LaneID>) -> String {
+    format!(
+        "The edits weren't applied, because they'd disconnect {} lanes from the rest of the map",
+        lanes.len()
+    )
+}
@@ -0,0 +1,110 @@
+// Guards the determinism that A/B testing depends on. Either steps two identical sims side by
+// side, or records or checks a trace of one run, halting at the first tick where they disagree.
+
+use crate::sweep::BaseConfig;
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::Map;
+use sim::{DeterminismTrace, Scenario, Sim, SimFlags, Snapshot, TraceChecker};
+
+pub enum Mode {
+    // Run two sims at once
+    Lockstep,
+    // Write a trace to this path
+    Record(String),
+    // Compare against a trace at this path
+    Check(String),
+}
+
+pub fn run(mode: Mode, sim_flags: &SimFlags, base: &BaseConfig, period: Duration) {
+    let mut timer = Timer::new("setup determinism check");
+    let mut map = Map::new(sim_flags.load.clone(), &mut timer);
+    map.hack_override_offstreet_spots(base.num_days);
+    let mut sim = setup(&map, sim_flags, base, &mut timer);
+    timer.done();
+    let end_time = base.time_limit.map(|dt| Time::START_OF_DAY + dt);
+
+    match mode {
+        Mode::Lockstep => {
+            let mut other = setup(&map, sim_flags, base, &mut Timer::throwaway());
+            let mut ticks = 0;
+            while step(&map, &mut sim, period, end_time) {
+                step(&map, &mut other, period, end_time);
+                if let Some(diff) = Snapshot::new(&sim).diff(&Snapshot::new(&other)) {
+                    diverged(ticks, sim.time(), diff);
+                }
+                ticks += 1;
+            }
+            println!("{} ticks matched through {}", ticks, sim.time());
+        }
+        Mode::Record(path) => {
+            let mut trace = DeterminismTrace::new(period);
+            while step(&map, &mut sim, period, end_time) {
+                trace.record(&Snapshot::new(&sim));
+            }
+            println!(
+                "Recorded {} ticks through {}",
+                trace.num_ticks(),
+                sim.time()
+            );
+            abstutil::write_binary(path, &trace);
+        }
+        Mode::Check(path) => {
+            let trace: DeterminismTrace = abstutil::read_binary(path, &mut Timer::throwaway());
+            let mut checker = TraceChecker::new(trace);
+            if checker.period() != period {
+                println!(
+                    "Ignoring --check_period; the trace was recorded every {}",
+                    checker.period()
+                );
+            }
+            let mut ticks = 0;
+            while step(&map, &mut sim, checker.period(), end_time) {
+                if let Some(diff) = checker.check(&Snapshot::new(&sim)) {
+                    diverged(ticks, sim.time(), diff);
+                }
+                ticks += 1;
+            }
+            if !checker.is_done() {
+                diverged(
+                    ticks,
+                    sim.time(),
+                    "The run finished before the trace did".to_string(),
+                );
+            }
+            println!("{} ticks matched the trace through {}", ticks, sim.time());
+        }
+    }
+}
+
+fn setup(map: &Map, sim_flags: &SimFlags, base: &BaseConfig, timer: &mut Timer) -> Sim {
+    let mut sim = Sim::new(map, sim_flags.opts.clone(), timer);
+    let mut scenario: Scenario =
+        abstutil::read_binary(abstutil::path_scenario(map.get_name(), "weekday"), timer);
+    scenario.bus_service.extend(base.bus_service.clone());
+    if base.use_driveways {
+        scenario = scenario.use_driveways(map);
+    }
+    scenario.repeat_days(base.num_days).instantiate(
+        &mut sim,
+        map,
+        &mut sim_flags.make_rng(),
+        timer,
+    );
+    sim
+}
+
+// Returns false without stepping once the sim is finished
+fn step(map: &Map, sim: &mut Sim, period: Duration, end_time: Option<Time>) -> bool {
+    if sim.is_done() || end_time.map(|t| sim.time() >= t).unwrap_or(false) {
+        return false;
+    }
+    sim.timed_step(map, period, &mut None, &mut Timer::throwaway());
+    true
+}
+
+fn diverged(ticks: usize, time: Time, diff: String) -> ! {
+    println!("After {} matching ticks, diverged at {}:", ticks, time);
+    println!("{}", diff);
+    std::process::exit(1);
+}
@@ -1,23 +1,164 @@
+mod api;
+mod determinism;
+mod metrics;
+mod sweep;
+
 use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::Duration;
 use map_model::Map;
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
-use sim::{AlertHandler, Scenario, Sim, SimFlags};
+use sim::{
+    conflicts_to_csv, AlertHandler, BusService, FcdRecorder, NoiseEstimate, PeakHourReport,
+    ReliabilityReport, RunManifest, Scenario, Sim, SimCallback, SimFlags, DEFAULT_ZONE_SIZE,
+};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 
 // This is specialized to experiment with running the pandemic model over long time periods.
 // Original functionality for profiling and debugging gridlock have been removed.
 
 fn main() {
     let mut args = CmdArgs::new();
+    // Like "info,sim::mechanics=debug"
+    abstutil::setup_logger(args.optional("--log"));
+    // Instead of running the experiment, serve a control API. See api.rs.
+    let port = args.optional_parse("--port", |s| s.parse::<u16>());
+    // The map and scenario the control API starts with. /map/load can switch later.
+    let api_map = args.optional("--map");
+    let api_scenario = args.optional("--scenario");
     let num_days = args
         .optional_parse("--days", |s| s.parse::<usize>())
         .unwrap_or(1);
+    // Like "12h" or "1h30m"
+    let time_limit = args.optional_parse("--time_limit", |s| s.parse::<Duration>());
+    // Write SUMO floating car data, sampling every --sumo_fcd_period (default 1s)
+    let sumo_fcd_output = args.optional("--sumo_fcd_output");
+    let sumo_fcd_period = args
+        .optional_parse("--sumo_fcd_period", |s| s.parse::<Duration>())
+        .unwrap_or_else(|| Duration::seconds(1.0));
+    // Write MATSim events
+    let matsim_events_output = args.optional("--matsim_events_output");
+    // Serve Prometheus metrics on this port while the sim runs
+    let metrics_port = args.optional_parse("--metrics_port", |s| s.parse::<u16>());
+    // Save a replay to watch later in the game's debug mode. Must end in .bin
+    let replay_output = args.optional("--replay_output");
+    // Write AM and PM peak hour volumes, V/C ratios, and delays per road and intersection as CSV
+    let peak_hour_output = args.optional("--peak_hour_output");
+    // Write vehicle volume, signal delay, conflicts, and finished trips per 15 minutes as CSV
+    let time_bin_output = args.optional("--time_bin_output");
+    // Write 85th and 95th percentile trip times and reliability indices between pairs of zones as
+    // CSV. Most useful with more than one day.
+    let reliability_output = args.optional("--reliability_output");
+    // Write conflict counts per intersection as CSV
+    let safety_output = args.optional("--safety_output");
+    // Write estimated road noise and the number of residents at every building as CSV
+    let noise_output = args.optional("--noise_output");
+    // Instead of one run, run every combination of these parameter ranges in parallel, like
+    // "cycle_length:60s..120s/10s". --days, --time_limit, --bus_service, and --driveways apply to
+    // every run. The other outputs only make sense for a single run, so they can't be combined with
+    // this. See sweep.rs.
+    let sweep = args.optional_parse("--sweep", sweep::parse);
+    // Repeat every run this many times with different random seeds, reporting means and 95%
+    // confidence intervals in the --sweep_output CSV. Works with or without --sweep.
+    let runs = args
+        .optional_parse("--runs", |s| s.parse::<u8>())
+        .unwrap_or(1);
+    let sweep_output = args
+        .optional("--sweep_output")
+        .unwrap_or_else(|| "sweep.csv".to_string());
+    // Run two copies of the sim side by side, halting at the first difference between them
+    let check_determinism = args.enabled("--check_determinism");
+    // Write a trace of the run to this path, to compare with a later run using --check_trace
+    let record_trace = args.optional("--record_trace");
+    let check_trace = args.optional("--check_trace");
+    // How often to compare runs or record the trace
+    let check_period = args
+        .optional_parse("--check_period", |s| s.parse::<Duration>())
+        .unwrap_or_else(|| Duration::seconds(1.0));
+    // People living in buildings with a private driveway keep their car inside, pulling in and out
+    // of the driveway instead of parking on the street
+    let use_driveways = args.enabled("--driveways");
+    // Like "44=10m/6" to run 6 buses on route 44, 10 minutes apart
+    let bus_service = args
+        .optional_parse("--bus_service", BusService::parse_list)
+        .unwrap_or_else(BTreeMap::new);
     args.done();
 
+    if let Some(port) = port {
+        api::serve(
+            port,
+            &api_map.unwrap_or_else(|| "montlake".to_string()),
+            &api_scenario.unwrap_or_else(|| "weekday".to_string()),
+        );
+        return;
+    }
+    if api_map.is_some() || api_scenario.is_some() {
+        panic!("--map and --scenario only work with --port");
+    }
+
     let mut sim_flags = SimFlags::synthetic_test("montlake", "pandemic");
+    if sweep.is_some() || runs > 1 {
+        for (flag, used) in vec![
+            ("--sumo_fcd_output", sumo_fcd_output.is_some()),
+            ("--matsim_events_output", matsim_events_output.is_some()),
+            ("--metrics_port", metrics_port.is_some()),
+            ("--replay_output", replay_output.is_some()),
+            ("--peak_hour_output", peak_hour_output.is_some()),
+            ("--time_bin_output", time_bin_output.is_some()),
+            ("--reliability_output", reliability_output.is_some()),
+            ("--safety_output", safety_output.is_some()),
+            ("--noise_output", noise_output.is_some()),
+            ("--check_determinism", check_determinism),
+            ("--record_trace", record_trace.is_some()),
+            ("--check_trace", check_trace.is_some()),
+        ] {
+            if used {
+                panic!(
+                    "{} only works for a single run, not with --sweep or --runs",
+                    flag
+                );
+            }
+        }
+        let base = sweep::BaseConfig {
+            num_days,
+            time_limit,
+            bus_service,
+            use_driveways,
+        };
+        let seeds = (0..runs)
+            .map(|i| sim_flags.rng_seed.wrapping_add(i))
+            .collect();
+        write_file(
+            sweep_output,
+            sweep::run(&base, sweep.unwrap_or_else(Vec::new), seeds),
+        );
+        return;
+    }
     sim_flags.opts.enable_pandemic_model = Some(XorShiftRng::from_seed([sim_flags.rng_seed; 16]));
     // Less spam
     sim_flags.opts.alerts = AlertHandler::Silence;
+    let determinism_mode = if check_determinism {
+        Some(determinism::Mode::Lockstep)
+    } else if let Some(path) = record_trace {
+        Some(determinism::Mode::Record(path))
+    } else {
+        check_trace.map(determinism::Mode::Check)
+    };
+    if let Some(mode) = determinism_mode {
+        let base = sweep::BaseConfig {
+            num_days,
+            time_limit,
+            bus_service,
+            use_driveways,
+        };
+        determinism::run(mode, &sim_flags, &base, check_period);
+        return;
+    }
+    sim_flags.opts.record_matsim_events = matsim_events_output.is_some();
+    sim_flags.opts.record_replay = replay_output.is_some();
     let mut timer = Timer::new("setup headless");
     let (mut map, _, mut rng) = sim_flags.load(&mut timer);
     map.hack_override_offstreet_spots(num_days);
@@ -25,24 +166,101 @@ fn main() {
     // ParkingSimState are out of sync.
     let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
 
-    let base_scenario: Scenario = abstutil::read_binary(
+    let mut base_scenario: Scenario = abstutil::read_binary(
         abstutil::path_scenario(map.get_name(), "weekday"),
         &mut timer,
     );
+    base_scenario.bus_service.extend(bus_service);
+    if use_driveways {
+        base_scenario = base_scenario.use_driveways(&map);
+    }
+    let scenario_name = base_scenario.scenario_name.clone();
     base_scenario
         .repeat_days(num_days)
         .instantiate(&mut sim, &map, &mut rng, &mut timer);
     timer.done();
 
-    run_experiment(&map, &mut sim);
+    let mut maybe_cb: Option<Box<dyn SimCallback>> = if sumo_fcd_output.is_some() {
+        sim.set_periodic_callback(sumo_fcd_period);
+        Some(Box::new(FcdRecorder::new()))
+    } else {
+        None
+    };
+
+    let metrics = metrics_port.map(|port| {
+        let metrics = Arc::new(Mutex::new(metrics::Metrics::new()));
+        metrics::serve_in_background(port, metrics.clone());
+        metrics
+    });
+
+    run_experiment(&map, &mut sim, time_limit, &mut maybe_cb, metrics);
+
+    if let Some(path) = sumo_fcd_output {
+        let recorder = maybe_cb.unwrap().downcast::<FcdRecorder>().ok().unwrap();
+        write_file(path, recorder.finish());
+    }
+    if let Some(path) = matsim_events_output {
+        write_file(path, sim.finish_matsim_events().unwrap());
+    }
+    if let Some(path) = peak_hour_output {
+        let report = PeakHourReport::new(sim.get_analytics(), &map, sim.time());
+        write_file(path, report.to_csv(&map));
+    }
+    if let Some(path) = time_bin_output {
+        let report = PeakHourReport::new(sim.get_analytics(), &map, sim.time());
+        write_file(path, report.bins_to_csv());
+    }
+    if let Some(path) = reliability_output {
+        let report = ReliabilityReport::new(&sim, &map, DEFAULT_ZONE_SIZE);
+        write_file(path, report.to_csv());
+    }
+    if let Some(path) = safety_output {
+        write_file(
+            path,
+            conflicts_to_csv(sim.get_analytics(), &map, sim.time()),
+        );
+    }
+    if let Some(path) = noise_output {
+        let noise = NoiseEstimate::new(&sim, &map);
+        for (threshold, people) in noise.exposure(&sim) {
+            println!(
+                "{} people live where road noise is at least {} dB(A)",
+                prettyprint_usize(people),
+                threshold
+            );
+        }
+        write_file(path, noise.to_csv(&sim));
+    }
+    if let Some(path) = replay_output {
+        abstutil::write_binary(path.clone(), &sim.make_replay(&map).unwrap());
+        println!("Wrote {}", path);
+    }
+    // Always keep track of what was run, so results from different sweeps don't get mixed up
+    RunManifest::new(
+        &map,
+        &sim,
+        &sim_flags.opts,
+        &scenario_name,
+        sim_flags.rng_seed,
+    )
+    .save();
 }
 
-fn run_experiment(map: &Map, sim: &mut Sim) {
+fn run_experiment(
+    map: &Map,
+    sim: &mut Sim,
+    time_limit: Option<Duration>,
+    maybe_cb: &mut Option<Box<dyn SimCallback>>,
+    metrics: Option<Arc<Mutex<metrics::Metrics>>>,
+) {
     let timer = Timer::new("run sim until done");
     sim.run_until_done(
         &map,
         |sim, _map| {
             // This'll run every 30 sim seconds
+            if let Some(ref m) = metrics {
+                m.lock().unwrap().update(sim);
+            }
             if false {
                 println!(
                     "At {}, {} infected",
@@ -51,8 +269,16 @@ fn run_experiment(map: &Map, sim: &mut Sim) {
                 );
             }
         },
-        None,
+        time_limit,
+        maybe_cb,
     );
     timer.done();
     println!("Done at {}", sim.time());
 }
+
+fn write_file(path: String, contents: String) {
+    match File::create(&path).and_then(|mut f| f.write_all(contents.as_bytes())) {
+        Ok(()) => println!("Wrote {}", path),
+        Err(err) => println!("Couldn't write {}: {}", path, err),
+    }
+}
@@ -0,0 +1,157 @@
+// Exposes counters about a long-running headless sim in the Prometheus text format
+// (https://prometheus.io/docs/instrumenting/exposition_formats/), so batch runs can be scraped
+// and graphed. Serves GET /metrics from a background thread; the sim itself just updates a shared
+// snapshot every so often.
+
+use geom::{Duration, Time};
+use instant::Instant;
+use sim::{GetDrawAgents, Sim};
+use std::fmt::Write as FmtWrite;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+pub struct Metrics {
+    sim_time: Time,
+    // Sim seconds per real second, measured between the last two updates
+    speed: f64,
+    steps: usize,
+    queued_commands: usize,
+    trips_finished: usize,
+    trips_unfinished: usize,
+    riders_left_behind: usize,
+
+    last_update: Instant,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            sim_time: Time::START_OF_DAY,
+            speed: 0.0,
+            steps: 0,
+            queued_commands: 0,
+            trips_finished: 0,
+            trips_unfinished: 0,
+            riders_left_behind: 0,
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self, sim: &Sim) {
+        let dt_real = Duration::realtime_elapsed(self.last_update);
+        if dt_real > Duration::ZERO {
+            self.speed = (sim.time() - self.sim_time) / dt_real;
+        }
+        self.last_update = Instant::now();
+
+        let (finished, unfinished, _) = sim.num_trips();
+        self.sim_time = sim.time();
+        self.steps = sim.step_count();
+        self.queued_commands = sim.num_queued_commands();
+        self.trips_finished = finished;
+        self.trips_unfinished = unfinished;
+        self.riders_left_behind = sim
+            .get_analytics()
+            .bus_loads
+            .iter()
+            .map(|(_, _, _, _, _, left_behind)| left_behind)
+            .sum();
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            writeln!(out, "# HELP abstreet_{} {}", name, help).unwrap();
+            writeln!(out, "# TYPE abstreet_{} {}", name, kind).unwrap();
+            writeln!(out, "abstreet_{} {}", name, value).unwrap();
+        };
+        metric(
+            "sim_time_seconds",
+            "gauge",
+            "Simulated time since midnight",
+            self.sim_time.inner_seconds(),
+        );
+        metric(
+            "sim_speed_ratio",
+            "gauge",
+            "Simulated seconds per real second",
+            self.speed,
+        );
+        metric(
+            "sim_steps_total",
+            "counter",
+            "Number of sim steps taken",
+            self.steps as f64,
+        );
+        metric(
+            "scheduler_queued_commands",
+            "gauge",
+            "Commands scheduled for the future",
+            self.queued_commands as f64,
+        );
+        metric(
+            "trips_finished_total",
+            "counter",
+            "Trips finished or aborted",
+            self.trips_finished as f64,
+        );
+        metric(
+            "trips_unfinished",
+            "gauge",
+            "Trips not finished yet",
+            self.trips_unfinished as f64,
+        );
+        metric(
+            "transit_riders_left_behind_total",
+            "counter",
+            "Times a rider couldn't board a full bus or train",
+            self.riders_left_behind as f64,
+        );
+        metric(
+            "process_memory_bytes",
+            "gauge",
+            "Virtual memory used by this process",
+            abstutil::process_used_memory_bytes() as f64,
+        );
+        out
+    }
+}
+
+pub fn serve_in_background(port: u16, metrics: Arc<Mutex<Metrics>>) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    println!("Serving metrics on http://localhost:{}/metrics", port);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(err) = handle_connection(stream, &metrics) {
+                        println!("WARNING: Couldn't handle metrics request: {}", err);
+                    }
+                }
+                Err(err) => println!("WARNING: Bad connection: {}", err),
+            }
+        }
+    });
+}
+
+fn handle_connection(stream: TcpStream, metrics: &Arc<Mutex<Metrics>>) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut request_line)?;
+    let (status, body) = if request_line.starts_with("GET /metrics") {
+        ("200 OK", metrics.lock().unwrap().render())
+    } else {
+        ("404 Not Found", String::new())
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
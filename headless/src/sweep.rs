@@ -0,0 +1,352 @@
+// Runs the same experiment many times, once per combination of parameter values and random seed,
+// and collects the headline metrics into one CSV. With more than one seed, each combination gets
+// the mean and a 95% confidence interval for every metric, so a difference between combinations
+// can be told apart from the noise of one random draw.
+
+use abstutil::Timer;
+use geom::Duration;
+use map_model::{EditCmd, EditIntersection, Map};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sim::{AlertHandler, BusService, RunManifest, Scenario, Sim, SimFlags};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Param {
+    // Every traffic signal's phases are stretched or squished to this cycle length
+    CycleLength,
+    // See SimOptions::yield_critical_gap
+    YieldCriticalGap,
+}
+
+impl Param {
+    fn name(self) -> &'static str {
+        match self {
+            Param::CycleLength => "cycle_length",
+            Param::YieldCriticalGap => "yield_critical_gap",
+        }
+    }
+}
+
+// Everything that stays the same across runs. Like a single run, every run uses montlake's weekday
+// scenario.
+pub struct BaseConfig {
+    pub num_days: usize,
+    pub time_limit: Option<Duration>,
+    pub bus_service: BTreeMap<String, BusService>,
+    pub use_driveways: bool,
+}
+
+// Like "cycle_length:60s..120s/10s,yield_critical_gap:3s..6s/1s". Both ends of a range are
+// included.
+pub fn parse(list: &str) -> Result<Vec<(Param, Vec<Duration>)>, String> {
+    let mut result = Vec::new();
+    for entry in list.split(',') {
+        let parts: Vec<&str> = entry.splitn(2, ':').collect();
+        if parts.len() != 2 {
+            return Err(format!("{} should look like param:start..end/step", entry));
+        }
+        let param = match parts[0] {
+            "cycle_length" => Param::CycleLength,
+            "yield_critical_gap" => Param::YieldCriticalGap,
+            x => {
+                return Err(format!(
+                    "Unknown parameter {}; try cycle_length or yield_critical_gap",
+                    x
+                ));
+            }
+        };
+        let range: Vec<&str> = parts[1].splitn(2, '/').collect();
+        let ends: Vec<&str> = range[0].splitn(2, "..").collect();
+        if range.len() != 2 || ends.len() != 2 {
+            return Err(format!("{} should look like param:start..end/step", entry));
+        }
+        let parse = |x: &str| {
+            x.parse::<Duration>()
+                .map_err(|err| format!("{}: {}", entry, err))
+        };
+        let (start, end, step) = (parse(ends[0])?, parse(ends[1])?, parse(range[1])?);
+        if start <= Duration::ZERO || step <= Duration::ZERO || end < start {
+            return Err(format!("{} needs a positive, increasing range", entry));
+        }
+        let mut values = Vec::new();
+        let mut value = start;
+        while value <= end {
+            values.push(value);
+            value += step;
+        }
+        result.push((param, values));
+    }
+    Ok(result)
+}
+
+// Every combination of the parameter values
+pub fn combinations(params: &Vec<(Param, Vec<Duration>)>) -> Vec<Vec<(Param, Duration)>> {
+    let mut results = vec![Vec::new()];
+    for (param, values) in params {
+        let mut next = Vec::new();
+        for combo in results {
+            for value in values {
+                let mut combo = combo.clone();
+                combo.push((*param, *value));
+                next.push(combo);
+            }
+        }
+        results = next;
+    }
+    results
+}
+
+// Runs in parallel, one per CPU, and returns CSV with one row per combination. Each run's manifest
+// is also recorded, like any other headless run, and the row lists their IDs.
+pub fn run(base: &BaseConfig, params: Vec<(Param, Vec<Duration>)>, seeds: Vec<u8>) -> String {
+    let combos = combinations(&params);
+    println!(
+        "Running {} combinations with {} seeds each",
+        combos.len(),
+        seeds.len()
+    );
+    let mut requests = Vec::new();
+    for idx in 0..combos.len() {
+        for seed in &seeds {
+            requests.push((idx, *seed));
+        }
+    }
+    let mut timer = Timer::new("parameter sweep");
+    let results = timer.parallelize("run sweep", requests, |(idx, seed)| {
+        (idx, run_one(base, &combos[idx], seed))
+    });
+    timer.done();
+
+    let metrics: BTreeSet<String> = results
+        .iter()
+        .flat_map(|(_, m)| m.metrics.keys().cloned())
+        .collect();
+    let mut out = String::new();
+    let mut header: Vec<String> = params.iter().map(|(p, _)| p.name().to_string()).collect();
+    header.push("runs".to_string());
+    header.push("run_ids".to_string());
+    for metric in &metrics {
+        header.push(metric.clone());
+        header.push(format!("{}_ci95", metric));
+    }
+    writeln!(out, "{}", header.join(",")).unwrap();
+    for (idx, combo) in combos.iter().enumerate() {
+        let runs: Vec<&RunManifest> = results
+            .iter()
+            .filter(|(i, _)| *i == idx)
+            .map(|(_, m)| m)
+            .collect();
+        let mut row: Vec<String> = combo
+            .iter()
+            .map(|(_, value)| value.inner_seconds().to_string())
+            .collect();
+        row.push(runs.len().to_string());
+        // Space-separated, since the IDs never contain spaces or commas
+        row.push(
+            runs.iter()
+                .map(|m| m.id.clone())
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        for metric in &metrics {
+            let values: Vec<f64> = runs
+                .iter()
+                .filter_map(|m| m.metrics.get(metric).cloned())
+                .collect();
+            let (mean, ci) = mean_and_ci95(&values);
+            if combos.len() == 1 {
+                println!(
+                    "{}: {:.2} ± {:.2}",
+                    metric,
+                    mean.unwrap_or(0.0),
+                    ci.unwrap_or(0.0)
+                );
+            }
+            row.push(mean.map(|x| x.to_string()).unwrap_or_else(String::new));
+            row.push(ci.map(|x| x.to_string()).unwrap_or_else(String::new));
+        }
+        writeln!(out, "{}", row.join(",")).unwrap();
+    }
+    out
+}
+
+// The mean and the half-width of its 95% confidence interval, using Student's t-distribution since
+// there usually aren't many runs. There's no interval with fewer than 2 values.
+fn mean_and_ci95(values: &Vec<f64>) -> (Option<f64>, Option<f64>) {
+    if values.is_empty() {
+        return (None, None);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() < 2 {
+        return (Some(mean), None);
+    }
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let ci = t_critical_value(values.len() - 1) * (variance / n).sqrt();
+    (Some(mean), Some(ci))
+}
+
+// Two-tailed, 95%. Past 10, each band uses its fewest degrees of freedom, so the interval errs on
+// the wide side.
+fn t_critical_value(degrees_of_freedom: usize) -> f64 {
+    const TABLE: [f64; 10] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    ];
+    match degrees_of_freedom {
+        0 => std::f64::INFINITY,
+        1..=10 => TABLE[degrees_of_freedom - 1],
+        11..=20 => 2.201,
+        21..=30 => 2.080,
+        31..=120 => 2.042,
+        _ => 1.96,
+    }
+}
+
+pub fn run_one(base: &BaseConfig, combo: &Vec<(Param, Duration)>, rng_seed: u8) -> RunManifest {
+    let run_name = format!(
+        "sweep_{}_seed{}",
+        combo
+            .iter()
+            .map(|(param, value)| format!("{}{}", param.name(), value.inner_seconds()))
+            .collect::<Vec<_>>()
+            .join("_"),
+        rng_seed
+    );
+    let mut timer = Timer::throwaway();
+    let mut sim_flags = SimFlags::synthetic_test("montlake", &run_name);
+    sim_flags.rng_seed = rng_seed;
+    sim_flags.opts.enable_pandemic_model = Some(XorShiftRng::from_seed([rng_seed; 16]));
+    sim_flags.opts.alerts = AlertHandler::Silence;
+    for (param, value) in combo {
+        if *param == Param::YieldCriticalGap {
+            sim_flags.opts.yield_critical_gap = *value;
+        }
+    }
+
+    let (mut map, _, mut rng) = sim_flags.load(&mut timer);
+    map.hack_override_offstreet_spots(base.num_days);
+    for (param, value) in combo {
+        if *param == Param::CycleLength {
+            set_cycle_length(&mut map, *value, &mut timer);
+        }
+    }
+    let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
+
+    let mut scenario: Scenario = abstutil::read_binary(
+        abstutil::path_scenario(map.get_name(), "weekday"),
+        &mut timer,
+    );
+    scenario.bus_service.extend(base.bus_service.clone());
+    if base.use_driveways {
+        scenario = scenario.use_driveways(&map);
+    }
+    let scenario_name = scenario.scenario_name.clone();
+    scenario
+        .repeat_days(base.num_days)
+        .instantiate(&mut sim, &map, &mut rng, &mut timer);
+    sim.run_until_done(&map, |_, _| {}, base.time_limit, &mut None);
+
+    let manifest = RunManifest::new(&map, &sim, &sim_flags.opts, &scenario_name, rng_seed);
+    manifest.save();
+    manifest
+}
+
+fn set_cycle_length(map: &mut Map, cycle_length: Duration, timer: &mut Timer) {
+    let mut edits = map.get_edits().clone();
+    edits.edits_name = format!("cycle length {}", cycle_length);
+    for i in map.all_intersections() {
+        if i.is_traffic_signal() {
+            edits.commands.push(EditCmd::ChangeIntersection {
+                i: i.id,
+                old: map.get_i_edit(i.id),
+                new: EditIntersection::TrafficSignal(
+                    map.get_traffic_signal(i.id).with_cycle_length(cycle_length),
+                ),
+            });
+        }
+    }
+    map.apply_edits(edits, timer);
+    map.recalculate_pathfinding_after_edits(timer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combinations, mean_and_ci95, parse, t_critical_value, Param};
+    use geom::Duration;
+
+    #[test]
+    fn test_parse() {
+        let params = parse("cycle_length:60s..80s/10s,yield_critical_gap:3s..3s/1s").unwrap();
+        assert_eq!(
+            params,
+            vec![
+                (
+                    Param::CycleLength,
+                    vec![
+                        Duration::seconds(60.0),
+                        Duration::seconds(70.0),
+                        Duration::seconds(80.0)
+                    ]
+                ),
+                (Param::YieldCriticalGap, vec![Duration::seconds(3.0)]),
+            ]
+        );
+
+        assert!(parse("cycle_length").is_err());
+        assert!(parse("speed:1s..2s/1s").is_err());
+        assert!(parse("cycle_length:60s..120s").is_err());
+        assert!(parse("cycle_length:120s..60s/10s").is_err());
+        assert!(parse("cycle_length:60s..120s/0s").is_err());
+        assert!(parse("cycle_length:abc..120s/10s").is_err());
+    }
+
+    #[test]
+    fn test_combinations() {
+        let a = Duration::seconds(1.0);
+        let b = Duration::seconds(2.0);
+        let c = Duration::seconds(3.0);
+        let combos = combinations(&vec![
+            (Param::CycleLength, vec![a, b]),
+            (Param::YieldCriticalGap, vec![c]),
+        ]);
+        assert_eq!(
+            combos,
+            vec![
+                vec![(Param::CycleLength, a), (Param::YieldCriticalGap, c)],
+                vec![(Param::CycleLength, b), (Param::YieldCriticalGap, c)],
+            ]
+        );
+
+        // No parameters means just one run with the defaults
+        assert_eq!(combinations(&Vec::new()), vec![Vec::new()]);
+    }
+
+    #[test]
+    fn test_mean_and_ci95() {
+        assert_eq!(mean_and_ci95(&Vec::new()), (None, None));
+        assert_eq!(mean_and_ci95(&vec![5.0]), (Some(5.0), None));
+
+        // Sample standard deviation 1, so the interval is t(2) * 1 / sqrt(3)
+        let (mean, ci) = mean_and_ci95(&vec![1.0, 2.0, 3.0]);
+        assert_eq!(mean, Some(2.0));
+        assert!((ci.unwrap() - 4.303 / 3.0_f64.sqrt()).abs() < 1e-9);
+
+        // No spread, no uncertainty
+        assert_eq!(mean_and_ci95(&vec![4.0, 4.0]), (Some(4.0), Some(0.0)));
+    }
+
+    #[test]
+    fn test_t_critical_value() {
+        assert!(t_critical_value(0).is_infinite());
+        assert_eq!(t_critical_value(1), 12.706);
+        assert_eq!(t_critical_value(10), 2.228);
+        assert_eq!(t_critical_value(15), 2.201);
+        assert_eq!(t_critical_value(1000), 1.96);
+        // More data never widens the interval
+        for df in 1..200 {
+            assert!(t_critical_value(df + 1) <= t_critical_value(df));
+        }
+    }
+}
@@ -30,6 +30,8 @@ pub fn osm_to_raw(name: &str) {
             elevation: None,
             clip: Some(format!("../data/input/austin/polygons/{}.poly", name)),
             drive_on_right: true,
+            projection: geom::Projection::default(),
+            simplify_epsilon: geom::Distance::meters(0.5),
         },
         &mut abstutil::Timer::throwaway(),
     );
@@ -0,0 +1,60 @@
+use abstutil::Timer;
+use geom::{Distance, Polygon, Pt2D};
+use map_model::Map;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use sim::{CensusArea, Population, Scenario};
+
+// Produces a plausible scenario for any map. If ../data/input/$city/census.kml exists, it should
+// have one shape per census block or parcel, with "residents" and "jobs" attributes. Otherwise,
+// the counts are guessed from building footprints.
+pub fn make_synthetic_scenario(city: &str, map: &Map, timer: &mut Timer) -> Scenario {
+    let path = format!("../data/input/{}/census.kml", city);
+    let pop = if abstutil::file_exists(path.clone()) {
+        let areas = read_areas(&path, map, timer);
+        Population::from_census(map, areas, timer)
+    } else {
+        timer.note(format!("No {}, so guessing population", path));
+        Population::guess(map)
+    };
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+    pop.make_scenario("synthetic", map, &mut rng, timer)
+}
+
+fn read_areas(path: &str, map: &Map, timer: &mut Timer) -> Vec<CensusArea> {
+    let shapes = kml::load(path, map.get_gps_bounds(), timer).unwrap();
+    let mut areas = Vec::new();
+    let mut skipped = 0;
+    for shape in shapes.shapes {
+        let pts: Vec<Pt2D> = shape
+            .points
+            .iter()
+            .filter_map(|gps| Pt2D::from_gps(*gps, map.get_gps_bounds()))
+            .collect();
+        let pts = Pt2D::approx_dedupe(pts, Distance::meters(1.0));
+        let residents = shape
+            .attributes
+            .get("residents")
+            .and_then(|x| x.parse::<usize>().ok());
+        let jobs = shape
+            .attributes
+            .get("jobs")
+            .and_then(|x| x.parse::<usize>().ok());
+        if pts.len() < 3 || (residents.is_none() && jobs.is_none()) {
+            skipped += 1;
+            continue;
+        }
+        areas.push(CensusArea {
+            polygon: Polygon::new(&pts),
+            residents: residents.unwrap_or(0),
+            jobs: jobs.unwrap_or(0),
+        });
+    }
+    if skipped > 0 {
+        timer.warn(format!(
+            "Skipped {} shapes in {} that weren't usable areas",
+            skipped, path
+        ));
+    }
+    areas
+}
@@ -0,0 +1,539 @@
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration, FindClosest, LonLat, Pt2D, Time};
+use map_model::{BuildingID, IntersectionID, Map, PathConstraints};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use sim::{
+    IndividTrip, OffMapLocation, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint, TripMode,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+
+// Import travel demand calibrated for other simulators. Neither format refers to anything in our
+// map, so every origin and destination is matched to the closest building by geometry, or to the
+// nearest border if it's off-map.
+
+// A trip before it's been matched to our map
+struct ExternalTrip {
+    person: String,
+    depart: Time,
+    from: LonLat,
+    to: LonLat,
+    mode: TripMode,
+}
+
+// Reads SUMO trip or route files (see
+// https://sumo.dlr.de/docs/Definition_of_Vehicles,_Vehicle_Types,_and_Routes.html). Each <trip>
+// or <vehicle> needs fromLonLat and toLonLat, or edges from the SUMO network, which must also be
+// passed in. Vehicles with a bicycle type or vClass become bike trips; everything else drives.
+pub fn import_sumo(
+    path: &str,
+    net_path: Option<&str>,
+    map: &Map,
+    timer: &mut Timer,
+) -> Result<Scenario, Box<dyn std::error::Error>> {
+    let edges = if let Some(p) = net_path {
+        read_sumo_net(p, timer)?
+    } else {
+        HashMap::new()
+    };
+    if edges.is_empty() {
+        timer.note(format!(
+            "No --sumo_net given, so only trips with fromLonLat and toLonLat in {} are used",
+            path
+        ));
+    }
+
+    timer.start(format!("read {}", path));
+    let mut reader = Reader::from_file(path)?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut trips = Vec::new();
+    let mut bike_types = Vec::new();
+    // A <vehicle> may list its edges in a nested <route>
+    let mut current_vehicle: Option<(BTreeMap<String, String>, TripMode)> = None;
+    loop {
+        let ev = reader.read_event(&mut buf)?;
+        match ev {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let attribs = attributes(e, &reader)?;
+                let is_empty = if let Event::Empty(_) = ev {
+                    true
+                } else {
+                    false
+                };
+                match e.name() {
+                    b"vType" => {
+                        if attribs.get("vClass").map(|x| x == "bicycle") == Some(true) {
+                            if let Some(id) = attribs.get("id") {
+                                bike_types.push(id.clone());
+                            }
+                        }
+                    }
+                    b"trip" | b"vehicle" => {
+                        let id = attribs.get("id").cloned().unwrap_or_else(String::new);
+                        // Vehicles departing when something else happens, like "triggered" by a
+                        // person boarding, don't have a time we can use
+                        if let Some(depart) = parse_time(attribs.get("depart")) {
+                            let is_bike = attribs
+                                .get("type")
+                                .map(|t| bike_types.contains(t))
+                                .unwrap_or(false)
+                                || attribs.get("vClass").map(|x| x == "bicycle") == Some(true);
+                            let mode = if is_bike {
+                                TripMode::Bike
+                            } else {
+                                TripMode::Drive
+                            };
+                            let from = if let Some(x) = attribs.get("fromLonLat") {
+                                parse_lonlat(x)
+                            } else {
+                                attribs.get("from").and_then(|x| edges.get(x).cloned())
+                            };
+                            let to = if let Some(x) = attribs.get("toLonLat") {
+                                parse_lonlat(x)
+                            } else {
+                                attribs.get("to").and_then(|x| edges.get(x).cloned())
+                            };
+                            if let (Some(from), Some(to)) = (from, to) {
+                                trips.push(ExternalTrip {
+                                    person: id,
+                                    depart,
+                                    from,
+                                    to,
+                                    mode,
+                                });
+                            } else if is_empty {
+                                timer.warn(format!(
+                                    "Skipping vehicle {}; no usable origin or destination",
+                                    id
+                                ));
+                            } else {
+                                current_vehicle = Some((attribs, mode));
+                            }
+                        } else {
+                            timer.warn(format!(
+                                "Skipping vehicle {}; can't use depart={:?}",
+                                id,
+                                attribs.get("depart")
+                            ));
+                        }
+                    }
+                    b"route" => {
+                        if let Some((vehicle, mode)) = current_vehicle.take() {
+                            let id = vehicle.get("id").cloned().unwrap_or_else(String::new);
+                            let edge_ids: Vec<&str> = attribs
+                                .get("edges")
+                                .map(|x| x.split(' ').collect())
+                                .unwrap_or_else(Vec::new);
+                            match (
+                                edge_ids.first().and_then(|x| edges.get(*x)),
+                                edge_ids.last().and_then(|x| edges.get(*x)),
+                            ) {
+                                (Some(from), Some(to)) => {
+                                    trips.push(ExternalTrip {
+                                        person: id,
+                                        depart: parse_time(vehicle.get("depart")).unwrap(),
+                                        from: *from,
+                                        to: *to,
+                                        mode,
+                                    });
+                                }
+                                _ => {
+                                    timer.warn(format!(
+                                        "Skipping vehicle {}; its route's edges aren't in the \
+                                         network (or no --sumo_net was given)",
+                                        id
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(e) => {
+                if e.name() == b"trip" || e.name() == b"vehicle" {
+                    if let Some((vehicle, _)) = current_vehicle.take() {
+                        timer.warn(format!(
+                            "Skipping vehicle {}; no usable origin or destination",
+                            vehicle.get("id").cloned().unwrap_or_else(String::new)
+                        ));
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    timer.stop(format!("read {}", path));
+
+    Ok(make_scenario("sumo", trips, map, timer))
+}
+
+// Reads a MATSim population (https://www.matsim.org/files/dtd/population_v6.dtd), using each
+// person's selected plan. Activity coordinates must be WGS84 longitude and latitude.
+pub fn import_matsim(
+    path: &str,
+    map: &Map,
+    timer: &mut Timer,
+) -> Result<Scenario, Box<dyn std::error::Error>> {
+    timer.start(format!("read {}", path));
+    let mut reader = Reader::from_file(path)?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut trips = Vec::new();
+
+    let mut person: Option<String> = None;
+    let mut in_selected_plan = false;
+    // The last activity, when it ends, and the mode of the leg after it
+    let mut last_activity: Option<(LonLat, Option<Time>)> = None;
+    let mut leg_mode: Option<TripMode> = None;
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) => {
+                let attribs = attributes(&e, &reader)?;
+                match e.name() {
+                    b"person" => {
+                        person = Some(attribs.get("id").cloned().unwrap_or_else(String::new));
+                    }
+                    b"plan" => {
+                        // If there's only one plan, it might not be marked
+                        in_selected_plan =
+                            attribs.get("selected").map(|x| x != "no") != Some(false);
+                        last_activity = None;
+                        leg_mode = None;
+                    }
+                    b"act" | b"activity" if in_selected_plan => {
+                        let pt = match (
+                            attribs.get("x").and_then(|x| x.parse::<f64>().ok()),
+                            attribs.get("y").and_then(|y| y.parse::<f64>().ok()),
+                        ) {
+                            (Some(x), Some(y)) => Some(LonLat::new(x, y)),
+                            _ => None,
+                        };
+                        if let Some(pt) = pt {
+                            // Activities without an end time (or with one we can't parse) are the
+                            // last of the day
+                            let end_time = parse_time(attribs.get("end_time"));
+                            if let (Some((from, Some(depart))), Some(mode)) =
+                                (last_activity, leg_mode)
+                            {
+                                trips.push(ExternalTrip {
+                                    person: person.clone().unwrap_or_else(String::new),
+                                    depart,
+                                    from,
+                                    to: pt,
+                                    mode,
+                                });
+                            }
+                            last_activity = Some((pt, end_time));
+                        } else {
+                            // The legs on either side of this can't be used
+                            timer.warn(format!(
+                                "Skipping an activity of {:?} without coordinates",
+                                person
+                            ));
+                            last_activity = None;
+                        }
+                        leg_mode = None;
+                    }
+                    b"leg" if in_selected_plan => {
+                        leg_mode = match attribs.get("mode").map(|x| x.as_str()).unwrap_or("") {
+                            "car" => Some(TripMode::Drive),
+                            "bike" => Some(TripMode::Bike),
+                            "walk" => Some(TripMode::Walk),
+                            "pt" => Some(TripMode::Transit),
+                            x => {
+                                timer.warn(format!("Skipping leg with unknown mode {:?}", x));
+                                None
+                            }
+                        };
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(e) => {
+                if e.name() == b"plan" {
+                    in_selected_plan = false;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    timer.stop(format!("read {}", path));
+
+    Ok(make_scenario("matsim", trips, map, timer))
+}
+
+fn make_scenario(name: &str, trips: Vec<ExternalTrip>, map: &Map, timer: &mut Timer) -> Scenario {
+    let matcher = Matcher::new(map);
+    let total = trips.len();
+    let mut people: BTreeMap<String, Vec<IndividTrip>> = BTreeMap::new();
+    let mut skipped = 0;
+    timer.start_iter("match trips to the map", trips.len());
+    for trip in trips {
+        timer.next();
+        if let Some((from, to)) = matcher.endpoints(&trip, map) {
+            people
+                .entry(trip.person)
+                .or_insert_with(Vec::new)
+                .push(IndividTrip {
+                    depart: trip.depart,
+                    trip: SpawnTrip::new(from, to, trip.mode, map),
+                });
+        } else {
+            skipped += 1;
+        }
+    }
+    timer.note(format!(
+        "{} trips matched to the map, skipped {} that never touch it",
+        prettyprint_usize(total - skipped),
+        prettyprint_usize(skipped)
+    ));
+
+    Scenario {
+        scenario_name: name.to_string(),
+        map_name: map.get_name().to_string(),
+        people: people
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (_, mut trips))| {
+                trips.sort_by_key(|t| t.depart);
+                PersonSpec {
+                    id: PersonID(idx),
+                    orig_id: None,
+                    trips,
+                }
+            })
+            .collect(),
+        only_seed_buses: None,
+        bus_service: BTreeMap::new(),
+    }
+    .remove_weird_schedules(map)
+}
+
+// If a point is further than this from any building, it's probably not a real trip endpoint.
+const MAX_DIST_TO_BLDG: Distance = Distance::const_meters(100.0);
+
+struct Matcher {
+    bldgs: FindClosest<BuildingID>,
+    // Per constraint, incoming and outgoing borders
+    borders: Vec<(
+        PathConstraints,
+        Vec<(IntersectionID, LonLat)>,
+        Vec<(IntersectionID, LonLat)>,
+    )>,
+}
+
+impl Matcher {
+    fn new(map: &Map) -> Matcher {
+        let mut bldgs = FindClosest::new(map.get_bounds());
+        for b in map.all_buildings() {
+            bldgs.add(b.id, b.polygon.points());
+        }
+        let bounds = map.get_gps_bounds();
+        let mut borders = Vec::new();
+        for constraints in vec![
+            PathConstraints::Pedestrian,
+            PathConstraints::Car,
+            PathConstraints::Bike,
+        ] {
+            let incoming = map
+                .all_incoming_borders()
+                .into_iter()
+                .filter(|i| !i.get_outgoing_lanes(map, constraints).is_empty())
+                .filter_map(|i| i.polygon.center().to_gps(bounds).map(|pt| (i.id, pt)))
+                .collect();
+            let outgoing = map
+                .all_outgoing_borders()
+                .into_iter()
+                .filter(|i| !i.get_incoming_lanes(map, constraints).is_empty())
+                .filter_map(|i| i.polygon.center().to_gps(bounds).map(|pt| (i.id, pt)))
+                .collect();
+            borders.push((constraints, incoming, outgoing));
+        }
+        Matcher { bldgs, borders }
+    }
+
+    fn endpoints(&self, trip: &ExternalTrip, map: &Map) -> Option<(TripEndpoint, TripEndpoint)> {
+        let from_bldg = self.match_bldg(trip.from, map);
+        let to_bldg = self.match_bldg(trip.to, map);
+        let constraints = match trip.mode {
+            TripMode::Walk | TripMode::Transit => PathConstraints::Pedestrian,
+            TripMode::Drive => PathConstraints::Car,
+            TripMode::Bike => PathConstraints::Bike,
+        };
+        let (_, incoming, outgoing) = self
+            .borders
+            .iter()
+            .find(|(c, _, _)| *c == constraints)
+            .unwrap();
+        match (from_bldg, to_bldg) {
+            (Some(b1), Some(b2)) => {
+                if b1 == b2 {
+                    None
+                } else {
+                    Some((TripEndpoint::Bldg(b1), TripEndpoint::Bldg(b2)))
+                }
+            }
+            (Some(b), None) => Some((TripEndpoint::Bldg(b), self.match_border(trip.to, outgoing)?)),
+            (None, Some(b)) => Some((
+                self.match_border(trip.from, incoming)?,
+                TripEndpoint::Bldg(b),
+            )),
+            // TODO Detect and handle pass-through trips
+            (None, None) => None,
+        }
+    }
+
+    fn match_bldg(&self, gps: LonLat, map: &Map) -> Option<BuildingID> {
+        let pt = Pt2D::from_gps(gps, map.get_gps_bounds())?;
+        self.bldgs.closest_pt(pt, MAX_DIST_TO_BLDG).map(|(b, _)| b)
+    }
+
+    fn match_border(
+        &self,
+        gps: LonLat,
+        borders: &Vec<(IntersectionID, LonLat)>,
+    ) -> Option<TripEndpoint> {
+        let i = borders.iter().min_by_key(|(_, pt)| pt.fast_dist(gps))?.0;
+        Some(TripEndpoint::Border(
+            i,
+            Some(OffMapLocation { gps, parcel_id: 0 }),
+        ))
+    }
+}
+
+// Returns the position of every edge in a SUMO network, taken as the middle of its first lane.
+// SUMO works in its own cartesian space, so this relies on the network's original boundary being
+// in longitude and latitude, as it is when imported from OSM.
+fn read_sumo_net(
+    path: &str,
+    timer: &mut Timer,
+) -> Result<HashMap<String, LonLat>, Box<dyn std::error::Error>> {
+    timer.start(format!("read {}", path));
+    let mut reader = Reader::from_file(path)?;
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    // (conv_boundary, orig_boundary) as (min x, min y, max x, max y)
+    let mut boundaries: Option<(Vec<f64>, Vec<f64>)> = None;
+    let mut current_edge: Option<String> = None;
+    let mut edges = HashMap::new();
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) => {
+                let attribs = attributes(&e, &reader)?;
+                match e.name() {
+                    b"location" => {
+                        let parse = |key: &str| -> Option<Vec<f64>> {
+                            attribs
+                                .get(key)?
+                                .split(',')
+                                .map(|x| x.parse::<f64>().ok())
+                                .collect()
+                        };
+                        if let (Some(conv), Some(orig)) =
+                            (parse("convBoundary"), parse("origBoundary"))
+                        {
+                            boundaries = Some((conv, orig));
+                        }
+                    }
+                    b"edge" => {
+                        // Internal edges are inside junctions
+                        if attribs.get("function").map(|x| x == "internal") != Some(true) {
+                            current_edge = attribs.get("id").cloned();
+                        }
+                    }
+                    b"lane" => {
+                        if let Some(id) = current_edge.take() {
+                            let pts: Vec<(f64, f64)> = attribs
+                                .get("shape")
+                                .map(|x| x.as_str())
+                                .unwrap_or("")
+                                .split(' ')
+                                .filter_map(|pair| {
+                                    let mut parts = pair.split(',');
+                                    let x = parts.next()?.parse::<f64>().ok()?;
+                                    let y = parts.next()?.parse::<f64>().ok()?;
+                                    Some((x, y))
+                                })
+                                .collect();
+                            if !pts.is_empty() {
+                                edges.insert(id, pts[pts.len() / 2]);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    timer.stop(format!("read {}", path));
+
+    let (conv, orig) = boundaries.ok_or_else(|| format!("{} has no <location>", path))?;
+    if conv.len() != 4 || orig.len() != 4 {
+        return Err(format!("{} has a weird <location>", path).into());
+    }
+    // Linearly interpolate between the two boundaries. Good enough at city scale.
+    Ok(edges
+        .into_iter()
+        .map(|(id, (x, y))| {
+            let pct_x = (x - conv[0]) / (conv[2] - conv[0]);
+            let pct_y = (y - conv[1]) / (conv[3] - conv[1]);
+            (
+                id,
+                LonLat::new(
+                    orig[0] + pct_x * (orig[2] - orig[0]),
+                    orig[1] + pct_y * (orig[3] - orig[1]),
+                ),
+            )
+        })
+        .collect())
+}
+
+fn parse_lonlat(x: &str) -> Option<LonLat> {
+    let parts: Vec<&str> = x.split(',').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    Some(LonLat::new(parts[0].parse().ok()?, parts[1].parse().ok()?))
+}
+
+// Both formats use seconds since midnight, possibly fractional, or [[HH:]MM:]SS with optional
+// fractional seconds. SUMO also has special values like "triggered" and "now", which aren't times.
+fn parse_time(x: Option<&String>) -> Option<Time> {
+    let parts: Vec<&str> = x?.split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut seconds = 0.0;
+    for part in parts {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Some(Time::START_OF_DAY + Duration::seconds(seconds))
+}
+
+fn attributes<B: BufRead>(
+    e: &BytesStart,
+    reader: &Reader<B>,
+) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    let mut result = BTreeMap::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        result.insert(
+            String::from_utf8(attr.key.to_vec())?,
+            attr.unescape_and_decode_value(reader)?,
+        );
+    }
+    Ok(result)
+}
@@ -1,4 +1,6 @@
 mod austin;
+mod census;
+mod demand;
 mod seattle;
 #[cfg(feature = "scenarios")]
 mod soundcast;
@@ -10,8 +12,13 @@ struct Job {
     city: String,
     osm_to_raw: bool,
     raw_to_map: bool,
+    update: bool,
     scenario: bool,
     scenario_everyone: bool,
+    synthetic: bool,
+    sumo_demand: Option<String>,
+    sumo_net: Option<String>,
+    matsim_plans: Option<String>,
 
     skip_ch: bool,
 
@@ -19,20 +26,34 @@ struct Job {
 
     oneshot: Option<String>,
     oneshot_clip: Option<String>,
+    oneshot_mercator: bool,
 }
 
 fn main() {
     let mut args = abstutil::CmdArgs::new();
+    // Like "info,map_model=debug"
+    abstutil::setup_logger(args.optional("--log"));
     let job = Job {
         city: args.optional("--city").unwrap_or("seattle".to_string()),
         // Download all raw input files, then convert OSM to the intermediate RawMap.
         osm_to_raw: args.enabled("--raw"),
         // Convert the RawMap to the final Map format.
         raw_to_map: args.enabled("--map"),
+        // When converting to a Map, keep building IDs from the existing map stable where nothing
+        // changed, and update scenarios to match. Useful when refreshing OSM data.
+        update: args.enabled("--update"),
         // Download trip demand data, then produce the typical weekday scenario.
         scenario: args.enabled("--scenario"),
         // Produce a variation of the weekday scenario including off-map trips.
         scenario_everyone: args.enabled("--scenario_everyone"),
+        // Produce a scenario from census data, or guesses based on buildings. Works for any city.
+        synthetic: args.enabled("--synthetic"),
+        // Produce a scenario from a SUMO trip or route file. If trips refer to edges instead of
+        // coordinates, also pass the SUMO network with --sumo_net.
+        sumo_demand: args.optional("--sumo_demand"),
+        sumo_net: args.optional("--sumo_net"),
+        // Produce a scenario from a MATSim population file, with coordinates in WGS84.
+        matsim_plans: args.optional("--matsim_plans"),
         // Skip the most expensive step of --map, building contraction hierarchies. The resulting
         // map won't be usable for simulation; as soon as you try to pathfind, it'll crash.
         skip_ch: args.enabled("--skip_ch"),
@@ -44,23 +65,33 @@ fn main() {
         // Ignore other arguments and just convert the given .osm file to a Map.
         oneshot: args.optional("--oneshot"),
         oneshot_clip: args.optional("--oneshot_clip"),
+        // Use the Mercator projection for the --oneshot map, instead of the default linear one.
+        oneshot_mercator: args.enabled("--oneshot_mercator"),
     };
     args.done();
     if !job.osm_to_raw
         && !job.raw_to_map
         && !job.scenario
         && !job.scenario_everyone
+        && !job.synthetic
+        && job.sumo_demand.is_none()
+        && job.matsim_plans.is_none()
         && job.oneshot.is_none()
     {
         println!(
             "Nothing to do! Pass some combination of --raw, --map, --scenario, \
-             --scenario_everyone or --oneshot"
+             --scenario_everyone, --synthetic, --sumo_demand, --matsim_plans or --oneshot"
         );
         std::process::exit(1);
     }
 
     if let Some(path) = job.oneshot {
-        oneshot(path, job.oneshot_clip);
+        let projection = if job.oneshot_mercator {
+            geom::Projection::Mercator
+        } else {
+            geom::Projection::default()
+        };
+        oneshot(path, job.oneshot_clip, projection);
         return;
     }
 
@@ -106,8 +137,18 @@ fn main() {
         }
 
         let mut maybe_map = if job.raw_to_map {
-            Some(utils::raw_to_map(&name, !job.skip_ch, &mut timer))
-        } else if job.scenario || job.scenario_everyone {
+            Some(utils::raw_to_map(
+                &name,
+                !job.skip_ch,
+                job.update,
+                &mut timer,
+            ))
+        } else if job.scenario
+            || job.scenario_everyone
+            || job.synthetic
+            || job.sumo_demand.is_some()
+            || job.matsim_plans.is_some()
+        {
             Some(map_model::Map::new(abstutil::path_map(&name), &mut timer))
         } else {
             None
@@ -144,13 +185,47 @@ fn main() {
             .save();
             timer.stop(format!("scenario_everyone for {}", name));
         }
+
+        if job.synthetic {
+            timer.start(format!("synthetic scenario for {}", name));
+            census::make_synthetic_scenario(&job.city, maybe_map.as_ref().unwrap(), &mut timer)
+                .save();
+            timer.stop(format!("synthetic scenario for {}", name));
+        }
+
+        if let Some(ref path) = job.sumo_demand {
+            timer.start(format!("SUMO scenario for {}", name));
+            match demand::import_sumo(
+                path,
+                job.sumo_net.as_ref().map(|x| x.as_str()),
+                maybe_map.as_ref().unwrap(),
+                &mut timer,
+            ) {
+                Ok(scenario) => scenario.save(),
+                Err(err) => println!("Couldn't import {}: {}", path, err),
+            }
+            timer.stop(format!("SUMO scenario for {}", name));
+        }
+
+        if let Some(ref path) = job.matsim_plans {
+            timer.start(format!("MATSim scenario for {}", name));
+            match demand::import_matsim(path, maybe_map.as_ref().unwrap(), &mut timer) {
+                Ok(scenario) => scenario.save(),
+                Err(err) => println!("Couldn't import {}: {}", path, err),
+            }
+            timer.stop(format!("MATSim scenario for {}", name));
+        }
     }
 }
 
-fn oneshot(osm_path: String, clip: Option<String>) {
+fn oneshot(osm_path: String, clip: Option<String>, projection: geom::Projection) {
     let mut timer = abstutil::Timer::new("oneshot");
     println!("- Running convert_osm on {}", osm_path);
-    let name = abstutil::basename(&osm_path);
+    // Both foo.osm and foo.osm.pbf should produce a map called foo. When stitching together
+    // multiple extracts, use the first.
+    let name = abstutil::basename(osm_path.split(',').next().unwrap())
+        .trim_end_matches(".osm")
+        .to_string();
     let raw = convert_osm::convert(
         convert_osm::Options {
             osm_input: osm_path,
@@ -165,6 +240,8 @@ fn oneshot(osm_path: String, clip: Option<String>) {
             elevation: None,
             clip,
             drive_on_right: true,
+            projection,
+            simplify_epsilon: geom::Distance::meters(0.5),
         },
         &mut timer,
     );
@@ -6,7 +6,7 @@ use sim::{
     IndividTrip, OffMapLocation, OrigPersonID, PersonID, PersonSpec, Scenario, SpawnTrip,
     TripEndpoint, TripMode,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Clone, Debug)]
 struct Trip {
@@ -296,6 +296,7 @@ pub fn make_weekday_scenario(
         map_name: map.get_name().to_string(),
         people,
         only_seed_buses: None,
+        bus_service: BTreeMap::new(),
     }
     .remove_weird_schedules(map)
 }
@@ -366,6 +367,7 @@ pub fn make_weekday_scenario_with_everyone(
         map_name: map.get_name().to_string(),
         people,
         only_seed_buses: None,
+        bus_service: BTreeMap::new(),
     }
     .remove_weird_schedules(map)
 }
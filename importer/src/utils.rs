@@ -101,14 +101,31 @@ fn run(cmd: &mut Command) {
     }
 }
 
-// Converts a RawMap to a Map.
-pub fn raw_to_map(name: &str, build_ch: bool, timer: &mut Timer) -> map_model::Map {
+// Converts a RawMap to a Map. If update is true and the map already exists, building IDs are kept
+// stable and existing scenarios are fixed up to match the new map.
+pub fn raw_to_map(name: &str, build_ch: bool, update: bool, timer: &mut Timer) -> map_model::Map {
     timer.start(format!("Raw->Map for {}", name));
     let raw: map_model::raw::RawMap = abstutil::read_binary(abstutil::path_raw_map(name), timer);
-    let map = map_model::Map::create_from_raw(raw, build_ch, timer);
+    let maybe_old_map: Option<map_model::Map> = if update {
+        map_model::Map::maybe_load(abstutil::path_map(name), timer).ok()
+    } else {
+        None
+    };
+    let mut map = map_model::Map::create_from_raw(raw, build_ch, timer);
+    if let Some(ref old_map) = maybe_old_map {
+        let kept = map.preserve_building_ids(old_map);
+        timer.note(format!(
+            "{} of {} buildings kept their ID",
+            abstutil::prettyprint_usize(kept),
+            abstutil::prettyprint_usize(map.all_buildings().len())
+        ));
+    }
     timer.start("save map");
     map.save();
     timer.stop("save map");
+    if let Some(ref old_map) = maybe_old_map {
+        update_scenarios_and_edits(old_map, &map, timer);
+    }
     timer.stop(format!("Raw->Map for {}", name));
 
     // TODO Just sticking this here for now
@@ -123,3 +140,20 @@ pub fn raw_to_map(name: &str, build_ch: bool, timer: &mut Timer) -> map_model::M
 
     map
 }
+
+fn update_scenarios_and_edits(old_map: &map_model::Map, map: &map_model::Map, timer: &mut Timer) {
+    for name in abstutil::list_all_objects(abstutil::path_all_scenarios(map.get_name())) {
+        let scenario: sim::Scenario =
+            abstutil::read_binary(abstutil::path_scenario(map.get_name(), &name), timer);
+        scenario.remap(old_map, map, timer).save();
+    }
+    // Edits refer to OSM IDs, so they usually survive. Just warn about the ones that don't.
+    for name in abstutil::list_all_objects(abstutil::path_all_edits(map.get_name())) {
+        if let Err(err) = map_model::MapEdits::load(map, &name, timer) {
+            timer.warn(format!(
+                "Edits {} don't apply to the new map: {}",
+                name, err
+            ));
+        }
+    }
+}
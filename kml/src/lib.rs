@@ -118,3 +118,32 @@ fn parse_pt(input: &str, gps_bounds: &GPSBounds) -> Option<LonLat> {
         None
     }
 }
+
+// Reads points, lines, and polygons from GeoJSON; see LonLat::read_geojson for how geometries
+// are split up. Shapes entirely outside gps_bounds are skipped.
+pub fn load_geojson(
+    path: &str,
+    gps_bounds: &GPSBounds,
+    timer: &mut Timer,
+) -> Result<ExtraShapes, std::io::Error> {
+    timer.start(format!("load {}", path));
+    let mut shapes = Vec::new();
+    let mut skipped_count = 0;
+    for (points, attributes) in LonLat::read_geojson(path.to_string(), timer)? {
+        if !points.iter().any(|pt| gps_bounds.contains(*pt)) {
+            skipped_count += 1;
+            continue;
+        }
+        shapes.push(ExtraShape { points, attributes });
+    }
+
+    println!(
+        "Got {} shapes from {} and skipped {} shapes",
+        prettyprint_usize(shapes.len()),
+        path,
+        prettyprint_usize(skipped_count)
+    );
+    timer.stop(format!("load {}", path));
+
+    Ok(ExtraShapes { shapes })
+}
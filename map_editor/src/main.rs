@@ -3,9 +3,9 @@ mod world;
 
 use abstutil::{CmdArgs, Timer};
 use ezgui::{
-    hotkey, Btn, Canvas, Choice, Color, Composite, Drawable, EventCtx, EventLoopMode, GeomBatch,
-    GfxCtx, HorizontalAlignment, Key, Line, Outcome, ScreenPt, Text, VerticalAlignment, Widget,
-    Wizard, GUI,
+    hotkey, Btn, Canvas, Choice, Color, Composite, CrashInfo, Drawable, EventCtx, EventLoopMode,
+    GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, ScreenPt, Text, VerticalAlignment,
+    Widget, Wizard, GUI,
 };
 use geom::{Distance, Line, Polygon, Pt2D};
 use map_model::raw::{OriginalBuilding, OriginalIntersection, OriginalRoad, RestrictionType};
@@ -181,7 +181,8 @@ impl GUI for UI {
                     }
                     Some(ID::Road(r)) => {
                         let could_swap = {
-                            let lanes = self.model.map.roads[&r].get_spec();
+                            let lanes =
+                                self.model.map.roads[&r].get_spec(self.model.map.driving_side);
                             lanes.fwd != lanes.back
                         };
 
@@ -209,7 +210,7 @@ impl GUI for UI {
                         {
                             let road = &self.model.map.roads[&r];
                             self.state = State::StampingRoads(
-                                road.get_spec().to_string(),
+                                road.get_spec(self.model.map.driving_side).to_string(),
                                 road.osm_tags
                                     .get(osm::NAME)
                                     .cloned()
@@ -352,7 +353,9 @@ impl GUI for UI {
             State::EditingLanes(id, ref mut wizard) => {
                 if let Some(s) = wizard.wrap(ctx).input_string_prefilled(
                     "Specify the lanes",
-                    self.model.map.roads[&id].get_spec().to_string(),
+                    self.model.map.roads[&id]
+                        .get_spec(self.model.map.driving_side)
+                        .to_string(),
                 ) {
                     self.model.edit_lanes(id, s, ctx.prerender);
                     self.state = State::viewing();
@@ -632,7 +635,7 @@ impl GUI for UI {
         }
     }
 
-    fn dump_before_abort(&self, canvas: &Canvas) {
+    fn dump_before_abort(&self, canvas: &Canvas, _: &CrashInfo) {
         if !self.model.map.name.is_empty() {
             canvas.save_camera_state(&self.model.map.name);
         }
@@ -721,8 +724,9 @@ fn find_overlapping_intersections(model: &Model, ctx: &EventCtx) -> Drawable {
 // TODO OriginalRoad is dangerous, as this map changes. :\
 fn find_short_roads(model: &Model) -> HashSet<OriginalRoad> {
     // Assume the full map has been built. We really care about short lanes there.
-    let map: map_model::Map =
-        abstutil::read_binary(abstutil::path_map(&model.map.name), &mut Timer::throwaway());
+    let map =
+        map_model::Map::maybe_load(abstutil::path_map(&model.map.name), &mut Timer::throwaway())
+            .unwrap();
     // Buses are 12.5
     let threshold = Distance::meters(13.0);
     let mut roads: HashSet<OriginalRoad> = HashSet::new();
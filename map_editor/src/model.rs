@@ -404,9 +404,10 @@ impl Model {
     pub fn swap_lanes(&mut self, id: OriginalRoad, prerender: &Prerender) {
         self.road_deleted(id);
 
+        let driving_side = self.map.driving_side;
         let (mut lanes, osm_tags) = {
             let r = self.map.roads.get_mut(&id).unwrap();
-            (r.get_spec(), &mut r.osm_tags)
+            (r.get_spec(driving_side), &mut r.osm_tags)
         };
         mem::swap(&mut lanes.fwd, &mut lanes.back);
         osm_tags.insert(osm::SYNTHETIC_LANES.to_string(), lanes.to_string());
@@ -498,7 +499,7 @@ impl Model {
         let unset =
             r.synthetic() && r.osm_tags.get(osm::NAME) == Some(&"Streety McStreetFace".to_string());
         let lanes_unknown = r.osm_tags.contains_key(osm::INFERRED_SIDEWALKS);
-        let spec = r.get_spec();
+        let spec = r.get_spec(self.map.driving_side);
         let center_pts = PolyLine::new(r.center_points.clone());
 
         let mut obj = Object::blank(ID::Road(id));
@@ -584,6 +585,7 @@ impl Model {
             LaneType::Biking => Color::rgb(15, 125, 75),
             LaneType::SharedLeftTurn => Color::YELLOW,
             LaneType::Construction => Color::rgb(255, 109, 0),
+            LaneType::LightRail => Color::rgb(110, 95, 80),
         };
         if unset {
             Color::rgba_f(0.9, color.g, color.b, 0.5)
@@ -766,6 +768,7 @@ impl Model {
                 public_garage_name: None,
                 num_parking_spots: 0,
                 amenities: BTreeSet::new(),
+                entrance: None,
             },
         );
         self.bldg_added(id, prerender);
@@ -777,10 +780,12 @@ impl Model {
 
         let b = self.map.buildings.get_mut(&id).unwrap();
         let old_center = b.polygon.center();
-        b.polygon = b.polygon.translate(
+        let (dx, dy) = (
             new_center.x() - old_center.x(),
             new_center.y() - old_center.y(),
         );
+        b.polygon = b.polygon.translate(dx, dy);
+        b.entrance = b.entrance.map(|pt| pt.offset(dx, dy));
 
         self.bldg_added(id, prerender);
     }
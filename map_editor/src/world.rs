@@ -1,6 +1,5 @@
-use aabb_quadtree::{ItemId, QuadTree};
 use ezgui::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Prerender};
-use geom::{Bounds, Circle, Distance, Polygon, Pt2D};
+use geom::{Bounds, Circle, Distance, Polygon, Pt2D, QuadTree, QuadTreeID};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -42,7 +41,7 @@ impl<ID: ObjectID> Object<ID> {
 struct WorldObject {
     unioned_polygon: Polygon,
     draw: Drawable,
-    quadtree_id: ItemId,
+    quadtree_id: QuadTreeID,
 }
 
 pub struct World<ID: ObjectID> {
@@ -58,20 +57,17 @@ impl<ID: ObjectID> World<ID> {
             // Force the quadtree to support any possible positions. Especially when creating
             // synthetic maps, the bounds change, but updating the quadtree is nontrivial. But they
             // have to be non-negative.
-            quadtree: QuadTree::default(
-                Bounds::from(&vec![
-                    Pt2D::new(0.0, 0.0),
-                    Pt2D::new(std::f64::MAX, std::f64::MAX),
-                ])
-                .as_bbox(),
-            ),
+            quadtree: QuadTree::new(&Bounds::from(&vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(std::f64::MAX, std::f64::MAX),
+            ])),
             current_selection: None,
         }
     }
 
     pub fn draw<F: Fn(ID) -> bool>(&self, g: &mut GfxCtx, show: F) {
         let mut objects: Vec<ID> = Vec::new();
-        for &(id, _, _) in &self.quadtree.query(g.get_screen_bounds().as_bbox()) {
+        for id in self.quadtree.query(&g.get_screen_bounds()) {
             if show(*id) {
                 objects.push(*id);
             }
@@ -99,11 +95,10 @@ impl<ID: ObjectID> World<ID> {
         };
 
         let mut objects: Vec<ID> = Vec::new();
-        for &(id, _, _) in &self.quadtree.query(
-            Circle::new(cursor, Distance::meters(3.0))
-                .get_bounds()
-                .as_bbox(),
-        ) {
+        for id in self
+            .quadtree
+            .query(&Circle::new(cursor, Distance::meters(3.0)).get_bounds())
+        {
             objects.push(*id);
         }
         objects.sort_by_key(|id| id.zorder());
@@ -138,7 +133,7 @@ impl<ID: ObjectID> World<ID> {
                 obj.id, bounds
             );
         }
-        let quadtree_id = self.quadtree.insert_with_box(obj.id, bounds.as_bbox());
+        let quadtree_id = self.quadtree.insert_with_bounds(obj.id, bounds);
         let draw = prerender.upload(GeomBatch::from(obj.geometry));
         self.objects.insert(
             obj.id,
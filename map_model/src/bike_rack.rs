@@ -0,0 +1,26 @@
+use crate::Position;
+use geom::Pt2D;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BikeRackID(pub usize);
+
+impl fmt::Display for BikeRackID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bike rack #{}", self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BikeRack {
+    pub id: BikeRackID,
+    pub pt: Pt2D,
+    pub osm_node_id: i64,
+    pub capacity: usize,
+
+    // Where cyclists lock up and continue walking
+    pub sidewalk_pos: Position,
+    // Guaranteed to be far enough from both ends of the lane for a bike to stop or start there
+    pub biking_pos: Position,
+}
@@ -1,7 +1,7 @@
 use crate::{LaneID, Position};
 use geom::{Line, PolyLine, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 // TODO reconsider pub usize. maybe outside world shouldnt know.
@@ -34,6 +34,67 @@ pub struct OffstreetParking {
     pub driving_pos: Position,
 }
 
+// What a building is mostly used for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LandUse {
+    Residential,
+    // Shops, offices, restaurants, hotels
+    Commercial,
+    // Anything from kindergartens to universities
+    School,
+    Industrial,
+    // Not mapped, or something else
+    Unknown,
+}
+
+impl LandUse {
+    // From the building's OSM tags. Buildings with amenities and no other clue are commercial.
+    pub fn from_osm(tags: &BTreeMap<String, String>, has_amenities: bool) -> LandUse {
+        if let Some(amenity) = tags.get("amenity") {
+            match amenity.as_ref() {
+                "school" | "university" | "college" | "kindergarten" => {
+                    return LandUse::School;
+                }
+                _ => {}
+            }
+        }
+        match tags.get("building").map(|x| x.as_ref()) {
+            Some("house")
+            | Some("apartments")
+            | Some("residential")
+            | Some("detached")
+            | Some("semidetached_house")
+            | Some("terrace")
+            | Some("dormitory")
+            | Some("bungalow")
+            | Some("houseboat") => LandUse::Residential,
+            Some("commercial") | Some("retail") | Some("office") | Some("supermarket")
+            | Some("kiosk") | Some("hotel") => LandUse::Commercial,
+            Some("school") | Some("university") | Some("college") | Some("kindergarten") => {
+                LandUse::School
+            }
+            Some("industrial") | Some("warehouse") | Some("manufacture") => LandUse::Industrial,
+            _ => {
+                if has_amenities || tags.contains_key("shop") || tags.contains_key("office") {
+                    LandUse::Commercial
+                } else {
+                    LandUse::Unknown
+                }
+            }
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            LandUse::Residential => "residential",
+            LandUse::Commercial => "commercial",
+            LandUse::School => "school",
+            LandUse::Industrial => "industrial",
+            LandUse::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Building {
     pub id: BuildingID,
@@ -46,6 +107,10 @@ pub struct Building {
     pub label_center: Pt2D,
     // (Name, amenity)
     pub amenities: BTreeSet<(String, String)>,
+    // From OSM building:levels. Defaults to 1 when unmapped. Can be fractional, like 1.5 for a
+    // house with an attic.
+    pub levels: f64,
+    pub land_use: LandUse,
 
     pub front_path: FrontPath,
     // Every building can't have OffstreetParking, because the nearest usable driving lane (not in
@@ -58,6 +123,11 @@ impl Building {
         self.front_path.sidewalk.lane()
     }
 
+    // Footprint times the number of levels, in square meters
+    pub fn floor_area(&self) -> f64 {
+        self.polygon.area() * self.levels
+    }
+
     pub fn house_number(&self) -> Option<String> {
         let num = self.address.split(" ").next().unwrap();
         if num != "???" {
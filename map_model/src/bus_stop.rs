@@ -1,4 +1,5 @@
-use crate::{LaneID, Position};
+use crate::{LaneID, LaneType, PathConstraints, Position};
+use geom::Time;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -32,6 +33,19 @@ pub struct BusStop {
     // anywhere.
     pub driving_pos: Position,
     pub sidewalk_pos: Position,
+    // Trains pull up to tracks instead of driving or bus lanes
+    pub is_train_stop: bool,
+}
+
+impl BusStop {
+    // Where a vehicle serving the stop can pull up
+    pub fn lane_types(is_train_stop: bool) -> Vec<LaneType> {
+        if is_train_stop {
+            vec![LaneType::LightRail]
+        } else {
+            vec![LaneType::Driving, LaneType::Bus]
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -39,4 +53,8 @@ pub struct BusRoute {
     pub id: BusRouteID,
     pub name: String,
     pub stops: Vec<BusStopID>,
+    // From the GTFS schedule. If empty, just one bus is seeded somewhere along the route.
+    pub spawn_times: Vec<Time>,
+    // Bus or Train
+    pub route_type: PathConstraints,
 }
@@ -1,7 +1,7 @@
-use crate::{LaneID, Map, PathConstraints};
+use crate::{BuildingID, BusStopID, LaneID, Map, PathConstraints};
 use abstutil::Timer;
 use petgraph::graphmap::DiGraphMap;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 
 // SCC = strongly connected component
 
@@ -11,6 +11,7 @@ pub fn find_scc(map: &Map, constraints: PathConstraints) -> (HashSet<LaneID>, Ha
     for turn in map.all_turns().values() {
         if constraints.can_use(map.get_l(turn.id.src), map)
             && constraints.can_use(map.get_l(turn.id.dst), map)
+            && constraints.can_use_turn(turn, map)
         {
             graph.add_edge(turn.id.src, turn.id.dst, 1);
         }
@@ -39,6 +40,77 @@ pub fn find_scc(map: &Map, constraints: PathConstraints) -> (HashSet<LaneID>, Ha
     (largest_group, disconnected)
 }
 
+// Lanes that cars or bikes can't reach from the main part of the map. Many maps have a few of
+// these to begin with, so compare the results before and after some edits.
+pub fn disconnected_vehicle_lanes(map: &Map) -> BTreeSet<LaneID> {
+    let mut lanes = BTreeSet::new();
+    for c in vec![PathConstraints::Car, PathConstraints::Bike] {
+        lanes.extend(find_scc(map, c).1);
+    }
+    lanes
+}
+
+// Something cut off from the main part of the map for some mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Unreachable {
+    Lane(LaneID, PathConstraints),
+    // Pedestrian means the front door, Car means the driveway
+    Building(BuildingID, PathConstraints),
+    // Either buses can't stop here, or pedestrians can't walk to it
+    BusStop(BusStopID),
+}
+
+impl Unreachable {
+    pub fn describe(&self) -> String {
+        match self {
+            Unreachable::Lane(l, c) => format!("{} for {:?}", l, c),
+            Unreachable::Building(b, PathConstraints::Car) => format!("driveway of {}", b),
+            Unreachable::Building(b, c) => format!("{} for {:?}", b, c),
+            Unreachable::BusStop(bs) => format!("{}", bs),
+        }
+    }
+}
+
+// Many maps have a few disconnected bits to begin with, so compare the results before and after
+// some edits to see what they broke.
+pub fn find_unreachable(map: &Map) -> BTreeSet<Unreachable> {
+    let mut results = BTreeSet::new();
+    let mut disconnected = Vec::new();
+    for c in vec![
+        PathConstraints::Pedestrian,
+        PathConstraints::Car,
+        PathConstraints::Bike,
+        PathConstraints::Bus,
+        PathConstraints::Train,
+    ] {
+        let (_, lanes) = find_scc(map, c);
+        for l in &lanes {
+            results.insert(Unreachable::Lane(*l, c));
+        }
+        disconnected.push(lanes);
+    }
+    let (walking, driving) = (&disconnected[0], &disconnected[1]);
+    let (busing, training) = (&disconnected[3], &disconnected[4]);
+
+    for b in map.all_buildings() {
+        if walking.contains(&b.sidewalk()) {
+            results.insert(Unreachable::Building(b.id, PathConstraints::Pedestrian));
+        }
+        if let Some(ref p) = b.parking {
+            if driving.contains(&p.driving_pos.lane()) {
+                results.insert(Unreachable::Building(b.id, PathConstraints::Car));
+            }
+        }
+    }
+    for bs in map.all_bus_stops().values() {
+        let vehicles = if bs.is_train_stop { training } else { busing };
+        if vehicles.contains(&bs.driving_pos.lane()) || walking.contains(&bs.sidewalk_pos.lane()) {
+            results.insert(Unreachable::BusStop(bs.id));
+        }
+    }
+    results
+}
+
 // Returns list of (driving lane, redirect here instead for parking)
 //
 // It's a bit weird to never attempt parking on roads not part of the largest SCC of the graph.
@@ -0,0 +1,61 @@
+use crate::{IntersectionID, RoadID, TurnID};
+use geom::Polygon;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// One physical crosswalk over a road at one of its ends. Usually two Crosswalk turns (one in each
+// direction) make up a crosswalk; degenerate intersections have more.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CrosswalkID {
+    pub i: IntersectionID,
+    // The road being crossed
+    pub r: RoadID,
+}
+
+impl fmt::Display for CrosswalkID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CrosswalkID({}, {})", self.r, self.i)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CrosswalkType {
+    // Zebra stripes
+    Marked,
+    // People still cross here, there's just nothing painted. They yield to vehicles.
+    Unmarked,
+    // Marked, and raised to sidewalk level like a speed table. Only matters for curb ramps so far;
+    // the simulation doesn't slow vehicles down for these.
+    Raised,
+}
+
+impl CrosswalkType {
+    pub fn all() -> Vec<CrosswalkType> {
+        vec![
+            CrosswalkType::Marked,
+            CrosswalkType::Unmarked,
+            CrosswalkType::Raised,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            CrosswalkType::Marked => "marked",
+            CrosswalkType::Unmarked => "unmarked",
+            CrosswalkType::Raised => "raised",
+        }
+    }
+
+    pub fn is_marked(self) -> bool {
+        self != CrosswalkType::Unmarked
+    }
+}
+
+// Derived from the turns in an intersection, not stored in the map.
+pub struct Crosswalk {
+    pub id: CrosswalkID,
+    // Every turn representing this crosswalk. The first one is canonical.
+    pub turns: Vec<TurnID>,
+    pub crosswalk_type: CrosswalkType,
+    pub polygon: Polygon,
+}
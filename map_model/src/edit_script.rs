@@ -0,0 +1,463 @@
+use crate::raw::{OriginalIntersection, OriginalRoad};
+use crate::{
+    osm, EditIntersection, IntersectionID, LaneType, Map, MapEdits, PathConstraints, RoadID,
+    TimedSpeedLimit,
+};
+use abstutil::Timer;
+use geom::{Distance, Speed};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+// Describes edits in bulk, like "turn every parking lane on an arterial into a bike lane", so
+// experiments can be generated by other tools instead of clicking around. Stored as JSON. The
+// rules are applied in order, each one seeing the results of the ones before it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EditScript {
+    // Becomes the name of the resulting edits
+    pub name: String,
+    pub rules: Vec<EditRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum EditRule {
+    ChangeLaneType {
+        roads: RoadFilter,
+        from: LaneType,
+        to: LaneType,
+    },
+    // Sets the width of every lane of this type
+    ChangeLaneWidth {
+        roads: RoadFilter,
+        lane_type: LaneType,
+        meters: f64,
+    },
+    ChangeSpeedLimit {
+        roads: RoadFilter,
+        mph: f64,
+    },
+    // Replaces any existing timed limits. An empty schedule removes them.
+    ChangeSpeedLimitSchedule {
+        roads: RoadFilter,
+        schedule: Vec<TimedSpeedLimit>,
+    },
+    // Like a neighborhood with diverters. An empty set of vehicles lifts the restriction.
+    ChangeNoThroughTraffic {
+        roads: RoadFilter,
+        vehicles: BTreeSet<PathConstraints>,
+    },
+    // Like "no cars on this street". An empty set of vehicles lifts the ban.
+    ChangeBannedVehicles {
+        roads: RoadFilter,
+        vehicles: BTreeSet<PathConstraints>,
+    },
+    // Closing a road to traffic would also close its intersections to every cross street, so
+    // prefer CloseRoads.
+    CloseIntersections {
+        intersections: IntersectionFilter,
+    },
+    // Multiplies the duration of every phase, keeping the order and offset the same
+    ScaleSignalTiming {
+        intersections: IntersectionFilter,
+        factor: f64,
+    },
+    ChangeRightTurnOnRed {
+        intersections: IntersectionFilter,
+        allowed: bool,
+    },
+    ChangeUTurns {
+        intersections: IntersectionFilter,
+        allowed: bool,
+    },
+    // Turns every lane for vehicles into construction. Pedestrians can still use the sidewalks.
+    CloseRoads {
+        roads: RoadFilter,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum RoadFilter {
+    All,
+    // OSM highway types, like "primary" or "residential"
+    HighwayTypes(Vec<String>),
+    Roads(Vec<OriginalRoad>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum IntersectionFilter {
+    // Closing every intersection would be silly, so CloseIntersections doesn't allow this
+    All,
+    Intersections(Vec<OriginalIntersection>),
+}
+
+impl EditScript {
+    pub fn load(path: String, timer: &mut Timer) -> Result<EditScript, String> {
+        abstutil::maybe_read_json(path.clone(), timer)
+            .map_err(|err| format!("Couldn't load {}: {}", path, err))
+    }
+
+    // Starts from the map's current edits. Individual changes that aren't allowed are skipped and
+    // described in the returned list of warnings. Nothing happens to the map until the edits are
+    // passed to Map::apply_edits.
+    pub fn make_edits(&self, map: &Map, timer: &mut Timer) -> (MapEdits, Vec<String>) {
+        let mut edits = map.get_edits().clone();
+        edits.edits_name = self.name.clone();
+        let mut warnings = Vec::new();
+        for rule in &self.rules {
+            let mut changes = 0;
+            match rule {
+                EditRule::ChangeLaneType { roads, from, to } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        for l in map.get_r(r).all_lanes() {
+                            if edits.lane_type(map, l) != *from {
+                                continue;
+                            }
+                            match edits.change_lane_type(map, l, *to) {
+                                Ok(()) => {
+                                    changes += 1;
+                                }
+                                Err(err) => warnings.push(err),
+                            }
+                        }
+                    }
+                }
+                EditRule::ChangeLaneWidth {
+                    roads,
+                    lane_type,
+                    meters,
+                } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        for l in map.get_r(r).all_lanes() {
+                            if edits.lane_type(map, l) != *lane_type {
+                                continue;
+                            }
+                            match edits.change_lane_width(map, l, Distance::meters(*meters)) {
+                                Ok(()) => {
+                                    changes += 1;
+                                }
+                                Err(err) => warnings.push(err),
+                            }
+                        }
+                    }
+                }
+                EditRule::ChangeSpeedLimit { roads, mph } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        match edits.change_speed_limit(map, r, Speed::miles_per_hour(*mph)) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::ChangeSpeedLimitSchedule { roads, schedule } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        match edits.change_speed_limit_schedule(map, r, schedule.clone()) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::ChangeNoThroughTraffic { roads, vehicles } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        match edits.change_no_through_traffic(map, r, vehicles.clone()) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::ChangeBannedVehicles { roads, vehicles } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        match edits.change_banned_vehicles(map, r, vehicles.clone()) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::CloseIntersections { intersections } => {
+                    if let IntersectionFilter::All = intersections {
+                        warnings.push(format!("Refusing to close every intersection"));
+                        continue;
+                    }
+                    for i in intersections.matches(map, &mut warnings) {
+                        match edits.close_intersection(map, i) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::ScaleSignalTiming {
+                    intersections,
+                    factor,
+                } => {
+                    for i in intersections.matches(map, &mut warnings) {
+                        let mut signal = match edits.intersection(map, i) {
+                            EditIntersection::TrafficSignal(ts) => ts,
+                            _ => {
+                                // Only complain about intersections that were asked for by name
+                                if let IntersectionFilter::Intersections(_) = intersections {
+                                    warnings.push(format!("{} doesn't have a traffic signal", i));
+                                }
+                                continue;
+                            }
+                        };
+                        for phase in &mut signal.phases {
+                            phase.duration = phase.duration * *factor;
+                        }
+                        match edits.change_traffic_signal(map, signal) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::ChangeRightTurnOnRed {
+                    intersections,
+                    allowed,
+                } => {
+                    for i in intersections.matches(map, &mut warnings) {
+                        match edits.intersection(map, i) {
+                            EditIntersection::TrafficSignal(_) => {}
+                            _ => {
+                                if let IntersectionFilter::Intersections(_) = intersections {
+                                    warnings.push(format!("{} doesn't have a traffic signal", i));
+                                }
+                                continue;
+                            }
+                        }
+                        match edits.change_right_turn_on_red(map, i, *allowed) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::ChangeUTurns {
+                    intersections,
+                    allowed,
+                } => {
+                    for i in intersections.matches(map, &mut warnings) {
+                        if map.get_i(i).is_border() {
+                            continue;
+                        }
+                        match edits.change_u_turns(map, i, *allowed) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+                EditRule::CloseRoads { roads } => {
+                    for r in roads.matches(map, &mut warnings) {
+                        match edits.close_road(map, r) {
+                            Ok(()) => {
+                                changes += 1;
+                            }
+                            Err(err) => warnings.push(err),
+                        }
+                    }
+                }
+            }
+            timer.note(format!("{:?} made {} changes", rule, changes));
+        }
+        (edits, warnings)
+    }
+}
+
+impl RoadFilter {
+    fn matches(&self, map: &Map, warnings: &mut Vec<String>) -> Vec<RoadID> {
+        match self {
+            RoadFilter::All => map.all_roads().iter().map(|r| r.id).collect(),
+            RoadFilter::HighwayTypes(types) => map
+                .all_roads()
+                .iter()
+                .filter(|r| {
+                    r.osm_tags
+                        .get(osm::HIGHWAY)
+                        .map(|hwy| types.contains(hwy))
+                        .unwrap_or(false)
+                })
+                .map(|r| r.id)
+                .collect(),
+            RoadFilter::Roads(roads) => {
+                let mut result = Vec::new();
+                for r in roads {
+                    match map.find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id)) {
+                        Ok(id) => result.push(id),
+                        Err(err) => warnings.push(err),
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+impl IntersectionFilter {
+    fn matches(&self, map: &Map, warnings: &mut Vec<String>) -> Vec<IntersectionID> {
+        match self {
+            IntersectionFilter::All => map.all_intersections().iter().map(|i| i.id).collect(),
+            IntersectionFilter::Intersections(list) => {
+                let mut result = Vec::new();
+                for i in list {
+                    match map.find_i_by_osm_id(i.osm_node_id) {
+                        Ok(id) => result.push(id),
+                        Err(err) => warnings.push(err),
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EditRule, EditScript, IntersectionFilter, RoadFilter};
+    use crate::raw::{OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad};
+    use crate::{osm, EditCmd, IntersectionType, LaneType, Map, RoadSpec};
+    use abstutil::Timer;
+    use geom::{Distance, GPSBounds, Polygon, Pt2D};
+    use std::collections::BTreeMap;
+
+    // A four-way stop in the middle, with a 200m road out to a border in each direction
+    fn four_way() -> Map {
+        let mut raw = RawMap::blank("test", "four_way");
+        let center = Pt2D::new(200.0, 200.0);
+        let ends = vec![
+            Pt2D::new(0.0, 200.0),
+            Pt2D::new(400.0, 200.0),
+            Pt2D::new(200.0, 0.0),
+            Pt2D::new(200.0, 400.0),
+        ];
+        raw.intersections.insert(
+            OriginalIntersection { osm_node_id: 0 },
+            RawIntersection {
+                point: center,
+                intersection_type: IntersectionType::StopSign,
+                elevation: Distance::ZERO,
+            },
+        );
+        for (idx, pt) in ends.into_iter().enumerate() {
+            let id = (idx + 1) as i64;
+            raw.intersections.insert(
+                OriginalIntersection { osm_node_id: id },
+                RawIntersection {
+                    point: pt,
+                    intersection_type: IntersectionType::Border,
+                    elevation: Distance::ZERO,
+                },
+            );
+
+            let mut osm_tags = BTreeMap::new();
+            osm_tags.insert(osm::HIGHWAY.to_string(), "residential".to_string());
+            osm_tags.insert(osm::OSM_WAY_ID.to_string(), id.to_string());
+            osm_tags.insert(
+                osm::SYNTHETIC_LANES.to_string(),
+                RoadSpec {
+                    fwd: vec![LaneType::Driving, LaneType::Parking, LaneType::Sidewalk],
+                    back: vec![LaneType::Driving, LaneType::Parking, LaneType::Sidewalk],
+                }
+                .to_string(),
+            );
+            raw.roads.insert(
+                road(id),
+                RawRoad {
+                    center_points: vec![pt, center],
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                },
+            );
+        }
+
+        raw.boundary_polygon = Polygon::rectangle(400.0, 400.0);
+        raw.gps_bounds = GPSBounds::new();
+        raw.gps_bounds
+            .update(Pt2D::new(0.0, 0.0).forcibly_to_gps(&GPSBounds::seattle_bounds()));
+        raw.gps_bounds
+            .update(Pt2D::new(400.0, 400.0).forcibly_to_gps(&GPSBounds::seattle_bounds()));
+        Map::create_from_raw(raw, false, &mut Timer::throwaway())
+    }
+
+    fn road(id: i64) -> OriginalRoad {
+        OriginalRoad {
+            osm_way_id: id,
+            i1: OriginalIntersection { osm_node_id: id },
+            i2: OriginalIntersection { osm_node_id: 0 },
+        }
+    }
+
+    fn script(rules: Vec<EditRule>) -> EditScript {
+        EditScript {
+            name: "test".to_string(),
+            rules,
+        }
+    }
+
+    #[test]
+    fn test_change_lane_type() {
+        let map = four_way();
+        let (edits, warnings) = script(vec![EditRule::ChangeLaneType {
+            roads: RoadFilter::HighwayTypes(vec!["residential".to_string()]),
+            from: LaneType::Parking,
+            to: LaneType::Biking,
+        }])
+        .make_edits(&map, &mut Timer::throwaway());
+        assert!(warnings.is_empty());
+        assert_eq!(edits.commands.len(), 8);
+        for cmd in &edits.commands {
+            match cmd {
+                EditCmd::ChangeLaneType { lt, orig_lt, .. } => {
+                    assert_eq!(*lt, LaneType::Biking);
+                    assert_eq!(*orig_lt, LaneType::Parking);
+                }
+                _ => panic!("Unexpected {:?}", cmd),
+            }
+        }
+    }
+
+    #[test]
+    fn test_close_road() {
+        let map = four_way();
+        let r = map.find_r_by_osm_id(1, (1, 0)).unwrap();
+        let (edits, warnings) = script(vec![EditRule::CloseRoads {
+            roads: RoadFilter::Roads(vec![road(1)]),
+        }])
+        .make_edits(&map, &mut Timer::throwaway());
+        assert!(warnings.is_empty());
+        // Both driving and parking lanes, but not the sidewalks. The intersection stays open for
+        // the cross street.
+        assert_eq!(edits.commands.len(), 4);
+        for cmd in &edits.commands {
+            match cmd {
+                EditCmd::ChangeLaneType { id, lt, .. } => {
+                    assert_eq!(map.get_l(*id).parent, r);
+                    assert_eq!(*lt, LaneType::Construction);
+                }
+                _ => panic!("Unexpected {:?}", cmd),
+            }
+        }
+    }
+
+    #[test]
+    fn test_close_every_intersection() {
+        let map = four_way();
+        let (edits, warnings) = script(vec![EditRule::CloseIntersections {
+            intersections: IntersectionFilter::All,
+        }])
+        .make_edits(&map, &mut Timer::throwaway());
+        assert_eq!(warnings.len(), 1);
+        assert!(edits.commands.is_empty());
+    }
+}
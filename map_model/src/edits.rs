@@ -1,12 +1,17 @@
 use crate::raw::{OriginalIntersection, OriginalRoad};
 use crate::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, LaneType, Map, RoadID, TurnID,
+    ApproachControl, BusRouteID, BusStop, BusStopID, ControlStopSign, ControlTrafficSignal,
+    CrosswalkID, CrosswalkType, IntersectionID, LaneID, LaneType, Map, PathConstraints, Position,
+    RoadID, SignalCorridor, TimedSpeedLimit, TurnID,
 };
 use abstutil::{deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap, Timer};
-use geom::Speed;
+use geom::{Distance, Speed};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
+// Even a squeezed sidewalk or bike lane needs this much room
+const MIN_LANE_WIDTH: Distance = Distance::const_meters(1.0);
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MapEdits {
     pub edits_name: String,
@@ -15,8 +20,22 @@ pub struct MapEdits {
     // Derived from commands, kept up to date by update_derived
     pub original_lts: BTreeMap<LaneID, LaneType>,
     pub reversed_lanes: BTreeSet<LaneID>,
+    pub changed_lane_widths: BTreeSet<LaneID>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub changed_speed_limits: BTreeSet<RoadID>,
+    pub changed_speed_limit_schedules: BTreeSet<RoadID>,
+    pub changed_no_through_traffic: BTreeSet<RoadID>,
+    pub changed_banned_vehicles: BTreeSet<RoadID>,
+    pub modal_filters: BTreeSet<RoadID>,
+    pub changed_crosswalks: BTreeSet<CrosswalkID>,
+    // Added, removed, or moved
+    pub changed_bus_stops: BTreeSet<BusStopID>,
+    pub banned_right_turn_on_red: BTreeSet<IntersectionID>,
+    pub banned_u_turns: BTreeSet<IntersectionID>,
+
+    // Just groups signals together; the timing itself is stored in each signal. Not part of the
+    // command stack.
+    pub signal_corridors: Vec<SignalCorridor>,
 
     // Edits without these are player generated.
     pub proposal_description: Vec<String>,
@@ -43,16 +62,73 @@ pub enum EditCmd {
         // New intended dst_i
         dst_i: IntersectionID,
     },
+    ChangeLaneWidth {
+        id: LaneID,
+        new: Distance,
+        old: Distance,
+    },
     ChangeSpeedLimit {
         id: RoadID,
         new: Speed,
         old: Speed,
     },
+    ChangeSpeedLimitSchedule {
+        id: RoadID,
+        new: Vec<TimedSpeedLimit>,
+        old: Vec<TimedSpeedLimit>,
+    },
+    ChangeNoThroughTraffic {
+        id: RoadID,
+        new: BTreeSet<PathConstraints>,
+        old: BTreeSet<PathConstraints>,
+    },
+    ChangeBannedVehicles {
+        id: RoadID,
+        new: BTreeSet<PathConstraints>,
+        old: BTreeSet<PathConstraints>,
+    },
+    ChangeModalFilter {
+        r: RoadID,
+        new: Option<IntersectionID>,
+        old: Option<IntersectionID>,
+    },
     ChangeIntersection {
         i: IntersectionID,
         new: EditIntersection,
         old: EditIntersection,
     },
+    ChangeCrosswalk {
+        id: CrosswalkID,
+        new: CrosswalkType,
+        old: CrosswalkType,
+    },
+    ChangeRightTurnOnRed {
+        i: IntersectionID,
+        allowed: bool,
+    },
+    ChangeUTurns {
+        i: IntersectionID,
+        allowed: bool,
+    },
+    AddBusStop {
+        id: BusStopID,
+        // Along the sidewalk in the ID
+        dist_along: Distance,
+        // Each route serving the stop, and where in the route's list of stops it goes
+        routes: Vec<(BusRouteID, usize)>,
+    },
+    // The same fields as AddBusStop, so the stop can be put back
+    RemoveBusStop {
+        id: BusStopID,
+        dist_along: Distance,
+        routes: Vec<(BusRouteID, usize)>,
+    },
+    // Just along the same sidewalk; to move somewhere else, remove it and add a new stop.
+    MoveBusStop {
+        id: BusStopID,
+        new: Distance,
+        old: Distance,
+    },
 }
 
 impl EditCmd {
@@ -60,12 +136,58 @@ impl EditCmd {
         match self {
             EditCmd::ChangeLaneType { lt, id, .. } => format!("{} on #{}", lt.short_name(), id.0),
             EditCmd::ReverseLane { l, .. } => format!("reverse {}", l),
+            EditCmd::ChangeLaneWidth { id, new, .. } => format!("{} wide #{}", new, id.0),
             EditCmd::ChangeSpeedLimit { id, new, .. } => format!("limit {} for {}", new, id),
+            EditCmd::ChangeSpeedLimitSchedule { id, new, .. } => {
+                if new.is_empty() {
+                    format!("no timed limits for {}", id)
+                } else {
+                    format!("timed limits for {}", id)
+                }
+            }
+            EditCmd::ChangeNoThroughTraffic { id, new, .. } => {
+                if new.is_empty() {
+                    format!("through traffic on {}", id)
+                } else {
+                    format!("no through traffic on {}", id)
+                }
+            }
+            EditCmd::ChangeBannedVehicles { id, new, .. } => {
+                if new.is_empty() {
+                    format!("lift bans on {}", id)
+                } else {
+                    format!("ban vehicles on {}", id)
+                }
+            }
+            EditCmd::ChangeModalFilter { r, new, .. } => match new {
+                Some(i) => format!("filter {} at #{}", r, i.0),
+                None => format!("no filter on {}", r),
+            },
             EditCmd::ChangeIntersection { i, new, .. } => match new {
                 EditIntersection::StopSign(_) => format!("stop sign #{}", i.0),
                 EditIntersection::TrafficSignal(_) => format!("traffic signal #{}", i.0),
                 EditIntersection::Closed => format!("close {}", i),
             },
+            EditCmd::ChangeCrosswalk { id, new, .. } => {
+                format!("{} crosswalk #{}", new.describe(), id.i.0)
+            }
+            EditCmd::ChangeRightTurnOnRed { i, allowed } => {
+                if *allowed {
+                    format!("right on red #{}", i.0)
+                } else {
+                    format!("no right on red #{}", i.0)
+                }
+            }
+            EditCmd::ChangeUTurns { i, allowed } => {
+                if *allowed {
+                    format!("U-turns #{}", i.0)
+                } else {
+                    format!("no U-turns #{}", i.0)
+                }
+            }
+            EditCmd::AddBusStop { id, .. } => format!("add stop on #{}", id.sidewalk.0),
+            EditCmd::RemoveBusStop { id, .. } => format!("remove stop on #{}", id.sidewalk.0),
+            EditCmd::MoveBusStop { id, .. } => format!("move stop on #{}", id.sidewalk.0),
         }
     }
 }
@@ -88,8 +210,18 @@ impl MapEdits {
 
             original_lts: BTreeMap::new(),
             reversed_lanes: BTreeSet::new(),
+            changed_lane_widths: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            changed_speed_limit_schedules: BTreeSet::new(),
+            changed_no_through_traffic: BTreeSet::new(),
+            changed_banned_vehicles: BTreeSet::new(),
+            modal_filters: BTreeSet::new(),
+            changed_crosswalks: BTreeSet::new(),
+            changed_bus_stops: BTreeSet::new(),
+            banned_right_turn_on_red: BTreeSet::new(),
+            banned_u_turns: BTreeSet::new(),
+            signal_corridors: Vec::new(),
         }
     }
 
@@ -113,11 +245,29 @@ impl MapEdits {
         );
     }
 
+    // Identifies what these edits do, regardless of their name or description. Two sets of edits
+    // with the same commands have the same checksum.
+    pub fn checksum(&self, map: &Map) -> String {
+        let perma = PermanentMapEdits::to_permanent(self, map);
+        abstutil::stable_hash(
+            abstutil::to_json(&(perma.commands, perma.signal_corridors)).as_bytes(),
+        )
+    }
+
     pub(crate) fn update_derived(&mut self, map: &Map) {
         let mut orig_lts = BTreeMap::new();
         let mut reversed_lanes = BTreeSet::new();
+        let mut changed_lane_widths = BTreeSet::new();
         let mut orig_intersections: BTreeMap<IntersectionID, EditIntersection> = BTreeMap::new();
         let mut changed_speed_limits = BTreeSet::new();
+        let mut changed_speed_limit_schedules = BTreeSet::new();
+        let mut changed_no_through_traffic = BTreeSet::new();
+        let mut changed_banned_vehicles = BTreeSet::new();
+        let mut modal_filters = BTreeSet::new();
+        let mut changed_crosswalks = BTreeSet::new();
+        let mut changed_bus_stops = BTreeSet::new();
+        let mut banned_right_turn_on_red = BTreeSet::new();
+        let mut banned_u_turns = BTreeSet::new();
 
         for cmd in &self.commands {
             match cmd {
@@ -133,14 +283,43 @@ impl MapEdits {
                         reversed_lanes.insert(*l);
                     }
                 }
+                EditCmd::ChangeLaneWidth { id, .. } => {
+                    changed_lane_widths.insert(*id);
+                }
                 EditCmd::ChangeSpeedLimit { id, .. } => {
                     changed_speed_limits.insert(*id);
                 }
+                EditCmd::ChangeSpeedLimitSchedule { id, .. } => {
+                    changed_speed_limit_schedules.insert(*id);
+                }
+                EditCmd::ChangeNoThroughTraffic { id, .. } => {
+                    changed_no_through_traffic.insert(*id);
+                }
+                EditCmd::ChangeBannedVehicles { id, .. } => {
+                    changed_banned_vehicles.insert(*id);
+                }
+                EditCmd::ChangeModalFilter { r, .. } => {
+                    modal_filters.insert(*r);
+                }
+                EditCmd::ChangeCrosswalk { id, .. } => {
+                    changed_crosswalks.insert(*id);
+                }
+                EditCmd::ChangeRightTurnOnRed { i, .. } => {
+                    banned_right_turn_on_red.insert(*i);
+                }
+                EditCmd::ChangeUTurns { i, .. } => {
+                    banned_u_turns.insert(*i);
+                }
                 EditCmd::ChangeIntersection { i, ref old, .. } => {
                     if !orig_intersections.contains_key(i) {
                         orig_intersections.insert(*i, old.clone());
                     }
                 }
+                EditCmd::AddBusStop { id, .. }
+                | EditCmd::RemoveBusStop { id, .. }
+                | EditCmd::MoveBusStop { id, .. } => {
+                    changed_bus_stops.insert(*id);
+                }
             }
         }
 
@@ -148,14 +327,52 @@ impl MapEdits {
         retain_btreemap(&mut orig_intersections, |i, orig| {
             map.get_i_edit(*i) != orig.clone()
         });
+        retain_btreeset(&mut changed_lane_widths, |l| {
+            map.get_l(*l).width != map.get_l(*l).default_width()
+        });
         retain_btreeset(&mut changed_speed_limits, |r| {
             map.get_r(*r).speed_limit != map.get_r(*r).speed_limit_from_osm()
         });
+        retain_btreeset(&mut changed_speed_limit_schedules, |r| {
+            map.get_r(*r).speed_limit_schedule != map.get_r(*r).speed_limit_schedule_from_osm()
+        });
+        retain_btreeset(&mut changed_no_through_traffic, |r| {
+            map.get_r(*r).no_through_traffic != map.get_r(*r).no_through_traffic_from_osm()
+        });
+        retain_btreeset(&mut changed_banned_vehicles, |r| {
+            map.get_r(*r).banned_vehicles != map.get_r(*r).banned_vehicles_from_osm()
+        });
+        retain_btreeset(&mut modal_filters, |r| map.get_r(*r).modal_filter.is_some());
+        retain_btreeset(&mut changed_crosswalks, |id| {
+            let r = map.get_r(id.r);
+            r.crosswalk_type(id.i) != r.crosswalk_type_from_osm(id.i)
+        });
+        retain_btreeset(&mut banned_right_turn_on_red, |i| {
+            !map.get_i(*i).right_turn_on_red
+        });
+        retain_btreeset(&mut banned_u_turns, |i| !map.get_i(*i).u_turns);
 
         self.original_lts = orig_lts;
         self.reversed_lanes = reversed_lanes;
+        self.changed_lane_widths = changed_lane_widths;
         self.original_intersections = orig_intersections;
         self.changed_speed_limits = changed_speed_limits;
+        self.changed_speed_limit_schedules = changed_speed_limit_schedules;
+        self.changed_no_through_traffic = changed_no_through_traffic;
+        self.changed_banned_vehicles = changed_banned_vehicles;
+        self.modal_filters = modal_filters;
+        self.changed_crosswalks = changed_crosswalks;
+        self.changed_bus_stops = changed_bus_stops;
+        self.banned_right_turn_on_red = banned_right_turn_on_red;
+        self.banned_u_turns = banned_u_turns;
+
+        // Signals might've been turned into stop signs
+        for corridor in &mut self.signal_corridors {
+            corridor
+                .members
+                .retain(|i| map.maybe_get_traffic_signal(*i).is_some());
+        }
+        self.signal_corridors.retain(|c| !c.members.is_empty());
     }
 
     // Assumes update_derived has been called.
@@ -173,6 +390,13 @@ impl MapEdits {
                 dst_i: map.get_l(*l).dst_i,
             });
         }
+        for l in &self.changed_lane_widths {
+            self.commands.push(EditCmd::ChangeLaneWidth {
+                id: *l,
+                new: map.get_l(*l).width,
+                old: map.get_l(*l).default_width(),
+            });
+        }
         for (i, old) in &self.original_intersections {
             self.commands.push(EditCmd::ChangeIntersection {
                 i: *i,
@@ -187,6 +411,724 @@ impl MapEdits {
                 old: map.get_r(*r).speed_limit_from_osm(),
             });
         }
+        for r in &self.changed_speed_limit_schedules {
+            self.commands.push(EditCmd::ChangeSpeedLimitSchedule {
+                id: *r,
+                new: map.get_r(*r).speed_limit_schedule.clone(),
+                old: map.get_r(*r).speed_limit_schedule_from_osm(),
+            });
+        }
+        for r in &self.changed_no_through_traffic {
+            self.commands.push(EditCmd::ChangeNoThroughTraffic {
+                id: *r,
+                new: map.get_r(*r).no_through_traffic.clone(),
+                old: map.get_r(*r).no_through_traffic_from_osm(),
+            });
+        }
+        for r in &self.changed_banned_vehicles {
+            self.commands.push(EditCmd::ChangeBannedVehicles {
+                id: *r,
+                new: map.get_r(*r).banned_vehicles.clone(),
+                old: map.get_r(*r).banned_vehicles_from_osm(),
+            });
+        }
+        for r in &self.modal_filters {
+            self.commands.push(EditCmd::ChangeModalFilter {
+                r: *r,
+                new: map.get_r(*r).modal_filter,
+                old: None,
+            });
+        }
+        for id in &self.changed_crosswalks {
+            let r = map.get_r(id.r);
+            self.commands.push(EditCmd::ChangeCrosswalk {
+                id: *id,
+                new: r.crosswalk_type(id.i),
+                old: r.crosswalk_type_from_osm(id.i),
+            });
+        }
+        for i in &self.banned_right_turn_on_red {
+            self.commands.push(EditCmd::ChangeRightTurnOnRed {
+                i: *i,
+                allowed: false,
+            });
+        }
+        for i in &self.banned_u_turns {
+            self.commands.push(EditCmd::ChangeUTurns {
+                i: *i,
+                allowed: false,
+            });
+        }
+        // A removed stop only remembers where it was in its routes through the command, and new
+        // stops are numbered based on the commands before them, so just keep these as they are.
+        for cmd in &map.get_edits().commands {
+            match cmd {
+                EditCmd::AddBusStop { .. }
+                | EditCmd::RemoveBusStop { .. }
+                | EditCmd::MoveBusStop { .. } => {
+                    self.commands.push(cmd.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// For building up edits programmatically, without the UI. Each of these checks the change against
+// the map plus the commands already recorded here, then records it. The edits should start from
+// map.get_edits(), but the new commands don't need to be applied yet, so a big batch can be built
+// up and passed to Map::apply_edits once.
+impl MapEdits {
+    // The type of a lane after all of these commands
+    pub fn lane_type(&self, map: &Map, l: LaneID) -> LaneType {
+        for cmd in self.commands.iter().rev() {
+            if let EditCmd::ChangeLaneType { id, lt, .. } = cmd {
+                if *id == l {
+                    return *lt;
+                }
+            }
+        }
+        map.get_l(l).lane_type
+    }
+
+    // The width of a lane after all of these commands
+    pub fn lane_width(&self, map: &Map, l: LaneID) -> Distance {
+        for cmd in self.commands.iter().rev() {
+            if let EditCmd::ChangeLaneWidth { id, new, .. } = cmd {
+                if *id == l {
+                    return *new;
+                }
+            }
+        }
+        map.get_l(l).width
+    }
+
+    // The control of an intersection after all of these commands
+    pub fn intersection(&self, map: &Map, i: IntersectionID) -> EditIntersection {
+        for cmd in self.commands.iter().rev() {
+            if let EditCmd::ChangeIntersection { i: id, new, .. } = cmd {
+                if *id == i {
+                    return new.clone();
+                }
+            }
+        }
+        map.get_i_edit(i)
+    }
+
+    // Returns an explanation if the change isn't allowed.
+    pub fn can_change_lane_type(&self, map: &Map, l: LaneID, new_lt: LaneType) -> Option<String> {
+        let current = self.lane_type(map, l);
+        // No-op change
+        if current == new_lt {
+            return None;
+        }
+        if current == LaneType::Sidewalk || new_lt == LaneType::Sidewalk {
+            return Some(format!("Sidewalks can't be added or removed"));
+        }
+        if current == LaneType::LightRail || new_lt == LaneType::LightRail {
+            return Some(format!("Tracks can't be added or removed"));
+        }
+
+        let r = map.get_parent(l);
+        let lts = |children: &Vec<(LaneID, LaneType)>| -> Vec<LaneType> {
+            children
+                .iter()
+                .map(|(l, _)| self.lane_type(map, *l))
+                .collect()
+        };
+        let (fwds, idx) = r.dir_and_offset(l);
+        let (mut proposed_lts, other_side) = if fwds {
+            (lts(&r.children_forwards), lts(&r.children_backwards))
+        } else {
+            (lts(&r.children_backwards), lts(&r.children_forwards))
+        };
+        proposed_lts[idx] = new_lt;
+
+        // Only one parking lane per side.
+        if proposed_lts
+            .iter()
+            .filter(|lt| **lt == LaneType::Parking)
+            .count()
+            > 1
+        {
+            // TODO Actually, we just don't want two adjacent parking lanes
+            // (What about dppd though?)
+            return Some(format!(
+                "You can only have one parking lane on the same side of the road"
+            ));
+        }
+
+        // Don't orphan a bus stop.
+        if !r.all_bus_stops(map).is_empty()
+            && !proposed_lts
+                .iter()
+                .any(|lt| *lt == LaneType::Driving || *lt == LaneType::Bus)
+        {
+            return Some(format!("You need a driving or bus lane for the bus stop!"));
+        }
+
+        let all_types: BTreeSet<LaneType> = other_side
+            .into_iter()
+            .chain(proposed_lts.iter().cloned())
+            .collect();
+
+        // A parking lane must have a driving lane somewhere on the road.
+        if all_types.contains(&LaneType::Parking) && !all_types.contains(&LaneType::Driving) {
+            return Some(format!(
+                "A parking lane needs a driving lane somewhere on the same road"
+            ));
+        }
+
+        None
+    }
+
+    pub fn change_lane_type(&mut self, map: &Map, l: LaneID, lt: LaneType) -> Result<(), String> {
+        if let Some(err) = self.can_change_lane_type(map, l, lt) {
+            return Err(format!("Can't change {}: {}", l, err));
+        }
+        let orig_lt = self.lane_type(map, l);
+        if orig_lt != lt {
+            self.commands
+                .push(EditCmd::ChangeLaneType { id: l, lt, orig_lt });
+        }
+        Ok(())
+    }
+
+    // Narrowing some lanes frees up room to widen others, but the whole road can't get wider than
+    // it was built, because the intersections at either end keep their original shape.
+    pub fn change_lane_width(&mut self, map: &Map, l: LaneID, new: Distance) -> Result<(), String> {
+        if new < MIN_LANE_WIDTH {
+            return Err(format!("Can't make {} narrower than {}", l, MIN_LANE_WIDTH));
+        }
+        let road = map.get_parent(l);
+        let mut total = Distance::ZERO;
+        let mut room = Distance::ZERO;
+        for lane in road.all_lanes() {
+            total += if lane == l {
+                new
+            } else {
+                self.lane_width(map, lane)
+            };
+            room += map.get_l(lane).default_width();
+        }
+        if total > room {
+            return Err(format!(
+                "{} would be {} wide, but there's only room for {}",
+                road.id, total, room
+            ));
+        }
+        let old = self.lane_width(map, l);
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeLaneWidth { id: l, new, old });
+        }
+        Ok(())
+    }
+
+    pub fn change_speed_limit(&mut self, map: &Map, r: RoadID, new: Speed) -> Result<(), String> {
+        if new <= Speed::ZERO {
+            return Err(format!("Can't set the speed limit of {} to {}", r, new));
+        }
+        let mut old = map.get_r(r).speed_limit;
+        for cmd in &self.commands {
+            if let EditCmd::ChangeSpeedLimit { id, new, .. } = cmd {
+                if *id == r {
+                    old = *new;
+                }
+            }
+        }
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeSpeedLimit { id: r, new, old });
+        }
+        Ok(())
+    }
+
+    // Replaces all of the limits that only apply at some times of day. An empty schedule means
+    // the normal speed limit always applies.
+    pub fn change_speed_limit_schedule(
+        &mut self,
+        map: &Map,
+        r: RoadID,
+        new: Vec<TimedSpeedLimit>,
+    ) -> Result<(), String> {
+        for timed in &new {
+            if timed.limit <= Speed::ZERO {
+                return Err(format!(
+                    "Can't set a timed speed limit of {} on {}",
+                    timed.limit, r
+                ));
+            }
+            if timed.start == timed.end {
+                return Err(format!(
+                    "A timed speed limit on {} starts and ends at {}",
+                    r, timed.start
+                ));
+            }
+        }
+        let mut old = map.get_r(r).speed_limit_schedule.clone();
+        for cmd in &self.commands {
+            if let EditCmd::ChangeSpeedLimitSchedule { id, new, .. } = cmd {
+                if *id == r {
+                    old = new.clone();
+                }
+            }
+        }
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeSpeedLimitSchedule { id: r, new, old });
+        }
+        Ok(())
+    }
+
+    // Routing avoids driving through a road with these restrictions, unless the trip starts or
+    // ends there.
+    pub fn change_no_through_traffic(
+        &mut self,
+        map: &Map,
+        r: RoadID,
+        new: BTreeSet<PathConstraints>,
+    ) -> Result<(), String> {
+        if new.contains(&PathConstraints::Pedestrian) {
+            return Err(format!("Can't ban pedestrians from passing through {}", r));
+        }
+        let mut old = map.get_r(r).no_through_traffic.clone();
+        for cmd in &self.commands {
+            if let EditCmd::ChangeNoThroughTraffic { id, new, .. } = cmd {
+                if *id == r {
+                    old = new.clone();
+                }
+            }
+        }
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeNoThroughTraffic { id: r, new, old });
+        }
+        Ok(())
+    }
+
+    // Routing avoids driving on a road banning these vehicles at all, unless the trip starts or
+    // ends there. Unlike no through traffic, this doesn't extend to connected roads with the same
+    // restriction. Like "trucks prohibited on this street", though there are no trucks yet.
+    pub fn change_banned_vehicles(
+        &mut self,
+        map: &Map,
+        r: RoadID,
+        new: BTreeSet<PathConstraints>,
+    ) -> Result<(), String> {
+        if new.contains(&PathConstraints::Pedestrian) {
+            return Err(format!("Can't ban pedestrians from {}", r));
+        }
+        if new.contains(&PathConstraints::Bus) || new.contains(&PathConstraints::Train) {
+            return Err(format!(
+                "Can't ban buses or trains from {}; change their routes instead",
+                r
+            ));
+        }
+        let mut old = map.get_r(r).banned_vehicles.clone();
+        for cmd in &self.commands {
+            if let EditCmd::ChangeBannedVehicles { id, new, .. } = cmd {
+                if *id == r {
+                    old = new.clone();
+                }
+            }
+        }
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeBannedVehicles { id: r, new, old });
+        }
+        Ok(())
+    }
+
+    // Reverses the lanes for cars and buses on the other side of the center line, like a series of
+    // ReverseLane commands. Bike lanes and anything past them stay put, so they become contraflow
+    // lanes. This can disconnect part of the map; apply with Map::try_apply_edits to catch that.
+    pub fn convert_to_one_way(
+        &mut self,
+        map: &Map,
+        r: RoadID,
+        forwards: bool,
+    ) -> Result<(), String> {
+        if map.get_r(r).modal_filter.is_some() {
+            return Err(format!(
+                "Cars couldn't turn around at the modal filter on {} if it was one-way",
+                r
+            ));
+        }
+        let (fwd, back) = self.road_lanes(map, r)?;
+        let from = if forwards { back } else { fwd };
+        let num = self.num_center_motor_lanes(map, &from);
+        if num == 0 {
+            return Err(format!("{} is already one-way", r));
+        }
+        self.reverse_center_lanes(map, r, &from, num, forwards);
+        Ok(())
+    }
+
+    // Splits the lanes for cars and buses of a one-way road between both directions. The center
+    // lanes switch direction. Like convert_to_one_way, use Map::try_apply_edits.
+    pub fn convert_to_two_way(&mut self, map: &Map, r: RoadID) -> Result<(), String> {
+        let (fwd, back) = self.road_lanes(map, r)?;
+        let num_fwd = self.num_center_motor_lanes(map, &fwd);
+        let num_back = self.num_center_motor_lanes(map, &back);
+        if num_fwd > 0 && num_back > 0 {
+            return Err(format!("{} is already two-way", r));
+        }
+        let (from, num, forwards) = if num_fwd > 0 {
+            (fwd, num_fwd, false)
+        } else {
+            (back, num_back, true)
+        };
+        if num < 2 {
+            return Err(format!(
+                "{} needs at least two lanes for cars to split between directions",
+                r
+            ));
+        }
+        self.reverse_center_lanes(map, r, &from, num / 2, forwards);
+        Ok(())
+    }
+
+    // The lanes of a road in each direction after all of these commands, ordered from the center
+    fn road_lanes(&self, map: &Map, r: RoadID) -> Result<(Vec<LaneID>, Vec<LaneID>), String> {
+        let road = map.get_r(r);
+        let mut fwd: Vec<LaneID> = road.children_forwards.iter().map(|(l, _)| *l).collect();
+        let mut back: Vec<LaneID> = road.children_backwards.iter().map(|(l, _)| *l).collect();
+        // The map already has its own edits applied, so first get back to the original
+        // directions. Only the lanes closest to the center are ever reversed, so the reversed
+        // lanes on each side are at the front, with the last one reversed first.
+        let reversed = &map.get_edits().reversed_lanes;
+        let num_fwd = fwd.iter().take_while(|l| reversed.contains(l)).count();
+        let num_back = back.iter().take_while(|l| reversed.contains(l)).count();
+        let mut orig_fwd: Vec<LaneID> = back.drain(0..num_back).rev().collect();
+        let mut orig_back: Vec<LaneID> = fwd.drain(0..num_fwd).rev().collect();
+        orig_fwd.extend(fwd);
+        orig_back.extend(back);
+        let (mut fwd, mut back) = (orig_fwd, orig_back);
+
+        for cmd in &self.commands {
+            if let EditCmd::ReverseLane { l, dst_i } = cmd {
+                if *dst_i == road.dst_i && back.get(0) == Some(l) {
+                    back.remove(0);
+                    fwd.insert(0, *l);
+                } else if *dst_i == road.src_i && fwd.get(0) == Some(l) {
+                    fwd.remove(0);
+                    back.insert(0, *l);
+                }
+            }
+        }
+        if fwd
+            .iter()
+            .chain(back.iter())
+            .any(|l| self.lane_type(map, *l) == LaneType::SharedLeftTurn)
+        {
+            return Err(format!(
+                "{} has a center turn lane; change it to another type first",
+                r
+            ));
+        }
+        Ok((fwd, back))
+    }
+
+    fn num_center_motor_lanes(&self, map: &Map, lanes: &Vec<LaneID>) -> usize {
+        lanes
+            .iter()
+            .take_while(|l| {
+                let lt = self.lane_type(map, **l);
+                lt == LaneType::Driving || lt == LaneType::Bus
+            })
+            .count()
+    }
+
+    fn reverse_center_lanes(
+        &mut self,
+        map: &Map,
+        r: RoadID,
+        from: &Vec<LaneID>,
+        num: usize,
+        forwards: bool,
+    ) {
+        let road = map.get_r(r);
+        let dst_i = if forwards { road.dst_i } else { road.src_i };
+        for l in from.iter().take(num) {
+            self.commands.push(EditCmd::ReverseLane { l: *l, dst_i });
+        }
+    }
+
+    // None removes the filter. The filter has to go at one end of the road, and cars need to be
+    // able to drive both ways to turn around there.
+    pub fn change_modal_filter(
+        &mut self,
+        map: &Map,
+        r: RoadID,
+        new: Option<IntersectionID>,
+    ) -> Result<(), String> {
+        let road = map.get_r(r);
+        if let Some(i) = new {
+            if i != road.src_i && i != road.dst_i {
+                return Err(format!("{} isn't at either end of {}", i, r));
+            }
+            if map.get_i(i).is_border() {
+                return Err(format!("Can't put a filter at {}; it's a border", i));
+            }
+            let (fwd, back) = road.get_lane_types();
+            if !fwd.contains(&LaneType::Driving) || !back.contains(&LaneType::Driving) {
+                return Err(format!(
+                    "Cars couldn't turn around at a filter on {}; it needs driving lanes both ways",
+                    r
+                ));
+            }
+        }
+        let mut old = road.modal_filter;
+        for cmd in &self.commands {
+            if let EditCmd::ChangeModalFilter { r: id, new, .. } = cmd {
+                if *id == r {
+                    old = *new;
+                }
+            }
+        }
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeModalFilter { r, new, old });
+        }
+        Ok(())
+    }
+
+    // The signal's ID says which intersection to change. That intersection doesn't have to have a
+    // signal already.
+    pub fn change_traffic_signal(
+        &mut self,
+        map: &Map,
+        signal: ControlTrafficSignal,
+    ) -> Result<(), String> {
+        let i = signal.id;
+        if map.get_i(i).is_border() {
+            return Err(format!("{} is a border; it can't have a traffic signal", i));
+        }
+        let signal = signal.validate()?;
+        let old = self.intersection(map, i);
+        let new = EditIntersection::TrafficSignal(signal);
+        if old != new {
+            self.commands
+                .push(EditCmd::ChangeIntersection { i, new, old });
+        }
+        Ok(())
+    }
+
+    pub fn change_right_turn_on_red(
+        &mut self,
+        map: &Map,
+        i: IntersectionID,
+        allowed: bool,
+    ) -> Result<(), String> {
+        if map.get_i(i).is_border() {
+            return Err(format!("{} is a border; it has no rules for turning", i));
+        }
+        let mut old = map.get_i(i).right_turn_on_red;
+        for cmd in &self.commands {
+            if let EditCmd::ChangeRightTurnOnRed { i: id, allowed } = cmd {
+                if *id == i {
+                    old = *allowed;
+                }
+            }
+        }
+        if old != allowed {
+            self.commands
+                .push(EditCmd::ChangeRightTurnOnRed { i, allowed });
+        }
+        Ok(())
+    }
+
+    // U-turns only show up where the road is wide enough, so changing this might not change
+    // anything.
+    pub fn change_u_turns(
+        &mut self,
+        map: &Map,
+        i: IntersectionID,
+        allowed: bool,
+    ) -> Result<(), String> {
+        if map.get_i(i).is_border() {
+            return Err(format!("{} is a border; it has no turns", i));
+        }
+        let mut old = map.get_i(i).u_turns;
+        for cmd in &self.commands {
+            if let EditCmd::ChangeUTurns { i: id, allowed } = cmd {
+                if *id == i {
+                    old = *allowed;
+                }
+            }
+        }
+        if old != allowed {
+            self.commands.push(EditCmd::ChangeUTurns { i, allowed });
+        }
+        Ok(())
+    }
+
+    // Closes the road to vehicles by turning all of their lanes into construction, leaving the
+    // sidewalks and any tracks. Unlike closing an intersection, cross streets stay connected. This
+    // can disconnect part of the map; apply with Map::try_apply_edits to catch that.
+    pub fn close_road(&mut self, map: &Map, r: RoadID) -> Result<(), String> {
+        let road = map.get_r(r);
+        if !road.all_bus_stops(map).is_empty() {
+            return Err(format!("{} has bus stops; it can't be closed", r));
+        }
+        // Parking lanes need a driving lane on the road, so close them first
+        let mut lanes: Vec<LaneID> = road
+            .all_lanes()
+            .into_iter()
+            .filter(|l| match self.lane_type(map, *l) {
+                LaneType::Sidewalk | LaneType::LightRail | LaneType::Construction => false,
+                _ => true,
+            })
+            .collect();
+        lanes.sort_by_key(|l| self.lane_type(map, *l) != LaneType::Parking);
+        // Don't leave the road half-closed if one lane can't change
+        let mut edits = self.clone();
+        for l in lanes {
+            edits.change_lane_type(map, l, LaneType::Construction)?;
+        }
+        *self = edits;
+        Ok(())
+    }
+
+    // Doesn't check if this disconnects part of the map; use Map::try_apply_edits.
+    pub fn close_intersection(&mut self, map: &Map, i: IntersectionID) -> Result<(), String> {
+        if map.get_i(i).is_border() {
+            return Err(format!("{} is a border; it can't be closed", i));
+        }
+        let old = self.intersection(map, i);
+        if old != EditIntersection::Closed {
+            self.commands.push(EditCmd::ChangeIntersection {
+                i,
+                new: EditIntersection::Closed,
+                old,
+            });
+        }
+        Ok(())
+    }
+
+    // The bus stop changes only check against the map, not earlier bus stop commands here, since
+    // the stops and routes shift around too much. Returns the new stop's ID.
+    pub fn add_bus_stop(
+        &mut self,
+        map: &Map,
+        sidewalk_pos: Position,
+        routes: Vec<(BusRouteID, usize)>,
+    ) -> Result<BusStopID, String> {
+        let sidewalk = sidewalk_pos.lane();
+        if !map.get_l(sidewalk).is_sidewalk() {
+            return Err(format!("{} isn't a sidewalk", sidewalk));
+        }
+        if routes.is_empty() {
+            return Err(format!("A new bus stop needs at least one route"));
+        }
+        let is_train_stop = map.get_br(routes[0].0).route_type == PathConstraints::Train;
+        if routes
+            .iter()
+            .any(|(r, _)| (map.get_br(*r).route_type == PathConstraints::Train) != is_train_stop)
+        {
+            return Err(format!("Buses and trains can't share a stop"));
+        }
+        let driving_lane = map
+            .find_closest_lane(sidewalk, BusStop::lane_types(is_train_stop))
+            .map_err(|_| format!("Nothing can pull up to a stop next to {}", sidewalk))?;
+        for (route, idx) in &routes {
+            let stops = &map.get_br(*route).stops;
+            if *idx > stops.len() {
+                return Err(format!("{} only has {} stops", route, stops.len()));
+            }
+            // A bus can't serve two stops in a row from the same lane
+            let before = if *idx == 0 { stops.len() - 1 } else { idx - 1 };
+            let after = if *idx == stops.len() { 0 } else { *idx };
+            for neighbor in &[stops[before], stops[after]] {
+                if map.get_bs(*neighbor).driving_pos.lane() == driving_lane {
+                    return Err(format!(
+                        "{} already stops next to {}",
+                        map.get_br(*route).name,
+                        sidewalk
+                    ));
+                }
+            }
+        }
+
+        // Don't reuse the number of any stop on this sidewalk, even one that's been removed
+        let mut used: Vec<BusStopID> = map.all_bus_stops().keys().cloned().collect();
+        for cmd in &self.commands {
+            match cmd {
+                EditCmd::AddBusStop { id, .. }
+                | EditCmd::RemoveBusStop { id, .. }
+                | EditCmd::MoveBusStop { id, .. } => {
+                    used.push(*id);
+                }
+                _ => {}
+            }
+        }
+        let id = BusStopID {
+            sidewalk,
+            idx: used
+                .into_iter()
+                .filter(|id| id.sidewalk == sidewalk)
+                .map(|id| id.idx + 1)
+                .max()
+                .unwrap_or(0),
+        };
+        self.commands.push(EditCmd::AddBusStop {
+            id,
+            dist_along: sidewalk_pos.dist_along(),
+            routes,
+        });
+        Ok(id)
+    }
+
+    pub fn remove_bus_stop(&mut self, map: &Map, id: BusStopID) -> Result<(), String> {
+        if !map.all_bus_stops().contains_key(&id) {
+            return Err(format!("{} doesn't exist", id));
+        }
+        let mut routes = Vec::new();
+        for route in map.get_all_bus_routes() {
+            let mut remaining = 0;
+            for (idx, stop) in route.stops.iter().enumerate() {
+                if *stop == id {
+                    routes.push((route.id, idx));
+                } else {
+                    remaining += 1;
+                }
+            }
+            if remaining < 2 && remaining < route.stops.len() {
+                return Err(format!(
+                    "{} would be left with fewer than 2 stops",
+                    route.name
+                ));
+            }
+        }
+        self.commands.push(EditCmd::RemoveBusStop {
+            id,
+            dist_along: map.get_bs(id).sidewalk_pos.dist_along(),
+            routes,
+        });
+        Ok(())
+    }
+
+    pub fn move_bus_stop(&mut self, map: &Map, id: BusStopID, new: Distance) -> Result<(), String> {
+        if !map.all_bus_stops().contains_key(&id) {
+            return Err(format!("{} doesn't exist", id));
+        }
+        let len = map.get_l(id.sidewalk).length();
+        if new < Distance::ZERO || new > len {
+            return Err(format!("{} is only {} long", id.sidewalk, len));
+        }
+        let mut old = map.get_bs(id).sidewalk_pos.dist_along();
+        for cmd in &self.commands {
+            if let EditCmd::MoveBusStop { id: stop, new, .. } = cmd {
+                if *stop == id {
+                    old = *new;
+                }
+            }
+        }
+        if old != new {
+            self.commands.push(EditCmd::MoveBusStop { id, new, old });
+        }
+        Ok(())
     }
 }
 
@@ -215,6 +1157,8 @@ pub struct PermanentMapEdits {
     pub map_name: String,
     pub edits_name: String,
     commands: Vec<PermanentEditCmd>,
+    #[serde(default)]
+    signal_corridors: Vec<PermanentSignalCorridor>,
 
     // Edits without these are player generated.
     pub proposal_description: Vec<String>,
@@ -222,6 +1166,12 @@ pub struct PermanentMapEdits {
     pub proposal_link: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+struct PermanentSignalCorridor {
+    name: String,
+    members: Vec<OriginalIntersection>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 enum PermanentEditIntersection {
     StopSign {
@@ -230,6 +1180,9 @@ enum PermanentEditIntersection {
             deserialize_with = "deserialize_btreemap"
         )]
         must_stop: BTreeMap<OriginalRoad, bool>,
+        // Roads with a yield sign. must_stop is false for these.
+        #[serde(default)]
+        yields: Vec<OriginalRoad>,
     },
     TrafficSignal(seattle_traffic_signals::TrafficSignal),
     Closed,
@@ -257,20 +1210,81 @@ enum PermanentEditCmd {
         // New intended dst_i
         dst_i: OriginalIntersection,
     },
+    ChangeLaneWidth {
+        id: OriginalLane,
+        new: Distance,
+        old: Distance,
+    },
     ChangeSpeedLimit {
         id: OriginalRoad,
         new: Speed,
         old: Speed,
     },
+    ChangeSpeedLimitSchedule {
+        id: OriginalRoad,
+        new: Vec<TimedSpeedLimit>,
+        old: Vec<TimedSpeedLimit>,
+    },
+    ChangeNoThroughTraffic {
+        id: OriginalRoad,
+        new: BTreeSet<PathConstraints>,
+        old: BTreeSet<PathConstraints>,
+    },
+    ChangeBannedVehicles {
+        id: OriginalRoad,
+        new: BTreeSet<PathConstraints>,
+        old: BTreeSet<PathConstraints>,
+    },
+    ChangeModalFilter {
+        r: OriginalRoad,
+        new: Option<OriginalIntersection>,
+        old: Option<OriginalIntersection>,
+    },
     ChangeIntersection {
         i: OriginalIntersection,
         new: PermanentEditIntersection,
         old: PermanentEditIntersection,
     },
+    ChangeCrosswalk {
+        i: OriginalIntersection,
+        r: OriginalRoad,
+        new: CrosswalkType,
+        old: CrosswalkType,
+    },
+    ChangeRightTurnOnRed {
+        i: OriginalIntersection,
+        allowed: bool,
+    },
+    ChangeUTurns {
+        i: OriginalIntersection,
+        allowed: bool,
+    },
+    AddBusStop {
+        id: OriginalBusStop,
+        dist_along: Distance,
+        // By route name
+        routes: Vec<(String, usize)>,
+    },
+    RemoveBusStop {
+        id: OriginalBusStop,
+        dist_along: Distance,
+        routes: Vec<(String, usize)>,
+    },
+    MoveBusStop {
+        id: OriginalBusStop,
+        new: Distance,
+        old: Distance,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct OriginalBusStop {
+    sidewalk: OriginalLane,
+    idx: usize,
 }
 
 impl PermanentMapEdits {
-    fn to_permanent(edits: &MapEdits, map: &Map) -> PermanentMapEdits {
+    pub fn to_permanent(edits: &MapEdits, map: &Map) -> PermanentMapEdits {
         PermanentMapEdits {
             map_name: map.get_name().to_string(),
             edits_name: edits.edits_name.clone(),
@@ -291,6 +1305,13 @@ impl PermanentMapEdits {
                         l: OriginalLane::to_permanent(*l, map),
                         dst_i: map.get_i(*dst_i).orig_id,
                     },
+                    EditCmd::ChangeLaneWidth { id, new, old } => {
+                        PermanentEditCmd::ChangeLaneWidth {
+                            id: OriginalLane::to_permanent(*id, map),
+                            new: *new,
+                            old: *old,
+                        }
+                    }
                     EditCmd::ChangeSpeedLimit { id, new, old } => {
                         PermanentEditCmd::ChangeSpeedLimit {
                             id: map.get_r(*id).orig_id,
@@ -298,6 +1319,34 @@ impl PermanentMapEdits {
                             old: *old,
                         }
                     }
+                    EditCmd::ChangeSpeedLimitSchedule { id, new, old } => {
+                        PermanentEditCmd::ChangeSpeedLimitSchedule {
+                            id: map.get_r(*id).orig_id,
+                            new: new.clone(),
+                            old: old.clone(),
+                        }
+                    }
+                    EditCmd::ChangeNoThroughTraffic { id, new, old } => {
+                        PermanentEditCmd::ChangeNoThroughTraffic {
+                            id: map.get_r(*id).orig_id,
+                            new: new.clone(),
+                            old: old.clone(),
+                        }
+                    }
+                    EditCmd::ChangeBannedVehicles { id, new, old } => {
+                        PermanentEditCmd::ChangeBannedVehicles {
+                            id: map.get_r(*id).orig_id,
+                            new: new.clone(),
+                            old: old.clone(),
+                        }
+                    }
+                    EditCmd::ChangeModalFilter { r, new, old } => {
+                        PermanentEditCmd::ChangeModalFilter {
+                            r: map.get_r(*r).orig_id,
+                            new: new.map(|i| map.get_i(i).orig_id),
+                            old: old.map(|i| map.get_i(i).orig_id),
+                        }
+                    }
                     EditCmd::ChangeIntersection { i, new, old } => {
                         PermanentEditCmd::ChangeIntersection {
                             i: map.get_i(*i).orig_id,
@@ -305,6 +1354,55 @@ impl PermanentMapEdits {
                             old: old.to_permanent(map),
                         }
                     }
+                    EditCmd::ChangeCrosswalk { id, new, old } => {
+                        PermanentEditCmd::ChangeCrosswalk {
+                            i: map.get_i(id.i).orig_id,
+                            r: map.get_r(id.r).orig_id,
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeRightTurnOnRed { i, allowed } => {
+                        PermanentEditCmd::ChangeRightTurnOnRed {
+                            i: map.get_i(*i).orig_id,
+                            allowed: *allowed,
+                        }
+                    }
+                    EditCmd::ChangeUTurns { i, allowed } => PermanentEditCmd::ChangeUTurns {
+                        i: map.get_i(*i).orig_id,
+                        allowed: *allowed,
+                    },
+                    EditCmd::AddBusStop {
+                        id,
+                        dist_along,
+                        routes,
+                    } => PermanentEditCmd::AddBusStop {
+                        id: OriginalBusStop::to_permanent(*id, map),
+                        dist_along: *dist_along,
+                        routes: route_names(routes, map),
+                    },
+                    EditCmd::RemoveBusStop {
+                        id,
+                        dist_along,
+                        routes,
+                    } => PermanentEditCmd::RemoveBusStop {
+                        id: OriginalBusStop::to_permanent(*id, map),
+                        dist_along: *dist_along,
+                        routes: route_names(routes, map),
+                    },
+                    EditCmd::MoveBusStop { id, new, old } => PermanentEditCmd::MoveBusStop {
+                        id: OriginalBusStop::to_permanent(*id, map),
+                        new: *new,
+                        old: *old,
+                    },
+                })
+                .collect(),
+            signal_corridors: edits
+                .signal_corridors
+                .iter()
+                .map(|c| PermanentSignalCorridor {
+                    name: c.name.clone(),
+                    members: c.members.iter().map(|i| map.get_i(*i).orig_id).collect(),
                 })
                 .collect(),
         }
@@ -331,6 +1429,10 @@ impl PermanentMapEdits {
                         let dst_i = map.find_i_by_osm_id(dst_i.osm_node_id)?;
                         Ok(EditCmd::ReverseLane { l, dst_i })
                     }
+                    PermanentEditCmd::ChangeLaneWidth { id, new, old } => {
+                        let id = id.from_permanent(map)?;
+                        Ok(EditCmd::ChangeLaneWidth { id, new, old })
+                    }
                     PermanentEditCmd::ChangeSpeedLimit { id, new, old } => {
                         let id = map.find_r_by_osm_id(
                             id.osm_way_id,
@@ -338,6 +1440,40 @@ impl PermanentMapEdits {
                         )?;
                         Ok(EditCmd::ChangeSpeedLimit { id, new, old })
                     }
+                    PermanentEditCmd::ChangeSpeedLimitSchedule { id, new, old } => {
+                        let id = map.find_r_by_osm_id(
+                            id.osm_way_id,
+                            (id.i1.osm_node_id, id.i2.osm_node_id),
+                        )?;
+                        Ok(EditCmd::ChangeSpeedLimitSchedule { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeNoThroughTraffic { id, new, old } => {
+                        let id = map.find_r_by_osm_id(
+                            id.osm_way_id,
+                            (id.i1.osm_node_id, id.i2.osm_node_id),
+                        )?;
+                        Ok(EditCmd::ChangeNoThroughTraffic { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeBannedVehicles { id, new, old } => {
+                        let id = map.find_r_by_osm_id(
+                            id.osm_way_id,
+                            (id.i1.osm_node_id, id.i2.osm_node_id),
+                        )?;
+                        Ok(EditCmd::ChangeBannedVehicles { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeModalFilter { r, new, old } => {
+                        let r = map
+                            .find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))?;
+                        let new = match new {
+                            Some(i) => Some(map.find_i_by_osm_id(i.osm_node_id)?),
+                            None => None,
+                        };
+                        let old = match old {
+                            Some(i) => Some(map.find_i_by_osm_id(i.osm_node_id)?),
+                            None => None,
+                        };
+                        Ok(EditCmd::ChangeModalFilter { r, new, old })
+                    }
                     PermanentEditCmd::ChangeIntersection { i, new, old } => {
                         let id = map.find_i_by_osm_id(i.osm_node_id)?;
                         Ok(EditCmd::ChangeIntersection {
@@ -350,13 +1486,80 @@ impl PermanentMapEdits {
                                 .ok_or(format!("old ChangeIntersection of {} invalid", i))?,
                         })
                     }
+                    PermanentEditCmd::ChangeCrosswalk { i, r, new, old } => {
+                        let id = CrosswalkID {
+                            i: map.find_i_by_osm_id(i.osm_node_id)?,
+                            r: map.find_r_by_osm_id(
+                                r.osm_way_id,
+                                (r.i1.osm_node_id, r.i2.osm_node_id),
+                            )?,
+                        };
+                        if map.maybe_get_crosswalk(id).is_none() {
+                            return Err(format!("crosswalk over {} at {} is gone", r, i));
+                        }
+                        Ok(EditCmd::ChangeCrosswalk { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeRightTurnOnRed { i, allowed } => {
+                        let i = map.find_i_by_osm_id(i.osm_node_id)?;
+                        Ok(EditCmd::ChangeRightTurnOnRed { i, allowed })
+                    }
+                    PermanentEditCmd::ChangeUTurns { i, allowed } => {
+                        let i = map.find_i_by_osm_id(i.osm_node_id)?;
+                        Ok(EditCmd::ChangeUTurns { i, allowed })
+                    }
+                    PermanentEditCmd::AddBusStop {
+                        id,
+                        dist_along,
+                        routes,
+                    } => Ok(EditCmd::AddBusStop {
+                        id: id.from_permanent(map)?,
+                        dist_along,
+                        routes: route_ids(routes, map)?,
+                    }),
+                    PermanentEditCmd::RemoveBusStop {
+                        id,
+                        dist_along,
+                        routes,
+                    } => Ok(EditCmd::RemoveBusStop {
+                        id: id.from_permanent(map)?,
+                        dist_along,
+                        routes: route_ids(routes, map)?,
+                    }),
+                    PermanentEditCmd::MoveBusStop { id, new, old } => Ok(EditCmd::MoveBusStop {
+                        id: id.from_permanent(map)?,
+                        new,
+                        old,
+                    }),
                 })
                 .collect::<Result<Vec<EditCmd>, String>>()?,
 
             original_lts: BTreeMap::new(),
             reversed_lanes: BTreeSet::new(),
+            changed_lane_widths: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            changed_speed_limit_schedules: BTreeSet::new(),
+            changed_no_through_traffic: BTreeSet::new(),
+            changed_banned_vehicles: BTreeSet::new(),
+            modal_filters: BTreeSet::new(),
+            changed_crosswalks: BTreeSet::new(),
+            changed_bus_stops: BTreeSet::new(),
+            banned_right_turn_on_red: BTreeSet::new(),
+            banned_u_turns: BTreeSet::new(),
+            signal_corridors: perma
+                .signal_corridors
+                .into_iter()
+                .map(|c| {
+                    Ok(SignalCorridor {
+                        name: c.name,
+                        members: c
+                            .members
+                            .into_iter()
+                            .map(|i| map.find_i_by_osm_id(i.osm_node_id))
+                            .collect::<Result<Vec<IntersectionID>, String>>()?,
+                    })
+                })
+                .collect::<Result<Vec<SignalCorridor>, String>>()?,
         };
         edits.update_derived(map);
         Ok(edits)
@@ -370,7 +1573,13 @@ impl EditIntersection {
                 must_stop: ss
                     .roads
                     .iter()
-                    .map(|(r, val)| (map.get_r(*r).orig_id, val.must_stop))
+                    .map(|(r, val)| (map.get_r(*r).orig_id, val.control == ApproachControl::Stop))
+                    .collect(),
+                yields: ss
+                    .roads
+                    .iter()
+                    .filter(|(_, val)| val.control == ApproachControl::Yield)
+                    .map(|(r, _)| map.get_r(*r).orig_id)
                     .collect(),
             },
             EditIntersection::TrafficSignal(ref ts) => {
@@ -384,7 +1593,7 @@ impl EditIntersection {
 impl PermanentEditIntersection {
     fn from_permanent(self, i: IntersectionID, map: &Map) -> Option<EditIntersection> {
         match self {
-            PermanentEditIntersection::StopSign { must_stop } => {
+            PermanentEditIntersection::StopSign { must_stop, yields } => {
                 let mut translated_must_stop = BTreeMap::new();
                 for (r, stop) in must_stop {
                     translated_must_stop.insert(
@@ -400,7 +1609,17 @@ impl PermanentEditIntersection {
                     return None;
                 }
                 for (r, stop) in translated_must_stop {
-                    ss.roads.get_mut(&r)?.must_stop = stop;
+                    ss.roads.get_mut(&r)?.control = if stop {
+                        ApproachControl::Stop
+                    } else {
+                        ApproachControl::Priority
+                    };
+                }
+                for r in yields {
+                    let r = map
+                        .find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))
+                        .ok()?;
+                    ss.roads.get_mut(&r)?.control = ApproachControl::Yield;
                 }
 
                 Some(EditIntersection::StopSign(ss))
@@ -442,3 +1661,36 @@ impl OriginalLane {
         }
     }
 }
+
+impl OriginalBusStop {
+    fn to_permanent(id: BusStopID, map: &Map) -> OriginalBusStop {
+        OriginalBusStop {
+            sidewalk: OriginalLane::to_permanent(id.sidewalk, map),
+            idx: id.idx,
+        }
+    }
+
+    fn from_permanent(self, map: &Map) -> Result<BusStopID, String> {
+        Ok(BusStopID {
+            sidewalk: self.sidewalk.from_permanent(map)?,
+            idx: self.idx,
+        })
+    }
+}
+
+fn route_names(routes: &Vec<(BusRouteID, usize)>, map: &Map) -> Vec<(String, usize)> {
+    routes
+        .iter()
+        .map(|(r, idx)| (map.get_br(*r).name.clone(), *idx))
+        .collect()
+}
+
+fn route_ids(routes: Vec<(String, usize)>, map: &Map) -> Result<Vec<(BusRouteID, usize)>, String> {
+    routes
+        .into_iter()
+        .map(|(name, idx)| match map.get_bus_route(&name) {
+            Some(r) => Ok((r.id, idx)),
+            None => Err(format!("bus route {} is gone", name)),
+        })
+        .collect()
+}
@@ -33,6 +33,11 @@ pub struct Intersection {
 
     pub intersection_type: IntersectionType,
     pub orig_id: OriginalIntersection,
+    // Only matters for traffic signals. The simulation can also ban this everywhere.
+    pub right_turn_on_red: bool,
+    // Vehicles can turn around here, if the road is wide enough. On by default; dead-ends always
+    // allow this.
+    pub u_turns: bool,
 
     // Note that a lane may belong to both incoming_lanes and outgoing_lanes.
     // TODO narrow down when and why. is it just sidewalks in weird cases?
@@ -48,6 +53,11 @@ impl Intersection {
         self.intersection_type == IntersectionType::Border
     }
 
+    // Only one road leads here, so vehicles have to turn around
+    pub fn is_deadend(&self) -> bool {
+        self.roads.len() == 1 && !self.is_border()
+    }
+
     pub fn is_closed(&self) -> bool {
         self.intersection_type == IntersectionType::Construction
     }
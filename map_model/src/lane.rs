@@ -1,7 +1,7 @@
 use crate::pathfind;
 use crate::{
     osm, BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, PathConstraints, Road, RoadID,
-    TurnType,
+    TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
 };
 use geom::{Angle, Distance, Line, PolyLine, Pt2D};
 use serde::{Deserialize, Serialize};
@@ -33,6 +33,7 @@ pub enum LaneType {
     Bus,
     SharedLeftTurn,
     Construction,
+    LightRail,
 }
 
 impl LaneType {
@@ -45,6 +46,7 @@ impl LaneType {
             LaneType::Sidewalk => false,
             LaneType::SharedLeftTurn => false,
             LaneType::Construction => false,
+            LaneType::LightRail => true,
         }
     }
 
@@ -57,6 +59,7 @@ impl LaneType {
             LaneType::Sidewalk => true,
             LaneType::SharedLeftTurn => false,
             LaneType::Construction => false,
+            LaneType::LightRail => true,
         }
     }
 
@@ -69,6 +72,7 @@ impl LaneType {
             LaneType::Sidewalk => "a sidewalk",
             LaneType::SharedLeftTurn => "a shared left-turn lane",
             LaneType::Construction => "a lane that's closed for construction",
+            LaneType::LightRail => "a light rail or streetcar track",
         }
     }
 
@@ -81,6 +85,7 @@ impl LaneType {
             LaneType::Sidewalk => "sidewalk",
             LaneType::SharedLeftTurn => "left-turn lane",
             LaneType::Construction => "construction",
+            LaneType::LightRail => "light rail track",
         }
     }
 }
@@ -159,6 +164,16 @@ impl Lane {
         self.lane_center_pts.length()
     }
 
+    // Returns [-1.0, 1.0]. 0 is flat, positive is uphill in the direction of the lane.
+    pub fn percent_grade(&self, map: &Map) -> f64 {
+        let run = self.length();
+        if run == Distance::ZERO {
+            return 0.0;
+        }
+        let rise = map.get_i(self.dst_i).elevation - map.get_i(self.src_i).elevation;
+        (rise / run).max(-1.0).min(1.0)
+    }
+
     pub fn intersections(&self) -> Vec<IntersectionID> {
         // TODO I think we're assuming there are no loop lanes
         vec![self.src_i, self.dst_i]
@@ -189,10 +204,23 @@ impl Lane {
         self.lane_type == LaneType::Bus
     }
 
+    pub fn is_light_rail(&self) -> bool {
+        self.lane_type == LaneType::LightRail
+    }
+
     pub fn is_sidewalk(&self) -> bool {
         self.lane_type == LaneType::Sidewalk
     }
 
+    // What the lane was built with, before any edits to its width.
+    pub fn default_width(&self) -> Distance {
+        if self.is_sidewalk() {
+            SIDEWALK_THICKNESS
+        } else {
+            NORMAL_LANE_THICKNESS
+        }
+    }
+
     pub fn is_parking(&self) -> bool {
         self.lane_type == LaneType::Parking
     }
@@ -212,51 +240,75 @@ impl Lane {
             return None;
         }
 
-        let (dir, offset) = road.dir_and_offset(self.id);
+        let (dir, _) = road.dir_and_offset(self.id);
+        // With oneway=-1, turn:lanes describes the direction traffic actually goes.
+        let reversed = road.osm_tags.get("oneway") == Some(&"-1".to_string());
         let all = if dir && road.osm_tags.contains_key(osm::ENDPT_FWD) {
             road.osm_tags
                 .get("turn:lanes:forward")
                 .or_else(|| road.osm_tags.get("turn:lanes"))?
         } else if !dir && road.osm_tags.contains_key(osm::ENDPT_BACK) {
-            road.osm_tags.get("turn:lanes:backward")?
+            road.osm_tags.get("turn:lanes:backward").or_else(|| {
+                if reversed {
+                    road.osm_tags.get("turn:lanes")
+                } else {
+                    None
+                }
+            })?
         } else {
             return None;
         };
+        // turn:lanes covers every lane vehicles can drive in, from left to right. The center turn
+        // lane is tagged separately.
+        let vehicle_lanes: Vec<LaneID> = if dir {
+            &road.children_forwards
+        } else {
+            &road.children_backwards
+        }
+        .iter()
+        .filter(|(_, lt)| *lt == LaneType::Driving || *lt == LaneType::Bus)
+        .map(|(l, _)| *l)
+        .collect();
         let parts: Vec<&str> = all.split('|').collect();
-        // TODO Verify the number of lanes matches up
-        let part = parts.get(offset)?;
-        if part == &"none" {
+        if parts.len() != vehicle_lanes.len() {
             return None;
         }
-        Some(
-            part.split(';')
-                .flat_map(|s| match s {
-                    "left" | "left\\left" => vec![TurnType::Left],
-                    "right" => vec![TurnType::Right],
-                    // TODO What is blank supposed to mean? From few observed cases, same as through
-                    "through" | "" => vec![
-                        TurnType::Straight,
-                        TurnType::LaneChangeLeft,
-                        TurnType::LaneChangeRight,
-                    ],
-                    // TODO Check this more carefully
-                    "slight_right" | "slight right" | "merge_to_right" => vec![
-                        TurnType::Straight,
-                        TurnType::LaneChangeRight,
-                        TurnType::Right,
-                    ],
-                    "slight_left" | "slight left" | "merge_to_left" | "sharp_left" => {
-                        vec![TurnType::Straight, TurnType::LaneChangeLeft, TurnType::Left]
-                    }
-                    "reverse" => {
-                        // TODO We need TurnType::UTurn. Until then, u-turns usually show up as
-                        // left turns.
-                        vec![TurnType::Left]
-                    }
-                    _ => panic!("What's turn restriction {}?", s),
-                })
-                .collect(),
-        )
+        let part = parts[vehicle_lanes.iter().position(|l| *l == self.id)?];
+        if part == "none" {
+            return None;
+        }
+        let mut types = BTreeSet::new();
+        for s in part.split(';') {
+            types.extend(match s {
+                "left" | "left\\left" => vec![TurnType::Left],
+                "right" => vec![TurnType::Right],
+                // TODO What is blank supposed to mean? From few observed cases, same as through
+                "through" | "" | "none" => vec![
+                    TurnType::Straight,
+                    TurnType::LaneChangeLeft,
+                    TurnType::LaneChangeRight,
+                ],
+                // TODO Check this more carefully
+                "slight_right" | "slight right" | "merge_to_right" | "sharp_right" => vec![
+                    TurnType::Straight,
+                    TurnType::LaneChangeRight,
+                    TurnType::Right,
+                ],
+                "slight_left" | "slight left" | "merge_to_left" | "sharp_left" => {
+                    vec![TurnType::Straight, TurnType::LaneChangeLeft, TurnType::Left]
+                }
+                "reverse" => {
+                    // TODO We need TurnType::UTurn. Until then, u-turns usually show up as
+                    // left turns.
+                    vec![TurnType::Left]
+                }
+                // Don't restrict anything if the tag is malformed
+                _ => {
+                    return None;
+                }
+            });
+        }
+        Some(types)
     }
 
     pub fn get_max_cost(&self, constraints: PathConstraints, map: &Map) -> usize {
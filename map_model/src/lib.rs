@@ -1,8 +1,11 @@
 mod area;
+mod bike_rack;
 mod building;
 mod bus_stop;
 mod city;
 pub mod connectivity;
+mod crosswalk;
+mod edit_script;
 mod edits;
 mod intersection;
 mod lane;
@@ -13,28 +16,33 @@ mod parking_lot;
 mod pathfind;
 pub mod raw;
 mod road;
+mod stats;
 mod stop_signs;
 mod traffic_signals;
 mod traversable;
 mod turn;
 
 pub use crate::area::{Area, AreaID, AreaType};
-pub use crate::building::{Building, BuildingID, FrontPath, OffstreetParking};
+pub use crate::bike_rack::{BikeRack, BikeRackID};
+pub use crate::building::{Building, BuildingID, FrontPath, LandUse, OffstreetParking};
 pub use crate::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
 pub use crate::city::City;
+pub use crate::crosswalk::{Crosswalk, CrosswalkID, CrosswalkType};
+pub use crate::edit_script::{EditRule, EditScript, IntersectionFilter, RoadFilter};
 pub use crate::edits::{
     EditCmd, EditEffects, EditIntersection, MapEdits, OriginalLane, PermanentMapEdits,
 };
 pub use crate::intersection::{Intersection, IntersectionID, IntersectionType};
 pub use crate::lane::{Lane, LaneID, LaneType, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH};
 pub use crate::make::initial::lane_specs::RoadSpec;
-pub use crate::map::Map;
+pub use crate::map::{Map, MAP_FORMAT_VERSION};
 pub use crate::parking_lot::{ParkingLot, ParkingLotID};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
-pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
-pub use crate::road::{DirectedRoadID, Road, RoadID};
-pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign};
-pub use crate::traffic_signals::{ControlTrafficSignal, Phase};
+pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep, WalkingProfile};
+pub use crate::road::{DirectedRoadID, Road, RoadID, TimedSpeedLimit};
+pub use crate::stats::MapStats;
+pub use crate::stop_signs::{ApproachControl, ControlStopSign, RoadWithStopSign};
+pub use crate::traffic_signals::{ControlTrafficSignal, Phase, SignalCorridor};
 pub use crate::traversable::{Position, Traversable};
 pub use crate::turn::{Turn, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType};
 use abstutil::Cloneable;
@@ -45,6 +53,7 @@ pub const NORMAL_LANE_THICKNESS: Distance = Distance::const_meters(2.5);
 pub const SIDEWALK_THICKNESS: Distance = Distance::const_meters(1.5);
 
 impl Cloneable for BusRouteID {}
+impl Cloneable for CrosswalkType {}
 impl Cloneable for ControlTrafficSignal {}
 impl Cloneable for IntersectionID {}
 impl Cloneable for LaneType {}
@@ -1,4 +1,4 @@
-use crate::{Road, RoadID};
+use crate::{osm, Road, RoadID};
 use abstutil::Timer;
 use geom::{Bounds, Distance, FindClosest};
 
@@ -7,7 +7,7 @@ pub fn find_bridges(roads: &mut Vec<Road>, bounds: &Bounds, timer: &mut Timer) {
     let mut bridges = Vec::new();
     for r in roads.iter() {
         closest.add(r.id, r.center_pts.points());
-        if r.osm_tags.contains_key("bridge") {
+        if r.osm_tags.contains_key(osm::BRIDGE) {
             bridges.push(r.id);
         }
     }
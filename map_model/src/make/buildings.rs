@@ -1,29 +1,41 @@
 use crate::make::sidewalk_finder::find_sidewalk_points;
-use crate::raw::{OriginalBuilding, RawBuilding, RawParkingLot};
+use crate::raw::{OriginalBuilding, RawBikeRack, RawBuilding, RawParkingLot};
 use crate::{
-    osm, Building, BuildingID, FrontPath, LaneID, LaneType, Map, OffstreetParking, ParkingLot,
-    ParkingLotID, Position, NORMAL_LANE_THICKNESS, PARKING_LOT_SPOT_LENGTH,
+    osm, BikeRack, BikeRackID, Building, BuildingID, FrontPath, LandUse, LaneID, LaneType, Map,
+    OffstreetParking, ParkingLot, ParkingLotID, Position, NORMAL_LANE_THICKNESS,
+    PARKING_LOT_SPOT_LENGTH,
 };
 use abstutil::Timer;
-use geom::{Angle, Distance, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Ring};
+use geom::{Angle, Distance, FindClosest, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Ring};
 use std::collections::{BTreeMap, HashSet};
 
 pub fn make_all_buildings(
     input: &BTreeMap<OriginalBuilding, RawBuilding>,
+    driveways: &Vec<Vec<Pt2D>>,
     map: &Map,
     timer: &mut Timer,
 ) -> Vec<Building> {
     timer.start("convert buildings");
+    // The front path starts at the mapped entrance, or just the center
     let mut center_per_bldg: BTreeMap<OriginalBuilding, HashablePt2D> = BTreeMap::new();
     let mut query: HashSet<HashablePt2D> = HashSet::new();
     timer.start_iter("get building center points", input.len());
     for (id, b) in input {
         timer.next();
-        let center = b.polygon.center().to_hashable();
+        let center = b
+            .entrance
+            .unwrap_or_else(|| b.polygon.center())
+            .to_hashable();
         center_per_bldg.insert(*id, center);
         query.insert(center);
     }
 
+    // Buildings with a mapped driveway get parking access where it meets the street
+    let driveway_per_bldg = match_driveways(input, driveways, map, timer);
+    for pl in driveway_per_bldg.values() {
+        query.insert(pl.last_pt().to_hashable());
+    }
+
     // Skip buildings that're too far away from their sidewalk
     let sidewalk_pts = find_sidewalk_points(
         map.get_bounds(),
@@ -47,8 +59,11 @@ pub fn make_all_buildings(
                 continue;
             }
             let b = &input[&orig_id];
-            let sidewalk_line =
-                trim_path(&b.polygon, Line::new(bldg_center.to_pt2d(), sidewalk_pt));
+            let sidewalk_line = if b.entrance.is_some() {
+                Line::new(bldg_center.to_pt2d(), sidewalk_pt)
+            } else {
+                trim_path(&b.polygon, Line::new(bldg_center.to_pt2d(), sidewalk_pt))
+            };
 
             let id = BuildingID(results.len());
             let mut bldg = Building {
@@ -62,27 +77,48 @@ pub fn make_all_buildings(
                     line: sidewalk_line.clone(),
                 },
                 amenities: b.amenities.clone(),
+                levels: b
+                    .osm_tags
+                    .get("building:levels")
+                    .and_then(|x| x.parse::<f64>().ok())
+                    .filter(|x| *x > 0.0)
+                    .unwrap_or(1.0),
+                land_use: LandUse::from_osm(&b.osm_tags, !b.amenities.is_empty()),
                 parking: None,
                 label_center: b.polygon.polylabel(),
             };
 
+            // Where does the driveway meet the street? Prefer the mapped one.
+            let (access_pos, mut driveway_pts) =
+                match driveway_per_bldg.get(&orig_id).and_then(|pl| {
+                    sidewalk_pts
+                        .get(&pl.last_pt().to_hashable())
+                        .map(|pos| (pl, pos))
+                }) {
+                    Some((pl, pos)) => (*pos, pl.points().clone()),
+                    None => (
+                        *sidewalk_pos,
+                        vec![sidewalk_line.pt1(), sidewalk_line.pt2()],
+                    ),
+                };
+
             // Can this building have a driveway? If it's not next to a driving lane, then no.
-            let sidewalk_lane = sidewalk_pos.lane();
+            let sidewalk_lane = access_pos.lane();
             if let Ok(driving_lane) = map
                 .get_parent(sidewalk_lane)
                 .find_closest_lane(sidewalk_lane, vec![LaneType::Driving])
             {
-                let driving_pos = sidewalk_pos.equiv_pos(driving_lane, Distance::ZERO, map);
+                let driving_pos = access_pos.equiv_pos(driving_lane, Distance::ZERO, map);
 
                 let buffer = Distance::meters(7.0);
                 if driving_pos.dist_along() > buffer
                     && map.get_l(driving_lane).length() - driving_pos.dist_along() > buffer
                 {
-                    let driveway_line = PolyLine::new(vec![
-                        sidewalk_line.pt1(),
-                        sidewalk_line.pt2(),
-                        driving_pos.pt(map),
-                    ]);
+                    driveway_pts.push(driving_pos.pt(map));
+                    let driveway_line = PolyLine::unchecked_new(Pt2D::approx_dedupe(
+                        driveway_pts,
+                        Distance::meters(0.1),
+                    ));
                     bldg.parking = Some(OffstreetParking {
                         public_garage_name: b.public_garage_name.clone(),
                         num_spots: b.num_parking_spots,
@@ -182,6 +218,8 @@ pub fn make_all_parking_lots(
                     aisles: Vec::new(),
                     osm_id: orig.osm_id,
                     spots: Vec::new(),
+                    extra_spots: 0,
+                    park_and_ride: orig.park_and_ride,
 
                     driveway_line,
                     driving_pos,
@@ -228,6 +266,20 @@ pub fn make_all_parking_lots(
     for lot in results.iter_mut() {
         timer.next();
         lot.spots = infer_spots(&lot.polygon, &lot.aisles);
+
+        // Trust the mapped capacity over the geometry
+        if let Some(capacity) = input
+            .iter()
+            .find(|l| l.osm_id == lot.osm_id)
+            .unwrap()
+            .capacity
+        {
+            if capacity < lot.spots.len() {
+                lot.spots.truncate(capacity);
+            } else {
+                lot.extra_spots = capacity - lot.spots.len();
+            }
+        }
     }
 
     timer.stop("convert parking lots");
@@ -235,6 +287,116 @@ pub fn make_all_parking_lots(
     results
 }
 
+// Racks without a mapped capacity are usually a single staple, which fits two bikes
+const DEFAULT_BIKE_RACK_CAPACITY: usize = 2;
+
+pub fn make_all_bike_racks(
+    input: &Vec<RawBikeRack>,
+    map: &Map,
+    timer: &mut Timer,
+) -> Vec<BikeRack> {
+    timer.start("convert bike racks");
+    let query: HashSet<HashablePt2D> = input.iter().map(|r| r.pt.to_hashable()).collect();
+    let sidewalk_pts = find_sidewalk_points(
+        map.get_bounds(),
+        query,
+        map.all_lanes(),
+        Distance::meters(50.0),
+        timer,
+    );
+
+    let mut results = Vec::new();
+    for orig in input {
+        let sidewalk_pos = match sidewalk_pts.get(&orig.pt.to_hashable()) {
+            Some(pos) => *pos,
+            None => {
+                continue;
+            }
+        };
+        let sidewalk_lane = sidewalk_pos.lane();
+        let biking_lane = match map
+            .get_parent(sidewalk_lane)
+            .sidewalk_to_bike(sidewalk_lane)
+        {
+            Some(l) => l,
+            None => {
+                timer.warn(format!(
+                    "Skipping bike rack {} because no lane nearby allows bikes",
+                    orig.osm_node_id
+                ));
+                continue;
+            }
+        };
+        // Keep away from the ends of the lane, so bikes can appear and vanish there
+        let buffer = Distance::meters(3.0);
+        let len = map.get_l(biking_lane).length();
+        if len <= buffer * 2.0 {
+            continue;
+        }
+        let mut dist = sidewalk_pos
+            .equiv_pos(biking_lane, Distance::ZERO, map)
+            .dist_along();
+        if dist < buffer {
+            dist = buffer;
+        } else if dist > len - buffer {
+            dist = len - buffer;
+        }
+
+        results.push(BikeRack {
+            id: BikeRackID(results.len()),
+            pt: orig.pt,
+            osm_node_id: orig.osm_node_id,
+            capacity: orig.capacity.unwrap_or(DEFAULT_BIKE_RACK_CAPACITY),
+            sidewalk_pos,
+            biking_pos: Position::new(biking_lane, dist),
+        });
+    }
+    timer.stop("convert bike racks");
+    results
+}
+
+// A driveway touches the building at one end. Returns the driveway oriented away from the
+// building, so the last point is near the street.
+fn match_driveways(
+    input: &BTreeMap<OriginalBuilding, RawBuilding>,
+    driveways: &Vec<Vec<Pt2D>>,
+    map: &Map,
+    timer: &mut Timer,
+) -> BTreeMap<OriginalBuilding, PolyLine> {
+    let mut closest: FindClosest<usize> = FindClosest::new(map.get_bounds());
+    for (idx, pts) in driveways.iter().enumerate() {
+        if pts.len() >= 2 {
+            closest.add(idx, pts);
+        }
+    }
+
+    let mut results = BTreeMap::new();
+    timer.start_iter("match driveways to buildings", input.len());
+    for (id, b) in input {
+        timer.next();
+        let touches = |pt: Pt2D| {
+            b.polygon.contains_pt(pt)
+                || b.polygon
+                    .points()
+                    .iter()
+                    .any(|p| p.dist_to(pt) < Distance::meters(3.0))
+        };
+        for (idx, _, _) in closest.all_close_pts(b.polygon.center(), Distance::meters(50.0)) {
+            let mut pts = driveways[idx].clone();
+            if touches(*pts.last().unwrap()) {
+                pts.reverse();
+            }
+            if touches(pts[0]) && !touches(*pts.last().unwrap()) {
+                if let Some(pl) = PolyLine::maybe_new(pts) {
+                    results.insert(*id, pl);
+                    break;
+                }
+            }
+        }
+    }
+    results
+}
+
 // Adjust the path to start on the building's border, not center
 fn trim_path(poly: &Polygon, path: Line) -> Line {
     for bldg_line in poly.points().windows(2) {
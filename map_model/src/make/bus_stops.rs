@@ -17,12 +17,16 @@ pub fn make_bus_stops(
 ) -> (BTreeMap<BusStopID, BusStop>, Vec<BusRoute>) {
     timer.start("make bus stops");
     let mut bus_stop_pts: HashSet<HashablePt2D> = HashSet::new();
+    // Buses and trains serving the same stop pull up to different lanes, so they get separate
+    // stops. Each point is used by buses, trains, or both.
+    let mut pt_is_rail: MultiMap<HashablePt2D, bool> = MultiMap::new();
     let mut route_lookups: HashMap<String, Vec<HashablePt2D>> = HashMap::new();
     for route in bus_routes {
         for gps in &route.stops {
             if let Some(pt) = Pt2D::from_gps(*gps, gps_bounds) {
                 let hash_pt = pt.to_hashable();
                 bus_stop_pts.insert(hash_pt);
+                pt_is_rail.insert(hash_pt, route.is_rail);
                 route_lookups
                     .entry(route.name.clone())
                     .or_insert_with(Vec::new)
@@ -31,7 +35,7 @@ pub fn make_bus_stops(
         }
     }
 
-    let mut stops_per_sidewalk: MultiMap<LaneID, (Distance, HashablePt2D)> = MultiMap::new();
+    let mut stops_per_sidewalk: MultiMap<LaneID, (Distance, HashablePt2D, bool)> = MultiMap::new();
     for (pt, pos) in find_sidewalk_points(
         bounds,
         bus_stop_pts,
@@ -41,40 +45,55 @@ pub fn make_bus_stops(
     )
     .into_iter()
     {
-        stops_per_sidewalk.insert(pos.lane(), (pos.dist_along(), pt));
+        for is_rail in pt_is_rail.get(pt) {
+            stops_per_sidewalk.insert(pos.lane(), (pos.dist_along(), pt, *is_rail));
+        }
     }
-    let mut point_to_stop_id: HashMap<HashablePt2D, BusStopID> = HashMap::new();
+    let mut point_to_stop_id: HashMap<(HashablePt2D, bool), BusStopID> = HashMap::new();
     let mut bus_stops: BTreeMap<BusStopID, BusStop> = BTreeMap::new();
 
     for (sidewalk_id, dists_set) in stops_per_sidewalk.consume().into_iter() {
         let road = map.get_parent(sidewalk_id);
-        if let Ok(driving_lane) =
-            road.find_closest_lane(sidewalk_id, vec![LaneType::Driving, LaneType::Bus])
-        {
-            let mut dists: Vec<(Distance, HashablePt2D)> = dists_set.into_iter().collect();
-            dists.sort_by_key(|(dist, _)| *dist);
-            for (idx, (dist_along, orig_pt)) in dists.into_iter().enumerate() {
-                let stop_id = BusStopID {
-                    sidewalk: sidewalk_id,
-                    idx,
-                };
-                point_to_stop_id.insert(orig_pt, stop_id);
-                let sidewalk_pos = Position::new(sidewalk_id, dist_along);
-                let driving_pos = sidewalk_pos.equiv_pos(driving_lane, Distance::ZERO, map);
-                bus_stops.insert(
-                    stop_id,
-                    BusStop {
-                        id: stop_id,
-                        sidewalk_pos,
-                        driving_pos,
-                    },
-                );
-            }
-        } else {
-            timer.warn(format!(
-                "Can't find driving lane next to {}: {:?} and {:?}",
-                sidewalk_id, road.children_forwards, road.children_backwards
-            ));
+        let driving_lane = road
+            .find_closest_lane(sidewalk_id, vec![LaneType::Driving, LaneType::Bus])
+            .ok();
+        let rail_lane = road
+            .find_closest_lane(sidewalk_id, vec![LaneType::LightRail])
+            .ok();
+        let mut dists: Vec<(Distance, HashablePt2D, bool)> = dists_set.into_iter().collect();
+        dists.sort_by_key(|(dist, _, _)| *dist);
+        let mut idx = 0;
+        for (dist_along, orig_pt, is_rail) in dists {
+            let lane = match (is_rail, driving_lane, rail_lane) {
+                (false, Some(l), _) | (true, _, Some(l)) => l,
+                _ => {
+                    timer.warn(format!(
+                        "Can't find {} next to {}: {:?} and {:?}",
+                        if is_rail { "tracks" } else { "driving lane" },
+                        sidewalk_id,
+                        road.children_forwards,
+                        road.children_backwards
+                    ));
+                    continue;
+                }
+            };
+            let stop_id = BusStopID {
+                sidewalk: sidewalk_id,
+                idx,
+            };
+            idx += 1;
+            point_to_stop_id.insert((orig_pt, is_rail), stop_id);
+            let sidewalk_pos = Position::new(sidewalk_id, dist_along);
+            let driving_pos = sidewalk_pos.equiv_pos(lane, Distance::ZERO, map);
+            bus_stops.insert(
+                stop_id,
+                BusStop {
+                    id: stop_id,
+                    sidewalk_pos,
+                    driving_pos,
+                    is_train_stop: is_rail,
+                },
+            );
         }
     }
 
@@ -85,7 +104,7 @@ pub fn make_bus_stops(
             .remove(&route_name)
             .unwrap_or_else(Vec::new)
             .into_iter()
-            .filter_map(|pt| point_to_stop_id.get(&pt))
+            .filter_map(|pt| point_to_stop_id.get(&(pt, route.is_rail)))
             .cloned()
             .collect();
         let id = BusRouteID(routes.len());
@@ -93,6 +112,12 @@ pub fn make_bus_stops(
             id,
             name: route_name.to_string(),
             stops,
+            spawn_times: route.spawn_times.clone(),
+            route_type: if route.is_rail {
+                PathConstraints::Train
+            } else {
+                PathConstraints::Bus
+            },
         });
     }
     timer.stop("make bus stops");
@@ -106,14 +131,14 @@ pub fn fix_bus_route(map: &Map, r: &mut BusRoute) -> bool {
         if stops.is_empty() {
             stops.push(stop);
         } else {
-            if check_stops(*stops.last().unwrap(), stop, map) {
+            if check_stops(*stops.last().unwrap(), stop, r.route_type, map) {
                 stops.push(stop);
             }
         }
     }
     // Don't forget the last and first
     while stops.len() >= 2 {
-        if check_stops(*stops.last().unwrap(), stops[0], map) {
+        if check_stops(*stops.last().unwrap(), stops[0], r.route_type, map) {
             break;
         }
         // TODO Or the front one
@@ -123,7 +148,7 @@ pub fn fix_bus_route(map: &Map, r: &mut BusRoute) -> bool {
     r.stops.len() >= 2
 }
 
-fn check_stops(stop1: BusStopID, stop2: BusStopID, map: &Map) -> bool {
+fn check_stops(stop1: BusStopID, stop2: BusStopID, route_type: PathConstraints, map: &Map) -> bool {
     let bs1 = map.get_bs(stop1);
     let bs2 = map.get_bs(stop2);
     // This is coming up because the dist_along's are in a bad order. But why should
@@ -133,7 +158,7 @@ fn check_stops(stop1: BusStopID, stop2: BusStopID, map: &Map) -> bool {
         .pathfind(PathRequest {
             start: bs1.driving_pos,
             end: bs2.driving_pos,
-            constraints: PathConstraints::Bus,
+            constraints: route_type,
         })
         .is_some();
     ok1 && ok2
@@ -1,10 +1,15 @@
 use crate::make::initial::{Intersection, Road};
 use crate::raw::{DrivingSide, OriginalIntersection, OriginalRoad};
-use abstutil::{wraparound_get, Timer};
-use geom::{Distance, Line, PolyLine, Polygon, Pt2D};
+use crate::IntersectionType;
+use abstutil::Timer;
+use geom::{Angle, Arc, Distance, InfiniteLine, Line, PolyLine, Polygon, Pt2D};
 use std::collections::BTreeMap;
 
 const DEGENERATE_INTERSECTION_HALF_LENGTH: Distance = Distance::const_meters(2.5);
+// Rounded corners are made of this many segments
+const CORNER_STEPS: usize = 6;
+// Corners shorter than this aren't worth rounding
+const CORNER_MIN_LENGTH: Distance = Distance::const_meters(0.5);
 
 // The polygon should exist entirely within the thick bands around all original roads -- it just
 // carves up part of that space, doesn't reach past it.
@@ -64,7 +69,9 @@ pub fn intersection_polygon(
     });
 
     if lines.len() == 1 {
-        deadend(roads, i.id, &lines, timer)
+        // Borders just stop at the edge of the map; real dead-ends get room to turn around
+        let turning_circle = i.intersection_type != IntersectionType::Border;
+        deadend(roads, i.id, turning_circle, &lines, timer)
     } else {
         generalized_trim_back(driving_side, roads, i.id, &lines, timer)
     }
@@ -140,11 +147,7 @@ fn generalized_trim_back(
 
             if let Some((hit, angle)) = use_pl1.intersection(&use_pl2) {
                 // Find where the perpendicular hits the original road line
-                let perp = Line::new(
-                    hit,
-                    hit.project_away(Distance::meters(1.0), angle.rotate_degs(90.0)),
-                )
-                .infinite();
+                let perp = InfiniteLine::from_pt_angle(hit, angle.rotate_degs(90.0));
                 // How could something perpendicular to a shifted polyline never hit the original
                 // polyline? Also, find the hit closest to the intersection -- this matters for
                 // very curvy roads, like highway ramps.
@@ -173,12 +176,7 @@ fn generalized_trim_back(
             if false {
                 let perp = Line::new(pl1.last_pt(), other_pl1.last_pt());
                 if perp.intersection(&pl2.last_line()).is_some() {
-                    let new_perp = Line::new(
-                        pl2.last_pt(),
-                        pl2.last_pt()
-                            .project_away(Distance::meters(1.0), perp.angle()),
-                    )
-                    .infinite();
+                    let new_perp = InfiniteLine::from_pt_angle(pl2.last_pt(), perp.angle());
                     // Find the hit closest to the intersection -- this matters for very curvy
                     // roads, like highway ramps.
                     if let Some(trim_to) = road_center.reversed().intersection_infinite(&new_perp) {
@@ -205,115 +203,41 @@ fn generalized_trim_back(
         }
     }
 
-    // After doing all the intersection checks, copy over the new centers. Also fill out the
-    // intersection polygon's points along the way.
-    let mut endpoints: Vec<Pt2D> = Vec::new();
-    for idx in 0..lines.len() as isize {
-        let (id, _, fwd_pl, back_pl) = wraparound_get(&lines, idx);
-        // TODO Ahhh these names are confusing. Adjacent to the fwd_pl, but it's a back pl.
-        let (_adj_back_id, _, adj_back_pl, _) = wraparound_get(&lines, idx + 1);
-        let (_adj_fwd_id, _, _, adj_fwd_pl) = wraparound_get(&lines, idx - 1);
-
-        roads.get_mut(&id).unwrap().trimmed_center_pts = new_road_centers[&id].clone();
-        let r = &roads[&id];
-
-        // Include collisions between polylines of adjacent roads, so the polygon doesn't cover area
-        // not originally covered by the thick road bands.
-        // It's apparently safe to always take the second_half here.
-        if fwd_pl.length() >= geom::EPSILON_DIST * 3.0
-            && adj_fwd_pl.length() >= geom::EPSILON_DIST * 3.0
-        {
-            if let Some((hit, _)) = fwd_pl.second_half().intersection(&adj_fwd_pl.second_half()) {
-                endpoints.push(hit);
-            } else {
-                // Style 1
-                /*endpoints.push(fwd_pl.last_pt());
-                endpoints.push(adj_fwd_pl.last_pt());*/
-
-                // Style 2
-                /*if let Some(hit) = fwd_pl
-                    .last_line()
-                    .infinite()
-                    .intersection(&adj_fwd_pl.last_line().infinite())
-                {
-                    endpoints.push(hit);
-                }*/
-            }
-        } else {
-            timer.warn(format!(
-                "Excluding collision between original polylines of {} and something, because \
-                 stuff's too short",
-                id
-            ));
-        }
-
-        // Shift those final centers out again to find the main endpoints for the polygon.
-        if r.dst_i == i {
-            endpoints.push(
-                driving_side
-                    .right_shift(r.trimmed_center_pts.clone(), r.fwd_width)
-                    .with_context(timer, format!("main polygon endpoints from {}", r.id))
-                    .last_pt(),
-            );
-            endpoints.push(
-                driving_side
-                    .left_shift(r.trimmed_center_pts.clone(), r.back_width)
-                    .with_context(timer, format!("main polygon endpoints from {}", r.id))
-                    .last_pt(),
-            );
-        } else {
-            endpoints.push(
-                driving_side
-                    .left_shift(r.trimmed_center_pts.clone(), r.back_width)
-                    .with_context(timer, format!("main polygon endpoints from {}", r.id))
-                    .first_pt(),
-            );
-            endpoints.push(
-                driving_side
-                    .right_shift(r.trimmed_center_pts.clone(), r.fwd_width)
-                    .with_context(timer, format!("main polygon endpoints from {}", r.id))
-                    .first_pt(),
-            );
-        }
-
-        if back_pl.length() >= geom::EPSILON_DIST * 3.0
-            && adj_back_pl.length() >= geom::EPSILON_DIST * 3.0
-        {
-            if let Some((hit, _)) = back_pl
-                .second_half()
-                .intersection(&adj_back_pl.second_half())
-            {
-                endpoints.push(hit);
-            } else {
-                // Style 1
-                /*endpoints.push(back_pl.last_pt());
-                endpoints.push(adj_back_pl.last_pt());*/
+    // After doing all the intersection checks, copy over the new centers.
+    for (id, _, _, _) in lines {
+        roads.get_mut(id).unwrap().trimmed_center_pts = new_road_centers[id].clone();
+    }
 
-                // Style 2
-                /*if let Some(hit) = back_pl
-                    .last_line()
-                    .infinite()
-                    .intersection(&adj_back_pl.last_line().infinite())
-                {
-                    endpoints.push(hit);
-                }*/
-            }
+    // Each road contributes the two corners of its trimmed end, then the polygon rounds the
+    // corner over to the next road. Ending the polygon at the trimmed ends keeps it from
+    // overlapping the lanes of skewed roads, and the rounded corners give pedestrians' turns
+    // around the corner a smooth path.
+    let mut ends: Vec<[(Pt2D, Angle); 2]> = Vec::new();
+    for (id, _, _, _) in lines {
+        let r = &roads[id];
+        // Oriented to point into the intersection
+        let (pl, width_normal, width_reverse) = if r.dst_i == i {
+            (r.trimmed_center_pts.clone(), r.fwd_width, r.back_width)
         } else {
-            timer.warn(format!(
-                "Excluding collision between original polylines of {} and something, because \
-                 stuff's too short",
-                id
-            ));
-        }
-
-        /*if *id == OriginalRoad(384) {
-            let thin = Distance::meters(1.0);
-            debug.push((format!("back of {}", id), back_pl.make_polygons(thin)));
-            debug.push((
-                format!("adj back of {}", adj_back_id),
-                adj_back_pl.make_polygons(thin),
-            ));
-        }*/
+            (r.trimmed_center_pts.reversed(), r.back_width, r.fwd_width)
+        };
+        let normal = driving_side
+            .right_shift(pl.clone(), width_normal)
+            .with_context(timer, format!("main polygon endpoints from {}", r.id));
+        let reverse = driving_side
+            .left_shift(pl, width_reverse)
+            .with_context(timer, format!("main polygon endpoints from {}", r.id));
+        ends.push([
+            (normal.last_pt(), normal.last_line().angle()),
+            (reverse.last_pt(), reverse.last_line().angle()),
+        ]);
+    }
+    let mut endpoints: Vec<Pt2D> = Vec::new();
+    for (idx, [normal, reverse]) in ends.iter().enumerate() {
+        let next_normal = ends[(idx + 1) % ends.len()][0];
+        endpoints.push(normal.0);
+        endpoints.push(reverse.0);
+        endpoints.extend(round_corner(*reverse, next_normal));
     }
 
     let main_result = close_off_polygon(Pt2D::approx_dedupe(endpoints, Distance::meters(0.1)));
@@ -347,9 +271,51 @@ fn generalized_trim_back(
     (close_off_polygon(endpoints), debug)*/
 }
 
+// The points strictly between two road edges, curving from one to the other. Both angles point
+// into the intersection. The curve stays inside the triangle formed by both edges and where they'd
+// meet if extended, so it never covers more than the original thick roads did. When the edges
+// don't meet in front of both roads, the corner is just cut straight across.
+fn round_corner(from: (Pt2D, Angle), to: (Pt2D, Angle)) -> Vec<Pt2D> {
+    let (pt1, angle1) = from;
+    let (pt2, angle2) = to;
+    let chord = pt1.dist_to(pt2);
+    if chord <= CORNER_MIN_LENGTH {
+        return Vec::new();
+    }
+    let ctrl = match InfiniteLine::from_pt_angle(pt1, angle1)
+        .intersection(&InfiniteLine::from_pt_angle(pt2, angle2))
+    {
+        Some(pt) => pt,
+        None => {
+            return Vec::new();
+        }
+    };
+    // The edges have to meet ahead of both roads, and not absurdly far away, like when they're
+    // nearly parallel.
+    for &(pt, angle) in &[(pt1, angle1), (pt2, angle2)] {
+        let dist = pt.dist_to(ctrl);
+        if dist > chord * 2.0 || pt.project_away(dist, angle).dist_to(ctrl) > dist / 2.0 {
+            return Vec::new();
+        }
+    }
+
+    // A quadratic Bezier curve
+    (1..CORNER_STEPS)
+        .map(|step| {
+            let t = (step as f64) / (CORNER_STEPS as f64);
+            let (a, b, c) = ((1.0 - t).powi(2), 2.0 * (1.0 - t) * t, t.powi(2));
+            Pt2D::new(
+                a * pt1.x() + b * ctrl.x() + c * pt2.x(),
+                a * pt1.y() + b * ctrl.y() + c * pt2.y(),
+            )
+        })
+        .collect()
+}
+
 fn deadend(
     roads: &mut BTreeMap<OriginalRoad, Road>,
     i: OriginalIntersection,
+    turning_circle: bool,
     lines: &Vec<(OriginalRoad, Line, PolyLine, PolyLine)>,
     timer: &mut Timer,
 ) -> (Vec<Pt2D>, Vec<(String, Polygon)>) {
@@ -370,10 +336,20 @@ fn deadend(
                 .exact_slice(Distance::ZERO, r.trimmed_center_pts.length() - len);
         }
 
-        (
-            close_off_polygon(vec![pt1, pt2, pl_b.last_pt(), pl_a.last_pt()]),
-            Vec::new(),
-        )
+        let mut pts = vec![pt1, pt2, pl_b.last_pt()];
+        if turning_circle {
+            // Round off the end of the road, so U-turns swinging around have somewhere to go
+            if let Some(arc) =
+                Arc::half_circle(pl_b.last_pt(), pl_a.last_pt(), pl_a.last_line().angle())
+                    .to_polyline(Distance::meters(1.0))
+            {
+                let mut arc = arc.into_points();
+                arc.pop();
+                pts.extend(arc.into_iter().skip(1));
+            }
+        }
+        pts.push(pl_a.last_pt());
+        (close_off_polygon(pts), Vec::new())
     } else {
         timer.warn(format!(
             "{} is a dead-end for {}, which is too short to make degenerate intersection geometry",
@@ -1,10 +1,14 @@
+use crate::raw::DrivingSide;
 use crate::{osm, LaneType};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::{fmt, iter};
 
 // (original direction, reversed direction)
-pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Vec<LaneType>) {
+pub fn get_lane_types(
+    osm_tags: &BTreeMap<String, String>,
+    driving_side: DrivingSide,
+) -> (Vec<LaneType>, Vec<LaneType>) {
     if let Some(s) = osm_tags.get(osm::SYNTHETIC_LANES) {
         if let Some(spec) = RoadSpec::parse(s.to_string()) {
             return (spec.fwd, spec.back);
@@ -17,59 +21,74 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
     if osm_tags.get("junction") == Some(&"roundabout".to_string()) {
         return (vec![LaneType::Driving, LaneType::Sidewalk], Vec::new());
     }
-    if osm_tags.get(osm::HIGHWAY) == Some(&"footway".to_string()) {
+    if osm_tags.get(osm::HIGHWAY) == Some(&"footway".to_string())
+        || osm_tags.get(osm::HIGHWAY) == Some(&"steps".to_string())
+        || osm_tags.get(osm::HIGHWAY) == Some(&"pedestrian".to_string())
+    {
         return (vec![LaneType::Sidewalk], Vec::new());
     }
+    // Tracks with their own right-of-way. Usually each track is mapped separately, but trains
+    // could go either way along one.
+    if !osm_tags.contains_key(osm::HIGHWAY) && osm::is_light_rail(osm_tags) {
+        if osm_tags.get("oneway") == Some(&"yes".to_string()) {
+            return (vec![LaneType::LightRail], Vec::new());
+        }
+        return (vec![LaneType::LightRail], vec![LaneType::LightRail]);
+    }
 
     // TODO Reversible roads should be handled differently?
-    let oneway = osm_tags.get("oneway") == Some(&"yes".to_string())
-        || osm_tags.get("oneway") == Some(&"reversible".to_string());
+    let oneway = match osm_tags.get("oneway").map(|x| x.as_str()) {
+        Some("yes") | Some("1") | Some("true") | Some("reversible") | Some("-1") => true,
+        Some("no") => false,
+        // Motorways are implied to be one-way
+        _ => {
+            osm_tags.get(osm::HIGHWAY) == Some(&"motorway".to_string())
+                || osm_tags.get(osm::HIGHWAY) == Some(&"motorway_link".to_string())
+        }
+    };
+    // Traffic flows against the direction of the way. Figure out the lanes as if it was a normal
+    // one-way, then flip them at the end.
+    let reversed = osm_tags.get("oneway") == Some(&"-1".to_string());
 
-    // How many driving lanes in each direction?
-    let num_driving_fwd = if let Some(n) = osm_tags
-        .get("lanes:forward")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
+    // How many driving lanes in each direction? The total might include a center turn lane, and
+    // turn:lanes lists every lane, so use that when the counts aren't tagged directly.
+    let num_both_ways = if let Some(n) = parse_num(osm_tags, "lanes:both_ways") {
         n
-    } else if let Some(n) = osm_tags
-        .get("lanes")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
-        if oneway {
-            n
-        } else if n % 2 == 0 {
-            n / 2
-        } else {
-            // TODO Really, this is ambiguous, but...
-            (n / 2).max(1)
-        }
-    } else {
-        // TODO Grrr.
+    } else if osm_tags.get("centre_turn_lane") == Some(&"yes".to_string()) {
         1
+    } else {
+        0
     };
-    let num_driving_back = if let Some(n) = osm_tags
-        .get("lanes:backward")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
-        n
-    } else if let Some(n) = osm_tags
-        .get("lanes")
-        .and_then(|num| num.parse::<usize>().ok())
-    {
-        if oneway {
-            0
-        } else if n % 2 == 0 {
-            n / 2
-        } else {
-            // TODO Really, this is ambiguous, but...
-            (n / 2).max(1)
-        }
+    let total = parse_num(osm_tags, "lanes")
+        .or_else(|| {
+            if oneway {
+                count_turn_lanes(osm_tags, "turn:lanes")
+            } else {
+                None
+            }
+        })
+        .map(|n| n.saturating_sub(num_both_ways));
+    let fwd = parse_num(osm_tags, "lanes:forward")
+        .or_else(|| count_turn_lanes(osm_tags, "turn:lanes:forward"));
+    let back = parse_num(osm_tags, "lanes:backward")
+        .or_else(|| count_turn_lanes(osm_tags, "turn:lanes:backward"));
+    let (num_driving_fwd, num_driving_back) = if oneway {
+        (total.or(fwd).unwrap_or(1).max(1), 0)
     } else {
-        // TODO Grrr.
-        if oneway {
-            0
-        } else {
-            1
+        match (fwd, back, total) {
+            (Some(f), Some(b), _) => (f, b),
+            (Some(f), None, Some(n)) => (f, n.saturating_sub(f).max(1)),
+            (None, Some(b), Some(n)) => (n.saturating_sub(b).max(1), b),
+            (None, None, Some(n)) => {
+                if n % 2 == 0 {
+                    (n / 2, n / 2)
+                } else {
+                    // TODO Really, this is ambiguous, but...
+                    ((n / 2).max(1), (n / 2).max(1))
+                }
+            }
+            // TODO Grrr.
+            (f, b, None) => (f.unwrap_or(1), b.unwrap_or(1)),
         }
     };
 
@@ -87,13 +106,21 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
     let mut fwd_side: Vec<LaneType> = iter::repeat(driving_lane).take(num_driving_fwd).collect();
     let mut back_side: Vec<LaneType> = iter::repeat(driving_lane).take(num_driving_back).collect();
     // TODO Fix upstream. https://wiki.openstreetmap.org/wiki/Key:centre_turn_lane
-    if osm_tags.get("lanes:both_ways") == Some(&"1".to_string())
-        || osm_tags.get("centre_turn_lane") == Some(&"yes".to_string())
-    {
+    if num_both_ways > 0 && !oneway {
         fwd_side.insert(0, LaneType::SharedLeftTurn);
     }
+    // Streetcars running down the middle of the road
+    if osm::is_light_rail(osm_tags) {
+        fwd_side.insert(0, LaneType::LightRail);
+        if !oneway {
+            back_side.insert(0, LaneType::LightRail);
+        }
+    }
 
     if driving_lane == LaneType::Construction {
+        if reversed {
+            return (back_side, fwd_side);
+        }
         return (fwd_side, back_side);
     }
 
@@ -108,16 +135,23 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
         }
     }
 
+    // Tags for one side of the way, like cycleway:right, are relative to the way's direction.
+    // The original direction is on the right side, unless driving on the left.
+    let (fwd_side_name, back_side_name) = match driving_side {
+        DrivingSide::Right => ("right", "left"),
+        DrivingSide::Left => ("left", "right"),
+    };
+
     if osm_tags.get("cycleway") == Some(&"lane".to_string()) {
         fwd_side.push(LaneType::Biking);
         if !back_side.is_empty() {
             back_side.push(LaneType::Biking);
         }
     } else {
-        if osm_tags.get("cycleway:right") == Some(&"lane".to_string()) {
+        if osm_tags.get(&format!("cycleway:{}", fwd_side_name)) == Some(&"lane".to_string()) {
             fwd_side.push(LaneType::Biking);
         }
-        if osm_tags.get("cycleway:left") == Some(&"lane".to_string()) {
+        if osm_tags.get(&format!("cycleway:{}", back_side_name)) == Some(&"lane".to_string()) {
             back_side.push(LaneType::Biking);
         }
     }
@@ -128,9 +162,13 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
                 || value == Some(&"diagonal".to_string())
                 || value == Some(&"perpendicular".to_string())
         }
-        let parking_lane_fwd = has_parking(osm_tags.get(osm::PARKING_RIGHT))
+        let (parking_fwd_key, parking_back_key) = match driving_side {
+            DrivingSide::Right => (osm::PARKING_RIGHT, osm::PARKING_LEFT),
+            DrivingSide::Left => (osm::PARKING_LEFT, osm::PARKING_RIGHT),
+        };
+        let parking_lane_fwd = has_parking(osm_tags.get(parking_fwd_key))
             || has_parking(osm_tags.get(osm::PARKING_BOTH));
-        let parking_lane_back = has_parking(osm_tags.get(osm::PARKING_LEFT))
+        let parking_lane_back = has_parking(osm_tags.get(parking_back_key))
             || has_parking(osm_tags.get(osm::PARKING_BOTH));
         if parking_lane_fwd {
             fwd_side.push(LaneType::Parking);
@@ -146,15 +184,29 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
     {
         fwd_side.push(LaneType::Sidewalk);
         back_side.push(LaneType::Sidewalk);
-    } else if osm_tags.get(osm::SIDEWALK) == Some(&"right".to_string()) {
+    } else if osm_tags.get(osm::SIDEWALK) == Some(&fwd_side_name.to_string()) {
         fwd_side.push(LaneType::Sidewalk);
-    } else if osm_tags.get(osm::SIDEWALK) == Some(&"left".to_string()) {
+    } else if osm_tags.get(osm::SIDEWALK) == Some(&back_side_name.to_string()) {
         back_side.push(LaneType::Sidewalk);
     }
 
+    if reversed {
+        // TODO Tags for the left and right side, like sidewalk=right, get flipped too.
+        return (back_side, fwd_side);
+    }
+
     (fwd_side, back_side)
 }
 
+fn parse_num(osm_tags: &BTreeMap<String, String>, key: &str) -> Option<usize> {
+    osm_tags.get(key).and_then(|num| num.parse::<usize>().ok())
+}
+
+// turn:lanes has one entry per lane, separated by |
+fn count_turn_lanes(osm_tags: &BTreeMap<String, String>, key: &str) -> Option<usize> {
+    osm_tags.get(key).map(|x| x.split('|').count())
+}
+
 // This is a convenient way for map_editor to plumb instructions here.
 #[derive(Serialize, Deserialize)]
 pub struct RoadSpec {
@@ -209,6 +261,7 @@ impl RoadSpec {
             LaneType::Bus => 'u',
             LaneType::SharedLeftTurn => 'l',
             LaneType::Construction => 'c',
+            LaneType::LightRail => 'r',
         }
     }
 
@@ -221,6 +274,7 @@ impl RoadSpec {
             'u' => Some(LaneType::Bus),
             'l' => Some(LaneType::SharedLeftTurn),
             'c' => Some(LaneType::Construction),
+            'r' => Some(LaneType::LightRail),
             _ => None,
         }
     }
@@ -2,7 +2,7 @@ mod geometry;
 pub mod lane_specs;
 
 pub use self::geometry::intersection_polygon;
-use crate::raw::{OriginalIntersection, OriginalRoad, RawMap, RawRoad};
+use crate::raw::{DrivingSide, OriginalIntersection, OriginalRoad, RawMap, RawRoad};
 use crate::{IntersectionType, LaneType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
 use abstutil::Timer;
 use geom::{Bounds, Distance, PolyLine, Pt2D};
@@ -28,8 +28,8 @@ pub struct Road {
 }
 
 impl Road {
-    pub fn new(id: OriginalRoad, r: &RawRoad) -> Road {
-        let lane_specs = get_lane_specs(&r.osm_tags);
+    pub fn new(id: OriginalRoad, r: &RawRoad, driving_side: DrivingSide) -> Road {
+        let lane_specs = get_lane_specs(&r.osm_tags, driving_side);
         let mut fwd_width = Distance::ZERO;
         let mut back_width = Distance::ZERO;
         for l in &lane_specs {
@@ -97,7 +97,7 @@ impl InitialMap {
             m.intersections.get_mut(&id.i1).unwrap().roads.insert(*id);
             m.intersections.get_mut(&id.i2).unwrap().roads.insert(*id);
 
-            m.roads.insert(*id, Road::new(*id, r));
+            m.roads.insert(*id, Road::new(*id, r, raw.driving_side));
         }
 
         timer.start_iter("find each intersection polygon", m.intersections.len());
@@ -116,8 +116,11 @@ pub struct LaneSpec {
     pub reverse_pts: bool,
 }
 
-pub fn get_lane_specs(osm_tags: &BTreeMap<String, String>) -> Vec<LaneSpec> {
-    let (side1_types, side2_types) = lane_specs::get_lane_types(osm_tags);
+pub fn get_lane_specs(
+    osm_tags: &BTreeMap<String, String>,
+    driving_side: DrivingSide,
+) -> Vec<LaneSpec> {
+    let (side1_types, side2_types) = lane_specs::get_lane_types(osm_tags, driving_side);
 
     let mut specs: Vec<LaneSpec> = Vec::new();
     for lane_type in side1_types {
@@ -0,0 +1,77 @@
+use crate::raw::{OriginalIntersection, OriginalRoad, RawMap, RawRoad};
+use abstutil::Timer;
+use geom::Distance;
+use std::collections::BTreeMap;
+
+// Untagged roads shorter than this are assumed to be an artifact of how OSM draws one junction
+const SHORT_ROAD_THRESHOLD: Distance = Distance::const_meters(5.0);
+
+// OSM draws dual carriageways as two one-way roads, so where one crosses another road, there's a
+// cluster of two to four intersections joined by tiny stubs. Slip lanes do the same. Each of those
+// would get its own signal and a handful of odd turns, so collapse every cluster into a single
+// intersection first.
+//
+// Returns the OSM node ID of every deleted intersection, mapped to the one it was merged into.
+pub fn merge_short_roads(map: &mut RawMap, timer: &mut Timer) -> BTreeMap<i64, i64> {
+    timer.start("merging short roads");
+    let candidates: Vec<OriginalRoad> = map
+        .roads
+        .iter()
+        .filter(|(_, r)| is_short(r))
+        .map(|(id, _)| *id)
+        .collect();
+
+    // Each merge re-keys the roads at the deleted intersection, so remember where it went
+    let mut merged_into: BTreeMap<OriginalIntersection, OriginalIntersection> = BTreeMap::new();
+    let mut count = 0;
+    for orig_id in candidates {
+        let id = OriginalRoad {
+            osm_way_id: orig_id.osm_way_id,
+            i1: resolve(&merged_into, orig_id.i1),
+            i2: resolve(&merged_into, orig_id.i2),
+        };
+        if !map.roads.contains_key(&id) {
+            continue;
+        }
+        match map.merge_short_road(id) {
+            Ok(keep_i) => {
+                merged_into.insert(id.i2, keep_i);
+                count += 1;
+            }
+            Err(err) => {
+                timer.warn(format!("Not merging short road: {}", err));
+            }
+        }
+    }
+    timer.note(format!(
+        "Merged {} short roads into their neighboring intersections",
+        count
+    ));
+    timer.stop("merging short roads");
+
+    merged_into
+        .keys()
+        .map(|i| (i.osm_node_id, resolve(&merged_into, *i).osm_node_id))
+        .collect()
+}
+
+fn is_short(r: &RawRoad) -> bool {
+    if r.osm_tags.get("junction") == Some(&"intersection".to_string()) {
+        return true;
+    }
+    let mut length = Distance::ZERO;
+    for pair in r.center_points.windows(2) {
+        length += pair[0].dist_to(pair[1]);
+    }
+    length < SHORT_ROAD_THRESHOLD
+}
+
+fn resolve(
+    merged_into: &BTreeMap<OriginalIntersection, OriginalIntersection>,
+    mut i: OriginalIntersection,
+) -> OriginalIntersection {
+    while let Some(next) = merged_into.get(&i) {
+        i = *next;
+    }
+    i
+}
@@ -2,6 +2,7 @@ pub mod bridges;
 pub mod buildings;
 pub mod bus_stops;
 pub mod initial;
+pub mod merge_intersections;
 pub mod remove_disconnected;
 pub mod sidewalk_finder;
 pub mod traffic_signals;
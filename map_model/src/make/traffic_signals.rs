@@ -403,6 +403,12 @@ fn make_phases(
         let mut phase = Phase::new();
 
         for (roads, turn_type, protected) in specs.into_iter() {
+            // The specs are written for driving on the right
+            let turn_type = match turn_type {
+                TurnType::Right => map.get_driving_side().right_turn(),
+                TurnType::Left => map.get_driving_side().left_turn(),
+                x => x,
+            };
             for group in turn_groups.values() {
                 if !roads.contains(&group.id.from.id) || turn_type != group.turn_type {
                     continue;
@@ -3,10 +3,13 @@ use crate::{
     Intersection, IntersectionID, Lane, LaneID, LaneType, Road, RoadID, Turn, TurnID, TurnType,
 };
 use abstutil::{wraparound_get, Timer, Warn};
-use geom::{Distance, Line, PolyLine, Pt2D, Ring};
+use geom::{Arc, Distance, Line, PolyLine, Pt2D, Ring};
 use nbez::{Bez3o, BezCurve, Point2d};
 use std::collections::{BTreeSet, HashMap, HashSet};
 
+// How far U-turns at a dead-end drive into the turning circle before swinging around
+const TURNAROUND_REACH: Distance = Distance::const_meters(5.0);
+
 // TODO Add proper warnings when the geometry is too small to handle.
 
 pub fn make_all_turns(
@@ -19,7 +22,7 @@ pub fn make_all_turns(
     assert!(!i.is_border());
 
     let mut raw_turns: Vec<Turn> = Vec::new();
-    raw_turns.extend(make_vehicle_turns(i, roads, lanes, timer));
+    raw_turns.extend(make_vehicle_turns(driving_side, i, roads, lanes, timer));
     raw_turns.extend(make_walking_turns(driving_side, i, roads, lanes, timer));
     let unique_turns = ensure_unique(raw_turns);
 
@@ -101,6 +104,7 @@ fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
 }
 
 fn make_vehicle_turns(
+    driving_side: DrivingSide,
     i: &Intersection,
     all_roads: &Vec<Road>,
     lanes: &Vec<Lane>,
@@ -123,6 +127,11 @@ fn make_vehicle_turns(
     lane_types.remove(&LaneType::Construction);
     lane_types.remove(&LaneType::Sidewalk);
 
+    let (outwards_lane_change, inwards_lane_change) = if driving_side == DrivingSide::Right {
+        (TurnType::LaneChangeRight, TurnType::LaneChangeLeft)
+    } else {
+        (TurnType::LaneChangeLeft, TurnType::LaneChangeRight)
+    };
     let mut result: Vec<Option<Turn>> = Vec::new();
 
     for lane_type in lane_types.into_iter() {
@@ -165,13 +174,13 @@ fn make_vehicle_turns(
                 let angle2 = lanes[outgoing[0].0].first_line().angle();
 
                 let type_from_angle = TurnType::from_angles(angle1, angle2);
-                let tt = if type_from_angle == TurnType::Right {
+                let tt = if type_from_angle == driving_side.right_turn() {
                     // This one's fragile, based on angles. Really we care that there aren't roads
                     // between the two.
                     if wraparound_get(&sorted_roads, (idx1 as isize) - 1).id == r2.id
                         || wraparound_get(&sorted_roads, (idx1 as isize) + 1).id == r2.id
                     {
-                        TurnType::Right
+                        type_from_angle
                     } else {
                         TurnType::Straight
                     }
@@ -213,10 +222,11 @@ fn make_vehicle_turns(
                                     continue;
                                 }
                                 if let Some(mut t) = make_vehicle_turn(lanes, i.id, l1, *l2, tt) {
+                                    // Lanes are numbered from the center of the road outwards
                                     if idx1 < idx2 {
-                                        t.turn_type = TurnType::LaneChangeRight;
+                                        t.turn_type = outwards_lane_change;
                                     } else if idx1 > idx2 {
-                                        t.turn_type = TurnType::LaneChangeLeft;
+                                        t.turn_type = inwards_lane_change;
                                     }
                                     result.push(Some(t));
                                 }
@@ -224,23 +234,18 @@ fn make_vehicle_turns(
                         }
                         all_incoming_lanes_covered = true;
                     }
-                    TurnType::Right => {
+                    TurnType::Right | TurnType::Left => {
+                        // Turns towards the curb come from the outermost lane, and turns across
+                        // oncoming traffic from the innermost
+                        let from_idx = if tt == driving_side.right_turn() {
+                            incoming.len() - 1
+                        } else {
+                            0
+                        };
                         for (idx, l1) in incoming.iter().enumerate() {
                             for l2 in &outgoing {
                                 let turn = make_vehicle_turn(lanes, i.id, *l1, *l2, tt);
-                                if idx == incoming.len() - 1 {
-                                    result.push(turn);
-                                } else {
-                                    maybe_add_turns.push(turn);
-                                }
-                            }
-                        }
-                    }
-                    TurnType::Left => {
-                        for (idx, l1) in incoming.iter().enumerate() {
-                            for l2 in &outgoing {
-                                let turn = make_vehicle_turn(lanes, i.id, *l1, *l2, tt);
-                                if idx == 0 {
+                                if idx == from_idx {
                                     result.push(turn);
                                 } else {
                                     maybe_add_turns.push(turn);
@@ -255,12 +260,43 @@ fn make_vehicle_turns(
             if !all_incoming_lanes_covered {
                 result.extend(maybe_add_turns);
             }
+
+            // Cars have to turn around at a modal filter
+            if lane_type == LaneType::Driving
+                && ((i.u_turns && u_turn_fits(r1, i.id, lanes)) || r1.modal_filter == Some(i.id))
+                && !filter_lanes(r1.outgoing_lanes(i.id), lane_type).is_empty()
+            {
+                // From the innermost lane back into the innermost lane going the other way
+                let l1 = incoming[0];
+                let l2 = filter_vehicle_lanes(r1.outgoing_lanes(i.id), lane_type)[0];
+                // Which way is the U-turn swinging?
+                let tt = TurnType::from_angles(
+                    lanes[l1.0].last_line().angle(),
+                    lanes[l1.0].last_pt().angle_to(lanes[l2.0].first_pt()),
+                );
+                result.push(make_vehicle_turn(lanes, i.id, l1, l2, tt));
+            }
         }
     }
 
     result.into_iter().filter_map(|x| x).collect()
 }
 
+// Is there room to turn around on this road? Without knowing about medians, require a center
+// turn lane or at least two lanes each way.
+fn u_turn_fits(r: &Road, i: IntersectionID, lanes: &Vec<Lane>) -> bool {
+    let incoming = filter_lanes(r.incoming_lanes(i), LaneType::Driving);
+    let outgoing = filter_lanes(r.outgoing_lanes(i), LaneType::Driving);
+    if incoming.is_empty() || outgoing.is_empty() {
+        return false;
+    }
+    let center_lane = r
+        .all_lanes()
+        .into_iter()
+        .any(|l| lanes[l.0].lane_type == LaneType::SharedLeftTurn);
+    center_lane || (incoming.len() >= 2 && outgoing.len() >= 2)
+}
+
 fn make_vehicle_turns_for_dead_end(
     i: &Intersection,
     roads: &Vec<Road>,
@@ -277,22 +313,58 @@ fn make_vehicle_turns_for_dead_end(
     let mut result = Vec::new();
     for l1 in incoming {
         for l2 in &outgoing {
-            result.push(make_vehicle_turn(
-                lanes,
-                i.id,
-                l1,
-                *l2,
-                TurnType::from_angles(
-                    lanes[l1.0].last_line().angle(),
-                    lanes[l2.0].first_line().angle(),
-                ),
-            ));
+            if i.is_deadend() {
+                result.push(make_turnaround(lanes, i.id, l1, *l2));
+            } else {
+                // Borders have no turning circle, so squeeze the U-turn in at the map edge
+                result.push(make_vehicle_turn(
+                    lanes,
+                    i.id,
+                    l1,
+                    *l2,
+                    TurnType::from_angles(
+                        lanes[l1.0].last_line().angle(),
+                        lanes[l2.0].first_line().angle(),
+                    ),
+                ));
+            }
         }
     }
 
     Warn::ok(result)
 }
 
+// Drive into the turning circle at the end of the road, swing around, and come back out. Without
+// this, the U-turn is squeezed into the gap between the two lanes.
+fn make_turnaround(lanes: &Vec<Lane>, i: IntersectionID, l1: LaneID, l2: LaneID) -> Option<Turn> {
+    let src = &lanes[l1.0];
+    let dst = &lanes[l2.0];
+    if src.last_pt() == dst.first_pt() {
+        return None;
+    }
+
+    let forwards = src.last_line().angle();
+    let pt1 = src.last_pt().project_away(TURNAROUND_REACH, forwards);
+    let pt2 = dst.first_pt().project_away(TURNAROUND_REACH, forwards);
+    let mut arc = Arc::half_circle(pt1, pt2, forwards)
+        .to_polyline(Distance::meters(1.0))?
+        .into_points();
+    // Make sure the endpoints exactly match, despite rounding
+    arc[0] = pt1;
+    *arc.last_mut().unwrap() = pt2;
+
+    let mut pts = vec![src.last_pt()];
+    pts.extend(arc);
+    pts.push(dst.first_pt());
+
+    Some(Turn {
+        id: turn_id(i, l1, l2),
+        turn_type: TurnType::from_angles(forwards, dst.first_line().angle()),
+        other_crosswalk_ids: BTreeSet::new(),
+        geom: PolyLine::new(pts),
+    })
+}
+
 fn make_walking_turns(
     driving_side: DrivingSide,
     i: &Intersection,
@@ -637,6 +709,10 @@ fn get_sidewalk<'a>(lanes: &'a Vec<Lane>, children: &Vec<(LaneID, LaneType)>) ->
 }
 
 fn filter_vehicle_lanes(lanes: &Vec<(LaneID, LaneType)>, preferred: LaneType) -> Vec<LaneID> {
+    // Trains stay on their tracks
+    if preferred == LaneType::LightRail {
+        return filter_lanes(lanes, preferred);
+    }
     let preferred = filter_lanes(lanes, preferred);
     if !preferred.is_empty() {
         return preferred;
@@ -667,6 +743,21 @@ fn make_vehicle_turn(
 
     let geom = if turn_type == TurnType::Straight {
         PolyLine::new(vec![src.last_pt(), dst.first_pt()])
+    } else if src.parent == dst.parent {
+        // A U-turn. Swing around in a half circle, continuing the way the source lane points.
+        match Arc::half_circle(src.last_pt(), dst.first_pt(), src.last_line().angle())
+            .to_polyline(Distance::meters(1.0))
+        {
+            Some(arc) => {
+                let mut pts = arc.into_points();
+                // Make sure the endpoints exactly match the lanes, despite rounding
+                pts[0] = src.last_pt();
+                *pts.last_mut().unwrap() = dst.first_pt();
+                PolyLine::new(pts)
+            }
+            // The lanes are too close together to swing around
+            None => PolyLine::new(vec![src.last_pt(), dst.first_pt()]),
+        }
     } else {
         // The control points are straight out/in from the source/destination lanes, so
         // that the car exits and enters at the same angle as the road.
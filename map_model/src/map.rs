@@ -1,16 +1,19 @@
-use crate::pathfind::Pathfinder;
+use crate::pathfind::{should_use_park_and_ride, walkshed, Pathfinder, WalkingProfile};
 use crate::raw::{DrivingSide, OriginalIntersection, OriginalRoad, RawMap};
 use crate::{
-    connectivity, make, osm, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop,
-    BusStopID, ControlStopSign, ControlTrafficSignal, EditCmd, EditEffects, EditIntersection,
-    Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, MapEdits, ParkingLot,
-    ParkingLotID, Path, PathConstraints, PathRequest, Position, Road, RoadID, Turn, TurnGroupID,
+    connectivity, make, osm, Area, AreaID, BikeRack, BikeRackID, Building, BuildingID, BusRoute,
+    BusRouteID, BusStop, BusStopID, ControlStopSign, ControlTrafficSignal, Crosswalk, CrosswalkID,
+    CrosswalkType, EditCmd, EditEffects, EditIntersection, Intersection, IntersectionID,
+    IntersectionType, Lane, LaneID, LaneType, MapEdits, ParkingLot, ParkingLotID, Path,
+    PathConstraints, PathRequest, Position, Road, RoadID, SignalCorridor, Turn, TurnGroupID,
     TurnID, TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer, Warn};
-use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Speed};
+use geom::{
+    Angle, Bounds, Distance, Duration, GPSBounds, Line, PolyLine, Polygon, Pt2D, Ring, Speed,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 #[derive(Serialize, Deserialize)]
 pub struct Map {
@@ -31,6 +34,7 @@ pub struct Map {
     bus_routes: Vec<BusRoute>,
     areas: Vec<Area>,
     parking_lots: Vec<ParkingLot>,
+    bike_racks: Vec<BikeRack>,
     boundary_polygon: Polygon,
 
     // Note that border nodes belong in neither!
@@ -47,14 +51,40 @@ pub struct Map {
 
     city_name: String,
     name: String,
+    // OSM node IDs of intersections that were merged away while importing, mapped to the one they
+    // became. Edits and proposals made before a merge still refer to the old IDs.
+    merged_intersections: BTreeMap<i64, i64>,
     #[serde(skip_serializing, skip_deserializing)]
     edits: MapEdits,
 }
 
+// Bump this whenever the serialized form of Map changes, so old files are detected instead of
+// failing to deserialize with a cryptic error.
+// 2: GPSBounds stores the projection
+// 3: Compact fixed-point Pt2D and u32 polygon indices
+// 4: Buildings store their number of levels
+// 5: Roads store the type of crosswalk at each end
+// 6: Stop signs distinguish yield signs from stop signs
+// 7: Intersections store if right turns on red are allowed
+// 8: Intersections store if U-turns are allowed
+// 9: Roads store speed limits that vary by time of day
+// 10: Roads store which vehicles can't pass through
+// 11: Roads store modal filters
+// 12: Light rail tracks, train routes, and train stops
+// 13: Park-and-ride parking lots
+// 14: Bike racks
+// 15: Building land use
+// 16: Intersections merged while importing
+// 17: Roads store which vehicles are banned
+pub const MAP_FORMAT_VERSION: u32 = 17;
+
+// Past this, cyclists would rather lock up at a pole by the door than walk from a rack
+const MAX_WALK_FROM_BIKE_RACK: Distance = Distance::const_meters(150.0);
+
 impl Map {
     pub fn new(path: String, timer: &mut Timer) -> Map {
         if path.starts_with(&abstutil::path_all_maps()) {
-            match abstutil::maybe_read_binary(path.clone(), timer) {
+            match Map::maybe_load(path.clone(), timer) {
                 Ok(map) => {
                     let map: Map = map;
 
@@ -88,6 +118,10 @@ impl Map {
                             "- parking lots: {} bytes",
                             prettyprint_usize(serialized_size_bytes(&map.parking_lots))
                         );
+                        println!(
+                            "- bike racks: {} bytes",
+                            prettyprint_usize(serialized_size_bytes(&map.bike_racks))
+                        );
                         println!(
                             "- pathfinder: {} bytes",
                             prettyprint_usize(serialized_size_bytes(&map.pathfinder))
@@ -125,6 +159,7 @@ impl Map {
             bus_routes: Vec::new(),
             areas: Vec::new(),
             parking_lots: Vec::new(),
+            bike_racks: Vec::new(),
             boundary_polygon: Polygon::new(&vec![
                 Pt2D::new(0.0, 0.0),
                 Pt2D::new(1.0, 0.0),
@@ -139,6 +174,7 @@ impl Map {
             pathfinder_dirty: false,
             city_name: "blank city".to_string(),
             name: "blank".to_string(),
+            merged_intersections: BTreeMap::new(),
             edits: MapEdits::new(),
         }
     }
@@ -146,6 +182,7 @@ impl Map {
     pub fn create_from_raw(mut raw: RawMap, build_ch: bool, timer: &mut Timer) -> Map {
         // Better to defer this and see RawMaps with more debug info in map_editor
         make::remove_disconnected::remove_disconnected_roads(&mut raw, timer);
+        let merged_intersections = make::merge_intersections::merge_short_roads(&mut raw, timer);
 
         timer.start("raw_map to InitialMap");
         let gps_bounds = raw.gps_bounds.clone();
@@ -155,6 +192,7 @@ impl Map {
 
         timer.start("InitialMap to half of Map");
         let mut m = make_half_map(&raw, initial_map, gps_bounds, bounds, timer);
+        m.merged_intersections = merged_intersections;
         timer.stop("InitialMap to half of Map");
 
         timer.start("finalize Map");
@@ -282,6 +320,10 @@ impl Map {
         &self.parking_lots
     }
 
+    pub fn all_bike_racks(&self) -> &Vec<BikeRack> {
+        &self.bike_racks
+    }
+
     pub fn maybe_get_r(&self, id: RoadID) -> Option<&Road> {
         self.roads.get(id.0)
     }
@@ -306,6 +348,10 @@ impl Map {
         self.parking_lots.get(id.0)
     }
 
+    pub fn maybe_get_bike_rack(&self, id: BikeRackID) -> Option<&BikeRack> {
+        self.bike_racks.get(id.0)
+    }
+
     pub fn maybe_get_a(&self, id: AreaID) -> Option<&Area> {
         self.areas.get(id.0)
     }
@@ -355,6 +401,10 @@ impl Map {
         &self.parking_lots[id.0]
     }
 
+    pub fn get_bike_rack(&self, id: BikeRackID) -> &BikeRack {
+        &self.bike_racks[id.0]
+    }
+
     pub fn get_stop_sign(&self, id: IntersectionID) -> &ControlStopSign {
         &self.stop_signs[&id]
     }
@@ -373,6 +423,56 @@ impl Map {
             .collect()
     }
 
+    pub fn get_crosswalks(&self, i: IntersectionID) -> Vec<Crosswalk> {
+        let mut turns_per_crosswalk: BTreeMap<CrosswalkID, Vec<TurnID>> = BTreeMap::new();
+        for t in self.get_turns_in_intersection(i) {
+            if let Some(id) = t.crosswalk_id(self) {
+                turns_per_crosswalk
+                    .entry(id)
+                    .or_insert_with(Vec::new)
+                    .push(t.id);
+            }
+        }
+        turns_per_crosswalk
+            .into_iter()
+            .map(|(id, turns)| self.make_crosswalk(id, turns))
+            .collect()
+    }
+
+    // Only builds the one crosswalk, instead of everything in the intersection
+    pub fn maybe_get_crosswalk(&self, id: CrosswalkID) -> Option<Crosswalk> {
+        let turns: Vec<TurnID> = self
+            .maybe_get_i(id.i)?
+            .turns
+            .iter()
+            .filter(|t| self.get_t(**t).crosswalk_id(self) == Some(id))
+            .cloned()
+            .collect();
+        if turns.is_empty() {
+            return None;
+        }
+        Some(self.make_crosswalk(id, turns))
+    }
+
+    fn make_crosswalk(&self, id: CrosswalkID, mut turns: Vec<TurnID>) -> Crosswalk {
+        turns.sort();
+        let turn = self.get_t(turns[0]);
+        let width = self.get_l(turn.id.src).width;
+        // The middle line in the crosswalk geometry is the main crossing line.
+        let pts = turn.geom.points();
+        let polygon = if pts.len() >= 3 && pts[1] != pts[2] {
+            Line::new(pts[1], pts[2]).make_polygons(width)
+        } else {
+            turn.geom.make_polygons(width)
+        };
+        Crosswalk {
+            id,
+            turns,
+            crosswalk_type: self.get_r(id.r).crosswalk_type(id.i),
+            polygon,
+        }
+    }
+
     // The turns may belong to two different intersections!
     pub fn get_turns_from_lane(&self, l: LaneID) -> Vec<&Turn> {
         let lane = self.get_l(l);
@@ -444,7 +544,7 @@ impl Map {
         let mut turns: Vec<&Turn> = self
             .get_next_turns_and_lanes(from, self.get_l(from).dst_i)
             .into_iter()
-            .filter(|(_, l)| constraints.can_use(l, self))
+            .filter(|(t, l)| constraints.can_use(l, self) && constraints.can_use_turn(t, self))
             .map(|(t, _)| t)
             .collect();
         // Sidewalks are bidirectional
@@ -492,6 +592,29 @@ impl Map {
         &self.name
     }
 
+    // Roughly how many bytes each part of the map uses, measured by serialized size. That misses
+    // allocator overhead and spare capacity, but it's enough to see what dominates on a big map.
+    pub fn memory_usage(&self) -> Vec<(&'static str, usize)> {
+        use abstutil::serialized_size_bytes as size;
+        vec![
+            ("roads", size(&self.roads)),
+            ("lanes", size(&self.lanes)),
+            ("intersections", size(&self.intersections)),
+            ("turns", size(&self.turns)),
+            ("buildings", size(&self.buildings)),
+            ("transit", size(&self.bus_stops) + size(&self.bus_routes)),
+            (
+                "areas, parking lots, bike racks",
+                size(&self.areas) + size(&self.parking_lots) + size(&self.bike_racks),
+            ),
+            (
+                "traffic control",
+                size(&self.stop_signs) + size(&self.traffic_signals),
+            ),
+            ("pathfinding", size(&self.pathfinder)),
+        ]
+    }
+
     pub fn all_bus_stops(&self) -> &BTreeMap<BusStopID, BusStop> {
         &self.bus_stops
     }
@@ -555,7 +678,12 @@ impl Map {
         assert_eq!(self.edits.edits_name, "untitled edits");
         assert!(self.edits.commands.is_empty());
         assert!(!self.pathfinder_dirty);
-        abstutil::write_binary(abstutil::path_map(&self.name), self);
+        abstutil::write_versioned_binary(abstutil::path_map(&self.name), MAP_FORMAT_VERSION, self);
+    }
+
+    // Just reads a prebuilt map, without any fallback to building it from a raw map.
+    pub fn maybe_load(path: String, timer: &mut Timer) -> Result<Map, std::io::Error> {
+        abstutil::maybe_read_versioned_binary(path, MAP_FORMAT_VERSION, timer)
     }
 
     pub fn find_closest_lane(&self, from: LaneID, types: Vec<LaneType>) -> Result<LaneID, Error> {
@@ -680,6 +808,43 @@ impl Map {
             .should_use_transit(self, start, end)
     }
 
+    pub fn should_use_park_and_ride(
+        &self,
+        start: BuildingID,
+        end: Position,
+    ) -> Option<(ParkingLotID, BusStopID, BusStopID, BusRouteID)> {
+        should_use_park_and_ride(self, start, end)
+    }
+
+    // Every building reachable from the start within the time limit, and how long it takes
+    pub fn walkshed(
+        &self,
+        start: Position,
+        profile: WalkingProfile,
+        limit: Duration,
+    ) -> BTreeMap<BuildingID, Duration> {
+        walkshed(self, start, profile, limit)
+    }
+
+    // Racks close enough to walk from to the building, closest first
+    pub fn find_bike_racks_near_building(&self, b: BuildingID) -> Vec<BikeRackID> {
+        let pt = self.get_b(b).front_path.sidewalk.pt(self);
+        let mut racks: Vec<(Distance, BikeRackID)> = self
+            .bike_racks
+            .iter()
+            .filter_map(|rack| {
+                let dist = rack.sidewalk_pos.pt(self).dist_to(pt);
+                if dist <= MAX_WALK_FROM_BIKE_RACK {
+                    Some((dist, rack.id))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        racks.sort_by_key(|(dist, _)| *dist);
+        racks.into_iter().map(|(_, id)| id).collect()
+    }
+
     // None for SharedSidewalkCorners
     pub fn get_turn_group(&self, t: TurnID) -> Option<TurnGroupID> {
         if let Some(ref ts) = self.maybe_get_traffic_signal(t.parent) {
@@ -701,10 +866,14 @@ impl Map {
         osm_way_id: i64,
         osm_node_ids: (i64, i64),
     ) -> Result<RoadID, String> {
+        let (i1, i2) = (
+            self.resolve_merged_i(osm_node_ids.0),
+            self.resolve_merged_i(osm_node_ids.1),
+        );
         for r in self.all_roads() {
             if r.orig_id.osm_way_id == osm_way_id
-                && r.orig_id.i1.osm_node_id == osm_node_ids.0
-                && r.orig_id.i2.osm_node_id == osm_node_ids.1
+                && r.orig_id.i1.osm_node_id == i1
+                && r.orig_id.i2.osm_node_id == i2
             {
                 return Ok(r.id);
             }
@@ -716,14 +885,22 @@ impl Map {
     }
 
     pub fn find_i_by_osm_id(&self, osm_node_id: i64) -> Result<IntersectionID, String> {
+        let resolved = self.resolve_merged_i(osm_node_id);
         for i in self.all_intersections() {
-            if i.orig_id.osm_node_id == osm_node_id {
+            if i.orig_id.osm_node_id == resolved {
                 return Ok(i.id);
             }
         }
         Err(format!("Can't find osm_node_id {}", osm_node_id))
     }
 
+    fn resolve_merged_i(&self, osm_node_id: i64) -> i64 {
+        self.merged_intersections
+            .get(&osm_node_id)
+            .cloned()
+            .unwrap_or(osm_node_id)
+    }
+
     pub fn find_b_by_osm_id(&self, osm_way_id: i64) -> Option<BuildingID> {
         for b in self.all_buildings() {
             if b.osm_way_id == osm_way_id {
@@ -753,6 +930,48 @@ impl Map {
         self.driving_side
     }
 
+    // After re-importing newer OSM data, keep the old ID for every building whose geometry hasn't
+    // changed. New and modified buildings fill in the gaps. Returns how many kept their ID.
+    pub fn preserve_building_ids(&mut self, old: &Map) -> usize {
+        let old_by_osm: HashMap<i64, &Building> = old
+            .all_buildings()
+            .iter()
+            .map(|b| (b.osm_way_id, b))
+            .collect();
+        let mut slots: Vec<Option<Building>> = self.buildings.iter().map(|_| None).collect();
+        let mut leftovers = Vec::new();
+        let mut kept = 0;
+        for b in self.buildings.drain(..) {
+            if let Some(old_b) = old_by_osm.get(&b.osm_way_id) {
+                if old_b.id.0 < slots.len()
+                    && slots[old_b.id.0].is_none()
+                    && old_b.polygon.points().len() == b.polygon.points().len()
+                    && same_place(old_b.polygon.center(), old, b.polygon.center(), self)
+                {
+                    kept += 1;
+                    slots[old_b.id.0] = Some(b);
+                    continue;
+                }
+            }
+            leftovers.push(b);
+        }
+        leftovers.reverse();
+
+        let mut new_ids: HashMap<BuildingID, BuildingID> = HashMap::new();
+        for (idx, slot) in slots.into_iter().enumerate() {
+            let mut b = slot.unwrap_or_else(|| leftovers.pop().unwrap());
+            new_ids.insert(b.id, BuildingID(idx));
+            b.id = BuildingID(idx);
+            self.buildings.push(b);
+        }
+        for l in &mut self.lanes {
+            for b in &mut l.building_paths {
+                *b = new_ids[b];
+            }
+        }
+        kept
+    }
+
     // TODO Sort of a temporary hack
     pub fn hack_override_offstreet_spots(&mut self, spots_per_bldg: usize) {
         for b in &mut self.buildings {
@@ -778,6 +997,11 @@ impl Map {
         &self.edits
     }
 
+    // Corridors aren't part of the command stack, so there's nothing to apply.
+    pub fn set_signal_corridors(&mut self, corridors: Vec<SignalCorridor>) {
+        self.edits.signal_corridors = corridors;
+    }
+
     // Panics on borders
     pub fn get_i_edit(&self, i: IntersectionID) -> EditIntersection {
         match self.get_i(i).intersection_type {
@@ -799,6 +1023,28 @@ impl Map {
         self.edits.save(self);
     }
 
+    // Like apply_edits, but if the new edits disconnect lanes that cars or bikes could reach
+    // before, the old edits are restored and those lanes are returned. Changing lane types or
+    // directions can easily do this. Doesn't update pathfinding yet.
+    pub fn try_apply_edits(
+        &mut self,
+        new_edits: MapEdits,
+        timer: &mut Timer,
+    ) -> Result<(), BTreeSet<LaneID>> {
+        let orig_edits = self.edits.clone();
+        let disconnected_before = connectivity::disconnected_vehicle_lanes(self);
+        self.apply_edits(new_edits, timer);
+        let newly_disconnected: BTreeSet<LaneID> = connectivity::disconnected_vehicle_lanes(self)
+            .difference(&disconnected_before)
+            .cloned()
+            .collect();
+        if newly_disconnected.is_empty() {
+            return Ok(());
+        }
+        self.apply_edits(orig_edits, timer);
+        Err(newly_disconnected)
+    }
+
     // new_edits assumed to be valid. Returns roads changed, turns deleted, turns added,
     // intersections modified. Doesn't update pathfinding yet.
     pub fn apply_edits(
@@ -847,7 +1093,10 @@ impl Map {
                 // Must exist, because we aren't allowed to orphan a bus stop.
                 let driving_lane = self
                     .get_r(*id)
-                    .find_closest_lane(sidewalk_pos.lane(), vec![LaneType::Driving, LaneType::Bus])
+                    .find_closest_lane(
+                        sidewalk_pos.lane(),
+                        BusStop::lane_types(self.get_bs(s).is_train_stop),
+                    )
                     .unwrap();
                 let driving_pos = sidewalk_pos.equiv_pos(driving_lane, Distance::ZERO, self);
                 self.bus_stops.get_mut(&s).unwrap().driving_pos = driving_pos;
@@ -921,6 +1170,7 @@ fn make_half_map(
         pathfinder_dirty: false,
         city_name: raw.city_name.clone(),
         name: raw.name.clone(),
+        merged_intersections: BTreeMap::new(),
         edits: MapEdits::new(),
     };
 
@@ -944,6 +1194,9 @@ fn make_half_map(
             // Might change later
             intersection_type: i.intersection_type,
             orig_id: i.id,
+            right_turn_on_red: true,
+            // Only where the road is wide enough to turn around
+            u_turns: true,
             incoming_lanes: Vec::new(),
             outgoing_lanes: Vec::new(),
             roads: i.roads.iter().map(|id| road_id_mapping[id]).collect(),
@@ -1001,13 +1254,20 @@ fn make_half_map(
             src_i: i1,
             dst_i: i2,
             speed_limit: Speed::ZERO,
-            zorder: if let Some(layer) = raw.roads[&r.id].osm_tags.get("layer") {
-                layer.parse::<isize>().unwrap()
-            } else {
-                0
-            },
+            speed_limit_schedule: Vec::new(),
+            no_through_traffic: BTreeSet::new(),
+            banned_vehicles: BTreeSet::new(),
+            modal_filter: None,
+            zorder: osm::layer(&raw.roads[&r.id].osm_tags),
+            crosswalk_forwards: CrosswalkType::Marked,
+            crosswalk_backwards: CrosswalkType::Marked,
         };
         road.speed_limit = road.speed_limit_from_osm();
+        road.speed_limit_schedule = road.speed_limit_schedule_from_osm();
+        road.no_through_traffic = road.no_through_traffic_from_osm();
+        road.banned_vehicles = road.banned_vehicles_from_osm();
+        road.crosswalk_forwards = road.crosswalk_type_from_osm(i2);
+        road.crosswalk_backwards = road.crosswalk_type_from_osm(i1);
 
         for lane in &r.lane_specs {
             let id = LaneID(map.lanes.len());
@@ -1108,7 +1368,8 @@ fn make_half_map(
     }
     timer.stop("find parking blackholes");
 
-    map.buildings = make::buildings::make_all_buildings(&raw.buildings, &map, timer);
+    map.buildings =
+        make::buildings::make_all_buildings(&raw.buildings, &raw.parking_aisles, &map, timer);
     for b in &map.buildings {
         let lane = b.sidewalk();
 
@@ -1121,6 +1382,7 @@ fn make_half_map(
 
     map.parking_lots =
         make::buildings::make_all_parking_lots(&raw.parking_lots, &raw.parking_aisles, &map, timer);
+    map.bike_racks = make::buildings::make_all_bike_racks(&raw.bike_racks, &map, timer);
 
     for (idx, a) in raw.areas.iter().enumerate() {
         map.areas.push(Area {
@@ -1236,6 +1498,24 @@ impl EditCmd {
                 recalculate_turns(dst_i, map, effects, timer);
                 true
             }
+            EditCmd::ChangeLaneWidth { id, new, .. } => {
+                if map.lanes[id.0].width == *new {
+                    return false;
+                }
+                change_lane_width(map, *id, *new, timer);
+
+                let r = map.lanes[id.0].parent;
+                effects.changed_roads.insert(r);
+                // The turns on either end need new geometry
+                let (src_i, dst_i) = (map.roads[r.0].src_i, map.roads[r.0].dst_i);
+                for i in &[src_i, dst_i] {
+                    effects.changed_intersections.insert(*i);
+                    if !map.intersections[i.0].is_closed() {
+                        recalculate_turns(*i, map, effects, timer);
+                    }
+                }
+                true
+            }
             EditCmd::ChangeSpeedLimit { id, new, .. } => {
                 if map.roads[id.0].speed_limit != *new {
                     map.roads[id.0].speed_limit = *new;
@@ -1245,6 +1525,73 @@ impl EditCmd {
                     false
                 }
             }
+            EditCmd::ChangeSpeedLimitSchedule { id, new, .. } => {
+                if map.roads[id.0].speed_limit_schedule == *new {
+                    return false;
+                }
+                map.roads[id.0].speed_limit_schedule = new.clone();
+                effects.changed_roads.insert(*id);
+                true
+            }
+            EditCmd::ChangeNoThroughTraffic { id, new, .. } => {
+                if map.roads[id.0].no_through_traffic == *new {
+                    return false;
+                }
+                map.roads[id.0].no_through_traffic = new.clone();
+                effects.changed_roads.insert(*id);
+                true
+            }
+            EditCmd::ChangeBannedVehicles { id, new, .. } => {
+                if map.roads[id.0].banned_vehicles == *new {
+                    return false;
+                }
+                map.roads[id.0].banned_vehicles = new.clone();
+                effects.changed_roads.insert(*id);
+                true
+            }
+            EditCmd::ChangeModalFilter { r, new, old } => {
+                if map.roads[r.0].modal_filter == *new {
+                    return false;
+                }
+                map.roads[r.0].modal_filter = *new;
+                effects.changed_roads.insert(*r);
+                // Cars can turn around at the filter, so the turns at both ends might change
+                for i in new.iter().chain(old.iter()) {
+                    effects.changed_intersections.insert(*i);
+                    if !map.intersections[i.0].is_closed() {
+                        recalculate_turns(*i, map, effects, timer);
+                    }
+                }
+                true
+            }
+            EditCmd::ChangeCrosswalk { id, new, .. } => {
+                let r = &mut map.roads[id.r.0];
+                if r.crosswalk_type(id.i) == *new {
+                    return false;
+                }
+                r.set_crosswalk_type(id.i, *new);
+                effects.changed_intersections.insert(id.i);
+                true
+            }
+            EditCmd::ChangeRightTurnOnRed { i, allowed } => {
+                if map.intersections[i.0].right_turn_on_red == *allowed {
+                    return false;
+                }
+                map.intersections[i.0].right_turn_on_red = *allowed;
+                effects.changed_intersections.insert(*i);
+                true
+            }
+            EditCmd::ChangeUTurns { i, allowed } => {
+                if map.intersections[i.0].u_turns == *allowed {
+                    return false;
+                }
+                map.intersections[i.0].u_turns = *allowed;
+                effects.changed_intersections.insert(*i);
+                if !map.intersections[i.0].is_closed() {
+                    recalculate_turns(*i, map, effects, timer);
+                }
+                true
+            }
             EditCmd::ChangeIntersection {
                 i,
                 ref new,
@@ -1276,6 +1623,88 @@ impl EditCmd {
                 }
                 true
             }
+            EditCmd::AddBusStop {
+                id,
+                dist_along,
+                routes,
+            } => {
+                if map.bus_stops.contains_key(id) {
+                    return false;
+                }
+                let sidewalk_pos = Position::new(id.sidewalk, *dist_along);
+                // MapEdits::add_bus_stop checks every route has the same type
+                let is_train_stop = map.get_br(routes[0].0).route_type == PathConstraints::Train;
+                // apply_edits fixes this up if the lanes change later
+                let driving_lane =
+                    match map.find_closest_lane(id.sidewalk, BusStop::lane_types(is_train_stop)) {
+                        Ok(l) => l,
+                        Err(_) => {
+                            // MapEdits::add_bus_stop checks this, but the lanes may have changed
+                            // since the edit was made
+                            timer.error(format!(
+                                "Can't add {}; nothing can pull up to {}",
+                                id, id.sidewalk
+                            ));
+                            return false;
+                        }
+                    };
+                let driving_pos = sidewalk_pos.equiv_pos(driving_lane, Distance::ZERO, map);
+                map.bus_stops.insert(
+                    *id,
+                    BusStop {
+                        id: *id,
+                        sidewalk_pos,
+                        driving_pos,
+                        is_train_stop,
+                    },
+                );
+                map.lanes[id.sidewalk.0].bus_stops.push(*id);
+                // Stops listed twice in one route are in order, so inserting the first doesn't
+                // disturb the second
+                for (route, idx) in routes {
+                    let stops = &mut map.bus_routes[route.0].stops;
+                    let idx = (*idx).min(stops.len());
+                    stops.insert(idx, *id);
+                }
+                effects
+                    .changed_roads
+                    .insert(map.lanes[id.sidewalk.0].parent);
+                true
+            }
+            EditCmd::RemoveBusStop { id, .. } => {
+                if map.bus_stops.remove(id).is_none() {
+                    return false;
+                }
+                map.lanes[id.sidewalk.0].bus_stops.retain(|s| s != id);
+                for route in &mut map.bus_routes {
+                    route.stops.retain(|s| s != id);
+                }
+                effects
+                    .changed_roads
+                    .insert(map.lanes[id.sidewalk.0].parent);
+                true
+            }
+            EditCmd::MoveBusStop { id, new, .. } => {
+                let stop = match map.bus_stops.get(id) {
+                    Some(stop) => stop,
+                    None => {
+                        return false;
+                    }
+                };
+                if stop.sidewalk_pos.dist_along() == *new {
+                    return false;
+                }
+                let sidewalk_pos = Position::new(id.sidewalk, *new);
+                let driving_pos =
+                    sidewalk_pos.equiv_pos(stop.driving_pos.lane(), Distance::ZERO, map);
+                let stop = map.bus_stops.get_mut(id).unwrap();
+                stop.sidewalk_pos = sidewalk_pos;
+                stop.driving_pos = driving_pos;
+                effects
+                    .changed_roads
+                    .insert(map.lanes[id.sidewalk.0].parent);
+                true
+            }
         }
     }
 
@@ -1301,6 +1730,12 @@ impl EditCmd {
                 }
                 .apply(effects, map, timer)
             }
+            EditCmd::ChangeLaneWidth { id, new, old } => EditCmd::ChangeLaneWidth {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
             EditCmd::ChangeSpeedLimit { id, old, .. } => {
                 if map.roads[id.0].speed_limit != *old {
                     map.roads[id.0].speed_limit = *old;
@@ -1310,6 +1745,48 @@ impl EditCmd {
                     false
                 }
             }
+            EditCmd::ChangeSpeedLimitSchedule { id, new, old } => {
+                EditCmd::ChangeSpeedLimitSchedule {
+                    id: *id,
+                    new: old.clone(),
+                    old: new.clone(),
+                }
+                .apply(effects, map, timer)
+            }
+            EditCmd::ChangeNoThroughTraffic { id, new, old } => EditCmd::ChangeNoThroughTraffic {
+                id: *id,
+                new: old.clone(),
+                old: new.clone(),
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeBannedVehicles { id, new, old } => EditCmd::ChangeBannedVehicles {
+                id: *id,
+                new: old.clone(),
+                old: new.clone(),
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeModalFilter { r, new, old } => EditCmd::ChangeModalFilter {
+                r: *r,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeCrosswalk { id, new, old } => EditCmd::ChangeCrosswalk {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeRightTurnOnRed { i, allowed } => EditCmd::ChangeRightTurnOnRed {
+                i: *i,
+                allowed: !allowed,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeUTurns { i, allowed } => EditCmd::ChangeUTurns {
+                i: *i,
+                allowed: !allowed,
+            }
+            .apply(effects, map, timer),
             EditCmd::ChangeIntersection {
                 i,
                 ref old,
@@ -1320,10 +1797,228 @@ impl EditCmd {
                 new: old.clone(),
             }
             .apply(effects, map, timer),
+            EditCmd::AddBusStop {
+                id,
+                dist_along,
+                routes,
+            } => EditCmd::RemoveBusStop {
+                id: *id,
+                dist_along: *dist_along,
+                routes: routes.clone(),
+            }
+            .apply(effects, map, timer),
+            EditCmd::RemoveBusStop {
+                id,
+                dist_along,
+                routes,
+            } => EditCmd::AddBusStop {
+                id: *id,
+                dist_along: *dist_along,
+                routes: routes.clone(),
+            }
+            .apply(effects, map, timer),
+            EditCmd::MoveBusStop { id, new, old } => EditCmd::MoveBusStop {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
         }
     }
 }
 
+// Lanes keep their order across the road, and the middle of the road stays in place, so the other
+// lanes slide over to make room. Building front paths and bus stops are just distances along their
+// lane, so they don't need to change, but their lines to the sidewalk don't get redrawn.
+fn change_lane_width(map: &mut Map, l: LaneID, width: Distance, timer: &mut Timer) {
+    let road = &map.roads[map.lanes[l.0].parent.0];
+    let r = road.id;
+    // From left to right, looking along the road's forward direction
+    let lanes: Vec<(LaneID, bool)> = road
+        .children_backwards
+        .iter()
+        .rev()
+        .map(|(id, _)| (*id, false))
+        .chain(road.children_forwards.iter().map(|(id, _)| (*id, true)))
+        .collect();
+    let old_total = road.width_fwd(map) + road.width_back(map);
+    let new_total = old_total - map.lanes[l.0].width + width;
+
+    let old_lengths: BTreeMap<LaneID, Distance> = lanes
+        .iter()
+        .map(|(id, _)| (*id, map.lanes[id.0].length()))
+        .collect();
+
+    let (leftmost, fwd) = lanes[0];
+    let pts = if fwd {
+        map.lanes[leftmost.0].lane_center_pts.clone()
+    } else {
+        map.lanes[leftmost.0].lane_center_pts.reversed()
+    };
+    let old_edge = map
+        .left_shift(pts, map.lanes[leftmost.0].width / 2.0)
+        .with_context(timer, format!("left edge of {}", r));
+    let left_edge = if new_total < old_total {
+        map.right_shift(old_edge, (old_total - new_total) / 2.0)
+    } else {
+        map.left_shift(old_edge, (new_total - old_total) / 2.0)
+    }
+    .with_context(timer, format!("left edge of {}", r));
+
+    map.lanes[l.0].width = width;
+    let mut dist_from_edge = Distance::ZERO;
+    for (id, fwd) in lanes {
+        let w = map.lanes[id.0].width;
+        let pts = map
+            .right_shift(left_edge.clone(), dist_from_edge + w / 2.0)
+            .with_context(timer, format!("shift for {}", id));
+        map.lanes[id.0].lane_center_pts = if fwd { pts } else { pts.reversed() };
+        dist_from_edge += w;
+    }
+
+    // The outermost lanes may now stick out past the intersections, or leave a gap
+    let right_edge = map
+        .right_shift(left_edge.clone(), new_total)
+        .with_context(timer, format!("right edge of {}", r));
+    let (src_i, dst_i) = (map.roads[r.0].src_i, map.roads[r.0].dst_i);
+    let src_end = (
+        left_edge.first_pt(),
+        right_edge.first_pt(),
+        left_edge.first_line().angle().opposite(),
+    );
+    let dst_end = (
+        left_edge.last_pt(),
+        right_edge.last_pt(),
+        left_edge.last_line().angle(),
+    );
+    for (i, (pt1, pt2, into_intersection)) in vec![(src_i, src_end), (dst_i, dst_end)] {
+        // Cover the new end of the road, reaching a little into the intersection
+        let depth = Distance::meters(1.0);
+        let quad = Ring::maybe_new(vec![
+            pt1,
+            pt2,
+            pt2.project_away(depth, into_intersection),
+            pt1.project_away(depth, into_intersection),
+            pt1,
+        ])
+        .map(|ring| Polygon::new(ring.points()));
+        if let Some(quad) = quad {
+            let mut merged = map.intersections[i.0].polygon.boolean_union(&quad);
+            if merged.len() == 1 {
+                map.intersections[i.0].polygon = merged.pop().unwrap();
+            }
+        }
+    }
+
+    reposition_along_lanes(map, &old_lengths);
+}
+
+// Shifting lanes sideways changes their length along curves. Keep everything positioned along the
+// changed lanes at the same fraction of the way along, and redraw the lines connecting to them.
+fn reposition_along_lanes(map: &mut Map, old_lengths: &BTreeMap<LaneID, Distance>) {
+    // Driveways and bike racks stay this far from the ends of the lane, if they were before
+    let buffer = Distance::meters(7.0);
+    let rescale = |map: &Map, pos: Position, keep_buffer: bool| -> Position {
+        let old_len = match old_lengths.get(&pos.lane()) {
+            Some(len) if *len > Distance::ZERO => *len,
+            _ => {
+                return pos;
+            }
+        };
+        let new_len = map.lanes[pos.lane().0].length();
+        let mut dist = pos.dist_along() * (new_len / old_len);
+        if keep_buffer && new_len > buffer * 2.0 {
+            dist = dist.max(buffer).min(new_len - buffer);
+        }
+        Position::new(pos.lane(), dist.max(Distance::ZERO).min(new_len))
+    };
+    // Connecting lines pass through the old points; move those
+    let redraw = |pts: &Vec<Pt2D>, moves: &Vec<(Pt2D, Pt2D)>| -> Vec<Pt2D> {
+        let pts = pts
+            .iter()
+            .map(|pt| {
+                moves
+                    .iter()
+                    .find(|(old, _)| old == pt)
+                    .map(|(_, new)| *new)
+                    .unwrap_or(*pt)
+            })
+            .collect();
+        Pt2D::approx_dedupe(pts, Distance::meters(0.1))
+    };
+
+    for idx in 0..map.buildings.len() {
+        let b = &map.buildings[idx];
+        let old_sidewalk = b.front_path.sidewalk;
+        let new_sidewalk = rescale(map, old_sidewalk, false);
+        let mut moves = vec![(old_sidewalk.pt(map), new_sidewalk.pt(map))];
+        let new_driving = b.parking.as_ref().map(|p| {
+            let pos = rescale(map, p.driving_pos, true);
+            moves.push((p.driving_pos.pt(map), pos.pt(map)));
+            pos
+        });
+        if moves.iter().all(|(old, new)| old == new) {
+            continue;
+        }
+
+        let b = &mut map.buildings[idx];
+        b.front_path.sidewalk = new_sidewalk;
+        if let Some(line) = Line::maybe_new(b.front_path.line.pt1(), moves[0].1) {
+            b.front_path.line = line;
+        }
+        if let Some(ref mut p) = b.parking {
+            p.driving_pos = new_driving.unwrap();
+            let pts = redraw(p.driveway_line.points(), &moves);
+            if pts.len() >= 2 {
+                p.driveway_line = PolyLine::unchecked_new(pts);
+            }
+        }
+    }
+
+    for idx in 0..map.parking_lots.len() {
+        let lot = &map.parking_lots[idx];
+        let sidewalk_pos = rescale(map, lot.sidewalk_pos, false);
+        let driving_pos = rescale(map, lot.driving_pos, true);
+        let moves = vec![
+            (lot.sidewalk_pos.pt(map), sidewalk_pos.pt(map)),
+            (lot.driving_pos.pt(map), driving_pos.pt(map)),
+        ];
+        if moves.iter().all(|(old, new)| old == new) {
+            continue;
+        }
+
+        let lot = &mut map.parking_lots[idx];
+        lot.sidewalk_pos = sidewalk_pos;
+        lot.driving_pos = driving_pos;
+        if let Some(line) = Line::maybe_new(lot.sidewalk_line.pt1(), moves[0].1) {
+            lot.sidewalk_line = line;
+        }
+        let pts = redraw(lot.driveway_line.points(), &moves);
+        if pts.len() >= 2 {
+            lot.driveway_line = PolyLine::unchecked_new(pts);
+        }
+    }
+
+    let stops: Vec<BusStopID> = map.bus_stops.keys().cloned().collect();
+    for id in stops {
+        let stop = &map.bus_stops[&id];
+        let sidewalk_pos = rescale(map, stop.sidewalk_pos, false);
+        let driving_pos = rescale(map, stop.driving_pos, false);
+        let stop = map.bus_stops.get_mut(&id).unwrap();
+        stop.sidewalk_pos = sidewalk_pos;
+        stop.driving_pos = driving_pos;
+    }
+
+    for idx in 0..map.bike_racks.len() {
+        let rack = &map.bike_racks[idx];
+        let sidewalk_pos = rescale(map, rack.sidewalk_pos, false);
+        let biking_pos = rescale(map, rack.biking_pos, true);
+        let rack = &mut map.bike_racks[idx];
+        rack.sidewalk_pos = sidewalk_pos;
+        rack.biking_pos = biking_pos;
+    }
+}
+
 // This clobbers previously set traffic signal overrides.
 // TODO Step 1: Detect and warn about that
 // TODO Step 2: Avoid when possible
@@ -1375,3 +2070,10 @@ fn recalculate_turns(
         IntersectionType::Border | IntersectionType::Construction => unreachable!(),
     }
 }
+
+// The GPS bounds of a map might change when it's re-imported, so compare in GPS space.
+fn same_place(pt1: Pt2D, map1: &Map, pt2: Pt2D, map2: &Map) -> bool {
+    pt1.forcibly_to_gps(map1.get_gps_bounds())
+        .gps_dist_meters(pt2.forcibly_to_gps(map2.get_gps_bounds()))
+        < Distance::meters(0.1)
+}
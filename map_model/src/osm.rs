@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 // These are common OSM keys. Keys used in just one or two places don't really need to be defined
 // here.
 
@@ -5,10 +7,15 @@
 pub const NAME: &str = "name";
 pub const HIGHWAY: &str = "highway";
 pub const MAXSPEED: &str = "maxspeed";
+pub const MAXSPEED_CONDITIONAL: &str = "maxspeed:conditional";
 pub const PARKING_RIGHT: &str = "parking:lane:right";
 pub const PARKING_LEFT: &str = "parking:lane:left";
 pub const PARKING_BOTH: &str = "parking:lane:both";
 pub const SIDEWALK: &str = "sidewalk";
+pub const LAYER: &str = "layer";
+pub const BRIDGE: &str = "bridge";
+pub const TUNNEL: &str = "tunnel";
+pub const RAILWAY: &str = "railway";
 
 // The rest of these are all inserted by A/B Street to plumb data between different stages of map
 // construction. They could be plumbed another way, but this is the most convenient.
@@ -22,6 +29,12 @@ pub const OSM_REL_ID: &str = "abst:osm_rel_id";
 // for interpreting turn restrictions.
 pub const ENDPT_FWD: &str = "abst:endpt_fwd";
 pub const ENDPT_BACK: &str = "abst:endpt_back";
+// The crosswalks over a road at one end have no paint.
+pub const UNMARKED_CROSSING_FWD: &str = "abst:unmarked_crossing_fwd";
+pub const UNMARKED_CROSSING_BACK: &str = "abst:unmarked_crossing_back";
+// The crosswalks over a road at one end have a raised kerb, without a curb ramp.
+pub const NO_CURB_RAMP_FWD: &str = "abst:no_curb_ramp_fwd";
+pub const NO_CURB_RAMP_BACK: &str = "abst:no_curb_ramp_back";
 
 // Synthetic roads have (some of) these.
 pub const SYNTHETIC: &str = "abst:synthetic";
@@ -30,3 +43,34 @@ pub const SYNTHETIC_LANES: &str = "abst:synthetic_lanes";
 // Any roads might have these.
 pub const INFERRED_PARKING: &str = "abst:parking_inferred";
 pub const INFERRED_SIDEWALKS: &str = "abst:sidewalks_inferred";
+
+// Streetcar or light rail tracks, either mapped as their own way or embedded in a road
+pub fn is_light_rail(tags: &BTreeMap<String, String>) -> bool {
+    tags.get(RAILWAY)
+        .or_else(|| tags.get("embedded_rails"))
+        .map(|x| x.split(';').any(|x| x == "tram" || x == "light_rail"))
+        .unwrap_or(false)
+}
+
+// Which vertical level something is on. 0 is the ground, positive is above it (bridges), and
+// negative is below (tunnels). Values like "-1;0" or "1.5" show up in practice, so just use the
+// first number.
+pub fn layer(tags: &BTreeMap<String, String>) -> isize {
+    if let Some(layer) = tags.get(LAYER) {
+        if let Ok(x) = layer.split(';').next().unwrap().trim().parse::<f64>() {
+            return x.round() as isize;
+        }
+    }
+    // Most bridges and tunnels don't bother with the layer tag
+    if tags.get(BRIDGE).map(|x| x != "no").unwrap_or(false) {
+        return 1;
+    }
+    if tags
+        .get(TUNNEL)
+        .map(|x| x != "no" && x != "building_passage")
+        .unwrap_or(false)
+    {
+        return -1;
+    }
+    0
+}
@@ -29,6 +29,11 @@ pub struct ParkingLot {
     pub osm_id: i64,
     // The middle of the "T", pointing towards the parking aisle
     pub spots: Vec<(Pt2D, Angle)>,
+    // Multi-level lots, or lots without mapped aisles, can hold more cars than there are spots to
+    // draw. Cars parked in these aren't visible.
+    pub extra_spots: usize,
+    // Reserved for people driving here and continuing on transit
+    pub park_and_ride: bool,
 
     // Goes from the lot to the driving lane
     pub driveway_line: PolyLine,
@@ -40,3 +45,9 @@ pub struct ParkingLot {
     pub sidewalk_line: Line,
     pub sidewalk_pos: Position,
 }
+
+impl ParkingLot {
+    pub fn capacity(&self) -> usize {
+        self.spots.len() + self.extra_spots
+    }
+}
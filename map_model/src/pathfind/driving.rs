@@ -139,11 +139,10 @@ fn make_input_graph(
         // vehicle.
         // TODO Need to test editing lanes inside an IntersectionCluster very carefully. See Mercer
         // and Dexter.
-        if ut
-            .path
-            .iter()
-            .all(|t| constraints.can_use(map.get_l(t.dst), map))
-        {
+        if ut.path.iter().all(|t| {
+            constraints.can_use(map.get_l(t.dst), map)
+                && constraints.can_use_turn(map.get_t(*t), map)
+        }) {
             uber_turn_entrances.insert(ut.entry(), idx);
         } else {
             // Similar to the hack below for unused lanes
@@ -205,10 +204,16 @@ fn make_input_graph(
     input_graph
 }
 
+// Added when entering a road closed to through traffic from one that isn't. Big enough that
+// routes only do it when the trip starts or ends inside, or when there's no other way. Trips that
+// start inside don't pay anything to leave.
+const NO_THROUGH_TRAFFIC_PENALTY: usize = 100_000;
+const BANNED_VEHICLE_PENALTY: usize = 100_000;
+
 pub fn cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -> usize {
     // TODO Could cost turns differently.
 
-    match constraints {
+    let base = match constraints {
         PathConstraints::Car => {
             // Prefer slightly longer route on faster roads
             let t1 = lane.length() / map.get_r(lane.parent).speed_limit;
@@ -217,8 +222,9 @@ pub fn cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -
         }
         PathConstraints::Bike => {
             // Speed limits don't matter, bikes are usually constrained by their own speed limit.
-            let dist = lane.length() + turn.geom.length();
-            // TODO Elevation gain is bad, loss is good.
+            // Only the lane climbs; turns happen inside flat intersections.
+            let dist =
+                lane.length() * climb_penalty(lane.percent_grade(map), 10.0) + turn.geom.length();
             // TODO If we're on a driving lane, higher speed limit is worse.
             // TODO Bike lanes next to parking is dangerous.
 
@@ -248,6 +254,39 @@ pub fn cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -
             };
             (lt_penalty * (t1 + t2)).inner_seconds().round() as usize
         }
+        PathConstraints::Train => {
+            let t1 = lane.length() / map.get_r(lane.parent).speed_limit;
+            let t2 = turn.geom.length() / map.get_parent(turn.id.dst).speed_limit;
+            (t1 + t2).inner_seconds().round() as usize
+        }
         PathConstraints::Pedestrian => unreachable!(),
+    };
+
+    let dst_road = map.get_parent(turn.id.dst);
+    let mut total = base;
+    if dst_road.no_through_traffic.contains(&constraints)
+        && !map
+            .get_r(lane.parent)
+            .no_through_traffic
+            .contains(&constraints)
+    {
+        total += NO_THROUGH_TRAFFIC_PENALTY;
+    }
+    // Unlike through traffic, entering a banned road always costs extra, even from another banned
+    // road. Trips ending there pay it no matter the route, and trips starting there never do.
+    if dst_road.banned_vehicles.contains(&constraints) {
+        total += BANNED_VEHICLE_PENALTY;
+    }
+    total
+}
+
+// A multiplier for the cost of going up or down a slope. Climbing gets more expensive with the
+// grade, scaled by how much the mode cares; going downhill is never cheaper than flat ground, since
+// the routing costs are distances, not speeds.
+pub fn climb_penalty(percent_grade: f64, steepness: f64) -> f64 {
+    if percent_grade > 0.0 {
+        1.0 + steepness * percent_grade
+    } else {
+        1.0
     }
 }
@@ -1,17 +1,21 @@
 mod driving;
 mod node_map;
+mod park_and_ride;
 // TODO tmp
 pub mod uber_turns;
 mod walking;
+mod walkshed;
 
 pub use self::driving::cost;
 use self::driving::VehiclePathfinder;
+pub use self::park_and_ride::should_use_park_and_ride;
 use self::walking::SidewalkPathfinder;
+pub use self::walkshed::{walkshed, WalkingProfile};
 use crate::{
-    osm, BusRouteID, BusStopID, Lane, LaneID, LaneType, Map, Position, Traversable, TurnID,
+    osm, BusRouteID, BusStopID, Lane, LaneID, LaneType, Map, Position, Traversable, Turn, TurnID,
 };
 use abstutil::Timer;
-use geom::{Distance, PolyLine, EPSILON_DIST};
+use geom::{Distance, PolyLine, SliceError, EPSILON_DIST};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt;
@@ -49,13 +53,13 @@ impl PathStep {
         map: &Map,
         start: Distance,
         dist_ahead: Option<Distance>,
-    ) -> Option<(PolyLine, Distance)> {
+    ) -> Result<(PolyLine, Distance), SliceError> {
         if let Some(d) = dist_ahead {
             if d < Distance::ZERO {
                 panic!("Negative dist_ahead?! {}", d);
             }
             if d == Distance::ZERO {
-                return None;
+                return Err(SliceError::TooShort { start, end: start });
             }
         }
 
@@ -220,13 +224,13 @@ impl Path {
         self.steps[self.steps.len() - 1]
     }
 
-    // dist_ahead is unlimited when None.
+    // dist_ahead is unlimited when None. Fails if there's no geometry left to trace.
     pub fn trace(
         &self,
         map: &Map,
         start_dist: Distance,
         dist_ahead: Option<Distance>,
-    ) -> Option<PolyLine> {
+    ) -> Result<PolyLine, SliceError> {
         let mut pts_so_far: Option<PolyLine> = None;
         let mut dist_remaining = dist_ahead;
 
@@ -246,18 +250,22 @@ impl Path {
         }
 
         // Special case the first step.
-        if let Some((pts, dist)) = self.steps[0].slice(map, start_dist, dist_remaining) {
-            pts_so_far = Some(pts);
-            if dist_remaining.is_some() {
-                dist_remaining = Some(dist);
+        let first_err = match self.steps[0].slice(map, start_dist, dist_remaining) {
+            Ok((pts, dist)) => {
+                pts_so_far = Some(pts);
+                if dist_remaining.is_some() {
+                    dist_remaining = Some(dist);
+                }
+                None
             }
-        }
+            Err(err) => Some(err),
+        };
 
         if self.steps.len() == 1 {
             // It's possible there are paths on their last step that're effectively empty, because
             // they're a 0-length turn, or something like a pedestrian crossing a front path and
             // immediately getting on a bike.
-            return pts_so_far;
+            return pts_so_far.ok_or_else(|| first_err.unwrap());
         }
 
         // Crunch through the intermediate steps, as long as we can.
@@ -266,7 +274,7 @@ impl Path {
                 if d <= Distance::ZERO {
                     // We know there's at least some geometry if we made it here, so unwrap to
                     // verify that understanding.
-                    return Some(pts_so_far.unwrap());
+                    return Ok(pts_so_far.unwrap());
                 }
             }
             // If we made it to the last step, maybe use the end_dist.
@@ -292,15 +300,14 @@ impl Path {
                 PathStep::ContraflowLane(l) => map.get_l(l).lane_center_pts.reversed().length(),
                 _ => Distance::ZERO,
             };
-            if let Some((new_pts, dist)) =
+            if let Ok((new_pts, dist)) =
                 self.steps[i].slice(map, start_dist_this_step, dist_remaining)
             {
                 if pts_so_far.is_some() {
                     if let Some(new) = pts_so_far.unwrap().maybe_extend(new_pts) {
                         pts_so_far = Some(new);
                     } else {
-                        println!("WARNING: Couldn't trace some path because of duplicate points");
-                        return None;
+                        return Err(SliceError::DuplicatePoints);
                     }
                 } else {
                     pts_so_far = Some(new_pts);
@@ -311,7 +318,8 @@ impl Path {
             }
         }
 
-        Some(pts_so_far.unwrap())
+        // If nothing was traced at all, the first step must've failed
+        pts_so_far.ok_or_else(|| first_err.unwrap())
     }
 
     pub fn get_steps(&self) -> &VecDeque<PathStep> {
@@ -321,12 +329,13 @@ impl Path {
 
 // Who's asking for a path?
 // TODO This is an awful name.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PathConstraints {
     Pedestrian,
     Car,
     Bike,
     Bus,
+    Train,
 }
 
 impl PathConstraints {
@@ -337,6 +346,7 @@ impl PathConstraints {
             LaneType::Driving => PathConstraints::Car,
             LaneType::Biking => PathConstraints::Bike,
             LaneType::Bus => PathConstraints::Bus,
+            LaneType::LightRail => PathConstraints::Train,
             _ => panic!("PathConstraints::from_lt({:?}) doesn't make sense", lt),
         }
     }
@@ -359,9 +369,22 @@ impl PathConstraints {
                 }
             }
             PathConstraints::Bus => l.is_driving() || l.is_bus(),
+            PathConstraints::Train => l.is_light_rail(),
         }
     }
 
+    // Only a few turns are off-limits, because of modal filters. Turning around at the filter is
+    // fine.
+    pub fn can_use_turn(self, t: &Turn, map: &Map) -> bool {
+        if self != PathConstraints::Car {
+            return true;
+        }
+        let r1 = map.get_parent(t.id.src);
+        let r2 = map.get_parent(t.id.dst);
+        r1.id == r2.id
+            || (r1.modal_filter != Some(t.id.parent) && r2.modal_filter != Some(t.id.parent))
+    }
+
     // Strict for bikes. If there are bike lanes, not allowed to use other lanes.
     pub fn filter_lanes(self, lanes: &Vec<LaneID>, map: &Map) -> Vec<LaneID> {
         let choices: Vec<LaneID> = lanes
@@ -473,6 +496,7 @@ pub struct Pathfinder {
     car_graph: VehiclePathfinder,
     bike_graph: VehiclePathfinder,
     bus_graph: VehiclePathfinder,
+    train_graph: VehiclePathfinder,
     walking_graph: SidewalkPathfinder,
     // TODO Option just during initialization! Ewww.
     walking_with_transit_graph: Option<SidewalkPathfinder>,
@@ -494,21 +518,32 @@ impl Pathfinder {
         let bus_graph = VehiclePathfinder::new(map, PathConstraints::Bus, Some(&car_graph));
         timer.stop("prepare pathfinding for buses");
 
+        // Most maps don't have any tracks, so this is cheap
+        timer.start("prepare pathfinding for trains");
+        let train_graph = VehiclePathfinder::new(map, PathConstraints::Train, None);
+        timer.stop("prepare pathfinding for trains");
+
         timer.start("prepare pathfinding for pedestrians");
-        let walking_graph = SidewalkPathfinder::new(map, false, &bus_graph);
+        let walking_graph = SidewalkPathfinder::new(map, false, &bus_graph, &train_graph);
         timer.stop("prepare pathfinding for pedestrians");
 
         Pathfinder {
             car_graph,
             bike_graph,
             bus_graph,
+            train_graph,
             walking_graph,
             walking_with_transit_graph: None,
         }
     }
 
     pub fn setup_walking_with_transit(&mut self, map: &Map) {
-        self.walking_with_transit_graph = Some(SidewalkPathfinder::new(map, true, &self.bus_graph));
+        self.walking_with_transit_graph = Some(SidewalkPathfinder::new(
+            map,
+            true,
+            &self.bus_graph,
+            &self.train_graph,
+        ));
     }
 
     pub fn pathfind(&self, req: PathRequest, map: &Map) -> Option<Path> {
@@ -517,6 +552,7 @@ impl Pathfinder {
             PathConstraints::Car => self.car_graph.pathfind(&req, map).map(|(p, _)| p),
             PathConstraints::Bike => self.bike_graph.pathfind(&req, map).map(|(p, _)| p),
             PathConstraints::Bus => self.bus_graph.pathfind(&req, map).map(|(p, _)| p),
+            PathConstraints::Train => self.train_graph.pathfind(&req, map).map(|(p, _)| p),
         }
     }
 
@@ -545,15 +581,20 @@ impl Pathfinder {
         self.bus_graph.apply_edits(map);
         timer.stop("apply edits to bus pathfinding");
 
+        timer.start("apply edits to train pathfinding");
+        self.train_graph.apply_edits(map);
+        timer.stop("apply edits to train pathfinding");
+
         timer.start("apply edits to pedestrian pathfinding");
-        self.walking_graph.apply_edits(map, &self.bus_graph);
+        self.walking_graph
+            .apply_edits(map, &self.bus_graph, &self.train_graph);
         timer.stop("apply edits to pedestrian pathfinding");
 
         timer.start("apply edits to pedestrian using transit pathfinding");
         self.walking_with_transit_graph
             .as_mut()
             .unwrap()
-            .apply_edits(map, &self.bus_graph);
+            .apply_edits(map, &self.bus_graph, &self.train_graph);
         timer.stop("apply edits to pedestrian using transit pathfinding");
     }
 }
@@ -37,6 +37,10 @@ impl<T: Copy + Ord + Debug + Serialize> NodeMap<T> {
         }
     }
 
+    pub fn all_nodes(&self) -> Vec<T> {
+        self.node_to_id.keys().cloned().collect()
+    }
+
     pub fn translate(&self, path: &ShortestPath) -> Vec<T> {
         path.get_nodes()
             .iter()
@@ -0,0 +1,102 @@
+use crate::{
+    BuildingID, BusRouteID, BusStopID, Map, ParkingLotID, Path, PathConstraints, PathRequest,
+    PathStep, Position,
+};
+use geom::{Distance, Duration, Speed};
+
+// Riders don't plan around the schedule, so expect some wait at the first stop.
+const EXPECTED_TRANSIT_WAIT: Duration = Duration::const_seconds(300.0);
+
+// Should somebody leaving a building drive to a park-and-ride lot, then take transit the rest of
+// the way? Only if that's estimated to be faster than walking to transit (or walking the whole
+// way) from the building. Returns the lot and the transit leg to take from there.
+//
+// The estimates use speed limits, so they don't know about congestion. Whether the lot has room
+// is only checked when the trip starts.
+pub fn should_use_park_and_ride(
+    map: &Map,
+    start: BuildingID,
+    end: Position,
+) -> Option<(ParkingLotID, BusStopID, BusStopID, BusRouteID)> {
+    if !map.all_parking_lots().iter().any(|pl| pl.park_and_ride) {
+        return None;
+    }
+    let baseline = {
+        let sidewalk = map.get_b(start).front_path.sidewalk;
+        if let Some((stop1, stop2, route)) = map.should_use_transit(sidewalk, end) {
+            transit_time(map, sidewalk, end, stop1, stop2, route)?
+        } else {
+            walking_time(map, sidewalk, end)?
+        }
+    };
+    let drive_start = Position::new(map.find_driving_lane_near_building(start), Distance::ZERO);
+
+    let mut best: Option<(Duration, (ParkingLotID, BusStopID, BusStopID, BusRouteID))> = None;
+    for pl in map.all_parking_lots() {
+        if !pl.park_and_ride {
+            continue;
+        }
+        let (stop1, stop2, route) = match map.should_use_transit(pl.sidewalk_pos, end) {
+            Some(x) => x,
+            None => continue,
+        };
+        let drive = match map.pathfind(PathRequest {
+            start: drive_start,
+            end: pl.driving_pos,
+            constraints: PathConstraints::Car,
+        }) {
+            Some(path) => driving_time(map, &path),
+            None => continue,
+        };
+        let total = match transit_time(map, pl.sidewalk_pos, end, stop1, stop2, route) {
+            Some(dt) => drive + dt,
+            None => continue,
+        };
+        if total < baseline && best.as_ref().map(|(dt, _)| total < *dt).unwrap_or(true) {
+            best = Some((total, (pl.id, stop1, stop2, route)));
+        }
+    }
+    best.map(|(_, x)| x)
+}
+
+fn transit_time(
+    map: &Map,
+    start: Position,
+    end: Position,
+    stop1: BusStopID,
+    stop2: BusStopID,
+    route: BusRouteID,
+) -> Option<Duration> {
+    let ride = map.pathfind(PathRequest {
+        start: map.get_bs(stop1).driving_pos,
+        end: map.get_bs(stop2).driving_pos,
+        constraints: map.get_br(route).route_type,
+    })?;
+    Some(
+        walking_time(map, start, map.get_bs(stop1).sidewalk_pos)?
+            + EXPECTED_TRANSIT_WAIT
+            + driving_time(map, &ride)
+            + walking_time(map, map.get_bs(stop2).sidewalk_pos, end)?,
+    )
+}
+
+fn walking_time(map: &Map, start: Position, end: Position) -> Option<Duration> {
+    let path = map.pathfind(PathRequest {
+        start,
+        end,
+        constraints: PathConstraints::Pedestrian,
+    })?;
+    Some(path.total_length() / Speed::meters_per_second(1.34))
+}
+
+// Just the speed limits along the way
+fn driving_time(map: &Map, path: &Path) -> Duration {
+    let mut total = Duration::ZERO;
+    for step in path.get_steps() {
+        if let PathStep::Lane(l) = step {
+            let lane = map.get_l(*l);
+            total += lane.length() / map.get_r(lane.parent).speed_limit;
+        }
+    }
+    total
+}
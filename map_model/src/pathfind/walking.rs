@@ -1,4 +1,4 @@
-use crate::pathfind::driving::VehiclePathfinder;
+use crate::pathfind::driving::{climb_penalty, VehiclePathfinder};
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::{
     BusRouteID, BusStopID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Position,
@@ -7,6 +7,7 @@ use fast_paths::{deserialize_32, serialize_32, FastGraph, InputGraph, PathCalcul
 use geom::{Distance, Speed};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 use thread_local::ThreadLocal;
 
 #[derive(Serialize, Deserialize)]
@@ -29,7 +30,12 @@ enum Node {
 }
 
 impl SidewalkPathfinder {
-    pub fn new(map: &Map, use_transit: bool, bus_graph: &VehiclePathfinder) -> SidewalkPathfinder {
+    pub fn new(
+        map: &Map,
+        use_transit: bool,
+        bus_graph: &VehiclePathfinder,
+        train_graph: &VehiclePathfinder,
+    ) -> SidewalkPathfinder {
         let mut nodes = NodeMap::new();
         // We're assuming that to start with, no sidewalks are closed for construction!
         for l in map.all_lanes() {
@@ -45,7 +51,13 @@ impl SidewalkPathfinder {
             }
         }
 
-        let graph = fast_paths::prepare(&make_input_graph(map, &nodes, use_transit, bus_graph));
+        let graph = fast_paths::prepare(&make_input_graph(
+            map,
+            &nodes,
+            use_transit,
+            bus_graph,
+            train_graph,
+        ));
         SidewalkPathfinder {
             graph,
             nodes,
@@ -54,10 +66,33 @@ impl SidewalkPathfinder {
         }
     }
 
-    pub fn apply_edits(&mut self, map: &Map, bus_graph: &VehiclePathfinder) {
-        // The NodeMap is all sidewalks and bus stops -- it won't change. So we can also reuse the
-        // node ordering.
-        let input_graph = make_input_graph(map, &self.nodes, self.use_transit, bus_graph);
+    pub fn apply_edits(
+        &mut self,
+        map: &Map,
+        bus_graph: &VehiclePathfinder,
+        train_graph: &VehiclePathfinder,
+    ) {
+        // Edits can add and remove bus stops. Then the nodes change, so start over.
+        if self.use_transit {
+            let stops: BTreeSet<BusStopID> = self
+                .nodes
+                .all_nodes()
+                .into_iter()
+                .filter_map(|n| match n {
+                    Node::RideBus(stop) => Some(stop),
+                    Node::SidewalkEndpoint(_, _) => None,
+                })
+                .collect();
+            if stops.iter().ne(map.all_bus_stops().keys()) {
+                *self = SidewalkPathfinder::new(map, true, bus_graph, train_graph);
+                return;
+            }
+        }
+
+        // Otherwise the NodeMap is all sidewalks and bus stops -- it won't change. So we can also
+        // reuse the node ordering.
+        let input_graph =
+            make_input_graph(map, &self.nodes, self.use_transit, bus_graph, train_graph);
         let node_ordering = self.graph.get_node_ordering();
         self.graph = fast_paths::prepare_with_order(&input_graph, &node_ordering).unwrap();
     }
@@ -201,16 +236,19 @@ fn make_input_graph(
     nodes: &NodeMap<Node>,
     use_transit: bool,
     bus_graph: &VehiclePathfinder,
+    train_graph: &VehiclePathfinder,
 ) -> InputGraph {
     let mut input_graph = InputGraph::new();
 
     for l in map.all_lanes() {
         if l.is_sidewalk() {
-            let cost = to_s(l.length());
+            // Sidewalks are walked in both directions, so the climb depends on which way.
+            let grade = l.percent_grade(map);
             let n1 = nodes.get(Node::SidewalkEndpoint(l.id, true));
             let n2 = nodes.get(Node::SidewalkEndpoint(l.id, false));
-            input_graph.add_edge(n1, n2, cost);
-            input_graph.add_edge(n2, n1, cost);
+            // n1 is at dst_i, so going to n2 walks against the lane's direction
+            input_graph.add_edge(n1, n2, to_s(l.length() * climb_penalty(-grade, 5.0)));
+            input_graph.add_edge(n2, n1, to_s(l.length() * climb_penalty(grade, 5.0)));
         }
     }
 
@@ -218,7 +256,16 @@ fn make_input_graph(
         if t.between_sidewalks() {
             let from = Node::SidewalkEndpoint(t.id.src, map.get_l(t.id.src).dst_i == t.id.parent);
             let to = Node::SidewalkEndpoint(t.id.dst, map.get_l(t.id.dst).dst_i == t.id.parent);
-            input_graph.add_edge(nodes.get(from), nodes.get(to), to_s(t.geom.length()));
+            // People go a bit out of their way to cross somewhere marked
+            let penalty = match t.crosswalk_id(map) {
+                Some(id) if !map.get_r(id.r).crosswalk_type(id.i).is_marked() => 1.5,
+                _ => 1.0,
+            };
+            input_graph.add_edge(
+                nodes.get(from),
+                nodes.get(to),
+                to_s(t.geom.length() * penalty),
+            );
         }
     }
 
@@ -245,6 +292,11 @@ fn make_input_graph(
         // Connect each adjacent stop along a route, with the cost based on how long it'll take a
         // bus to drive between the stops. Optimistically assume no waiting time at a stop.
         for route in map.get_all_bus_routes() {
+            let graph = if route.route_type == PathConstraints::Train {
+                train_graph
+            } else {
+                bus_graph
+            };
             for (stop1, stop2) in
                 route
                     .stops
@@ -255,11 +307,11 @@ fn make_input_graph(
                         &route.stops[0],
                     )))
             {
-                if let Some((_, driving_cost)) = bus_graph.pathfind(
+                if let Some((_, driving_cost)) = graph.pathfind(
                     &PathRequest {
                         start: map.get_bs(*stop1).driving_pos,
                         end: map.get_bs(*stop2).driving_pos,
-                        constraints: PathConstraints::Bus,
+                        constraints: route.route_type,
                     },
                     map,
                 ) {
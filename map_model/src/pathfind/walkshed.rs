@@ -0,0 +1,136 @@
+use crate::{BuildingID, Lane, LaneID, Map, Position, Turn};
+use geom::{Duration, Speed};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+// The steepest ramp the ADA allows
+const MAX_WHEELCHAIR_GRADE: f64 = 1.0 / 12.0;
+
+// How somebody gets around on foot. This only shapes walksheds for now; the pathfinder and the
+// simulation still route every pedestrian the same way, so it can't audit trips or routes yet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum WalkingProfile {
+    Default,
+    // Avoids stairs, steep sidewalks, and crosswalks without curb ramps
+    Wheelchair,
+}
+
+impl WalkingProfile {
+    pub fn speed(self) -> Speed {
+        match self {
+            WalkingProfile::Default => Speed::meters_per_second(1.34),
+            // Manual wheelchairs are a bit slower on average
+            WalkingProfile::Wheelchair => Speed::meters_per_second(1.0),
+        }
+    }
+
+    // Why this profile can't use a sidewalk, if it can't
+    pub fn sidewalk_obstacle(self, lane: &Lane, map: &Map) -> Option<&'static str> {
+        if self == WalkingProfile::Default {
+            return None;
+        }
+        if map.get_r(lane.parent).is_stairs() {
+            return Some("stairs");
+        }
+        if lane.percent_grade(map).abs() > MAX_WHEELCHAIR_GRADE {
+            return Some("steep grade");
+        }
+        None
+    }
+
+    // Why this profile can't use a turn between sidewalks, if it can't
+    pub fn turn_obstacle(self, turn: &Turn, map: &Map) -> Option<&'static str> {
+        if self == WalkingProfile::Default {
+            return None;
+        }
+        if let Some(id) = turn.crosswalk_id(map) {
+            if !map.get_r(id.r).has_curb_ramps(id.i) {
+                return Some("no curb ramp");
+            }
+        }
+        None
+    }
+}
+
+// Finds every building reachable from the start within the time limit, and how long it takes to
+// get there. Unlike the sidewalk pathfinder, this doesn't consider transit, and it uses the
+// profile's travel time instead of penalizing hills and unmarked crossings.
+pub fn walkshed(
+    map: &Map,
+    start: Position,
+    profile: WalkingProfile,
+    limit: Duration,
+) -> BTreeMap<BuildingID, Duration> {
+    let speed = profile.speed();
+    let start_lane = map.get_l(start.lane());
+
+    // Dijkstra over the ends of sidewalks. false is src_i, true is dst_i.
+    let mut best: HashMap<(LaneID, bool), Duration> = HashMap::new();
+    let mut queue: BinaryHeap<Reverse<(Duration, LaneID, bool)>> = BinaryHeap::new();
+    // Somebody already on an inaccessible sidewalk can at least leave it.
+    queue.push(Reverse((start.dist_along() / speed, start_lane.id, false)));
+    queue.push(Reverse((
+        (start_lane.length() - start.dist_along()) / speed,
+        start_lane.id,
+        true,
+    )));
+    while let Some(Reverse((time, l, dst_end))) = queue.pop() {
+        if time > limit || best.contains_key(&(l, dst_end)) {
+            continue;
+        }
+        best.insert((l, dst_end), time);
+
+        let lane = map.get_l(l);
+        if profile.sidewalk_obstacle(lane, map).is_none() {
+            queue.push(Reverse((time + lane.length() / speed, l, !dst_end)));
+        }
+        let i = if dst_end { lane.dst_i } else { lane.src_i };
+        for turn in map.get_turns_from_lane(l) {
+            if turn.id.parent != i || profile.turn_obstacle(turn, map).is_some() {
+                continue;
+            }
+            let next = map.get_l(turn.id.dst);
+            queue.push(Reverse((
+                time + turn.geom.length() / speed,
+                next.id,
+                next.dst_i == i,
+            )));
+        }
+    }
+
+    let mut results = BTreeMap::new();
+    for b in map.all_buildings() {
+        let pos = b.front_path.sidewalk;
+        let lane = map.get_l(pos.lane());
+        let mut time = if pos.lane() == start_lane.id {
+            // Don't go to the end of the sidewalk and back
+            Some(if pos.dist_along() > start.dist_along() {
+                (pos.dist_along() - start.dist_along()) / speed
+            } else {
+                (start.dist_along() - pos.dist_along()) / speed
+            })
+        } else {
+            None
+        };
+        if profile.sidewalk_obstacle(lane, map).is_none() {
+            for (dst_end, dist) in vec![
+                (false, pos.dist_along()),
+                (true, lane.length() - pos.dist_along()),
+            ] {
+                if let Some(t) = best.get(&(lane.id, dst_end)) {
+                    let t = *t + dist / speed;
+                    if time.map(|x| t < x).unwrap_or(true) {
+                        time = Some(t);
+                    }
+                }
+            }
+        }
+        if let Some(t) = time {
+            if t <= limit {
+                results.insert(b.id, t);
+            }
+        }
+    }
+    results
+}
@@ -1,5 +1,5 @@
 use crate::make::initial::lane_specs::get_lane_types;
-use crate::{osm, AreaType, IntersectionType, RoadSpec};
+use crate::{osm, AreaType, IntersectionType, RoadSpec, TurnType};
 use abstutil::{deserialize_btreemap, serialize_btreemap, Timer, Warn};
 use geom::{Angle, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D};
 use gtfs::Route;
@@ -30,6 +30,7 @@ pub struct RawMap {
     pub areas: Vec<RawArea>,
     pub parking_lots: Vec<RawParkingLot>,
     pub parking_aisles: Vec<Vec<Pt2D>>,
+    pub bike_racks: Vec<RawBikeRack>,
 
     pub boundary_polygon: Polygon,
     pub gps_bounds: GPSBounds,
@@ -97,6 +98,7 @@ impl RawMap {
             areas: Vec::new(),
             parking_lots: Vec::new(),
             parking_aisles: Vec::new(),
+            bike_racks: Vec::new(),
             // Some nonsense thing
             boundary_polygon: Polygon::rectangle(1.0, 1.0),
             gps_bounds: GPSBounds::new(),
@@ -166,7 +168,10 @@ impl RawMap {
         };
         let mut roads = BTreeMap::new();
         for r in &i.roads {
-            roads.insert(*r, initial::Road::new(*r, &self.roads[r]));
+            roads.insert(
+                *r,
+                initial::Road::new(*r, &self.roads[r], self.driving_side),
+            );
         }
 
         let (i_pts, debug) =
@@ -282,6 +287,104 @@ impl RawMap {
 
         Some(fixed)
     }
+
+    // Collapses a short road into one intersection, keeping i1 and deleting i2. The other roads at
+    // i2 get re-keyed to i1, so any OriginalRoad held by the caller may be stale afterwards.
+    // Complicated turn restrictions passing through the short road become simple ones at the
+    // merged intersection.
+    pub fn merge_short_road(
+        &mut self,
+        short: OriginalRoad,
+    ) -> Result<OriginalIntersection, String> {
+        let (keep_i, destroy_i) = (short.i1, short.i2);
+        if keep_i == destroy_i {
+            return Err(format!("{} is a loop", short));
+        }
+        for i in &[keep_i, destroy_i] {
+            if self.intersections[i].intersection_type == IntersectionType::Border {
+                return Err(format!("{} touches a border", short));
+            }
+        }
+        let mut renamed: BTreeMap<OriginalRoad, OriginalRoad> = BTreeMap::new();
+        for old_id in self.roads_per_intersection(destroy_i) {
+            if old_id == short {
+                continue;
+            }
+            let mut new_id = old_id;
+            if new_id.i1 == destroy_i {
+                new_id.i1 = keep_i;
+            }
+            if new_id.i2 == destroy_i {
+                new_id.i2 = keep_i;
+            }
+            // Another road between the same two intersections would become a loop
+            if new_id.i1 == new_id.i2 || self.roads.contains_key(&new_id) {
+                return Err(format!("{} would collapse {}", short, old_id));
+            }
+            renamed.insert(old_id, new_id);
+        }
+
+        let mut new_restrictions = Vec::new();
+        for (id, road) in &self.roads {
+            for (via, to) in &road.complicated_turn_restrictions {
+                if *via == short {
+                    new_restrictions.push((*id, *to));
+                }
+            }
+        }
+        self.delete_road(short);
+        for road in self.roads.values_mut() {
+            road.complicated_turn_restrictions
+                .retain(|(via, to)| *via != short && *to != short);
+        }
+
+        let destroyed = self.intersections.remove(&destroy_i).unwrap();
+        if destroyed.intersection_type == IntersectionType::TrafficSignal {
+            self.intersections
+                .get_mut(&keep_i)
+                .unwrap()
+                .intersection_type = IntersectionType::TrafficSignal;
+        }
+        // The moved roads still end where the destroyed intersection was, so extend them to the
+        // kept one.
+        let keep_pt = self.intersections[&keep_i].point;
+        for (old_id, new_id) in &renamed {
+            let mut road = self.roads.remove(old_id).unwrap();
+            if old_id.i1 == destroy_i && road.center_points[0] != keep_pt {
+                road.center_points.insert(0, keep_pt);
+            }
+            if old_id.i2 == destroy_i && *road.center_points.last().unwrap() != keep_pt {
+                road.center_points.push(keep_pt);
+            }
+            self.roads.insert(*new_id, road);
+        }
+
+        let fix = |r: &mut OriginalRoad| {
+            if let Some(new_id) = renamed.get(r) {
+                *r = *new_id;
+            }
+        };
+        for road in self.roads.values_mut() {
+            for (_, to) in &mut road.turn_restrictions {
+                fix(to);
+            }
+            for (via, to) in &mut road.complicated_turn_restrictions {
+                fix(via);
+                fix(to);
+            }
+        }
+        for (mut from, mut to) in new_restrictions {
+            fix(&mut from);
+            fix(&mut to);
+            self.roads
+                .get_mut(&from)
+                .unwrap()
+                .turn_restrictions
+                .push((RestrictionType::BanTurns, to));
+        }
+
+        Ok(keep_i)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -296,8 +399,8 @@ pub struct RawRoad {
 }
 
 impl RawRoad {
-    pub fn get_spec(&self) -> RoadSpec {
-        let (fwd, back) = get_lane_types(&self.osm_tags);
+    pub fn get_spec(&self, driving_side: DrivingSide) -> RoadSpec {
+        let (fwd, back) = get_lane_types(&self.osm_tags, driving_side);
         RoadSpec { fwd, back }
     }
 
@@ -323,6 +426,8 @@ pub struct RawBuilding {
     pub num_parking_spots: usize,
     // (Name, amenity type)
     pub amenities: BTreeSet<(String, String)>,
+    // A mapped entrance on the outline. The front path starts here, instead of the center.
+    pub entrance: Option<Pt2D>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -337,6 +442,17 @@ pub struct RawArea {
 pub struct RawParkingLot {
     pub polygon: Polygon,
     pub osm_id: i64,
+    // From the capacity tag, if it's mapped
+    pub capacity: Option<usize>,
+    pub park_and_ride: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawBikeRack {
+    pub pt: Pt2D,
+    pub osm_node_id: i64,
+    // From the capacity tag, if it's mapped
+    pub capacity: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -414,4 +530,21 @@ impl DrivingSide {
             DrivingSide::Left => a.opposite(),
         }
     }
+
+    // TurnType itself is just geometry. A "right turn" here is the easy one towards the curb,
+    // which is a left turn for DrivingSide::Left.
+    pub fn right_turn(self) -> TurnType {
+        match self {
+            DrivingSide::Right => TurnType::Right,
+            DrivingSide::Left => TurnType::Left,
+        }
+    }
+
+    // The turn across oncoming traffic
+    pub fn left_turn(self) -> TurnType {
+        match self {
+            DrivingSide::Right => TurnType::Left,
+            DrivingSide::Left => TurnType::Right,
+        }
+    }
 }
@@ -1,9 +1,11 @@
 use crate::raw::{OriginalRoad, RestrictionType};
-use crate::{osm, BusStopID, IntersectionID, LaneID, LaneType, Map, PathConstraints};
+use crate::{
+    osm, BusStopID, CrosswalkType, IntersectionID, LaneID, LaneType, Map, PathConstraints,
+};
 use abstutil::{Error, Warn};
-use geom::{Distance, PolyLine, Polygon, Speed};
+use geom::{Distance, Duration, PolyLine, Polygon, Speed, Time, Units};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
 
 // TODO reconsider pub usize. maybe outside world shouldnt know.
@@ -98,7 +100,22 @@ pub struct Road {
     pub complicated_turn_restrictions: Vec<(RoadID, RoadID)>,
     pub orig_id: OriginalRoad,
     pub speed_limit: Speed,
+    // Lower limits that only apply at some times of day. Usually empty.
+    pub speed_limit_schedule: Vec<TimedSpeedLimit>,
+    // These vehicles may only use this road to reach somewhere along it, or somewhere along other
+    // connected roads with the same restriction. That models a whole neighborhood closed to
+    // through traffic by diverters just by restricting all of its roads.
+    pub no_through_traffic: BTreeSet<PathConstraints>,
+    // These vehicles may only use this road when the trip starts or ends along it.
+    pub banned_vehicles: BTreeSet<PathConstraints>,
+    // Bollards or planters at this end of the road stop cars from passing between the road and
+    // the intersection. People walking and cycling still get through, and so do buses, like with
+    // a bus gate. Cars can turn around at the filter.
+    pub modal_filter: Option<IntersectionID>,
     pub zorder: isize,
+    // The crosswalks over this road at dst_i and src_i
+    pub crosswalk_forwards: CrosswalkType,
+    pub crosswalk_backwards: CrosswalkType,
 
     // Invariant: A road must contain at least one child
     // These are ordered from closest to center lane (left-most when driving on the right) to
@@ -113,6 +130,38 @@ pub struct Road {
     pub dst_i: IntersectionID,
 }
 
+// A lower speed limit that applies during part of every day, like a school zone or a limit at
+// night
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TimedSpeedLimit {
+    pub limit: Speed,
+    // Times of day. If end is before start, the window wraps past midnight.
+    pub start: Time,
+    pub end: Time,
+}
+
+impl TimedSpeedLimit {
+    pub fn is_active(&self, now: Time) -> bool {
+        // The schedule repeats for simulations longer than a day
+        let time_of_day = Time::START_OF_DAY
+            + Duration::seconds(now.inner_seconds() % Duration::hours(24).inner_seconds());
+        if self.start <= self.end {
+            time_of_day >= self.start && time_of_day < self.end
+        } else {
+            time_of_day >= self.start || time_of_day < self.end
+        }
+    }
+
+    pub fn describe(&self, units: Units) -> String {
+        format!(
+            "{} from {} to {}",
+            self.limit.describe_rounded(units),
+            self.start.ampm_tostring(),
+            self.end.ampm_tostring()
+        )
+    }
+}
+
 impl Road {
     pub fn get_lane_types(&self) -> (Vec<LaneType>, Vec<LaneType>) {
         (
@@ -208,13 +257,12 @@ impl Road {
     }
 
     pub(crate) fn speed_limit_from_osm(&self) -> Speed {
-        if let Some(limit) = self.osm_tags.get(osm::MAXSPEED) {
-            // TODO handle other units
-            if limit.ends_with(" mph") {
-                if let Ok(mph) = limit[0..limit.len() - 4].parse::<f64>() {
-                    return Speed::miles_per_hour(mph);
-                }
-            }
+        if let Some(limit) = self
+            .osm_tags
+            .get(osm::MAXSPEED)
+            .and_then(|x| parse_osm_speed(x))
+        {
+            return limit;
         }
 
         if self.osm_tags.get(osm::HIGHWAY) == Some(&"primary".to_string())
@@ -222,9 +270,75 @@ impl Road {
         {
             return Speed::miles_per_hour(40.0);
         }
+        if self.osm_tags.get(osm::HIGHWAY) == Some(&"service".to_string()) {
+            return Speed::miles_per_hour(10.0);
+        }
+        // Tracks with their own right-of-way
+        if !self.osm_tags.contains_key(osm::HIGHWAY) && osm::is_light_rail(&self.osm_tags) {
+            return Speed::miles_per_hour(30.0);
+        }
         Speed::miles_per_hour(20.0)
     }
 
+    // From tags like "motor_vehicle=destination". Buses are never restricted, since they just
+    // follow their route.
+    pub(crate) fn no_through_traffic_from_osm(&self) -> BTreeSet<PathConstraints> {
+        let mut restricted = BTreeSet::new();
+        let destination = "destination".to_string();
+        if self.osm_tags.get("access") == Some(&destination)
+            || self.osm_tags.get("vehicle") == Some(&destination)
+        {
+            restricted.insert(PathConstraints::Car);
+            restricted.insert(PathConstraints::Bike);
+        }
+        if self.osm_tags.get("motor_vehicle") == Some(&destination)
+            || self.osm_tags.get("motorcar") == Some(&destination)
+        {
+            restricted.insert(PathConstraints::Car);
+        }
+        if self.osm_tags.get("bicycle") == Some(&destination) {
+            restricted.insert(PathConstraints::Bike);
+        }
+        restricted
+    }
+
+    // From tags like "motor_vehicle=no". Trucks aren't simulated yet, so "hgv=no" is ignored.
+    pub(crate) fn banned_vehicles_from_osm(&self) -> BTreeSet<PathConstraints> {
+        let mut banned = BTreeSet::new();
+        let no = "no".to_string();
+        if self.osm_tags.get("access") == Some(&no) || self.osm_tags.get("vehicle") == Some(&no) {
+            banned.insert(PathConstraints::Car);
+            banned.insert(PathConstraints::Bike);
+        }
+        if self.osm_tags.get("motor_vehicle") == Some(&no)
+            || self.osm_tags.get("motorcar") == Some(&no)
+        {
+            banned.insert(PathConstraints::Car);
+        }
+        if self.osm_tags.get("bicycle") == Some(&no) {
+            banned.insert(PathConstraints::Bike);
+        }
+        banned
+    }
+
+    // The speed limit in effect at some time, taking the schedule into account
+    pub fn speed_limit_at(&self, now: Time) -> Speed {
+        let mut limit = self.speed_limit;
+        for timed in &self.speed_limit_schedule {
+            if timed.is_active(now) {
+                limit = limit.min(timed.limit);
+            }
+        }
+        limit
+    }
+
+    pub(crate) fn speed_limit_schedule_from_osm(&self) -> Vec<TimedSpeedLimit> {
+        match self.osm_tags.get(osm::MAXSPEED_CONDITIONAL) {
+            Some(tag) => parse_maxspeed_conditional(tag),
+            None => Vec::new(),
+        }
+    }
+
     pub fn incoming_lanes(&self, i: IntersectionID) -> &Vec<(LaneID, LaneType)> {
         if self.src_i == i {
             &self.children_backwards
@@ -409,7 +523,12 @@ impl Road {
 
                 "residential" => 5,
 
+                // Alleys and other service roads
+                "service" => 2,
+
                 "footway" => 1,
+                "steps" => 1,
+                "pedestrian" => 1,
 
                 "unclassified" => 0,
                 "road" => 0,
@@ -419,11 +538,62 @@ impl Road {
                 // convert_osm/src/osm_reader.rs's is_road().
                 _ => panic!("Unknown OSM highway {}", highway),
             }
+        } else if osm::is_light_rail(&self.osm_tags) {
+            // Trains don't stop for cross traffic
+            21
         } else {
             0
         }
     }
 
+    pub fn crosswalk_type(&self, i: IntersectionID) -> CrosswalkType {
+        if i == self.dst_i {
+            self.crosswalk_forwards
+        } else {
+            self.crosswalk_backwards
+        }
+    }
+
+    pub(crate) fn set_crosswalk_type(&mut self, i: IntersectionID, ct: CrosswalkType) {
+        if i == self.dst_i {
+            self.crosswalk_forwards = ct;
+        } else {
+            self.crosswalk_backwards = ct;
+        }
+    }
+
+    // Are the crosswalks over this road at one end painted? Assume so unless OSM says otherwise.
+    pub fn crosswalk_type_from_osm(&self, i: IntersectionID) -> CrosswalkType {
+        let key = if i == self.dst_i {
+            osm::UNMARKED_CROSSING_FWD
+        } else {
+            osm::UNMARKED_CROSSING_BACK
+        };
+        if self.osm_tags.get(key) == Some(&"true".to_string()) {
+            CrosswalkType::Unmarked
+        } else {
+            CrosswalkType::Marked
+        }
+    }
+
+    // Can somebody in a wheelchair get on and off the crosswalks over this road at one end? Assume
+    // so unless OSM says there's a raised kerb. Raised crosswalks are level with the sidewalk.
+    pub fn has_curb_ramps(&self, i: IntersectionID) -> bool {
+        if self.crosswalk_type(i) == CrosswalkType::Raised {
+            return true;
+        }
+        let key = if i == self.dst_i {
+            osm::NO_CURB_RAMP_FWD
+        } else {
+            osm::NO_CURB_RAMP_BACK
+        };
+        self.osm_tags.get(key) != Some(&"true".to_string())
+    }
+
+    pub fn is_stairs(&self) -> bool {
+        self.osm_tags.get(osm::HIGHWAY) == Some(&"steps".to_string())
+    }
+
     pub fn all_bus_stops(&self, map: &Map) -> Vec<BusStopID> {
         let mut stops = Vec::new();
         for id in self.all_lanes() {
@@ -450,3 +620,151 @@ impl Road {
         grade
     }
 }
+
+// OSM assumes km/h when there's no unit
+fn parse_osm_speed(x: &str) -> Option<Speed> {
+    if let Ok(kph) = x.trim().parse::<f64>() {
+        return Some(Speed::km_per_hour(kph));
+    }
+    x.parse::<Speed>().ok()
+}
+
+// Handles tags like "20 mph @ (Mo-Fr 07:00-09:00, 14:00-16:00); 30 @ (22:00-06:00)". The days are
+// ignored, since the simulation only has one kind of day. Conditions besides the time of day, like
+// "wet", are skipped.
+fn parse_maxspeed_conditional(tag: &str) -> Vec<TimedSpeedLimit> {
+    let mut schedule = Vec::new();
+    for condition in split_outside_parens(tag, ';') {
+        let parts: Vec<&str> = condition.splitn(2, '@').collect();
+        if parts.len() != 2 {
+            continue;
+        }
+        let limit = match parse_osm_speed(parts[0]) {
+            Some(limit) => limit,
+            None => {
+                continue;
+            }
+        };
+        let windows = parts[1]
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')');
+        for window in windows.split(|c| c == ',' || c == ';') {
+            // Skip any days at the beginning
+            let hours: Vec<&str> = window
+                .split_whitespace()
+                .last()
+                .unwrap_or("")
+                .split('-')
+                .collect();
+            if hours.len() != 2 {
+                continue;
+            }
+            if let (Ok(start), Ok(end)) = (
+                Time::parse(&format!("{}:00", hours[0])),
+                Time::parse(&format!("{}:00", hours[1])),
+            ) {
+                if start != end {
+                    schedule.push(TimedSpeedLimit { limit, start, end });
+                }
+            }
+        }
+    }
+    schedule
+}
+
+// Separators inside parentheses belong to the opening hours of one condition
+fn split_outside_parens(x: &str, sep: char) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth: usize = 0;
+    let mut start = 0;
+    for (idx, c) in x.char_indices() {
+        if c == '(' {
+            depth += 1;
+        } else if c == ')' {
+            depth = depth.saturating_sub(1);
+        } else if c == sep && depth == 0 {
+            result.push(&x[start..idx]);
+            start = idx + c.len_utf8();
+        }
+    }
+    result.push(&x[start..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_maxspeed_conditional, TimedSpeedLimit};
+    use geom::{Duration, Speed, Time};
+
+    fn hm(hours: usize, minutes: usize) -> Time {
+        Time::START_OF_DAY + Duration::hours(hours) + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn test_parse_maxspeed_conditional() {
+        assert_eq!(
+            parse_maxspeed_conditional("20 mph @ (Mo-Fr 07:00-09:00, 14:00-16:00)"),
+            vec![
+                TimedSpeedLimit {
+                    limit: Speed::miles_per_hour(20.0),
+                    start: hm(7, 0),
+                    end: hm(9, 0),
+                },
+                TimedSpeedLimit {
+                    limit: Speed::miles_per_hour(20.0),
+                    start: hm(14, 0),
+                    end: hm(16, 0),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_maxspeed_conditional("30 @ (22:00-06:00); 20 mph @ (Mo-Fr 07:30-09:00)"),
+            vec![
+                TimedSpeedLimit {
+                    limit: Speed::km_per_hour(30.0),
+                    start: hm(22, 0),
+                    end: hm(6, 0),
+                },
+                TimedSpeedLimit {
+                    limit: Speed::miles_per_hour(20.0),
+                    start: hm(7, 30),
+                    end: hm(9, 0),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_maxspeed_conditional("40 km/h @ (Mo-Fr 07:00-09:00; Sa 10:00-12:00)").len(),
+            2
+        );
+        assert!(parse_maxspeed_conditional("30 @ wet").is_empty());
+        assert!(parse_maxspeed_conditional("none @ (07:00-09:00)").is_empty());
+        assert!(parse_maxspeed_conditional("20 mph").is_empty());
+    }
+
+    #[test]
+    fn test_is_active_past_midnight() {
+        let night = TimedSpeedLimit {
+            limit: Speed::miles_per_hour(20.0),
+            start: hm(22, 0),
+            end: hm(6, 0),
+        };
+        assert!(night.is_active(hm(22, 0)));
+        assert!(night.is_active(hm(23, 30)));
+        assert!(night.is_active(hm(3, 0)));
+        assert!(!night.is_active(hm(6, 0)));
+        assert!(!night.is_active(hm(12, 0)));
+        // The schedule repeats the next day
+        assert!(night.is_active(hm(27, 0)));
+        assert!(!night.is_active(hm(36, 0)));
+
+        let morning = TimedSpeedLimit {
+            limit: Speed::miles_per_hour(20.0),
+            start: hm(7, 0),
+            end: hm(9, 0),
+        };
+        assert!(morning.is_active(hm(8, 0)));
+        assert!(!morning.is_active(hm(9, 0)));
+        assert!(!morning.is_active(hm(23, 0)));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{IntersectionType, LaneType, Map};
+use geom::{Distance, Units};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// A summary of the whole map, meant for including in proposals and for sanity checking imports.
+// Everything is keyed by strings so the JSON export stays readable.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapStats {
+    pub map_name: String,
+    pub num_roads: usize,
+    pub num_buildings: usize,
+
+    // Summed over lanes, so a two-lane road counts twice
+    pub lane_length_per_type: BTreeMap<String, Distance>,
+    pub intersections_per_type: BTreeMap<String, usize>,
+
+    pub onstreet_parking_spots: usize,
+    pub offstreet_parking_spots: usize,
+    pub parking_lot_spots: usize,
+
+    // Of all roads carrying vehicles, the length with a sidewalk on both sides, exactly one side,
+    // or neither side.
+    pub length_with_sidewalks_both_sides: Distance,
+    pub length_with_sidewalks_one_side: Distance,
+    pub length_without_sidewalks: Distance,
+
+    // The map doesn't know about people. Callers with a scenario fill this out.
+    pub population_per_zone: BTreeMap<String, usize>,
+}
+
+impl MapStats {
+    pub fn new(map: &Map) -> MapStats {
+        let mut lane_length_per_type = BTreeMap::new();
+        let mut onstreet_parking_spots = 0;
+        for l in map.all_lanes() {
+            *lane_length_per_type
+                .entry(describe_lt(l.lane_type).to_string())
+                .or_insert(Distance::ZERO) += l.length();
+            if l.is_parking() {
+                onstreet_parking_spots += l.number_parking_spots();
+            }
+        }
+
+        let mut intersections_per_type = BTreeMap::new();
+        for i in map.all_intersections() {
+            let key = match i.intersection_type {
+                IntersectionType::StopSign => "stop sign",
+                IntersectionType::TrafficSignal => "traffic signal",
+                IntersectionType::Border => "border",
+                IntersectionType::Construction => "construction",
+            };
+            *intersections_per_type.entry(key.to_string()).or_insert(0) += 1;
+        }
+
+        let mut offstreet_parking_spots = 0;
+        for b in map.all_buildings() {
+            if let Some(ref p) = b.parking {
+                offstreet_parking_spots += p.num_spots;
+            }
+        }
+        let parking_lot_spots = map.all_parking_lots().iter().map(|pl| pl.capacity()).sum();
+
+        let mut length_with_sidewalks_both_sides = Distance::ZERO;
+        let mut length_with_sidewalks_one_side = Distance::ZERO;
+        let mut length_without_sidewalks = Distance::ZERO;
+        for r in map.all_roads() {
+            let (fwd, back) = r.get_lane_types();
+            if !fwd
+                .iter()
+                .chain(back.iter())
+                .any(|lt| lt.is_for_moving_vehicles())
+            {
+                continue;
+            }
+            let len = r.center_pts.length();
+            match (
+                fwd.contains(&LaneType::Sidewalk),
+                back.contains(&LaneType::Sidewalk),
+            ) {
+                (true, true) => {
+                    length_with_sidewalks_both_sides += len;
+                }
+                (false, false) => {
+                    length_without_sidewalks += len;
+                }
+                _ => {
+                    length_with_sidewalks_one_side += len;
+                }
+            }
+        }
+
+        MapStats {
+            map_name: map.get_name().to_string(),
+            num_roads: map.all_roads().len(),
+            num_buildings: map.all_buildings().len(),
+            lane_length_per_type,
+            intersections_per_type,
+            onstreet_parking_spots,
+            offstreet_parking_spots,
+            parking_lot_spots,
+            length_with_sidewalks_both_sides,
+            length_with_sidewalks_one_side,
+            length_without_sidewalks,
+            population_per_zone: BTreeMap::new(),
+        }
+    }
+
+    // Fraction (0 to 1) of the road length with a sidewalk on at least one side.
+    pub fn sidewalk_coverage(&self) -> f64 {
+        let total = self.length_with_sidewalks_both_sides
+            + self.length_with_sidewalks_one_side
+            + self.length_without_sidewalks;
+        if total == Distance::ZERO {
+            return 0.0;
+        }
+        (self.length_with_sidewalks_both_sides + self.length_with_sidewalks_one_side) / total
+    }
+
+    pub fn to_markdown(&self, units: Units) -> String {
+        let mut s = String::new();
+        writeln!(s, "# Statistics for {}", self.map_name).unwrap();
+        writeln!(s).unwrap();
+        writeln!(
+            s,
+            "{} roads, {} buildings",
+            abstutil::prettyprint_usize(self.num_roads),
+            abstutil::prettyprint_usize(self.num_buildings)
+        )
+        .unwrap();
+        writeln!(s).unwrap();
+
+        writeln!(s, "## Lanes").unwrap();
+        writeln!(s).unwrap();
+        writeln!(s, "| Lane type | Total length |").unwrap();
+        writeln!(s, "|---|---|").unwrap();
+        for (lt, dist) in &self.lane_length_per_type {
+            writeln!(s, "| {} | {} |", lt, dist.describe_rounded(units)).unwrap();
+        }
+        writeln!(s).unwrap();
+
+        writeln!(s, "## Intersections").unwrap();
+        writeln!(s).unwrap();
+        writeln!(s, "| Control | Count |").unwrap();
+        writeln!(s, "|---|---|").unwrap();
+        for (control, cnt) in &self.intersections_per_type {
+            writeln!(s, "| {} | {} |", control, abstutil::prettyprint_usize(*cnt)).unwrap();
+        }
+        writeln!(s).unwrap();
+
+        writeln!(s, "## Parking").unwrap();
+        writeln!(s).unwrap();
+        writeln!(
+            s,
+            "- {} on-street spots",
+            abstutil::prettyprint_usize(self.onstreet_parking_spots)
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "- {} off-street spots in buildings",
+            abstutil::prettyprint_usize(self.offstreet_parking_spots)
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "- {} spots in parking lots",
+            abstutil::prettyprint_usize(self.parking_lot_spots)
+        )
+        .unwrap();
+        writeln!(s).unwrap();
+
+        writeln!(s, "## Sidewalks").unwrap();
+        writeln!(s).unwrap();
+        writeln!(
+            s,
+            "{}% of road length has a sidewalk on at least one side",
+            (self.sidewalk_coverage() * 100.0).round()
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "- Both sides: {}",
+            self.length_with_sidewalks_both_sides
+                .describe_rounded(units)
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "- One side: {}",
+            self.length_with_sidewalks_one_side.describe_rounded(units)
+        )
+        .unwrap();
+        writeln!(
+            s,
+            "- Neither side: {}",
+            self.length_without_sidewalks.describe_rounded(units)
+        )
+        .unwrap();
+
+        if !self.population_per_zone.is_empty() {
+            writeln!(s).unwrap();
+            writeln!(s, "## Population").unwrap();
+            writeln!(s).unwrap();
+            writeln!(s, "| Zone | People |").unwrap();
+            writeln!(s, "|---|---|").unwrap();
+            for (zone, cnt) in &self.population_per_zone {
+                writeln!(s, "| {} | {} |", zone, abstutil::prettyprint_usize(*cnt)).unwrap();
+            }
+        }
+
+        s
+    }
+}
+
+fn describe_lt(lt: LaneType) -> &'static str {
+    match lt {
+        LaneType::Driving => "driving",
+        LaneType::Parking => "parking",
+        LaneType::Sidewalk => "sidewalk",
+        LaneType::Biking => "bike",
+        LaneType::Bus => "bus",
+        LaneType::SharedLeftTurn => "shared left-turn",
+        LaneType::Construction => "construction",
+        LaneType::LightRail => "light rail",
+    }
+}
@@ -40,7 +40,28 @@ pub struct ControlStopSign {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RoadWithStopSign {
     pub rightmost_lane: LaneID,
-    pub must_stop: bool,
+    pub control: ApproachControl,
+}
+
+// What's posted where a road enters the intersection
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ApproachControl {
+    // No sign; this road has the right-of-way
+    Priority,
+    // Slow down and wait for a big enough gap in traffic on the priority roads
+    Yield,
+    // Like Yield, but always come to a full stop first
+    Stop,
+}
+
+impl ApproachControl {
+    pub fn describe(self) -> &'static str {
+        match self {
+            ApproachControl::Priority => "no sign",
+            ApproachControl::Yield => "yield sign",
+            ApproachControl::Stop => "stop sign",
+        }
+    }
 }
 
 impl ControlStopSign {
@@ -67,7 +88,7 @@ impl ControlStopSign {
                     *r,
                     RoadWithStopSign {
                         rightmost_lane: *travel_lanes.last().unwrap(),
-                        must_stop: false,
+                        control: ApproachControl::Priority,
                     },
                 );
             }
@@ -93,7 +114,7 @@ impl ControlStopSign {
         // highest-priority roads.
         for (r, cfg) in ss.roads.iter_mut() {
             if ranks.len() == 1 || rank[r] != ranks[0] {
-                cfg.must_stop = true;
+                cfg.control = ApproachControl::Stop;
             }
         }
         ss
@@ -103,20 +124,41 @@ impl ControlStopSign {
     pub fn get_priority(&self, turn: TurnID, map: &Map) -> TurnPriority {
         match map.get_t(turn).turn_type {
             TurnType::SharedSidewalkCorner => TurnPriority::Protected,
-            // TODO This actually feels like a policy bit that should be flippable.
-            TurnType::Crosswalk => TurnPriority::Protected,
-            _ => {
-                if self.roads[&map.get_l(turn.src).parent].must_stop {
-                    TurnPriority::Yield
-                } else {
+            // People wait for a gap in traffic before using unmarked crosswalks
+            TurnType::Crosswalk => {
+                let id = map.get_t(turn).crosswalk_id(map).unwrap();
+                if map.get_r(id.r).crosswalk_type(id.i).is_marked() {
                     TurnPriority::Protected
+                } else {
+                    TurnPriority::Yield
                 }
             }
+            _ => match self.roads[&map.get_l(turn.src).parent].control {
+                ApproachControl::Priority => TurnPriority::Protected,
+                ApproachControl::Yield | ApproachControl::Stop => TurnPriority::Yield,
+            },
         }
     }
 
+    // Cycles through no sign, yield, and stop
     pub fn flip_sign(&mut self, r: RoadID) {
         let ss = self.roads.get_mut(&r).unwrap();
-        ss.must_stop = !ss.must_stop;
+        ss.control = match ss.control {
+            ApproachControl::Priority => ApproachControl::Yield,
+            ApproachControl::Yield => ApproachControl::Stop,
+            ApproachControl::Stop => ApproachControl::Priority,
+        };
+    }
+
+    // Swaps every stop sign for a yield sign. Returns true if this did anything.
+    pub fn convert_to_yields(&mut self) -> bool {
+        let mut changed = false;
+        for ss in self.roads.values_mut() {
+            if ss.control == ApproachControl::Stop {
+                ss.control = ApproachControl::Yield;
+                changed = true;
+            }
+        }
+        changed
     }
 }
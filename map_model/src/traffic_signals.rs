@@ -1,6 +1,7 @@
 use crate::make::traffic_signals::{brute_force, get_possible_policies};
 use crate::{
-    DirectedRoadID, IntersectionID, Map, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType,
+    DirectedRoadID, IntersectionID, Map, RoadID, TurnGroup, TurnGroupID, TurnID, TurnPriority,
+    TurnType,
 };
 use abstutil::{deserialize_btreemap, retain_btreeset, serialize_btreemap, Timer};
 use geom::{Duration, Time};
@@ -56,6 +57,17 @@ impl ControlTrafficSignal {
         cycle_length
     }
 
+    // Stretches or squishes every phase to match the new cycle length.
+    pub fn with_cycle_length(&self, cycle_length: Duration) -> ControlTrafficSignal {
+        let mut signal = self.clone();
+        let ratio = cycle_length / self.cycle_length();
+        for phase in &mut signal.phases {
+            phase.duration = phase.duration * ratio;
+        }
+        signal.offset = signal.offset % cycle_length;
+        signal
+    }
+
     pub fn current_phase_and_remaining_time(&self, now: Time) -> (usize, &Phase, Duration) {
         let mut now_offset = ((now + self.offset) - Time::START_OF_DAY) % self.cycle_length();
         for (idx, p) in self.phases.iter().enumerate() {
@@ -158,6 +170,64 @@ impl ControlTrafficSignal {
         }
         self != &orig
     }
+
+    // Gives pedestrians a head start, so they're visible in the crosswalk before turning vehicles
+    // get a green light. Every phase with crosswalks is split in two: first just the crosswalks,
+    // then everything as before. The cycle length doesn't change. Phases too short to split are
+    // left alone. Returns true if this did anything.
+    pub fn add_leading_pedestrian_intervals(&mut self, head_start: Duration) -> bool {
+        if head_start <= Duration::ZERO {
+            return false;
+        }
+        let mut phases: Vec<Phase> = Vec::new();
+        let mut changed = false;
+        for mut phase in self.phases.drain(..) {
+            let crosswalks: BTreeSet<TurnGroupID> = phase
+                .protected_groups
+                .iter()
+                .filter(|g| g.crosswalk)
+                .cloned()
+                .collect();
+            let walk_only =
+                crosswalks.len() == phase.protected_groups.len() && phase.yield_groups.is_empty();
+            let already_split = phases
+                .last()
+                .map(|prev| prev.protected_groups == crosswalks && prev.yield_groups.is_empty())
+                .unwrap_or(false);
+            if crosswalks.is_empty()
+                || walk_only
+                || already_split
+                || phase.duration < head_start * 2.0
+            {
+                phases.push(phase);
+                continue;
+            }
+
+            phases.push(Phase {
+                protected_groups: crosswalks,
+                yield_groups: BTreeSet::new(),
+                duration: head_start,
+            });
+            phase.duration = phase.duration - head_start;
+            phases.push(phase);
+            changed = true;
+        }
+        self.phases = phases;
+        changed
+    }
+
+    // A walk-only phase right before one protecting the same crosswalks and more, like the ones
+    // add_leading_pedestrian_intervals makes. Pedestrians starting during it keep their green
+    // into the next phase.
+    pub fn is_leading_pedestrian_interval(&self, idx: usize) -> bool {
+        let phase = &self.phases[idx];
+        let next = &self.phases[(idx + 1) % self.phases.len()];
+        !phase.protected_groups.is_empty()
+            && phase.yield_groups.is_empty()
+            && phase.protected_groups.iter().all(|g| g.crosswalk)
+            && phase.protected_groups.is_subset(&next.protected_groups)
+            && next.protected_groups.len() > phase.protected_groups.len()
+    }
 }
 
 impl Phase {
@@ -194,6 +264,33 @@ impl Phase {
         self.get_priority_of_group(g)
     }
 
+    // Is this turn only allowed during this phase because it's a right turn on red? That's when
+    // it's permitted, but nothing else coming from the same road is. When driving on the left,
+    // it's a left turn on red.
+    pub fn is_right_turn_on_red(
+        &self,
+        t: TurnID,
+        parent: &ControlTrafficSignal,
+        map: &Map,
+    ) -> bool {
+        let right_turn = map.get_driving_side().right_turn();
+        let g = match parent.turn_groups.values().find(|g| g.members.contains(&t)) {
+            Some(g) => g,
+            None => {
+                return false;
+            }
+        };
+        if g.turn_type != right_turn || !self.yield_groups.contains(&g.id) {
+            return false;
+        }
+        !parent.turn_groups.values().any(|other| {
+            other.id.from == g.id.from
+                && other.turn_type != right_turn
+                && other.turn_type != TurnType::Crosswalk
+                && self.get_priority_of_group(other.id) != TurnPriority::Banned
+        })
+    }
+
     pub fn get_priority_of_group(&self, g: TurnGroupID) -> TurnPriority {
         if self.protected_groups.contains(&g) {
             TurnPriority::Protected
@@ -226,6 +323,110 @@ impl Phase {
     }
 }
 
+// Several signals along one route, sharing a cycle length, with offsets staggered so vehicles
+// moving along the corridor can hit a sequence of green lights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalCorridor {
+    pub name: String,
+    // In order along the corridor. Consecutive members should be connected by a road.
+    pub members: Vec<IntersectionID>,
+}
+
+impl SignalCorridor {
+    // None if the members disagree
+    pub fn cycle_length(&self, map: &Map) -> Option<Duration> {
+        let mut lengths = self
+            .members
+            .iter()
+            .map(|i| map.get_traffic_signal(*i).cycle_length());
+        let first = lengths.next()?;
+        if lengths.all(|x| x == first) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    // The road between each pair of consecutive members
+    pub fn roads(&self, map: &Map) -> Result<Vec<RoadID>, String> {
+        let mut roads = Vec::new();
+        for pair in self.members.windows(2) {
+            let r = map
+                .get_i(pair[0])
+                .roads
+                .iter()
+                .find(|r| {
+                    let r = map.get_r(**r);
+                    (r.src_i == pair[0] && r.dst_i == pair[1])
+                        || (r.src_i == pair[1] && r.dst_i == pair[0])
+                })
+                .ok_or_else(|| format!("No road between {} and {}", pair[0], pair[1]))?;
+            roads.push(*r);
+        }
+        Ok(roads)
+    }
+
+    // Stretches or squishes every phase of every member to match the new cycle length.
+    pub fn with_cycle_length(
+        &self,
+        map: &Map,
+        cycle_length: Duration,
+    ) -> Vec<ControlTrafficSignal> {
+        self.members
+            .iter()
+            .map(|i| map.get_traffic_signal(*i).with_cycle_length(cycle_length))
+            .collect()
+    }
+
+    // Sets offsets so that a vehicle leaving the first member when the corridor turns green,
+    // traveling at the speed limit, arrives at each later member right as it turns green. Assumes
+    // the cycle lengths already match. The first member's offset doesn't change.
+    pub fn green_wave(&self, map: &Map) -> Result<Vec<ControlTrafficSignal>, String> {
+        let cycle_length = self
+            .cycle_length(map)
+            .ok_or_else(|| format!("The signals in {} have different cycle lengths", self.name))?;
+        let roads = self.roads(map)?;
+        if roads.is_empty() {
+            return Err(format!("{} only has one signal", self.name));
+        }
+
+        let first = map.get_traffic_signal(self.members[0]);
+        let first_start = green_start(first, |g| g.to.id == roads[0]);
+        let mut travel_time = Duration::ZERO;
+        let mut results = vec![first.clone()];
+        for (idx, r) in roads.iter().enumerate() {
+            let road = map.get_r(*r);
+            travel_time += road.center_pts.length() / road.speed_limit;
+
+            let mut signal = map.get_traffic_signal(self.members[idx + 1]).clone();
+            let start = green_start(&signal, |g| g.from.id == *r);
+            let mut offset = (start - first_start + first.offset - travel_time) % cycle_length;
+            if offset < Duration::ZERO {
+                offset = offset + cycle_length;
+            }
+            signal.offset = offset;
+            results.push(signal);
+        }
+        Ok(results)
+    }
+}
+
+// How far into the cycle does the first phase protecting a matching group begin?
+fn green_start<F: Fn(&TurnGroupID) -> bool>(signal: &ControlTrafficSignal, pred: F) -> Duration {
+    let mut start = Duration::ZERO;
+    for phase in &signal.phases {
+        if phase
+            .protected_groups
+            .iter()
+            .any(|g| !g.crosswalk && pred(g))
+        {
+            return start;
+        }
+        start += phase.duration;
+    }
+    Duration::ZERO
+}
+
 impl ControlTrafficSignal {
     pub fn export(&self, map: &Map) -> seattle_traffic_signals::TrafficSignal {
         seattle_traffic_signals::TrafficSignal {
@@ -1,5 +1,5 @@
 use crate::{LaneID, Map, TurnID};
-use geom::{Angle, Distance, PolyLine, Pt2D, Speed};
+use geom::{Angle, Distance, PolyLine, Pt2D, SliceError, Speed, Time};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -122,13 +122,30 @@ impl Traversable {
         }
     }
 
-    pub fn slice(&self, start: Distance, end: Distance, map: &Map) -> Option<(PolyLine, Distance)> {
+    pub fn slice(
+        &self,
+        start: Distance,
+        end: Distance,
+        map: &Map,
+    ) -> Result<(PolyLine, Distance), SliceError> {
         match *self {
             Traversable::Lane(id) => map.get_l(id).lane_center_pts.slice(start, end),
             Traversable::Turn(id) => map.get_t(id).geom.slice(start, end),
         }
     }
 
+    pub fn maybe_exact_slice(
+        &self,
+        start: Distance,
+        end: Distance,
+        map: &Map,
+    ) -> Result<PolyLine, SliceError> {
+        match *self {
+            Traversable::Lane(id) => map.get_l(id).lane_center_pts.maybe_exact_slice(start, end),
+            Traversable::Turn(id) => map.get_t(id).geom.maybe_exact_slice(start, end),
+        }
+    }
+
     pub fn exact_slice(&self, start: Distance, end: Distance, map: &Map) -> PolyLine {
         match *self {
             Traversable::Lane(id) => map.get_l(id).lane_center_pts.exact_slice(start, end),
@@ -143,6 +160,14 @@ impl Traversable {
         }
     }
 
+    // Includes any limits that only apply at some times of day
+    pub fn speed_limit_at(&self, map: &Map, now: Time) -> Speed {
+        match *self {
+            Traversable::Lane(id) => map.get_parent(id).speed_limit_at(now),
+            Traversable::Turn(id) => map.get_parent(id.dst).speed_limit_at(now),
+        }
+    }
+
     pub fn get_zorder(&self, map: &Map) -> isize {
         match *self {
             Traversable::Lane(id) => map.get_parent(id).zorder,
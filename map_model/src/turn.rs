@@ -1,4 +1,4 @@
-use crate::{DirectedRoadID, IntersectionID, LaneID, Map};
+use crate::{CrosswalkID, DirectedRoadID, IntersectionID, LaneID, Map};
 use abstutil::MultiMap;
 use geom::{Angle, Distance, PolyLine, Pt2D};
 use serde::{Deserialize, Serialize};
@@ -36,14 +36,13 @@ pub enum TurnType {
 
 impl TurnType {
     pub fn from_angles(from: Angle, to: Angle) -> TurnType {
-        let diff = from.shortest_rotation_towards(to).normalized_degrees();
-        if diff < 10.0 || diff > 350.0 {
+        // y points down in map-space, so positive rotation is clockwise on screen
+        let rotation = from.signed_degrees_towards(to);
+        if rotation.abs() < 10.0 {
             TurnType::Straight
-        } else if diff > 180.0 {
-            // Clockwise rotation
+        } else if rotation > 0.0 {
             TurnType::Right
         } else {
-            // Counter-clockwise rotation
             TurnType::Left
         }
     }
@@ -109,6 +108,23 @@ impl Turn {
     pub fn between_sidewalks(&self) -> bool {
         self.turn_type == TurnType::SharedSidewalkCorner || self.turn_type == TurnType::Crosswalk
     }
+
+    // Only for crosswalks. All of the turns making up one physical crosswalk agree on this.
+    pub fn crosswalk_id(&self, map: &Map) -> Option<CrosswalkID> {
+        if self.turn_type != TurnType::Crosswalk {
+            return None;
+        }
+        let canonical = self
+            .other_crosswalk_ids
+            .iter()
+            .chain(std::iter::once(&self.id))
+            .min()
+            .unwrap();
+        Some(CrosswalkID {
+            i: self.id.parent,
+            r: map.get_l(canonical.src).parent,
+        })
+    }
 }
 
 // One road usually has 4 crosswalks, each a singleton TurnGroup. We need all of the information
@@ -0,0 +1,149 @@
+use abstutil::Timer;
+use map_model::{Map, PermanentMapEdits, MAP_FORMAT_VERSION};
+use serde_json::Value;
+use sim::Scenario;
+
+// Brings files written by older releases up to the current schema, so they don't have to be
+// regenerated from scratch. Each function returns a description of what changed, or None if the
+// file was already current.
+
+// Old maps that still deserialize just get the versioned header. Otherwise, rebuild from the raw
+// map, if it's around.
+pub fn upgrade_map(path: &str, dry_run: bool, timer: &mut Timer) -> Result<Option<String>, String> {
+    let (version, bytes) =
+        abstutil::read_versioned_bytes(path, timer).map_err(|err| err.to_string())?;
+    if version == MAP_FORMAT_VERSION {
+        return Ok(None);
+    }
+    if version > MAP_FORMAT_VERSION {
+        return Err(format!(
+            "version {} is newer than this build ({})",
+            version, MAP_FORMAT_VERSION
+        ));
+    }
+
+    let (map, how) = match abstutil::from_binary::<Map>(&bytes) {
+        Ok(map) => (map, format!("added version header (was {})", version)),
+        Err(err) => {
+            let name = abstutil::basename(path);
+            let raw_path = abstutil::path_raw_map(&name);
+            if !abstutil::file_exists(raw_path.clone()) {
+                return Err(format!(
+                    "version {} doesn't deserialize ({}), and {} is missing",
+                    version, err, raw_path
+                ));
+            }
+            if dry_run {
+                return Ok(Some(format!("would rebuild from {}", raw_path)));
+            }
+            // The raw map might be outdated too, in which case it has to be imported again
+            let raw = abstutil::maybe_read_binary(raw_path.clone(), timer).map_err(|err| {
+                format!(
+                    "{} is also outdated ({}); re-run the importer",
+                    raw_path, err
+                )
+            })?;
+            (
+                Map::create_from_raw(raw, true, timer),
+                format!("rebuilt from {}", raw_path),
+            )
+        }
+    };
+    if !dry_run {
+        abstutil::write_versioned_binary(path.to_string(), MAP_FORMAT_VERSION, &map);
+    }
+    Ok(Some(how))
+}
+
+// Edits are JSON, so older versions are fixed up field-by-field before parsing.
+pub fn upgrade_edits(path: &str, dry_run: bool) -> Result<Option<String>, String> {
+    let raw = abstutil::slurp_file(path).map_err(|err| err.to_string())?;
+    let mut value: Value = serde_json::from_slice(&raw).map_err(|err| err.to_string())?;
+
+    let mut changes = Vec::new();
+    for (name, fix) in EDIT_MIGRATIONS {
+        if fix(&mut value) {
+            changes.push(*name);
+        }
+    }
+    if changes.is_empty() {
+        return Ok(None);
+    }
+
+    // Make sure the result actually matches the current schema
+    let edits: PermanentMapEdits =
+        serde_json::from_value(value).map_err(|err| format!("still invalid: {}", err))?;
+    if !dry_run {
+        abstutil::write_json(path.to_string(), &edits);
+    }
+    Ok(Some(changes.join(", ")))
+}
+
+// Scenarios aren't versioned yet, so just make sure they still load and match the map, including
+// every building, border, and bus stop they refer to.
+pub fn check_scenario(path: &str, map: &Map, timer: &mut Timer) -> Result<Option<String>, String> {
+    let scenario: Scenario =
+        abstutil::maybe_read_binary(path.to_string(), timer).map_err(|err| err.to_string())?;
+    if scenario.map_name != map.get_name() {
+        return Err(format!(
+            "belongs to {}, not {}",
+            scenario.map_name,
+            map.get_name()
+        ));
+    }
+    scenario.check_ids(map)?;
+    Ok(None)
+}
+
+// Each of these returns true if it changed something. They have to be idempotent.
+const EDIT_MIGRATIONS: &[(&str, fn(&mut Value) -> bool)] = &[
+    ("added proposal_description", add_proposal_description),
+    ("added proposal_link", add_proposal_link),
+    ("added signal_corridors", add_signal_corridors),
+    ("added yield signs to stop signs", add_stop_sign_yields),
+];
+
+fn add_proposal_description(value: &mut Value) -> bool {
+    add_missing(value, "proposal_description", Value::Array(Vec::new()))
+}
+
+fn add_proposal_link(value: &mut Value) -> bool {
+    add_missing(value, "proposal_link", Value::Null)
+}
+
+fn add_signal_corridors(value: &mut Value) -> bool {
+    add_missing(value, "signal_corridors", Value::Array(Vec::new()))
+}
+
+// Stop signs show up in the old and new state of intersection edits
+fn add_stop_sign_yields(value: &mut Value) -> bool {
+    let mut changed = false;
+    match value {
+        Value::Object(ref mut map) => {
+            for (key, inner) in map.iter_mut() {
+                if key == "StopSign" {
+                    changed |= add_missing(inner, "yields", Value::Array(Vec::new()));
+                } else {
+                    changed |= add_stop_sign_yields(inner);
+                }
+            }
+        }
+        Value::Array(ref mut list) => {
+            for inner in list {
+                changed |= add_stop_sign_yields(inner);
+            }
+        }
+        _ => {}
+    }
+    changed
+}
+
+fn add_missing(value: &mut Value, key: &str, default: Value) -> bool {
+    if let Value::Object(ref mut map) = value {
+        if !map.contains_key(key) {
+            map.insert(key.to_string(), default);
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,57 @@
+use abstutil::{CmdArgs, Timer};
+use map_model::Map;
+
+// Upgrades every map, set of edits, and scenario installed locally. Run from the map_upgrade
+// directory, like the other tools.
+fn main() {
+    let mut args = CmdArgs::new();
+    let dry_run = args.enabled("--dry_run");
+    args.done();
+
+    let mut timer = Timer::new("upgrade data files");
+    let mut failures = 0;
+    let mut report = |path: String, result: Result<Option<String>, String>| match result {
+        Ok(Some(how)) => {
+            println!("{}: {}", path, how);
+        }
+        Ok(None) => {}
+        Err(err) => {
+            println!("{}: FAILED, {}", path, err);
+            failures += 1;
+        }
+    };
+
+    for name in abstutil::list_all_objects(abstutil::path_all_maps()) {
+        let path = abstutil::path_map(&name);
+        let result = map_upgrade::upgrade_map(&path, dry_run, &mut timer);
+        report(path, result);
+
+        for edits in abstutil::list_all_objects(abstutil::path_all_edits(&name)) {
+            let path = abstutil::path_edits(&name, &edits);
+            report(path.clone(), map_upgrade::upgrade_edits(&path, dry_run));
+        }
+
+        let scenarios = abstutil::list_all_objects(abstutil::path_all_scenarios(&name));
+        if scenarios.is_empty() {
+            continue;
+        }
+        let map = match Map::maybe_load(abstutil::path_map(&name), &mut timer) {
+            Ok(map) => map,
+            Err(_) => {
+                // Already reported above
+                continue;
+            }
+        };
+        for scenario in scenarios {
+            let path = abstutil::path_scenario(&name, &scenario);
+            let result = map_upgrade::check_scenario(&path, &map, &mut timer);
+            report(path, result);
+        }
+    }
+
+    timer.done();
+    if failures > 0 {
+        println!("{} files couldn't be upgraded", failures);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,155 @@
+// Python bindings for running the simulation headlessly. Build with maturin, then:
+//
+//   import abstreet
+//   sim = abstreet.Simulation("montlake", "weekday")
+//   sim.apply_edits(open("../data/player/edits/montlake/bike lanes.json").read())
+//   sim.run_until("12:00:00")
+//   for (trip, finished, mode, duration) in sim.finished_trips():
+//       ...
+//
+// Times and durations are plain seconds. Like the rest of the tools, this expects to run from
+// a directory where ../data exists.
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::{Map, PermanentMapEdits};
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use sim::{Scenario, Sim, SimFlags};
+use std::collections::BTreeMap;
+
+#[pyclass]
+struct Simulation {
+    map: Map,
+    sim: Sim,
+    scenario: Scenario,
+    flags: SimFlags,
+}
+
+#[pymethods]
+impl Simulation {
+    #[new]
+    fn new(map_name: &str, scenario_name: &str) -> PyResult<Simulation> {
+        let mut timer = Timer::new(format!("load {} / {}", map_name, scenario_name));
+        let map_path = abstutil::path_map(map_name);
+        if !abstutil::file_exists(map_path.clone()) {
+            return Err(err(format!("{} doesn't exist", map_path)));
+        }
+        let map = Map::new(map_path, &mut timer);
+        let scenario: Scenario = abstutil::maybe_read_binary(
+            abstutil::path_scenario(map_name, scenario_name),
+            &mut timer,
+        )
+        .map_err(|e| err(format!("Couldn't load scenario {}: {}", scenario_name, e)))?;
+        let flags = SimFlags::synthetic_test(map_name, "python");
+        let mut sim = Simulation {
+            sim: Sim::new(&map, flags.opts.clone(), &mut timer),
+            map,
+            scenario,
+            flags,
+        };
+        sim.reset();
+        Ok(sim)
+    }
+
+    // Restart the scenario from midnight.
+    fn reset(&mut self) {
+        let mut timer = Timer::new("reset sim");
+        let mut rng = self.flags.make_rng();
+        self.sim = Sim::new(&self.map, self.flags.opts.clone(), &mut timer);
+        self.scenario
+            .instantiate(&mut self.sim, &self.map, &mut rng, &mut timer);
+    }
+
+    // Takes PermanentMapEdits as JSON, the same format the game saves. Replaces any existing
+    // edits and restarts the sim.
+    fn apply_edits(&mut self, json: &str) -> PyResult<()> {
+        let perma: PermanentMapEdits =
+            abstutil::from_json(json.as_bytes()).map_err(|e| err(e.to_string()))?;
+        let edits = PermanentMapEdits::from_permanent(perma, &self.map).map_err(err)?;
+        let mut timer = Timer::new("apply edits");
+        self.map.apply_edits(edits, &mut timer);
+        self.map.recalculate_pathfinding_after_edits(&mut timer);
+        self.reset();
+        Ok(())
+    }
+
+    // Seconds since midnight
+    fn time(&self) -> f64 {
+        self.sim.time().inner_seconds()
+    }
+
+    // Accepts anything Time::parse does, like "07:30:00".
+    fn run_until(&mut self, time: &str) -> PyResult<()> {
+        let t = Time::parse(time).map_err(|e| err(e.to_string()))?;
+        if t <= self.sim.time() {
+            return Err(err(format!(
+                "{} is in the past; the sim is at {}",
+                t,
+                self.sim.time()
+            )));
+        }
+        let dt = t - self.sim.time();
+        self.sim
+            .timed_step(&self.map, dt, &mut None, &mut Timer::throwaway());
+        Ok(())
+    }
+
+    fn run_until_done(&mut self) {
+        self.sim
+            .run_until_done(&self.map, |_, _| {}, None, &mut None);
+    }
+
+    fn is_done(&self) -> bool {
+        self.sim.is_done()
+    }
+
+    // (trip ID, finish time, mode, duration). The mode is None if the trip was aborted.
+    fn finished_trips(&self) -> Vec<(usize, f64, Option<String>, f64)> {
+        self.sim
+            .get_analytics()
+            .finished_trips
+            .iter()
+            .map(|(t, id, mode, duration)| {
+                (
+                    id.0,
+                    t.inner_seconds(),
+                    mode.map(|m| m.ongoing_verb().to_string()),
+                    duration.inner_seconds(),
+                )
+            })
+            .collect()
+    }
+
+    // How many agents crossed each road so far, keyed by road ID
+    fn road_thruput(&self) -> BTreeMap<usize, usize> {
+        let mut result = BTreeMap::new();
+        for ((r, _, _), count) in &self.sim.get_analytics().road_thruput.counts {
+            *result.entry(r.0).or_insert(0) += count;
+        }
+        result
+    }
+
+    // Total delay per intersection, keyed by intersection ID
+    fn intersection_delays(&self) -> BTreeMap<usize, f64> {
+        self.sim
+            .get_analytics()
+            .intersection_delays
+            .iter()
+            .map(|(i, delays)| {
+                let total: Duration = delays.iter().map(|(_, dt, _, _)| *dt).sum();
+                (i.0, total.inner_seconds())
+            })
+            .collect()
+    }
+}
+
+fn err(msg: String) -> PyErr {
+    PyErr::new::<ValueError, _>(msg)
+}
+
+#[pymodule]
+fn abstreet(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Simulation>()?;
+    Ok(())
+}
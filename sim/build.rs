@@ -0,0 +1,16 @@
+use std::process::Command;
+
+// Remember which code the binary was built from, for RunManifest
+fn main() {
+    let version = Command::new("git")
+        .args(&["describe", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|x| x.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ABST_CODE_VERSION={}", version);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/index");
+}
@@ -1,12 +1,22 @@
-use crate::{AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType};
-use abstutil::Counter;
+use crate::{
+    AlertLocation, CarID, ConflictType, Event, ParkingSpot, TripID, TripMode, TripPhaseType,
+};
+use abstutil::{Counter, Timer};
 use geom::{Distance, Duration, Histogram, Time};
 use map_model::{
     BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathRequest, RoadID,
     Traversable, TurnGroupID,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+// Metrics are also aggregated in bins this long, the way traffic counts are usually reported.
+pub const TIME_BIN: Duration = Duration::const_seconds(15.0 * 60.0);
+
+// Prebaked results are only useful if they deserialize exactly; a change in any field's layout
+// would otherwise be silently misread. Bump this whenever Analytics changes.
+// 1: Intersection delays record the road the agent arrived from
+pub const ANALYTICS_FORMAT_VERSION: u32 = 1;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Analytics {
@@ -17,13 +27,19 @@ pub struct Analytics {
     pub demand: BTreeMap<TurnGroupID, usize>,
     pub bus_arrivals: Vec<(Time, CarID, BusRouteID, BusStopID)>,
     pub bus_passengers_waiting: Vec<(Time, BusStopID, BusRouteID)>,
+    // Time, route, stop, passengers aboard when leaving, capacity, riders left behind by a full
+    // bus
+    pub bus_loads: Vec<(Time, BusRouteID, BusStopID, usize, usize, usize)>,
     pub started_trips: BTreeMap<TripID, Time>,
     // TODO Hack: No TripMode means aborted
     // Finish time, ID, mode (or None as aborted), trip duration
     pub finished_trips: Vec<(Time, TripID, Option<TripMode>, Duration)>,
     // TODO This subsumes finished_trips
     pub trip_log: Vec<(Time, TripID, Option<PathRequest>, TripPhaseType)>,
-    pub intersection_delays: BTreeMap<IntersectionID, Vec<(Time, Duration, TripMode)>>,
+    // Only at traffic signals. The RoadID is the one the agent arrived from.
+    pub intersection_delays: BTreeMap<IntersectionID, Vec<(Time, Duration, TripMode, RoadID)>>,
+    // Surrogate safety measures, at any kind of intersection
+    pub intersection_conflicts: BTreeMap<IntersectionID, Vec<(Time, ConflictType)>>,
     // Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
@@ -36,6 +52,32 @@ pub struct Analytics {
 }
 
 impl Analytics {
+    pub fn save_prebaked(&self, map_name: &str, scenario_name: &str) {
+        abstutil::write_versioned_binary(
+            abstutil::path_prebaked_results(map_name, scenario_name),
+            ANALYTICS_FORMAT_VERSION,
+            self,
+        );
+    }
+
+    // Results baked with a different layout are refused; they need to be regenerated.
+    pub fn load_prebaked(
+        map_name: &str,
+        scenario_name: &str,
+        timer: &mut abstutil::Timer,
+    ) -> Result<Analytics, String> {
+        let path = abstutil::path_prebaked_results(map_name, scenario_name);
+        let (version, bytes) =
+            abstutil::read_versioned_bytes(&path, timer).map_err(|err| err.to_string())?;
+        if version != ANALYTICS_FORMAT_VERSION {
+            return Err(format!(
+                "{} is version {}, but this build needs {}; prebake again",
+                path, version, ANALYTICS_FORMAT_VERSION
+            ));
+        }
+        abstutil::from_binary(&bytes).map_err(|err| format!("{}: {}", path, err))
+    }
+
     pub fn new() -> Analytics {
         Analytics {
             road_thruput: TimeSeriesCount::new(),
@@ -43,10 +85,12 @@ impl Analytics {
             demand: BTreeMap::new(),
             bus_arrivals: Vec::new(),
             bus_passengers_waiting: Vec::new(),
+            bus_loads: Vec::new(),
             started_trips: BTreeMap::new(),
             finished_trips: Vec::new(),
             trip_log: Vec::new(),
             intersection_delays: BTreeMap::new(),
+            intersection_conflicts: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
             alerts: Vec::new(),
@@ -94,6 +138,20 @@ impl Analytics {
             }
         }
 
+        // Bus crowding
+        if let Event::BusLoadMeasured {
+            route,
+            stop,
+            passengers,
+            capacity,
+            left_behind,
+            ..
+        } = ev
+        {
+            self.bus_loads
+                .push((time, route, stop, passengers, capacity, left_behind));
+        }
+
         // Started trips
         if let Event::TripPhaseStarting(id, _, _, _) = ev {
             self.started_trips.entry(id).or_insert(time);
@@ -115,11 +173,19 @@ impl Analytics {
         }
 
         // Intersection delays
-        if let Event::IntersectionDelayMeasured(id, delay, mode) = ev {
+        if let Event::IntersectionDelayMeasured(t, delay, mode) = ev {
             self.intersection_delays
-                .entry(id)
+                .entry(t.parent)
                 .or_insert_with(Vec::new)
-                .push((time, delay, mode));
+                .push((time, delay, mode, map.get_l(t.src).parent));
+        }
+
+        // Conflicts
+        if let Event::ConflictMeasured(i, conflict) = ev {
+            self.intersection_conflicts
+                .entry(i)
+                .or_insert_with(Vec::new)
+                .push((time, conflict));
         }
 
         // Parking spot changes
@@ -230,13 +296,33 @@ impl Analytics {
         results
     }
 
+    // How many conflicts of one type (or any type) have happened at each intersection so far?
+    pub fn conflicts_per_intersection(
+        &self,
+        now: Time,
+        filter: Option<ConflictType>,
+    ) -> Counter<IntersectionID> {
+        let mut cnt = Counter::new();
+        for (i, list) in &self.intersection_conflicts {
+            for (t, conflict) in list {
+                if *t > now {
+                    break;
+                }
+                if filter.map(|f| f == *conflict).unwrap_or(true) {
+                    cnt.inc(*i);
+                }
+            }
+        }
+        cnt
+    }
+
     // Find intersections where the cumulative sum of delay has changed. Negative means faster.
     pub fn compare_delay(&self, now: Time, before: &Analytics) -> Vec<(IntersectionID, Duration)> {
         let mut results = Vec::new();
         for (i, list1) in &self.intersection_delays {
             if let Some(list2) = before.intersection_delays.get(i) {
                 let mut sum1 = Duration::ZERO;
-                for (t, dt, _) in list1 {
+                for (t, dt, _, _) in list1 {
                     if *t > now {
                         break;
                     }
@@ -244,7 +330,7 @@ impl Analytics {
                 }
 
                 let mut sum2 = Duration::ZERO;
-                for (t, dt, _) in list2 {
+                for (t, dt, _, _) in list2 {
                     if *t > now {
                         break;
                     }
@@ -365,6 +451,29 @@ impl Analytics {
             .collect()
     }
 
+    // Up to some moment in time, how full were buses leaving each stop of a route (as a percent of
+    // capacity), and how many riders in total were left behind by full buses?
+    pub fn bus_crowding(
+        &self,
+        now: Time,
+        r: BusRouteID,
+    ) -> BTreeMap<BusStopID, (Histogram<usize>, usize)> {
+        let mut per_stop: BTreeMap<BusStopID, (Histogram<usize>, usize)> = BTreeMap::new();
+        for (t, route, stop, passengers, capacity, left_behind) in &self.bus_loads {
+            if *t > now {
+                break;
+            }
+            if *route == r {
+                let entry = per_stop
+                    .entry(*stop)
+                    .or_insert_with(|| (Histogram::new(), 0));
+                entry.0.add(100 * passengers / capacity);
+                entry.1 += left_behind;
+            }
+        }
+        per_stop
+    }
+
     pub fn get_trip_phases(&self, trip: TripID, map: &Map) -> Vec<TripPhase> {
         let mut phases: Vec<TripPhase> = Vec::new();
         for (t, id, maybe_req, phase_type) in &self.trip_log {
@@ -393,6 +502,43 @@ impl Analytics {
         phases
     }
 
+    // Like get_trip_phases for many trips at once, scanning the log just once.
+    pub fn get_some_trip_phases(
+        &self,
+        trips: &BTreeSet<TripID>,
+        map: &Map,
+        timer: &mut Timer,
+    ) -> BTreeMap<TripID, Vec<TripPhase>> {
+        let mut results: BTreeMap<TripID, Vec<TripPhase>> = BTreeMap::new();
+        let mut done: BTreeSet<TripID> = BTreeSet::new();
+        timer.start_iter("calculate trip phases", self.trip_log.len());
+        for (t, id, maybe_req, phase_type) in &self.trip_log {
+            timer.next();
+            if !trips.contains(id) || done.contains(id) {
+                continue;
+            }
+            let phases = results.entry(*id).or_insert_with(Vec::new);
+            if let Some(ref mut last) = phases.last_mut() {
+                last.end_time = Some(*t);
+            }
+            if *phase_type == TripPhaseType::Finished || *phase_type == TripPhaseType::Aborted {
+                done.insert(*id);
+                continue;
+            }
+            phases.push(TripPhase {
+                start_time: *t,
+                end_time: None,
+                path: maybe_req.as_ref().and_then(|req| {
+                    map.pathfind(req.clone())
+                        .map(|path| (req.start.dist_along(), path))
+                }),
+                has_path_req: maybe_req.is_some(),
+                phase_type: *phase_type,
+            })
+        }
+        results
+    }
+
     pub fn get_all_trip_phases(&self) -> BTreeMap<TripID, Vec<TripPhase>> {
         let mut trips = BTreeMap::new();
         for (t, id, maybe_req, phase_type) in &self.trip_log {
@@ -546,6 +692,9 @@ pub struct TripPhase {
 pub struct TimeSeriesCount<X: Ord + Clone> {
     // (Road or intersection, mode, hour block) -> count for that hour
     pub counts: BTreeMap<(X, TripMode, usize), usize>,
+    // The same, but in blocks of TIME_BIN
+    #[serde(default)]
+    pub binned: BTreeMap<(X, TripMode, usize), usize>,
 
     // Very expensive to store, so it's optional. But useful to flag on to experiment with
     // representations better than the hour count above.
@@ -556,6 +705,7 @@ impl<X: Ord + Clone> TimeSeriesCount<X> {
     fn new() -> TimeSeriesCount<X> {
         TimeSeriesCount {
             counts: BTreeMap::new(),
+            binned: BTreeMap::new(),
             raw: Vec::new(),
         }
     }
@@ -567,7 +717,8 @@ impl<X: Ord + Clone> TimeSeriesCount<X> {
         }
 
         let hour = time.get_parts().0;
-        *self.counts.entry((id, mode, hour)).or_insert(0) += 1;
+        *self.counts.entry((id.clone(), mode, hour)).or_insert(0) += 1;
+        *self.binned.entry((id, mode, time_bin(time))).or_insert(0) += 1;
     }
 
     pub fn total_for(&self, id: X) -> usize {
@@ -652,6 +803,11 @@ impl<X: Ord + Clone> TimeSeriesCount<X> {
     }
 }
 
+// Which TIME_BIN this falls in, counting from midnight
+pub fn time_bin(time: Time) -> usize {
+    (time.inner_seconds() / TIME_BIN.inner_seconds()) as usize
+}
+
 struct Window {
     times: VecDeque<Time>,
     window_size: Duration,
@@ -0,0 +1,222 @@
+use crate::{AgentID, Sim};
+use geom::{Duration, Time};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
+// A/B testing is only meaningful if running the same sim twice gives exactly the same result.
+// These catch the first moment two runs disagree, either by comparing two sims stepped side by
+// side, or by comparing a run against a trace recorded earlier.
+
+// The full state of every car and pedestrian at one moment
+pub struct Snapshot {
+    pub time: Time,
+    agents: BTreeMap<AgentID, String>,
+}
+
+impl Snapshot {
+    pub fn new(sim: &Sim) -> Snapshot {
+        Snapshot {
+            time: sim.time(),
+            agents: sim.agent_states(),
+        }
+    }
+
+    fn hashes(&self) -> BTreeMap<AgentID, u64> {
+        self.agents
+            .iter()
+            .map(|(id, state)| (*id, hash(state)))
+            .collect()
+    }
+
+    // None if the two match. Otherwise, describes every agent that differs.
+    pub fn diff(&self, other: &Snapshot) -> Option<String> {
+        let mut out = String::new();
+        if self.time != other.time {
+            writeln!(out, "Times differ: {} vs {}", self.time, other.time).unwrap();
+        }
+        for (id, state) in &self.agents {
+            match other.agents.get(id) {
+                Some(other_state) => {
+                    if state != other_state {
+                        writeln!(out, "{} differs:", id).unwrap();
+                        out.push_str(&diff_lines(state, other_state));
+                    }
+                }
+                None => {
+                    writeln!(out, "{} only exists in the first run:\n{}", id, state).unwrap();
+                }
+            }
+        }
+        for (id, state) in &other.agents {
+            if !self.agents.contains_key(id) {
+                writeln!(out, "{} only exists in the second run:\n{}", id, state).unwrap();
+            }
+        }
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+}
+
+// A compact record of one run, so a later run can be checked against it. Each tick only has a
+// hash per agent, and only for agents that changed since the previous tick. The hashes are stable
+// across processes, but a trace should be checked by the same build that recorded it.
+#[derive(Serialize, Deserialize)]
+pub struct DeterminismTrace {
+    pub period: Duration,
+    ticks: Vec<Tick>,
+    #[serde(skip_serializing, skip_deserializing)]
+    latest: BTreeMap<AgentID, u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Tick {
+    time: Time,
+    // None means the agent vanished
+    changed: Vec<(AgentID, Option<u64>)>,
+}
+
+impl DeterminismTrace {
+    pub fn new(period: Duration) -> DeterminismTrace {
+        DeterminismTrace {
+            period,
+            ticks: Vec::new(),
+            latest: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        let hashes = snapshot.hashes();
+        let mut changed = Vec::new();
+        for (id, h) in &hashes {
+            if self.latest.get(id) != Some(h) {
+                changed.push((*id, Some(*h)));
+            }
+        }
+        for id in self.latest.keys() {
+            if !hashes.contains_key(id) {
+                changed.push((*id, None));
+            }
+        }
+        self.ticks.push(Tick {
+            time: snapshot.time,
+            changed,
+        });
+        self.latest = hashes;
+    }
+
+    pub fn num_ticks(&self) -> usize {
+        self.ticks.len()
+    }
+}
+
+// Replays a recorded trace one tick at a time
+pub struct TraceChecker {
+    trace: DeterminismTrace,
+    next_tick: usize,
+    expected: BTreeMap<AgentID, u64>,
+}
+
+impl TraceChecker {
+    pub fn new(trace: DeterminismTrace) -> TraceChecker {
+        TraceChecker {
+            trace,
+            next_tick: 0,
+            expected: BTreeMap::new(),
+        }
+    }
+
+    pub fn period(&self) -> Duration {
+        self.trace.period
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_tick == self.trace.ticks.len()
+    }
+
+    // None if the snapshot matches the next tick of the trace. Since the trace only has hashes,
+    // a mismatch can only show this run's side of each agent that differs.
+    pub fn check(&mut self, snapshot: &Snapshot) -> Option<String> {
+        let tick = match self.trace.ticks.get(self.next_tick) {
+            Some(tick) => tick,
+            None => {
+                return Some(format!("The trace ends before {}", snapshot.time));
+            }
+        };
+        self.next_tick += 1;
+        for (id, h) in &tick.changed {
+            if let Some(h) = h {
+                self.expected.insert(*id, *h);
+            } else {
+                self.expected.remove(id);
+            }
+        }
+
+        let mut out = String::new();
+        if tick.time != snapshot.time {
+            writeln!(
+                out,
+                "Times differ: {} in the trace, {} now",
+                tick.time, snapshot.time
+            )
+            .unwrap();
+        }
+        for (id, h) in snapshot.hashes() {
+            match self.expected.get(&id) {
+                Some(expected) if *expected == h => {}
+                Some(_) => {
+                    writeln!(
+                        out,
+                        "{} differs. Its state now:\n{}",
+                        id, snapshot.agents[&id]
+                    )
+                    .unwrap();
+                }
+                None => {
+                    writeln!(out, "{} isn't in the trace:\n{}", id, snapshot.agents[&id]).unwrap();
+                }
+            }
+        }
+        for id in self.expected.keys() {
+            if !snapshot.agents.contains_key(id) {
+                writeln!(out, "{} is in the trace, but not this run", id).unwrap();
+            }
+        }
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+}
+
+fn hash(state: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Line by line, which is good enough for two pretty-printed dumps of the same struct
+fn diff_lines(before: &str, after: &str) -> String {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+    let mut out = String::new();
+    for idx in 0..before.len().max(after.len()) {
+        let (x, y) = (before.get(idx), after.get(idx));
+        if x == y {
+            continue;
+        }
+        if let Some(x) = x {
+            writeln!(out, "- {}", x).unwrap();
+        }
+        if let Some(y) = y {
+            writeln!(out, "+ {}", y).unwrap();
+        }
+    }
+    out
+}
@@ -1,9 +1,11 @@
 use crate::{
-    AgentID, CarID, OffMapLocation, ParkingSpot, PedestrianID, PersonID, TripID, TripMode,
+    AgentID, CarID, ConflictType, OffMapLocation, ParkingSpot, PedestrianID, PersonID, TripID,
+    TripMode,
 };
 use geom::Duration;
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path, PathRequest, Traversable,
+    TurnID,
 };
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +19,16 @@ pub enum Event {
 
     BusArrivedAtStop(CarID, BusRouteID, BusStopID),
     BusDepartedFromStop(CarID, BusRouteID, BusStopID),
+    // After passengers board and deboard at a stop
+    BusLoadMeasured {
+        bus: CarID,
+        route: BusRouteID,
+        stop: BusStopID,
+        passengers: usize,
+        capacity: usize,
+        // Riders who couldn't board because the bus was full
+        left_behind: usize,
+    },
 
     PersonEntersBuilding(PersonID, BuildingID),
     PersonLeavesBuilding(PersonID, BuildingID),
@@ -30,7 +42,8 @@ pub enum Event {
     BikeStoppedAtSidewalk(CarID, LaneID),
 
     AgentEntersTraversable(AgentID, Traversable),
-    IntersectionDelayMeasured(IntersectionID, Duration, TripMode),
+    IntersectionDelayMeasured(TurnID, Duration, TripMode),
+    ConflictMeasured(IntersectionID, ConflictType),
 
     TripFinished {
         trip: TripID,
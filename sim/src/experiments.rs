@@ -0,0 +1,141 @@
+use crate::{ModelConstants, Sim, SimOptions, TripMode};
+use geom::{Duration, Time};
+use map_model::Map;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Everything that went into a finished run and a few headline results, so runs from dozens of
+// sweeps can be told apart and reproduced later. Each run is stored as its own JSON file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunManifest {
+    // Unique per map
+    pub id: String,
+    // Seconds since the Unix epoch
+    pub finished_at: u64,
+
+    pub map_name: String,
+    // Of the map file. Re-importing a map changes this, and runs before and after aren't
+    // comparable.
+    pub map_hash: String,
+    pub edits_name: String,
+    // Different edits can share a name, like "untitled edits"
+    #[serde(default)]
+    pub edits_hash: String,
+    pub scenario_name: String,
+    pub rng_seed: u8,
+    // From git when the binary was built, if it was built from a checkout
+    pub code_version: String,
+    // See SimOptions::describe
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+    // The initial constants and any calibration during the run
+    #[serde(default)]
+    pub constants: Vec<(Time, ModelConstants)>,
+
+    pub sim_time: Time,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+impl RunManifest {
+    pub fn new(
+        map: &Map,
+        sim: &Sim,
+        opts: &SimOptions,
+        scenario_name: &str,
+        rng_seed: u8,
+    ) -> RunManifest {
+        let finished_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        RunManifest {
+            id: format!("{}_{}", sim.get_run_name(), finished_at),
+            finished_at,
+            map_name: map.get_name().to_string(),
+            map_hash: hash_file(&abstutil::path_map(map.get_name())),
+            edits_name: map.get_edits().edits_name.clone(),
+            edits_hash: map.get_edits().checksum(map),
+            scenario_name: scenario_name.to_string(),
+            rng_seed,
+            code_version: option_env!("ABST_CODE_VERSION")
+                .unwrap_or("unknown")
+                .to_string(),
+            options: opts.describe(),
+            constants: sim.get_model_constants_history().clone(),
+            sim_time: sim.time(),
+            metrics: metrics(sim),
+        }
+    }
+
+    pub fn save(&self) {
+        abstutil::write_json(abstutil::path_experiment(&self.map_name, &self.id), self);
+    }
+
+    // Newest first
+    pub fn load_all(map_name: &str) -> Vec<RunManifest> {
+        let mut list: Vec<RunManifest> =
+            abstutil::load_all_objects(abstutil::path_all_experiments(map_name))
+                .into_iter()
+                .map(|(_, m)| m)
+                .collect();
+        list.sort_by_key(|m| std::cmp::Reverse(m.finished_at));
+        list
+    }
+}
+
+fn metrics(sim: &Sim) -> BTreeMap<String, f64> {
+    let analytics = sim.get_analytics();
+    let mut metrics = BTreeMap::new();
+
+    let mut finished = 0;
+    let mut total_time = Duration::ZERO;
+    for (_, _, maybe_mode, dt) in &analytics.finished_trips {
+        if maybe_mode.is_some() {
+            finished += 1;
+            total_time += *dt;
+        }
+    }
+    metrics.insert("trips_finished".to_string(), finished as f64);
+    metrics.insert(
+        "trips_cancelled".to_string(),
+        (analytics.finished_trips.len() - finished) as f64,
+    );
+    if finished > 0 {
+        metrics.insert(
+            "mean_trip_time_seconds".to_string(),
+            total_time.inner_seconds() / (finished as f64),
+        );
+    }
+
+    let mut total_delay = Duration::ZERO;
+    for list in analytics.intersection_delays.values() {
+        for (_, dt, mode, _) in list {
+            if *mode == TripMode::Drive || *mode == TripMode::Transit {
+                total_delay += *dt;
+            }
+        }
+    }
+    metrics.insert(
+        "total_signal_delay_hours".to_string(),
+        total_delay.inner_seconds() / 3600.0,
+    );
+    metrics.insert(
+        "conflicts".to_string(),
+        analytics
+            .intersection_conflicts
+            .values()
+            .map(|list| list.len())
+            .sum::<usize>() as f64,
+    );
+    if let Some(pandemic) = sim.get_pandemic_model() {
+        metrics.insert("infected".to_string(), pandemic.count_infected() as f64);
+    }
+    metrics
+}
+
+fn hash_file(path: &str) -> String {
+    match abstutil::slurp_file(path) {
+        Ok(bytes) => abstutil::stable_hash(&bytes),
+        Err(_) => "unknown".to_string(),
+    }
+}
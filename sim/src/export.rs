@@ -0,0 +1,486 @@
+use crate::{
+    AgentID, Analytics, CarID, CarStatus, Event, GetDrawAgents, PersonID, Sim, SimCallback, TripID,
+    TripMode, TripPhaseType, VehicleType,
+};
+use abstutil::Timer;
+use geom::{Distance, Duration, PolyLine, Pt2D, Speed, Time};
+use map_model::{LaneID, Map, Traversable};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TraceFormat {
+    GeoJSON,
+    GPX,
+}
+
+impl TraceFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TraceFormat::GeoJSON => "geojson",
+            TraceFormat::GPX => "gpx",
+        }
+    }
+}
+
+// One phase of a trip that actually followed a path, with a timestamp for every point.
+struct Trace {
+    trip: TripID,
+    mode: TripMode,
+    phase: TripPhaseType,
+    pts: Vec<(f64, f64, Time)>,
+}
+
+// Exports the path of every phase of the given trips, so results can be overlaid in QGIS,
+// kepler.gl, etc. Trips that haven't finished (or were aborted) are skipped. The simulation
+// doesn't record exact positions over time, so timestamps are interpolated along each phase,
+// assuming constant speed.
+pub fn export_trip_traces(
+    analytics: &Analytics,
+    trips: &Vec<TripID>,
+    map: &Map,
+    format: TraceFormat,
+    timer: &mut Timer,
+) -> String {
+    let mut modes: BTreeMap<TripID, TripMode> = BTreeMap::new();
+    for (_, id, maybe_mode, _) in &analytics.finished_trips {
+        if let Some(mode) = maybe_mode {
+            modes.insert(*id, *mode);
+        }
+    }
+    let trips: BTreeSet<TripID> = trips
+        .iter()
+        .filter(|id| modes.contains_key(id))
+        .cloned()
+        .collect();
+
+    let mut traces = Vec::new();
+    for (id, phases) in analytics.get_some_trip_phases(&trips, map, timer) {
+        let mode = modes[&id];
+        for phase in phases {
+            let end_time = match phase.end_time {
+                Some(t) => t,
+                None => continue,
+            };
+            if let Some((start_dist, path)) = phase.path {
+                if let Ok(pl) = path.trace(map, start_dist, None) {
+                    traces.push(Trace {
+                        trip: id,
+                        mode,
+                        phase: phase.phase_type,
+                        pts: timestamp_pts(&pl, phase.start_time, end_time, map),
+                    });
+                }
+            }
+        }
+    }
+
+    match format {
+        TraceFormat::GeoJSON => to_geojson(traces, map),
+        TraceFormat::GPX => to_gpx(traces, map),
+    }
+}
+
+fn timestamp_pts(pl: &PolyLine, start: Time, end: Time, map: &Map) -> Vec<(f64, f64, Time)> {
+    let total = pl.length();
+    let mut dist_so_far = Distance::ZERO;
+    let mut result = Vec::new();
+    for (idx, pt) in pl.points().iter().enumerate() {
+        if idx > 0 {
+            dist_so_far += pl.points()[idx - 1].dist_to(*pt);
+        }
+        let pct = if total == Distance::ZERO {
+            0.0
+        } else {
+            (dist_so_far / total).min(1.0)
+        };
+        let gps = pt.forcibly_to_gps(map.get_gps_bounds());
+        result.push((gps.x(), gps.y(), start + pct * (end - start)));
+    }
+    result
+}
+
+#[derive(Serialize)]
+struct FeatureCollection {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    features: Vec<Feature>,
+}
+
+#[derive(Serialize)]
+struct Feature {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    geometry: LineString,
+    properties: Properties,
+}
+
+#[derive(Serialize)]
+struct LineString {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    coordinates: Vec<[f64; 2]>,
+}
+
+#[derive(Serialize)]
+struct Properties {
+    trip: usize,
+    mode: String,
+    phase: String,
+    start_time: String,
+    end_time: String,
+    // Seconds since midnight for every point, parallel to the coordinates. kepler.gl can animate
+    // trips using these.
+    times: Vec<f64>,
+}
+
+fn to_geojson(traces: Vec<Trace>, map: &Map) -> String {
+    let features = traces
+        .into_iter()
+        .map(|t| Feature {
+            kind: "Feature",
+            properties: Properties {
+                trip: t.trip.0,
+                mode: t.mode.ongoing_verb().to_string(),
+                phase: t.phase.describe(map),
+                start_time: t.pts[0].2.to_string(),
+                end_time: t.pts.last().unwrap().2.to_string(),
+                times: t.pts.iter().map(|(_, _, t)| t.inner_seconds()).collect(),
+            },
+            geometry: LineString {
+                kind: "LineString",
+                coordinates: t.pts.into_iter().map(|(x, y, _)| [x, y]).collect(),
+            },
+        })
+        .collect();
+    abstutil::to_json(&FeatureCollection {
+        kind: "FeatureCollection",
+        features,
+    })
+}
+
+fn to_gpx(traces: Vec<Trace>, map: &Map) -> String {
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        out,
+        r#"<gpx version="1.1" creator="abstreet" xmlns="http://www.topografix.com/GPX/1/1">"#
+    )
+    .unwrap();
+    for t in traces {
+        writeln!(out, "  <trk>").unwrap();
+        writeln!(
+            out,
+            "    <name>Trip #{} ({}, {})</name>",
+            t.trip.0,
+            t.mode.ongoing_verb(),
+            escape_xml(&t.phase.describe(map))
+        )
+        .unwrap();
+        writeln!(out, "    <trkseg>").unwrap();
+        for (lon, lat, time) in t.pts {
+            writeln!(
+                out,
+                r#"      <trkpt lat="{}" lon="{}"><time>{}</time></trkpt>"#,
+                lat,
+                lon,
+                iso_timestamp(time)
+            )
+            .unwrap();
+        }
+        writeln!(out, "    </trkseg>").unwrap();
+        writeln!(out, "  </trk>").unwrap();
+    }
+    writeln!(out, "</gpx>").unwrap();
+    out
+}
+
+// The simulation has no notion of a date, so pretend everything happens starting at the epoch.
+fn iso_timestamp(time: Time) -> String {
+    let (hours, minutes, seconds, centis) = time.get_parts();
+    format!(
+        "1970-01-{:02}T{:02}:{:02}:{:02}.{}Z",
+        1 + hours / 24,
+        hours % 24,
+        minutes,
+        seconds,
+        centis
+    )
+}
+
+fn escape_xml(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Samples the position of every moving agent, producing SUMO floating car data
+// (https://sumo.dlr.de/docs/Simulation/Output/FCDOutput.html). Register this as the periodic
+// SimCallback; the frequency passed to set_periodic_callback is the sampling rate. Positions are
+// written like SUMO's --fcd-output.geo, with x as longitude and y as latitude.
+pub struct FcdRecorder {
+    out: String,
+    // Speed isn't tracked directly, so estimate it from the last sample.
+    last_pos: BTreeMap<String, Pt2D>,
+    last_time: Option<Time>,
+}
+
+impl FcdRecorder {
+    pub fn new() -> FcdRecorder {
+        let mut out = String::new();
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(out, "<fcd-export>").unwrap();
+        FcdRecorder {
+            out,
+            last_pos: BTreeMap::new(),
+            last_time: None,
+        }
+    }
+
+    pub fn record(&mut self, sim: &Sim, map: &Map) {
+        let time = sim.time();
+        let dt = self.last_time.map(|t| time - t);
+        let mut agents = Vec::new();
+        for car in sim.get_all_draw_cars(map) {
+            if car.status == CarStatus::Parked {
+                continue;
+            }
+            let kind = match car.id.1 {
+                VehicleType::Car => "car",
+                VehicleType::Bus => "bus",
+                VehicleType::Bike => "bike",
+                VehicleType::Train => "tram",
+            };
+            agents.push((
+                format!("{}{}", kind, car.id.0),
+                kind,
+                car.body.last_pt(),
+                car.body.last_line().angle(),
+                car.on,
+            ));
+        }
+        for ped in sim.get_all_draw_peds(map) {
+            agents.push((
+                format!("ped{}", ped.id.0),
+                "pedestrian",
+                ped.pos,
+                ped.facing,
+                ped.on,
+            ));
+        }
+
+        writeln!(
+            self.out,
+            r#"  <timestep time="{:.2}">"#,
+            time.inner_seconds()
+        )
+        .unwrap();
+        let mut new_pos = BTreeMap::new();
+        for (id, kind, pt, angle, on) in agents {
+            let speed = match (self.last_pos.get(&id), dt) {
+                (Some(last), Some(dt)) if dt > Duration::ZERO => {
+                    Speed::meters_per_second(last.dist_to(pt).inner_meters() / dt.inner_seconds())
+                }
+                _ => Speed::ZERO,
+            };
+            let gps = pt.forcibly_to_gps(map.get_gps_bounds());
+            // SUMO measures angles in degrees clockwise from north. Map-space has y pointing down,
+            // so angles already increase clockwise, but 0 is east.
+            let heading = (angle.normalized_degrees() + 90.0) % 360.0;
+            let lane = match on {
+                Traversable::Lane(l) => l.0.to_string(),
+                // Like SUMO's internal lanes
+                Traversable::Turn(t) => format!(":{}_{}_{}", t.parent.0, t.src.0, t.dst.0),
+            };
+            writeln!(
+                self.out,
+                r#"    <{} id="{}" x="{}" y="{}" angle="{:.2}" type="{}" speed="{:.2}" lane="{}"/>"#,
+                if kind == "pedestrian" {
+                    "person"
+                } else {
+                    "vehicle"
+                },
+                id,
+                gps.x(),
+                gps.y(),
+                heading,
+                kind,
+                speed.inner_meters_per_second(),
+                lane
+            )
+            .unwrap();
+            new_pos.insert(id, pt);
+        }
+        writeln!(self.out, "  </timestep>").unwrap();
+
+        self.last_pos = new_pos;
+        self.last_time = Some(time);
+    }
+
+    pub fn finish(mut self) -> String {
+        writeln!(self.out, "</fcd-export>").unwrap();
+        self.out
+    }
+}
+
+impl SimCallback for FcdRecorder {
+    fn run(&mut self, sim: &Sim, map: &Map) -> bool {
+        self.record(sim, map);
+        false
+    }
+}
+
+// Translates the sim's event stream into MATSim events
+// (https://www.matsim.org/docs/userguide/output-files). Links are lanes and legs are trip
+// phases. Pedestrians don't emit link events, matching how MATSim usually teleports walking legs.
+#[derive(Clone)]
+pub(crate) struct MatsimEvents {
+    out: String,
+    current_link: BTreeMap<CarID, LaneID>,
+    // Who's taking the current leg of each trip, how, and where it ends
+    current_leg: BTreeMap<TripID, (PersonID, &'static str, Option<LaneID>)>,
+}
+
+impl MatsimEvents {
+    pub fn new() -> MatsimEvents {
+        let mut out = String::new();
+        writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#).unwrap();
+        writeln!(out, r#"<events version="1.0">"#).unwrap();
+        MatsimEvents {
+            out,
+            current_link: BTreeMap::new(),
+            current_leg: BTreeMap::new(),
+        }
+    }
+
+    pub fn handle_event(&mut self, time: Time, ev: &Event, map: &Map) {
+        match ev {
+            Event::AgentEntersTraversable(AgentID::Car(car), Traversable::Lane(l)) => {
+                if let Some(prev) = self.current_link.insert(*car, *l) {
+                    self.write(
+                        time,
+                        "left link",
+                        vec![("vehicle", car.0.to_string()), ("link", prev.0.to_string())],
+                    );
+                }
+                self.write(
+                    time,
+                    "entered link",
+                    vec![("vehicle", car.0.to_string()), ("link", l.0.to_string())],
+                );
+            }
+            Event::CarReachedParkingSpot(car, _) | Event::BikeStoppedAtSidewalk(car, _) => {
+                if let Some(prev) = self.current_link.remove(car) {
+                    self.write(
+                        time,
+                        "left link",
+                        vec![("vehicle", car.0.to_string()), ("link", prev.0.to_string())],
+                    );
+                }
+            }
+            Event::BusArrivedAtStop(bus, _, stop) => {
+                self.write(
+                    time,
+                    "VehicleArrivesAtFacility",
+                    vec![
+                        ("vehicle", bus.0.to_string()),
+                        ("facility", format!("stop{}_{}", stop.sidewalk.0, stop.idx)),
+                    ],
+                );
+            }
+            Event::BusDepartedFromStop(bus, _, stop) => {
+                self.write(
+                    time,
+                    "VehicleDepartsAtFacility",
+                    vec![
+                        ("vehicle", bus.0.to_string()),
+                        ("facility", format!("stop{}_{}", stop.sidewalk.0, stop.idx)),
+                    ],
+                );
+            }
+            Event::PersonLeavesBuilding(person, b) => {
+                self.write(
+                    time,
+                    "actend",
+                    vec![
+                        ("person", person.0.to_string()),
+                        ("link", map.get_b(*b).sidewalk().0.to_string()),
+                        ("actType", "building".to_string()),
+                        ("facility", format!("bldg{}", b.0)),
+                    ],
+                );
+            }
+            Event::PersonEntersBuilding(person, b) => {
+                self.write(
+                    time,
+                    "actstart",
+                    vec![
+                        ("person", person.0.to_string()),
+                        ("link", map.get_b(*b).sidewalk().0.to_string()),
+                        ("actType", "building".to_string()),
+                        ("facility", format!("bldg{}", b.0)),
+                    ],
+                );
+            }
+            // Searching for parking is still part of the driving leg
+            Event::TripPhaseStarting(_, _, _, TripPhaseType::Parking) => {}
+            Event::TripPhaseStarting(trip, person, maybe_req, phase_type) => {
+                self.end_leg(time, *trip);
+                let mode = match phase_type {
+                    TripPhaseType::Driving => "car",
+                    TripPhaseType::Walking => "walk",
+                    TripPhaseType::Biking => "bike",
+                    TripPhaseType::RidingBus(_, _, _) => "pt",
+                    _ => {
+                        return;
+                    }
+                };
+                let mut attribs = vec![("person", person.0.to_string())];
+                if let Some(req) = maybe_req {
+                    attribs.push(("link", req.start.lane().0.to_string()));
+                }
+                attribs.push(("legMode", mode.to_string()));
+                self.write(time, "departure", attribs);
+                self.current_leg.insert(
+                    *trip,
+                    (*person, mode, maybe_req.as_ref().map(|req| req.end.lane())),
+                );
+            }
+            Event::TripFinished { trip, .. } | Event::TripAborted(trip) => {
+                self.end_leg(time, *trip);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        writeln!(self.out, "</events>").unwrap();
+        self.out
+    }
+
+    fn end_leg(&mut self, time: Time, trip: TripID) {
+        if let Some((person, mode, end)) = self.current_leg.remove(&trip) {
+            let mut attribs = vec![("person", person.0.to_string())];
+            if let Some(l) = end {
+                attribs.push(("link", l.0.to_string()));
+            }
+            attribs.push(("legMode", mode.to_string()));
+            self.write(time, "arrival", attribs);
+        }
+    }
+
+    fn write(&mut self, time: Time, event_type: &str, attribs: Vec<(&str, String)>) {
+        write!(
+            self.out,
+            r#"  <event time="{}" type="{}""#,
+            time.inner_seconds(),
+            event_type
+        )
+        .unwrap();
+        for (k, v) in attribs {
+            write!(self.out, r#" {}="{}""#, k, v).unwrap();
+        }
+        writeln!(self.out, "/>").unwrap();
+    }
+}
@@ -1,29 +1,50 @@
 mod analytics;
+mod determinism;
 mod events;
+mod experiments;
+mod export;
 mod make;
 mod mechanics;
+mod noise;
 mod pandemic;
+mod peak_hour;
+mod reliability;
 mod render;
+mod replay;
+mod report;
 mod router;
+mod safety;
 mod scheduler;
 mod sim;
 mod transit;
 mod trips;
 
-pub use self::analytics::{Analytics, TripPhase};
+pub use self::analytics::{Analytics, TripPhase, ANALYTICS_FORMAT_VERSION, TIME_BIN};
+pub use self::determinism::{DeterminismTrace, Snapshot, TraceChecker};
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::experiments::RunManifest;
+pub(crate) use self::export::MatsimEvents;
+pub use self::export::{export_trip_traces, FcdRecorder, TraceFormat};
 pub use self::make::{
-    BorderSpawnOverTime, IndividTrip, OffMapLocation, OriginDestination, PersonSpec, Scenario,
-    ScenarioGenerator, SimFlags, SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
+    BorderSpawnOverTime, BusService, CensusArea, IndividTrip, OffMapLocation, OriginDestination,
+    PersonSpec, Population, Scenario, ScenarioGenerator, SimFlags, SpawnOverTime, SpawnTrip,
+    TripSpawner, TripSpec,
 };
 pub(crate) use self::mechanics::{
     DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
 };
+pub use self::noise::{NoiseEstimate, NOISE_THRESHOLDS};
 pub(crate) use self::pandemic::PandemicModel;
+pub use self::peak_hour::{LocationStats, PeakHour, PeakHourReport};
+pub use self::reliability::{ODReliability, ReliabilityReport, Zone, DEFAULT_ZONE_SIZE};
+pub use self::replay::Replay;
+pub(crate) use self::replay::ReplayRecorder;
+pub use self::report::ab_report_html;
 pub(crate) use self::router::{ActionAtEnd, Router};
+pub use self::safety::{conflicts_to_csv, ConflictType};
 pub(crate) use self::scheduler::{Command, Scheduler};
-pub use self::sim::{AgentProperties, AlertHandler, Sim, SimCallback, SimOptions};
+pub use self::sim::{AgentProperties, AlertHandler, ModelConstants, Sim, SimCallback, SimOptions};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{Person, PersonState, TripResult};
 pub use self::trips::{TripEndpoint, TripMode};
@@ -35,8 +56,8 @@ pub use crate::render::{
 use abstutil::Cloneable;
 use geom::{Distance, Pt2D, Speed, Time};
 use map_model::{
-    BuildingID, BusStopID, DirectedRoadID, IntersectionID, LaneID, Map, ParkingLotID, Path,
-    PathConstraints, PathRequest, Position,
+    BikeRackID, BuildingID, BusStopID, DirectedRoadID, IntersectionID, LaneID, Map, ParkingLotID,
+    Path, PathConstraints, PathRequest, Position,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -49,6 +70,11 @@ pub const MIN_CAR_LENGTH: Distance = Distance::const_meters(4.5);
 pub const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub const BUS_LENGTH: Distance = Distance::const_meters(12.5);
+// A two-car streetcar
+pub const TRAIN_LENGTH: Distance = Distance::const_meters(25.0);
+// Seated and standing passengers. Full vehicles skip boarding.
+pub const BUS_CAPACITY: usize = 60;
+pub const TRAIN_CAPACITY: usize = 150;
 
 // At all speeds (including at rest), cars must be at least this far apart, measured from front of
 // one car to the back of the other.
@@ -66,6 +92,7 @@ impl fmt::Display for CarID {
             VehicleType::Car => write!(f, "Car #{}", self.0),
             VehicleType::Bus => write!(f, "Bus #{}", self.0),
             VehicleType::Bike => write!(f, "Bike #{}", self.0),
+            VehicleType::Train => write!(f, "Train #{}", self.0),
         }
     }
 }
@@ -131,6 +158,7 @@ pub enum VehicleType {
     Car,
     Bus,
     Bike,
+    Train,
 }
 
 impl fmt::Display for VehicleType {
@@ -139,6 +167,7 @@ impl fmt::Display for VehicleType {
             VehicleType::Car => write!(f, "car"),
             VehicleType::Bus => write!(f, "bus"),
             VehicleType::Bike => write!(f, "bike"),
+            VehicleType::Train => write!(f, "train"),
         }
     }
 }
@@ -149,8 +178,14 @@ impl VehicleType {
             VehicleType::Car => PathConstraints::Car,
             VehicleType::Bus => PathConstraints::Bus,
             VehicleType::Bike => PathConstraints::Bike,
+            VehicleType::Train => PathConstraints::Train,
         }
     }
+
+    // Buses and trains both follow a route
+    pub fn is_transit(self) -> bool {
+        self == VehicleType::Bus || self == VehicleType::Train
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -203,6 +238,9 @@ pub struct ParkedCar {
 pub enum DrivingGoal {
     ParkNear(BuildingID),
     Border(IntersectionID, LaneID, Option<OffMapLocation>),
+    // Pull into the building's driveway instead of looking for a parking spot. The building must
+    // have one.
+    EnterDriveway(BuildingID),
 }
 
 impl DrivingGoal {
@@ -231,9 +269,12 @@ impl DrivingGoal {
                     let l = map.find_biking_lane_near_building(*b);
                     Position::new(l, map.get_l(l).length() / 2.0)
                 }
-                PathConstraints::Bus | PathConstraints::Pedestrian => unreachable!(),
+                PathConstraints::Bus | PathConstraints::Train | PathConstraints::Pedestrian => {
+                    unreachable!()
+                }
             },
             DrivingGoal::Border(_, l, _) => Position::new(*l, map.get_l(*l).length()),
+            DrivingGoal::EnterDriveway(b) => map.get_b(*b).parking.as_ref().unwrap().driving_pos,
         }
     }
 
@@ -254,6 +295,7 @@ impl DrivingGoal {
                 map.get_l(*last_lane).length(),
                 *i,
             )),
+            DrivingGoal::EnterDriveway(b) => Some(Router::enter_driveway(path, *b, map)),
         }
     }
 
@@ -261,6 +303,7 @@ impl DrivingGoal {
         match self {
             DrivingGoal::ParkNear(b) => map.get_b(*b).polygon.center(),
             DrivingGoal::Border(i, _, _) => map.get_i(*i).polygon.center(),
+            DrivingGoal::EnterDriveway(b) => map.get_b(*b).polygon.center(),
         }
     }
 }
@@ -327,6 +370,15 @@ impl SidewalkSpot {
         })
     }
 
+    // A rack mapped in OSM, instead of somewhere arbitrary along the sidewalk
+    pub fn bike_rack_at(rack: BikeRackID, map: &Map) -> SidewalkSpot {
+        let rack = map.get_bike_rack(rack);
+        SidewalkSpot {
+            connection: SidewalkPOI::BikeRack(rack.biking_pos),
+            sidewalk_pos: rack.sidewalk_pos,
+        }
+    }
+
     pub fn bike_from_bike_rack(sidewalk: LaneID, map: &Map) -> Option<SidewalkSpot> {
         assert!(map.get_l(sidewalk).is_sidewalk());
         let driving_lane = map.get_parent(sidewalk).sidewalk_to_bike(sidewalk)?;
@@ -495,6 +547,9 @@ pub struct CreateCar {
     pub req: PathRequest,
     pub start_dist: Distance,
     pub maybe_parked_car: Option<ParkedCar>,
+    // A vehicle that isn't in any tracked parking spot can still pull out of a building's
+    // driveway.
+    pub leaving_driveway: Option<BuildingID>,
     // None for buses
     pub trip_and_person: Option<(TripID, PersonID)>,
 }
@@ -514,6 +569,37 @@ impl CreateCar {
             req,
             start_dist: start_pos.dist_along(),
             maybe_parked_car: None,
+            leaving_driveway: None,
+            trip_and_person: Some((trip, person)),
+        }
+    }
+
+    pub fn for_driveway(
+        vehicle: Vehicle,
+        b: BuildingID,
+        router: Router,
+        req: PathRequest,
+        trip: TripID,
+        person: PersonID,
+        map: &Map,
+    ) -> CreateCar {
+        // Like unparking from an offstreet spot, the back of the car starts at the end of the
+        // driveway
+        let start_dist = map
+            .get_b(b)
+            .parking
+            .as_ref()
+            .unwrap()
+            .driving_pos
+            .dist_along()
+            + vehicle.length;
+        CreateCar {
+            vehicle,
+            router,
+            req,
+            start_dist,
+            maybe_parked_car: None,
+            leaving_driveway: Some(b),
             trip_and_person: Some((trip, person)),
         }
     }
@@ -533,6 +619,7 @@ impl CreateCar {
             req,
             start_dist,
             maybe_parked_car: Some(parked_car),
+            leaving_driveway: None,
             trip_and_person: Some((trip, person)),
         }
     }
@@ -1,8 +1,8 @@
 use crate::{DrivingGoal, IndividTrip, PersonID, PersonSpec, Scenario, SidewalkSpot, SpawnTrip};
 use abstutil::Timer;
 use geom::{Duration, Time};
-use map_model::{BuildingID, DirectedRoadID, Map, PathConstraints};
-use rand::seq::SliceRandom;
+use map_model::{BuildingID, DirectedRoadID, LandUse, Map, PathConstraints};
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
@@ -54,24 +54,32 @@ impl ScenarioGenerator {
 
         timer.start(format!("Generating scenario {}", self.scenario_name));
 
+        let ends = TripEnds::new(map);
+        if !self.spawn_over_time.is_empty() && ends.production.is_none() {
+            timer.warn(format!(
+                "{} has no buildings to start trips from, so SpawnOverTime does nothing",
+                map.get_name()
+            ));
+        }
         for s in &self.spawn_over_time {
             timer.start_iter("SpawnOverTime each agent", s.num_agents);
             for _ in 0..s.num_agents {
                 timer.next();
-                s.spawn_agent(rng, &mut scenario, map, timer);
+                s.spawn_agent(rng, &mut scenario, map, &ends, timer);
             }
         }
 
         timer.start_iter("BorderSpawnOverTime", self.border_spawn_over_time.len());
         for s in &self.border_spawn_over_time {
             timer.next();
-            s.spawn_peds(rng, &mut scenario, map, timer);
+            s.spawn_peds(rng, &mut scenario, map, &ends, timer);
             s.spawn_vehicles(
                 s.num_cars,
                 PathConstraints::Car,
                 rng,
                 &mut scenario,
                 map,
+                &ends,
                 timer,
             );
             s.spawn_vehicles(
@@ -80,6 +88,7 @@ impl ScenarioGenerator {
                 rng,
                 &mut scenario,
                 map,
+                &ends,
                 timer,
             );
         }
@@ -166,18 +175,24 @@ impl SpawnOverTime {
         rng: &mut XorShiftRng,
         scenario: &mut Scenario,
         map: &Map,
+        ends: &TripEnds,
         timer: &mut Timer,
     ) {
         let depart = rand_time(rng, self.start_time, self.stop_time);
         // Note that it's fine for agents to start/end at the same building. Later we might
         // want a better assignment of people per household, or workers per office building.
-        let from_bldg = map.all_buildings().choose(rng).unwrap().id;
+        let from_bldg = if let Some(b) = ends.pick_origin(rng) {
+            b
+        } else {
+            // generate already warned about this
+            return;
+        };
         let id = PersonID(scenario.people.len());
 
         if rng.gen_bool(self.percent_driving) {
-            if let Some(goal) = self
-                .goal
-                .pick_driving_goal(PathConstraints::Car, map, rng, timer)
+            if let Some(goal) =
+                self.goal
+                    .pick_driving_goal(PathConstraints::Car, map, ends, rng, timer)
             {
                 scenario.people.push(PersonSpec {
                     id,
@@ -194,9 +209,9 @@ impl SpawnOverTime {
         let start_spot = SidewalkSpot::building(from_bldg, map);
 
         if rng.gen_bool(self.percent_biking) {
-            if let Some(goal) = self
-                .goal
-                .pick_driving_goal(PathConstraints::Bike, map, rng, timer)
+            if let Some(goal) =
+                self.goal
+                    .pick_driving_goal(PathConstraints::Bike, map, ends, rng, timer)
             {
                 scenario.people.push(PersonSpec {
                     id,
@@ -210,7 +225,7 @@ impl SpawnOverTime {
             }
         }
 
-        if let Some(goal) = self.goal.pick_walking_goal(map, rng, timer) {
+        if let Some(goal) = self.goal.pick_walking_goal(map, ends, rng, timer) {
             if start_spot == goal {
                 timer.warn("Skipping walking trip between same two buildings".to_string());
                 return;
@@ -255,6 +270,7 @@ impl BorderSpawnOverTime {
         rng: &mut XorShiftRng,
         scenario: &mut Scenario,
         map: &Map,
+        ends: &TripEnds,
         timer: &mut Timer,
     ) {
         if self.num_peds == 0 {
@@ -276,7 +292,7 @@ impl BorderSpawnOverTime {
         for _ in 0..self.num_peds {
             let depart = rand_time(rng, self.start_time, self.stop_time);
             let id = PersonID(scenario.people.len());
-            if let Some(goal) = self.goal.pick_walking_goal(map, rng, timer) {
+            if let Some(goal) = self.goal.pick_walking_goal(map, ends, rng, timer) {
                 if rng.gen_bool(self.percent_use_transit) {
                     // TODO This throws away some work. It also sequentially does expensive
                     // work right here.
@@ -320,11 +336,15 @@ impl BorderSpawnOverTime {
         rng: &mut XorShiftRng,
         scenario: &mut Scenario,
         map: &Map,
+        ends: &TripEnds,
         timer: &mut Timer,
     ) {
         for _ in 0..num {
             let depart = rand_time(rng, self.start_time, self.stop_time);
-            if let Some(goal) = self.goal.pick_driving_goal(constraints, map, rng, timer) {
+            if let Some(goal) = self
+                .goal
+                .pick_driving_goal(constraints, map, ends, rng, timer)
+            {
                 let id = PersonID(scenario.people.len());
                 scenario.people.push(PersonSpec {
                     id,
@@ -356,13 +376,12 @@ impl OriginDestination {
         &self,
         constraints: PathConstraints,
         map: &Map,
+        ends: &TripEnds,
         rng: &mut XorShiftRng,
         timer: &mut Timer,
     ) -> Option<DrivingGoal> {
         match self {
-            OriginDestination::Anywhere => Some(DrivingGoal::ParkNear(
-                map.all_buildings().choose(rng).unwrap().id,
-            )),
+            OriginDestination::Anywhere => ends.pick_destination(rng).map(DrivingGoal::ParkNear),
             OriginDestination::GotoBldg(b) => Some(DrivingGoal::ParkNear(*b)),
             OriginDestination::EndOfRoad(dr) => {
                 let goal = DrivingGoal::end_at_border(*dr, constraints, None, map);
@@ -380,14 +399,14 @@ impl OriginDestination {
     fn pick_walking_goal(
         &self,
         map: &Map,
+        ends: &TripEnds,
         rng: &mut XorShiftRng,
         timer: &mut Timer,
     ) -> Option<SidewalkSpot> {
         match self {
-            OriginDestination::Anywhere => Some(SidewalkSpot::building(
-                map.all_buildings().choose(rng).unwrap().id,
-                map,
-            )),
+            OriginDestination::Anywhere => ends
+                .pick_destination(rng)
+                .map(|b| SidewalkSpot::building(b, map)),
             OriginDestination::EndOfRoad(dr) => {
                 let goal = SidewalkSpot::end_at_border(dr.dst_i(map), None, map);
                 if goal.is_none() {
@@ -400,7 +419,59 @@ impl OriginDestination {
     }
 }
 
-fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
+// Daily person trips per 100 square meters of floor space, as (produced, attracted). Homes produce
+// trips; shops, schools, and workplaces attract them. These are rough guesses in the spirit of ITE
+// trip generation rates, not calibrated against anything.
+pub(crate) fn trip_rates(land_use: LandUse) -> (f64, f64) {
+    match land_use {
+        LandUse::Residential => (1.0, 0.2),
+        LandUse::Commercial => (0.2, 4.0),
+        LandUse::School => (0.1, 2.5),
+        LandUse::Industrial => (0.1, 0.5),
+        // Most unlabelled buildings are houses
+        LandUse::Unknown => (0.8, 0.3),
+    }
+}
+
+// Picks where trips start and end, weighting each building by its floor space and the trip rates
+// for its land use. Either side is None when no building has any weight, like on maps without
+// buildings.
+pub(crate) struct TripEnds {
+    bldgs: Vec<BuildingID>,
+    production: Option<WeightedIndex<f64>>,
+    attraction: Option<WeightedIndex<f64>>,
+}
+
+impl TripEnds {
+    pub fn new(map: &Map) -> TripEnds {
+        let mut bldgs = Vec::new();
+        let mut production = Vec::new();
+        let mut attraction = Vec::new();
+        for b in map.all_buildings() {
+            let (p, a) = trip_rates(b.land_use);
+            // Don't let degenerate polygons drop out entirely
+            let area = b.floor_area().max(1.0) / 100.0;
+            bldgs.push(b.id);
+            production.push(p * area);
+            attraction.push(a * area);
+        }
+        TripEnds {
+            bldgs,
+            production: WeightedIndex::new(production).ok(),
+            attraction: WeightedIndex::new(attraction).ok(),
+        }
+    }
+
+    pub fn pick_origin(&self, rng: &mut XorShiftRng) -> Option<BuildingID> {
+        Some(self.bldgs[self.production.as_ref()?.sample(rng)])
+    }
+
+    pub fn pick_destination(&self, rng: &mut XorShiftRng) -> Option<BuildingID> {
+        Some(self.bldgs[self.attraction.as_ref()?.sample(rng)])
+    }
+}
+
+pub(crate) fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
     assert!(high > low);
     Time::START_OF_DAY + Duration::seconds(rng.gen_range(low.inner_seconds(), high.inner_seconds()))
 }
@@ -1,5 +1,6 @@
 use crate::{AlertHandler, Scenario, Sim, SimOptions};
 use abstutil::CmdArgs;
+use geom::Duration;
 use map_model::{Map, MapEdits};
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
@@ -31,7 +32,14 @@ impl SimFlags {
                 use_freeform_policy_everywhere: args.enabled("--freeform_policy"),
                 dont_block_the_box: !args.enabled("--disable_block_the_box"),
                 recalc_lanechanging: !args.enabled("--disable_recalc_lc"),
+                narrow_lane_slowdown: args.enabled("--narrow_lane_slowdown"),
                 break_turn_conflict_cycles: !args.enabled("--disable_break_turn_conflict_cycles"),
+                // Like "5s" or "1m". A bare number is seconds.
+                yield_critical_gap: args
+                    .optional_parse("--yield_critical_gap", |s| s.parse::<Duration>())
+                    .unwrap_or_else(|| Duration::seconds(5.0)),
+                right_turn_on_red: !args.enabled("--no_right_turn_on_red"),
+                crowded_walking: args.enabled("--crowded_walking"),
                 enable_pandemic_model: if args.enabled("--pandemic") {
                     Some(XorShiftRng::from_seed([rng_seed; 16]))
                 } else {
@@ -47,6 +55,8 @@ impl SimFlags {
                     })
                     .unwrap_or(AlertHandler::Print),
                 pathfinding_upfront: args.enabled("--pathfinding_upfront"),
+                record_matsim_events: args.enabled("--record_matsim_events"),
+                record_replay: args.enabled("--record_replay"),
             },
         }
     }
@@ -77,7 +87,10 @@ impl SimFlags {
         if self.load.starts_with("../data/player/saves/") {
             timer.note(format!("Resuming from {}", self.load));
 
-            let mut sim: Sim = abstutil::read_binary(self.load.clone(), timer);
+            let mut sim = match Sim::read_savestate(self.load.clone(), timer) {
+                Ok(sim) => sim,
+                Err(err) => panic!("Can't resume from {}: {}", self.load, err),
+            };
 
             let mut map = Map::new(abstutil::path_map(&sim.map_name), timer);
             if sim.edits_name != "untitled edits" {
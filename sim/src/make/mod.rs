@@ -1,5 +1,6 @@
 mod generator;
 mod load;
+mod population;
 mod scenario;
 mod spawner;
 
@@ -7,5 +8,8 @@ pub use self::generator::{
     BorderSpawnOverTime, OriginDestination, ScenarioGenerator, SpawnOverTime,
 };
 pub use self::load::SimFlags;
-pub use self::scenario::{IndividTrip, OffMapLocation, PersonSpec, Scenario, SpawnTrip};
+pub use self::population::{CensusArea, Population};
+pub use self::scenario::{
+    BusService, IndividTrip, OffMapLocation, PersonSpec, Scenario, SpawnTrip,
+};
 pub use self::spawner::{TripSpawner, TripSpec};
@@ -0,0 +1,223 @@
+use crate::make::generator::rand_time;
+use crate::{IndividTrip, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint, TripMode};
+use abstutil::Timer;
+use geom::{Distance, Duration, Polygon, Time};
+use map_model::{BuildingID, LandUse, Map};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// How many people live and work in each building. This comes from census blocks or parcels when
+// they're available, and otherwise is guessed from building footprints.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Population {
+    pub residents: BTreeMap<BuildingID, usize>,
+    pub jobs: BTreeMap<BuildingID, usize>,
+}
+
+// One census block or parcel
+pub struct CensusArea {
+    pub polygon: Polygon,
+    pub residents: usize,
+    pub jobs: usize,
+}
+
+impl Population {
+    // Split each area's counts between the buildings inside of it, proportional to footprint.
+    // Residents prefer buildings without amenities, and jobs prefer buildings with them.
+    pub fn from_census(map: &Map, areas: Vec<CensusArea>, timer: &mut Timer) -> Population {
+        let mut pop = Population {
+            residents: BTreeMap::new(),
+            jobs: BTreeMap::new(),
+        };
+        let mut unmatched = 0;
+        timer.start_iter("match census areas to buildings", areas.len());
+        for area in areas {
+            timer.next();
+            let bounds = area.polygon.get_bounds();
+            let mut homes = Vec::new();
+            let mut shops = Vec::new();
+            for b in map.all_buildings() {
+                let center = b.polygon.center();
+                if !bounds.contains(center) || !area.polygon.contains_pt(center) {
+                    continue;
+                }
+                if b.amenities.is_empty() {
+                    homes.push((b.id, b.polygon.area()));
+                } else {
+                    shops.push((b.id, b.polygon.area()));
+                }
+            }
+            if homes.is_empty() && shops.is_empty() {
+                unmatched += 1;
+                continue;
+            }
+            split(
+                area.residents,
+                if homes.is_empty() { &shops } else { &homes },
+                &mut pop.residents,
+            );
+            split(
+                area.jobs,
+                if shops.is_empty() { &homes } else { &shops },
+                &mut pop.jobs,
+            );
+        }
+        if unmatched > 0 {
+            timer.warn(format!(
+                "{} census areas didn't contain any buildings",
+                unmatched
+            ));
+        }
+        pop
+    }
+
+    // Without any real data, go by each building's land use. Buildings with amenities are already
+    // commercial, so assume the rest of the unlabelled ones are housing.
+    pub fn guess(map: &Map) -> Population {
+        let mut pop = Population {
+            residents: BTreeMap::new(),
+            jobs: BTreeMap::new(),
+        };
+        for b in map.all_buildings() {
+            let area = b.floor_area();
+            let residential = match b.land_use {
+                LandUse::Residential | LandUse::Unknown => true,
+                LandUse::Commercial | LandUse::School | LandUse::Industrial => false,
+            };
+            if residential {
+                pop.residents
+                    .insert(b.id, ((area / SQ_METERS_PER_RESIDENT) as usize).max(1));
+            } else {
+                pop.jobs.insert(
+                    b.id,
+                    ((area / SQ_METERS_PER_JOB) as usize).max(b.amenities.len()),
+                );
+            }
+        }
+        pop
+    }
+
+    // Most people commute to a job and back, some run an errand during the day, and the rest stay
+    // home. Workplaces are picked in proportion to how many jobs they have.
+    pub fn make_scenario(
+        &self,
+        name: &str,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let mut scenario = Scenario::empty(map, name);
+        scenario.only_seed_buses = None;
+
+        let workplaces: Vec<BuildingID> = self.jobs.keys().cloned().collect();
+        let weights = match WeightedIndex::new(self.jobs.values()) {
+            Ok(w) => w,
+            Err(err) => {
+                timer.warn(format!("Nobody has anywhere to go: {}", err));
+                return scenario;
+            }
+        };
+
+        timer.start_iter("synthesize people", self.residents.len());
+        for (home, num_residents) in &self.residents {
+            timer.next();
+            for _ in 0..*num_residents {
+                let (leave, stay) = if rng.gen_bool(PERCENT_COMMUTE) {
+                    (
+                        rand_time(rng, hours(7), hours(9)),
+                        Duration::minutes(rng.gen_range(480, 540)),
+                    )
+                } else if rng.gen_bool(PERCENT_ERRAND) {
+                    (
+                        rand_time(rng, hours(10), hours(18)),
+                        Duration::minutes(rng.gen_range(30, 90)),
+                    )
+                } else {
+                    continue;
+                };
+                let dst = workplaces[weights.sample(rng)];
+                if dst == *home {
+                    continue;
+                }
+                let mode = pick_mode(map, *home, dst, rng);
+                scenario.people.push(PersonSpec {
+                    id: PersonID(scenario.people.len()),
+                    orig_id: None,
+                    trips: vec![
+                        IndividTrip {
+                            depart: leave,
+                            trip: SpawnTrip::new(
+                                TripEndpoint::Bldg(*home),
+                                TripEndpoint::Bldg(dst),
+                                mode,
+                                map,
+                            ),
+                        },
+                        IndividTrip {
+                            depart: leave + stay,
+                            trip: SpawnTrip::new(
+                                TripEndpoint::Bldg(dst),
+                                TripEndpoint::Bldg(*home),
+                                mode,
+                                map,
+                            ),
+                        },
+                    ],
+                });
+            }
+        }
+        scenario
+    }
+}
+
+// TODO These are rough guesses, not calibrated against anything
+const SQ_METERS_PER_RESIDENT: f64 = 40.0;
+const SQ_METERS_PER_JOB: f64 = 30.0;
+const PERCENT_COMMUTE: f64 = 0.6;
+const PERCENT_ERRAND: f64 = 0.5;
+
+fn hours(h: usize) -> Time {
+    Time::START_OF_DAY + Duration::hours(h)
+}
+
+// Divide the total proportional to the weights. Leftovers from rounding go to the heaviest one.
+fn split(total: usize, weights: &Vec<(BuildingID, f64)>, into: &mut BTreeMap<BuildingID, usize>) {
+    if total == 0 {
+        return;
+    }
+    let sum: f64 = weights.iter().map(|(_, w)| *w).sum();
+    let mut remaining = total;
+    for (b, w) in weights {
+        let n = ((total as f64) * w / sum).floor() as usize;
+        *into.entry(*b).or_insert(0) += n;
+        remaining -= n;
+    }
+    let heaviest = weights
+        .iter()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap()
+        .0;
+    *into.entry(heaviest).or_insert(0) += remaining;
+}
+
+// Based only on straight-line distance
+fn pick_mode(map: &Map, from: BuildingID, to: BuildingID, rng: &mut XorShiftRng) -> TripMode {
+    let dist = map
+        .get_b(from)
+        .polygon
+        .center()
+        .dist_to(map.get_b(to).polygon.center());
+    if dist < Distance::miles(0.5) && rng.gen_bool(0.9) {
+        return TripMode::Walk;
+    }
+    if dist < Distance::miles(3.0) && rng.gen_bool(0.2) {
+        return TripMode::Bike;
+    }
+    if rng.gen_bool(0.2) {
+        return TripMode::Transit;
+    }
+    TripMode::Drive
+}
@@ -5,14 +5,16 @@ use crate::{
 };
 use abstutil::{prettyprint_usize, Counter, Timer};
 use geom::{Distance, Duration, LonLat, Speed, Time};
+use log::{debug, info};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, DirectedRoadID, Map, PathConstraints, Position, RoadID,
+    BuildingID, BusRouteID, BusStopID, DirectedRoadID, IntersectionID, Map, ParkingLotID,
+    PathConstraints, Position, RoadID,
 };
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 // How to start a simulation.
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -23,6 +25,18 @@ pub struct Scenario {
     pub people: Vec<PersonSpec>,
     // None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    // Keyed by route name. Replaces any schedule the route already has.
+    #[serde(default)]
+    pub bus_service: BTreeMap<String, BusService>,
+}
+
+// Runs a route with a fixed fleet instead of its normal schedule. Buses are dispatched from the
+// first stop, one per headway, until the whole fleet is out. Since buses loop around the route
+// forever, the headway only holds afterwards if the fleet is big enough to cover a round trip.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct BusService {
+    pub headway: Duration,
+    pub fleet_size: usize,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -65,6 +79,21 @@ pub enum SpawnTrip {
         trip_time: Duration,
         mode: TripMode,
     },
+    // The car is kept in the building's garage, not in a tracked parking spot, and pulls out of
+    // the driveway. The building must have a driveway.
+    FromDriveway(BuildingID, DrivingGoal),
+    // Drive from the building to a park-and-ride lot, then take transit
+    UsingParkAndRide(
+        BuildingID,
+        SidewalkSpot,
+        ParkingLotID,
+        BusRouteID,
+        BusStopID,
+        BusStopID,
+    ),
+    // Go from the first building back to the lot and drive home to the second building. Only
+    // created while instantiating, for the trip after a UsingParkAndRide.
+    ParkAndRideHome(BuildingID, ParkingLotID, BuildingID),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -73,6 +102,46 @@ pub struct OffMapLocation {
     pub gps: LonLat,
 }
 
+impl BusService {
+    // Parses a list like "44=10m/6,48=15m/4", meaning route 44 runs 6 buses 10 minutes apart.
+    pub fn parse_list(list: &str) -> Result<BTreeMap<String, BusService>, String> {
+        let mut result = BTreeMap::new();
+        for entry in list.split(',') {
+            let parts: Vec<&str> = entry.rsplitn(2, '=').collect();
+            let values: Vec<&str> = parts[0].rsplitn(2, '/').collect();
+            if parts.len() != 2 || values.len() != 2 {
+                return Err(format!(
+                    "{} should look like route=headway/fleet size",
+                    entry
+                ));
+            }
+            let fleet_size = values[0]
+                .parse::<usize>()
+                .map_err(|err| format!("{}: {}", entry, err))?;
+            let headway = values[1]
+                .parse::<Duration>()
+                .map_err(|err| format!("{}: {}", entry, err))?;
+            let service = BusService {
+                headway,
+                fleet_size,
+            };
+            service
+                .validate()
+                .map_err(|err| format!("{}: {}", entry, err))?;
+            result.insert(parts[1].to_string(), service);
+        }
+        Ok(result)
+    }
+
+    // Scenario files aren't parsed by parse_list, so check them again before using them.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fleet_size == 0 || self.headway <= Duration::ZERO {
+            return Err("needs at least one bus and a positive headway".to_string());
+        }
+        Ok(())
+    }
+}
+
 impl Scenario {
     // Any case where map edits could change the calls to the RNG, we have to fork.
     pub fn instantiate(&self, sim: &mut Sim, map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) {
@@ -83,13 +152,13 @@ impl Scenario {
         if let Some(ref routes) = self.only_seed_buses {
             for route in map.get_all_bus_routes() {
                 if routes.contains(&route.name) {
-                    sim.seed_bus_route(route, map, timer);
+                    sim.seed_bus_route(route, self.bus_service.get(&route.name), map, timer);
                 }
             }
         } else {
             // All of them
             for route in map.get_all_bus_routes() {
-                sim.seed_bus_route(route, map, timer);
+                sim.seed_bus_route(route, self.bus_service.get(&route.name), map, timer);
             }
         }
 
@@ -102,6 +171,8 @@ impl Scenario {
             if let Err(err) = p.check_schedule(map) {
                 panic!("{}", err);
             }
+            let fixed = p.retrieve_park_and_ride_cars(map);
+            let p = fixed.as_ref().unwrap_or(p);
 
             let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
                 p.get_vehicles(rng);
@@ -149,6 +220,7 @@ impl Scenario {
             map_name: map.get_name().to_string(),
             people: Vec::new(),
             only_seed_buses: Some(BTreeSet::new()),
+            bus_service: BTreeMap::new(),
         }
     }
 
@@ -218,6 +290,48 @@ impl Scenario {
         self
     }
 
+    // People driving from or to a building with a private driveway, but no tracked parking spots,
+    // keep their car inside instead of parking nearby. They pull in and out of the driveway.
+    pub fn use_driveways(mut self, map: &Map) -> Scenario {
+        let has_driveway = |b: BuildingID| {
+            map.get_b(b)
+                .parking
+                .as_ref()
+                .map(|p| p.public_garage_name.is_none() && p.num_spots == 0)
+                .unwrap_or(false)
+        };
+        for person in &mut self.people {
+            for trip in &mut person.trips {
+                match trip.trip {
+                    SpawnTrip::VehicleAppearing {
+                        ref mut goal,
+                        is_bike: false,
+                        ..
+                    }
+                    | SpawnTrip::FromBorder {
+                        ref mut goal,
+                        is_bike: false,
+                        ..
+                    }
+                    | SpawnTrip::UsingParkedCar(_, ref mut goal) => {
+                        if let DrivingGoal::ParkNear(b) = *goal {
+                            if has_driveway(b) {
+                                *goal = DrivingGoal::EnterDriveway(b);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if let SpawnTrip::UsingParkedCar(b, ref goal) = trip.trip {
+                    if has_driveway(b) {
+                        trip.trip = SpawnTrip::FromDriveway(b, goal.clone());
+                    }
+                }
+            }
+        }
+        self
+    }
+
     pub fn count_parked_cars_per_bldg(&self) -> Counter<BuildingID> {
         let mut per_bldg = Counter::new();
         // Pass in a dummy RNG
@@ -237,11 +351,11 @@ impl Scenario {
             .retain(|person| match person.check_schedule(map) {
                 Ok(()) => true,
                 Err(err) => {
-                    println!("{}", err);
+                    debug!("{}", err);
                     false
                 }
             });
-        println!(
+        info!(
             "{} of {} people have nonsense schedules",
             prettyprint_usize(orig - self.people.len()),
             prettyprint_usize(orig)
@@ -252,6 +366,147 @@ impl Scenario {
         }
         self
     }
+
+    // After a map is re-imported from newer data, translate buildings, borders, and the lanes
+    // vehicles appear on to the new map using their OSM IDs. People visiting anything that's gone
+    // are dropped.
+    pub fn remap(mut self, old_map: &Map, new_map: &Map, timer: &mut Timer) -> Scenario {
+        let bldgs_by_osm: HashMap<i64, BuildingID> = new_map
+            .all_buildings()
+            .iter()
+            .map(|b| (b.osm_way_id, b.id))
+            .collect();
+        let borders_by_osm: HashMap<i64, IntersectionID> = new_map
+            .all_intersections()
+            .iter()
+            .filter(|i| i.is_border())
+            .map(|i| (i.orig_id.osm_node_id, i.id))
+            .collect();
+        let remap_endpt = |endpt: TripEndpoint| -> Option<TripEndpoint> {
+            match endpt {
+                TripEndpoint::Bldg(b) => bldgs_by_osm
+                    .get(&old_map.get_b(b).osm_way_id)
+                    .map(|b| TripEndpoint::Bldg(*b)),
+                TripEndpoint::Border(i, loc) => borders_by_osm
+                    .get(&old_map.get_i(i).orig_id.osm_node_id)
+                    .map(|i| TripEndpoint::Border(*i, loc)),
+            }
+        };
+        let roads_by_orig: HashMap<map_model::raw::OriginalRoad, RoadID> = new_map
+            .all_roads()
+            .iter()
+            .map(|r| (r.orig_id, r.id))
+            .collect();
+        // Vehicles appear on a lane, so find the lane in the same spot on the new map's version of
+        // the road.
+        let remap_appearing = |trip: &SpawnTrip| -> Option<SpawnTrip> {
+            let (start, goal, is_bike) = match trip {
+                SpawnTrip::VehicleAppearing {
+                    start,
+                    goal,
+                    is_bike,
+                } => (start, goal, *is_bike),
+                _ => unreachable!(),
+            };
+            let old_lane = old_map.get_l(start.lane());
+            let old_road = old_map.get_r(old_lane.parent);
+            let (fwds, offset) = old_road.dir_and_offset(old_lane.id);
+            let new_road = new_map.get_r(*roads_by_orig.get(&old_road.orig_id)?);
+            let children = if fwds {
+                &new_road.children_forwards
+            } else {
+                &new_road.children_backwards
+            };
+            // Prefer the lane in the same position, but settle for any of the same type
+            let (lane, _) = children
+                .get(offset)
+                .filter(|(_, lt)| *lt == old_lane.lane_type)
+                .or_else(|| children.iter().find(|(_, lt)| *lt == old_lane.lane_type))?;
+            let constraints = if is_bike {
+                PathConstraints::Bike
+            } else {
+                PathConstraints::Car
+            };
+            let goal = match (goal, remap_endpt(trip.end(old_map))?) {
+                (DrivingGoal::EnterDriveway(_), TripEndpoint::Bldg(b)) => {
+                    DrivingGoal::EnterDriveway(b)
+                }
+                (_, TripEndpoint::Bldg(b)) => DrivingGoal::ParkNear(b),
+                (_, TripEndpoint::Border(i, loc)) => DrivingGoal::end_at_border(
+                    new_map.get_i(i).some_incoming_road(new_map)?,
+                    constraints,
+                    loc,
+                    new_map,
+                )?,
+            };
+            Some(SpawnTrip::VehicleAppearing {
+                start: Position::new(*lane, start.dist_along().min(new_map.get_l(*lane).length())),
+                goal,
+                is_bike,
+            })
+        };
+
+        let orig = self.people.len();
+        timer.start_iter("remap people", orig);
+        self.people.retain(|person| {
+            timer.next();
+            person.trips.iter().all(|t| match t.trip {
+                SpawnTrip::Remote { .. } => true,
+                SpawnTrip::VehicleAppearing { .. } => remap_appearing(&t.trip).is_some(),
+                _ => {
+                    remap_endpt(t.trip.start(old_map)).is_some()
+                        && remap_endpt(t.trip.end(old_map)).is_some()
+                }
+            })
+        });
+        for person in &mut self.people {
+            for t in &mut person.trips {
+                match t.trip {
+                    SpawnTrip::Remote { .. } => {
+                        continue;
+                    }
+                    SpawnTrip::VehicleAppearing { .. } => {
+                        t.trip = remap_appearing(&t.trip).unwrap();
+                        continue;
+                    }
+                    _ => {}
+                }
+                let mode = t.trip.mode();
+                t.trip = SpawnTrip::new(
+                    remap_endpt(t.trip.start(old_map)).unwrap(),
+                    remap_endpt(t.trip.end(old_map)).unwrap(),
+                    mode,
+                    new_map,
+                );
+            }
+        }
+        if self.people.len() != orig {
+            timer.warn(format!(
+                "{} of {} people in {} visit something that isn't in the new map",
+                prettyprint_usize(orig - self.people.len()),
+                prettyprint_usize(orig),
+                self.scenario_name
+            ));
+        }
+        for (idx, person) in self.people.iter_mut().enumerate() {
+            person.id = PersonID(idx);
+        }
+        self.map_name = new_map.get_name().to_string();
+        self
+    }
+
+    // Scenarios refer to buildings, borders, and bus stops by ID. After the map is regenerated,
+    // make sure all of those still exist. Returns the first problem found.
+    pub fn check_ids(&self, map: &Map) -> Result<(), String> {
+        for person in &self.people {
+            for trip in &person.trips {
+                trip.trip
+                    .check_ids(map)
+                    .map_err(|err| format!("{} at {}: {}", person.id, trip.depart, err))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 fn seed_parked_cars(
@@ -372,7 +627,7 @@ impl SpawnTrip {
         rng: &mut XorShiftRng,
         map: &Map,
     ) -> TripSpec {
-        match self {
+        match self.replan_transit(map) {
             SpawnTrip::VehicleAppearing { start, goal, .. } => TripSpec::VehicleAppearing {
                 start_pos: start,
                 goal,
@@ -422,6 +677,11 @@ impl SpawnTrip {
                 goal,
                 car: use_vehicle.unwrap(),
             },
+            SpawnTrip::FromDriveway(start_bldg, goal) => TripSpec::LeavingDriveway {
+                start_bldg,
+                goal,
+                use_vehicle: use_vehicle.unwrap(),
+            },
             SpawnTrip::UsingBike(start, goal) => TripSpec::UsingBike {
                 bike: use_vehicle.unwrap(),
                 start,
@@ -435,6 +695,27 @@ impl SpawnTrip {
                 stop1,
                 stop2,
             },
+            SpawnTrip::UsingParkAndRide(start_bldg, goal, lot, route, stop1, stop2) => {
+                TripSpec::UsingParkAndRide {
+                    car: use_vehicle.unwrap(),
+                    start_bldg,
+                    lot,
+                    goal,
+                    route,
+                    stop1,
+                    stop2,
+                }
+            }
+            SpawnTrip::ParkAndRideHome(start_bldg, lot, home) => TripSpec::ParkAndRideHome {
+                car: use_vehicle.unwrap(),
+                start_bldg,
+                lot,
+                transit: map.should_use_transit(
+                    SidewalkSpot::building(start_bldg, map).sidewalk_pos,
+                    map.get_pl(lot).sidewalk_pos,
+                ),
+                home,
+            },
             SpawnTrip::Remote {
                 from,
                 to,
@@ -449,6 +730,47 @@ impl SpawnTrip {
         }
     }
 
+    // Map edits can remove the bus stops a scenario planned transit trips around. Re-plan those
+    // trips with the stops that remain, or just walk. A park-and-ride trip never gains a car it
+    // wasn't given, and a plain transit trip never becomes a park-and-ride.
+    fn replan_transit(self, map: &Map) -> SpawnTrip {
+        let still_served = |route: BusRouteID, stop1: BusStopID, stop2: BusStopID| {
+            let stops = &map.get_br(route).stops;
+            map.maybe_get_bs(stop1).is_some()
+                && map.maybe_get_bs(stop2).is_some()
+                && stops.contains(&stop1)
+                && stops.contains(&stop2)
+        };
+        let transit_or_walk = |start: SidewalkSpot, goal: SidewalkSpot| match map
+            .should_use_transit(start.sidewalk_pos, goal.sidewalk_pos)
+        {
+            Some((stop1, stop2, route)) => {
+                SpawnTrip::UsingTransit(start, goal, route, stop1, stop2)
+            }
+            None => SpawnTrip::JustWalking(start, goal),
+        };
+
+        match self {
+            SpawnTrip::UsingTransit(start, goal, route, stop1, stop2)
+                if !still_served(route, stop1, stop2) =>
+            {
+                transit_or_walk(start, goal)
+            }
+            SpawnTrip::UsingParkAndRide(b, goal, _, route, stop1, stop2)
+                if !still_served(route, stop1, stop2) =>
+            {
+                if let Some((lot, stop1, stop2, route)) =
+                    map.should_use_park_and_ride(b, goal.sidewalk_pos)
+                {
+                    SpawnTrip::UsingParkAndRide(b, goal, lot, route, stop1, stop2)
+                } else {
+                    transit_or_walk(SidewalkSpot::building(b, map), goal)
+                }
+            }
+            x => x,
+        }
+    }
+
     pub fn start(&self, map: &Map) -> TripEndpoint {
         match self {
             SpawnTrip::VehicleAppearing { ref start, .. } => {
@@ -457,7 +779,10 @@ impl SpawnTrip {
             SpawnTrip::FromBorder { dr, ref origin, .. } => {
                 TripEndpoint::Border(dr.src_i(map), origin.clone())
             }
-            SpawnTrip::UsingParkedCar(b, _) => TripEndpoint::Bldg(*b),
+            SpawnTrip::UsingParkedCar(b, _)
+            | SpawnTrip::FromDriveway(b, _)
+            | SpawnTrip::UsingParkAndRide(b, _, _, _, _, _)
+            | SpawnTrip::ParkAndRideHome(b, _, _) => TripEndpoint::Bldg(*b),
             SpawnTrip::UsingBike(ref spot, _)
             | SpawnTrip::JustWalking(ref spot, _)
             | SpawnTrip::UsingTransit(ref spot, _, _, _, _) => match spot.connection {
@@ -480,17 +805,19 @@ impl SpawnTrip {
             SpawnTrip::VehicleAppearing { ref goal, .. }
             | SpawnTrip::FromBorder { ref goal, .. }
             | SpawnTrip::UsingParkedCar(_, ref goal)
+            | SpawnTrip::FromDriveway(_, ref goal)
             | SpawnTrip::UsingBike(_, ref goal) => match goal {
-                DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
+                DrivingGoal::ParkNear(b) | DrivingGoal::EnterDriveway(b) => TripEndpoint::Bldg(*b),
                 DrivingGoal::Border(i, _, ref loc) => TripEndpoint::Border(*i, loc.clone()),
             },
-            SpawnTrip::JustWalking(_, ref spot) | SpawnTrip::UsingTransit(_, ref spot, _, _, _) => {
-                match spot.connection {
-                    SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
-                    SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
-                    _ => unreachable!(),
-                }
-            }
+            SpawnTrip::ParkAndRideHome(_, _, b) => TripEndpoint::Bldg(*b),
+            SpawnTrip::JustWalking(_, ref spot)
+            | SpawnTrip::UsingTransit(_, ref spot, _, _, _)
+            | SpawnTrip::UsingParkAndRide(_, ref spot, _, _, _, _) => match spot.connection {
+                SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
+                SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
+                _ => unreachable!(),
+            },
             // Pick an arbitrary border
             SpawnTrip::Remote { ref to, .. } => {
                 TripEndpoint::Border(map.all_incoming_borders()[0].id, Some(to.clone()))
@@ -498,6 +825,78 @@ impl SpawnTrip {
         }
     }
 
+    fn check_ids(&self, map: &Map) -> Result<(), String> {
+        match self {
+            SpawnTrip::VehicleAppearing { start, goal, .. } => {
+                if map.maybe_get_l(start.lane()).is_none() {
+                    return Err(format!("{} doesn't exist", start.lane()));
+                }
+                check_goal(goal, map)
+            }
+            SpawnTrip::FromBorder { dr, goal, .. } => {
+                match map.maybe_get_r(dr.id) {
+                    Some(_) => check_border(dr.src_i(map), map)?,
+                    None => {
+                        return Err(format!("{} doesn't exist", dr.id));
+                    }
+                }
+                check_goal(goal, map)
+            }
+            SpawnTrip::UsingParkedCar(b, goal) | SpawnTrip::FromDriveway(b, goal) => {
+                check_bldg(*b, map)?;
+                check_goal(goal, map)
+            }
+            SpawnTrip::UsingBike(start, goal) => {
+                check_spot(start, map)?;
+                check_goal(goal, map)
+            }
+            SpawnTrip::JustWalking(start, end) => {
+                check_spot(start, map)?;
+                check_spot(end, map)
+            }
+            SpawnTrip::UsingTransit(start, end, route, stop1, stop2) => {
+                check_spot(start, map)?;
+                check_spot(end, map)?;
+                check_route(*route, map)?;
+                check_bus_stop(*stop1, map)?;
+                check_bus_stop(*stop2, map)
+            }
+            SpawnTrip::Remote { .. } => Ok(()),
+            SpawnTrip::UsingParkAndRide(b, end, pl, route, stop1, stop2) => {
+                check_bldg(*b, map)?;
+                check_spot(end, map)?;
+                check_parking_lot(*pl, map)?;
+                check_route(*route, map)?;
+                check_bus_stop(*stop1, map)?;
+                check_bus_stop(*stop2, map)
+            }
+            SpawnTrip::ParkAndRideHome(b1, pl, b2) => {
+                check_bldg(*b1, map)?;
+                check_parking_lot(*pl, map)?;
+                check_bldg(*b2, map)
+            }
+        }
+    }
+
+    pub fn mode(&self) -> TripMode {
+        match self {
+            SpawnTrip::VehicleAppearing { is_bike, .. } | SpawnTrip::FromBorder { is_bike, .. } => {
+                if *is_bike {
+                    TripMode::Bike
+                } else {
+                    TripMode::Drive
+                }
+            }
+            SpawnTrip::UsingParkedCar(_, _) | SpawnTrip::FromDriveway(_, _) => TripMode::Drive,
+            SpawnTrip::UsingBike(_, _) => TripMode::Bike,
+            SpawnTrip::JustWalking(_, _) => TripMode::Walk,
+            SpawnTrip::UsingTransit(_, _, _, _, _)
+            | SpawnTrip::UsingParkAndRide(_, _, _, _, _, _)
+            | SpawnTrip::ParkAndRideHome(_, _, _) => TripMode::Transit,
+            SpawnTrip::Remote { mode, .. } => *mode,
+        }
+    }
+
     pub fn new(from: TripEndpoint, to: TripEndpoint, mode: TripMode, map: &Map) -> SpawnTrip {
         match mode {
             TripMode::Drive => match from {
@@ -529,6 +928,13 @@ impl SpawnTrip {
             TripMode::Transit => {
                 let start = from.start_sidewalk_spot(map);
                 let goal = to.end_sidewalk_spot(map);
+                if let TripEndpoint::Bldg(b) = from {
+                    if let Some((lot, stop1, stop2, route)) =
+                        map.should_use_park_and_ride(b, goal.sidewalk_pos)
+                    {
+                        return SpawnTrip::UsingParkAndRide(b, goal, lot, route, stop1, stop2);
+                    }
+                }
                 if let Some((stop1, stop2, route)) =
                     map.should_use_transit(start.sidewalk_pos, goal.sidewalk_pos)
                 {
@@ -543,9 +949,99 @@ impl SpawnTrip {
     }
 }
 
+fn check_bldg(b: BuildingID, map: &Map) -> Result<(), String> {
+    if map.maybe_get_b(b).is_none() {
+        return Err(format!("{} doesn't exist", b));
+    }
+    Ok(())
+}
+
+fn check_border(i: IntersectionID, map: &Map) -> Result<(), String> {
+    match map.maybe_get_i(i) {
+        Some(i) if i.is_border() => Ok(()),
+        Some(_) => Err(format!("{} isn't a border", i)),
+        None => Err(format!("{} doesn't exist", i)),
+    }
+}
+
+fn check_bus_stop(stop: BusStopID, map: &Map) -> Result<(), String> {
+    if map.maybe_get_bs(stop).is_none() {
+        return Err(format!("{} doesn't exist", stop));
+    }
+    Ok(())
+}
+
+fn check_route(route: BusRouteID, map: &Map) -> Result<(), String> {
+    if route.0 >= map.get_all_bus_routes().len() {
+        return Err(format!("{} doesn't exist", route));
+    }
+    Ok(())
+}
+
+fn check_parking_lot(pl: ParkingLotID, map: &Map) -> Result<(), String> {
+    if map.maybe_get_pl(pl).is_none() {
+        return Err(format!("{} doesn't exist", pl));
+    }
+    Ok(())
+}
+
+fn check_goal(goal: &DrivingGoal, map: &Map) -> Result<(), String> {
+    match goal {
+        DrivingGoal::ParkNear(b) | DrivingGoal::EnterDriveway(b) => check_bldg(*b, map),
+        DrivingGoal::Border(i, l, _) => {
+            check_border(*i, map)?;
+            if map.maybe_get_l(*l).is_none() {
+                return Err(format!("{} doesn't exist", l));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn check_spot(spot: &SidewalkSpot, map: &Map) -> Result<(), String> {
+    if map.maybe_get_l(spot.sidewalk_pos.lane()).is_none() {
+        return Err(format!("{} doesn't exist", spot.sidewalk_pos.lane()));
+    }
+    match spot.connection {
+        SidewalkPOI::Building(b) => check_bldg(b, map),
+        SidewalkPOI::BusStop(stop) => check_bus_stop(stop, map),
+        SidewalkPOI::Border(i, _) => check_border(i, map),
+        _ => Ok(()),
+    }
+}
+
 impl PersonSpec {
     // Verify that the trip start/endpoints of the person match up
     fn check_schedule(&self, map: &Map) -> Result<(), String> {
+        for trip in &self.trips {
+            let mut driveways = Vec::new();
+            match trip.trip {
+                SpawnTrip::VehicleAppearing { ref goal, .. }
+                | SpawnTrip::FromBorder { ref goal, .. }
+                | SpawnTrip::UsingParkedCar(_, ref goal)
+                | SpawnTrip::UsingBike(_, ref goal) => {
+                    if let DrivingGoal::EnterDriveway(b) = goal {
+                        driveways.push(*b);
+                    }
+                }
+                SpawnTrip::FromDriveway(b, ref goal) => {
+                    driveways.push(b);
+                    if let DrivingGoal::EnterDriveway(b) = goal {
+                        driveways.push(*b);
+                    }
+                }
+                _ => {}
+            }
+            for b in driveways {
+                if map.get_b(b).parking.is_none() {
+                    return Err(format!(
+                        "At {}, {} {:?} uses the driveway of {}, which doesn't have one",
+                        trip.depart, self.id, self.orig_id, b
+                    ));
+                }
+            }
+        }
+
         for pair in self.trips.iter().zip(self.trips.iter().skip(1)) {
             if pair.0.depart >= pair.1.depart {
                 return Err(format!(
@@ -586,6 +1082,39 @@ impl PersonSpec {
         Ok(())
     }
 
+    // A car driven to a park-and-ride lot stays there until the person comes back for it, so the
+    // first later trip heading back to where the car started goes through the lot. None if no
+    // trips change.
+    fn retrieve_park_and_ride_cars(&self, map: &Map) -> Option<PersonSpec> {
+        let mut replace = Vec::new();
+        let mut car_left_at: Option<(BuildingID, ParkingLotID)> = None;
+        for (idx, trip) in self.trips.iter().enumerate() {
+            if let Some((home, lot)) = car_left_at {
+                if let (TripEndpoint::Bldg(start), TripEndpoint::Bldg(end)) =
+                    (trip.trip.start(map), trip.trip.end(map))
+                {
+                    if end == home && start != home {
+                        replace.push((idx, SpawnTrip::ParkAndRideHome(start, lot, home)));
+                        car_left_at = None;
+                        continue;
+                    }
+                }
+            }
+            if let SpawnTrip::UsingParkAndRide(b, _, lot, _, _, _) = trip.trip {
+                car_left_at = Some((b, lot));
+            }
+        }
+        if replace.is_empty() {
+            return None;
+        }
+
+        let mut fixed = self.clone();
+        for (idx, trip) in replace {
+            fixed.trips[idx].trip = trip;
+        }
+        Some(fixed)
+    }
+
     fn get_vehicles(
         &self,
         rng: &mut XorShiftRng,
@@ -601,6 +1130,10 @@ impl PersonSpec {
         let mut bike_idx = None;
         // For each indexed car, is it parked somewhere, or off-map?
         let mut car_locations: Vec<(usize, Option<BuildingID>)> = Vec::new();
+        // Cars that pulled into a driveway aren't in any parking spot, and only leave the same way
+        let mut cars_in_garage: Vec<(usize, BuildingID)> = Vec::new();
+        // Cars left at a park-and-ride lot
+        let mut cars_in_lot: Vec<(usize, ParkingLotID)> = Vec::new();
 
         for trip in &self.trips {
             let use_for_trip = match trip.trip {
@@ -640,6 +1173,9 @@ impl PersonSpec {
                             DrivingGoal::Border(_, _, _) => {
                                 car_locations.push((idx, None));
                             }
+                            DrivingGoal::EnterDriveway(b) => {
+                                cars_in_garage.push((idx, *b));
+                            }
                         }
 
                         Some(idx)
@@ -670,6 +1206,82 @@ impl PersonSpec {
                         DrivingGoal::Border(_, _, _) => {
                             car_locations.push((idx, None));
                         }
+                        DrivingGoal::EnterDriveway(b) => {
+                            cars_in_garage.push((idx, *b));
+                        }
+                    }
+
+                    Some(idx)
+                }
+                SpawnTrip::UsingParkAndRide(b, _, lot, _, _, _) => {
+                    let idx = if let Some(idx) = car_locations
+                        .iter()
+                        .find(|(_, parked_at)| *parked_at == Some(b))
+                        .map(|(idx, _)| *idx)
+                    {
+                        idx
+                    } else {
+                        let idx = vehicle_specs.len();
+                        vehicle_specs.push(Scenario::rand_car(rng));
+                        cars_initially_parked_at.push((idx, b));
+                        idx
+                    };
+
+                    car_locations.retain(|(i, _)| idx != *i);
+                    cars_in_lot.push((idx, lot));
+
+                    Some(idx)
+                }
+                SpawnTrip::ParkAndRideHome(_, lot, home) => {
+                    let idx = if let Some(idx) = cars_in_lot
+                        .iter()
+                        .find(|(_, parked_at)| *parked_at == lot)
+                        .map(|(idx, _)| *idx)
+                    {
+                        idx
+                    } else {
+                        // The trip only exists because of an earlier park-and-ride, so this
+                        // shouldn't happen. Just start a new car at home; the trip won't find it
+                        // in the lot and will go home without it.
+                        let idx = vehicle_specs.len();
+                        vehicle_specs.push(Scenario::rand_car(rng));
+                        cars_initially_parked_at.push((idx, home));
+                        idx
+                    };
+
+                    cars_in_lot.retain(|(i, _)| idx != *i);
+                    car_locations.retain(|(i, _)| idx != *i);
+                    car_locations.push((idx, Some(home)));
+
+                    Some(idx)
+                }
+                SpawnTrip::FromDriveway(b, ref goal) => {
+                    // Is there already a car in this garage?
+                    let idx = if let Some(idx) = cars_in_garage
+                        .iter()
+                        .find(|(_, garage)| *garage == b)
+                        .map(|(idx, _)| *idx)
+                    {
+                        idx
+                    } else {
+                        // Need a new car. It's inside the building, so it doesn't start in any
+                        // parking spot.
+                        let idx = vehicle_specs.len();
+                        vehicle_specs.push(Scenario::rand_car(rng));
+                        idx
+                    };
+
+                    cars_in_garage.retain(|(i, _)| idx != *i);
+                    match goal {
+                        DrivingGoal::ParkNear(b) => {
+                            car_locations.push((idx, Some(*b)));
+                        }
+                        DrivingGoal::Border(_, _, _) => {
+                            car_locations.push((idx, None));
+                        }
+                        DrivingGoal::EnterDriveway(b) => {
+                            cars_in_garage.push((idx, *b));
+                        }
                     }
 
                     Some(idx)
@@ -694,7 +1306,7 @@ impl PersonSpec {
                 n -= 1;
             }
             if n > 1 {
-                println!("{} needs {} cars", self.id, n);
+                debug!("{} needs {} cars", self.id, n);
             }
         }
 
@@ -4,8 +4,10 @@ use crate::{
 };
 use abstutil::Timer;
 use geom::{Duration, Time, EPSILON_DIST};
+use log::{debug, warn};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints, PathRequest, Position,
+    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, ParkingLotID, PathConstraints,
+    PathRequest, Position,
 };
 use serde::{Deserialize, Serialize};
 
@@ -58,6 +60,34 @@ pub enum TripSpec {
         trip_time: Duration,
         mode: TripMode,
     },
+    // The person gets in a vehicle inside the building (that isn't tracked in any parking spot)
+    // and pulls out of the driveway.
+    LeavingDriveway {
+        start_bldg: BuildingID,
+        goal: DrivingGoal,
+        // This must be a currently off-map vehicle owned by the person.
+        use_vehicle: CarID,
+    },
+    // Drive a parked car to a park-and-ride lot, then continue on transit. If the lot is full when
+    // the trip starts, walk or take transit straight from the building instead.
+    UsingParkAndRide {
+        car: CarID,
+        start_bldg: BuildingID,
+        lot: ParkingLotID,
+        goal: SidewalkSpot,
+        route: BusRouteID,
+        stop1: BusStopID,
+        stop2: BusStopID,
+    },
+    // Go back to the park-and-ride lot, optionally on transit, and drive the car left there home.
+    // If the car isn't there, go home without it.
+    ParkAndRideHome {
+        car: CarID,
+        start_bldg: BuildingID,
+        lot: ParkingLotID,
+        transit: Option<(BusStopID, BusStopID, BusRouteID)>,
+        home: BuildingID,
+    },
 }
 
 // This structure is created temporarily by a Scenario or to interactively spawn agents.
@@ -113,11 +143,19 @@ impl TripSpawner {
                             );
                         }
                     }
-                    DrivingGoal::ParkNear(_) => {}
+                    DrivingGoal::ParkNear(_) | DrivingGoal::EnterDriveway(_) => {}
                 }
             }
             TripSpec::NoRoomToSpawn { .. } => {}
             TripSpec::UsingParkedCar { .. } => {}
+            TripSpec::LeavingDriveway { start_bldg, .. } => {
+                if map.get_b(*start_bldg).parking.is_none() {
+                    panic!(
+                        "Can't leave {} by driveway; it doesn't have one",
+                        start_bldg
+                    );
+                }
+            }
             TripSpec::JustWalking { start, goal, .. } => {
                 if start == goal {
                     panic!(
@@ -130,7 +168,7 @@ impl TripSpawner {
                 // TODO These trips are just silently erased; they don't even show up as aborted
                 // trips! Really need to fix the underlying problem.
                 if SidewalkSpot::bike_from_bike_rack(start.sidewalk_pos.lane(), map).is_none() {
-                    println!(
+                    warn!(
                         "Can't start biking from {}; no biking or driving lane nearby?",
                         start.sidewalk_pos.lane()
                     );
@@ -144,7 +182,7 @@ impl TripSpawner {
                         .bike_to_sidewalk(last_lane)
                         .is_none()
                     {
-                        println!(
+                        warn!(
                             "Can't fulfill {:?} for a bike trip; no sidewalk near {}",
                             goal, last_lane
                         );
@@ -152,7 +190,7 @@ impl TripSpawner {
                     }
                     // A bike trip going from one lane to the same lane should... just walk.
                     if start.sidewalk_pos.lane() == map.get_b(*b).sidewalk() {
-                        println!(
+                        debug!(
                             "Bike trip from {:?} to {:?} will just walk; it's the same sidewalk!",
                             start, goal
                         );
@@ -171,6 +209,7 @@ impl TripSpawner {
             }
             TripSpec::UsingTransit { .. } => {}
             TripSpec::Remote { .. } => {}
+            TripSpec::UsingParkAndRide { .. } | TripSpec::ParkAndRideHome { .. } => {}
         };
 
         self.trips.push((person.id, start_time, spec, trip_start));
@@ -267,7 +306,23 @@ impl TripSpawner {
                         DrivingGoal::ParkNear(b) => {
                             legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
                         }
-                        DrivingGoal::Border(_, _, _) => {}
+                        DrivingGoal::Border(_, _, _) | DrivingGoal::EnterDriveway(_) => {}
+                    }
+                    trips.new_trip(
+                        person.id,
+                        start_time,
+                        trip_start,
+                        TripMode::Drive,
+                        legs,
+                        map,
+                    )
+                }
+                TripSpec::LeavingDriveway {
+                    goal, use_vehicle, ..
+                } => {
+                    let mut legs = vec![TripLeg::Drive(use_vehicle, goal.clone())];
+                    if let DrivingGoal::ParkNear(b) = goal {
+                        legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
                     }
                     trips.new_trip(
                         person.id,
@@ -297,7 +352,7 @@ impl TripSpawner {
                         DrivingGoal::ParkNear(b) => {
                             legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
                         }
-                        DrivingGoal::Border(_, _, _) => {}
+                        DrivingGoal::Border(_, _, _) | DrivingGoal::EnterDriveway(_) => {}
                     };
                     trips.new_trip(person.id, start_time, trip_start, TripMode::Bike, legs, map)
                 }
@@ -322,6 +377,48 @@ impl TripSpawner {
                         map,
                     )
                 }
+                TripSpec::UsingParkAndRide {
+                    car,
+                    lot,
+                    goal,
+                    route,
+                    stop1,
+                    stop2,
+                    ..
+                } => trips.new_trip(
+                    person.id,
+                    start_time,
+                    trip_start,
+                    TripMode::Transit,
+                    vec![
+                        TripLeg::Walk(SidewalkSpot::deferred_parking_spot()),
+                        TripLeg::DriveToLot(car, lot),
+                        TripLeg::Walk(SidewalkSpot::bus_stop(stop1, map)),
+                        TripLeg::RideBus(route, stop2),
+                        TripLeg::Walk(goal),
+                    ],
+                    map,
+                ),
+                TripSpec::ParkAndRideHome {
+                    car, transit, home, ..
+                } => {
+                    let mut legs = Vec::new();
+                    if let Some((stop1, stop2, route)) = transit {
+                        legs.push(TripLeg::Walk(SidewalkSpot::bus_stop(stop1, map)));
+                        legs.push(TripLeg::RideBus(route, stop2));
+                    }
+                    legs.push(TripLeg::Walk(SidewalkSpot::deferred_parking_spot()));
+                    legs.push(TripLeg::Drive(car, DrivingGoal::ParkNear(home)));
+                    legs.push(TripLeg::Walk(SidewalkSpot::building(home, map)));
+                    trips.new_trip(
+                        person.id,
+                        start_time,
+                        trip_start,
+                        TripMode::Transit,
+                        legs,
+                        map,
+                    )
+                }
                 TripSpec::Remote { to, mode, .. } => trips.new_trip(
                     person.id,
                     start_time,
@@ -380,6 +477,13 @@ impl TripSpec {
             TripSpec::NoRoomToSpawn { .. } => None,
             // We don't know where the parked car will be
             TripSpec::UsingParkedCar { .. } => None,
+            TripSpec::LeavingDriveway {
+                start_bldg, goal, ..
+            } => Some(PathRequest {
+                start: map.get_b(*start_bldg).parking.as_ref()?.driving_pos,
+                end: goal.goal_pos(PathConstraints::Car, map),
+                constraints: PathConstraints::Car,
+            }),
             TripSpec::JustWalking { start, goal, .. } => Some(PathRequest {
                 start: start.sidewalk_pos,
                 end: goal.sidewalk_pos,
@@ -398,6 +502,8 @@ impl TripSpec {
                 constraints: PathConstraints::Pedestrian,
             }),
             TripSpec::Remote { .. } => None,
+            // Like UsingParkedCar
+            TripSpec::UsingParkAndRide { .. } | TripSpec::ParkAndRideHome { .. } => None,
         }
     }
 }
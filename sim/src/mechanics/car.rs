@@ -3,7 +3,8 @@ use crate::{
     TransitSimState, TripID, Vehicle, VehicleType,
 };
 use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{Map, Traversable};
+use log::warn;
+use map_model::{BuildingID, Map, Traversable, NORMAL_LANE_THICKNESS};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
@@ -25,7 +26,13 @@ pub struct Car {
 
 impl Car {
     // Assumes the current head of the path is the thing to cross.
-    pub fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> CarState {
+    pub fn crossing_state(
+        &self,
+        start_dist: Distance,
+        start_time: Time,
+        map: &Map,
+        narrow_lane_slowdown: bool,
+    ) -> CarState {
         let dist_int = DistanceInterval::new_driving(
             start_dist,
             if self.router.last_step() {
@@ -34,7 +41,7 @@ impl Car {
                 self.router.head().length(map)
             },
         );
-        self.crossing_state_with_end_dist(dist_int, start_time, map)
+        self.crossing_state_with_end_dist(dist_int, start_time, map, narrow_lane_slowdown)
     }
 
     pub fn crossing_state_with_end_dist(
@@ -42,29 +49,55 @@ impl Car {
         dist_int: DistanceInterval,
         start_time: Time,
         map: &Map,
+        narrow_lane_slowdown: bool,
     ) -> CarState {
         let on = self.router.head();
-        let mut speed = on.speed_limit(map);
+        let mut speed = on.speed_limit_at(map, start_time);
         if let Some(s) = self.vehicle.max_speed {
             speed = speed.min(s);
         }
+        if narrow_lane_slowdown {
+            if let Traversable::Lane(l) = on {
+                // In proportion to how squeezed the lane is, but never below half speed
+                let width = map.get_l(l).width;
+                if width < NORMAL_LANE_THICKNESS {
+                    speed = speed * (width / NORMAL_LANE_THICKNESS).max(0.5);
+                }
+            }
+        }
         let dt = (dist_int.end - dist_int.start) / speed;
         CarState::Crossing(TimeInterval::new(start_time, start_time + dt), dist_int)
     }
 
+    // None if the geometry is too weird to draw the car. This is logged, but shouldn't take down
+    // the whole UI.
     pub fn get_draw_car(
         &self,
         front: Distance,
         now: Time,
         map: &Map,
         transit: &TransitSimState,
-    ) -> DrawCarInput {
+    ) -> Option<DrawCarInput> {
         assert!(front >= Distance::ZERO);
         // This goes from back to front
         let raw_body = if front >= self.vehicle.length {
-            self.router
+            match self
+                .router
                 .head()
-                .exact_slice(front - self.vehicle.length, front, map)
+                .maybe_exact_slice(front - self.vehicle.length, front, map)
+            {
+                Ok(pl) => pl,
+                Err(err) => {
+                    warn!(
+                        "Can't draw {} at {} on {:?}: {}",
+                        self.vehicle.id,
+                        front,
+                        self.router.head(),
+                        err
+                    );
+                    return None;
+                }
+            }
         } else {
             // TODO This is redoing some of the Path::trace work...
             let mut result = self
@@ -72,32 +105,34 @@ impl Car {
                 .head()
                 .slice(Distance::ZERO, front, map)
                 .map(|(pl, _)| pl.into_points())
-                .unwrap_or_else(Vec::new);
+                .unwrap_or_else(|_| Vec::new());
             let mut leftover = self.vehicle.length - front;
             let mut i = 0;
             while leftover > Distance::ZERO {
                 if i == self.last_steps.len() {
-                    panic!(
+                    warn!(
                         "{} spawned too close to short stuff; still need to account for {}",
                         self.vehicle.id, leftover
                     );
+                    return None;
                 }
                 let len = self.last_steps[i].length(map);
                 let start = (len - leftover).max(Distance::ZERO);
                 let piece = self.last_steps[i]
                     .slice(start, len, map)
                     .map(|(pl, _)| pl.into_points())
-                    .unwrap_or_else(Vec::new);
+                    .unwrap_or_else(|_| Vec::new());
                 result = PolyLine::append(piece, result);
                 leftover -= len;
                 i += 1;
             }
 
             if result.len() < 2 {
-                panic!(
+                warn!(
                     "{} at {} has front at {}. Didn't even wind up with two points",
                     self.vehicle.id, now, front
                 );
+                return None;
             }
             PolyLine::new(result)
         };
@@ -133,33 +168,26 @@ impl Car {
                             ParkingSpot::Lot(pl, _) => &map.get_pl(*pl).driveway_line,
                             _ => unreachable!(),
                         };
-
-                        // Append the car's polyline on the street with the driveway
-                        let full_piece = if is_parking {
-                            raw_body.extend(driveway.reversed())
-                        } else {
-                            driveway.clone().extend(raw_body).reversed()
-                        };
-                        // Then make the car creep along the added length of the driveway (which
-                        // could be really short)
-                        let creep_along = driveway.length() * percent_time;
-                        // TODO Ideally the car would slowly (dis)appear into the building, but
-                        // some stuff downstream needs to understand that the windows and such will
-                        // get cut off. :)
-                        let sliced =
-                            full_piece.exact_slice(creep_along, creep_along + self.vehicle.length);
-                        if is_parking {
-                            sliced
-                        } else {
-                            sliced.reversed()
-                        }
+                        self.along_driveway(raw_body, driveway, percent_time, is_parking)?
                     }
                 }
             }
+            CarState::LeavingDriveway(_, b, ref time_int) => self.along_driveway(
+                raw_body,
+                &map.get_b(b).parking.as_ref().unwrap().driveway_line,
+                1.0 - time_int.percent(now),
+                false,
+            )?,
+            CarState::EnteringDriveway(_, b, ref time_int) => self.along_driveway(
+                raw_body,
+                &map.get_b(b).parking.as_ref().unwrap().driveway_line,
+                time_int.percent(now),
+                true,
+            )?,
             _ => raw_body,
         };
 
-        DrawCarInput {
+        Some(DrawCarInput {
             id: self.vehicle.id,
             waiting_for_turn: match self.state {
                 // TODO Maybe also when Crossing?
@@ -178,11 +206,13 @@ impl Car {
                 // Eh they're technically moving, but this is a bit easier to spot
                 CarState::Unparking(_, _, _) => CarStatus::Parked,
                 CarState::Parking(_, _, _) => CarStatus::Parked,
+                CarState::LeavingDriveway(_, _, _) => CarStatus::Parked,
+                CarState::EnteringDriveway(_, _, _) => CarStatus::Parked,
                 // Changing color for idling buses is helpful
                 CarState::Idling(_, _) => CarStatus::Parked,
             },
             on: self.router.head(),
-            label: if self.vehicle.vehicle_type == VehicleType::Bus {
+            label: if self.vehicle.vehicle_type.is_transit() {
                 Some(
                     map.get_br(transit.bus_route(self.vehicle.id))
                         .name
@@ -192,6 +222,40 @@ impl Car {
                 None
             },
             body,
+        })
+    }
+
+    // Pull the car's body onto (or off of) a driveway
+    fn along_driveway(
+        &self,
+        raw_body: PolyLine,
+        driveway: &PolyLine,
+        percent_time: f64,
+        is_parking: bool,
+    ) -> Option<PolyLine> {
+        // Append the car's polyline on the street with the driveway
+        let full_piece = if is_parking {
+            raw_body.extend(driveway.reversed())
+        } else {
+            driveway.clone().extend(raw_body).reversed()
+        };
+        // Then make the car creep along the added length of the driveway (which could be really
+        // short)
+        let creep_along = driveway.length() * percent_time;
+        // TODO Ideally the car would slowly (dis)appear into the building, but some stuff
+        // downstream needs to understand that the windows and such will get cut off. :)
+        let sliced =
+            match full_piece.maybe_exact_slice(creep_along, creep_along + self.vehicle.length) {
+                Ok(pl) => pl,
+                Err(err) => {
+                    warn!("Can't draw {} on its driveway: {}", self.vehicle.id, err);
+                    return None;
+                }
+            };
+        if is_parking {
+            Some(sliced)
+        } else {
+            Some(sliced.reversed())
         }
     }
 }
@@ -205,6 +269,9 @@ pub enum CarState {
     Unparking(Distance, ParkingSpot, TimeInterval),
     Parking(Distance, ParkingSpot, TimeInterval),
     Idling(Distance, TimeInterval),
+    // Like Unparking and Parking, but the car isn't in any tracked spot inside the building
+    LeavingDriveway(Distance, BuildingID, TimeInterval),
+    EnteringDriveway(Distance, BuildingID, TimeInterval),
 }
 
 impl CarState {
@@ -216,6 +283,8 @@ impl CarState {
             CarState::Unparking(_, _, ref time_int) => time_int.end,
             CarState::Parking(_, _, ref time_int) => time_int.end,
             CarState::Idling(_, ref time_int) => time_int.end,
+            CarState::LeavingDriveway(_, _, ref time_int) => time_int.end,
+            CarState::EnteringDriveway(_, _, ref time_int) => time_int.end,
         }
     }
 }
@@ -2,19 +2,26 @@ use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::{
     ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DistanceInterval,
-    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSimState, PersonID, Scheduler,
-    TimeInterval, TransitSimState, TripManager, TripPositions, UnzoomedAgent, Vehicle,
-    WalkingSimState, FOLLOWING_DISTANCE,
+    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSimState, ParkingSpot, PersonID,
+    Scheduler, TimeInterval, TransitSimState, TripManager, TripPositions, UnzoomedAgent, Vehicle,
+    VehicleType, WalkingSimState,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::{Distance, Duration, PolyLine, Time};
+use log::{info, warn};
 use map_model::{LaneID, Map, Path, PathStep, Traversable};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashSet, VecDeque};
 
 const TIME_TO_UNPARK: Duration = Duration::const_seconds(10.0);
 const TIME_TO_PARK: Duration = Duration::const_seconds(15.0);
+// Pulling straight in or out of a driveway is quicker than parallel parking, but the car still
+// blocks the lane while it happens.
+const TIME_TO_LEAVE_DRIVEWAY: Duration = Duration::const_seconds(5.0);
+const TIME_TO_ENTER_DRIVEWAY: Duration = Duration::const_seconds(6.0);
 const TIME_TO_WAIT_AT_STOP: Duration = Duration::const_seconds(10.0);
+// Trains have more doors, but more people get on and off at a station
+const TIME_TO_WAIT_AT_STATION: Duration = Duration::const_seconds(30.0);
 
 // TODO Do something else.
 pub(crate) const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
@@ -35,26 +42,42 @@ pub struct DrivingSimState {
     events: Vec<Event>,
 
     recalc_lanechanging: bool,
+    narrow_lane_slowdown: bool,
 }
 
 impl DrivingSimState {
-    pub fn new(map: &Map, recalc_lanechanging: bool) -> DrivingSimState {
+    // See Queue::following_distance. Each queue picks up the new value once it's empty, so always
+    // use the queue's own value for spacing.
+    pub fn set_following_distance(&mut self, dist: Distance) {
+        for queue in self.queues.values_mut() {
+            queue.next_following_distance = dist;
+            queue.maybe_change_following_distance();
+        }
+    }
+
+    pub fn new(
+        map: &Map,
+        recalc_lanechanging: bool,
+        narrow_lane_slowdown: bool,
+        following_distance: Distance,
+    ) -> DrivingSimState {
         let mut sim = DrivingSimState {
             cars: BTreeMap::new(),
             queues: BTreeMap::new(),
             events: Vec::new(),
             recalc_lanechanging,
+            narrow_lane_slowdown,
         };
 
         for l in map.all_lanes() {
             if l.lane_type.is_for_moving_vehicles() {
-                let q = Queue::new(Traversable::Lane(l.id), map);
+                let q = Queue::new(Traversable::Lane(l.id), map, following_distance);
                 sim.queues.insert(q.id, q);
             }
         }
         for t in map.all_turns().values() {
             if !t.between_sidewalks() {
-                let q = Queue::new(Traversable::Turn(t.id), map);
+                let q = Queue::new(Traversable::Turn(t.id), map, following_distance);
                 sim.queues.insert(q.id, q);
             }
         }
@@ -98,7 +121,13 @@ impl DrivingSimState {
                 car.state = CarState::Unparking(
                     params.start_dist,
                     p.spot,
-                    TimeInterval::new(now, now + TIME_TO_UNPARK),
+                    TimeInterval::new(now, now + time_to_unpark(p.spot)),
+                );
+            } else if let Some(b) = params.leaving_driveway {
+                car.state = CarState::LeavingDriveway(
+                    params.start_dist,
+                    b,
+                    TimeInterval::new(now, now + TIME_TO_LEAVE_DRIVEWAY),
                 );
             } else {
                 // Have to do this early
@@ -123,23 +152,25 @@ impl DrivingSimState {
                     // We might've decided to go park somewhere farther, so get_end_dist no longer
                     // makes sense.
                     if car.router.last_step() && params.start_dist > car.router.get_end_dist() {
-                        println!(
-                            "WARNING: {} wants to spawn past their end on a one-step path",
+                        warn!(
+                            "{} wants to spawn past their end on a one-step path",
                             car.vehicle.id
                         );
                         return false;
                     }
                 }
 
-                car.state = car.crossing_state(params.start_dist, now, map);
+                car.state =
+                    car.crossing_state(params.start_dist, now, map, self.narrow_lane_slowdown);
             }
             scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             {
                 let queue = self.queues.get_mut(&Traversable::Lane(first_lane)).unwrap();
+                queue.maybe_change_following_distance();
                 queue.cars.insert(idx, car.vehicle.id);
                 // Don't use try_to_reserve_entry -- it's overly conservative.
                 // get_idx_to_insert_car does a more detailed check of the current space usage.
-                queue.reserved_length += car.vehicle.length + FOLLOWING_DISTANCE;
+                queue.reserved_length += car.vehicle.length + queue.following_distance;
             }
             self.cars.insert(car.vehicle.id, car);
             return true;
@@ -183,7 +214,7 @@ impl DrivingSimState {
             let car = &self.cars[&id];
             match car.state {
                 CarState::Queued { .. } => car.router.last_step(),
-                CarState::Parking(_, _, _) => true,
+                CarState::Parking(_, _, _) | CarState::EnteringDriveway(_, _, _) => true,
                 _ => false,
             }
         };
@@ -258,7 +289,7 @@ impl DrivingSimState {
                     scheduler.push(now, Command::UpdateCar(car.vehicle.id));
                 }
             }
-            CarState::Unparking(front, _, _) => {
+            CarState::Unparking(front, _, _) | CarState::LeavingDriveway(front, _, _) => {
                 if car.router.last_step() {
                     // Actually, we need to do this first. Ignore the answer -- if we're
                     // doing something weird like vanishing or re-parking immediately
@@ -273,14 +304,14 @@ impl DrivingSimState {
                         &mut self.events,
                     );
                 }
-                car.state = car.crossing_state(front, now, map);
+                car.state = car.crossing_state(front, now, map, self.narrow_lane_slowdown);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             }
             CarState::Idling(dist, _) => {
                 car.router = transit.bus_departed_from_stop(car.vehicle.id);
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
-                car.state = car.crossing_state(dist, now, map);
+                car.state = car.crossing_state(dist, now, map, self.narrow_lane_slowdown);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
 
                 // Update our follower, so they know we stopped idling.
@@ -300,9 +331,10 @@ impl DrivingSimState {
                                 follower.total_blocked_time += now - blocked_since;
                                 follower.state = follower.crossing_state(
                                     // Since the follower was Queued, this must be where they are.
-                                    dist - car.vehicle.length - FOLLOWING_DISTANCE,
+                                    dist - car.vehicle.length - queue.following_distance,
                                     now,
                                     map,
+                                    self.narrow_lane_slowdown,
                                 );
                                 scheduler.update(
                                     follower.state.get_end_time(),
@@ -317,7 +349,9 @@ impl DrivingSimState {
                         CarState::Crossing(_, _)
                         | CarState::Unparking(_, _, _)
                         | CarState::Parking(_, _, _)
-                        | CarState::Idling(_, _) => {}
+                        | CarState::Idling(_, _)
+                        | CarState::LeavingDriveway(_, _, _)
+                        | CarState::EnteringDriveway(_, _, _) => {}
                     }
                 }
             }
@@ -329,7 +363,7 @@ impl DrivingSimState {
                 assert!(from != goto);
 
                 if let Traversable::Turn(t) = goto {
-                    let mut speed = goto.speed_limit(map);
+                    let mut speed = goto.speed_limit_at(map, now);
                     if let Some(s) = car.vehicle.max_speed {
                         speed = speed.min(s);
                     }
@@ -364,7 +398,7 @@ impl DrivingSimState {
                     &mut self.events,
                 );
                 car.total_blocked_time += now - blocked_since;
-                car.state = car.crossing_state(Distance::ZERO, now, map);
+                car.state = car.crossing_state(Distance::ZERO, now, map, self.narrow_lane_slowdown);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.events.push(Event::AgentEntersTraversable(
                     AgentID::Car(car.vehicle.id),
@@ -382,10 +416,11 @@ impl DrivingSimState {
                     car.crossing_state_with_end_dist(
                         DistanceInterval::new_driving(
                             Distance::ZERO,
-                            car.vehicle.length + FOLLOWING_DISTANCE,
+                            car.vehicle.length + self.queues[&goto].following_distance,
                         ),
                         now,
                         map,
+                        self.narrow_lane_slowdown,
                     )
                     .get_end_time(),
                     Command::UpdateLaggyHead(car.vehicle.id),
@@ -397,7 +432,7 @@ impl DrivingSimState {
                     .cars
                     .push_back(car.vehicle.id);
             }
-            CarState::Parking(_, _, _) => unreachable!(),
+            CarState::Parking(_, _, _) | CarState::EnteringDriveway(_, _, _) => unreachable!(),
         }
         false
     }
@@ -422,6 +457,7 @@ impl DrivingSimState {
             CarState::Crossing(_, _)
             | CarState::Unparking(_, _, _)
             | CarState::Idling(_, _)
+            | CarState::LeavingDriveway(_, _, _)
             | CarState::WaitingToAdvance { .. } => unreachable!(),
             CarState::Queued { blocked_since } => {
                 match car.router.maybe_handle_end(
@@ -464,7 +500,7 @@ impl DrivingSimState {
                         car.state = CarState::Parking(
                             our_dist,
                             spot,
-                            TimeInterval::new(now, now + TIME_TO_PARK),
+                            TimeInterval::new(now, now + time_to_park(spot)),
                         );
                         // If we don't do this, then we might have another car creep up
                         // behind, see the spot free, and start parking too. This can
@@ -474,9 +510,21 @@ impl DrivingSimState {
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         true
                     }
+                    Some(ActionAtEnd::EnterDriveway(b)) => {
+                        car.total_blocked_time += now - blocked_since;
+                        car.state = CarState::EnteringDriveway(
+                            our_dist,
+                            b,
+                            TimeInterval::new(now, now + TIME_TO_ENTER_DRIVEWAY),
+                        );
+                        scheduler
+                            .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                        true
+                    }
                     Some(ActionAtEnd::GotoLaneEnd) => {
                         car.total_blocked_time += now - blocked_since;
-                        car.state = car.crossing_state(our_dist, now, map);
+                        car.state =
+                            car.crossing_state(our_dist, now, map, self.narrow_lane_slowdown);
                         scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         true
@@ -499,13 +547,21 @@ impl DrivingSimState {
                             car.vehicle.id,
                             trips,
                             walking,
+                            parking,
                             scheduler,
                             map,
                         );
                         car.total_blocked_time += now - blocked_since;
                         car.state = CarState::Idling(
                             our_dist,
-                            TimeInterval::new(now, now + TIME_TO_WAIT_AT_STOP),
+                            TimeInterval::new(
+                                now,
+                                now + if car.vehicle.vehicle_type == VehicleType::Train {
+                                    TIME_TO_WAIT_AT_STATION
+                                } else {
+                                    TIME_TO_WAIT_AT_STOP
+                                },
+                            ),
                         );
                         scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
@@ -556,6 +612,18 @@ impl DrivingSimState {
                 );
                 false
             }
+            CarState::EnteringDriveway(_, b, _) => {
+                trips.car_entered_driveway(
+                    now,
+                    car.vehicle.id,
+                    b,
+                    car.total_blocked_time,
+                    map,
+                    parking,
+                    scheduler,
+                );
+                false
+            }
         }
     }
 
@@ -578,7 +646,7 @@ impl DrivingSimState {
         // Hacks to delete cars that're mid-turn
         if let Traversable::Turn(_) = car.router.head() {
             let queue = self.queues.get_mut(&car.router.head()).unwrap();
-            queue.reserved_length += car.vehicle.length + FOLLOWING_DISTANCE;
+            queue.reserved_length += car.vehicle.length + queue.following_distance;
         }
         if let Some(Traversable::Turn(t)) = car.router.maybe_next() {
             intersections.cancel_request(AgentID::Car(c), t);
@@ -638,7 +706,8 @@ impl DrivingSimState {
                 CarState::Queued { blocked_since } => {
                     // Prevent them from jumping forwards.
                     follower.total_blocked_time += now - blocked_since;
-                    follower.state = follower.crossing_state(follower_dist, now, map);
+                    follower.state =
+                        follower.crossing_state(follower_dist, now, map, self.narrow_lane_slowdown);
                     scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -648,7 +717,8 @@ impl DrivingSimState {
                     // If the follower was still Crossing, they might not've been blocked
                     // by leader yet. In that case, recalculating their Crossing state is a
                     // no-op.
-                    follower.state = follower.crossing_state(follower_dist, now, map);
+                    follower.state =
+                        follower.crossing_state(follower_dist, now, map, self.narrow_lane_slowdown);
                     scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -657,7 +727,9 @@ impl DrivingSimState {
                 // They weren't blocked
                 CarState::Unparking(_, _, _)
                 | CarState::Parking(_, _, _)
-                | CarState::Idling(_, _) => {}
+                | CarState::Idling(_, _)
+                | CarState::LeavingDriveway(_, _, _)
+                | CarState::EnteringDriveway(_, _, _) => {}
                 CarState::WaitingToAdvance { .. } => unreachable!(),
             }
         }
@@ -687,7 +759,8 @@ impl DrivingSimState {
         };
 
         // Trim off as many of the oldest last_steps as we've made distance.
-        let mut dist_left_to_cleanup = self.cars[&id].vehicle.length + FOLLOWING_DISTANCE;
+        let following_distance = self.queues[&currently_on].following_distance;
+        let mut dist_left_to_cleanup = self.cars[&id].vehicle.length + following_distance;
         dist_left_to_cleanup -= dist_along_last;
         let mut num_to_trim = None;
         for (idx, step) in self.cars[&id].last_steps.iter().enumerate() {
@@ -713,10 +786,11 @@ impl DrivingSimState {
                     // fine for correctness.
                     DistanceInterval::new_driving(
                         dist_along_last,
-                        self.cars[&id].vehicle.length + FOLLOWING_DISTANCE,
+                        self.cars[&id].vehicle.length + following_distance,
                     ),
                     now,
                     map,
+                    self.narrow_lane_slowdown,
                 )
                 .get_end_time();
             // Sometimes due to rounding, retry_at will be exactly time, but we really need to
@@ -797,7 +871,9 @@ impl DrivingSimState {
                         CarState::Crossing(_, _)
                         | CarState::Unparking(_, _, _)
                         | CarState::Parking(_, _, _)
-                        | CarState::Idling(_, _) => {}
+                        | CarState::Idling(_, _)
+                        | CarState::LeavingDriveway(_, _, _)
+                        | CarState::EnteringDriveway(_, _, _) => {}
                     }
                 }
             } else {
@@ -829,6 +905,39 @@ impl DrivingSimState {
         result
     }
 
+    // For every lane with cars stopped at the end, how many are queued there and how far back
+    // from the end they stretch. Cars still moving towards the end of the lane don't count.
+    pub fn get_queue_lengths(&self, now: Time) -> BTreeMap<LaneID, (usize, Distance)> {
+        let mut result = BTreeMap::new();
+        for queue in self.queues.values() {
+            let l = match queue.id {
+                Traversable::Lane(l) => l,
+                Traversable::Turn(_) => continue,
+            };
+            if queue.cars.is_empty() {
+                continue;
+            }
+
+            let mut count = 0;
+            let mut back = queue.geom_len;
+            // Front of the queue first
+            for (c, front) in queue.get_car_positions(now, &self.cars, &self.queues) {
+                let car = &self.cars[&c];
+                match car.state {
+                    CarState::Queued { .. } | CarState::WaitingToAdvance { .. } => {
+                        count += 1;
+                        back = (front - car.vehicle.length).max(Distance::ZERO);
+                    }
+                    _ => break,
+                }
+            }
+            if count > 0 {
+                result.insert(l, (count, queue.geom_len - back));
+            }
+        }
+        result
+    }
+
     pub fn does_car_exist(&self, id: CarID) -> bool {
         self.cars.contains_key(&id)
     }
@@ -863,7 +972,7 @@ impl DrivingSimState {
                 queue
                     .get_car_positions(now, &self.cars, &self.queues)
                     .into_iter()
-                    .map(|(id, dist)| self.cars[&id].get_draw_car(dist, now, map, transit)),
+                    .filter_map(|(id, dist)| self.cars[&id].get_draw_car(dist, now, map, transit)),
             );
         }
         result
@@ -894,24 +1003,37 @@ impl DrivingSimState {
             Some(q) => q
                 .get_car_positions(now, &self.cars, &self.queues)
                 .into_iter()
-                .map(|(id, dist)| self.cars[&id].get_draw_car(dist, now, map, transit))
+                .filter_map(|(id, dist)| self.cars[&id].get_draw_car(dist, now, map, transit))
                 .collect(),
             None => Vec::new(),
         }
     }
 
+    pub fn path_size_bytes(&self) -> usize {
+        self.cars
+            .values()
+            .map(|car| abstutil::serialized_size_bytes(car.router.get_path()))
+            .sum()
+    }
+
+    pub fn collect_agent_states(&self, states: &mut BTreeMap<AgentID, String>) {
+        for (id, car) in &self.cars {
+            states.insert(AgentID::Car(*id), abstutil::to_json(car));
+        }
+    }
+
     pub fn debug_car(&self, id: CarID) {
         if let Some(ref car) = self.cars.get(&id) {
-            println!("{}", abstutil::to_json(car));
-            println!("State: {:?}", car.state);
+            info!("{}", abstutil::to_json(car));
+            info!("State: {:?}", car.state);
         } else {
-            println!("{} is parked somewhere", id);
+            info!("{} is parked somewhere", id);
         }
     }
 
     pub fn debug_lane(&self, id: LaneID) {
         if let Some(ref queue) = self.queues.get(&Traversable::Lane(id)) {
-            println!("{}", abstutil::to_json(queue));
+            info!("{}", abstutil::to_json(queue));
         }
     }
 
@@ -962,7 +1084,7 @@ impl DrivingSimState {
             .find(|(c, _)| *c == id)
             .unwrap()
             .1;
-        car.router.get_path().trace(map, front, dist_ahead)
+        car.router.get_path().trace(map, front, dist_ahead).ok()
     }
 
     pub fn percent_along_route(&self, id: CarID) -> f64 {
@@ -1040,3 +1162,17 @@ impl DrivingSimState {
         std::mem::replace(&mut self.events, Vec::new())
     }
 }
+
+fn time_to_unpark(spot: ParkingSpot) -> Duration {
+    match spot {
+        ParkingSpot::Onstreet(_, _) => TIME_TO_UNPARK,
+        ParkingSpot::Offstreet(_, _) | ParkingSpot::Lot(_, _) => TIME_TO_LEAVE_DRIVEWAY,
+    }
+}
+
+fn time_to_park(spot: ParkingSpot) -> Duration {
+    match spot {
+        ParkingSpot::Onstreet(_, _) => TIME_TO_PARK,
+        ParkingSpot::Offstreet(_, _) | ParkingSpot::Lot(_, _) => TIME_TO_ENTER_DRIVEWAY,
+    }
+}
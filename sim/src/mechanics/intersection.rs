@@ -1,17 +1,27 @@
-use crate::mechanics::car::Car;
+use crate::mechanics::car::{Car, CarState};
+use crate::mechanics::walking::crowded_walking_speed;
 use crate::mechanics::Queue;
-use crate::{AgentID, AlertLocation, CarID, Command, Event, Scheduler, Speed, TripMode};
+use crate::{
+    AgentID, AlertLocation, CarID, Command, ConflictType, Event, Scheduler, Speed, TripMode,
+    VehicleType,
+};
 use abstutil::{deserialize_btreemap, retain_btreeset, serialize_btreemap};
-use geom::{Duration, Time};
+use geom::{Acceleration, Duration, Time};
+use log::info;
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, Map, RoadID, Traversable,
-    TurnID, TurnPriority, TurnType,
+    ApproachControl, ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, Map, RoadID,
+    Traversable, TurnID, TurnPriority, TurnType,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
+// Starting a turn less than this long before a car with the right of way arrives counts as a
+// conflict. 1.5s is a common post-encroachment time threshold.
+const CONFLICT_GAP: Duration = Duration::const_seconds(1.5);
+// Stopping harder than this counts as hard braking. About a third of a g.
+const HARD_BRAKING: Acceleration = Acceleration::const_meters_per_second_squared(3.4);
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct IntersectionSimState {
@@ -19,6 +29,12 @@ pub struct IntersectionSimState {
     use_freeform_policy_everywhere: bool,
     dont_block_the_box: bool,
     break_turn_conflict_cycles: bool,
+    yield_critical_gap: Duration,
+    // After a signal turns green, vehicles that were already waiting can't start for this long
+    start_up_lost_time: Duration,
+    right_turn_on_red: bool,
+    // See SimOptions::crowded_walking
+    crowded_walking: bool,
     // (x, y) means x is blocked by y. It's a many-to-many relationship. TODO Better data
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
@@ -50,12 +66,19 @@ impl IntersectionSimState {
         use_freeform_policy_everywhere: bool,
         dont_block_the_box: bool,
         break_turn_conflict_cycles: bool,
+        yield_critical_gap: Duration,
+        right_turn_on_red: bool,
+        crowded_walking: bool,
     ) -> IntersectionSimState {
         let mut sim = IntersectionSimState {
             state: BTreeMap::new(),
             use_freeform_policy_everywhere,
             dont_block_the_box,
             break_turn_conflict_cycles,
+            yield_critical_gap,
+            start_up_lost_time: Duration::ZERO,
+            right_turn_on_red,
+            crowded_walking,
             blocked_by: BTreeSet::new(),
             events: Vec::new(),
         };
@@ -75,6 +98,11 @@ impl IntersectionSimState {
         sim
     }
 
+    pub fn set_timing(&mut self, yield_critical_gap: Duration, start_up_lost_time: Duration) {
+        self.yield_critical_gap = yield_critical_gap;
+        self.start_up_lost_time = start_up_lost_time;
+    }
+
     pub fn nobody_headed_towards(&self, lane: LaneID, i: IntersectionID) -> bool {
         !self.state[&i]
             .accepted
@@ -230,6 +258,7 @@ impl IntersectionSimState {
         )>,
     ) -> bool {
         let req = Request { agent, turn };
+        let first_request = !self.state[&turn.parent].waiting.contains_key(&req);
         self.state
             .get_mut(&turn.parent)
             .unwrap()
@@ -248,8 +277,20 @@ impl IntersectionSimState {
             unreachable!()
         };
         if !allowed {
+            if first_request && self.stopped_hard(&req, now, map) {
+                self.events.push(Event::ConflictMeasured(
+                    turn.parent,
+                    ConflictType::HardBraking,
+                ));
+            }
             return false;
         }
+        // Only recorded if the turn actually starts
+        let conflicts = if let (AgentID::Car(_), Some((cars, queues))) = (agent, readonly_pair) {
+            self.find_conflicts(&req, now, map, cars, queues)
+        } else {
+            Vec::new()
+        };
 
         // Don't block the box
         if let Some((car, _, queues)) = maybe_cars_and_queues {
@@ -292,12 +333,16 @@ impl IntersectionSimState {
         let delay = now - state.waiting.remove(&req).unwrap();
         if map.maybe_get_traffic_signal(state.id).is_some() {
             self.events.push(Event::IntersectionDelayMeasured(
-                turn.parent,
+                turn,
                 delay,
                 TripMode::from_agent(agent),
             ));
         }
         state.accepted.insert(req);
+        for conflict in conflicts {
+            self.events
+                .push(Event::ConflictMeasured(turn.parent, conflict));
+        }
         if self.break_turn_conflict_cycles {
             if let AgentID::Car(car) = agent {
                 retain_btreeset(&mut self.blocked_by, |(c, _)| *c != car);
@@ -308,13 +353,13 @@ impl IntersectionSimState {
     }
 
     pub fn debug(&self, id: IntersectionID, map: &Map) {
-        println!("{}", abstutil::to_json(&self.state[&id]));
+        info!("{}", abstutil::to_json(&self.state[&id]));
         if let Some(ref sign) = map.maybe_get_stop_sign(id) {
-            println!("{}", abstutil::to_json(sign));
+            info!("{}", abstutil::to_json(sign));
         } else if let Some(ref signal) = map.maybe_get_traffic_signal(id) {
-            println!("{}", abstutil::to_json(signal));
+            info!("{}", abstutil::to_json(signal));
         } else {
-            println!("Border");
+            info!("Border");
         }
     }
 
@@ -423,7 +468,11 @@ impl IntersectionSimState {
         assert!(our_priority != TurnPriority::Banned);
         let our_time = self.state[&req.turn.parent].waiting[req];
 
-        if our_priority == TurnPriority::Yield && now < our_time + WAIT_AT_STOP_SIGN {
+        // Drivers at a yield sign can roll through; everybody else yielding stops first.
+        let full_stop = our_priority == TurnPriority::Yield
+            && (map.get_t(req.turn).turn_type == TurnType::Crosswalk
+                || sign.roads[&map.get_l(req.turn.src).parent].control == ApproachControl::Stop);
+        if full_stop && now < our_time + WAIT_AT_STOP_SIGN {
             // Since we have "ownership" of scheduling for req.agent, don't need to use
             // scheduler.update.
             scheduler.push(
@@ -446,12 +495,172 @@ impl IntersectionSimState {
         // If a case #1 could've started by now, then they would have. Since they didn't, they must
         // be blocked.
 
-        // TODO Make sure we can optimistically finish this turn before an approaching
-        // higher-priority vehicle wants to begin.
+        // Don't pull out in front of a car on a priority road that's about to cross our path.
+        if our_priority == TurnPriority::Yield {
+            if let Some((cars, queues)) = maybe_cars_and_queues {
+                if let Some(arrival) =
+                    self.next_conflicting_arrival(req, map, sign, now, cars, queues)
+                {
+                    if arrival - now < self.yield_critical_gap {
+                        // If they're still moving, try again once they arrive. If they're stuck,
+                        // we'll get woken up when they go.
+                        if arrival > now {
+                            scheduler.push(arrival, Command::update_agent(req.agent));
+                        }
+                        return false;
+                    }
+                }
+            }
+        }
 
         true
     }
 
+    // When will the first car approaching on a priority road reach the intersection and want to do
+    // a turn conflicting with this request? Only looks at the front car in each lane.
+    fn next_conflicting_arrival(
+        &self,
+        req: &Request,
+        map: &Map,
+        sign: &ControlStopSign,
+        now: Time,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+    ) -> Option<Time> {
+        let our_turn = map.get_t(req.turn);
+        let mut earliest: Option<Time> = None;
+        for (r, ss) in &sign.roads {
+            if ss.control != ApproachControl::Priority {
+                continue;
+            }
+            for (l, _) in map.get_r(*r).incoming_lanes(sign.id) {
+                let car = match queues
+                    .get(&Traversable::Lane(*l))
+                    .and_then(|q| q.cars.front())
+                {
+                    Some(id) => &cars[id],
+                    None => continue,
+                };
+                let turn = match car.router.maybe_next() {
+                    Some(Traversable::Turn(t)) => t,
+                    _ => continue,
+                };
+                if !our_turn.conflicts_with(map.get_t(turn)) {
+                    continue;
+                }
+                let arrival = match car.state {
+                    CarState::Queued { .. } | CarState::WaitingToAdvance { .. } => now,
+                    _ => car.state.get_end_time(),
+                };
+                if earliest.map(|t| arrival < t).unwrap_or(true) {
+                    earliest = Some(arrival);
+                }
+            }
+        }
+        earliest
+    }
+
+    // Does this turn have the right of way right now? Everybody else has to yield to it.
+    fn has_right_of_way(&self, turn: TurnID, now: Time, map: &Map) -> bool {
+        if self.use_freeform_policy_everywhere {
+            return false;
+        }
+        if let Some(signal) = map.maybe_get_traffic_signal(turn.parent) {
+            let (_, phase, _) = signal.current_phase_and_remaining_time(now);
+            phase.get_priority_of_turn(turn, signal) == TurnPriority::Protected
+        } else if let Some(sign) = map.maybe_get_stop_sign(turn.parent) {
+            sign.get_priority(turn, map) == TurnPriority::Protected
+        } else {
+            false
+        }
+    }
+
+    // Surrogate safety measures for a car about to start a turn
+    fn find_conflicts(
+        &self,
+        req: &Request,
+        now: Time,
+        map: &Map,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+    ) -> Vec<ConflictType> {
+        let mut conflicts = Vec::new();
+        let our_turn = map.get_t(req.turn);
+
+        // Did we just cut off a moving car with the right of way? Only the front car in each lane
+        // matters.
+        if !self.has_right_of_way(req.turn, now, map) {
+            for l in &map.get_i(req.turn.parent).incoming_lanes {
+                let car = match queues
+                    .get(&Traversable::Lane(*l))
+                    .and_then(|q| q.cars.front())
+                {
+                    Some(id) => &cars[id],
+                    None => continue,
+                };
+                if AgentID::Car(car.vehicle.id) == req.agent {
+                    continue;
+                }
+                let arrival = match car.state {
+                    CarState::Crossing(ref time_int, _) => time_int.end,
+                    _ => continue,
+                };
+                let turn = match car.router.maybe_next() {
+                    Some(Traversable::Turn(t)) => t,
+                    _ => continue,
+                };
+                if arrival - now < CONFLICT_GAP
+                    && our_turn.conflicts_with(map.get_t(turn))
+                    && self.has_right_of_way(turn, now, map)
+                {
+                    conflicts.push(ConflictType::SmallGap);
+                    break;
+                }
+            }
+        }
+
+        // Is somebody who's allowed to cross waiting on a crosswalk we're about to drive over?
+        let ped_waiting = self.state[&req.turn.parent].waiting.keys().any(|other| {
+            matches!(other.agent, AgentID::Pedestrian(_))
+                && our_turn.conflicts_with(map.get_t(other.turn))
+                && self.has_right_of_way(other.turn, now, map)
+        });
+        if ped_waiting {
+            conflicts.push(ConflictType::PedestrianNearMiss);
+        }
+
+        conflicts
+    }
+
+    // A car arriving at a signal that just turned red might have had to slam on the brakes. Did
+    // the light change too recently to stop comfortably from the approach's speed limit?
+    fn stopped_hard(&self, req: &Request, now: Time, map: &Map) -> bool {
+        if !matches!(req.agent, AgentID::Car(_))
+            || is_train(req.agent)
+            || self.use_freeform_policy_everywhere
+        {
+            return false;
+        }
+        let signal = match map.maybe_get_traffic_signal(req.turn.parent) {
+            Some(s) => s,
+            None => {
+                return false;
+            }
+        };
+        let (idx, phase, remaining_phase_time) = signal.current_phase_and_remaining_time(now);
+        let prev_phase = &signal.phases[(idx + signal.phases.len() - 1) % signal.phases.len()];
+        if phase.get_priority_of_turn(req.turn, signal) != TurnPriority::Banned
+            || prev_phase.get_priority_of_turn(req.turn, signal) == TurnPriority::Banned
+        {
+            return false;
+        }
+
+        // Where was the car when the light changed?
+        let since_change = phase.duration - remaining_phase_time;
+        let speed = Traversable::Lane(req.turn.src).speed_limit_at(map, now);
+        HARD_BRAKING.stopping_distance(speed) > speed * since_change
+    }
+
     fn traffic_signal_policy(
         &mut self,
         req: &Request,
@@ -469,13 +678,32 @@ impl IntersectionSimState {
             return true;
         }
 
-        let (_, phase, remaining_phase_time) = signal.current_phase_and_remaining_time(now);
+        // Transit signal priority. Trains don't wait for their phase, just for anybody already in
+        // the way to finish. Nobody new starts a conflicting turn while a train waits.
+        if is_train(req.agent) {
+            return self.handle_accepted_conflicts(req, map, maybe_cars_and_queues);
+        }
+        if self.state[&req.turn.parent]
+            .waiting
+            .keys()
+            .any(|other| is_train(other.agent) && map.get_t(other.turn).conflicts_with(turn))
+        {
+            return false;
+        }
+
+        let (idx, phase, mut remaining_phase_time) = signal.current_phase_and_remaining_time(now);
 
         // Can't go at all this phase.
         let our_priority = phase.get_priority_of_turn(req.turn, signal);
         if our_priority == TurnPriority::Banned {
             return false;
         }
+        if our_priority == TurnPriority::Yield
+            && !(self.right_turn_on_red && map.get_i(turn.id.parent).right_turn_on_red)
+            && phase.is_right_turn_on_red(req.turn, signal, map)
+        {
+            return false;
+        }
 
         // Somebody might already be doing a Yield turn that conflicts with this one.
         if !self.handle_accepted_conflicts(req, map, maybe_cars_and_queues) {
@@ -483,6 +711,17 @@ impl IntersectionSimState {
         }
 
         let our_time = self.state[&req.turn.parent].waiting[req];
+        // Drivers who were stopped for the red take a moment to react and get moving
+        let green_since = now - (phase.duration - remaining_phase_time);
+        if let AgentID::Car(_) = req.agent {
+            if our_time < green_since && now < green_since + self.start_up_lost_time {
+                scheduler.update(
+                    green_since + self.start_up_lost_time,
+                    Command::update_agent(req.agent),
+                );
+                return false;
+            }
+        }
         if our_priority == TurnPriority::Yield
             && now < our_time + WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL
         {
@@ -503,18 +742,45 @@ impl IntersectionSimState {
         // TODO Make sure we can optimistically finish this turn before an approaching
         // higher-priority vehicle wants to begin.
 
+        // Crosswalks stay green after a leading pedestrian interval
+        let mut phase_duration = phase.duration;
+        if turn.turn_type == TurnType::Crosswalk && signal.is_leading_pedestrian_interval(idx) {
+            let next = signal.phases[(idx + 1) % signal.phases.len()].duration;
+            phase_duration += next;
+            remaining_phase_time += next;
+        }
+
         // Optimistically if nobody else is in the way, this is how long it'll take to finish the
         // turn. Don't start the turn if we won't finish by the time the light changes. If we get
         // it wrong, that's fine -- block the box a bit.
-        let time_to_cross = turn.geom.length() / speed;
+        let mut time_to_cross = turn.geom.length() / speed;
+        if turn.turn_type == TurnType::Crosswalk && self.crowded_walking {
+            // A big platoon of pedestrians takes longer to clear the crosswalk.
+            let state = &self.state[&req.turn.parent];
+            let platoon = state
+                .waiting
+                .keys()
+                .chain(state.accepted.iter())
+                .filter(|other| other.turn == req.turn && other.agent != req.agent)
+                .count();
+            let crowded = turn.geom.length()
+                / crowded_walking_speed(speed, platoon, Traversable::Turn(req.turn), map);
+            // But if the platoon can't clear in any phase, don't make them wait forever. Anybody
+            // starting in the first half of the phase may go.
+            if crowded <= phase_duration {
+                time_to_cross = crowded;
+            } else if phase_duration / 2.0 > time_to_cross {
+                time_to_cross = phase_duration / 2.0;
+            }
+        }
         if time_to_cross > remaining_phase_time {
             // Actually, we might have bigger problems...
-            if time_to_cross > phase.duration {
+            if time_to_cross > phase_duration {
                 self.events.push(Event::Alert(
                     AlertLocation::Intersection(req.turn.parent),
                     format!(
                         "{:?} is impossible to fit into phase duration of {}",
-                        req, phase.duration
+                        req, phase_duration
                     ),
                 ));
             } else {
@@ -619,6 +885,13 @@ impl IntersectionSimState {
     }
 }
 
+fn is_train(agent: AgentID) -> bool {
+    match agent {
+        AgentID::Car(c) => c.1 == VehicleType::Train,
+        _ => false,
+    }
+}
+
 // TODO Sometimes a traffic signal is surrounded by tiny lanes with almost no capacity. Workaround
 // for now.
 fn allow_block_the_box(osm_node_id: i64) -> bool {
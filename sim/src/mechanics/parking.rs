@@ -5,8 +5,8 @@ use abstutil::{
 };
 use geom::{Distance, PolyLine, Pt2D};
 use map_model::{
-    BuildingID, Lane, LaneID, LaneType, Map, ParkingLotID, PathConstraints, PathStep, Position,
-    Traversable, TurnID,
+    BikeRackID, BuildingID, Lane, LaneID, LaneType, Map, ParkingLotID, PathConstraints, PathStep,
+    Position, Traversable, TurnID,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
@@ -49,6 +49,26 @@ pub struct ParkingSimState {
         deserialize_with = "deserialize_multimap"
     )]
     driving_to_lots: MultiMap<LaneID, ParkingLotID>,
+    // Park-and-ride lots only take cars whose owners continue on transit. They claim a spot when
+    // their trip starts. Per lot, how many claimed cars haven't finished parking?
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    park_and_ride_claims: BTreeMap<ParkingLotID, usize>,
+
+    // Bike racks. A bike takes up space at a rack from the moment it sets off for it, until it's
+    // ridden away again.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    bikes_at_racks: BTreeMap<CarID, BikeRackID>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    bike_rack_usage: BTreeMap<BikeRackID, usize>,
 
     events: Vec<Event>,
 }
@@ -68,6 +88,9 @@ impl ParkingSimState {
             driving_to_offstreet: MultiMap::new(),
             num_spots_per_lot: BTreeMap::new(),
             driving_to_lots: MultiMap::new(),
+            park_and_ride_claims: BTreeMap::new(),
+            bikes_at_racks: BTreeMap::new(),
+            bike_rack_usage: BTreeMap::new(),
 
             events: Vec::new(),
         };
@@ -87,11 +110,11 @@ impl ParkingSimState {
         }
         for pl in map.all_parking_lots() {
             // TODO Parking lots without any spots shouldn't be possible
-            if pl.spots.is_empty() {
+            if pl.capacity() == 0 {
                 continue;
             }
             if map.get_l(pl.driving_pos.lane()).parking_blackhole.is_none() {
-                sim.num_spots_per_lot.insert(pl.id, pl.spots.len());
+                sim.num_spots_per_lot.insert(pl.id, pl.capacity());
                 sim.driving_to_lots.insert(pl.driving_pos.lane(), pl.id);
             }
         }
@@ -132,6 +155,63 @@ impl ParkingSimState {
         spots
     }
 
+    // Returns false if the lot is already full, counting cars on their way there.
+    pub fn claim_park_and_ride_spot(&mut self, pl: ParkingLotID) -> bool {
+        let free = self.get_free_lot_spots(pl).len();
+        let claims = self.park_and_ride_claims.entry(pl).or_insert(0);
+        if free > *claims {
+            *claims += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // When the car parks, or the trip is aborted first
+    pub fn release_park_and_ride_claim(&mut self, pl: ParkingLotID) {
+        *self.park_and_ride_claims.get_mut(&pl).unwrap() -= 1;
+    }
+
+    // Looks for a rack near the building with room, and holds space there for the bike.
+    pub fn claim_bike_rack(
+        &mut self,
+        bike: CarID,
+        near: BuildingID,
+        map: &Map,
+    ) -> Option<BikeRackID> {
+        assert!(!self.bikes_at_racks.contains_key(&bike));
+        for id in map.find_bike_racks_near_building(near) {
+            let rack = map.get_bike_rack(id);
+            // Edits might've taken bikes off the lane
+            let lane = map.get_l(rack.biking_pos.lane());
+            if !PathConstraints::Bike.can_use(lane, map) || lane.parking_blackhole.is_some() {
+                continue;
+            }
+            let used = self.bike_rack_usage.entry(id).or_insert(0);
+            if *used < rack.capacity {
+                *used += 1;
+                self.bikes_at_racks.insert(bike, id);
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    // Where the bike was left last, if it was at a rack
+    pub fn get_bike_rack(&self, bike: CarID) -> Option<BikeRackID> {
+        self.bikes_at_racks.get(&bike).cloned()
+    }
+
+    pub fn unlock_bike(&mut self, bike: CarID) {
+        if let Some(rack) = self.bikes_at_racks.remove(&bike) {
+            *self.bike_rack_usage.get_mut(&rack).unwrap() -= 1;
+        }
+    }
+
+    pub fn get_bike_rack_usage(&self, rack: BikeRackID) -> usize {
+        self.bike_rack_usage.get(&rack).cloned().unwrap_or(0)
+    }
+
     pub fn reserve_spot(&mut self, spot: ParkingSpot) {
         assert!(self.is_free(spot));
         self.reserved_spots.insert(spot);
@@ -219,7 +299,7 @@ impl ParkingSimState {
             ParkingSpot::Offstreet(_, _) => None,
             ParkingSpot::Lot(pl, idx) => {
                 let pl = map.get_pl(pl);
-                let (pt, angle) = pl.spots[idx];
+                let (pt, angle) = pl.spots.get(idx).cloned()?;
                 let buffer = Distance::meters(0.5);
                 Some(DrawCarInput {
                     id: p.vehicle.id,
@@ -242,9 +322,12 @@ impl ParkingSimState {
     pub fn canonical_pt(&self, id: CarID, map: &Map) -> Option<Pt2D> {
         let p = self.parked_cars.get(&id)?;
         match p.spot {
-            ParkingSpot::Onstreet(_, _) | ParkingSpot::Lot(_, _) => {
-                self.get_draw_car(id, map).map(|c| c.body.last_pt())
-            }
+            ParkingSpot::Onstreet(_, _) => self.get_draw_car(id, map).map(|c| c.body.last_pt()),
+            ParkingSpot::Lot(pl, _) => Some(
+                self.get_draw_car(id, map)
+                    .map(|c| c.body.last_pt())
+                    .unwrap_or_else(|| map.get_pl(pl).polygon.center()),
+            ),
             ParkingSpot::Offstreet(b, _) => Some(map.get_b(b).label_center),
         }
     }
@@ -308,6 +391,9 @@ impl ParkingSimState {
         }
 
         for pl in self.driving_to_lots.get(driving_pos.lane()) {
+            if map.get_pl(*pl).park_and_ride {
+                continue;
+            }
             let lot_dist = map.get_pl(*pl).driving_pos.dist_along();
             if driving_pos.dist_along() < lot_dist {
                 for idx in 0..self.num_spots_per_lot[&pl] {
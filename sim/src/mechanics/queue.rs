@@ -1,6 +1,7 @@
 use crate::mechanics::car::{Car, CarState};
-use crate::{CarID, FOLLOWING_DISTANCE};
+use crate::CarID;
 use geom::{Distance, Time};
+use log::info;
 use map_model::{Map, Traversable};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
@@ -13,22 +14,39 @@ pub struct Queue {
     pub laggy_head: Option<CarID>,
 
     pub geom_len: Distance,
-    // When a car's turn is accepted, reserve the vehicle length + FOLLOWING_DISTANCE for the
+    // When a car's turn is accepted, reserve the vehicle length + following_distance for the
     // target lane. When the car completely leaves (stops being the laggy_head), free up that
     // space. To prevent blocking the box for possibly scary amounts of time, allocate some of this
     // length first. This is unused for turns themselves. This value can exceed geom_len (for the
     // edge case of ONE long car on a short queue).
     pub reserved_length: Distance,
+
+    // Usually FOLLOWING_DISTANCE, but it can be calibrated while the sim runs. Changing it with
+    // cars on the queue would break the spacing everything else assumes, so the new value waits
+    // in next_following_distance until the queue is empty.
+    pub following_distance: Distance,
+    pub next_following_distance: Distance,
 }
 
 impl Queue {
-    pub fn new(id: Traversable, map: &Map) -> Queue {
+    pub fn new(id: Traversable, map: &Map, following_distance: Distance) -> Queue {
         Queue {
             id,
             cars: VecDeque::new(),
             laggy_head: None,
             geom_len: id.length(map),
             reserved_length: Distance::ZERO,
+            following_distance,
+            next_following_distance: following_distance,
+        }
+    }
+
+    pub fn maybe_change_following_distance(&mut self) {
+        if self.cars.is_empty()
+            && self.laggy_head.is_none()
+            && self.reserved_length == Distance::ZERO
+        {
+            self.following_distance = self.next_following_distance;
         }
     }
 
@@ -58,12 +76,12 @@ impl Queue {
         for id in &self.cars {
             let bound = match result.last() {
                 Some((leader, last_dist)) => {
-                    *last_dist - cars[leader].vehicle.length - FOLLOWING_DISTANCE
+                    *last_dist - cars[leader].vehicle.length - self.following_distance
                 }
                 None => match self.laggy_head {
                     Some(id) => {
                         // The simple but broken version:
-                        //self.geom_len - cars[&id].vehicle.length - FOLLOWING_DISTANCE
+                        //self.geom_len - cars[&id].vehicle.length - following_distance
 
                         // The expensive case. We need to figure out exactly where the laggy head
                         // is on their queue.
@@ -79,7 +97,7 @@ impl Queue {
                             // 1) Hope that the last person in this queue isn't bounded by the
                             //    agent in front of them yet. geom_len
                             // 2) Assume the leader has advanced minimally into the next lane.
-                            //    geom_len - laggy head's length - FOLLOWING_DISTANCE.
+                            //    geom_len - laggy head's length - following_distance.
                             //
                             // For now, optimistically assume 1. If we're wrong, consequences could
                             // be queue spillover (we're too optimistic about the number of
@@ -105,11 +123,11 @@ impl Queue {
                             // They might actually be out of the way, but laggy_head hasn't been
                             // updated yet.
                             if dist_away_from_this_queue
-                                < leader.vehicle.length + FOLLOWING_DISTANCE
+                                < leader.vehicle.length + self.following_distance
                             {
                                 self.geom_len
                                     - (cars[&id].vehicle.length - dist_away_from_this_queue)
-                                    - FOLLOWING_DISTANCE
+                                    - self.following_distance
                             } else {
                                 self.geom_len
                             }
@@ -151,11 +169,13 @@ impl Queue {
                 CarState::Unparking(front, _, _) => front,
                 CarState::Parking(front, _, _) => front,
                 CarState::Idling(front, _) => front,
+                CarState::LeavingDriveway(front, _, _) => front,
+                CarState::EnteringDriveway(front, _, _) => front,
             };
 
             result.push((*id, front));
         }
-        validate_positions(result, cars, now, self.id)
+        validate_positions(result, cars, now, self.id, self.following_distance)
     }
 
     pub fn get_idx_to_insert_car(
@@ -184,13 +204,13 @@ impl Queue {
 
         // Are we too close to the leader?
         if idx != 0
-            && dists[idx - 1].1 - cars[&dists[idx - 1].0].vehicle.length - FOLLOWING_DISTANCE
+            && dists[idx - 1].1 - cars[&dists[idx - 1].0].vehicle.length - self.following_distance
                 < start_dist
         {
             return None;
         }
         // Or the follower?
-        if idx != dists.len() && start_dist - vehicle_len - FOLLOWING_DISTANCE < dists[idx].1 {
+        if idx != dists.len() && start_dist - vehicle_len - self.following_distance < dists[idx].1 {
             return None;
         }
 
@@ -200,11 +220,12 @@ impl Queue {
     // If true, there's room and the car must actually start the turn (because the space is
     // reserved).
     pub fn try_to_reserve_entry(&mut self, car: &Car, force_entry: bool) -> bool {
-        // Sometimes a car + FOLLOWING_DISTANCE might be longer than the geom_len entirely. In that
+        self.maybe_change_following_distance();
+        // Sometimes a car + following_distance might be longer than the geom_len entirely. In that
         // case, it just means the car won't totally fit on the queue at once, which is fine.
         // Reserve the normal amount of space; the next car trying to enter will get rejected.
         // Also allow this don't-block-the-box prevention to be disabled.
-        let dist = car.vehicle.length + FOLLOWING_DISTANCE;
+        let dist = car.vehicle.length + self.following_distance;
         if self.reserved_length + dist < self.geom_len
             || self.reserved_length == Distance::ZERO
             || force_entry
@@ -218,11 +239,11 @@ impl Queue {
     // TODO Refactor
     pub fn room_for_car(&self, car: &Car) -> bool {
         self.reserved_length == Distance::ZERO
-            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.geom_len
+            || self.reserved_length + car.vehicle.length + self.following_distance < self.geom_len
     }
 
     pub fn free_reserved_space(&mut self, car: &Car) {
-        self.reserved_length -= car.vehicle.length + FOLLOWING_DISTANCE;
+        self.reserved_length -= car.vehicle.length + self.following_distance;
         assert!(self.reserved_length >= Distance::ZERO);
     }
 }
@@ -232,9 +253,10 @@ fn validate_positions(
     cars: &BTreeMap<CarID, Car>,
     now: Time,
     id: Traversable,
+    following_distance: Distance,
 ) -> Vec<(CarID, Distance)> {
     for pair in dists.windows(2) {
-        if pair[0].1 - cars[&pair[0].0].vehicle.length - FOLLOWING_DISTANCE < pair[1].1 {
+        if pair[0].1 - cars[&pair[0].0].vehicle.length - following_distance < pair[1].1 {
             dump_cars(&dists, cars, id, now);
             panic!(
                 "get_car_positions wound up with bad positioning: {} then {}\n{:?}",
@@ -251,33 +273,44 @@ fn dump_cars(
     id: Traversable,
     now: Time,
 ) {
-    println!("\nOn {} at {}...", id, now);
+    info!("On {} at {}...", id, now);
     for (id, dist) in dists {
         let car = &cars[id];
-        println!("- {} @ {} (length {})", id, dist, car.vehicle.length);
+        info!("- {} @ {} (length {})", id, dist, car.vehicle.length);
         match car.state {
             CarState::Crossing(ref time_int, ref dist_int) => {
-                println!(
+                info!(
                     "  Going {} .. {} during {} .. {}",
                     dist_int.start, dist_int.end, time_int.start, time_int.end
                 );
             }
             CarState::Queued { .. } => {
-                println!("  Queued currently");
+                info!("  Queued currently");
             }
             CarState::WaitingToAdvance { .. } => {
-                println!("  WaitingToAdvance currently");
+                info!("  WaitingToAdvance currently");
             }
             CarState::Unparking(_, _, ref time_int) => {
-                println!("  Unparking during {} .. {}", time_int.start, time_int.end);
+                info!("  Unparking during {} .. {}", time_int.start, time_int.end);
             }
             CarState::Parking(_, _, ref time_int) => {
-                println!("  Parking during {} .. {}", time_int.start, time_int.end);
+                info!("  Parking during {} .. {}", time_int.start, time_int.end);
             }
             CarState::Idling(_, ref time_int) => {
-                println!("  Idling during {} .. {}", time_int.start, time_int.end);
+                info!("  Idling during {} .. {}", time_int.start, time_int.end);
+            }
+            CarState::LeavingDriveway(_, _, ref time_int) => {
+                info!(
+                    "  Leaving driveway during {} .. {}",
+                    time_int.start, time_int.end
+                );
+            }
+            CarState::EnteringDriveway(_, _, ref time_int) => {
+                info!(
+                    "  Entering driveway during {} .. {}",
+                    time_int.start, time_int.end
+                );
             }
         }
     }
-    println!();
 }
@@ -6,14 +6,20 @@ use crate::{
 };
 use abstutil::{deserialize_multimap, serialize_multimap, MultiMap};
 use geom::{Distance, Duration, Line, PolyLine, Speed, Time};
+use log::info;
 use map_model::{
-    BuildingID, BusRouteID, Map, ParkingLotID, Path, PathStep, Traversable, SIDEWALK_THICKNESS,
+    BuildingID, BusRouteID, Map, ParkingLotID, Path, PathStep, Traversable, TurnType,
+    SIDEWALK_THICKNESS,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
 const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
+// Past this many people per square meter, nobody can move. Weidmann's fundamental diagram.
+const JAM_DENSITY: f64 = 5.4;
+// Even a packed crowd shuffles forward eventually.
+const MIN_CROWDED_SPEED_FACTOR: f64 = 0.1;
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct WalkingSimState {
@@ -25,14 +31,17 @@ pub struct WalkingSimState {
     )]
     peds_per_traversable: MultiMap<Traversable, PedestrianID>,
     events: Vec<Event>,
+    // See SimOptions::crowded_walking
+    crowded_walking: bool,
 }
 
 impl WalkingSimState {
-    pub fn new() -> WalkingSimState {
+    pub fn new(crowded_walking: bool) -> WalkingSimState {
         WalkingSimState {
             peds: BTreeMap::new(),
             peds_per_traversable: MultiMap::new(),
             events: Vec::new(),
+            crowded_walking,
         }
     }
 
@@ -84,7 +93,13 @@ impl WalkingSimState {
                 Line::new(driving_pos.pt(map), params.start.sidewalk_pos.pt(map)),
                 TimeInterval::new(now, now + TIME_TO_FINISH_BIKING),
             ),
-            _ => ped.crossing_state(params.start.sidewalk_pos.dist_along(), now, map),
+            _ => ped.crossing_state(
+                params.start.sidewalk_pos.dist_along(),
+                now,
+                &self.peds_per_traversable,
+                self.crowded_walking,
+                map,
+            ),
         };
 
         scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -223,6 +238,7 @@ impl WalkingSimState {
                         map,
                         intersections,
                         &mut self.peds_per_traversable,
+                        self.crowded_walking,
                         &mut self.events,
                         scheduler,
                     ) {
@@ -239,6 +255,7 @@ impl WalkingSimState {
                     map,
                     intersections,
                     &mut self.peds_per_traversable,
+                    self.crowded_walking,
                     &mut self.events,
                     scheduler,
                 ) {
@@ -247,8 +264,13 @@ impl WalkingSimState {
                 }
             }
             PedState::LeavingBuilding(b, _) => {
-                ped.state =
-                    ped.crossing_state(map.get_b(b).front_path.sidewalk.dist_along(), now, map);
+                ped.state = ped.crossing_state(
+                    map.get_b(b).front_path.sidewalk.dist_along(),
+                    now,
+                    &self.peds_per_traversable,
+                    self.crowded_walking,
+                    map,
+                );
                 scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
             }
             PedState::EnteringBuilding(bldg, _) => {
@@ -266,7 +288,13 @@ impl WalkingSimState {
                 self.peds.remove(&id);
             }
             PedState::LeavingParkingLot(pl, _) => {
-                ped.state = ped.crossing_state(map.get_pl(pl).sidewalk_pos.dist_along(), now, map);
+                ped.state = ped.crossing_state(
+                    map.get_pl(pl).sidewalk_pos.dist_along(),
+                    now,
+                    &self.peds_per_traversable,
+                    self.crowded_walking,
+                    map,
+                );
                 scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
             }
             PedState::EnteringParkingLot(_, _) => {
@@ -301,7 +329,13 @@ impl WalkingSimState {
                 self.peds.remove(&id);
             }
             PedState::FinishingBiking(ref spot, _, _) => {
-                ped.state = ped.crossing_state(spot.sidewalk_pos.dist_along(), now, map);
+                ped.state = ped.crossing_state(
+                    spot.sidewalk_pos.dist_along(),
+                    now,
+                    &self.peds_per_traversable,
+                    self.crowded_walking,
+                    map,
+                );
                 scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
             }
             PedState::WaitingForBus(_, _) => unreachable!(),
@@ -320,11 +354,24 @@ impl WalkingSimState {
         };
     }
 
+    pub fn path_size_bytes(&self) -> usize {
+        self.peds
+            .values()
+            .map(|ped| abstutil::serialized_size_bytes(&ped.path))
+            .sum()
+    }
+
+    pub fn collect_agent_states(&self, states: &mut BTreeMap<AgentID, String>) {
+        for (id, ped) in &self.peds {
+            states.insert(AgentID::Pedestrian(*id), abstutil::to_json(ped));
+        }
+    }
+
     pub fn debug_ped(&self, id: PedestrianID) {
         if let Some(ped) = self.peds.get(&id) {
-            println!("{}", abstutil::to_json(ped));
+            info!("{}", abstutil::to_json(ped));
         } else {
-            println!("{} doesn't exist", id);
+            info!("{} doesn't exist", id);
         }
     }
 
@@ -363,7 +410,7 @@ impl WalkingSimState {
         let body_radius = SIDEWALK_THICKNESS / 4.0;
         let dist = (p.get_dist_along(now, map) + body_radius)
             .min(p.path.current_step().as_traversable().length(map));
-        p.path.trace(map, dist, dist_ahead)
+        p.path.trace(map, dist, dist_ahead).ok()
     }
 
     pub fn get_path(&self, id: PedestrianID) -> Option<&Path> {
@@ -528,7 +575,16 @@ struct Pedestrian {
 }
 
 impl Pedestrian {
-    fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> PedState {
+    // If crowded_walking is on, walking speed depends on how crowded the sidewalk or crosswalk is
+    // when the pedestrian starts along it.
+    fn crossing_state(
+        &self,
+        start_dist: Distance,
+        start_time: Time,
+        peds_per_traversable: &MultiMap<Traversable, PedestrianID>,
+        crowded_walking: bool,
+        map: &Map,
+    ) -> PedState {
         let end_dist = if self.path.is_last_step() {
             self.goal.sidewalk_pos.dist_along()
         } else {
@@ -540,7 +596,18 @@ impl Pedestrian {
             }
         };
         let dist_int = DistanceInterval::new_walking(start_dist, end_dist);
-        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / self.speed);
+        let speed = if crowded_walking {
+            let on = self.path.current_step().as_traversable();
+            let others = peds_per_traversable
+                .get(on)
+                .iter()
+                .filter(|p| **p != self.id)
+                .count();
+            crowded_walking_speed(self.speed, others, on, map)
+        } else {
+            self.speed
+        };
+        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / speed);
         PedState::Crossing(dist_int, time_int)
     }
 
@@ -668,6 +735,7 @@ impl Pedestrian {
         map: &Map,
         intersections: &mut IntersectionSimState,
         peds_per_traversable: &mut MultiMap<Traversable, PedestrianID>,
+        crowded_walking: bool,
         events: &mut Vec<Event>,
         scheduler: &mut Scheduler,
     ) -> bool {
@@ -692,7 +760,8 @@ impl Pedestrian {
             PathStep::ContraflowLane(l) => map.get_l(l).length(),
             PathStep::Turn(_) => Distance::ZERO,
         };
-        self.state = self.crossing_state(start_dist, now, map);
+        self.state =
+            self.crossing_state(start_dist, now, peds_per_traversable, crowded_walking, map);
         peds_per_traversable.insert(self.path.current_step().as_traversable(), self.id);
         events.push(Event::AgentEntersTraversable(
             AgentID::Pedestrian(self.id),
@@ -702,6 +771,39 @@ impl Pedestrian {
     }
 }
 
+// How fast somebody can walk along a sidewalk or crosswalk, with some other people already on it.
+// Dense flows slow everybody down.
+pub(crate) fn crowded_walking_speed(
+    speed: Speed,
+    others: usize,
+    on: Traversable,
+    map: &Map,
+) -> Speed {
+    let area = match on {
+        Traversable::Lane(l) => {
+            let lane = map.get_l(l);
+            lane.length().inner_meters() * lane.width.inner_meters()
+        }
+        Traversable::Turn(t) => {
+            let turn = map.get_t(t);
+            // Corners are tiny, and people spread out into the crosswalks on either side anyway.
+            if turn.turn_type != TurnType::Crosswalk {
+                return speed;
+            }
+            turn.geom.length().inner_meters() * map.get_l(t.src).width.inner_meters()
+        }
+    };
+    if area <= 0.0 {
+        return speed;
+    }
+    let density = ((others + 1) as f64) / area;
+    if density >= JAM_DENSITY {
+        return speed * MIN_CROWDED_SPEED_FACTOR;
+    }
+    let factor = 1.0 - (-1.913 * (1.0 / density - 1.0 / JAM_DENSITY)).exp();
+    speed * factor.max(MIN_CROWDED_SPEED_FACTOR)
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 enum PedState {
     Crossing(DistanceInterval, TimeInterval),
@@ -0,0 +1,145 @@
+use crate::{Person, Sim, TripEndpoint, TripMode};
+use geom::{Distance, FindClosest};
+use map_model::{BuildingID, Map, RoadID};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Roads further than this from a building don't add anything noticeable
+const MAX_DIST: Distance = Distance::const_meters(300.0);
+// The noise model isn't valid closer than this to the kerb
+const MIN_DIST: Distance = Distance::const_meters(4.0);
+
+// Thresholds for reporting exposure, in dB(A). 55 is where the WHO starts to see health effects
+// from road noise; 65 and 70 are common thresholds for mitigation.
+pub const NOISE_THRESHOLDS: [f64; 3] = [55.0, 65.0, 70.0];
+
+// Estimates road traffic noise at every building, as an hourly equivalent level in dB(A) averaged
+// over the simulation so far. Uses the Calculation of Road Traffic Noise (CRTN) method from the
+// volume, speed limit, and share of buses on each road, then the distance from the road to the
+// building. Nothing blocks the sound, so buildings behind other buildings come out too loud.
+pub struct NoiseEstimate {
+    pub per_road: BTreeMap<RoadID, f64>,
+    pub per_building: BTreeMap<BuildingID, f64>,
+}
+
+impl NoiseEstimate {
+    pub fn new(sim: &Sim, map: &Map) -> NoiseEstimate {
+        let hours = (sim.time().inner_seconds() / 3600.0).max(1.0);
+        let mut vehicles: BTreeMap<RoadID, (usize, usize)> = BTreeMap::new();
+        for ((r, mode, _), count) in &sim.get_analytics().road_thruput.counts {
+            let entry = vehicles.entry(*r).or_insert((0, 0));
+            match mode {
+                TripMode::Drive => {
+                    entry.0 += count;
+                }
+                TripMode::Transit => {
+                    entry.1 += count;
+                }
+                _ => {}
+            }
+        }
+
+        let mut per_road = BTreeMap::new();
+        let mut closest = FindClosest::new(map.get_bounds());
+        for (r, (cars, buses)) in vehicles {
+            let flow = ((cars + buses) as f64) / hours;
+            if flow == 0.0 {
+                continue;
+            }
+            let pct_heavy = 100.0 * (buses as f64) / ((cars + buses) as f64);
+            let road = map.get_r(r);
+            // In km/h. The speed correction doesn't make sense below about 20.
+            let kph = (road.speed_limit.inner_meters_per_second() * 3.6).max(20.0);
+            per_road.insert(r, basic_noise_level(flow, kph, pct_heavy));
+            closest.add(r, road.center_pts.points());
+        }
+
+        let mut per_building = BTreeMap::new();
+        for b in map.all_buildings() {
+            let mut energy = 0.0;
+            for (r, _, dist) in closest.all_close_pts(b.label_center, MAX_DIST) {
+                let mut half_width = Distance::ZERO;
+                for l in map.get_r(r).all_lanes() {
+                    half_width += map.get_l(l).width / 2.0;
+                }
+                let from_kerb = if dist - half_width > MIN_DIST {
+                    dist - half_width
+                } else {
+                    MIN_DIST
+                };
+                energy += 10.0_f64.powf((per_road[&r] - distance_loss(from_kerb)) / 10.0);
+            }
+            if energy > 0.0 {
+                per_building.insert(b.id, 10.0 * energy.log10());
+            }
+        }
+
+        NoiseEstimate {
+            per_road,
+            per_building,
+        }
+    }
+
+    // How many people live somewhere at least as loud as each of NOISE_THRESHOLDS? Somebody's home
+    // is where their first trip starts.
+    pub fn exposure(&self, sim: &Sim) -> Vec<(f64, usize)> {
+        let mut counts = vec![0; NOISE_THRESHOLDS.len()];
+        for person in sim.get_all_people() {
+            if let Some(db) = home(sim, person).and_then(|b| self.per_building.get(&b)) {
+                for (idx, threshold) in NOISE_THRESHOLDS.iter().enumerate() {
+                    if db >= threshold {
+                        counts[idx] += 1;
+                    }
+                }
+            }
+        }
+        NOISE_THRESHOLDS.iter().cloned().zip(counts).collect()
+    }
+
+    // As CSV, with one row per building
+    pub fn to_csv(&self, sim: &Sim) -> String {
+        let mut residents: BTreeMap<BuildingID, usize> = BTreeMap::new();
+        for person in sim.get_all_people() {
+            if let Some(b) = home(sim, person) {
+                *residents.entry(b).or_insert(0) += 1;
+            }
+        }
+
+        let mut out = String::new();
+        writeln!(out, "building,noise_db,residents").unwrap();
+        for (b, db) in &self.per_building {
+            writeln!(
+                out,
+                "{},{:.1},{}",
+                b.0,
+                db,
+                residents.get(b).cloned().unwrap_or(0)
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+fn home(sim: &Sim, person: &Person) -> Option<BuildingID> {
+    match sim.trip_info(*person.trips.get(0)?).1 {
+        TripEndpoint::Bldg(b) => Some(b),
+        TripEndpoint::Border(_, _) => None,
+    }
+}
+
+// The level 10m from the kerb, converted from CRTN's L10 to an equivalent level by subtracting 3dB
+fn basic_noise_level(flow: f64, kph: f64, pct_heavy: f64) -> f64 {
+    let l10 = 42.2
+        + 10.0 * flow.log10()
+        + 33.0 * (kph + 40.0 + 500.0 / kph).log10()
+        + 10.0 * (1.0 + 5.0 * pct_heavy / kph).log10()
+        - 68.8;
+    l10 - 3.0
+}
+
+// Relative to the basic level, using the slant distance from a source 0.5m up to a receiver 4m up
+fn distance_loss(from_kerb: Distance) -> f64 {
+    let slant = ((from_kerb.inner_meters() + 3.5).powi(2) + 25.0).sqrt();
+    10.0 * (slant / 13.5).log10()
+}
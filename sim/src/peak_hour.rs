@@ -0,0 +1,345 @@
+use crate::analytics::{time_bin, TIME_BIN};
+use crate::{Analytics, TripMode};
+use geom::{Duration, Time};
+use map_model::{osm, IntersectionID, LaneType, Map, RoadID};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// A peak hour is four consecutive bins
+const BINS_PER_HOUR: usize = 4;
+
+// Summarizes a day the way a traffic study would: find the busiest hour in the morning and in the
+// afternoon, then report volumes, volume-to-capacity ratios, and signal delay for every road and
+// intersection during those hours.
+pub struct PeakHourReport {
+    // The rest are per TIME_BIN, starting at midnight.
+    // Vehicles entering any road
+    pub system_volume: Vec<usize>,
+    // Average delay of vehicles at traffic signals. None when nobody waited.
+    pub system_delay: Vec<Option<Duration>>,
+    pub system_conflicts: Vec<usize>,
+    // Including aborted trips
+    pub finished_trips: Vec<usize>,
+    pub am: Option<PeakHour>,
+    pub pm: Option<PeakHour>,
+}
+
+pub struct PeakHour {
+    pub start: Time,
+    // Vehicles entering any road during the hour
+    pub volume: usize,
+    // The hourly volume divided by 4 times the busiest bin's volume. 1.0 means traffic was steady
+    // through the hour.
+    pub peak_hour_factor: f64,
+    // Sorted by decreasing volume-to-capacity ratio
+    pub roads: Vec<LocationStats<RoadID>>,
+    pub intersections: Vec<LocationStats<IntersectionID>>,
+}
+
+pub struct LocationStats<X> {
+    pub id: X,
+    // Cars and buses during the hour
+    pub volume: usize,
+    // Vehicles per hour
+    pub capacity: usize,
+    // Only measured at traffic signals. For roads, this is the delay at the signal at the end.
+    pub avg_delay: Option<Duration>,
+}
+
+impl<X> LocationStats<X> {
+    pub fn volume_to_capacity(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        (self.volume as f64) / (self.capacity as f64)
+    }
+}
+
+impl PeakHourReport {
+    pub fn new(analytics: &Analytics, map: &Map, now: Time) -> PeakHourReport {
+        let num_bins = time_bin(now) + 1;
+        let mut system_volume = vec![0; num_bins];
+        for ((_, mode, bin), count) in &analytics.road_thruput.binned {
+            if is_vehicle(*mode) && *bin < num_bins {
+                system_volume[*bin] += count;
+            }
+        }
+        let mut delays = vec![(Duration::ZERO, 0); num_bins];
+        for list in analytics.intersection_delays.values() {
+            for (t, dt, mode, _) in list {
+                if is_vehicle(*mode) && *t <= now {
+                    delays[time_bin(*t)].0 += *dt;
+                    delays[time_bin(*t)].1 += 1;
+                }
+            }
+        }
+        let mut system_conflicts = vec![0; num_bins];
+        for list in analytics.intersection_conflicts.values() {
+            for (t, _) in list {
+                if *t <= now {
+                    system_conflicts[time_bin(*t)] += 1;
+                }
+            }
+        }
+        let mut finished_trips = vec![0; num_bins];
+        for (t, _, _, _) in &analytics.finished_trips {
+            if *t <= now {
+                finished_trips[time_bin(*t)] += 1;
+            }
+        }
+
+        // The AM peak starts before noon, the PM peak after. Times past midnight wrap around, so
+        // multi-day runs find the busiest hours across all days.
+        let noon = time_bin(Time::START_OF_DAY + Duration::hours(12));
+        let day = time_bin(Time::START_OF_DAY + Duration::hours(24));
+        let mut am_best: Option<(usize, usize)> = None;
+        let mut pm_best: Option<(usize, usize)> = None;
+        for start in 0..num_bins.saturating_sub(BINS_PER_HOUR - 1) {
+            let volume: usize = system_volume[start..start + BINS_PER_HOUR].iter().sum();
+            if volume == 0 {
+                continue;
+            }
+            let best = if start % day < noon {
+                &mut am_best
+            } else {
+                &mut pm_best
+            };
+            if best.map(|(_, v)| volume > v).unwrap_or(true) {
+                *best = Some((start, volume));
+            }
+        }
+
+        PeakHourReport {
+            am: am_best.map(|(start, _)| PeakHour::new(analytics, map, &system_volume, start)),
+            pm: pm_best.map(|(start, _)| PeakHour::new(analytics, map, &system_volume, start)),
+            system_volume,
+            system_delay: delays
+                .iter()
+                .map(|x| if x.1 == 0 { None } else { average(Some(x)) })
+                .collect(),
+            system_conflicts,
+            finished_trips,
+        }
+    }
+
+    // As CSV, with one row per TIME_BIN
+    pub fn bins_to_csv(&self) -> String {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "start,vehicles,avg_delay_seconds,conflicts,finished_trips"
+        )
+        .unwrap();
+        for bin in 0..self.system_volume.len() {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                (Time::START_OF_DAY + TIME_BIN * (bin as f64)).ampm_tostring(),
+                self.system_volume[bin],
+                self.system_delay[bin]
+                    .map(|dt| format!("{:.1}", dt.inner_seconds()))
+                    .unwrap_or_else(String::new),
+                self.system_conflicts[bin],
+                self.finished_trips[bin]
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    // As CSV, with one row per road and intersection per peak hour
+    pub fn to_csv(&self, map: &Map) -> String {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "peak,start,peak_hour_factor,kind,id,name,volume,capacity,v_c,avg_delay_seconds"
+        )
+        .unwrap();
+        for (name, peak) in vec![("AM", &self.am), ("PM", &self.pm)] {
+            let peak = match peak {
+                Some(p) => p,
+                None => continue,
+            };
+            let prefix = format!(
+                "{},{},{:.2}",
+                name,
+                peak.start.ampm_tostring(),
+                peak.peak_hour_factor
+            );
+            for r in &peak.roads {
+                writeln!(
+                    out,
+                    "{},road,{},{},{}",
+                    prefix,
+                    r.id.0,
+                    escape(&map.get_r(r.id).get_name()),
+                    row(r)
+                )
+                .unwrap();
+            }
+            for i in &peak.intersections {
+                writeln!(
+                    out,
+                    "{},intersection,{},{},{}",
+                    prefix,
+                    i.id.0,
+                    escape(&map.get_i(i.id).name(map)),
+                    row(i)
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
+
+impl PeakHour {
+    fn new(analytics: &Analytics, map: &Map, system_volume: &Vec<usize>, start: usize) -> PeakHour {
+        let bins = start..start + BINS_PER_HOUR;
+        let slice = &system_volume[bins.clone()];
+        let volume: usize = slice.iter().sum();
+        let busiest = *slice.iter().max().unwrap();
+
+        let mut road_volume: BTreeMap<RoadID, usize> = BTreeMap::new();
+        for ((r, mode, bin), count) in &analytics.road_thruput.binned {
+            if is_vehicle(*mode) && bins.contains(bin) {
+                *road_volume.entry(*r).or_insert(0) += count;
+            }
+        }
+        let mut intersection_volume: BTreeMap<IntersectionID, usize> = BTreeMap::new();
+        for ((i, mode, bin), count) in &analytics.intersection_thruput.binned {
+            if is_vehicle(*mode) && bins.contains(bin) {
+                *intersection_volume.entry(*i).or_insert(0) += count;
+            }
+        }
+
+        let mut road_delays: BTreeMap<RoadID, (Duration, usize)> = BTreeMap::new();
+        let mut intersection_delays: BTreeMap<IntersectionID, (Duration, usize)> = BTreeMap::new();
+        for (i, list) in &analytics.intersection_delays {
+            for (t, dt, mode, from) in list {
+                if !is_vehicle(*mode) || !bins.contains(&time_bin(*t)) {
+                    continue;
+                }
+                let entry = road_delays.entry(*from).or_insert((Duration::ZERO, 0));
+                entry.0 += *dt;
+                entry.1 += 1;
+                let entry = intersection_delays.entry(*i).or_insert((Duration::ZERO, 0));
+                entry.0 += *dt;
+                entry.1 += 1;
+            }
+        }
+
+        let mut roads = Vec::new();
+        for r in map.all_roads() {
+            let capacity = road_capacity(map, r.id);
+            if capacity == 0 {
+                continue;
+            }
+            roads.push(LocationStats {
+                id: r.id,
+                volume: road_volume.get(&r.id).cloned().unwrap_or(0),
+                capacity,
+                avg_delay: average(road_delays.get(&r.id)),
+            });
+        }
+        let mut intersections = Vec::new();
+        for i in map.all_intersections() {
+            // Everything entering the intersection comes from one of these roads
+            let capacity = i
+                .roads
+                .iter()
+                .map(|r| {
+                    let road = map.get_r(*r);
+                    let incoming = road
+                        .incoming_lanes(i.id)
+                        .iter()
+                        .filter(|(_, lt)| is_vehicle_lane(*lt))
+                        .count();
+                    incoming * lane_capacity(map, *r)
+                })
+                .sum();
+            if capacity == 0 {
+                continue;
+            }
+            intersections.push(LocationStats {
+                id: i.id,
+                volume: intersection_volume.get(&i.id).cloned().unwrap_or(0),
+                capacity,
+                avg_delay: average(intersection_delays.get(&i.id)),
+            });
+        }
+        sort_by_v_c(&mut roads);
+        sort_by_v_c(&mut intersections);
+
+        PeakHour {
+            start: Time::START_OF_DAY + TIME_BIN * (start as f64),
+            volume,
+            peak_hour_factor: (volume as f64) / ((BINS_PER_HOUR * busiest) as f64),
+            roads,
+            intersections,
+        }
+    }
+}
+
+// Bikes and pedestrians don't use up road capacity
+fn is_vehicle(mode: TripMode) -> bool {
+    mode == TripMode::Drive || mode == TripMode::Transit
+}
+
+fn is_vehicle_lane(lt: LaneType) -> bool {
+    lt == LaneType::Driving || lt == LaneType::Bus
+}
+
+// Both directions of the road together, in vehicles per hour
+fn road_capacity(map: &Map, r: RoadID) -> usize {
+    let road = map.get_r(r);
+    let lanes = road
+        .children_forwards
+        .iter()
+        .chain(road.children_backwards.iter())
+        .filter(|(_, lt)| is_vehicle_lane(*lt))
+        .count();
+    lanes * lane_capacity(map, r)
+}
+
+// Vehicles per hour per lane. These are rough planning-level numbers; bigger roads have fewer
+// interruptions.
+// TODO Account for signal timing and turning movements, like the Highway Capacity Manual does
+fn lane_capacity(map: &Map, r: RoadID) -> usize {
+    match map.get_r(r).osm_tags.get(osm::HIGHWAY).map(|x| x.as_str()) {
+        Some("motorway") | Some("motorway_link") | Some("trunk") | Some("trunk_link") => 1800,
+        Some("primary") | Some("primary_link") | Some("secondary") | Some("secondary_link") => 900,
+        Some("tertiary") | Some("tertiary_link") => 700,
+        _ => 500,
+    }
+}
+
+fn average(x: Option<&(Duration, usize)>) -> Option<Duration> {
+    x.map(|(total, count)| *total / (*count as f64))
+}
+
+fn sort_by_v_c<X>(list: &mut Vec<LocationStats<X>>) {
+    list.sort_by(|a, b| {
+        b.volume_to_capacity()
+            .partial_cmp(&a.volume_to_capacity())
+            .unwrap()
+    });
+}
+
+fn row<X>(stats: &LocationStats<X>) -> String {
+    format!(
+        "{},{},{:.2},{}",
+        stats.volume,
+        stats.capacity,
+        stats.volume_to_capacity(),
+        stats
+            .avg_delay
+            .map(|dt| format!("{:.1}", dt.inner_seconds()))
+            .unwrap_or_else(String::new)
+    )
+}
+
+// Quote a CSV field, doubling any quotes inside it
+fn escape(x: &str) -> String {
+    format!("\"{}\"", x.replace('"', "\"\""))
+}
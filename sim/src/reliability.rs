@@ -0,0 +1,141 @@
+use crate::{Sim, TripEndpoint, TripMode};
+use geom::{Distance, Duration, Histogram, Statistic};
+use map_model::Map;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Pairs with fewer finished trips than this don't say much about reliability
+const MIN_SAMPLES: usize = 5;
+
+// Trip origins and destinations are grouped into square zones of this size
+pub const DEFAULT_ZONE_SIZE: Distance = Distance::const_meters(500.0);
+
+// A zone is a square cell in a grid covering the map, named by its column and row.
+pub type Zone = (isize, isize);
+
+// How consistent are trip times between each pair of zones? Averages hide the bad days; a commuter
+// has to plan around the slow trips, so this reports the 85th and 95th percentiles and the indices
+// the FHWA uses for travel time reliability.
+pub struct ReliabilityReport {
+    pub zone_size: Distance,
+    // Sorted by decreasing planning time index
+    pub pairs: Vec<ODReliability>,
+}
+
+pub struct ODReliability {
+    pub from: Zone,
+    pub to: Zone,
+    pub mode: TripMode,
+    pub count: usize,
+    pub fastest: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p85: Duration,
+    pub p95: Duration,
+}
+
+impl ODReliability {
+    // How much longer than a free-flowing trip to budget to arrive on time 95% of the time. The
+    // fastest trip seen between the pair stands in for free-flow conditions.
+    pub fn planning_time_index(&self) -> f64 {
+        if self.fastest == Duration::ZERO {
+            return 1.0;
+        }
+        self.p95 / self.fastest
+    }
+
+    // The extra time to budget beyond an average trip, as a fraction of the average
+    pub fn buffer_time_index(&self) -> f64 {
+        if self.mean == Duration::ZERO {
+            return 0.0;
+        }
+        (self.p95 - self.mean) / self.mean
+    }
+}
+
+impl ReliabilityReport {
+    // Only looks at finished trips. Repeating a scenario over several days (or several runs with
+    // different seeds) gives each pair more samples.
+    pub fn new(sim: &Sim, map: &Map, zone_size: Distance) -> ReliabilityReport {
+        let mut per_pair: BTreeMap<(Zone, Zone, TripMode), Histogram<Duration>> = BTreeMap::new();
+        for (_, trip, maybe_mode, dt) in &sim.get_analytics().finished_trips {
+            let mode = match maybe_mode {
+                Some(m) => *m,
+                None => continue,
+            };
+            let (_, from, to, _) = sim.trip_info(*trip);
+            per_pair
+                .entry((zone(map, &from, zone_size), zone(map, &to, zone_size), mode))
+                .or_insert_with(Histogram::new)
+                .add(*dt);
+        }
+
+        let mut pairs = Vec::new();
+        for ((from, to, mode), hgram) in per_pair {
+            if hgram.count() < MIN_SAMPLES {
+                continue;
+            }
+            pairs.push(ODReliability {
+                from,
+                to,
+                mode,
+                count: hgram.count(),
+                fastest: hgram.select(Statistic::Min),
+                mean: hgram.select(Statistic::Mean),
+                p50: hgram.select(Statistic::P50),
+                p85: hgram.select(Statistic::P85),
+                p95: hgram.select(Statistic::P95),
+            });
+        }
+        pairs.sort_by(|a, b| {
+            b.planning_time_index()
+                .partial_cmp(&a.planning_time_index())
+                .unwrap()
+        });
+        ReliabilityReport { zone_size, pairs }
+    }
+
+    // As CSV, with one row per pair of zones and mode. Times are in seconds.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "from_zone,to_zone,mode,trips,fastest,mean,p50,p85,p95,planning_time_index,\
+             buffer_time_index"
+        )
+        .unwrap();
+        for pair in &self.pairs {
+            writeln!(
+                out,
+                "{}_{},{}_{},{:?},{},{:.1},{:.1},{:.1},{:.1},{:.1},{:.2},{:.2}",
+                pair.from.0,
+                pair.from.1,
+                pair.to.0,
+                pair.to.1,
+                pair.mode,
+                pair.count,
+                pair.fastest.inner_seconds(),
+                pair.mean.inner_seconds(),
+                pair.p50.inner_seconds(),
+                pair.p85.inner_seconds(),
+                pair.p95.inner_seconds(),
+                pair.planning_time_index(),
+                pair.buffer_time_index()
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+fn zone(map: &Map, endpt: &TripEndpoint, zone_size: Distance) -> Zone {
+    let pt = match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(*b).label_center,
+        TripEndpoint::Border(i, _) => map.get_i(*i).polygon.center(),
+    };
+    let size = zone_size.inner_meters();
+    (
+        (pt.x() / size).floor() as isize,
+        (pt.y() / size).floor() as isize,
+    )
+}
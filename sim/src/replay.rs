@@ -0,0 +1,142 @@
+use crate::{AgentID, Analytics, ModelConstants};
+use geom::{Duration, Pt2D, Time};
+use map_model::{Map, Traversable};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// How often to check for agents that vanished without entering another traversable.
+const CHECK_VANISHED_EVERY: Duration = Duration::const_seconds(10.0);
+
+// A compact record of a finished run, for sharing and watching later without simulating
+// anything. Instead of a savestate plus every event, this just remembers when each agent entered
+// each lane or turn; positions in between are interpolated. That's enough to scrub through the
+// day and look at the same analytics the live sim would show.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub map_name: String,
+    pub edits_name: String,
+    // Movements refer to lane and turn IDs, so the replay only makes sense with the same edits
+    pub edits_checksum: String,
+    pub run_name: String,
+    pub end_time: Time,
+    pub analytics: Analytics,
+    // Including any calibration while the run was recorded
+    pub constants: Vec<(Time, ModelConstants)>,
+    // None means the agent vanished (parked, entered a building, left the map)
+    movements: BTreeMap<AgentID, Vec<(Time, Option<Traversable>)>>,
+}
+
+impl Replay {
+    // Fails if the replay was recorded on a different map or with different edits.
+    pub fn check_map(&self, map: &Map) -> Result<(), String> {
+        if self.map_name != map.get_name() {
+            return Err(format!(
+                "This replay was recorded on {}, not {}",
+                self.map_name,
+                map.get_name()
+            ));
+        }
+        if self.edits_checksum != map.get_edits().checksum(map) {
+            return Err(format!(
+                "This replay was recorded with different edits ({}) than the current ones ({})",
+                self.edits_name,
+                map.get_edits().edits_name
+            ));
+        }
+        Ok(())
+    }
+
+    // Every agent on the map at this time, with an interpolated position.
+    pub fn agents_at(&self, time: Time, map: &Map) -> Vec<(AgentID, Pt2D)> {
+        let mut result = Vec::new();
+        for (id, moves) in &self.movements {
+            // The last entry at or before the time
+            let idx = match moves.binary_search_by(|(t, _)| t.cmp(&time)) {
+                Ok(idx) => idx,
+                Err(0) => {
+                    continue;
+                }
+                Err(idx) => idx - 1,
+            };
+            let (t1, trav) = moves[idx];
+            let trav = match trav {
+                Some(trav) => trav,
+                None => {
+                    continue;
+                }
+            };
+            let t2 = moves.get(idx + 1).map(|(t, _)| *t).unwrap_or(self.end_time);
+            let pct = if t2 > t1 {
+                ((time - t1) / (t2 - t1)).min(1.0)
+            } else {
+                1.0
+            };
+            let (pt, _) = trav.dist_along(trav.length(map) * pct, map);
+            result.push((*id, pt));
+        }
+        result
+    }
+
+    pub fn num_agents(&self) -> usize {
+        self.movements.len()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ReplayRecorder {
+    movements: BTreeMap<AgentID, Vec<(Time, Option<Traversable>)>>,
+    last_check: Time,
+}
+
+impl ReplayRecorder {
+    pub fn new() -> ReplayRecorder {
+        ReplayRecorder {
+            movements: BTreeMap::new(),
+            last_check: Time::START_OF_DAY,
+        }
+    }
+
+    pub fn agent_entered(&mut self, time: Time, id: AgentID, trav: Traversable) {
+        self.movements
+            .entry(id)
+            .or_insert_with(Vec::new)
+            .push((time, Some(trav)));
+    }
+
+    pub fn needs_check(&self, time: Time) -> bool {
+        time - self.last_check >= CHECK_VANISHED_EVERY
+    }
+
+    // Mark anybody who isn't active anymore as vanished.
+    pub fn check_vanished(&mut self, time: Time, active: Vec<AgentID>) {
+        self.last_check = time;
+        let active: BTreeSet<AgentID> = active.into_iter().collect();
+        for (id, moves) in self.movements.iter_mut() {
+            if moves.last().unwrap().1.is_some() && !active.contains(id) {
+                moves.push((time, None));
+            }
+        }
+    }
+
+    pub fn finish(
+        &self,
+        map_name: String,
+        edits_name: String,
+        edits_checksum: String,
+        run_name: String,
+        end_time: Time,
+        analytics: Analytics,
+        constants: Vec<(Time, ModelConstants)>,
+    ) -> Replay {
+        Replay {
+            map_name,
+            edits_name,
+            edits_checksum,
+            run_name,
+            end_time,
+            analytics,
+            constants,
+            movements: self.movements.clone(),
+        }
+    }
+}
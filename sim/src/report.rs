@@ -0,0 +1,390 @@
+use crate::{Analytics, ConflictType, PeakHour, PeakHourReport, TripMode};
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Bounds, Distance, Duration, Histogram, Pt2D, Statistic, Time};
+use map_model::{Map, RoadID};
+use std::fmt::Write;
+
+// Chart dimensions, in pixels
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 200.0;
+const MAP_WIDTH: f64 = 800.0;
+
+// Compares a run with the current map edits ("after") against the same scenario without them
+// ("before"), as one self-contained HTML page that can be attached to a proposal. Maps and charts
+// are inline SVG, so there's nothing else to send along; print it from a browser to get a PDF.
+pub fn ab_report_html(map: &Map, before: &Analytics, after: &Analytics, now: Time) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\nbody {{ font-family: sans-serif; max-width: 900px; margin: auto; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+         td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: right; }}\n\
+         .better {{ color: #1a9850; }}\n.worse {{ color: #d73027; }}\n\
+         section {{ page-break-inside: avoid; }}\n</style>\n</head>\n<body>",
+        escape(&title(map))
+    )
+    .unwrap();
+    writeln!(out, "<h1>{}</h1>", escape(&title(map))).unwrap();
+    writeln!(
+        out,
+        "<p>Simulated until {} on {}, with and without the edits.</p>",
+        now.ampm_tostring(),
+        escape(map.get_name())
+    )
+    .unwrap();
+
+    trip_times(&mut out, before, after, now);
+    peak_hours(&mut out, map, before, after, now);
+    conflicts(&mut out, before, after, now);
+    throughput_map(&mut out, map, before, after, now);
+    for corridor in &map.get_edits().signal_corridors {
+        // Corridors that don't connect anymore were already flagged when editing
+        if let Ok(roads) = corridor.roads(map) {
+            corridor_chart(&mut out, &corridor.name, &roads, before, after, now);
+        }
+    }
+
+    writeln!(out, "</body>\n</html>").unwrap();
+    out
+}
+
+fn title(map: &Map) -> String {
+    format!("Effects of \"{}\"", map.get_edits().edits_name)
+}
+
+fn trip_times(out: &mut String, before: &Analytics, after: &Analytics, now: Time) {
+    writeln!(out, "<section>\n<h2>Trip times</h2>").unwrap();
+    writeln!(
+        out,
+        "<p>Only trips that finished in both runs are compared.</p>\n<table>\n<tr><th>Mode</th>\
+         <th>Trips</th><th>Faster</th><th>Slower</th><th>Median before</th>\
+         <th>Median after</th><th>Total change</th></tr>"
+    )
+    .unwrap();
+    let all = after.both_finished_trips(now, before);
+    for mode in TripMode::all() {
+        let mut hgram_before = Histogram::new();
+        let mut hgram_after = Histogram::new();
+        let mut faster = 0;
+        let mut slower = 0;
+        let mut total = Duration::ZERO;
+        for (b, a, _) in all.iter().filter(|(_, _, m)| *m == mode) {
+            hgram_before.add(*b);
+            hgram_after.add(*a);
+            if a < b {
+                faster += 1;
+            } else if a > b {
+                slower += 1;
+            }
+            total += *a - *b;
+        }
+        if hgram_before.count() == 0 {
+            continue;
+        }
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+            mode.noun(),
+            prettyprint_usize(hgram_before.count()),
+            prettyprint_usize(faster),
+            prettyprint_usize(slower),
+            hgram_before.select(Statistic::P50),
+            hgram_after.select(Statistic::P50),
+            change_cell(total.inner_seconds(), total.to_string())
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>\n</section>").unwrap();
+}
+
+fn peak_hours(out: &mut String, map: &Map, before: &Analytics, after: &Analytics, now: Time) {
+    let before = PeakHourReport::new(before, map, now);
+    let after = PeakHourReport::new(after, map, now);
+    writeln!(out, "<section>\n<h2>Peak hours</h2>").unwrap();
+    writeln!(
+        out,
+        "<table>\n<tr><th></th><th>Start</th><th>Vehicles</th><th>Peak hour factor</th>\
+         <th>Roads over capacity</th></tr>"
+    )
+    .unwrap();
+    for (name, peak) in vec![
+        ("AM before", &before.am),
+        ("AM after", &after.am),
+        ("PM before", &before.pm),
+        ("PM after", &after.pm),
+    ] {
+        match peak {
+            Some(p) => {
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td></tr>",
+                    name,
+                    p.start.ampm_tostring(),
+                    prettyprint_usize(p.volume),
+                    p.peak_hour_factor,
+                    over_capacity(p)
+                )
+                .unwrap();
+            }
+            None => {
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td colspan=\"4\">no traffic</td></tr>",
+                    name
+                )
+                .unwrap();
+            }
+        }
+    }
+    writeln!(out, "</table>\n</section>").unwrap();
+}
+
+fn over_capacity(peak: &PeakHour) -> usize {
+    peak.roads
+        .iter()
+        .filter(|r| r.volume_to_capacity() > 1.0)
+        .count()
+}
+
+fn conflicts(out: &mut String, before: &Analytics, after: &Analytics, now: Time) {
+    let count = |analytics: &Analytics| {
+        let mut cnt = Counter::new();
+        for list in analytics.intersection_conflicts.values() {
+            for (t, conflict) in list {
+                if *t > now {
+                    break;
+                }
+                cnt.inc(*conflict);
+            }
+        }
+        cnt
+    };
+    let before = count(before);
+    let after = count(after);
+
+    writeln!(out, "<section>\n<h2>Conflicts</h2>").unwrap();
+    writeln!(
+        out,
+        "<table>\n<tr><th>Type</th><th>Before</th><th>After</th><th>Change</th></tr>"
+    )
+    .unwrap();
+    for conflict in ConflictType::all() {
+        let (b, a) = (before.get(conflict), after.get(conflict));
+        let change = (a as isize) - (b as isize);
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+            conflict.describe(),
+            prettyprint_usize(b),
+            prettyprint_usize(a),
+            change_cell(change as f64, format!("{:+}", change))
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>\n</section>").unwrap();
+}
+
+// Every road, colored by how many more or fewer vehicles used it
+fn throughput_map(out: &mut String, map: &Map, before: &Analytics, after: &Analytics, now: Time) {
+    let total = |analytics: &Analytics| {
+        let mut cnt = Counter::new();
+        for ((r, mode, hour), count) in &analytics.road_thruput.counts {
+            if *hour <= now.get_hours() && (*mode == TripMode::Drive || *mode == TripMode::Transit)
+            {
+                cnt.add(*r, *count);
+            }
+        }
+        cnt
+    };
+    let before = total(before);
+    let after = total(after);
+    let mut biggest = 1;
+    for r in map.all_roads() {
+        let change = (after.get(r.id) as isize) - (before.get(r.id) as isize);
+        biggest = biggest.max(change.abs());
+    }
+
+    writeln!(out, "<section>\n<h2>Change in vehicles per road</h2>").unwrap();
+    writeln!(
+        out,
+        "<p><span class=\"worse\">Red</span> roads carry more vehicles with the edits, \
+         <span class=\"better\">green</span> fewer. The most changed road has {} vehicles \
+         difference.</p>",
+        prettyprint_usize(biggest as usize)
+    )
+    .unwrap();
+    let svg = SvgMap::new(map);
+    svg.start(out);
+    for r in map.all_roads() {
+        let change = (after.get(r.id) as isize) - (before.get(r.id) as isize);
+        let color = if change > 0 {
+            "#d73027"
+        } else if change < 0 {
+            "#1a9850"
+        } else {
+            "#bbbbbb"
+        };
+        let opacity = if change == 0 {
+            1.0
+        } else {
+            0.3 + 0.7 * (change.abs() as f64) / (biggest as f64)
+        };
+        svg.road(out, map, r.id, color, opacity);
+    }
+    writeln!(out, "</svg>\n</section>").unwrap();
+}
+
+// Vehicles per hour on each road between signals, summed along the corridor
+fn corridor_chart(
+    out: &mut String,
+    name: &str,
+    roads: &Vec<RoadID>,
+    before: &Analytics,
+    after: &Analytics,
+    now: Time,
+) {
+    let hourly = |analytics: &Analytics| {
+        let mut per_hour = vec![0; now.get_hours() + 1];
+        for ((r, mode, hour), count) in &analytics.road_thruput.counts {
+            if *hour < per_hour.len()
+                && roads.contains(r)
+                && (*mode == TripMode::Drive || *mode == TripMode::Transit)
+            {
+                per_hour[*hour] += count;
+            }
+        }
+        per_hour
+    };
+    let before = hourly(before);
+    let after = hourly(after);
+    let max = before
+        .iter()
+        .chain(after.iter())
+        .max()
+        .cloned()
+        .unwrap_or(0)
+        .max(1);
+
+    writeln!(out, "<section>\n<h2>Corridor: {}</h2>", escape(name)).unwrap();
+    writeln!(
+        out,
+        "<p>Vehicles per hour along the corridor, before (grey) and after (blue). {} before, {} \
+         after in total.</p>",
+        prettyprint_usize(before.iter().sum()),
+        prettyprint_usize(after.iter().sum())
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        CHART_WIDTH,
+        CHART_HEIGHT + 20.0
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<text x=\"0\" y=\"12\" font-size=\"12\">{}</text>\n<text x=\"0\" y=\"{}\" \
+         font-size=\"12\">{}</text>",
+        prettyprint_usize(max),
+        CHART_HEIGHT + 15.0,
+        Time::START_OF_DAY.ampm_tostring()
+    )
+    .unwrap();
+    for (series, color) in vec![(&before, "#999999"), (&after, "#2166ac")] {
+        let pts: Vec<String> = series
+            .iter()
+            .enumerate()
+            .map(|(hour, count)| {
+                let x = if series.len() == 1 {
+                    0.0
+                } else {
+                    CHART_WIDTH * (hour as f64) / ((series.len() - 1) as f64)
+                };
+                let y = CHART_HEIGHT * (1.0 - (*count as f64) / (max as f64));
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        writeln!(
+            out,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>",
+            pts.join(" "),
+            color
+        )
+        .unwrap();
+    }
+    writeln!(out, "</svg>\n</section>").unwrap();
+}
+
+// Draws the map in its own coordinates, scaled down to a fixed width
+struct SvgMap {
+    bounds: Bounds,
+}
+
+impl SvgMap {
+    fn new(map: &Map) -> SvgMap {
+        SvgMap {
+            bounds: map.get_bounds().clone(),
+        }
+    }
+
+    fn start(&self, out: &mut String) {
+        let b = &self.bounds;
+        let (width, height) = (b.max_x - b.min_x, b.max_y - b.min_y);
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{:.0}\" \
+             viewBox=\"{:.1} {:.1} {:.1} {:.1}\" style=\"background: #f8f8f8\">",
+            MAP_WIDTH,
+            MAP_WIDTH * height / width,
+            b.min_x,
+            b.min_y,
+            width,
+            height
+        )
+        .unwrap();
+    }
+
+    fn road(&self, out: &mut String, map: &Map, r: RoadID, color: &str, opacity: f64) {
+        let road = map.get_r(r);
+        let mut width = Distance::ZERO;
+        for l in road.all_lanes() {
+            width += map.get_l(l).width;
+        }
+        writeln!(
+            out,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-opacity=\"{:.2}\" \
+             stroke-width=\"{:.1}\" stroke-linecap=\"round\"/>",
+            points(road.center_pts.points()),
+            color,
+            opacity,
+            width.inner_meters()
+        )
+        .unwrap();
+    }
+}
+
+fn points(pts: &Vec<Pt2D>) -> String {
+    pts.iter()
+        .map(|pt| format!("{:.1},{:.1}", pt.x(), pt.y()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// A table cell for a change where less is better
+fn change_cell(change: f64, label: String) -> String {
+    if change < 0.0 {
+        format!("<td class=\"better\">{}</td>", label)
+    } else if change > 0.0 {
+        format!("<td class=\"worse\">{}</td>", label)
+    } else {
+        format!("<td>{}</td>", label)
+    }
+}
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
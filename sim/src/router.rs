@@ -3,9 +3,10 @@ use crate::{
     Event, ParkingSimState, ParkingSpot, PersonID, SidewalkSpot, TripID, TripPhaseType, Vehicle,
 };
 use geom::Distance;
+use log::warn;
 use map_model::{
-    BuildingID, IntersectionID, Map, Path, PathConstraints, PathRequest, PathStep, Position,
-    Traversable, TurnID,
+    BikeRackID, BuildingID, IntersectionID, Map, ParkingLotID, Path, PathConstraints, PathRequest,
+    PathStep, Position, Traversable, TurnID,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -25,6 +26,7 @@ pub enum ActionAtEnd {
     StopBiking(SidewalkSpot),
     BusAtStop,
     GiveUpOnParking,
+    EnterDriveway(BuildingID),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -41,12 +43,27 @@ enum Goal {
         end_dist: Distance,
         i: IntersectionID,
     },
+    // A spot in the lot was claimed before the trip started, so one will be free.
+    ParkInLot {
+        lot: ParkingLotID,
+        end_dist: Distance,
+    },
     BikeThenStop {
         end_dist: Distance,
     },
+    // A space at the rack was claimed before the bike set off.
+    BikeToRack {
+        rack: BikeRackID,
+        end_dist: Distance,
+    },
     FollowBusRoute {
         end_dist: Distance,
     },
+    // Pull into the building's driveway; the car isn't tracked in any parking spot afterwards.
+    EnterDriveway {
+        bldg: BuildingID,
+        end_dist: Distance,
+    },
 }
 
 impl Router {
@@ -68,6 +85,16 @@ impl Router {
         }
     }
 
+    pub fn park_in_lot(path: Path, lot: ParkingLotID, map: &Map) -> Router {
+        Router {
+            path,
+            goal: Goal::ParkInLot {
+                lot,
+                end_dist: map.get_pl(lot).driving_pos.dist_along(),
+            },
+        }
+    }
+
     pub fn bike_then_stop(path: Path, end_dist: Distance, map: &Map) -> Option<Router> {
         let last_lane = path.get_steps().iter().last().unwrap().as_lane();
         if map
@@ -80,11 +107,37 @@ impl Router {
                 goal: Goal::BikeThenStop { end_dist },
             })
         } else {
-            println!("{} is the end of a bike route, with no sidewalk", last_lane);
+            warn!("{} is the end of a bike route, with no sidewalk", last_lane);
             None
         }
     }
 
+    pub fn bike_to_rack(path: Path, rack: BikeRackID, map: &Map) -> Router {
+        Router {
+            path,
+            goal: Goal::BikeToRack {
+                rack,
+                end_dist: map.get_bike_rack(rack).biking_pos.dist_along(),
+            },
+        }
+    }
+
+    pub fn enter_driveway(path: Path, bldg: BuildingID, map: &Map) -> Router {
+        Router {
+            path,
+            goal: Goal::EnterDriveway {
+                bldg,
+                end_dist: map
+                    .get_b(bldg)
+                    .parking
+                    .as_ref()
+                    .unwrap()
+                    .driving_pos
+                    .dist_along(),
+            },
+        }
+    }
+
     pub fn follow_bus_route(path: Path, end_dist: Distance) -> Router {
         Router {
             path,
@@ -117,13 +170,16 @@ impl Router {
         assert!(self.last_step());
         match self.goal {
             Goal::EndAtBorder { end_dist, .. } => end_dist,
+            Goal::ParkInLot { end_dist, .. } => end_dist,
             Goal::ParkNearBuilding {
                 spot,
                 stuck_end_dist,
                 ..
             } => stuck_end_dist.unwrap_or_else(|| spot.unwrap().1),
             Goal::BikeThenStop { end_dist } => end_dist,
+            Goal::BikeToRack { end_dist, .. } => end_dist,
             Goal::FollowBusRoute { end_dist } => end_dist,
+            Goal::EnterDriveway { end_dist, .. } => end_dist,
         }
     }
 
@@ -187,6 +243,17 @@ impl Router {
                     None
                 }
             }
+            Goal::ParkInLot { lot, end_dist } => {
+                if end_dist != front {
+                    return None;
+                }
+                if let Some(spot) = parking.get_free_lot_spots(lot).into_iter().next() {
+                    Some(ActionAtEnd::StartParking(spot))
+                } else {
+                    warn!("{} claimed a spot in {}, but it's full", vehicle.id, lot);
+                    Some(ActionAtEnd::GiveUpOnParking)
+                }
+            }
             Goal::ParkNearBuilding {
                 ref mut spot,
                 ref mut stuck_end_dist,
@@ -268,9 +335,9 @@ impl Router {
                                 ));
                             }
                         } else {
-                            println!(
-                                "WARNING: {} can't find parking on {} or anywhere reachable from \
-                                 it. Possibly we're just totally out of parking space!",
+                            warn!(
+                                "{} can't find parking on {} or anywhere reachable from it. \
+                                 Possibly we're just totally out of parking space!",
                                 vehicle.id, current_lane
                             );
                             *stuck_end_dist = Some(map.get_l(current_lane).length());
@@ -300,6 +367,15 @@ impl Router {
                     None
                 }
             }
+            Goal::BikeToRack { rack, end_dist } => {
+                if end_dist == front {
+                    Some(ActionAtEnd::StopBiking(SidewalkSpot::bike_rack_at(
+                        rack, map,
+                    )))
+                } else {
+                    None
+                }
+            }
             Goal::FollowBusRoute { end_dist } => {
                 if end_dist == front {
                     Some(ActionAtEnd::BusAtStop)
@@ -307,6 +383,13 @@ impl Router {
                     None
                 }
             }
+            Goal::EnterDriveway { bldg, end_dist } => {
+                if end_dist == front {
+                    Some(ActionAtEnd::EnterDriveway(bldg))
+                } else {
+                    None
+                }
+            }
         }
     }
 
@@ -0,0 +1,91 @@
+use crate::{Analytics, TripMode};
+use abstutil::Counter;
+use geom::Time;
+use map_model::{IntersectionID, Map};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Crashes are rare and the simulation never has any, so instead count events that often precede
+// them. These are surrogate safety measures, like the conflicts counted by video analysis of real
+// intersections.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub enum ConflictType {
+    // A car started a turn without the right of way, just before a car with the right of way
+    // arrived
+    SmallGap,
+    // A car had to stop harder than comfortable for a light that just turned red
+    HardBraking,
+    // A car turned across a crosswalk in front of somebody with the right of way waiting to use it
+    PedestrianNearMiss,
+}
+
+impl ConflictType {
+    pub fn all() -> Vec<ConflictType> {
+        vec![
+            ConflictType::SmallGap,
+            ConflictType::HardBraking,
+            ConflictType::PedestrianNearMiss,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            ConflictType::SmallGap => "small gaps",
+            ConflictType::HardBraking => "hard braking",
+            ConflictType::PedestrianNearMiss => "pedestrian near misses",
+        }
+    }
+}
+
+// As CSV, with one row per intersection that's had any conflicts. Conflicts per 1,000 vehicles
+// makes busy and quiet intersections comparable.
+pub fn conflicts_to_csv(analytics: &Analytics, map: &Map, now: Time) -> String {
+    let mut per_type: BTreeMap<IntersectionID, Counter<ConflictType>> = BTreeMap::new();
+    for (i, list) in &analytics.intersection_conflicts {
+        for (t, conflict) in list {
+            if *t > now {
+                break;
+            }
+            per_type
+                .entry(*i)
+                .or_insert_with(Counter::new)
+                .inc(*conflict);
+        }
+    }
+    let mut vehicles: Counter<IntersectionID> = Counter::new();
+    for ((i, mode, _), count) in &analytics.intersection_thruput.counts {
+        if *mode == TripMode::Drive || *mode == TripMode::Transit {
+            vehicles.add(*i, *count);
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "intersection,name,vehicles,small_gaps,hard_braking,pedestrian_near_misses,\
+         conflicts_per_1000_vehicles"
+    )
+    .unwrap();
+    for (i, counts) in per_type {
+        let total = counts.sum();
+        let volume = vehicles.get(i);
+        writeln!(
+            out,
+            "{},\"{}\",{},{},{},{},{}",
+            i.0,
+            map.get_i(i).name(map),
+            volume,
+            counts.get(ConflictType::SmallGap),
+            counts.get(ConflictType::HardBraking),
+            counts.get(ConflictType::PedestrianNearMiss),
+            if volume == 0 {
+                String::new()
+            } else {
+                format!("{:.2}", 1000.0 * (total as f64) / (volume as f64))
+            }
+        )
+        .unwrap();
+    }
+    out
+}
@@ -3,7 +3,8 @@ use crate::{
 };
 use derivative::Derivative;
 use geom::{Duration, Histogram, Time};
-use map_model::{IntersectionID, Path, PathRequest};
+use log::warn;
+use map_model::{BusRouteID, IntersectionID, Path, PathRequest};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::btree_map::Entry;
@@ -23,6 +24,8 @@ pub enum Command {
     Callback(Duration),
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    // The scheduled time is part of the command, so many departures can be queued at once
+    StartBus(BusRouteID, Time),
 }
 
 impl Command {
@@ -46,6 +49,7 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::FinishRemoteTrip(t) => CommandType::FinishRemoteTrip(*t),
+            Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
         }
     }
 }
@@ -62,6 +66,7 @@ pub enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    StartBus(BusRouteID, Time),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -173,7 +178,7 @@ impl Scheduler {
     // unexpectedly.
     pub fn must_cancel_by_type(&mut self, cmd: CommandType) {
         if self.queued_commands.remove(&cmd).is_none() {
-            println!(
+            warn!(
                 "must_cancel_by_type({:?}) didn't find a matching command",
                 cmd
             );
@@ -214,6 +219,10 @@ impl Scheduler {
         }
     }
 
+    pub fn num_queued_commands(&self) -> usize {
+        self.queued_commands.len()
+    }
+
     pub fn describe_stats(&self) -> String {
         format!("delta times for events: {}", self.delta_times.describe())
     }
@@ -1,15 +1,18 @@
 use crate::{
-    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents, IntersectionSimState, OrigPersonID,
-    PandemicModel, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, Person, PersonID,
-    PersonState, Router, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripEndpoint,
-    TripID, TripManager, TripMode, TripPhaseType, TripPositions, TripResult, TripSpawner,
-    UnzoomedAgent, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, MIN_CAR_LENGTH,
+    AgentID, AlertLocation, Analytics, BusService, CarID, Command, CreateCar, DrawCarInput,
+    DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents,
+    IntersectionSimState, MatsimEvents, OrigPersonID, PandemicModel, ParkedCar, ParkingSimState,
+    ParkingSpot, PedestrianID, Person, PersonID, PersonState, Replay, ReplayRecorder, Router,
+    Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripEndpoint, TripID, TripManager,
+    TripMode, TripPhaseType, TripPositions, TripResult, TripSpawner, UnzoomedAgent, Vehicle,
+    VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, FOLLOWING_DISTANCE, MIN_CAR_LENGTH,
+    TRAIN_LENGTH,
 };
 use abstutil::Timer;
 use derivative::Derivative;
 use geom::{Distance, Duration, PolyLine, Pt2D, Speed, Time};
 use instant::Instant;
+use log::{debug, error, info, warn};
 use map_model::{
     BuildingID, BusRoute, BusRouteID, IntersectionID, LaneID, Map, ParkingLotID, Path,
     PathConstraints, PathRequest, PathStep, Position, RoadID, Traversable,
@@ -22,6 +25,10 @@ use std::panic;
 // TODO Do something else.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
 
+// Bump this whenever the serialized form of Sim changes. Unversioned savestates count as 0.
+// 1: Compact fixed-point Pt2D
+pub const SAVESTATE_FORMAT_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Clone, Derivative)]
 #[derivative(PartialEq)]
 pub struct Sim {
@@ -36,6 +43,9 @@ pub struct Sim {
     pandemic: Option<PandemicModel>,
     scheduler: Scheduler,
     time: Time,
+    constants: ModelConstants,
+    // The initial constants and every change after, so the run can be reproduced
+    constants_history: Vec<(Time, ModelConstants)>,
 
     // TODO Reconsider these
     pub(crate) map_name: String,
@@ -60,6 +70,14 @@ pub struct Sim {
     #[derivative(PartialEq = "ignore")]
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    matsim_events: Option<MatsimEvents>,
+
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    replay: Option<ReplayRecorder>,
 }
 
 #[derive(Clone)]
@@ -68,10 +86,24 @@ pub struct SimOptions {
     pub use_freeform_policy_everywhere: bool,
     pub dont_block_the_box: bool,
     pub recalc_lanechanging: bool,
+    // Drivers slow down on lanes narrower than normal
+    pub narrow_lane_slowdown: bool,
     pub break_turn_conflict_cycles: bool,
+    // Drivers at a yield or stop sign won't start a turn if a car on a priority road will reach a
+    // conflicting turn sooner than this.
+    pub yield_critical_gap: Duration,
+    // The citywide default. Individual intersections can ban it through map edits.
+    pub right_turn_on_red: bool,
+    // Pedestrians slow down on crowded sidewalks and crosswalks, and big groups wait longer to
+    // cross.
+    pub crowded_walking: bool,
     pub enable_pandemic_model: Option<XorShiftRng>,
     pub alerts: AlertHandler,
     pub pathfinding_upfront: bool,
+    // Translate every event into MATSim's format. Retrieve with finish_matsim_events.
+    pub record_matsim_events: bool,
+    // Remember enough to watch the run later. Retrieve with make_replay.
+    pub record_replay: bool,
 }
 
 #[derive(Clone)]
@@ -90,17 +122,99 @@ impl std::default::Default for AlertHandler {
     }
 }
 
+// Behavioral constants that can be calibrated while the simulation runs, instead of recompiling
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModelConstants {
+    // See FOLLOWING_DISTANCE. Each lane and turn picks up a new value the next time it's empty.
+    pub following_distance: Distance,
+    // After a traffic signal turns green, how long the first vehicle waiting takes to start
+    pub start_up_lost_time: Duration,
+    // See SimOptions::yield_critical_gap
+    pub yield_critical_gap: Duration,
+    // Walking speeds are spread across this range. Changing it stretches everybody's speed to the
+    // new range, keeping who's faster than who. People walking right now keep their old speed
+    // until their next walk.
+    pub min_walking_speed: Speed,
+    pub max_walking_speed: Speed,
+}
+
+impl ModelConstants {
+    fn new(opts: &SimOptions) -> ModelConstants {
+        ModelConstants {
+            following_distance: FOLLOWING_DISTANCE,
+            start_up_lost_time: Duration::ZERO,
+            yield_critical_gap: opts.yield_critical_gap,
+            // Matches Scenario::rand_ped_speed
+            min_walking_speed: Speed::miles_per_hour(2.0),
+            max_walking_speed: Speed::miles_per_hour(3.0),
+        }
+    }
+}
+
 impl SimOptions {
+    // Everything that changes how the simulation behaves, for telling runs apart. Skips the run
+    // name and what gets recorded.
+    pub fn describe(&self) -> BTreeMap<String, String> {
+        let mut result = BTreeMap::new();
+        result.insert(
+            "use_freeform_policy_everywhere".to_string(),
+            self.use_freeform_policy_everywhere.to_string(),
+        );
+        result.insert(
+            "dont_block_the_box".to_string(),
+            self.dont_block_the_box.to_string(),
+        );
+        result.insert(
+            "recalc_lanechanging".to_string(),
+            self.recalc_lanechanging.to_string(),
+        );
+        result.insert(
+            "narrow_lane_slowdown".to_string(),
+            self.narrow_lane_slowdown.to_string(),
+        );
+        result.insert(
+            "break_turn_conflict_cycles".to_string(),
+            self.break_turn_conflict_cycles.to_string(),
+        );
+        result.insert(
+            "yield_critical_gap".to_string(),
+            self.yield_critical_gap.to_string(),
+        );
+        result.insert(
+            "right_turn_on_red".to_string(),
+            self.right_turn_on_red.to_string(),
+        );
+        result.insert(
+            "crowded_walking".to_string(),
+            self.crowded_walking.to_string(),
+        );
+        result.insert(
+            "enable_pandemic_model".to_string(),
+            self.enable_pandemic_model.is_some().to_string(),
+        );
+        result.insert(
+            "pathfinding_upfront".to_string(),
+            self.pathfinding_upfront.to_string(),
+        );
+        result
+    }
+
     pub fn new(run_name: &str) -> SimOptions {
         SimOptions {
             run_name: run_name.to_string(),
             use_freeform_policy_everywhere: false,
             dont_block_the_box: true,
             recalc_lanechanging: true,
+            narrow_lane_slowdown: false,
             break_turn_conflict_cycles: true,
+            yield_critical_gap: Duration::seconds(5.0),
+            right_turn_on_red: true,
+            crowded_walking: false,
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             pathfinding_upfront: false,
+            record_matsim_events: false,
+            record_replay: false,
         }
     }
 }
@@ -109,16 +223,25 @@ impl SimOptions {
 impl Sim {
     pub fn new(map: &Map, opts: SimOptions, timer: &mut Timer) -> Sim {
         let mut scheduler = Scheduler::new();
+        let constants = ModelConstants::new(&opts);
         Sim {
-            driving: DrivingSimState::new(map, opts.recalc_lanechanging),
+            driving: DrivingSimState::new(
+                map,
+                opts.recalc_lanechanging,
+                opts.narrow_lane_slowdown,
+                constants.following_distance,
+            ),
             parking: ParkingSimState::new(map, timer),
-            walking: WalkingSimState::new(),
+            walking: WalkingSimState::new(opts.crowded_walking),
             intersections: IntersectionSimState::new(
                 map,
                 &mut scheduler,
                 opts.use_freeform_policy_everywhere,
                 opts.dont_block_the_box,
                 opts.break_turn_conflict_cycles,
+                opts.yield_critical_gap,
+                opts.right_turn_on_red,
+                opts.crowded_walking,
             ),
             transit: TransitSimState::new(),
             trips: TripManager::new(opts.pathfinding_upfront),
@@ -129,6 +252,8 @@ impl Sim {
             },
             scheduler,
             time: Time::START_OF_DAY,
+            constants_history: vec![(Time::START_OF_DAY, constants.clone())],
+            constants,
 
             map_name: map.get_name().to_string(),
             // TODO
@@ -137,6 +262,16 @@ impl Sim {
             step_count: 0,
             trip_positions: None,
             alerts: opts.alerts,
+            matsim_events: if opts.record_matsim_events {
+                Some(MatsimEvents::new())
+            } else {
+                None
+            },
+            replay: if opts.record_replay {
+                Some(ReplayRecorder::new())
+            } else {
+                None
+            },
 
             analytics: Analytics::new(),
         }
@@ -236,80 +371,190 @@ impl Sim {
         self.parking.add_parked_car(ParkedCar { vehicle, spot });
     }
 
-    pub fn seed_bus_route(&mut self, route: &BusRoute, map: &Map, timer: &mut Timer) -> Vec<CarID> {
-        let mut results: Vec<CarID> = Vec::new();
-
-        // Try to spawn just ONE bus anywhere.
-        // TODO Be more realistic. One bus per stop is too much, one is too little.
-        for (next_stop_idx, req, mut path, end_dist) in
-            self.transit.create_empty_route(route, map).into_iter()
-        {
-            // For now, no desire for randomness. Caller can pass in list of specs if that ever
-            // changes.
-            let vehicle = VehicleSpec {
-                vehicle_type: VehicleType::Bus,
-                length: BUS_LENGTH,
-                max_speed: None,
+    pub fn seed_bus_route(
+        &mut self,
+        route: &BusRoute,
+        service: Option<&BusService>,
+        map: &Map,
+        timer: &mut Timer,
+    ) -> Vec<CarID> {
+        let stops = match self.transit.create_empty_route(route, map) {
+            Ok(stops) => stops,
+            Err(err) => {
+                timer.warn(format!("Not running {}: {}", route.name, err));
+                return Vec::new();
             }
-            .make(CarID(self.trips.new_car_id(), VehicleType::Bus), None);
-            let id = vehicle.id;
+        };
 
-            loop {
-                if path.is_last_step() {
+        // An experiment overrides any real schedule
+        if let Some(service) = service {
+            match service.validate() {
+                Ok(()) => {
+                    for i in 0..service.fleet_size {
+                        let t = self.time + service.headway * (i as f64);
+                        self.scheduler.push(t, Command::StartBus(route.id, t));
+                    }
+                    return Vec::new();
+                }
+                Err(err) => {
                     timer.warn(format!(
-                        "Giving up on seeding a bus headed towards stop {} of {} ({})",
-                        next_stop_idx, route.name, route.id
+                        "Using the usual schedule for {}, because its service {}",
+                        route.name, err
                     ));
-                    break;
-                }
-                let start_lane = if let PathStep::Lane(l) = path.current_step() {
-                    l
-                } else {
-                    path.shift(map);
-                    // TODO Technically should update request, but it shouldn't matter
-                    continue;
-                };
-                if map.get_l(start_lane).length() < vehicle.length {
-                    path.shift(map);
-                    // TODO Technically should update request, but it shouldn't matter
-                    continue;
                 }
+            }
+        }
 
-                // Bypass some layers of abstraction that don't make sense for buses.
-                if self.driving.start_car_on_lane(
-                    self.time,
-                    CreateCar {
-                        start_dist: vehicle.length,
-                        vehicle: vehicle.clone(),
-                        req: req.clone(),
-                        router: Router::follow_bus_route(path.clone(), end_dist),
-                        maybe_parked_car: None,
-                        trip_and_person: None,
-                    },
-                    map,
-                    &self.intersections,
-                    &self.parking,
-                    &mut self.scheduler,
-                ) {
-                    self.transit.bus_created(id, route.id, next_stop_idx);
-                    self.analytics.record_demand(&path, map);
-                    results.push(id);
-                    return results;
-                } else {
-                    path.shift(map);
+        // If there's a real schedule, follow it. Each bus begins at the first stop.
+        if !route.spawn_times.is_empty() {
+            for t in &route.spawn_times {
+                if *t >= self.time {
+                    self.scheduler.push(*t, Command::StartBus(route.id, *t));
                 }
             }
+            return Vec::new();
         }
-        if results.is_empty() {
-            // TODO Bigger failure
-            timer.warn(format!("Failed to make ANY buses for {}!", route.name));
+
+        // Otherwise, try to spawn just ONE bus anywhere.
+        // TODO Be more realistic. One bus per stop is too much, one is too little.
+        for (next_stop_idx, req, path, end_dist) in stops {
+            if let Some(id) = self.start_bus(route.id, next_stop_idx, req, path, end_dist, map) {
+                return vec![id];
+            }
+            timer.warn(format!(
+                "Giving up on seeding a bus headed towards stop {} of {} ({})",
+                next_stop_idx, route.name, route.id
+            ));
+        }
+        // TODO Bigger failure
+        timer.warn(format!("Failed to make ANY buses for {}!", route.name));
+        Vec::new()
+    }
+
+    fn start_bus(
+        &mut self,
+        route: BusRouteID,
+        next_stop_idx: usize,
+        req: PathRequest,
+        mut path: Path,
+        end_dist: Distance,
+        map: &Map,
+    ) -> Option<CarID> {
+        // For now, no desire for randomness. Caller can pass in list of specs if that ever
+        // changes.
+        let (vehicle_type, length) = if map.get_br(route).route_type == PathConstraints::Train {
+            (VehicleType::Train, TRAIN_LENGTH)
+        } else {
+            (VehicleType::Bus, BUS_LENGTH)
+        };
+        let vehicle = VehicleSpec {
+            vehicle_type,
+            length,
+            max_speed: None,
+        }
+        .make(CarID(self.trips.new_car_id(), vehicle_type), None);
+        let id = vehicle.id;
+
+        loop {
+            if path.is_last_step() {
+                return None;
+            }
+            let start_lane = if let PathStep::Lane(l) = path.current_step() {
+                l
+            } else {
+                path.shift(map);
+                // TODO Technically should update request, but it shouldn't matter
+                continue;
+            };
+            if map.get_l(start_lane).length() < vehicle.length {
+                path.shift(map);
+                // TODO Technically should update request, but it shouldn't matter
+                continue;
+            }
+
+            // Bypass some layers of abstraction that don't make sense for buses.
+            if self.driving.start_car_on_lane(
+                self.time,
+                CreateCar {
+                    start_dist: vehicle.length,
+                    vehicle: vehicle.clone(),
+                    req: req.clone(),
+                    router: Router::follow_bus_route(path.clone(), end_dist),
+                    maybe_parked_car: None,
+                    leaving_driveway: None,
+                    trip_and_person: None,
+                },
+                map,
+                &self.intersections,
+                &self.parking,
+                &mut self.scheduler,
+            ) {
+                self.transit.bus_created(id, route, next_stop_idx);
+                self.analytics.record_demand(&path, map);
+                return Some(id);
+            } else {
+                path.shift(map);
+            }
         }
-        results
     }
 
     pub fn set_name(&mut self, name: String) {
         self.run_name = name;
     }
+
+    pub fn get_run_name(&self) -> &String {
+        &self.run_name
+    }
+
+    // Roughly how many bytes each part of the sim uses, measured by serialized size. Paths are
+    // split out from the cars and pedestrians following them, but the paths of trips that haven't
+    // started yet count towards the scheduler.
+    pub fn memory_usage(&self) -> Vec<(&'static str, usize)> {
+        use abstutil::serialized_size_bytes as size;
+        let car_paths = self.driving.path_size_bytes();
+        let ped_paths = self.walking.path_size_bytes();
+        vec![
+            ("cars", size(&self.driving) - car_paths),
+            ("pedestrians", size(&self.walking) - ped_paths),
+            ("paths", car_paths + ped_paths),
+            ("parking", size(&self.parking)),
+            ("intersections", size(&self.intersections)),
+            ("transit", size(&self.transit)),
+            ("trips and people", size(&self.trips)),
+            ("scheduler", size(&self.scheduler)),
+            ("analytics", size(&self.analytics)),
+        ]
+    }
+
+    pub fn get_model_constants(&self) -> &ModelConstants {
+        &self.constants
+    }
+
+    pub fn set_model_constants(&mut self, constants: ModelConstants) {
+        if constants.following_distance != self.constants.following_distance {
+            self.driving
+                .set_following_distance(constants.following_distance);
+        }
+        self.intersections
+            .set_timing(constants.yield_critical_gap, constants.start_up_lost_time);
+        if constants.min_walking_speed != self.constants.min_walking_speed
+            || constants.max_walking_speed != self.constants.max_walking_speed
+        {
+            self.trips.rescale_walking_speeds(
+                (
+                    self.constants.min_walking_speed,
+                    self.constants.max_walking_speed,
+                ),
+                (constants.min_walking_speed, constants.max_walking_speed),
+            );
+        }
+        self.constants_history.push((self.time, constants.clone()));
+        self.constants = constants;
+    }
+
+    pub fn get_model_constants_history(&self) -> &Vec<(Time, ModelConstants)> {
+        &self.constants_history
+    }
 }
 
 // Drawing
@@ -426,6 +671,7 @@ impl Sim {
         maybe_cb: &mut Option<Box<dyn SimCallback>>,
     ) -> bool {
         self.time = time;
+        abstutil::set_log_sim_time(time.inner_seconds());
         let mut events = Vec::new();
         let mut halt = false;
         match cmd {
@@ -464,6 +710,12 @@ impl Sim {
                         }
                         self.parking.remove_parked_car(parked_car);
                     }
+                    if let Some(b) = create_car.leaving_driveway {
+                        events.push(Event::PersonLeavesBuilding(
+                            create_car.trip_and_person.unwrap().1,
+                            b,
+                        ));
+                    }
                     if let Some((trip, person)) = create_car.trip_and_person {
                         events.push(Event::TripPhaseStarting(
                             trip,
@@ -487,7 +739,7 @@ impl Sim {
                 } else {
                     // Buses don't use Command::SpawnCar, so this must exist.
                     let (trip, person) = create_car.trip_and_person.unwrap();
-                    println!(
+                    warn!(
                         "No room to spawn car for {} by {}. Not retrying!",
                         trip, person
                     );
@@ -591,6 +843,19 @@ impl Sim {
                     .unwrap()
                     .handle_cmd(self.time, cmd, &mut self.scheduler);
             }
+            Command::StartBus(route, _) => {
+                let (next_stop_idx, req, path, end_dist) = self.transit.start_of_route(route);
+                if self
+                    .start_bus(route, next_stop_idx, req, path, end_dist, map)
+                    .is_none()
+                {
+                    // TODO Buses running late should count against the route somewhere
+                    self.scheduler.push(
+                        self.time + BLIND_RETRY_TO_SPAWN,
+                        Command::StartBus(route, self.time + BLIND_RETRY_TO_SPAWN),
+                    );
+                }
+            }
             Command::FinishRemoteTrip(trip) => {
                 self.trips.remote_trip_finished(
                     self.time,
@@ -619,9 +884,30 @@ impl Sim {
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
             }
+            if let Some(ref mut m) = self.matsim_events {
+                m.handle_event(self.time, &ev, map);
+            }
+            if let Some(ref mut r) = self.replay {
+                if let Event::AgentEntersTraversable(id, trav) = ev {
+                    r.agent_entered(self.time, id, trav);
+                }
+            }
 
             self.analytics.event(ev, self.time, map);
         }
+
+        if self
+            .replay
+            .as_ref()
+            .map(|r| r.needs_check(self.time))
+            .unwrap_or(false)
+        {
+            let active = self.active_agents();
+            self.replay
+                .as_mut()
+                .unwrap()
+                .check_vanished(self.time, active);
+        }
     }
 
     pub fn timed_step(
@@ -644,12 +930,12 @@ impl Sim {
                 match self.alerts {
                     AlertHandler::Print => {
                         for (t, loc, msg) in self.analytics.alerts.drain(..) {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
+                            warn!("Alert at {} ({:?}): {}", t, loc, msg);
                         }
                     }
                     AlertHandler::Block => {
                         for (t, loc, msg) in &self.analytics.alerts {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
+                            warn!("Alert at {} ({:?}): {}", t, loc, msg);
                         }
                         break;
                     }
@@ -660,7 +946,7 @@ impl Sim {
             }
             if Duration::realtime_elapsed(last_update) >= Duration::seconds(1.0) {
                 // TODO Not timer?
-                println!(
+                info!(
                     "- After {}, the sim is at {}",
                     Duration::realtime_elapsed(start),
                     self.time
@@ -697,12 +983,12 @@ impl Sim {
                 match self.alerts {
                     AlertHandler::Print => {
                         for (t, loc, msg) in self.analytics.alerts.drain(..) {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
+                            warn!("Alert at {} ({:?}): {}", t, loc, msg);
                         }
                     }
                     AlertHandler::Block => {
                         for (t, loc, msg) in &self.analytics.alerts {
-                            println!("Alert at {} ({:?}): {}", t, loc, msg);
+                            warn!("Alert at {} ({:?}): {}", t, loc, msg);
                         }
                         break;
                     }
@@ -715,12 +1001,9 @@ impl Sim {
     }
 
     pub fn dump_before_abort(&self) {
-        println!(
-            "********************************************************************************"
-        );
-        println!("At {}", self.time);
+        error!("At {}", self.time);
         if let Some(path) = self.find_previous_savestate(self.time) {
-            println!("Debug from {}", path);
+            error!("Debug from {}", path);
         }
     }
 }
@@ -734,6 +1017,8 @@ impl Sim {
         callback: F,
         // Interpreted as a relative time
         time_limit: Option<Duration>,
+        // Run by set_periodic_callback, like FcdRecorder
+        maybe_cb: &mut Option<Box<dyn SimCallback>>,
     ) {
         let mut last_print = Instant::now();
         let mut last_sim_time = self.time();
@@ -743,15 +1028,11 @@ impl Sim {
             let dt = time_limit.unwrap_or_else(|| Duration::seconds(30.0));
 
             match panic::catch_unwind(panic::AssertUnwindSafe(|| {
-                self.timed_step(map, dt, &mut None, &mut Timer::throwaway());
+                self.timed_step(map, dt, maybe_cb, &mut Timer::throwaway());
             })) {
                 Ok(()) => {}
                 Err(err) => {
-                    println!(
-                        "*************************************************************************\
-                         *******"
-                    );
-                    println!("Sim broke:");
+                    error!("Sim broke:");
                     self.dump_before_abort();
                     panic::resume_unwind(err);
                 }
@@ -760,7 +1041,7 @@ impl Sim {
             let dt_real = Duration::realtime_elapsed(last_print);
             if dt_real >= Duration::seconds(1.0) {
                 let (finished, unfinished, _) = self.num_trips();
-                println!(
+                info!(
                     "{}: {} trips finished, {} unfinished, speed = {:.2}x, {}",
                     self.time(),
                     abstutil::prettyprint_usize(finished),
@@ -773,7 +1054,7 @@ impl Sim {
             }
             callback(self, map);
             if self.is_done() {
-                println!(
+                info!(
                     "{}: speed = {:.2}x, {}",
                     self.time(),
                     (self.time() - last_sim_time) / dt_real,
@@ -783,7 +1064,9 @@ impl Sim {
             }
 
             if let Some(lim) = time_limit {
-                panic!("Time limit {} hit", lim);
+                // Don't panic, so callers can still export what happened so far
+                info!("Time limit {} hit", lim);
+                break;
             }
         }
     }
@@ -807,46 +1090,57 @@ impl Sim {
     }
 
     pub fn save(&mut self) -> String {
+        let path = self.save_path(self.time);
+        self.save_as(path.clone());
+        path
+    }
+
+    pub fn save_as(&mut self, path: String) {
         let restore = self.scheduler.before_savestate();
 
         if true {
-            println!("sim savestate breakdown:");
-            println!(
+            debug!("sim savestate breakdown:");
+            debug!(
                 "- driving: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.driving))
             );
-            println!(
+            debug!(
                 "- parking: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.parking))
             );
-            println!(
+            debug!(
                 "- walking: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.walking))
             );
-            println!(
+            debug!(
                 "- intersections: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.intersections))
             );
-            println!(
+            debug!(
                 "- transit: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.transit))
             );
-            println!(
+            debug!(
                 "- trips: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.trips))
             );
-            println!(
+            debug!(
                 "- scheduler: {} bytes",
                 abstutil::prettyprint_usize(abstutil::serialized_size_bytes(&self.scheduler))
             );
         }
 
-        let path = self.save_path(self.time);
-        abstutil::write_binary(path.clone(), self);
+        abstutil::write_versioned_binary(path, SAVESTATE_FORMAT_VERSION, self);
 
         self.scheduler.after_savestate(restore);
+    }
 
-        path
+    // The contents of a savestate, to be written somewhere later
+    pub fn savestate_bytes(&mut self) -> Vec<u8> {
+        let restore = self.scheduler.before_savestate();
+        let bytes = abstutil::to_versioned_binary(SAVESTATE_FORMAT_VERSION, self);
+        self.scheduler.after_savestate(restore);
+        bytes
     }
 
     pub fn find_previous_savestate(&self, base_time: Time) -> Option<String> {
@@ -862,11 +1156,27 @@ impl Sim {
         map: &Map,
         timer: &mut Timer,
     ) -> Result<Sim, std::io::Error> {
-        let mut sim: Sim = abstutil::maybe_read_binary(path, timer)?;
+        let mut sim = Sim::read_savestate(path, timer)?;
         sim.restore_paths(map, timer);
         Ok(sim)
     }
 
+    // Doesn't restore paths yet; that needs the map.
+    pub fn read_savestate(path: String, timer: &mut Timer) -> Result<Sim, std::io::Error> {
+        // Savestates are cheap to regenerate, so just refuse any written with a different layout
+        let (version, bytes) = abstutil::read_versioned_bytes(&path, timer)?;
+        if version != SAVESTATE_FORMAT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "{} is version {}, but this build needs {}",
+                    path, version, SAVESTATE_FORMAT_VERSION
+                ),
+            ));
+        }
+        abstutil::from_binary(&bytes)
+    }
+
     pub fn restore_paths(&mut self, map: &Map, timer: &mut Timer) {
         let paths = timer.parallelize(
             "calculate paths",
@@ -887,6 +1197,11 @@ impl Sim {
         self.trips.is_done()
     }
 
+    // How many things are scheduled to happen in the future
+    pub fn num_queued_commands(&self) -> usize {
+        self.scheduler.num_queued_commands()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.time == Time::START_OF_DAY && self.is_done()
     }
@@ -944,12 +1259,15 @@ impl Sim {
                 "Route".to_string(),
                 map.get_br(self.transit.bus_route(car)).name.clone(),
             ),
-            ("Passengers".to_string(), passengers.len().to_string()),
+            (
+                "Passengers".to_string(),
+                format!("{} / {}", passengers.len(), self.transit.get_capacity(car)),
+            ),
         ]
     }
 
     pub fn bus_route_id(&self, maybe_bus: CarID) -> Option<BusRouteID> {
-        if maybe_bus.1 == VehicleType::Bus {
+        if maybe_bus.1.is_transit() {
             Some(self.transit.bus_route(maybe_bus))
         } else {
             None
@@ -960,6 +1278,14 @@ impl Sim {
         self.trips.active_agents()
     }
 
+    // The full state of every car and pedestrian, for checking determinism
+    pub(crate) fn agent_states(&self) -> BTreeMap<AgentID, String> {
+        let mut states = BTreeMap::new();
+        self.driving.collect_agent_states(&mut states);
+        self.walking.collect_agent_states(&mut states);
+        states
+    }
+
     pub fn agent_to_trip(&self, id: AgentID) -> Option<TripID> {
         self.trips.agent_to_trip(id)
     }
@@ -972,6 +1298,10 @@ impl Sim {
     pub fn trip_info(&self, id: TripID) -> (Time, TripEndpoint, TripEndpoint, TripMode) {
         self.trips.trip_info(id)
     }
+    // Every trip in the scenario, whether or not it's started yet
+    pub fn all_trip_info(&self) -> Vec<(TripID, Time, TripEndpoint, TripEndpoint, TripMode)> {
+        self.trips.all_trip_info()
+    }
     // If trip is finished, returns (total time, total waiting time)
     pub fn finished_trip_time(&self, id: TripID) -> Option<(Duration, Duration)> {
         self.trips.finished_trip_time(id)
@@ -1013,7 +1343,12 @@ impl Sim {
     }
 
     pub fn lookup_car_id(&self, idx: usize) -> Option<CarID> {
-        for vt in &[VehicleType::Car, VehicleType::Bike, VehicleType::Bus] {
+        for vt in &[
+            VehicleType::Car,
+            VehicleType::Bike,
+            VehicleType::Bus,
+            VehicleType::Train,
+        ] {
             let id = CarID(idx, *vt);
             if self.driving.does_car_exist(id) {
                 return Some(id);
@@ -1136,6 +1471,11 @@ impl Sim {
             .find_blockage_front(car, map, &self.intersections)
     }
 
+    // Per lane, (number of vehicles queued at the end, length of the queue)
+    pub fn get_queue_lengths(&self) -> BTreeMap<LaneID, (usize, Distance)> {
+        self.driving.get_queue_lengths(self.time)
+    }
+
     // For intersections with an agent waiting beyond some threshold, return when they started
     // waiting. Sorted by earliest waiting (likely the root cause of gridlock).
     pub fn delayed_intersections(&self, threshold: Duration) -> Vec<(IntersectionID, Time)> {
@@ -1161,6 +1501,38 @@ impl Sim {
         self.pandemic.as_ref()
     }
 
+    // Only if SimOptions::record_matsim_events was set. Stops recording.
+    pub fn finish_matsim_events(&mut self) -> Option<String> {
+        self.matsim_events.take().map(|m| m.finish())
+    }
+
+    // Only if SimOptions::record_replay was set. Covers everything up to the current time.
+    pub fn make_replay(&self, map: &Map) -> Option<Replay> {
+        self.replay.as_ref().map(|r| {
+            r.finish(
+                self.map_name.clone(),
+                self.edits_name.clone(),
+                map.get_edits().checksum(map),
+                self.run_name.clone(),
+                self.time,
+                self.analytics.clone(),
+                self.constants_history.clone(),
+            )
+        })
+    }
+
+    pub fn replay_path(&self) -> String {
+        abstutil::path_replay(
+            &self.map_name,
+            &format!(
+                "{}_{}_{}",
+                self.edits_name,
+                self.run_name,
+                self.time.as_filename()
+            ),
+        )
+    }
+
     pub fn get_end_of_day(&self) -> Time {
         // Always count at least 24 hours
         self.scheduler
@@ -1188,9 +1560,9 @@ impl Sim {
                 &mut self.scheduler,
                 map,
             );
-            println!("Forcibly killed {}", id);
+            info!("Forcibly killed {}", id);
         } else {
-            println!("{} has no trip?!", id);
+            warn!("{} has no trip?!", id);
         }
     }
 
@@ -1,12 +1,11 @@
 use crate::{
-    CarID, Event, PedestrianID, PersonID, Router, Scheduler, TripID, TripManager, TripPhaseType,
-    WalkingSimState,
+    CarID, Event, ParkingSimState, PedestrianID, PersonID, Router, Scheduler, TripID, TripManager,
+    TripPhaseType, VehicleType, WalkingSimState, BUS_CAPACITY, TRAIN_CAPACITY,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::{Distance, Time};
-use map_model::{
-    BusRoute, BusRouteID, BusStopID, Map, Path, PathConstraints, PathRequest, Position,
-};
+use log::warn;
+use map_model::{BusRoute, BusRouteID, BusStopID, Map, Path, PathRequest, Position};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -34,6 +33,7 @@ struct Bus {
     route: BusRouteID,
     // Where does each passenger want to deboard?
     passengers: Vec<(PersonID, BusStopID)>,
+    capacity: usize,
     state: BusState,
 }
 
@@ -77,12 +77,12 @@ impl TransitSimState {
     }
 
     // Returns (next stop, first path, end distance for next stop) for all of the stops in the
-    // route.
+    // route. Fails if a bus can't drive between two stops, which edits to the stops can cause.
     pub fn create_empty_route(
         &mut self,
         bus_route: &BusRoute,
         map: &Map,
-    ) -> Vec<(StopIdx, PathRequest, Path, Distance)> {
+    ) -> Result<Vec<(StopIdx, PathRequest, Path, Distance)>, String> {
         assert!(bus_route.stops.len() > 1);
 
         let route = Route {
@@ -101,21 +101,23 @@ impl TransitSimState {
                     let req = PathRequest {
                         start: stop1.driving_pos,
                         end: map.get_bs(bus_route.stops[stop2_idx]).driving_pos,
-                        constraints: PathConstraints::Bus,
+                        constraints: bus_route.route_type,
                     };
-                    let path = map.pathfind(req.clone()).expect(&format!(
-                        "No route between bus stops {:?} and {:?}",
-                        stop1_id, bus_route.stops[stop2_idx]
-                    ));
-                    StopForRoute {
+                    let path = map.pathfind(req.clone()).ok_or_else(|| {
+                        format!(
+                            "No route between bus stops {:?} and {:?}",
+                            stop1_id, bus_route.stops[stop2_idx]
+                        )
+                    })?;
+                    Ok(StopForRoute {
                         id: *stop1_id,
                         driving_pos: stop1.driving_pos,
                         req,
                         path_to_next_stop: path,
                         next_stop_idx: stop2_idx,
-                    }
+                    })
                 })
-                .collect(),
+                .collect::<Result<Vec<StopForRoute>, String>>()?,
         };
 
         let stops = route
@@ -131,7 +133,19 @@ impl TransitSimState {
             })
             .collect();
         self.routes.insert(bus_route.id, route);
-        stops
+        Ok(stops)
+    }
+
+    // Same as one entry of create_empty_route, for a bus starting at the first stop.
+    pub fn start_of_route(&self, route: BusRouteID) -> (StopIdx, PathRequest, Path, Distance) {
+        let route = &self.routes[&route];
+        let stop = &route.stops[0];
+        (
+            stop.next_stop_idx,
+            stop.req.clone(),
+            stop.path_to_next_stop.clone(),
+            route.stops[stop.next_stop_idx].driving_pos.dist_along(),
+        )
     }
 
     pub fn bus_created(&mut self, bus: CarID, route: BusRouteID, next_stop_idx: StopIdx) {
@@ -142,6 +156,11 @@ impl TransitSimState {
                 car: bus,
                 route,
                 passengers: Vec::new(),
+                capacity: if bus.1 == VehicleType::Train {
+                    TRAIN_CAPACITY
+                } else {
+                    BUS_CAPACITY
+                },
                 state: BusState::DrivingToStop(next_stop_idx),
             },
         );
@@ -153,6 +172,7 @@ impl TransitSimState {
         id: CarID,
         trips: &mut TripManager,
         walking: &mut WalkingSimState,
+        parking: &ParkingSimState,
         scheduler: &mut Scheduler,
         map: &Map,
     ) {
@@ -168,19 +188,23 @@ impl TransitSimState {
                 let mut still_riding = Vec::new();
                 for (person, stop2) in bus.passengers.drain(..) {
                     if stop1 == stop2 {
-                        trips.person_left_bus(now, person, bus.car, map, scheduler);
+                        trips.person_left_bus(now, person, bus.car, map, parking, scheduler);
                     } else {
                         still_riding.push((person, stop2));
                     }
                 }
                 bus.passengers = still_riding;
 
-                // Board new passengers.
+                // Board new passengers, until the bus is full.
                 let mut still_waiting = Vec::new();
+                let mut left_behind = 0;
                 for (ped, route, stop2, started_waiting) in
                     self.peds_waiting.remove(&stop1).unwrap_or_else(Vec::new)
                 {
-                    if bus.route == route {
+                    if bus.route == route && bus.passengers.len() >= bus.capacity {
+                        left_behind += 1;
+                        still_waiting.push((ped, route, stop2, started_waiting));
+                    } else if bus.route == route {
                         let (trip, person) = trips.ped_boarded_bus(
                             now,
                             ped,
@@ -194,7 +218,7 @@ impl TransitSimState {
                             Some(PathRequest {
                                 start: map.get_bs(stop1).driving_pos,
                                 end: map.get_bs(stop2).driving_pos,
-                                constraints: PathConstraints::Bus,
+                                constraints: map.get_br(route).route_type,
                             }),
                             TripPhaseType::RidingBus(route, stop1, bus.car),
                         ));
@@ -204,6 +228,14 @@ impl TransitSimState {
                     }
                 }
                 self.peds_waiting.insert(stop1, still_waiting);
+                self.events.push(Event::BusLoadMeasured {
+                    bus: id,
+                    route: bus.route,
+                    stop: stop1,
+                    passengers: bus.passengers.len(),
+                    capacity: bus.capacity,
+                    left_behind,
+                });
             }
             BusState::AtStop(_) => unreachable!(),
         };
@@ -244,7 +276,9 @@ impl TransitSimState {
         if let Some(route) = self.routes.get(&route_id) {
             for bus in &route.buses {
                 if let BusState::AtStop(idx) = self.buses[bus].state {
-                    if route.stops[idx].id == stop1 {
+                    if route.stops[idx].id == stop1
+                        && self.buses[bus].passengers.len() < self.buses[bus].capacity
+                    {
                         self.buses
                             .get_mut(bus)
                             .unwrap()
@@ -256,7 +290,7 @@ impl TransitSimState {
                             Some(PathRequest {
                                 start: map.get_bs(stop1).driving_pos,
                                 end: map.get_bs(stop2).driving_pos,
-                                constraints: PathConstraints::Bus,
+                                constraints: map.get_br(route_id).route_type,
                             }),
                             TripPhaseType::RidingBus(route_id, stop1, *bus),
                         ));
@@ -265,8 +299,8 @@ impl TransitSimState {
                 }
             }
         } else {
-            println!(
-                "WARNING: {} waiting for {}, but that route hasn't been instantiated",
+            warn!(
+                "{} waiting for {}, but that route hasn't been instantiated",
                 ped, route_id
             );
         }
@@ -286,6 +320,10 @@ impl TransitSimState {
         &self.buses[&bus].passengers
     }
 
+    pub fn get_capacity(&self, bus: CarID) -> usize {
+        self.buses[&bus].capacity
+    }
+
     pub fn bus_route(&self, bus: CarID) -> BusRouteID {
         self.buses[&bus].route
     }
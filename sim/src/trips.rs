@@ -1,14 +1,15 @@
 use crate::{
     AgentID, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal, Event,
     OffMapLocation, OrigPersonID, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, PersonID,
-    Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID, TripPhaseType, TripSpec,
+    Router, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID, TripPhaseType, TripSpec,
     Vehicle, VehicleSpec, VehicleType, WalkingSimState,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
 use geom::{Distance, Duration, Speed, Time};
+use log::info;
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints, PathRequest,
-    Position,
+    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, ParkingLotID, Path, PathConstraints,
+    PathRequest, Position,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
@@ -105,7 +106,7 @@ impl TripManager {
                 _ => unreachable!(),
             },
             Some(TripLeg::Drive(_, ref goal)) => match goal {
-                DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
+                DrivingGoal::ParkNear(b) | DrivingGoal::EnterDriveway(b) => TripEndpoint::Bldg(*b),
                 DrivingGoal::Border(i, _, loc) => TripEndpoint::Border(*i, loc.clone()),
             },
             Some(TripLeg::Remote(ref to)) => {
@@ -182,6 +183,10 @@ impl TripManager {
             Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
                 assert_eq!(car, c);
             }
+            Some(TripLeg::DriveToLot(c, pl)) => {
+                assert_eq!(car, c);
+                parking.release_park_and_ride_claim(pl);
+            }
             _ => unreachable!(),
         };
 
@@ -222,6 +227,43 @@ impl TripManager {
         }
     }
 
+    // The car vanishes into the building without occupying any parking spot, so the trip is over.
+    pub fn car_entered_driveway(
+        &mut self,
+        now: Time,
+        car: CarID,
+        b: BuildingID,
+        blocked_time: Duration,
+        map: &Map,
+        parking: &mut ParkingSimState,
+        scheduler: &mut Scheduler,
+    ) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.total_blocked_time += blocked_time;
+
+        match trip.legs.pop_front() {
+            Some(TripLeg::Drive(c, DrivingGoal::EnterDriveway(bldg))) => {
+                assert_eq!(car, c);
+                assert_eq!(b, bldg);
+            }
+            _ => unreachable!(),
+        };
+        assert!(trip.legs.is_empty());
+        assert!(!trip.finished_at.is_some());
+        trip.finished_at = Some(now);
+        self.unfinished_trips -= 1;
+        self.events.push(Event::TripFinished {
+            trip: trip.id,
+            mode: trip.mode,
+            total_time: now - trip.departure,
+            blocked_time: trip.total_blocked_time,
+        });
+        let person = trip.person;
+        self.people[person.0].state = PersonState::Inside(b);
+        self.events.push(Event::PersonEntersBuilding(person, b));
+        self.person_finished_trip(now, person, parking, scheduler, map);
+    }
+
     pub fn ped_reached_parking_spot(
         &mut self,
         now: Time,
@@ -242,13 +284,12 @@ impl TripManager {
 
         trip.assert_walking_leg(SidewalkSpot::deferred_parking_spot());
         let parked_car = parking.get_car_at_spot(spot).unwrap().clone();
-        let drive_to = match trip.legs[0] {
-            TripLeg::Drive(c, ref to) => {
-                assert_eq!(c, parked_car.vehicle.id);
-                to.clone()
-            }
+        let (car, end) = match trip.legs[0] {
+            TripLeg::Drive(c, ref to) => (c, to.goal_pos(PathConstraints::Car, map)),
+            TripLeg::DriveToLot(c, pl) => (c, map.get_pl(pl).driving_pos),
             _ => unreachable!(),
         };
+        assert_eq!(car, parked_car.vehicle.id);
 
         let mut start = parking.spot_to_driving_pos(parked_car.spot, &parked_car.vehicle, map);
         match spot {
@@ -263,7 +304,6 @@ impl TripManager {
                 start = Position::new(start.lane(), start.dist_along() + parked_car.vehicle.length);
             }
         }
-        let end = drive_to.goal_pos(PathConstraints::Car, map);
         let req = PathRequest {
             start,
             end,
@@ -286,9 +326,13 @@ impl TripManager {
             return;
         };
 
-        let router = drive_to
-            .make_router(path, map, parked_car.vehicle.vehicle_type)
-            .unwrap();
+        let router = match trip.legs[0] {
+            TripLeg::Drive(_, ref to) => to
+                .make_router(path, map, parked_car.vehicle.vehicle_type)
+                .unwrap(),
+            TripLeg::DriveToLot(_, pl) => Router::park_in_lot(path, pl, map),
+            _ => unreachable!(),
+        };
         scheduler.push(
             now,
             Command::SpawnCar(
@@ -332,16 +376,29 @@ impl TripManager {
             _ => unreachable!(),
         };
 
-        let end = drive_to.goal_pos(PathConstraints::Bike, map);
+        parking.unlock_bike(bike);
+        // Lock up at a rack near the destination if one has room, or anywhere nearby otherwise
+        let rack = match drive_to {
+            DrivingGoal::ParkNear(b) => parking.claim_bike_rack(bike, b, map),
+            _ => None,
+        };
+        let end = if let Some(rack) = rack {
+            map.get_bike_rack(rack).biking_pos
+        } else {
+            drive_to.goal_pos(PathConstraints::Bike, map)
+        };
         let req = PathRequest {
             start: driving_pos,
             end,
             constraints: PathConstraints::Bike,
         };
-        if let Some(router) = map
-            .pathfind(req.clone())
-            .and_then(|path| drive_to.make_router(path, map, VehicleType::Bike))
-        {
+        if let Some(router) = map.pathfind(req.clone()).and_then(|path| {
+            if let Some(rack) = rack {
+                Some(Router::bike_to_rack(path, rack, map))
+            } else {
+                drive_to.make_router(path, map, VehicleType::Bike)
+            }
+        }) {
             scheduler.push(
                 now,
                 Command::SpawnCar(
@@ -522,6 +579,7 @@ impl TripManager {
         person: PersonID,
         bus: CarID,
         map: &Map,
+        parking: &ParkingSimState,
         scheduler: &mut Scheduler,
     ) {
         let trip = &mut self.trips[self
@@ -535,9 +593,31 @@ impl TripManager {
         };
         self.people[person.0].on_bus.take().unwrap();
 
-        if !trip.spawn_ped(
+        let walk_to = match trip.legs[0] {
+            // Heading back to a car left at a park-and-ride lot
+            TripLeg::Walk(ref to) if to.connection == SidewalkPOI::DeferredParkingSpot => {
+                let car = match trip.legs[1] {
+                    TripLeg::Drive(c, _) => c,
+                    _ => unreachable!(),
+                };
+                if let Some(parked_car) = parking.lookup_parked_car(car) {
+                    SidewalkSpot::parking_spot(parked_car.spot, map, parking)
+                } else {
+                    self.events.push(Event::Alert(
+                        AlertLocation::Person(person),
+                        format!("{} got off the bus, but {} is gone", person, car),
+                    ));
+                    self.unfinished_trips -= 1;
+                    return;
+                }
+            }
+            TripLeg::Walk(ref to) => to.clone(),
+            _ => unreachable!(),
+        };
+        if !trip.spawn_ped_to(
             now,
             start,
+            walk_to,
             &self.people[trip.person.0],
             map,
             scheduler,
@@ -682,6 +762,18 @@ impl TripManager {
         trip.aborted = true;
         self.events.push(Event::TripAborted(trip.id));
         let person = trip.person;
+        for leg in &trip.legs {
+            match leg {
+                TripLeg::DriveToLot(_, pl) => {
+                    parking.release_park_and_ride_claim(*pl);
+                }
+                // The bike won't reach the rack it claimed
+                TripLeg::Drive(bike, _) if bike.1 == VehicleType::Bike => {
+                    parking.unlock_bike(*bike);
+                }
+                _ => {}
+            }
+        }
 
         // Maintain consistentency for anyone listening to events
         if let PersonState::Inside(b) = self.people[person.0].state {
@@ -702,9 +794,14 @@ impl TripManager {
             TripEndpoint::Bldg(b) => PersonState::Inside(b),
             TripEndpoint::Border(_, _) => PersonState::OffMap,
         };
-        // Don't forget the car!
+        // Don't forget the car! If it was headed into a driveway, it just winds up inside the
+        // building, not in any parking spot.
+        let into_driveway = match trip.legs.back() {
+            Some(TripLeg::Drive(_, DrivingGoal::EnterDriveway(_))) => true,
+            _ => false,
+        };
         if let Some(vehicle) = abandoned_vehicle {
-            if vehicle.vehicle_type == VehicleType::Car {
+            if vehicle.vehicle_type == VehicleType::Car && !into_driveway {
                 if let TripEndpoint::Bldg(b) = trip.end {
                     let driving_lane = map.find_driving_lane_near_building(b);
                     if let Some(spot) = parking
@@ -746,10 +843,13 @@ impl TripManager {
         } else {
             // If the trip was aborted because we'e totally out of parking, don't forget to clean
             // this up.
-            if let TripLeg::Drive(c, _) = &trip.legs[0] {
-                if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(*c)) {
-                    assert_eq!(t, trip.id);
+            match trip.legs[0] {
+                TripLeg::Drive(c, _) | TripLeg::DriveToLot(c, _) => {
+                    if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(c)) {
+                        assert_eq!(t, trip.id);
+                    }
                 }
+                _ => {}
             }
         }
 
@@ -783,7 +883,7 @@ impl TripManager {
         let person = &self.people[trip.person.0];
         let a = match &trip.legs[0] {
             TripLeg::Walk(_) => AgentID::Pedestrian(person.ped),
-            TripLeg::Drive(c, _) => AgentID::Car(*c),
+            TripLeg::Drive(c, _) | TripLeg::DriveToLot(c, _) => AgentID::Car(*c),
             TripLeg::RideBus(_, _) => AgentID::BusPassenger(person.id, person.on_bus.unwrap()),
             TripLeg::Remote(_) => {
                 return TripResult::RemoteTrip;
@@ -805,9 +905,9 @@ impl TripManager {
     pub fn debug_trip(&self, id: AgentID) {
         if let Some(t) = self.active_trip_mode.get(&id) {
             let trip = &self.trips[t.0];
-            println!("{} has goal {:?}", trip.id, trip.legs.back().unwrap());
+            info!("{} has goal {:?}", trip.id, trip.legs.back().unwrap());
         } else {
-            println!("{} has no trip, must be parked car", id);
+            info!("{} has no trip, must be parked car", id);
         }
     }
 
@@ -856,6 +956,12 @@ impl TripManager {
         let t = &self.trips[id.0];
         (t.departure, t.start.clone(), t.end.clone(), t.mode)
     }
+    pub fn all_trip_info(&self) -> Vec<(TripID, Time, TripEndpoint, TripEndpoint, TripMode)> {
+        self.trips
+            .iter()
+            .map(|t| (t.id, t.departure, t.start.clone(), t.end.clone(), t.mode))
+            .collect()
+    }
     pub fn finished_trip_time(&self, id: TripID) -> Option<(Duration, Duration)> {
         let t = &self.trips[id.0];
         Some((t.finished_at? - t.departure, t.total_blocked_time))
@@ -878,10 +984,135 @@ impl TripManager {
         &self.people
     }
 
+    // Maps everybody's walking speed from one range to another, keeping their place in the range.
+    // Speeds outside the old range wind up at the nearest end of the new one.
+    pub fn rescale_walking_speeds(&mut self, old: (Speed, Speed), new: (Speed, Speed)) {
+        let (old_min, old_max) = (
+            old.0.inner_meters_per_second(),
+            old.1.inner_meters_per_second(),
+        );
+        let (new_min, new_max) = (
+            new.0.inner_meters_per_second(),
+            new.1.inner_meters_per_second(),
+        );
+        for person in &mut self.people {
+            let pct = if old_max > old_min {
+                abstutil::clamp(
+                    (person.ped_speed.inner_meters_per_second() - old_min) / (old_max - old_min),
+                    0.0,
+                    1.0,
+                )
+            } else {
+                0.5
+            };
+            person.ped_speed = Speed::meters_per_second(new_min + pct * (new_max - new_min));
+        }
+    }
+
     pub fn trip_to_person(&self, id: TripID) -> PersonID {
         self.trips[id.0].person
     }
 
+    // Start walking from a building to wherever the car is parked
+    fn walk_to_parked_car(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        car: CarID,
+        start_bldg: BuildingID,
+        parking: &mut ParkingSimState,
+        scheduler: &mut Scheduler,
+        map: &Map,
+    ) {
+        let person = &mut self.people[self.trips[trip.0].person.0];
+        assert_eq!(person.state, PersonState::Inside(start_bldg));
+        person.state = PersonState::Trip(trip);
+
+        // TODO For now, use the car we decided to statically. That makes sense in most
+        // cases.
+
+        if let Some(parked_car) = parking.lookup_parked_car(car).cloned() {
+            let start = SidewalkSpot::building(start_bldg, map);
+            let walking_goal = SidewalkSpot::parking_spot(parked_car.spot, map, parking);
+            let req = PathRequest {
+                start: start.sidewalk_pos,
+                end: walking_goal.sidewalk_pos,
+                constraints: PathConstraints::Pedestrian,
+            };
+            if let Some(path) = map.pathfind(req.clone()) {
+                scheduler.push(
+                    now,
+                    Command::SpawnPed(CreatePedestrian {
+                        id: person.ped,
+                        speed: person.ped_speed,
+                        start,
+                        goal: walking_goal,
+                        path,
+                        req,
+                        trip,
+                        person: person.id,
+                    }),
+                );
+            } else {
+                self.events.push(Event::Alert(
+                    AlertLocation::Person(person.id),
+                    format!("UsingParkedCar trip couldn't find the walking path {}", req),
+                ));
+                // Move the car to the destination
+                parking.remove_parked_car(parked_car.clone());
+                self.abort_trip(now, trip, Some(parked_car.vehicle), parking, scheduler, map);
+            }
+        } else {
+            // This should only happen when a driving trip has been aborted and there was
+            // absolutely no room to warp the car.
+            self.events.push(Event::Alert(
+                AlertLocation::Person(person.id),
+                format!(
+                    "{} should have {} parked somewhere, but it's unavailable, so \
+                     aborting {}",
+                    person.id, car, trip
+                ),
+            ));
+            self.abort_trip(now, trip, None, parking, scheduler, map);
+        }
+    }
+
+    // The car can't be used, so walk or take transit straight from the building instead
+    fn walk_or_ride_instead(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        start_bldg: BuildingID,
+        goal: SidewalkSpot,
+        parking: &mut ParkingSimState,
+        scheduler: &mut Scheduler,
+        map: &Map,
+    ) {
+        let start = SidewalkSpot::building(start_bldg, map);
+        let spec = if let Some((stop1, stop2, route)) =
+            map.should_use_transit(start.sidewalk_pos, goal.sidewalk_pos)
+        {
+            self.trips[trip.0].legs = VecDeque::from(vec![
+                TripLeg::Walk(SidewalkSpot::bus_stop(stop1, map)),
+                TripLeg::RideBus(route, stop2),
+                TripLeg::Walk(goal.clone()),
+            ]);
+            TripSpec::UsingTransit {
+                start,
+                goal,
+                route,
+                stop1,
+                stop2,
+            }
+        } else {
+            self.trips[trip.0].legs = VecDeque::from(vec![TripLeg::Walk(goal.clone())]);
+            TripSpec::JustWalking { start, goal }
+        };
+        let req = spec.get_pathfinding_request(map);
+        let path = req.clone().and_then(|r| map.pathfind(r));
+        self.start_trip(now, trip, spec, req, path, parking, scheduler, map);
+    }
+
     fn person_finished_trip(
         &mut self,
         now: Time,
@@ -1002,63 +1233,38 @@ impl TripManager {
             }
             TripSpec::UsingParkedCar {
                 car, start_bldg, ..
+            } => {
+                self.walk_to_parked_car(now, trip, car, start_bldg, parking, scheduler, map);
+            }
+            TripSpec::LeavingDriveway {
+                start_bldg,
+                goal,
+                use_vehicle,
             } => {
                 assert_eq!(person.state, PersonState::Inside(start_bldg));
                 person.state = PersonState::Trip(trip);
 
-                // TODO For now, use the car we decided to statically. That makes sense in most
-                // cases.
-
-                if let Some(parked_car) = parking.lookup_parked_car(car).cloned() {
-                    let start = SidewalkSpot::building(start_bldg, map);
-                    let walking_goal = SidewalkSpot::parking_spot(parked_car.spot, map, parking);
-                    let req = PathRequest {
-                        start: start.sidewalk_pos,
-                        end: walking_goal.sidewalk_pos,
-                        constraints: PathConstraints::Pedestrian,
-                    };
-                    if let Some(path) = map.pathfind(req.clone()) {
-                        scheduler.push(
-                            now,
-                            Command::SpawnPed(CreatePedestrian {
-                                id: person.ped,
-                                speed: person.ped_speed,
-                                start,
-                                goal: walking_goal,
-                                path,
-                                req,
-                                trip,
-                                person: person.id,
-                            }),
-                        );
-                    } else {
-                        self.events.push(Event::Alert(
-                            AlertLocation::Person(person.id),
-                            format!("UsingParkedCar trip couldn't find the walking path {}", req),
-                        ));
-                        // Move the car to the destination
-                        parking.remove_parked_car(parked_car.clone());
-                        self.abort_trip(
-                            now,
-                            trip,
-                            Some(parked_car.vehicle),
-                            parking,
-                            scheduler,
-                            map,
-                        );
-                    }
+                let vehicle = person.get_vehicle(use_vehicle);
+                assert!(parking.lookup_parked_car(vehicle.id).is_none());
+                let req = maybe_req.unwrap();
+                if let Some(router) =
+                    maybe_path.and_then(|path| goal.make_router(path, map, vehicle.vehicle_type))
+                {
+                    scheduler.push(
+                        now,
+                        Command::SpawnCar(
+                            CreateCar::for_driveway(
+                                vehicle, start_bldg, router, req, trip, person.id, map,
+                            ),
+                            true,
+                        ),
+                    );
                 } else {
-                    // This should only happen when a driving trip has been aborted and there was
-                    // absolutely no room to warp the car.
                     self.events.push(Event::Alert(
                         AlertLocation::Person(person.id),
-                        format!(
-                            "{} should have {} parked somewhere, but it's unavailable, so \
-                             aborting {}",
-                            person.id, car, trip
-                        ),
+                        format!("LeavingDriveway trip couldn't find the first path: {}", req),
                     ));
-                    self.abort_trip(now, trip, None, parking, scheduler, map);
+                    self.abort_trip(now, trip, Some(vehicle), parking, scheduler, map);
                 }
             }
             TripSpec::JustWalking { start, goal } => {
@@ -1114,7 +1320,7 @@ impl TripManager {
                     self.abort_trip(now, trip, None, parking, scheduler, map);
                 }
             }
-            TripSpec::UsingBike { start, .. } => {
+            TripSpec::UsingBike { start, bike, .. } => {
                 assert_eq!(
                     person.state,
                     match start.connection {
@@ -1144,9 +1350,28 @@ impl TripManager {
                 );
                 person.state = PersonState::Trip(trip);
 
-                let walk_to =
-                    SidewalkSpot::bike_from_bike_rack(start.sidewalk_pos.lane(), map).unwrap();
-                let req = maybe_req.unwrap();
+                let rack = match start.connection {
+                    SidewalkPOI::Building(_) => parking.get_bike_rack(bike),
+                    _ => None,
+                };
+                let (walk_to, req, maybe_path) = if let Some(rack) = rack {
+                    // Go back to wherever the bike was locked up last time
+                    let walk_to = SidewalkSpot::bike_rack_at(rack, map);
+                    self.trips[trip.0].legs[0] = TripLeg::Walk(walk_to.clone());
+                    let req = PathRequest {
+                        start: start.sidewalk_pos,
+                        end: walk_to.sidewalk_pos,
+                        constraints: PathConstraints::Pedestrian,
+                    };
+                    let path = map.pathfind(req.clone());
+                    (walk_to, req, path)
+                } else {
+                    (
+                        SidewalkSpot::bike_from_bike_rack(start.sidewalk_pos.lane(), map).unwrap(),
+                        maybe_req.unwrap(),
+                        maybe_path,
+                    )
+                };
                 if let Some(path) = maybe_path {
                     scheduler.push(
                         now,
@@ -1169,6 +1394,24 @@ impl TripManager {
                     self.abort_trip(now, trip, None, parking, scheduler, map);
                 }
             }
+            TripSpec::UsingParkAndRide {
+                car,
+                start_bldg,
+                lot,
+                goal,
+                ..
+            } => {
+                if parking.claim_park_and_ride_spot(lot) {
+                    self.walk_to_parked_car(now, trip, car, start_bldg, parking, scheduler, map);
+                } else {
+                    // The transit stops were picked from the lot, so plan again from the building
+                    self.events.push(Event::Alert(
+                        AlertLocation::Person(person.id),
+                        format!("{} is full, so {} won't drive there", lot, trip),
+                    ));
+                    self.walk_or_ride_instead(now, trip, start_bldg, goal, parking, scheduler, map);
+                }
+            }
             TripSpec::UsingTransit { start, stop1, .. } => {
                 assert_eq!(
                     person.state,
@@ -1238,6 +1481,42 @@ impl TripManager {
                     TripPhaseType::Remote,
                 ));
             }
+            TripSpec::ParkAndRideHome {
+                car,
+                start_bldg,
+                lot,
+                transit,
+                home,
+            } => {
+                let in_lot = match parking.lookup_parked_car(car).map(|p| p.spot) {
+                    Some(ParkingSpot::Lot(pl, _)) => pl == lot,
+                    _ => false,
+                };
+                if !in_lot {
+                    self.events.push(Event::Alert(
+                        AlertLocation::Person(person.id),
+                        format!(
+                            "{} isn't at {}, so {} will go home without it",
+                            car, lot, trip
+                        ),
+                    ));
+                    let goal = SidewalkSpot::building(home, map);
+                    self.walk_or_ride_instead(now, trip, start_bldg, goal, parking, scheduler, map);
+                } else if let Some((stop1, stop2, route)) = transit {
+                    let spec = TripSpec::UsingTransit {
+                        start: SidewalkSpot::building(start_bldg, map),
+                        goal: SidewalkSpot::bus_stop(stop2, map),
+                        route,
+                        stop1,
+                        stop2,
+                    };
+                    let req = spec.get_pathfinding_request(map);
+                    let path = req.clone().and_then(|r| map.pathfind(r));
+                    self.start_trip(now, trip, spec, req, path, parking, scheduler, map);
+                } else {
+                    self.walk_to_parked_car(now, trip, car, start_bldg, parking, scheduler, map);
+                }
+            }
         }
     }
 }
@@ -1272,7 +1551,19 @@ impl Trip {
             TripLeg::Walk(ref to) => to.clone(),
             _ => unreachable!(),
         };
+        self.spawn_ped_to(now, start, walk_to, person, map, scheduler, events)
+    }
 
+    fn spawn_ped_to(
+        &self,
+        now: Time,
+        start: SidewalkSpot,
+        walk_to: SidewalkSpot,
+        person: &Person,
+        map: &Map,
+        scheduler: &mut Scheduler,
+        events: &mut Vec<Event>,
+    ) -> bool {
         let req = PathRequest {
             start: start.sidewalk_pos,
             end: walk_to.sidewalk_pos,
@@ -1326,6 +1617,9 @@ pub enum TripLeg {
     Drive(CarID, DrivingGoal),
     RideBus(BusRouteID, BusStopID),
     Remote(OffMapLocation),
+    // The first half of a park-and-ride trip. A spot in the lot was claimed before the trip
+    // started.
+    DriveToLot(CarID, ParkingLotID),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord)]
@@ -1353,7 +1647,7 @@ impl TripMode {
                 VehicleType::Car => TripMode::Drive,
                 VehicleType::Bike => TripMode::Bike,
                 // TODO Little confusing; this means buses, not bus riders.
-                VehicleType::Bus => TripMode::Transit,
+                VehicleType::Bus | VehicleType::Train => TripMode::Transit,
             },
             // TODO Now we can detangle this, right?
             AgentID::BusPassenger(_, _) => TripMode::Transit,